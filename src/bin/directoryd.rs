@@ -32,16 +32,24 @@ struct Cli {
         default_value = "user:password",
     )]
     pub auth: (String, String),
+    /// Optional `bitcoin.conf` style file with the RPC credentials (rpcuser, rpcpassword, rpcconnect, rpcport).
+    /// Overrides the ADDRESS:PORT and USER:PASSWORD values.
+    #[clap(long, value_name = "PATH")]
+    pub rpc_conf: Option<PathBuf>,
 }
 
 fn main() -> Result<(), DirectoryServerError> {
     let args = Cli::parse();
     setup_directory_logger(log::LevelFilter::Info, args.data_directory.clone());
 
-    let rpc_config = RPCConfig {
-        url: args.rpc,
-        auth: Auth::UserPass(args.auth.0, args.auth.1),
-        wallet_name: "random".to_string(), // we can put anything here as it will get updated in the init.
+    let rpc_config = match args.rpc_conf {
+        Some(path) => RPCConfig::from_config_file(path)?,
+        None => RPCConfig {
+            url: args.rpc,
+            auth: Auth::UserPass(args.auth.0, args.auth.1),
+            wallet_name: "random".to_string(), // we can put anything here as it will get updated in the init.
+        }
+        .with_env_overrides(),
     };
 
     #[cfg(not(feature = "integration-test"))]
@@ -2,7 +2,7 @@ use std::{net::TcpStream, time::Duration};
 
 use clap::Parser;
 use coinswap::{
-    maker::{MakerError, RpcMsgReq, RpcMsgResp},
+    maker::{MakerError, OfferFees, RpcMsgReq, RpcMsgResp},
     utill::{read_message, send_message, DEFAULT_TX_FEE_RATE},
 };
 
@@ -68,6 +68,44 @@ enum Commands {
     ShowFidelity,
     /// Sync the maker wallet with current blockchain state.
     SyncWallet,
+    /// Export the wallet seed mnemonic for backup. Requires `allow_seed_export = true` in the makerd config.
+    /// Anyone with the seed can spend all the wallet funds, keep it safe.
+    ExportSeed {
+        /// Confirm exporting the seed.
+        #[clap(long)]
+        confirm: bool,
+    },
+    /// Show the live offer advertised to takers.
+    GetOffer,
+    /// Update the live offer. Takers see the change at their next offerbook sync.
+    SetOffer {
+        /// Minimum swap amount, in sats.
+        #[clap(long)]
+        min_size: u64,
+        /// Maximum swap amount, in sats. Further capped by the wallet's liquidity.
+        #[clap(long)]
+        max_size: u64,
+        /// Fixed fee per swap, in sats.
+        #[clap(long)]
+        base_fee: u64,
+        /// Fee in percent of the swap amount.
+        #[clap(long)]
+        amount_relative_fee_pct: f64,
+        /// Fee in percent of the swap amount, per block of refund locktime.
+        #[clap(long)]
+        time_relative_fee_pct: f64,
+    },
+    /// Show the event log, like started, completed and failed swaps, one JSON object per line.
+    GetEvents {
+        /// Only show events at or after this Unix timestamp, in seconds.
+        #[clap(long, default_value = "0")]
+        since: u64,
+        /// Maximum number of events to show, oldest first.
+        #[clap(long, default_value = "100")]
+        limit: usize,
+    },
+    /// List the other makers registered at the directory servers.
+    GetKnownMakers,
 }
 
 fn main() -> Result<(), MakerError> {
@@ -126,6 +164,38 @@ fn main() -> Result<(), MakerError> {
         Commands::SyncWallet => {
             send_rpc_req(stream, RpcMsgReq::SyncWallet)?;
         }
+        Commands::ExportSeed { confirm } => {
+            send_rpc_req(stream, RpcMsgReq::ExportSeed { confirm })?;
+        }
+        Commands::GetOffer => {
+            send_rpc_req(stream, RpcMsgReq::GetOffer)?;
+        }
+        Commands::SetOffer {
+            min_size,
+            max_size,
+            base_fee,
+            amount_relative_fee_pct,
+            time_relative_fee_pct,
+        } => {
+            send_rpc_req(
+                stream,
+                RpcMsgReq::SetOffer {
+                    min_size,
+                    max_size,
+                    fee: OfferFees {
+                        base_fee,
+                        amount_relative_fee_pct,
+                        time_relative_fee_pct,
+                    },
+                },
+            )?;
+        }
+        Commands::GetEvents { since, limit } => {
+            send_rpc_req(stream, RpcMsgReq::GetEvents { since, limit })?;
+        }
+        Commands::GetKnownMakers => {
+            send_rpc_req(stream, RpcMsgReq::GetKnownMakers)?;
+        }
     }
 
     Ok(())
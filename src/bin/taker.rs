@@ -1,8 +1,8 @@
-use bitcoin::{Address, Amount};
+use bitcoin::Address;
 use bitcoind::bitcoincore_rpc::Auth;
 use clap::Parser;
 use coinswap::{
-    taker::{error::TakerError, SwapParams, Taker, TakerBehavior},
+    taker::{error::TakerError, FixedPriceSource, SwapAmount, SwapParams, Taker, TakerBehavior},
     utill::{
         parse_proxy_auth, setup_taker_logger, ConnectionType, DEFAULT_TX_FEE_RATE,
         REQUIRED_CONFIRMS, UTXO,
@@ -41,6 +41,10 @@ struct Cli {
     /// Bitcoin Core RPC authentication string. Ex: username:password
     #[clap(name="USER:PASSWORD",short='a',long, value_parser = parse_proxy_auth, default_value = "user:password")]
     pub auth: (String, String),
+    /// Optional `bitcoin.conf` style file with the RPC credentials (rpcuser, rpcpassword, rpcconnect, rpcport).
+    /// Overrides the ADDRESS:PORT and USER:PASSWORD values.
+    #[clap(long, value_name = "PATH")]
+    pub rpc_conf: Option<PathBuf>,
     #[clap(long, short = 't', default_value = "")]
     pub tor_auth: String,
 
@@ -52,6 +56,10 @@ struct Cli {
     #[clap(long, short = 'v', possible_values = &["off", "error", "warn", "info", "debug", "trace"], default_value = "info")]
     pub verbosity: String,
 
+    /// Optional BTC price in a fiat currency, to use fiat amounts. Ex: USD:65000
+    #[clap(long, short = 'p', value_name = "CURRENCY:PRICE")]
+    pub fiat_price: Option<FixedPriceSource>,
+
     /// List of commands for various wallet operations
     #[clap(subcommand)]
     command: Commands,
@@ -81,9 +89,9 @@ enum Commands {
         /// Recipient's address.
         #[clap(long, short = 't')]
         address: String,
-        /// Amount to send in sats
+        /// Amount to send in sats, or in fiat with --fiat-price. Ex: 20000, $50, 50EUR
         #[clap(long, short = 'a')]
-        amount: u64,
+        amount: SwapAmount,
         /// Feerate in sats/vByte. Defaults to 2 sats/vByte
         #[clap(long, short = 'f')]
         feerate: Option<f64>,
@@ -98,9 +106,15 @@ enum Commands {
         /// Adding more makers in the swap will incur more swap fees.
         #[clap(long, short = 'm', default_value = "2")]
         makers: usize,
-        /// Sets the swap amount in sats.
+        /// Sets the swap amount in sats, or in fiat with --fiat-price. Ex: 20000, $50, 50EUR
         #[clap(long, short = 'a', default_value = "20000")]
-        amount: u64,
+        amount: SwapAmount,
+        /// Optional label attached to the received swap coins.
+        #[clap(long, short = 'l')]
+        label: Option<String>,
+        /// Proceed with a mainnet swap, even if it's below the safe-mode's minimum privacy configuration.
+        #[clap(long)]
+        override_safe_mode: bool,
         // /// Sets how many new swap utxos to get. The swap amount will be randomly distrubted across the new utxos.
         // /// Increasing this number also increases total swap fee.
         // #[clap(long, short = 'u', default_value = "1")]
@@ -108,6 +122,16 @@ enum Commands {
     },
     /// Recover from all failed swaps
     Recover,
+    /// Check the stored swapcoins against the chain, and list orphaned or inconsistent ones.
+    /// Nothing is modified in the wallet.
+    CheckIntegrity,
+    /// Move the received swap coins to fresh addresses, to break the link with the swap.
+    /// Coins too small to pay the fee are left in place.
+    FreshenSwapcoins {
+        /// Feerate in sats/vByte. Defaults to 2 sats/vByte
+        #[clap(long, short = 'f')]
+        feerate: Option<f64>,
+    },
 }
 
 fn main() -> Result<(), TakerError> {
@@ -121,10 +145,14 @@ fn main() -> Result<(), TakerError> {
         args.data_directory.clone(), //default path handled inside the function.
     );
 
-    let rpc_config = RPCConfig {
-        url: args.rpc,
-        auth: Auth::UserPass(args.auth.0, args.auth.1),
-        wallet_name: "random".to_string(), // we can put anything here as it will get updated in the init.
+    let rpc_config = match args.rpc_conf {
+        Some(path) => RPCConfig::from_config_file(path)?,
+        None => RPCConfig {
+            url: args.rpc,
+            auth: Auth::UserPass(args.auth.0, args.auth.1),
+            wallet_name: "random".to_string(), // we can put anything here as it will get updated in the init.
+        }
+        .with_env_overrides(),
     };
 
     #[cfg(not(feature = "integration-test"))]
@@ -143,6 +171,10 @@ fn main() -> Result<(), TakerError> {
         Some(connection_type),
     )?;
 
+    if let Some(fiat_price) = args.fiat_price.clone() {
+        taker.set_price_source(Box::new(fiat_price));
+    }
+
     match args.command {
         Commands::ListUtxo => {
             let utxos = taker.get_wallet().list_all_utxo_spend_info()?;
@@ -178,16 +210,34 @@ fn main() -> Result<(), TakerError> {
         }
         Commands::GetBalances => {
             let balances = taker.get_wallet().get_balances()?;
-            println!(
-                "{}",
-                to_string_pretty(&json!({
-                    "regular": balances.regular.to_sat(),
-                    "contract": balances.contract.to_sat(),
-                    "swap": balances.swap.to_sat(),
-                    "spendable": balances.spendable.to_sat(),
-                }))
-                .unwrap()
-            );
+            if let Some(fiat_price) = &args.fiat_price {
+                let display = |amount| taker.display_amount(amount, &fiat_price.currency);
+                println!(
+                    "{}",
+                    to_string_pretty(&json!({
+                        "regular": display(balances.regular),
+                        "contract": display(balances.contract),
+                        "swap": display(balances.swap),
+                        "spendable": display(balances.spendable),
+                        "immature": display(balances.immature),
+                        "pending_swap_out": display(balances.pending_swap_out),
+                    }))
+                    .unwrap()
+                );
+            } else {
+                println!(
+                    "{}",
+                    to_string_pretty(&json!({
+                        "regular": balances.regular.to_sat(),
+                        "contract": balances.contract.to_sat(),
+                        "swap": balances.swap.to_sat(),
+                        "spendable": balances.spendable.to_sat(),
+                        "immature": balances.immature.to_sat(),
+                        "pending_swap_out": balances.pending_swap_out.to_sat(),
+                    }))
+                    .unwrap()
+                );
+            }
         }
         Commands::GetNewAddress => {
             let address = taker.get_wallet_mut().get_next_external_address()?;
@@ -198,22 +248,16 @@ fn main() -> Result<(), TakerError> {
             amount,
             feerate,
         } => {
-            let amount = Amount::from_sat(amount);
-
-            let coins_to_spend = taker.get_wallet().coin_select(amount)?;
+            let amount = taker.resolve_amount(&amount)?;
 
             let destination = Destination::Multi(vec![(
                 Address::from_str(&address).unwrap().assume_checked(),
                 amount,
             )]);
 
-            let tx = taker.get_wallet_mut().spend_from_wallet(
-                feerate.unwrap_or(DEFAULT_TX_FEE_RATE),
-                destination,
-                &coins_to_spend,
-            )?;
-
-            let txid = taker.get_wallet().send_tx(&tx).unwrap();
+            let txid = taker
+                .get_wallet_mut()
+                .send_to_address(destination, feerate.unwrap_or(DEFAULT_TX_FEE_RATE))?;
 
             println!("{}", txid);
 
@@ -234,12 +278,23 @@ fn main() -> Result<(), TakerError> {
                 .iter()
                 .for_each(|offer| println!("{}", taker.display_offer(offer)));
         }
-        Commands::Coinswap { makers, amount } => {
+        Commands::Coinswap {
+            makers,
+            amount,
+            label,
+            override_safe_mode,
+        } => {
+            if override_safe_mode {
+                log::warn!("Safe-mode overridden for this swap");
+                taker.config.safe_mode = false;
+            }
             let swap_params = SwapParams {
-                send_amount: Amount::from_sat(amount),
+                send_amount: taker.resolve_amount(&amount)?,
                 maker_count: makers,
                 tx_count: 1,
                 required_confirms: REQUIRED_CONFIRMS,
+                swap_label: label,
+                ..Default::default()
             };
             taker.do_coinswap(swap_params)?;
         }
@@ -247,6 +302,23 @@ fn main() -> Result<(), TakerError> {
         Commands::Recover => {
             taker.recover_from_swap()?;
         }
+
+        Commands::CheckIntegrity => {
+            let issues = taker.get_wallet().check_integrity()?;
+            if issues.is_empty() {
+                println!("No wallet integrity issues found");
+            }
+            for issue in issues {
+                println!("{:?}", issue);
+            }
+        }
+
+        Commands::FreshenSwapcoins { feerate } => {
+            let txids = taker.post_swap_freshen(feerate.unwrap_or(DEFAULT_TX_FEE_RATE))?;
+            for txid in txids {
+                println!("{}", txid);
+            }
+        }
     }
 
     Ok(())
@@ -10,22 +10,26 @@ use crate::{
     protocol::{
         contract::check_hashvalues_are_equal,
         messages::{FidelityProof, ReqContractSigsForSender},
-        Hash160,
+        Hashvalue,
     },
     utill::{
         check_tor_status, get_maker_dir, redeemscript_to_scriptpubkey, ConnectionType,
-        DEFAULT_TX_FEE_RATE, HEART_BEAT_INTERVAL, REQUIRED_CONFIRMS,
+        DEFAULT_TX_FEE_RATE, HEART_BEAT_INTERVAL,
     },
-    wallet::{RPCConfig, SwapCoin, WalletSwapCoin},
+    wallet::{Destination, RPCConfig, SwapCoin, WalletSwapCoin},
 };
 use bitcoin::{
+    absolute::LockTime,
     ecdsa::Signature,
     secp256k1::{self, Secp256k1},
-    OutPoint, PublicKey, ScriptBuf, Transaction,
+    Amount, Network, OutPoint, PublicKey, ScriptBuf, Transaction, Txid,
 };
 use bitcoind::bitcoincore_rpc::RpcApi;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    io::ErrorKind,
+    net::{IpAddr, TcpListener},
     path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering::Relaxed},
@@ -38,25 +42,39 @@ use std::{
 use crate::{
     protocol::{
         contract::{
-            check_hashlock_has_pubkey, check_multisig_has_pubkey, check_reedemscript_is_multisig,
-            find_funding_output_index, read_contract_locktime,
+            check_fee_escalation_schedule, check_hashlock_has_pubkey, check_multisig_has_pubkey,
+            check_reedemscript_is_multisig, find_funding_output_index, read_contract_locktime,
+            sign_fee_escalated_contract_txs, CONTRACT_FEE_ESCALATION_BLOCKS,
         },
         messages::ProofOfFunding,
+        trace::{ProtocolTrace, PROTOCOL_TRACE_FILE},
     },
     wallet::{IncomingSwapCoin, OutgoingSwapCoin, Wallet, WalletError},
 };
 
-use super::{config::MakerConfig, error::MakerError};
+use super::{
+    config::MakerConfig,
+    error::MakerError,
+    events::{EventLog, MakerEventKind, EVENT_LOG_FILE},
+};
 
 /// Interval for health checks on a stable RPC connection with bitcoind.
 pub const RPC_PING_INTERVAL: u32 = 9;
 
 /// Maker triggers the recovery mechanism, if Taker is idle for more than 15 mins during a swap.
+/// This is the default of the configurable `idle_connection_timeout`.
 #[cfg(feature = "integration-test")]
 pub const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60);
 #[cfg(not(feature = "integration-test"))]
 pub const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60 * 15);
 
+/// Maker triggers the recovery mechanism, if a swap isn't completed 6 hours after the Taker's proof of funding,
+/// even when the Taker is still responding. This is the default of the configurable `swap_timeout`.
+#[cfg(feature = "integration-test")]
+pub const SWAP_TIMEOUT: Duration = Duration::from_secs(60 * 10);
+#[cfg(not(feature = "integration-test"))]
+pub const SWAP_TIMEOUT: Duration = Duration::from_secs(60 * 60 * 6);
+
 /// The minimum difference in locktime (in blocks) between the incoming and outgoing swaps.
 ///
 /// This value specifies the reaction time, in blocks, available to a Maker
@@ -67,11 +85,17 @@ pub const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60 * 15);
 /// To enhance safety, the default value is set to 20 blocks.
 pub const MIN_CONTRACT_REACTION_TIME: u16 = 20;
 
+/// Default of the highest refund locktime (in blocks) of the outgoing contracts a Maker accepts, about two days.
+///
+/// Takers compute it per hop from `REFUND_LOCKTIME` and `REFUND_LOCKTIME_STEP` in `taker::api.rs`, which stays
+/// below this cap for routes of up to `MAX_MAKER_COUNT` makers.
+pub const MAX_ACCEPTED_LOCKTIME: u16 = 288;
+
 /// # Fee Parameters for Coinswap
 ///
 /// These parameters define the fees charged by Makers in a coinswap transaction.
 ///
-/// TODO: These parameters are currently hardcoded. Consider making them configurable for Makers in the future.
+/// These are the defaults of the advertised [`OfferFees`], which can be adjusted over RPC while the Maker is running.
 ///p
 /// - `BASE_FEE`: A fixed base fee charged by the Maker for providing its services
 /// - `AMOUNT_RELATIVE_FEE_PCT`: A percentage fee based on the swap amount.
@@ -109,6 +133,95 @@ pub const TIME_RELATIVE_FEE_PCT: f64 = 0.005;
 /// Minimum Coinswap amount; makers will not#[cfg(feature = "integration-test")] accept amounts below this.
 pub const MIN_SWAP_AMOUNT: u64 = 10_000;
 
+/// Fees charged by the Maker for a swap, as advertised in its offer.
+///
+/// See the fee parameters above for how the total swap fee is calculated.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OfferFees {
+    /// Fixed fee, in sats.
+    pub base_fee: u64,
+    /// Fee in percent of the swap amount.
+    pub amount_relative_fee_pct: f64,
+    /// Fee in percent of the swap amount, per block of refund locktime.
+    pub time_relative_fee_pct: f64,
+}
+
+impl Default for OfferFees {
+    fn default() -> Self {
+        Self {
+            base_fee: BASE_FEE,
+            amount_relative_fee_pct: AMOUNT_RELATIVE_FEE_PCT,
+            time_relative_fee_pct: TIME_RELATIVE_FEE_PCT,
+        }
+    }
+}
+
+/// The adjustable terms of the Maker's offer.
+///
+/// These can be changed over RPC while the Maker is running. New offers served to
+/// Takers, and new swaps, use the latest terms.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OfferTerms {
+    /// Minimum swap amount, in sats.
+    pub min_size: u64,
+    /// Maximum swap amount, in sats.
+    ///
+    /// The advertised maximum is further capped by the wallet's available liquidity.
+    pub max_size: u64,
+    /// Fees charged for a swap.
+    pub fee: OfferFees,
+}
+
+impl OfferTerms {
+    /// Checks that the terms are consistent.
+    pub fn validate(&self) -> Result<(), MakerError> {
+        if self.min_size == 0 {
+            return Err(MakerError::General("Offer min_size must be above zero"));
+        }
+        if self.min_size > self.max_size {
+            return Err(MakerError::General(
+                "Offer min_size must not be above max_size",
+            ));
+        }
+        let pcts = [
+            self.fee.amount_relative_fee_pct,
+            self.fee.time_relative_fee_pct,
+        ];
+        if pcts.iter().any(|pct| !pct.is_finite() || *pct < 0.0) {
+            return Err(MakerError::General(
+                "Offer fee percentages must be finite and not negative",
+            ));
+        }
+        if self.fee.amount_relative_fee_pct >= 100.0 {
+            return Err(MakerError::General(
+                "Offer amount relative fee must be below 100%",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if every input of the transaction is a segwit input, i.e. has a witness.
+///
+/// Nested segwit inputs also carry a witness, and count as segwit.
+pub(crate) fn spends_only_segwit_inputs(tx: &Transaction) -> bool {
+    tx.input.iter().all(|input| !input.witness.is_empty())
+}
+
+/// Rejects a funding tx spending non-segwit inputs, when the maker requires segwit funding.
+fn check_segwit_funding(require_segwit_funding: bool, tx: &Transaction) -> Result<(), MakerError> {
+    if require_segwit_funding && !spends_only_segwit_inputs(tx) {
+        log::warn!(
+            "Funding tx {} spends non-segwit inputs, rejecting it",
+            tx.compute_txid()
+        );
+        return Err(MakerError::General(
+            "Funding tx spends non-segwit inputs, which this maker doesn't accept",
+        ));
+    }
+    Ok(())
+}
+
 /// Interval for redeeming expired bonds, creating new ones if needed,  
 /// and updating the DNS server with the latest bond proof and maker address.
 #[cfg(feature = "integration-test")]
@@ -116,6 +229,12 @@ pub(crate) const FIDELITY_BOND_DNS_UPDATE_INTERVAL: u32 = 30;
 #[cfg(not(feature = "integration-test"))]
 pub(crate) const FIDELITY_BOND_DNS_UPDATE_INTERVAL: u32 = 600; // 1 Block Interval
 
+/// Interval to verify that the advertised fidelity bond is still unspent and valid.
+#[cfg(feature = "integration-test")]
+pub(crate) const FIDELITY_BOND_CHECK_INTERVAL: u32 = 9;
+#[cfg(not(feature = "integration-test"))]
+pub(crate) const FIDELITY_BOND_CHECK_INTERVAL: u32 = 60;
+
 /// Interval to check if there is enough liquidity for swaps.
 /// If the available balance is below the minimum, maker server won't listen for any swap requests until funds are added.
 #[cfg(feature = "integration-test")]
@@ -134,6 +253,8 @@ pub enum MakerBehavior {
     Normal,
     /// Simulates closure at the "Request Contract Signatures for Sender" step.
     CloseAtReqContractSigsForSender,
+    /// Simulates a slow response at the "Request Contract Signatures for Sender" step.
+    SlowAtReqContractSigsForSender,
     /// Simulates closure at the "Proof of Funding" step.
     CloseAtProofOfFunding,
     /// Simulates closure at the "Contract Signatures for Receiver and Sender" step.
@@ -142,8 +263,21 @@ pub enum MakerBehavior {
     CloseAtContractSigsForRecvr,
     /// Simulates closure at the "Hash Preimage" step.
     CloseAtHashPreimage,
+    /// Simulates dropping the connection at settlement, after the "Hash Preimage" is received
+    /// and recorded, but before the "Private Key Handover" is sent.
+    DropAtSettlement,
     /// Simulates broadcasting the contract immediately after setup.
     BroadcastContractAfterSetup,
+    /// Simulates substituting a different hashlock in the outgoing contracts.
+    WrongHashlock,
+    /// Simulates funding the last hop of the swap short, while claiming the agreed amount to the Taker.
+    ShortchangeLastHop,
+    /// Simulates a buggy maker sending every message twice.
+    ReplayMessages,
+    /// Simulates signing the sender's contracts with valid signatures, carrying the wrong sighash flag.
+    WrongSighashFlag,
+    /// Simulates building the contract txs of the incoming swap with a different miner fee than agreed.
+    WrongContractFee,
 }
 
 /// Expected messages for the taker in the context of [ConnectionState] structure.
@@ -170,6 +304,15 @@ pub(crate) struct ConnectionState {
     pub(crate) incoming_swapcoins: Vec<IncomingSwapCoin>,
     pub(crate) outgoing_swapcoins: Vec<OutgoingSwapCoin>,
     pub(crate) pending_funding_txes: Vec<Transaction>,
+    /// Large messages are sent compressed, as negotiated with the Taker at the handshake.
+    pub(crate) compression: bool,
+    /// Id of the swap, known from the Taker's proof of funding.
+    pub(crate) swap_id: Option<String>,
+    /// Time of the Taker's first proof of funding, the swap must complete within `swap_timeout` of.
+    pub(crate) started_at: Option<Instant>,
+    /// Value of the swap reserved against `max_contract_exposure` at the Taker's contract signatures
+    /// request, held until the swap settles or is aborted.
+    pub(crate) reserved_exposure: Amount,
 }
 
 pub(crate) struct ThreadPool {
@@ -244,6 +387,14 @@ pub struct Maker {
     pub(crate) data_dir: PathBuf,
     /// Thread pool for managing all spawned threads
     pub(crate) thread_pool: Arc<ThreadPool>,
+    /// Live terms of the advertised offer
+    pub(crate) offer_terms: RwLock<OfferTerms>,
+    /// A flag to re-register with the directory servers at the next server loop iteration
+    pub(crate) dns_update_requested: AtomicBool,
+    /// Log of the notable events, like swaps and bond rotations
+    pub(crate) event_log: EventLog,
+    /// Trace of the protocol messages, if enabled in the config
+    pub(crate) protocol_trace: ProtocolTrace,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -285,6 +436,9 @@ impl Maker {
 
         rpc_config.wallet_name = wallet_file_name;
 
+        // If config file doesn't exist, default config will be loaded.
+        let mut config = MakerConfig::new(Some(&data_dir.join("config.toml")))?;
+
         let mut wallet = if wallet_path.exists() {
             // wallet already exists , load the wallet
             let wallet = Wallet::load(&wallet_path, &rpc_config)?;
@@ -292,14 +446,11 @@ impl Maker {
             wallet
         } else {
             // wallet doesn't exists at the given path , create a new one
-            let wallet = Wallet::init(&wallet_path, &rpc_config)?;
+            let wallet = Wallet::init(&wallet_path, &rpc_config, config.address_strategy)?;
             log::info!("New Wallet created at : {:?}", wallet_path);
             wallet
         };
 
-        // If config file doesn't exist, default config will be loaded.
-        let mut config = MakerConfig::new(Some(&data_dir.join("config.toml")))?;
-
         if let Some(port) = network_port {
             config.network_port = port;
         }
@@ -324,18 +475,62 @@ impl Maker {
             config.tor_auth_password = tor_auth_password;
         }
 
+        if config.tor_only && config.connection_type != ConnectionType::TOR {
+            return Err(MakerError::General(
+                "tor_only is set, but the connection type is not TOR",
+            ));
+        }
+
+        check_bind_address(&config.bind_address, config.connection_type)?;
+
+        // Fail fast on ports taken by another process, like a second maker with the same config.
+        // Both ports are held while checking, so they can't be given the same free port.
+        let network_listener = select_port(
+            &config.bind_address,
+            config.network_port,
+            config.auto_select_ports,
+        )?;
+        let rpc_listener = select_port("127.0.0.1", config.rpc_port, config.auto_select_ports)?;
+        config.network_port = network_listener.local_addr()?.port();
+        config.rpc_port = rpc_listener.local_addr()?.port();
+        drop((network_listener, rpc_listener));
+
+        check_require_fidelity_bond(config.require_fidelity_bond, wallet.store.network)?;
+
         if matches!(connection_type, Some(ConnectionType::TOR)) {
             check_tor_status(config.control_port, config.tor_auth_password.as_str())?;
         }
 
         config.write_to_file(&data_dir.join("config.toml"))?;
 
+        wallet.set_anti_fee_sniping(config.anti_fee_sniping);
+        wallet.set_tx_ordering(config.tx_ordering);
+        wallet.set_avoid_swap_clustering(config.avoid_swap_clustering);
+        wallet.set_allow_key_export(config.allow_key_export);
+        wallet.set_reserve_amount(Amount::from_sat(config.reserve_amount));
+        wallet.set_min_fee_rate(config.min_fee_rate);
+
         log::info!("Initializing wallet sync");
         wallet.sync()?;
         log::info!("Completed wallet sync");
 
+        check_readiness(&config, &wallet)?;
+
         let network_port = config.network_port;
 
+        let offer_terms = OfferTerms {
+            min_size: config.min_swap_amount,
+            max_size: u64::MAX,
+            fee: OfferFees::default(),
+        };
+
+        let event_log = EventLog::new(data_dir.join(EVENT_LOG_FILE));
+        let protocol_trace = ProtocolTrace::new(
+            config
+                .protocol_trace
+                .then(|| data_dir.join(PROTOCOL_TRACE_FILE)),
+        );
+
         Ok(Self {
             behavior,
             config,
@@ -346,9 +541,25 @@ impl Maker {
             is_setup_complete: AtomicBool::new(false),
             data_dir,
             thread_pool: Arc::new(ThreadPool::new(network_port)),
+            offer_terms: RwLock::new(offer_terms),
+            dns_update_requested: AtomicBool::new(false),
+            event_log,
+            protocol_trace,
         })
     }
 
+    /// Records an event in the event log. A failure to write it is only logged, so it never
+    /// interrupts a swap.
+    pub(crate) fn record_event(&self, kind: MakerEventKind) {
+        if let Err(e) = self.event_log.record(kind) {
+            log::error!(
+                "[{}] Failed to record event: {:?}",
+                self.config.network_port,
+                e
+            );
+        }
+    }
+
     pub(crate) fn get_data_dir(&self) -> &PathBuf {
         &self.data_dir
     }
@@ -358,6 +569,66 @@ impl Maker {
         &self.wallet
     }
 
+    /// Returns the total value of the Maker's own coins locked in the contracts of live swaps.
+    ///
+    /// These are the values reserved by the ongoing swaps, from the Taker's contract signatures request
+    /// until they settle, and the outgoing swapcoins still in the wallet without the preimage, i.e. of
+    /// swaps being recovered.
+    pub fn contract_exposure(&self) -> Result<Amount, MakerError> {
+        let ongoing_swaps = self.ongoing_swap_state.lock()?;
+        self.contract_exposure_of(&ongoing_swaps)
+    }
+
+    /// [Maker::contract_exposure] of the already locked `ongoing_swaps`, for a new reservation to be
+    /// checked and made under the same lock.
+    pub(crate) fn contract_exposure_of(
+        &self,
+        ongoing_swaps: &HashMap<String, (ConnectionState, Instant)>,
+    ) -> Result<Amount, MakerError> {
+        let mut exposure = Amount::ZERO;
+        let mut ongoing_swapcoins = HashSet::new();
+        for (state, _) in ongoing_swaps.values() {
+            let mut outgoing_amount = Amount::ZERO;
+            for swapcoin in &state.outgoing_swapcoins {
+                ongoing_swapcoins.insert(swapcoin.get_multisig_redeemscript());
+                outgoing_amount += swapcoin.funding_amount;
+            }
+            // Swaps funded without a reservation, ex: with no cap set, count for their outgoing swapcoins.
+            exposure += state.reserved_exposure.max(outgoing_amount);
+        }
+        let (_, unfinished_outgoings) = self.wallet.read()?.find_unfinished_swapcoins();
+        for swapcoin in unfinished_outgoings {
+            if !ongoing_swapcoins.contains(&swapcoin.get_multisig_redeemscript()) {
+                exposure += swapcoin.funding_amount;
+            }
+        }
+        Ok(exposure)
+    }
+
+    /// Returns the offer terms currently advertised to Takers.
+    ///
+    /// The maximum size is capped by the wallet's available liquidity.
+    pub fn get_offer_terms(&self) -> Result<OfferTerms, MakerError> {
+        let mut terms = *self.offer_terms.read()?;
+        terms.max_size = terms.max_size.min(self.wallet.read()?.store.offer_maxsize);
+        Ok(terms)
+    }
+
+    /// Updates the live offer terms, after validating them.
+    ///
+    /// A re-registration with the directory servers is requested, so Takers pick up the change.
+    pub fn set_offer_terms(&self, terms: OfferTerms) -> Result<(), MakerError> {
+        terms.validate()?;
+        *self.offer_terms.write()? = terms;
+        self.dns_update_requested.store(true, Relaxed);
+        log::info!(
+            "[{}] Offer terms updated: {:?}",
+            self.config.network_port,
+            terms
+        );
+        Ok(())
+    }
+
     /// Ensures all unconfirmed fidelity bonds in the maker's wallet are tracked until confirmation.  
     /// Once confirmed, updates their confirmation details in the wallet.
     pub(super) fn track_and_update_unconfirmed_fidelity_bonds(&self) -> Result<(), MakerError> {
@@ -396,11 +667,28 @@ impl Maker {
     pub(crate) fn verify_proof_of_funding(
         &self,
         message: &ProofOfFunding,
-    ) -> Result<Hash160, MakerError> {
+    ) -> Result<Hashvalue, MakerError> {
         if message.confirmed_funding_txes.is_empty() {
             return Err(MakerError::General("No funding txs provided by Taker"));
         }
 
+        // The Taker negotiates the confirmation target upward. Asking for less than this maker's requirement
+        // would have it wait too little for the funding txs of the next hop.
+        if message.required_confirms != 0
+            && message.required_confirms < self.config.required_confirms
+        {
+            log::warn!(
+                "[{}] Taker confirmation target {} is below the required {}",
+                self.config.network_port,
+                message.required_confirms,
+                self.config.required_confirms
+            );
+            return Err(MakerError::General(
+                "Confirmation target below the maker's required confirmations",
+            ));
+        }
+        let required_confirms = message.required_confirms.max(self.config.required_confirms);
+
         for funding_info in &message.confirmed_funding_txes {
             // check that the new locktime is sufficently short enough compared to the
             // locktime in the provided funding tx
@@ -411,6 +699,8 @@ impl Maker {
                 ));
             }
 
+            check_segwit_funding(self.config.require_segwit_funding, &funding_info.funding_tx)?;
+
             let funding_output_index = find_funding_output_index(funding_info)?;
 
             //check the funding_tx is confirmed to required depth
@@ -425,7 +715,7 @@ impl Maker {
                 )
                 .map_err(WalletError::Rpc)?
             {
-                if txout.confirmations < REQUIRED_CONFIRMS {
+                if txout.confirmations < required_confirms {
                     return Err(MakerError::General(
                         "funding tx not confirmed to required depth",
                     ));
@@ -434,6 +724,11 @@ impl Maker {
                 return Err(MakerError::General("funding tx output doesnt exist"));
             }
 
+            self.wallet.read()?.verify_funding_tx_merkleproof(
+                &funding_info.funding_tx,
+                &funding_info.funding_tx_merkleproof,
+            )?;
+
             check_reedemscript_is_multisig(&funding_info.multisig_redeemscript)?;
 
             let (_, tweabale_pubkey) = self.wallet.read()?.get_tweakable_keypair()?;
@@ -474,8 +769,11 @@ impl Maker {
     pub(crate) fn verify_and_sign_contract_tx(
         &self,
         message: &ReqContractSigsForSender,
-    ) -> Result<Vec<Signature>, MakerError> {
+    ) -> Result<(Vec<Signature>, Vec<Vec<Signature>>), MakerError> {
         let mut sigs = Vec::<Signature>::new();
+        let mut escalated_sigs = Vec::new();
+        let fee_escalation =
+            agreed_fee_escalation(&message.fee_escalation, self.config.max_contract_fee);
         for txinfo in &message.txs_info {
             if txinfo.senders_contract_tx.input.len() != 1
                 || txinfo.senders_contract_tx.output.len() != 1
@@ -535,8 +833,21 @@ impl Maker {
                 &multisig_privkey,
             )?;
             sigs.push(sig);
+
+            check_fee_escalation_schedule(
+                &txinfo.senders_contract_tx,
+                txinfo.funding_input_value,
+                &message.fee_escalation,
+            )?;
+            escalated_sigs.push(sign_fee_escalated_contract_txs(
+                &txinfo.senders_contract_tx,
+                &txinfo.multisig_redeemscript,
+                txinfo.funding_input_value,
+                &fee_escalation,
+                &multisig_privkey,
+            )?);
         }
-        Ok(sigs)
+        Ok((sigs, escalated_sigs))
     }
 }
 
@@ -557,25 +868,20 @@ pub(crate) fn check_for_broadcasted_contracts(maker: Arc<Maker>) -> Result<(), M
                 let txids_to_watch = connection_state
                     .incoming_swapcoins
                     .iter()
-                    .map(|is| is.contract_tx.compute_txid())
-                    .chain(
-                        connection_state
-                            .outgoing_swapcoins
-                            .iter()
-                            .map(|oc| oc.contract_tx.compute_txid()),
-                    )
+                    .flat_map(|is| {
+                        std::iter::once(is.contract_tx.compute_txid())
+                            .chain(is.get_escalated_contract_txids())
+                    })
+                    .chain(connection_state.outgoing_swapcoins.iter().flat_map(|oc| {
+                        std::iter::once(oc.contract_tx.compute_txid())
+                            .chain(oc.get_escalated_contract_txids())
+                    }))
                     .collect::<Vec<_>>();
 
                 // No need to check for other contracts in the connection state, if any one of them
                 // is ever observed in the mempool/block, run recovery routine.
                 for txid in txids_to_watch {
-                    if maker
-                        .wallet
-                        .read()?
-                        .rpc
-                        .get_raw_transaction_info(&txid, None)
-                        .is_ok()
-                    {
+                    if maker.wallet.read()?.get_tx_confirmations(&txid).is_ok() {
                         let mut outgoings = Vec::new();
                         let mut incomings = Vec::new();
                         // Something is broadcasted. Report, Recover and Abort.
@@ -593,8 +899,8 @@ pub(crate) fn check_for_broadcasted_contracts(maker: Arc<Maker>) -> Result<(), M
                         {
                             let contract_timelock = og_sc.get_timelock()?;
                             let next_internal_address =
-                                &maker.wallet.read()?.get_next_internal_addresses(1)?[0];
-                            let time_lock_spend = maker.wallet.read()?.create_timelock_spend(
+                                &maker.wallet.write()?.get_next_internal_addresses(1)?[0];
+                            let time_lock_spend = maker.wallet.write()?.create_timelock_spend(
                                 og_sc,
                                 next_internal_address,
                                 DEFAULT_TX_FEE_RATE,
@@ -673,8 +979,8 @@ pub(crate) fn restore_broadcasted_contracts_on_reboot(
     // fully signed.
     for og_sc in out.iter() {
         let contract_timelock = og_sc.get_timelock()?;
-        let next_internal_address = &maker.wallet.read()?.get_next_internal_addresses(1)?[0];
-        let time_lock_spend = maker.wallet.read()?.create_timelock_spend(
+        let next_internal_address = &maker.wallet.write()?.get_next_internal_addresses(1)?[0];
+        let time_lock_spend = maker.wallet.write()?.create_timelock_spend(
             og_sc,
             next_internal_address,
             DEFAULT_TX_FEE_RATE,
@@ -736,9 +1042,390 @@ pub(crate) fn restore_broadcasted_contracts_on_reboot(
     Ok(())
 }
 
-/// Check that if any Taker connection went idle.
+/// Periodically saves the wallet to disk, every `wallet_autosave_interval` seconds, until shutdown.
+///
+/// This limits the data lost on a crash between the protocol-driven saves. The save is done under the
+/// wallet's read lock, so it never races with an in-progress mutation, which requires the write lock.
+/// A failed save, ex: on a transient I/O error, is logged and retried at the next tick.
+pub fn autosave_wallet(maker: Arc<Maker>) -> Result<(), MakerError> {
+    let interval = Duration::from_secs(maker.config.wallet_autosave_interval);
+    if interval.is_zero() {
+        return Ok(());
+    }
+
+    let mut last_saved = Instant::now();
+    while !maker.shutdown.load(Relaxed) {
+        if last_saved.elapsed() >= interval {
+            match maker.wallet.read()?.save_to_disk() {
+                Ok(()) => {
+                    log::debug!("[{}] Wallet auto-saved", maker.config.network_port);
+                    last_saved = Instant::now();
+                }
+                Err(e) => log::error!(
+                    "[{}] Wallet auto-save failed, retrying: {:?}",
+                    maker.config.network_port,
+                    e
+                ),
+            }
+        }
+        std::thread::sleep(interval.min(HEART_BEAT_INTERVAL));
+    }
+    Ok(())
+}
+
+/// Binds a listener to `port` on `address`, erroring with [`MakerError::PortInUse`] if the port is taken.
+pub(crate) fn bind_port(address: &str, port: u16) -> Result<TcpListener, MakerError> {
+    TcpListener::bind((address, port)).map_err(|e| match e.kind() {
+        ErrorKind::AddrInUse => MakerError::PortInUse { port },
+        _ => MakerError::IO(e),
+    })
+}
+
+/// Binds a listener to `port`, or to a free port when it's in use and `auto_select` is set.
+fn select_port(address: &str, port: u16, auto_select: bool) -> Result<TcpListener, MakerError> {
+    match bind_port(address, port) {
+        Err(MakerError::PortInUse { port }) if auto_select => {
+            let listener = bind_port(address, 0)?;
+            log::warn!(
+                "Port {} is already in use, using port {} instead",
+                port,
+                listener.local_addr()?.port()
+            );
+            Ok(listener)
+        }
+        Err(MakerError::PortInUse { port }) => {
+            log::error!(
+                "Port {} is already in use. Configure another port, or set auto_select_ports",
+                port
+            );
+            Err(MakerError::PortInUse { port })
+        }
+        result => result,
+    }
+}
+
+/// Bondless makers are only allowed on development and test networks. Mainnet always requires a fidelity bond.
+fn check_require_fidelity_bond(
+    require_fidelity_bond: bool,
+    network: Network,
+) -> Result<(), MakerError> {
+    if !require_fidelity_bond && network == Network::Bitcoin {
+        return Err(MakerError::General(
+            "require_fidelity_bond can't be disabled on mainnet",
+        ));
+    }
+    Ok(())
+}
+
+/// Errors if `bind_address` is not an IP address, or exposes a Tor maker beyond loopback.
+///
+/// A Tor maker is reached through its hidden service, which forwards to loopback. Listening on
+/// another interface would make it reachable on its clearnet IP too, deanonymizing it.
+fn check_bind_address(
+    bind_address: &str,
+    connection_type: ConnectionType,
+) -> Result<(), MakerError> {
+    let Ok(ip) = bind_address.parse::<IpAddr>() else {
+        log::error!("Invalid bind_address {}", bind_address);
+        return Err(MakerError::General(
+            "bind_address is not a valid IP address",
+        ));
+    };
+    if connection_type == ConnectionType::TOR && !ip.is_loopback() {
+        log::error!(
+            "bind_address {} is not a loopback address, which would expose the Tor maker on clearnet",
+            bind_address
+        );
+        return Err(MakerError::General(
+            "bind_address must be a loopback address when the connection type is TOR",
+        ));
+    }
+    Ok(())
+}
+
+/// Errors if the node is still syncing the blockchain.
+fn check_node_synced(
+    initial_block_download: bool,
+    blocks: u64,
+    headers: u64,
+) -> Result<(), MakerError> {
+    if initial_block_download || blocks < headers {
+        log::error!(
+            "The bitcoin node is not synced, at block {} of {}. Wait for it to finish syncing",
+            blocks,
+            headers
+        );
+        return Err(MakerError::NodeNotSynced { blocks, headers });
+    }
+    Ok(())
+}
+
+/// Runs a self-diagnostic of the node and the wallet at startup, and logs a readiness report.
+///
+/// Errors if the node is not synced, or serves another wallet. A missing or spent fidelity bond, and an
+/// empty wallet, are only reported, as the bond is created when the server starts.
+fn check_readiness(config: &MakerConfig, wallet: &Wallet) -> Result<(), MakerError> {
+    let chain_info = wallet.rpc.get_blockchain_info().map_err(WalletError::Rpc)?;
+    check_node_synced(
+        chain_info.initial_block_download,
+        chain_info.blocks,
+        chain_info.headers,
+    )?;
+
+    let loaded = wallet
+        .rpc
+        .get_wallet_info()
+        .map_err(WalletError::Rpc)?
+        .wallet_name;
+    if loaded != wallet.store.file_name {
+        log::error!(
+            "The bitcoin node serves the wallet {}, instead of {}. Check the rpc wallet name",
+            loaded,
+            wallet.store.file_name
+        );
+        return Err(MakerError::WrongWalletLoaded {
+            expected: wallet.store.file_name.clone(),
+            loaded,
+        });
+    }
+
+    log::info!(
+        "[{}] Readiness: node synced on {} at height {}",
+        config.network_port,
+        chain_info.chain,
+        chain_info.blocks
+    );
+    log::info!(
+        "[{}] Readiness: wallet {} loaded",
+        config.network_port,
+        loaded
+    );
+
+    if config.require_fidelity_bond {
+        let live_bond = wallet
+            .get_fidelity_bonds()
+            .values()
+            .filter(|(_, _, is_spent)| !is_spent)
+            .map(|(bond, _, _)| bond)
+            .find(|bond| {
+                wallet
+                    .rpc
+                    .get_tx_out(&bond.outpoint.txid, bond.outpoint.vout, Some(true))
+                    .is_ok_and(|txout| txout.is_some())
+            });
+        match live_bond {
+            Some(bond) => log::info!(
+                "[{}] Readiness: fidelity bond {} of {} unspent",
+                config.network_port,
+                bond.outpoint,
+                bond.amount
+            ),
+            None => log::warn!(
+                "[{}] Readiness: no unspent fidelity bond, a new one of {} is created at server start",
+                config.network_port,
+                Amount::from_sat(config.fidelity_amount)
+            ),
+        }
+    }
+
+    let spendable = wallet.get_balances()?.spendable;
+    if spendable == Amount::ZERO {
+        log::warn!(
+            "[{}] Readiness: no spendable balance, fund the wallet to serve swaps",
+            config.network_port
+        );
+    } else {
+        log::info!(
+            "[{}] Readiness: spendable balance {}",
+            config.network_port,
+            spendable
+        );
+    }
+    Ok(())
+}
+
+/// Fee allowance for the bond top-up tx, kept on top of the reserve so the fee never eats into it.
+const BOND_TOPUP_FEE_ALLOWANCE: Amount = Amount::from_sat(10_000);
+
+/// Amount of the bond to create for a top-up, if a bigger one than `current_bond` can be funded.
+///
+/// The new bond is capped at `target`, and only funded from the `spendable` balance above `reserve`.
+pub(crate) fn bond_topup_amount(
+    current_bond: Amount,
+    spendable: Amount,
+    target: Amount,
+    reserve: Amount,
+) -> Option<Amount> {
+    let available = spendable
+        .checked_sub(reserve)?
+        .checked_sub(BOND_TOPUP_FEE_ALLOWANCE)?;
+    let amount = available.min(target);
+    (amount > current_bond).then_some(amount)
+}
+
+/// Rolls swap earnings into a bigger fidelity bond, if `auto_bond_topup` is enabled.
+///
+/// A new bond is created when the spendable balance above `bond_topup_reserve` can fund a bond bigger
+/// than the current highest one, up to `bond_topup_target`. Bonds can't be extended, so the old bond
+/// stays locked until it expires. Nothing is done while a swap is ongoing or unfinished swapcoins are in
+/// the wallet, so the funds needed to settle or recover live contracts are never touched. The swap state
+/// stays locked until the bond is created, so no swap starts in between. The initial bond is created at
+/// server setup, and isn't handled here.
+///
+/// Returns the index of the new bond, if one was created.
+pub fn top_up_fidelity_bond(maker: &Maker) -> Result<Option<u32>, MakerError> {
+    if !maker.config.auto_bond_topup {
+        return Ok(None);
+    }
+    let ongoing_swaps = maker.ongoing_swap_state.lock()?;
+    if !ongoing_swaps.is_empty() {
+        return Ok(None);
+    }
+
+    maker.wallet.write()?.sync_no_fail();
+
+    let (amount, locktime) = {
+        let wallet = maker.wallet.read()?;
+        let (inc, out) = wallet.find_unfinished_swapcoins();
+        if !inc.is_empty() || !out.is_empty() {
+            log::info!(
+                "[{}] Skipping fidelity bond top-up, as swaps are unfinished",
+                maker.config.network_port
+            );
+            return Ok(None);
+        }
+
+        let current_bond = match wallet.get_highest_fidelity_index()? {
+            Some(i) => wallet.store.fidelity_bond[&i].0.amount,
+            None => return Ok(None),
+        };
+
+        let spendable = wallet.get_balances()?.spendable;
+        let amount = match bond_topup_amount(
+            current_bond,
+            spendable,
+            Amount::from_sat(maker.config.bond_topup_target),
+            Amount::from_sat(maker.config.bond_topup_reserve),
+        ) {
+            Some(amount) => amount,
+            None => return Ok(None),
+        };
+
+        let current_height = wallet.rpc.get_block_count().map_err(WalletError::Rpc)? as u32;
+        let locktime = LockTime::from_height(maker.config.fidelity_timelock + current_height)
+            .map_err(WalletError::Locktime)?;
+
+        log::info!(
+            "[{}] Topping up fidelity bond | Current: {} sats | New: {} sats | Spendable: {} sats",
+            maker.config.network_port,
+            current_bond.to_sat(),
+            amount.to_sat(),
+            spendable.to_sat()
+        );
+        (amount, locktime)
+    };
+
+    let index = maker
+        .wallet
+        .write()?
+        .create_fidelity(amount, locktime, DEFAULT_TX_FEE_RATE)?;
+
+    let mut wallet = maker.wallet.write()?;
+    wallet.sync_no_fail();
+    wallet.save_to_disk()?;
+    drop(wallet);
+    drop(ongoing_swaps);
+
+    maker.record_event(MakerEventKind::BondRotated { index });
+
+    Ok(Some(index))
+}
+
+/// The fees of a proposed contract fee escalation schedule, the maker agrees to sign variants for.
+///
+/// These are the leading fees of the schedule, up to `max_contract_fee` sats.
+pub(crate) fn agreed_fee_escalation(
+    fee_escalation: &[Amount],
+    max_contract_fee: u64,
+) -> Vec<Amount> {
+    fee_escalation
+        .iter()
+        .take_while(|fee| fee.to_sat() <= max_contract_fee)
+        .copied()
+        .collect()
+}
+
+/// Whether `utxo_count` regular UTXOs are worth consolidating at the estimated `feerate`.
+pub(crate) fn consolidation_due(
+    utxo_count: usize,
+    min_utxos: u32,
+    feerate: f64,
+    max_feerate: f64,
+) -> bool {
+    utxo_count >= (min_utxos as usize).max(2) && feerate <= max_feerate
+}
+
+/// Combines the regular UTXOs of the wallet into a single one, if `auto_consolidate` is enabled.
+///
+/// Consolidation happens when the wallet holds at least `consolidation_min_utxos` confirmed regular
+/// UTXOs, and the fee rate estimated for the next blocks is at most `consolidation_max_feerate`. Without
+/// an estimate, as on regtest, [DEFAULT_TX_FEE_RATE] is assumed, raised to `min_fee_rate`. Fidelity
+/// bonds, contract outputs and protected UTXOs are never spent, and nothing is done while a swap is
+/// ongoing or unfinished swapcoins are in the wallet.
+///
+/// Returns the txid of the consolidation tx, if one was broadcasted.
+pub fn consolidate_utxos(maker: &Maker) -> Result<Option<Txid>, MakerError> {
+    if !maker.config.auto_consolidate || !maker.ongoing_swap_state.lock()?.is_empty() {
+        return Ok(None);
+    }
+
+    let mut wallet = maker.wallet.write()?;
+    wallet.sync_no_fail();
+
+    let (inc, out) = wallet.find_unfinished_swapcoins();
+    if !inc.is_empty() || !out.is_empty() {
+        log::info!(
+            "[{}] Skipping UTXO consolidation, as swaps are unfinished",
+            maker.config.network_port
+        );
+        return Ok(None);
+    }
+
+    let coins = wallet.list_consolidation_utxo_spend_info()?;
+    let feerate = wallet.estimate_fee_rate(6);
+
+    if !consolidation_due(
+        coins.len(),
+        maker.config.consolidation_min_utxos,
+        feerate,
+        maker.config.consolidation_max_feerate,
+    ) {
+        return Ok(None);
+    }
+
+    let total = coins.iter().map(|(utxo, _)| utxo.amount).sum::<Amount>();
+    log::info!(
+        "[{}] Consolidating {} UTXOs | Total: {} sats | Fee rate: {} sats/vByte",
+        maker.config.network_port,
+        coins.len(),
+        total.to_sat(),
+        feerate
+    );
+
+    let address = wallet.get_next_internal_addresses(1)?[0].clone();
+    let tx = wallet.spend_from_wallet(feerate, Destination::Sweep(address), &coins)?;
+    let txid = wallet.send_tx(&tx)?;
+
+    wallet.sync_no_fail();
+    wallet.save_to_disk()?;
+
+    Ok(Some(txid))
+}
+
+/// Check that if any Taker connection went idle, or any swap went past the swap timeout.
 ///
 /// If a connection remains idle for more than idle timeout time, thats a potential DOS attack.
+/// A Taker that keeps responding but never completes the swap holds the Maker's funds just the same.
 /// Broadcast the contract transactions and claim funds via timelock.
 pub(crate) fn check_for_idle_states(maker: Arc<Maker>) -> Result<(), MakerError> {
     let mut bad_ip = Vec::new();
@@ -759,55 +1446,91 @@ pub(crate) fn check_for_idle_states(maker: Arc<Maker>) -> Result<(), MakerError>
                 let no_response_since =
                     current_time.saturating_duration_since(*last_connected_time);
 
-                if no_response_since > IDLE_CONNECTION_TIMEOUT {
-                    log::error!(
-                        "[{}] Potential Dropped Connection from taker. No response since : {} secs. Recovering from swap",
-                        maker.config.network_port,
-                        no_response_since.as_secs()
-                    );
-
-                    // Extract Incoming and Outgoing contracts, and timelock spends of the contract transactions.
-                    // fully signed.
-                    for (og_sc, ic_sc) in state
-                        .outgoing_swapcoins
-                        .iter()
-                        .zip(state.incoming_swapcoins.iter())
+                // A swap never funded only holds its contract exposure reservation, there is
+                // nothing to recover. The Taker aborted it, release the reservation.
+                if state.started_at.is_none() {
+                    if no_response_since > Duration::from_secs(maker.config.idle_connection_timeout)
                     {
-                        let contract_timelock = og_sc.get_timelock()?;
-                        let contract = og_sc.get_fully_signed_contract_tx()?;
-                        let next_internal_address =
-                            &maker.wallet.read()?.get_next_internal_addresses(1)?[0];
-                        let time_lock_spend = maker.wallet.read()?.create_timelock_spend(
-                            og_sc,
-                            next_internal_address,
-                            DEFAULT_TX_FEE_RATE,
-                        )?;
-                        outgoings.push((
-                            (og_sc.get_multisig_redeemscript(), contract),
-                            (contract_timelock, time_lock_spend),
-                        ));
-                        let incoming_contract = ic_sc.get_fully_signed_contract_tx()?;
-                        incomings.push((ic_sc.get_multisig_redeemscript(), incoming_contract));
+                        log::info!(
+                            "[{}] Releasing the contract exposure of {} reserved by an aborted swap",
+                            maker.config.network_port,
+                            state.reserved_exposure
+                        );
+                        bad_ip.push(ip.clone());
                     }
-                    bad_ip.push(ip.clone());
-                    // Spawn a separate thread to wait for contract maturity and broadcasting timelocked.
-                    let maker_clone = maker.clone();
-                    log::info!(
-                        "[{}] Spawning recovery thread after Taker dropped",
-                        maker.config.network_port
-                    );
-                    let handle = std::thread::Builder::new()
-                        .name("Swap Recovery Thread".to_string())
-                        .spawn(move || {
-                            if let Err(e) = recover_from_swap(maker_clone, outgoings, incomings) {
-                                log::error!("Failed to recover from swap due to: {:?}", e);
-                            }
-                        })?;
-                    maker.thread_pool.add_thread(handle);
-                    // Clear the state values here
-                    *state = ConnectionState::default();
-                    break;
+                    continue;
+                }
+
+                let swap_duration = state
+                    .started_at
+                    .map(|started_at| current_time.saturating_duration_since(started_at))
+                    .unwrap_or_default();
+
+                let reason = if no_response_since
+                    > Duration::from_secs(maker.config.idle_connection_timeout)
+                {
+                    format!(
+                        "No response from the Taker since {} secs, recovering the contracts",
+                        no_response_since.as_secs()
+                    )
+                } else if maker.config.swap_timeout != 0
+                    && swap_duration > Duration::from_secs(maker.config.swap_timeout)
+                {
+                    format!(
+                        "Swap not completed {} secs after the proof of funding, recovering the contracts",
+                        swap_duration.as_secs()
+                    )
+                } else {
+                    continue;
+                };
+
+                log::error!("[{}] {}", maker.config.network_port, reason);
+
+                // Extract Incoming and Outgoing contracts, and timelock spends of the contract transactions.
+                // fully signed.
+                for (og_sc, ic_sc) in state
+                    .outgoing_swapcoins
+                    .iter()
+                    .zip(state.incoming_swapcoins.iter())
+                {
+                    let contract_timelock = og_sc.get_timelock()?;
+                    let contract = og_sc.get_fully_signed_contract_tx()?;
+                    let next_internal_address =
+                        &maker.wallet.write()?.get_next_internal_addresses(1)?[0];
+                    let time_lock_spend = maker.wallet.write()?.create_timelock_spend(
+                        og_sc,
+                        next_internal_address,
+                        DEFAULT_TX_FEE_RATE,
+                    )?;
+                    outgoings.push((
+                        (og_sc.get_multisig_redeemscript(), contract),
+                        (contract_timelock, time_lock_spend),
+                    ));
+                    let incoming_contract = ic_sc.get_fully_signed_contract_tx()?;
+                    incomings.push((ic_sc.get_multisig_redeemscript(), incoming_contract));
                 }
+                bad_ip.push(ip.clone());
+                maker.record_event(MakerEventKind::SwapFailed {
+                    id: Some(ip.clone()),
+                    reason,
+                });
+                // Spawn a separate thread to wait for contract maturity and broadcasting timelocked.
+                let maker_clone = maker.clone();
+                log::info!(
+                    "[{}] Spawning recovery thread after Taker dropped",
+                    maker.config.network_port
+                );
+                let handle = std::thread::Builder::new()
+                    .name("Swap Recovery Thread".to_string())
+                    .spawn(move || {
+                        if let Err(e) = recover_from_swap(maker_clone, outgoings, incomings) {
+                            log::error!("Failed to recover from swap due to: {:?}", e);
+                        }
+                    })?;
+                maker.thread_pool.add_thread(handle);
+                // Clear the state values here
+                *state = ConnectionState::default();
+                break;
             }
 
             // Clear the state entry here
@@ -827,7 +1550,7 @@ pub(crate) fn check_for_idle_states(maker: Arc<Maker>) -> Result<(), MakerError>
 pub(crate) fn recover_from_swap(
     maker: Arc<Maker>,
     // Tuple of ((Multisig_reedemscript, Contract Tx), (Timelock, Timelock Tx))
-    outgoings: Vec<((ScriptBuf, Transaction), (u16, Transaction))>,
+    mut outgoings: Vec<((ScriptBuf, Transaction), (u16, Transaction))>,
     // Tuple of (Multisig Reedemscript, Contract Tx)
     incomings: Vec<(ScriptBuf, Transaction)>,
 ) -> Result<(), MakerError> {
@@ -836,8 +1559,7 @@ pub(crate) fn recover_from_swap(
         if maker
             .wallet
             .read()?
-            .rpc
-            .get_raw_transaction_info(&tx.compute_txid(), None)
+            .get_tx_confirmations(&tx.compute_txid())
             .is_ok()
         {
             log::info!(
@@ -871,12 +1593,11 @@ pub(crate) fn recover_from_swap(
     }
 
     //broadcast all the outgoing contracts
-    for ((og_rs, tx), _) in outgoings.iter() {
+    for ((og_rs, tx), (_, timelocked_tx)) in outgoings.iter_mut() {
         let check_tx_result = maker
             .wallet
             .read()?
-            .rpc
-            .get_raw_transaction_info(&tx.compute_txid(), None);
+            .get_tx_confirmations(&tx.compute_txid());
 
         match check_tx_result {
             Ok(_) => {
@@ -895,6 +1616,31 @@ pub(crate) fn recover_from_swap(
                             tx.compute_txid()
                         );
                     }
+                    // Too low fee for the current mempool, go for a fee-escalated variant.
+                    Err(WalletError::FeeTooLow { required }) => {
+                        log::warn!(
+                            "[{}] Outgoing Contract rejected, {} sats fee required. Bumping its fee : {}",
+                            maker.config.network_port,
+                            required,
+                            tx.compute_txid()
+                        );
+                        match maker
+                            .wallet
+                            .write()?
+                            .bump_outgoing_contract_fee(og_rs, DEFAULT_TX_FEE_RATE)
+                        {
+                            Ok(Some((escalated_tx, timelock_spend))) => {
+                                *tx = escalated_tx;
+                                *timelocked_tx = timelock_spend;
+                            }
+                            Ok(None) => log::warn!(
+                                "[{}] No fee-escalated variant left for Outgoing Contract : {}",
+                                maker.config.network_port,
+                                tx.compute_txid()
+                            ),
+                            Err(e) => log::warn!("Contract Tx fee bump failed : {:?}", e),
+                        }
+                    }
                     Err(e) => {
                         log::info!(
                             "Can't send ougoing contract: {} | {:?}",
@@ -930,34 +1676,77 @@ pub(crate) fn recover_from_swap(
 
     let mut i = 0;
 
+    // Height of the last broadcast of each outgoing contract, to bump its fee once stuck.
+    let start_height = maker
+        .wallet
+        .read()?
+        .rpc
+        .get_block_count()
+        .map_err(WalletError::Rpc)?;
+    let mut broadcast_heights = vec![start_height; outgoings.len()];
+
     while !maker.shutdown.load(Relaxed) {
         if i >= trigger_count || i == 0 {
-            for ((outgoing_reedemscript, contract), (timelock, timelocked_tx)) in outgoings.iter() {
+            for (
+                ((outgoing_reedemscript, contract), (timelock, timelocked_tx)),
+                broadcast_height,
+            ) in outgoings.iter_mut().zip(broadcast_heights.iter_mut())
+            {
                 // We have already broadcasted this tx, so skip
-                if timelock_boardcasted.contains(&timelocked_tx) {
+                if timelock_boardcasted.contains(&timelocked_tx.compute_txid()) {
                     continue;
                 }
                 // Check if the contract tx has reached required maturity
                 // Failure here means the transaction hasn't been broadcasted yet. So do nothing and try again.
-                let tx_from_chain = if let Ok(result) = maker
+                let confirmations = if let Ok(confirmations) = maker
                     .wallet
                     .read()?
-                    .rpc
-                    .get_raw_transaction_info(&contract.compute_txid(), None)
+                    .get_tx_confirmations(&contract.compute_txid())
                 {
                     log::info!(
                         "[{}] Contract Txid : {} reached confirmation : {:?}, Required Confirmation : {}",
                         maker.config.network_port,
                         contract.compute_txid(),
-                        result.confirmations,
+                        confirmations,
                         timelock
                     );
-                    result
+                    confirmations
                 } else {
                     continue;
                 };
 
-                if let Some(confirmation) = tx_from_chain.confirmations {
+                let current_height = maker
+                    .wallet
+                    .read()?
+                    .rpc
+                    .get_block_count()
+                    .map_err(WalletError::Rpc)?;
+                if confirmations.is_none()
+                    && current_height >= *broadcast_height + CONTRACT_FEE_ESCALATION_BLOCKS
+                {
+                    // Still stuck in the mempool, replace it by the next fee-escalated variant.
+                    match maker
+                        .wallet
+                        .write()?
+                        .bump_outgoing_contract_fee(outgoing_reedemscript, DEFAULT_TX_FEE_RATE)
+                    {
+                        Ok(Some((escalated_tx, timelock_spend))) => {
+                            log::info!(
+                                "[{}] Replaced unconfirmed Contract Txid : {} by fee-escalated Contract Txid : {}",
+                                maker.config.network_port,
+                                contract.compute_txid(),
+                                escalated_tx.compute_txid()
+                            );
+                            *contract = escalated_tx;
+                            *timelocked_tx = timelock_spend;
+                        }
+                        Ok(None) => {}
+                        Err(e) => log::warn!("Contract Tx fee bump failed : {:?}", e),
+                    }
+                    *broadcast_height = current_height;
+                }
+
+                if let Some(confirmation) = confirmations {
                     // Now the transaction is confirmed in a block, check for required maturity
                     if confirmation > (*timelock as u32) {
                         log::info!(
@@ -975,9 +1764,9 @@ pub(crate) fn recover_from_swap(
                             .wallet
                             .read()?
                             .rpc
-                            .send_raw_transaction(timelocked_tx)
+                            .send_raw_transaction(&*timelocked_tx)
                             .map_err(WalletError::Rpc)?;
-                        timelock_boardcasted.push(timelocked_tx);
+                        timelock_boardcasted.push(timelocked_tx.compute_txid());
 
                         let outgoing_removed = maker
                             .wallet
@@ -1026,3 +1815,238 @@ pub(crate) fn recover_from_swap(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_bind_address() {
+        assert!(check_bind_address("127.0.0.1", ConnectionType::CLEARNET).is_ok());
+        assert!(check_bind_address("0.0.0.0", ConnectionType::CLEARNET).is_ok());
+        assert!(check_bind_address("127.0.0.1", ConnectionType::TOR).is_ok());
+        assert!(check_bind_address("::1", ConnectionType::TOR).is_ok());
+        assert!(check_bind_address("not-an-ip", ConnectionType::CLEARNET).is_err());
+
+        // A Tor maker doesn't listen beyond loopback.
+        for address in ["0.0.0.0", "192.168.1.10", "::"] {
+            assert!(matches!(
+                check_bind_address(address, ConnectionType::TOR),
+                Err(MakerError::General(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_check_node_synced() {
+        assert!(check_node_synced(false, 800_000, 800_000).is_ok());
+        assert!(matches!(
+            check_node_synced(true, 800_000, 800_000),
+            Err(MakerError::NodeNotSynced { .. })
+        ));
+        assert!(matches!(
+            check_node_synced(false, 700_000, 800_000),
+            Err(MakerError::NodeNotSynced {
+                blocks: 700_000,
+                headers: 800_000
+            })
+        ));
+    }
+
+    #[test]
+    fn test_bond_topup_amount() {
+        let sats = Amount::from_sat;
+        let target = sats(1_000_000);
+        let reserve = sats(200_000);
+
+        // Earnings above the reserve grow the bond.
+        assert_eq!(
+            bond_topup_amount(sats(100_000), sats(710_000), target, reserve),
+            Some(sats(500_000))
+        );
+        // The bond is capped at the target.
+        assert_eq!(
+            bond_topup_amount(sats(100_000), sats(5_000_000), target, reserve),
+            Some(target)
+        );
+        // Nothing to do, once the target is reached.
+        assert_eq!(
+            bond_topup_amount(target, sats(5_000_000), target, reserve),
+            None
+        );
+        // Not enough earnings above the reserve for a bigger bond.
+        assert_eq!(
+            bond_topup_amount(sats(500_000), sats(710_000), target, reserve),
+            None
+        );
+        // The reserve is never touched.
+        assert_eq!(
+            bond_topup_amount(sats(100_000), sats(150_000), target, reserve),
+            None
+        );
+    }
+
+    #[test]
+    fn test_check_require_fidelity_bond() {
+        for network in [Network::Regtest, Network::Signet, Network::Testnet] {
+            assert!(check_require_fidelity_bond(false, network).is_ok());
+        }
+        assert!(check_require_fidelity_bond(true, Network::Bitcoin).is_ok());
+        assert!(matches!(
+            check_require_fidelity_bond(false, Network::Bitcoin),
+            Err(MakerError::General(_))
+        ));
+    }
+
+    #[test]
+    fn test_select_port() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        assert!(matches!(
+            select_port("127.0.0.1", port, false),
+            Err(MakerError::PortInUse { port: p }) if p == port
+        ));
+
+        let selected = select_port("127.0.0.1", port, true).unwrap();
+        assert_ne!(selected.local_addr().unwrap().port(), port);
+
+        drop(taken);
+        let selected = select_port("127.0.0.1", port, false).unwrap();
+        assert_eq!(selected.local_addr().unwrap().port(), port);
+    }
+
+    #[test]
+    fn test_consolidation_due() {
+        // Enough UTXOs at a low fee rate.
+        assert!(consolidation_due(20, 20, 1.0, 2.0));
+        assert!(consolidation_due(25, 20, 2.0, 2.0));
+        // Too few UTXOs.
+        assert!(!consolidation_due(19, 20, 1.0, 2.0));
+        // Fees are too high.
+        assert!(!consolidation_due(50, 20, 2.5, 2.0));
+        // A single UTXO is never worth a tx.
+        assert!(!consolidation_due(1, 0, 1.0, 2.0));
+    }
+
+    #[test]
+    fn test_agreed_fee_escalation() {
+        let schedule = [2000, 4000, 8000].map(Amount::from_sat);
+        assert_eq!(agreed_fee_escalation(&schedule, 10_000), schedule.to_vec());
+        // Only the leading fees within the cap.
+        assert_eq!(
+            agreed_fee_escalation(&schedule, 4000),
+            schedule[..2].to_vec()
+        );
+        assert!(agreed_fee_escalation(&schedule, 1999).is_empty());
+        // Disabled.
+        assert!(agreed_fee_escalation(&schedule, 0).is_empty());
+    }
+
+    #[test]
+    fn test_reject_legacy_funding_inputs() {
+        use bitcoin::{
+            absolute::LockTime, hashes::Hash, transaction::Version, Amount, Sequence, TxIn, TxOut,
+            Txid, Witness,
+        };
+
+        let input = |vout: u32, script_sig: ScriptBuf, witness: Witness| TxIn {
+            previous_output: OutPoint::new(Txid::all_zeros(), vout),
+            script_sig,
+            sequence: Sequence::MAX,
+            witness,
+        };
+        let segwit_input = |vout| input(vout, ScriptBuf::new(), Witness::from_slice(&[[1u8; 72]]));
+        // A P2PKH spend carries the signature in the scriptsig, and no witness.
+        let legacy_input = |vout| {
+            input(
+                vout,
+                ScriptBuf::from_bytes(vec![0x01, 0x01]),
+                Witness::new(),
+            )
+        };
+        let funding_tx = |input| Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input,
+            output: vec![TxOut {
+                value: Amount::from_sat(10_000),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        };
+
+        assert!(spends_only_segwit_inputs(&funding_tx(vec![
+            segwit_input(0),
+            segwit_input(1)
+        ])));
+        assert!(!spends_only_segwit_inputs(&funding_tx(vec![
+            segwit_input(0),
+            legacy_input(1)
+        ])));
+
+        // A segwit-only maker rejects the funding with a legacy input, others accept it.
+        let mixed_funding = funding_tx(vec![segwit_input(0), legacy_input(1)]);
+        assert!(matches!(
+            check_segwit_funding(true, &mixed_funding),
+            Err(MakerError::General(_))
+        ));
+        assert!(check_segwit_funding(false, &mixed_funding).is_ok());
+        assert!(check_segwit_funding(true, &funding_tx(vec![segwit_input(0)])).is_ok());
+    }
+
+    #[test]
+    fn test_offer_terms_validation() {
+        let terms = OfferTerms {
+            min_size: 10_000,
+            max_size: 1_000_000,
+            fee: OfferFees::default(),
+        };
+        assert!(terms.validate().is_ok());
+
+        // Equal bounds are allowed.
+        let fixed_size = OfferTerms {
+            min_size: 1_000_000,
+            ..terms
+        };
+        assert!(fixed_size.validate().is_ok());
+
+        let inverted_bounds = OfferTerms {
+            min_size: 2_000_000,
+            ..terms
+        };
+        assert!(inverted_bounds.validate().is_err());
+
+        let zero_min = OfferTerms {
+            min_size: 0,
+            ..terms
+        };
+        assert!(zero_min.validate().is_err());
+
+        let negative_fee = OfferTerms {
+            fee: OfferFees {
+                time_relative_fee_pct: -0.1,
+                ..terms.fee
+            },
+            ..terms
+        };
+        assert!(negative_fee.validate().is_err());
+
+        let whole_amount_fee = OfferTerms {
+            fee: OfferFees {
+                amount_relative_fee_pct: 100.0,
+                ..terms.fee
+            },
+            ..terms
+        };
+        assert!(whole_amount_fee.validate().is_err());
+
+        let nan_fee = OfferTerms {
+            fee: OfferFees {
+                amount_relative_fee_pct: f64::NAN,
+                ..terms.fee
+            },
+            ..terms
+        };
+        assert!(nan_fee.validate().is_err());
+    }
+}
@@ -5,9 +5,15 @@ use std::{io, path::Path};
 
 use std::io::Write;
 
-use crate::utill::{get_maker_dir, parse_field, ConnectionType};
+use crate::{
+    utill::{
+        get_maker_dir, parse_field, parse_list, ConnectionType, MIN_FEE_RATE, REQUIRED_CONFIRMS,
+        TCP_KEEPALIVE_INTERVAL,
+    },
+    wallet::{AddressStrategy, AntiFeeSniping, TxOrdering},
+};
 
-use super::api::MIN_SWAP_AMOUNT;
+use super::api::{IDLE_CONNECTION_TIMEOUT, MAX_ACCEPTED_LOCKTIME, MIN_SWAP_AMOUNT, SWAP_TIMEOUT};
 
 /// Maker Configuration, controlling various maker behavior.
 #[derive(Debug, Clone, PartialEq)]
@@ -24,14 +30,88 @@ pub struct MakerConfig {
     pub socks_port: u16,
     /// Authentication password
     pub tor_auth_password: String,
-    /// Directory server address (can be clearnet or onion)
-    pub directory_server_address: String,
+    /// Directory server addresses (can be clearnet or onion). The maker registers with all of them.
+    pub directory_server_addresses: Vec<String>,
     /// Fidelity Bond amount
     pub fidelity_amount: u64,
     /// Fidelity Bond timelock in Block heights.
     pub fidelity_timelock: u32,
     /// Connection type
     pub connection_type: ConnectionType,
+    /// Only accept taker connections arriving through the Tor hidden service.
+    pub tor_only: bool,
+    /// Signal BIP125 opt-in RBF on funding tx inputs. If `false`, funding txs are non-replaceable.
+    pub funding_rbf: bool,
+    /// Interval in seconds for periodically saving the wallet to disk. `0` disables the auto-save.
+    pub wallet_autosave_interval: u64,
+    /// nLockTime policy of funding and regular spend txs.
+    pub anti_fee_sniping: AntiFeeSniping,
+    /// Ordering of the inputs and outputs of funding and regular spend txs.
+    pub tx_ordering: TxOrdering,
+    /// Keychain layout of the change addresses. Only applies when the wallet is created.
+    pub address_strategy: AddressStrategy,
+    /// Allow exporting the wallet seed mnemonic over the RPC, for backups.
+    pub allow_seed_export: bool,
+    /// Allow exporting the private keys of wallet addresses.
+    pub allow_key_export: bool,
+    /// Seconds without any message or heartbeat from the Taker, after which an ongoing swap is recovered.
+    pub idle_connection_timeout: u64,
+    /// Seconds after the Taker's proof of funding, after which an uncompleted swap is recovered, even if the
+    /// Taker is still responding. `0` disables it.
+    pub swap_timeout: u64,
+    /// Don't fund swaps with outputs created by recent swaps, so they aren't clustered together.
+    pub avoid_swap_clustering: bool,
+    /// Reject swaps whose incoming funding txs spend non-segwit inputs. Advertised in the offer.
+    pub require_segwit_funding: bool,
+    /// Highest number of funding txs accepted in a swap. Advertised in the offer. `0` disables the limit.
+    pub max_tx_count: u32,
+    /// Periodically roll swap earnings into a bigger fidelity bond.
+    pub auto_bond_topup: bool,
+    /// Fidelity bond amount in sats, the auto top-up grows the bond up to.
+    pub bond_topup_target: u64,
+    /// Spendable balance in sats, the auto top-up always leaves in the wallet.
+    pub bond_topup_reserve: u64,
+    /// Swaps below this amount in sats are rejected as probing, whatever the advertised `min_size`. `0` disables it.
+    pub probing_floor: u64,
+    /// Periodically combine small regular UTXOs into a single one, while no swap is ongoing.
+    pub auto_consolidate: bool,
+    /// Minimum number of regular UTXOs in the wallet, for a consolidation to happen.
+    pub consolidation_min_utxos: u32,
+    /// Highest estimated fee rate in sats/vByte, at which a consolidation is done.
+    pub consolidation_max_feerate: f64,
+    /// Seconds a swap connection is idle before TCP keepalive probes are sent. `0` disables the keepalive.
+    pub tcp_keepalive_interval: u64,
+    /// Set `TCP_NODELAY` on swap connections, so the protocol messages are sent without delay.
+    pub tcp_nodelay: bool,
+    /// Highest miner fee in sats of the fee-escalated contract tx variants the maker signs. `0` signs none.
+    pub max_contract_fee: u64,
+    /// Balance in sats never spent by swaps or automatic coin selection. `0` reserves nothing.
+    pub reserve_amount: u64,
+    /// Confirmations required on the incoming funding txs, advertised in the offer. Takers asking for
+    /// less are declined.
+    pub required_confirms: u32,
+    /// IP address of the interface the swap listener binds to. `0.0.0.0` listens on all interfaces.
+    pub bind_address: String,
+    /// Floor in sats/vByte of the fee rate of every transaction, applied after any fee estimate.
+    pub min_fee_rate: f64,
+    /// Highest refund locktime in blocks of the outgoing contracts, proposed by the Taker, that is accepted.
+    pub max_accepted_locktime: u16,
+    /// Serve offers only with a fidelity bond. Can't be disabled on mainnet.
+    pub require_fidelity_bond: bool,
+    /// Trace every protocol message exchanged with the takers to `protocol_trace.log` in the data
+    /// directory, with the secrets redacted. For troubleshooting.
+    pub protocol_trace: bool,
+    /// Expect a PROXY protocol (v1 or v2) header on every connection, sent by a TCP reverse proxy in
+    /// front of the maker, and attribute the connection to the client address it declares.
+    pub proxy_protocol: bool,
+    /// Highest number of connections accepted from a client IP per minute. 0 disables the limit.
+    pub max_connections_per_minute: u32,
+    /// Highest total value in sats of the maker's own coins locked in the contracts of live swaps.
+    /// Swaps that would go above it are declined. 0 disables the cap.
+    pub max_contract_exposure: u64,
+    /// Pick a free port when the configured network or RPC port is already in use, instead of failing
+    /// to start. The chosen ports are logged and saved to the config file.
+    pub auto_select_ports: bool,
 }
 
 impl Default for MakerConfig {
@@ -43,12 +123,15 @@ impl Default for MakerConfig {
             control_port: 9051,
             socks_port: 9050,
             tor_auth_password: "".to_string(),
-            directory_server_address:
-                "ri3t5m2na2eestaigqtxm3f4u7njy65aunxeh7aftgid3bdeo3bz65qd.onion:8080".to_string(),
+            directory_server_addresses: vec![if cfg!(feature = "integration-test") {
+                "127.0.0.1:8080".to_string()
+            } else {
+                "ri3t5m2na2eestaigqtxm3f4u7njy65aunxeh7aftgid3bdeo3bz65qd.onion:8080".to_string()
+            }],
             #[cfg(feature = "integration-test")]
             fidelity_amount: 5_000_000, // 0.05 BTC for tests
             #[cfg(feature = "integration-test")]
-            fidelity_timelock: 26_000, // Approx 6 months of blocks for test
+            fidelity_timelock: 950, // Quick maturity for tests
             #[cfg(not(feature = "integration-test"))]
             fidelity_amount: 50_000, // 50K sats for production
             #[cfg(not(feature = "integration-test"))]
@@ -58,6 +141,40 @@ impl Default for MakerConfig {
             } else {
                 ConnectionType::TOR
             },
+            tor_only: false,
+            funding_rbf: true,
+            wallet_autosave_interval: 0,
+            anti_fee_sniping: AntiFeeSniping::default(),
+            tx_ordering: TxOrdering::default(),
+            address_strategy: AddressStrategy::default(),
+            allow_seed_export: false,
+            allow_key_export: false,
+            idle_connection_timeout: IDLE_CONNECTION_TIMEOUT.as_secs(),
+            swap_timeout: SWAP_TIMEOUT.as_secs(),
+            avoid_swap_clustering: false,
+            require_segwit_funding: false,
+            max_tx_count: 0,
+            auto_bond_topup: false,
+            bond_topup_target: 500_000,
+            bond_topup_reserve: 1_000_000,
+            probing_floor: 0,
+            auto_consolidate: false,
+            consolidation_min_utxos: 20,
+            consolidation_max_feerate: 2.0,
+            tcp_keepalive_interval: TCP_KEEPALIVE_INTERVAL,
+            tcp_nodelay: true,
+            max_contract_fee: 10_000,
+            reserve_amount: 0,
+            required_confirms: REQUIRED_CONFIRMS,
+            bind_address: "127.0.0.1".to_string(),
+            min_fee_rate: MIN_FEE_RATE,
+            max_accepted_locktime: MAX_ACCEPTED_LOCKTIME,
+            require_fidelity_bond: true,
+            protocol_trace: false,
+            proxy_protocol: false,
+            max_connections_per_minute: 0,
+            max_contract_exposure: 0,
+            auto_select_ports: false,
         }
     }
 }
@@ -109,9 +226,13 @@ impl MakerConfig {
                 config_map.get("tor_auth_password"),
                 default_config.tor_auth_password,
             ),
-            directory_server_address: parse_field(
-                config_map.get("directory_server_address"),
-                default_config.directory_server_address,
+            // Older configs hold a single `directory_server_address`.
+            directory_server_addresses: parse_list(
+                config_map.get("directory_server_addresses"),
+                parse_list(
+                    config_map.get("directory_server_address"),
+                    default_config.directory_server_addresses,
+                ),
             ),
             fidelity_amount: parse_field(
                 config_map.get("fidelity_amount"),
@@ -125,6 +246,118 @@ impl MakerConfig {
                 config_map.get("connection_type"),
                 default_config.connection_type,
             ),
+            tor_only: parse_field(config_map.get("tor_only"), default_config.tor_only),
+            funding_rbf: parse_field(config_map.get("funding_rbf"), default_config.funding_rbf),
+            wallet_autosave_interval: parse_field(
+                config_map.get("wallet_autosave_interval"),
+                default_config.wallet_autosave_interval,
+            ),
+            anti_fee_sniping: parse_field(
+                config_map.get("anti_fee_sniping"),
+                default_config.anti_fee_sniping,
+            ),
+            tx_ordering: parse_field(config_map.get("tx_ordering"), default_config.tx_ordering),
+            address_strategy: parse_field(
+                config_map.get("address_strategy"),
+                default_config.address_strategy,
+            ),
+            allow_seed_export: parse_field(
+                config_map.get("allow_seed_export"),
+                default_config.allow_seed_export,
+            ),
+            allow_key_export: parse_field(
+                config_map.get("allow_key_export"),
+                default_config.allow_key_export,
+            ),
+            idle_connection_timeout: parse_field(
+                config_map.get("idle_connection_timeout"),
+                default_config.idle_connection_timeout,
+            ),
+            swap_timeout: parse_field(config_map.get("swap_timeout"), default_config.swap_timeout),
+            avoid_swap_clustering: parse_field(
+                config_map.get("avoid_swap_clustering"),
+                default_config.avoid_swap_clustering,
+            ),
+            require_segwit_funding: parse_field(
+                config_map.get("require_segwit_funding"),
+                default_config.require_segwit_funding,
+            ),
+            max_tx_count: parse_field(config_map.get("max_tx_count"), default_config.max_tx_count),
+            auto_bond_topup: parse_field(
+                config_map.get("auto_bond_topup"),
+                default_config.auto_bond_topup,
+            ),
+            bond_topup_target: parse_field(
+                config_map.get("bond_topup_target"),
+                default_config.bond_topup_target,
+            ),
+            bond_topup_reserve: parse_field(
+                config_map.get("bond_topup_reserve"),
+                default_config.bond_topup_reserve,
+            ),
+            probing_floor: parse_field(
+                config_map.get("probing_floor"),
+                default_config.probing_floor,
+            ),
+            auto_consolidate: parse_field(
+                config_map.get("auto_consolidate"),
+                default_config.auto_consolidate,
+            ),
+            consolidation_min_utxos: parse_field(
+                config_map.get("consolidation_min_utxos"),
+                default_config.consolidation_min_utxos,
+            ),
+            consolidation_max_feerate: parse_field(
+                config_map.get("consolidation_max_feerate"),
+                default_config.consolidation_max_feerate,
+            ),
+            tcp_keepalive_interval: parse_field(
+                config_map.get("tcp_keepalive_interval"),
+                default_config.tcp_keepalive_interval,
+            ),
+            tcp_nodelay: parse_field(config_map.get("tcp_nodelay"), default_config.tcp_nodelay),
+            max_contract_fee: parse_field(
+                config_map.get("max_contract_fee"),
+                default_config.max_contract_fee,
+            ),
+            reserve_amount: parse_field(
+                config_map.get("reserve_amount"),
+                default_config.reserve_amount,
+            ),
+            required_confirms: parse_field(
+                config_map.get("required_confirms"),
+                default_config.required_confirms,
+            ),
+            bind_address: parse_field(config_map.get("bind_address"), default_config.bind_address),
+            min_fee_rate: parse_field(config_map.get("min_fee_rate"), default_config.min_fee_rate),
+            max_accepted_locktime: parse_field(
+                config_map.get("max_accepted_locktime"),
+                default_config.max_accepted_locktime,
+            ),
+            require_fidelity_bond: parse_field(
+                config_map.get("require_fidelity_bond"),
+                default_config.require_fidelity_bond,
+            ),
+            protocol_trace: parse_field(
+                config_map.get("protocol_trace"),
+                default_config.protocol_trace,
+            ),
+            proxy_protocol: parse_field(
+                config_map.get("proxy_protocol"),
+                default_config.proxy_protocol,
+            ),
+            max_connections_per_minute: parse_field(
+                config_map.get("max_connections_per_minute"),
+                default_config.max_connections_per_minute,
+            ),
+            max_contract_exposure: parse_field(
+                config_map.get("max_contract_exposure"),
+                default_config.max_contract_exposure,
+            ),
+            auto_select_ports: parse_field(
+                config_map.get("auto_select_ports"),
+                default_config.auto_select_ports,
+            ),
         })
     }
 
@@ -140,7 +373,41 @@ min_swap_amount = {}
 fidelity_amount = {}
 fidelity_timelock = {}
 connection_type = {:?}
-directory_server_address = {}
+tor_only = {}
+funding_rbf = {}
+wallet_autosave_interval = {}
+anti_fee_sniping = {}
+tx_ordering = {}
+address_strategy = {}
+allow_seed_export = {}
+allow_key_export = {}
+idle_connection_timeout = {}
+swap_timeout = {}
+avoid_swap_clustering = {}
+require_segwit_funding = {}
+max_tx_count = {}
+auto_bond_topup = {}
+bond_topup_target = {}
+bond_topup_reserve = {}
+probing_floor = {}
+auto_consolidate = {}
+consolidation_min_utxos = {}
+consolidation_max_feerate = {}
+tcp_keepalive_interval = {}
+tcp_nodelay = {}
+max_contract_fee = {}
+reserve_amount = {}
+required_confirms = {}
+bind_address = {}
+min_fee_rate = {}
+max_accepted_locktime = {}
+require_fidelity_bond = {}
+protocol_trace = {}
+proxy_protocol = {}
+max_connections_per_minute = {}
+max_contract_exposure = {}
+auto_select_ports = {}
+directory_server_addresses = {}
 ",
             self.network_port,
             self.rpc_port,
@@ -151,7 +418,41 @@ directory_server_address = {}
             self.fidelity_amount,
             self.fidelity_timelock,
             self.connection_type,
-            self.directory_server_address,
+            self.tor_only,
+            self.funding_rbf,
+            self.wallet_autosave_interval,
+            self.anti_fee_sniping,
+            self.tx_ordering,
+            self.address_strategy,
+            self.allow_seed_export,
+            self.allow_key_export,
+            self.idle_connection_timeout,
+            self.swap_timeout,
+            self.avoid_swap_clustering,
+            self.require_segwit_funding,
+            self.max_tx_count,
+            self.auto_bond_topup,
+            self.bond_topup_target,
+            self.bond_topup_reserve,
+            self.probing_floor,
+            self.auto_consolidate,
+            self.consolidation_min_utxos,
+            self.consolidation_max_feerate,
+            self.tcp_keepalive_interval,
+            self.tcp_nodelay,
+            self.max_contract_fee,
+            self.reserve_amount,
+            self.required_confirms,
+            self.bind_address,
+            self.min_fee_rate,
+            self.max_accepted_locktime,
+            self.require_fidelity_bond,
+            self.protocol_trace,
+            self.proxy_protocol,
+            self.max_connections_per_minute,
+            self.max_contract_exposure,
+            self.auto_select_ports,
+            self.directory_server_addresses.join(","),
         );
 
         std::fs::create_dir_all(path.parent().expect("Path should NOT be root!"))?;
@@ -233,6 +534,389 @@ mod tests {
         assert_eq!(config, MakerConfig::default());
     }
 
+    #[test]
+    fn test_tor_only_config() {
+        let contents = r#"
+            connection_type = TOR
+            tor_only = true
+        "#;
+        let config_path = create_temp_config(contents, "tor_only_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(config.tor_only);
+        assert_eq!(config.connection_type, ConnectionType::TOR);
+    }
+
+    #[test]
+    fn test_allow_seed_export_config() {
+        assert!(!MakerConfig::default().allow_seed_export);
+
+        let contents = r#"
+            allow_seed_export = true
+        "#;
+        let config_path = create_temp_config(contents, "seed_export_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(config.allow_seed_export);
+    }
+
+    #[test]
+    fn test_allow_key_export_config() {
+        assert!(!MakerConfig::default().allow_key_export);
+
+        let contents = r#"
+            allow_key_export = true
+        "#;
+        let config_path = create_temp_config(contents, "key_export_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(config.allow_key_export);
+    }
+
+    #[test]
+    fn test_directory_server_addresses_config() {
+        let contents = r#"
+            directory_server_addresses = abc.onion:8080,def.onion:8080
+        "#;
+        let config_path = create_temp_config(contents, "dns_list_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(
+            config.directory_server_addresses,
+            vec!["abc.onion:8080".to_string(), "def.onion:8080".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_avoid_swap_clustering_config() {
+        assert!(!MakerConfig::default().avoid_swap_clustering);
+
+        let contents = r#"
+            avoid_swap_clustering = true
+        "#;
+        let config_path = create_temp_config(contents, "swap_clustering_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(config.avoid_swap_clustering);
+    }
+
+    #[test]
+    fn test_require_segwit_funding_config() {
+        assert!(!MakerConfig::default().require_segwit_funding);
+
+        let contents = r#"
+            require_segwit_funding = true
+        "#;
+        let config_path = create_temp_config(contents, "segwit_funding_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(config.require_segwit_funding);
+    }
+
+    #[test]
+    fn test_auto_bond_topup_config() {
+        assert!(!MakerConfig::default().auto_bond_topup);
+
+        let contents = r#"
+            auto_bond_topup = true
+            bond_topup_target = 2000000
+            bond_topup_reserve = 300000
+        "#;
+        let config_path = create_temp_config(contents, "bond_topup_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(
+            MakerConfig {
+                auto_bond_topup: true,
+                bond_topup_target: 2_000_000,
+                bond_topup_reserve: 300_000,
+                ..MakerConfig::default()
+            },
+            config
+        );
+    }
+
+    #[test]
+    fn test_max_contract_fee_config() {
+        assert_eq!(MakerConfig::default().max_contract_fee, 10_000);
+
+        let contents = r#"
+            max_contract_fee = 0
+        "#;
+        let config_path = create_temp_config(contents, "max_contract_fee_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.max_contract_fee, 0);
+    }
+
+    #[test]
+    fn test_reserve_amount_config() {
+        assert_eq!(MakerConfig::default().reserve_amount, 0);
+
+        let contents = r#"
+            reserve_amount = 100000
+        "#;
+        let config_path = create_temp_config(contents, "reserve_amount_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.reserve_amount, 100_000);
+    }
+
+    #[test]
+    fn test_required_confirms_config() {
+        assert_eq!(MakerConfig::default().required_confirms, REQUIRED_CONFIRMS);
+
+        let contents = r#"
+            required_confirms = 3
+        "#;
+        let config_path = create_temp_config(contents, "required_confirms_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.required_confirms, 3);
+    }
+
+    #[test]
+    fn test_bind_address_config() {
+        assert_eq!(MakerConfig::default().bind_address, "127.0.0.1");
+
+        let contents = r#"
+            bind_address = 0.0.0.0
+        "#;
+        let config_path = create_temp_config(contents, "bind_address_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.bind_address, "0.0.0.0");
+    }
+
+    #[test]
+    fn test_min_fee_rate_config() {
+        assert_eq!(MakerConfig::default().min_fee_rate, MIN_FEE_RATE);
+
+        let contents = r#"
+            min_fee_rate = 5.5
+        "#;
+        let config_path = create_temp_config(contents, "min_fee_rate_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.min_fee_rate, 5.5);
+    }
+
+    #[test]
+    fn test_probing_floor_config() {
+        assert_eq!(MakerConfig::default().probing_floor, 0);
+
+        let contents = r#"
+            probing_floor = 50000
+        "#;
+        let config_path = create_temp_config(contents, "probing_floor_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.probing_floor, 50_000);
+    }
+
+    #[test]
+    fn test_max_tx_count_config() {
+        assert_eq!(MakerConfig::default().max_tx_count, 0);
+
+        let contents = r#"
+            max_tx_count = 4
+        "#;
+        let config_path = create_temp_config(contents, "max_tx_count_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.max_tx_count, 4);
+    }
+
+    #[test]
+    fn test_auto_consolidate_config() {
+        assert!(!MakerConfig::default().auto_consolidate);
+
+        let contents = r#"
+            auto_consolidate = true
+            consolidation_min_utxos = 5
+            consolidation_max_feerate = 3.5
+        "#;
+        let config_path = create_temp_config(contents, "consolidate_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(config.auto_consolidate);
+        assert_eq!(config.consolidation_min_utxos, 5);
+        assert_eq!(config.consolidation_max_feerate, 3.5);
+    }
+
+    #[test]
+    fn test_tcp_socket_config() {
+        let default_config = MakerConfig::default();
+        assert_eq!(
+            default_config.tcp_keepalive_interval,
+            TCP_KEEPALIVE_INTERVAL
+        );
+        assert!(default_config.tcp_nodelay);
+
+        let contents = r#"
+            tcp_keepalive_interval = 0
+            tcp_nodelay = false
+        "#;
+        let config_path = create_temp_config(contents, "tcp_socket_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.tcp_keepalive_interval, 0);
+        assert!(!config.tcp_nodelay);
+    }
+
+    #[test]
+    fn test_idle_connection_timeout_config() {
+        assert_eq!(
+            MakerConfig::default().idle_connection_timeout,
+            IDLE_CONNECTION_TIMEOUT.as_secs()
+        );
+
+        let contents = r#"
+            idle_connection_timeout = 8
+        "#;
+        let config_path = create_temp_config(contents, "idle_timeout_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.idle_connection_timeout, 8);
+    }
+
+    #[test]
+    fn test_swap_timeout_config() {
+        assert_eq!(MakerConfig::default().swap_timeout, SWAP_TIMEOUT.as_secs());
+
+        let contents = r#"
+            swap_timeout = 0
+        "#;
+        let config_path = create_temp_config(contents, "swap_timeout_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.swap_timeout, 0);
+    }
+
+    #[test]
+    fn test_max_accepted_locktime_config() {
+        assert_eq!(
+            MakerConfig::default().max_accepted_locktime,
+            MAX_ACCEPTED_LOCKTIME
+        );
+
+        let contents = r#"
+            max_accepted_locktime = 144
+        "#;
+        let config_path = create_temp_config(contents, "max_locktime_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.max_accepted_locktime, 144);
+    }
+
+    #[test]
+    fn test_require_fidelity_bond_config() {
+        assert!(MakerConfig::default().require_fidelity_bond);
+
+        let contents = r#"
+            require_fidelity_bond = false
+        "#;
+        let config_path = create_temp_config(contents, "bondless_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(!config.require_fidelity_bond);
+    }
+
+    #[test]
+    fn test_protocol_trace_config() {
+        assert!(!MakerConfig::default().protocol_trace);
+
+        let contents = r#"
+            protocol_trace = true
+        "#;
+        let config_path = create_temp_config(contents, "protocol_trace_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(config.protocol_trace);
+    }
+
+    #[test]
+    fn test_proxy_protocol_config() {
+        let default_config = MakerConfig::default();
+        assert!(!default_config.proxy_protocol);
+        assert_eq!(default_config.max_connections_per_minute, 0);
+
+        let contents = r#"
+            proxy_protocol = true
+            max_connections_per_minute = 30
+        "#;
+        let config_path = create_temp_config(contents, "proxy_protocol_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(config.proxy_protocol);
+        assert_eq!(config.max_connections_per_minute, 30);
+    }
+
+    #[test]
+    fn test_max_contract_exposure_config() {
+        assert_eq!(MakerConfig::default().max_contract_exposure, 0);
+
+        let contents = r#"
+            max_contract_exposure = 2000000
+        "#;
+        let config_path = create_temp_config(contents, "max_contract_exposure_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.max_contract_exposure, 2_000_000);
+    }
+
+    #[test]
+    fn test_auto_select_ports_config() {
+        assert!(!MakerConfig::default().auto_select_ports);
+
+        let contents = r#"
+            auto_select_ports = true
+        "#;
+        let config_path = create_temp_config(contents, "auto_select_ports_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert!(config.auto_select_ports);
+    }
+
+    #[test]
+    fn test_address_strategy_config() {
+        assert_eq!(
+            MakerConfig::default().address_strategy,
+            AddressStrategy::SplitChain
+        );
+
+        let contents = r#"
+            address_strategy = singlechain
+        "#;
+        let config_path = create_temp_config(contents, "address_strategy_maker_config.toml");
+        let config = MakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.address_strategy, AddressStrategy::SingleChain);
+    }
+
     #[test]
     fn test_missing_file() {
         let config_path = get_maker_dir().join("maker.toml");
@@ -42,6 +42,51 @@ pub enum MakerError {
     Protocol(ProtocolError),
     /// Tor Error
     TorError(TorError),
+    /// Represents a swap declined as a probing attempt, as its amount is below the maker's probing floor.
+    ProbingSwap {
+        /// The swap amount in sats.
+        amount: u64,
+        /// The configured probing floor in sats.
+        floor: u64,
+    },
+    /// Represents a swap declined as the refund locktime proposed by the Taker is above the maker's cap.
+    LocktimeTooHigh {
+        /// The proposed refund locktime in blocks.
+        locktime: u16,
+        /// The configured maximum accepted locktime in blocks.
+        max: u16,
+    },
+    /// Represents a swap declined for lack of capacity, as it would take the value locked in the
+    /// contracts of live swaps above the maker's cap.
+    CapacityExceeded {
+        /// The value in sats already locked in live contracts.
+        exposure: u64,
+        /// The swap amount in sats.
+        amount: u64,
+        /// The configured maximum contract exposure in sats.
+        max: u64,
+    },
+    /// Represents the bitcoin node still syncing the blockchain, in initial block download or behind
+    /// the best known headers. The maker can't serve swaps until the node is synced.
+    NodeNotSynced {
+        /// Number of blocks validated by the node.
+        blocks: u64,
+        /// Number of headers known by the node.
+        headers: u64,
+    },
+    /// Represents the bitcoin node serving another wallet than the maker's.
+    WrongWalletLoaded {
+        /// Name of the maker's wallet.
+        expected: String,
+        /// Name of the wallet the node serves.
+        loaded: String,
+    },
+    /// Represents a swap or RPC port already in use by another process, like a second maker
+    /// configured with the same port.
+    PortInUse {
+        /// The port in use.
+        port: u16,
+    },
 }
 
 impl From<TorError> for MakerError {
@@ -0,0 +1,184 @@
+//! The Maker's event log.
+//!
+//! Notable events, like swaps starting, completing or failing, are appended with their timestamp to
+//! `events.log` in the data directory, one JSON object per line. They can be queried over RPC with
+//! [RpcMsgReq::GetEvents](crate::maker::RpcMsgReq::GetEvents).
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::error::MakerError;
+
+/// Name of the event log file, in the Maker's data directory.
+pub(crate) const EVENT_LOG_FILE: &str = "events.log";
+
+/// An entry of the event log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MakerEvent {
+    /// Unix timestamp in seconds of the event.
+    pub timestamp: u64,
+    /// What happened.
+    #[serde(flatten)]
+    pub kind: MakerEventKind,
+}
+
+/// The kinds of events recorded by the Maker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum MakerEventKind {
+    /// A swap was started, at the Taker's first proof of funding.
+    SwapStarted {
+        /// The swap id, chosen by the Taker.
+        id: String,
+    },
+    /// A swap was completed, at the private key handover.
+    SwapCompleted {
+        /// The swap id, chosen by the Taker.
+        id: String,
+    },
+    /// A swap failed or was declined.
+    SwapFailed {
+        /// The swap id, if the swap got as far as the proof of funding.
+        id: Option<String>,
+        /// Why the swap failed.
+        reason: String,
+    },
+    /// A new fidelity bond was created, and is advertised from now on.
+    BondRotated {
+        /// Index of the new bond in the wallet.
+        index: u32,
+    },
+}
+
+/// Append-only log of [MakerEvent]s, stored as JSON lines.
+#[derive(Debug)]
+pub(crate) struct EventLog {
+    path: PathBuf,
+    // Serializes the appends, so concurrent swap threads never interleave their lines.
+    lock: Mutex<()>,
+}
+
+impl EventLog {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Appends an event, timestamped now, to the log.
+    pub(crate) fn record(&self, kind: MakerEventKind) -> Result<(), MakerError> {
+        let event = MakerEvent {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system time is before the unix epoch")
+                .as_secs(),
+            kind,
+        };
+        let line = serde_json::to_string(&event).map_err(std::io::Error::from)?;
+
+        let _guard = self.lock.lock()?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    /// Reads up to `limit` events, in the order they were recorded, whose timestamp is at or after `since`.
+    ///
+    /// Unreadable lines, e.g. one cut off by a crash, are skipped.
+    pub(crate) fn read(&self, since: u64, limit: usize) -> Result<Vec<MakerEvent>, MakerError> {
+        let _guard = self.lock.lock()?;
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut events = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if events.len() >= limit {
+                break;
+            }
+            let line = line?;
+            match serde_json::from_str::<MakerEvent>(&line) {
+                Ok(event) if event.timestamp >= since => events.push(event),
+                Ok(_) => {}
+                Err(e) => log::warn!("Skipping unreadable event log line {:?}: {}", line, e),
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_log_since_and_limit() {
+        let dir = std::env::temp_dir().join("coinswap_event_log_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(EVENT_LOG_FILE);
+        let _ = std::fs::remove_file(&path);
+        let log = EventLog::new(path.clone());
+
+        // No file yet.
+        assert!(log.read(0, 10).unwrap().is_empty());
+
+        let started = MakerEventKind::SwapStarted {
+            id: "swap".to_string(),
+        };
+        let completed = MakerEventKind::SwapCompleted {
+            id: "swap".to_string(),
+        };
+        log.record(started.clone()).unwrap();
+        log.record(completed.clone()).unwrap();
+        // A line cut off by a crash.
+        writeln!(
+            OpenOptions::new().append(true).open(&path).unwrap(),
+            "{{\"timestamp\":"
+        )
+        .unwrap();
+        log.record(MakerEventKind::BondRotated { index: 1 })
+            .unwrap();
+
+        let events = log.read(0, 10).unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.kind.clone()).collect::<Vec<_>>(),
+            vec![
+                started.clone(),
+                completed,
+                MakerEventKind::BondRotated { index: 1 }
+            ]
+        );
+        assert_eq!(
+            log.read(0, 1)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.kind)
+                .collect::<Vec<_>>(),
+            vec![started]
+        );
+        assert!(log.read(events[2].timestamp + 1, 10).unwrap().is_empty());
+
+        // Stored as one JSON object per line.
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents
+            .lines()
+            .next()
+            .unwrap()
+            .contains("\"event\":\"swap_started\""));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -7,39 +7,47 @@
 //! The file includes functions to validate and sign contract transactions, verify proof of funding, and handle unexpected recovery scenarios.
 //! Implements the core functionality for a Maker in a Bitcoin coinswap protocol.
 
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
 use bitcoin::{
-    hashes::Hash,
     secp256k1::{self, Secp256k1},
-    Amount, OutPoint, PublicKey, Transaction, Txid,
+    sighash::EcdsaSighashType,
+    Amount, OutPoint, PublicKey, ScriptBuf, Transaction, Txid,
 };
 
 use super::{
     api::{
-        recover_from_swap, ConnectionState, ExpectedMessage, Maker, MakerBehavior,
-        AMOUNT_RELATIVE_FEE_PCT, BASE_FEE, MIN_CONTRACT_REACTION_TIME, TIME_RELATIVE_FEE_PCT,
+        agreed_fee_escalation, recover_from_swap, ConnectionState, ExpectedMessage, Maker,
+        MakerBehavior, MIN_CONTRACT_REACTION_TIME,
     },
     error::MakerError,
+    events::MakerEventKind,
 };
 
 use crate::{
     protocol::{
         contract::{
-            calculate_coinswap_fee, create_receivers_contract_tx, find_funding_output_index,
-            read_hashvalue_from_contract, read_pubkeys_from_multisig_redeemscript,
+            calculate_coinswap_fee, check_fee_escalation_schedule, create_receivers_contract_tx,
+            create_senders_contract_tx, find_funding_output_index, read_hashvalue_from_contract,
+            read_pubkeys_from_multisig_redeemscript,
         },
         error::ProtocolError,
         messages::{
             ContractSigsAsRecvrAndSender, ContractSigsForRecvr, ContractSigsForRecvrAndSender,
-            ContractSigsForSender, HashPreimage, MakerHello, MakerToTakerMessage, MultisigPrivkey,
-            Offer, PrivKeyHandover, ProofOfFunding, ReqContractSigsForRecvr,
-            ReqContractSigsForSender, SenderContractTxInfo, TakerToMakerMessage,
+            ContractSigsForSender, HashPreimage, IdentityChallenge, IdentityProof, MakerHello,
+            MakerToTakerMessage, MultisigPrivkey, Offer, PrivKeyHandover, ProofOfFunding,
+            ReqContractSigsForRecvr, ReqContractSigsForSender, SenderContractTxInfo,
+            TakerToMakerMessage,
         },
-        Hash160,
+        HashlockVariant, Hashvalue,
     },
-    utill::{DEFAULT_TX_FEE_RATE, REQUIRED_CONFIRMS},
-    wallet::{IncomingSwapCoin, SwapCoin, WalletError, WalletSwapCoin},
+    taker::api::REFUND_LOCKTIME,
+    utill::DEFAULT_TX_FEE_RATE,
+    wallet::{funding_input_sequence, IncomingSwapCoin, SwapCoin, WalletError, WalletSwapCoin},
 };
 
 /// The Global Handle Message function. Takes in a [`Arc<Maker>`] and handle messages
@@ -63,6 +71,21 @@ pub(crate) fn handle_message(
         return Ok(None);
     }
 
+    // Keep the swap alive on taker heartbeats, and acknowledge them.
+    if let TakerToMakerMessage::Heartbeat(id) = message {
+        let mut ongoing_swaps = maker.ongoing_swap_state.lock()?;
+        let (_, timer) = ongoing_swaps.get_mut(&id).ok_or(MakerError::General(
+            "Heartbeat received for an unknown swap",
+        ))?;
+        *timer = Instant::now();
+        log::info!(
+            "[{}] Heartbeat received for swap {}. Reseting timer.",
+            maker.config.network_port,
+            id
+        );
+        return Ok(Some(MakerToTakerMessage::RespHeartbeat(id)));
+    }
+
     let outgoing_message = match connection_state.allowed_message {
         ExpectedMessage::TakerHello => {
             if let TakerToMakerMessage::TakerHello(m) = message {
@@ -77,9 +100,11 @@ pub(crate) fn handle_message(
                     .into());
                 }
                 connection_state.allowed_message = ExpectedMessage::NewlyConnectedTaker;
+                connection_state.compression = m.compression;
                 let reply = MakerToTakerMessage::MakerHello(MakerHello {
                     protocol_version_min: 1,
                     protocol_version_max: 1,
+                    compression: true,
                 });
                 Some(reply)
             } else {
@@ -91,30 +116,38 @@ pub(crate) fn handle_message(
         }
         ExpectedMessage::NewlyConnectedTaker => match message {
             TakerToMakerMessage::ReqGiveOffer(_) => {
-                let (tweakable_point, max_size) = {
-                    let wallet_reader = maker.wallet.read()?;
-                    let max_size = wallet_reader.store.offer_maxsize;
-                    let tweakable_point = wallet_reader.get_tweakable_keypair()?.1;
-                    (tweakable_point, max_size)
-                };
+                let tweakable_point = maker.wallet.read()?.get_tweakable_keypair()?.1;
+                let terms = maker.get_offer_terms()?;
                 connection_state.allowed_message = ExpectedMessage::ReqContractSigsForSender;
-                let fidelity = maker.highest_fidelity_proof.read()?;
-                let fidelity = fidelity.as_ref().expect("proof expected");
+                let fidelity = if maker.config.require_fidelity_bond {
+                    let fidelity = maker.highest_fidelity_proof.read()?;
+                    Some(fidelity.clone().ok_or(MakerError::General(
+                        "No valid fidelity bond, refusing to serve offers",
+                    ))?)
+                } else {
+                    None
+                };
                 Some(MakerToTakerMessage::RespOffer(Box::new(Offer {
-                    base_fee: BASE_FEE,
-                    amount_relative_fee_pct: AMOUNT_RELATIVE_FEE_PCT,
-                    time_relative_fee_pct: TIME_RELATIVE_FEE_PCT,
-                    required_confirms: REQUIRED_CONFIRMS,
+                    base_fee: terms.fee.base_fee,
+                    amount_relative_fee_pct: terms.fee.amount_relative_fee_pct,
+                    time_relative_fee_pct: terms.fee.time_relative_fee_pct,
+                    required_confirms: maker.config.required_confirms,
                     minimum_locktime: MIN_CONTRACT_REACTION_TIME,
-                    max_size,
-                    min_size: maker.config.min_swap_amount,
+                    max_size: terms.max_size,
+                    min_size: terms.min_size,
                     tweakable_point,
-                    fidelity: fidelity.clone(),
+                    fidelity,
+                    segwit_only: maker.config.require_segwit_funding,
+                    hashlock_variants: vec![HashlockVariant::Hash160, HashlockVariant::Sha256],
+                    max_tx_count: maker.config.max_tx_count,
                 })))
             }
+            TakerToMakerMessage::ReqIdentityProof(challenge) => {
+                Some(maker.handle_req_identity_proof(challenge)?)
+            }
             TakerToMakerMessage::ReqContractSigsForSender(message) => {
                 connection_state.allowed_message = ExpectedMessage::ProofOfFunding;
-                Some(maker.handle_req_contract_sigs_for_sender(message)?)
+                Some(maker.handle_req_contract_sigs_for_sender(connection_state, message)?)
             }
             TakerToMakerMessage::RespProofOfFunding(proof) => {
                 connection_state.allowed_message =
@@ -139,7 +172,7 @@ pub(crate) fn handle_message(
         ExpectedMessage::ReqContractSigsForSender => {
             if let TakerToMakerMessage::ReqContractSigsForSender(message) = message {
                 connection_state.allowed_message = ExpectedMessage::ProofOfFunding;
-                Some(maker.handle_req_contract_sigs_for_sender(message)?)
+                Some(maker.handle_req_contract_sigs_for_sender(connection_state, message)?)
             } else {
                 return Err(MakerError::UnexpectedMessage {
                     expected: "ReqContractSigsForSender".to_string(),
@@ -217,20 +250,59 @@ pub(crate) fn handle_message(
 }
 
 impl Maker {
+    /// Handles an [IdentityChallenge] and returns a [MakerToTakerMessage::RespIdentityProof], signed by
+    /// the key of the challenged fidelity bond. Fails if this Maker doesn't hold the bond.
+    pub(crate) fn handle_req_identity_proof(
+        &self,
+        challenge: IdentityChallenge,
+    ) -> Result<MakerToTakerMessage, MakerError> {
+        let sig = self
+            .wallet
+            .read()?
+            .sign_identity_challenge(&challenge.bond_outpoint, &challenge.nonce)?;
+        Ok(MakerToTakerMessage::RespIdentityProof(IdentityProof {
+            sig,
+        }))
+    }
+
     /// This is the first message handler for the Maker. It receives a [ReqContractSigsForSender] message,
     /// checks the validity of contract transactions, and provide's the signature for the sender side.
     /// This will fail if the maker doesn't have enough utxos to fund the next coinswap hop, or the contract
     /// transaction isn't valid.
     pub(crate) fn handle_req_contract_sigs_for_sender(
         &self,
+        connection_state: &mut ConnectionState,
         message: ReqContractSigsForSender,
     ) -> Result<MakerToTakerMessage, MakerError> {
         if let MakerBehavior::CloseAtReqContractSigsForSender = self.behavior {
             return Err(self.behavior.into());
         }
+        if let MakerBehavior::SlowAtReqContractSigsForSender = self.behavior {
+            thread::sleep(Duration::from_secs(20));
+        }
+
+        let max_tx_count = self.config.max_tx_count as usize;
+        if max_tx_count != 0 && message.txs_info.len() > max_tx_count {
+            log::error!(
+                "Too many funding txs, got {}, max {}",
+                message.txs_info.len(),
+                max_tx_count
+            );
+            return Err(MakerError::General("too many funding txs"));
+        }
+
+        let total_funding_amount = message.txs_info.iter().fold(0u64, |acc, txinfo| {
+            acc + txinfo.funding_input_value.to_sat()
+        });
+        check_probing_floor(total_funding_amount, self.config.probing_floor)?;
 
         // Verify and sign the contract transaction, check function definition for all the checks.
-        let sigs = self.verify_and_sign_contract_tx(&message)?;
+        let (mut sigs, escalated_sigs) = self.verify_and_sign_contract_tx(&message)?;
+        if let MakerBehavior::WrongSighashFlag = self.behavior {
+            for sig in sigs.iter_mut() {
+                sig.sighash_type = EcdsaSighashType::None;
+            }
+        }
 
         let funding_txids = message
             .txs_info
@@ -238,10 +310,6 @@ impl Maker {
             .map(|txinfo| txinfo.senders_contract_tx.input[0].previous_output.txid)
             .collect::<Vec<_>>();
 
-        let total_funding_amount = message.txs_info.iter().fold(0u64, |acc, txinfo| {
-            acc + txinfo.funding_input_value.to_sat()
-        });
-
         log::info!(
             "[{}] Total Funding Amount = {} | Funding Txids = {:?}",
             self.config.network_port,
@@ -249,21 +317,60 @@ impl Maker {
             funding_txids
         );
 
-        let max_size = self.wallet.read()?.store.offer_maxsize;
-        if total_funding_amount >= self.config.min_swap_amount && total_funding_amount <= max_size {
+        let terms = self.get_offer_terms()?;
+        if total_funding_amount >= terms.min_size && total_funding_amount <= terms.max_size {
+            if let Some(first_txinfo) = message.txs_info.first() {
+                self.reserve_contract_exposure(
+                    connection_state,
+                    &first_txinfo.multisig_redeemscript,
+                    Amount::from_sat(total_funding_amount),
+                )?;
+            }
             Ok(MakerToTakerMessage::RespContractSigsForSender(
-                ContractSigsForSender { sigs },
+                ContractSigsForSender {
+                    sigs,
+                    escalated_sigs,
+                },
             ))
         } else {
             log::error!(
                 "Funding amount not within min/max limit, min {}, max {}",
-                self.config.min_swap_amount,
-                max_size
+                terms.min_size,
+                terms.max_size
             );
             Err(MakerError::General("not enough funds"))
         }
     }
 
+    /// Reserves the `amount` of a swap against `max_contract_exposure`, declining it for capacity if the
+    /// value locked in live contracts would go above it.
+    ///
+    /// Until the swap is funded, the reservation is held by a provisional ongoing swap, keyed by its first
+    /// incoming multisig. The proof of funding hands it over to the swap, and it's released once the swap
+    /// settles or is recovered. A swap never funded is aborted, and released after `idle_connection_timeout`.
+    fn reserve_contract_exposure(
+        &self,
+        connection_state: &mut ConnectionState,
+        multisig_redeemscript: &ScriptBuf,
+        amount: Amount,
+    ) -> Result<(), MakerError> {
+        if self.config.max_contract_exposure == 0 {
+            return Ok(());
+        }
+        let mut ongoing_swaps = self.ongoing_swap_state.lock()?;
+        // A retried request replaces its own reservation.
+        let reservation_id = exposure_reservation_id(multisig_redeemscript);
+        ongoing_swaps.remove(&reservation_id);
+        check_contract_exposure(
+            self.contract_exposure_of(&ongoing_swaps)?.to_sat(),
+            amount.to_sat(),
+            self.config.max_contract_exposure,
+        )?;
+        connection_state.reserved_exposure = amount;
+        ongoing_swaps.insert(reservation_id, (connection_state.clone(), Instant::now()));
+        Ok(())
+    }
+
     /// Validates the [ProofOfFunding] message, initiate the next hop,
     /// and create the `[ReqContractSigsAsRecvrAndSender`\] message.
     pub(crate) fn handle_proof_of_funding(
@@ -278,6 +385,28 @@ impl Maker {
         // Basic verification of ProofOfFunding Message.
         // Check function definition for all the checks performed.
         let hashvalue = self.verify_proof_of_funding(&message)?;
+        check_refund_locktime(message.refund_locktime, self.config.max_accepted_locktime)?;
+        let hashvalue = if let MakerBehavior::WrongHashlock = self.behavior {
+            Hashvalue::from_preimage(hashvalue.variant(), b"not the taker's hashlock")
+        } else {
+            hashvalue
+        };
+
+        // Calculate output amounts for the next hop
+        let incoming_amount = message
+            .confirmed_funding_txes
+            .iter()
+            .try_fold(0u64, |acc, fi| {
+                let index = find_funding_output_index(fi)?;
+                let txout = fi
+                    .funding_tx
+                    .output
+                    .get(index as usize)
+                    .expect("output at index expected");
+                Ok::<_, MakerError>(acc + txout.value.to_sat())
+            })?;
+        check_probing_floor(incoming_amount, self.config.probing_floor)?;
+
         log::info!(
             "[{}] Validated Proof of Funding of receiving swap. Adding Incoming Swaps.",
             self.config.network_port
@@ -298,6 +427,11 @@ impl Maker {
 
             self.wallet.write()?.sync()?;
 
+            let contract_fee = if let MakerBehavior::WrongContractFee = self.behavior {
+                message.contract_feerate + 1000
+            } else {
+                message.contract_feerate
+            };
             let receiver_contract_tx = create_receivers_contract_tx(
                 OutPoint {
                     txid: funding_info.funding_tx.compute_txid(),
@@ -305,7 +439,7 @@ impl Maker {
                 },
                 funding_output.value,
                 &funding_info.contract_redeemscript,
-                Amount::from_sat(message.contract_feerate),
+                Amount::from_sat(contract_fee),
             )?;
 
             let (tweakable_privkey, _) = self.wallet.read()?.get_tweakable_keypair()?;
@@ -344,26 +478,13 @@ impl Maker {
             }
         }
 
-        // Calculate output amounts for the next hop
-        let incoming_amount = message
-            .confirmed_funding_txes
-            .iter()
-            .try_fold(0u64, |acc, fi| {
-                let index = find_funding_output_index(fi)?;
-                let txout = fi
-                    .funding_tx
-                    .output
-                    .get(index as usize)
-                    .expect("output at index expected");
-                Ok::<_, MakerError>(acc + txout.value.to_sat())
-            })?;
-
+        let fee = self.offer_terms.read()?.fee;
         let calc_coinswap_fees = calculate_coinswap_fee(
             incoming_amount,
             message.refund_locktime,
-            BASE_FEE,
-            AMOUNT_RELATIVE_FEE_PCT,
-            TIME_RELATIVE_FEE_PCT,
+            fee.base_fee,
+            fee.amount_relative_fee_pct,
+            fee.time_relative_fee_pct,
         );
 
         // NOTE: The `contract_feerate` currently represents the hardcoded `MINER_FEE` of a transaction, not the fee rate.
@@ -386,10 +507,19 @@ impl Maker {
             ));
         };
 
+        // Only the last hop pays the Taker, and has the shortest refund locktime.
+        let shortchange = if matches!(self.behavior, MakerBehavior::ShortchangeLastHop)
+            && message.refund_locktime == REFUND_LOCKTIME
+        {
+            10_000
+        } else {
+            0
+        };
+
         // Create outgoing coinswap of the next hop
-        let (my_funding_txes, outgoing_swapcoins, act_funding_txs_fees) = {
+        let (my_funding_txes, mut outgoing_swapcoins, act_funding_txs_fees) = {
             self.wallet.write()?.initalize_coinswap(
-                Amount::from_sat(outgoing_amount),
+                Amount::from_sat(outgoing_amount - shortchange),
                 &message
                     .next_coinswap_info
                     .iter()
@@ -403,9 +533,23 @@ impl Maker {
                 hashvalue,
                 message.refund_locktime,
                 Amount::from_sat(message.contract_feerate),
+                funding_input_sequence(self.config.funding_rbf),
+                false,
             )?
         };
 
+        if shortchange > 0 {
+            // Claim the shortchanged amount back in the first contract, so it matches the agreed amount.
+            let outgoing_swapcoin = &mut outgoing_swapcoins[0];
+            outgoing_swapcoin.funding_amount += Amount::from_sat(shortchange);
+            outgoing_swapcoin.contract_tx = create_senders_contract_tx(
+                outgoing_swapcoin.contract_tx.input[0].previous_output,
+                outgoing_swapcoin.funding_amount,
+                &outgoing_swapcoin.contract_redeemscript,
+                Amount::from_sat(message.contract_feerate),
+            )?;
+        }
+
         let act_coinswap_fees = incoming_amount
             .checked_sub(outgoing_amount + act_funding_txs_fees.to_sat())
             .expect("This should not overflow as we just above.");
@@ -466,11 +610,31 @@ impl Maker {
             })
             .collect::<Result<Vec<SenderContractTxInfo>, WalletError>>()?;
 
-        // Update the connection state.
-        self.ongoing_swap_state.lock()?.insert(
-            message.id.clone(),
-            (connection_state.clone(), Instant::now()),
-        );
+        // Update the connection state. The swap timeout runs from the first proof of funding.
+        connection_state.swap_id = Some(message.id.clone());
+        let mut ongoing_swaps = self.ongoing_swap_state.lock()?;
+        // Take over the contract exposure reserved at the contract signatures request.
+        if let Some((reservation, _)) = message.confirmed_funding_txes.first().and_then(|fi| {
+            ongoing_swaps.remove(&exposure_reservation_id(&fi.multisig_redeemscript))
+        }) {
+            connection_state.reserved_exposure = reservation.reserved_exposure;
+        }
+        let started_at = ongoing_swaps
+            .get(&message.id)
+            .and_then(|(state, _)| state.started_at);
+        connection_state.started_at = Some(started_at.unwrap_or_else(Instant::now));
+        let started = ongoing_swaps
+            .insert(
+                message.id.clone(),
+                (connection_state.clone(), Instant::now()),
+            )
+            .is_none();
+        drop(ongoing_swaps);
+        if started {
+            self.record_event(MakerEventKind::SwapStarted {
+                id: message.id.clone(),
+            });
+        }
 
         log::info!("Connection state initiatilzed for swap id: {}", message.id);
 
@@ -506,6 +670,22 @@ impl Maker {
             incoming_swapcoin.others_contract_sig = Some(*receivers_sig);
         }
 
+        // Older takers don't exchange fee-escalated variants.
+        if !message.receivers_escalated_sigs.is_empty() {
+            if message.receivers_escalated_sigs.len() != connection_state.incoming_swapcoins.len() {
+                return Err(MakerError::General(
+                    "invalid number of reciever's escalated signatures",
+                ));
+            }
+            for (sigs, incoming_swapcoin) in message
+                .receivers_escalated_sigs
+                .iter()
+                .zip(connection_state.incoming_swapcoins.iter_mut())
+            {
+                incoming_swapcoin.apply_escalated_contract_sigs(&message.fee_escalation, sigs)?;
+            }
+        }
+
         if message.senders_sigs.len() != connection_state.outgoing_swapcoins.len() {
             return Err(MakerError::General("invalid number of sender's signatures"));
         }
@@ -520,6 +700,21 @@ impl Maker {
             outgoing_swapcoin.others_contract_sig = Some(*senders_sig);
         }
 
+        if !message.senders_escalated_sigs.is_empty() {
+            if message.senders_escalated_sigs.len() != connection_state.outgoing_swapcoins.len() {
+                return Err(MakerError::General(
+                    "invalid number of sender's escalated signatures",
+                ));
+            }
+            for (sigs, outgoing_swapcoin) in message
+                .senders_escalated_sigs
+                .iter()
+                .zip(connection_state.outgoing_swapcoins.iter_mut())
+            {
+                outgoing_swapcoin.apply_escalated_contract_sigs(&message.fee_escalation, sigs)?;
+            }
+        }
+
         {
             let mut wallet_writer = self.wallet.write()?;
             for (incoming_sc, outgoing_sc) in connection_state
@@ -566,21 +761,38 @@ impl Maker {
             return Err(self.behavior.into());
         }
 
-        let sigs = message
+        let fee_escalation =
+            agreed_fee_escalation(&message.fee_escalation, self.config.max_contract_fee);
+        let (sigs, escalated_sigs) = message
             .txs
             .iter()
             .map(|txinfo| {
-                Ok(self
-                    .wallet
-                    .read()?
+                let wallet = self.wallet.read()?;
+                let outgoing_swapcoin = wallet
                     .find_outgoing_swapcoin(&txinfo.multisig_redeemscript)
-                    .expect("Outgoing Swapcoin expected")
-                    .sign_contract_tx_with_my_privkey(&txinfo.contract_tx)?)
+                    .expect("Outgoing Swapcoin expected");
+                check_fee_escalation_schedule(
+                    &txinfo.contract_tx,
+                    outgoing_swapcoin.funding_amount,
+                    &message.fee_escalation,
+                )?;
+                Ok((
+                    outgoing_swapcoin.sign_contract_tx_with_my_privkey(&txinfo.contract_tx)?,
+                    outgoing_swapcoin.sign_escalated_contract_txs_with_my_privkey(
+                        &txinfo.contract_tx,
+                        &fee_escalation,
+                    )?,
+                ))
             })
-            .collect::<Result<Vec<_>, MakerError>>()?;
+            .collect::<Result<Vec<_>, MakerError>>()?
+            .into_iter()
+            .unzip();
 
         Ok(MakerToTakerMessage::RespContractSigsForRecvr(
-            ContractSigsForRecvr { sigs },
+            ContractSigsForRecvr {
+                sigs,
+                escalated_sigs,
+            },
         ))
     }
 
@@ -593,24 +805,29 @@ impl Maker {
             return Err(self.behavior.into());
         }
 
-        let hashvalue = Hash160::hash(&message.preimage);
+        // The contract commits to the hash function of the negotiated hashlock variant.
+        let check_preimage = |contract_redeemscript: &ScriptBuf| {
+            let hashvalue = read_hashvalue_from_contract(contract_redeemscript)?;
+            if Hashvalue::from_preimage(hashvalue.variant(), &message.preimage) != hashvalue {
+                return Err(MakerError::General("not correct hash preimage"));
+            }
+            Ok(hashvalue)
+        };
+
         for multisig_redeemscript in &message.senders_multisig_redeemscripts {
             let mut wallet_write = self.wallet.write()?;
             let incoming_swapcoin = wallet_write
                 .find_incoming_swapcoin_mut(multisig_redeemscript)
                 .expect("Incoming swampcoin expected");
-            if read_hashvalue_from_contract(&incoming_swapcoin.contract_redeemscript)? != hashvalue
-            {
-                return Err(MakerError::General("not correct hash preimage"));
-            }
+            let hashvalue = check_preimage(&incoming_swapcoin.contract_redeemscript)?;
             incoming_swapcoin.hash_preimage = Some(message.preimage);
+            log::info!(
+                "[{}] received preimage for hashvalue={}",
+                self.config.network_port,
+                hashvalue
+            );
         }
 
-        log::info!(
-            "[{}] received preimage for hashvalue={}",
-            self.config.network_port,
-            hashvalue
-        );
         let mut swapcoin_private_keys = Vec::<MultisigPrivkey>::new();
 
         // Send our privkey and mark the outgoing swapcoin as "done".
@@ -619,12 +836,8 @@ impl Maker {
             let outgoing_swapcoin = wallet_write
                 .find_outgoing_swapcoin_mut(multisig_redeemscript)
                 .expect("outgoing swapcoin expected");
-            if read_hashvalue_from_contract(&outgoing_swapcoin.contract_redeemscript)? != hashvalue
-            {
-                return Err(MakerError::General("not correct hash preimage"));
-            } else {
-                outgoing_swapcoin.hash_preimage.replace(message.preimage);
-            }
+            check_preimage(&outgoing_swapcoin.contract_redeemscript)?;
+            outgoing_swapcoin.hash_preimage.replace(message.preimage);
 
             swapcoin_private_keys.push(MultisigPrivkey {
                 multisig_redeemscript: multisig_redeemscript.clone(),
@@ -633,6 +846,11 @@ impl Maker {
         }
 
         self.wallet.write()?.save_to_disk()?;
+
+        if let MakerBehavior::DropAtSettlement = self.behavior {
+            return Err(self.behavior.into());
+        }
+
         Ok(MakerToTakerMessage::RespPrivKeyHandover(PrivKeyHandover {
             multisig_privkeys: swapcoin_private_keys,
         }))
@@ -655,7 +873,23 @@ impl Maker {
 
         // Reset the connection state so watchtowers are not triggered.
         let mut conn_state = self.ongoing_swap_state.lock()?;
-        *conn_state = HashMap::default();
+        let completed = conn_state
+            .iter()
+            .filter(|(_, (state, _))| {
+                state.incoming_swapcoins.iter().any(|swapcoin| {
+                    message.multisig_privkeys.iter().any(|privkey| {
+                        privkey.multisig_redeemscript == swapcoin.get_multisig_redeemscript()
+                    })
+                })
+            })
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        // The reservations of swaps not funded yet are kept.
+        conn_state.retain(|_, (state, _)| state.started_at.is_none());
+        drop(conn_state);
+        for id in completed {
+            self.record_event(MakerEventKind::SwapCompleted { id });
+        }
 
         log::info!("initializing Wallet Sync.");
         {
@@ -698,8 +932,8 @@ fn unexpected_recovery(maker: Arc<Maker>) -> Result<(), MakerError> {
                     continue;
                 }
             };
-            let next_internal_address = &maker.wallet.read()?.get_next_internal_addresses(1)?[0];
-            let time_lock_spend = maker.wallet.read()?.create_timelock_spend(
+            let next_internal_address = &maker.wallet.write()?.get_next_internal_addresses(1)?[0];
+            let time_lock_spend = maker.wallet.write()?.create_timelock_spend(
                 og_sc,
                 next_internal_address,
                 DEFAULT_TX_FEE_RATE,
@@ -724,3 +958,108 @@ fn unexpected_recovery(maker: Arc<Maker>) -> Result<(), MakerError> {
     }
     Ok(())
 }
+
+/// Declines swaps below the probing floor. Dust-sized swaps are used to probe and map makers,
+/// without ever risking real funds. A `floor` of `0` accepts everything.
+fn check_probing_floor(amount: u64, floor: u64) -> Result<(), MakerError> {
+    if amount < floor {
+        log::warn!(
+            "Declining swap of {} sats as probing, below the floor of {} sats",
+            amount,
+            floor
+        );
+        return Err(MakerError::ProbingSwap { amount, floor });
+    }
+    Ok(())
+}
+
+/// Id of the provisional ongoing swap holding the contract exposure reservation of a swap not funded yet,
+/// with `multisig_redeemscript` as first incoming multisig.
+fn exposure_reservation_id(multisig_redeemscript: &ScriptBuf) -> String {
+    format!("reserved-{}", multisig_redeemscript.to_hex_string())
+}
+
+/// Declines swaps that would take the value locked in live contracts from `exposure` to above `max`.
+/// A `max` of `0` accepts everything.
+fn check_contract_exposure(exposure: u64, amount: u64, max: u64) -> Result<(), MakerError> {
+    if max != 0 && exposure.saturating_add(amount) > max {
+        log::warn!(
+            "Declining swap of {} sats for capacity, with {} sats already in live contracts, above the maximum of {} sats",
+            amount,
+            exposure,
+            max
+        );
+        return Err(MakerError::CapacityExceeded {
+            exposure,
+            amount,
+            max,
+        });
+    }
+    Ok(())
+}
+
+/// Declines swaps whose outgoing contracts would lock the maker's funds for longer than `max` blocks,
+/// before they can be refunded.
+fn check_refund_locktime(locktime: u16, max: u16) -> Result<(), MakerError> {
+    if locktime > max {
+        log::warn!(
+            "Declining swap with a refund locktime of {} blocks, above the maximum of {} blocks",
+            locktime,
+            max
+        );
+        return Err(MakerError::LocktimeTooHigh { locktime, max });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maker::api::MAX_ACCEPTED_LOCKTIME;
+
+    #[test]
+    fn test_check_probing_floor() {
+        // Disabled by default.
+        assert!(check_probing_floor(1, 0).is_ok());
+
+        assert!(check_probing_floor(50_000, 50_000).is_ok());
+        assert!(check_probing_floor(1_000_000, 50_000).is_ok());
+        assert!(matches!(
+            check_probing_floor(10_000, 50_000),
+            Err(MakerError::ProbingSwap {
+                amount: 10_000,
+                floor: 50_000
+            })
+        ));
+    }
+
+    #[test]
+    fn test_check_refund_locktime() {
+        assert!(check_refund_locktime(20, MAX_ACCEPTED_LOCKTIME).is_ok());
+        assert!(check_refund_locktime(144, 144).is_ok());
+        assert!(matches!(
+            check_refund_locktime(u16::MAX, 144),
+            Err(MakerError::LocktimeTooHigh {
+                locktime: u16::MAX,
+                max: 144
+            })
+        ));
+    }
+
+    #[test]
+    fn test_check_contract_exposure() {
+        // Disabled by default.
+        assert!(check_contract_exposure(u64::MAX, 1_000_000, 0).is_ok());
+
+        assert!(check_contract_exposure(0, 1_000_000, 1_000_000).is_ok());
+        assert!(check_contract_exposure(600_000, 400_000, 1_000_000).is_ok());
+        assert!(matches!(
+            check_contract_exposure(600_000, 500_000, 1_000_000),
+            Err(MakerError::CapacityExceeded {
+                exposure: 600_000,
+                amount: 500_000,
+                max: 1_000_000
+            })
+        ));
+    }
+}
@@ -13,11 +13,17 @@
 mod api;
 mod config;
 mod error;
+mod events;
 mod handlers;
+mod proxy_protocol;
 mod rpc;
 mod server;
 
-pub use api::{Maker, MakerBehavior};
+pub use api::{
+    autosave_wallet, consolidate_utxos, top_up_fidelity_bond, Maker, MakerBehavior, OfferFees,
+    OfferTerms, MAX_ACCEPTED_LOCKTIME,
+};
 pub use error::MakerError;
+pub use events::{MakerEvent, MakerEventKind};
 pub use rpc::{RpcMsgReq, RpcMsgResp};
 pub use server::start_maker_server;
@@ -0,0 +1,203 @@
+//! Parsing of the PROXY protocol header, sent by a TCP reverse proxy in front of the Maker.
+//!
+//! The proxy prepends the header to every connection it forwards, declaring the address of the
+//! client. Both the text v1 and the binary v2 formats of the
+//! [PROXY protocol](https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt) are supported.
+
+use std::{
+    io::Read,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use super::error::MakerError;
+
+/// Signature starting a v2 header.
+const V2_SIGNATURE: [u8; 12] = [
+    0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A,
+];
+
+/// Longest v1 header, including the trailing CRLF.
+const V1_MAX_LEN: usize = 107;
+
+/// Reads the PROXY protocol header at the start of `stream`, and returns the client address it declares.
+///
+/// Only the header is read, the protocol messages following it are left on the stream. Returns `None`
+/// for a header declaring no client, like the `UNKNOWN` v1 or `LOCAL` v2 ones of the proxy's health checks.
+pub(crate) fn read_proxy_header(stream: &mut impl Read) -> Result<Option<SocketAddr>, MakerError> {
+    // Both versions are longer than the v2 signature.
+    let mut start = [0u8; 12];
+    stream.read_exact(&mut start)?;
+
+    if start == V2_SIGNATURE {
+        read_v2_header(stream)
+    } else if start.starts_with(b"PROXY ") {
+        read_v1_header(stream, &start)
+    } else {
+        Err(MakerError::General("Missing PROXY protocol header"))
+    }
+}
+
+fn read_v1_header(stream: &mut impl Read, start: &[u8]) -> Result<Option<SocketAddr>, MakerError> {
+    let mut header = start.to_vec();
+    while !header.ends_with(b"\r\n") {
+        if header.len() >= V1_MAX_LEN {
+            return Err(MakerError::General("PROXY protocol v1 header too long"));
+        }
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte)?;
+        header.push(byte[0]);
+    }
+
+    let header = std::str::from_utf8(&header[..header.len() - 2])
+        .map_err(|_| MakerError::General("Invalid PROXY protocol v1 header"))?;
+    let fields = header.split(' ').collect::<Vec<_>>();
+    let (src_ip, src_port) = match fields.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => return Ok(None),
+        ["PROXY", "TCP4", src_ip, _, src_port, _] => (
+            src_ip.parse::<Ipv4Addr>().map(IpAddr::V4),
+            src_port.parse::<u16>(),
+        ),
+        ["PROXY", "TCP6", src_ip, _, src_port, _] => (
+            src_ip.parse::<Ipv6Addr>().map(IpAddr::V6),
+            src_port.parse::<u16>(),
+        ),
+        _ => return Err(MakerError::General("Invalid PROXY protocol v1 header")),
+    };
+    match (src_ip, src_port) {
+        (Ok(ip), Ok(port)) => Ok(Some(SocketAddr::new(ip, port))),
+        _ => Err(MakerError::General("Invalid PROXY protocol v1 header")),
+    }
+}
+
+fn read_v2_header(stream: &mut impl Read) -> Result<Option<SocketAddr>, MakerError> {
+    let mut fixed = [0u8; 4];
+    stream.read_exact(&mut fixed)?;
+    let [version_command, family, len_high, len_low] = fixed;
+    if version_command >> 4 != 2 {
+        return Err(MakerError::General("Unsupported PROXY protocol version"));
+    }
+
+    // The addresses, and any TLVs after them, are always read, to leave only the protocol messages.
+    let mut addresses = vec![0u8; u16::from_be_bytes([len_high, len_low]) as usize];
+    stream.read_exact(&mut addresses)?;
+
+    match version_command & 0x0F {
+        // LOCAL: a connection of the proxy itself.
+        0x0 => return Ok(None),
+        // PROXY: a forwarded connection.
+        0x1 => {}
+        _ => return Err(MakerError::General("Invalid PROXY protocol v2 command")),
+    }
+
+    let too_short = MakerError::General("PROXY protocol v2 addresses too short");
+    match family {
+        // TCP over IPv4: source and destination addresses, then source and destination ports.
+        0x11 => {
+            let bytes = addresses.get(..12).ok_or(too_short)?;
+            let ip = Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]);
+            let port = u16::from_be_bytes([bytes[8], bytes[9]]);
+            Ok(Some(SocketAddr::new(IpAddr::V4(ip), port)))
+        }
+        // TCP over IPv6.
+        0x21 => {
+            let bytes = addresses.get(..36).ok_or(too_short)?;
+            let mut ip = [0u8; 16];
+            ip.copy_from_slice(&bytes[..16]);
+            let port = u16::from_be_bytes([bytes[32], bytes[33]]);
+            Ok(Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(ip)), port)))
+        }
+        // Unspecified, UDP or unix socket: no usable client address.
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(header: &[u8]) -> Result<Option<SocketAddr>, MakerError> {
+        read_proxy_header(&mut &header[..])
+    }
+
+    fn v2_header(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.push(0x20 | command);
+        header.push(family);
+        header.extend((addresses.len() as u16).to_be_bytes());
+        header.extend(addresses);
+        header
+    }
+
+    #[test]
+    fn test_proxy_protocol_v1() {
+        assert_eq!(
+            parse(b"PROXY TCP4 203.0.113.7 192.0.2.1 45678 6102\r\n").unwrap(),
+            Some("203.0.113.7:45678".parse().unwrap())
+        );
+        assert_eq!(
+            parse(b"PROXY TCP6 2001:db8::7 2001:db8::1 45678 6102\r\n").unwrap(),
+            Some("[2001:db8::7]:45678".parse().unwrap())
+        );
+        assert_eq!(parse(b"PROXY UNKNOWN\r\n").unwrap(), None);
+
+        // The messages after the header are left on the stream.
+        let mut stream = &b"PROXY TCP4 203.0.113.7 192.0.2.1 45678 6102\r\nhello"[..];
+        read_proxy_header(&mut stream).unwrap();
+        assert_eq!(stream, b"hello");
+
+        // Malformed headers.
+        assert!(parse(b"PROXY TCP4 203.0.113.7 192.0.2.1 45678\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 2001:db8::7 2001:db8::1 45678 6102\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 203.0.113.7 192.0.2.1 456789 6102\r\n").is_err());
+        let mut too_long = b"PROXY UNKNOWN ".to_vec();
+        too_long.extend([b'a'; V1_MAX_LEN]);
+        too_long.extend(b"\r\n");
+        assert!(parse(&too_long).is_err());
+    }
+
+    #[test]
+    fn test_proxy_protocol_v2() {
+        let mut tcp4 = vec![203, 0, 113, 7, 192, 0, 2, 1];
+        tcp4.extend(45678u16.to_be_bytes());
+        tcp4.extend(6102u16.to_be_bytes());
+        assert_eq!(
+            parse(&v2_header(0x1, 0x11, &tcp4)).unwrap(),
+            Some("203.0.113.7:45678".parse().unwrap())
+        );
+
+        let mut tcp6 = "2001:db8::7".parse::<Ipv6Addr>().unwrap().octets().to_vec();
+        tcp6.extend("2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        tcp6.extend(45678u16.to_be_bytes());
+        tcp6.extend(6102u16.to_be_bytes());
+        assert_eq!(
+            parse(&v2_header(0x1, 0x21, &tcp6)).unwrap(),
+            Some("[2001:db8::7]:45678".parse().unwrap())
+        );
+
+        // Health checks of the proxy, and TLVs after the addresses.
+        assert_eq!(parse(&v2_header(0x0, 0x00, &[])).unwrap(), None);
+        let mut with_tlv = tcp4.clone();
+        with_tlv.extend([0x04, 0x00, 0x01, 0xFF]);
+        let mut stream = v2_header(0x1, 0x11, &with_tlv);
+        stream.extend(b"hello");
+        let mut stream = &stream[..];
+        assert_eq!(
+            read_proxy_header(&mut stream).unwrap(),
+            Some("203.0.113.7:45678".parse().unwrap())
+        );
+        assert_eq!(stream, b"hello");
+
+        // Malformed headers.
+        assert!(parse(&v2_header(0x1, 0x11, &tcp4[..8])).is_err());
+        assert!(parse(&v2_header(0x2, 0x11, &tcp4)).is_err());
+    }
+
+    #[test]
+    fn test_missing_proxy_header() {
+        // A direct connection, starting with a protocol message.
+        let mut message = vec![0, 0, 0, 20];
+        message.extend([0u8; 20]);
+        assert!(parse(&message).is_err());
+        assert!(parse(b"PROXY").is_err());
+    }
+}
@@ -6,7 +6,13 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, to_string_pretty};
 use std::path::PathBuf;
 
-use crate::wallet::Balances;
+use crate::{
+    maker::{
+        api::{OfferFees, OfferTerms},
+        events::MakerEvent,
+    },
+    wallet::Balances,
+};
 
 /// Enum representing RPC message requests.
 ///
@@ -47,6 +53,35 @@ pub enum RpcMsgReq {
     ListFidelity,
     /// Request to sync the internal wallet with blockchain.
     SyncWallet,
+    /// Request to export the wallet seed mnemonic, for backups.
+    ///
+    /// Refused unless `confirm` is set, and seed export is allowed in the config.
+    ExportSeed {
+        /// Confirms that the seed should be exported.
+        confirm: bool,
+    },
+    /// Request to show the live offer advertised to Takers.
+    GetOffer,
+    /// Request to update the live offer.
+    ///
+    /// Rejected if the terms are inconsistent, e.g. `min_size` above `max_size`.
+    SetOffer {
+        /// Minimum swap amount, in sats.
+        min_size: u64,
+        /// Maximum swap amount, in sats.
+        max_size: u64,
+        /// Fees charged for a swap.
+        fee: OfferFees,
+    },
+    /// Request to read the event log.
+    GetEvents {
+        /// Only events at or after this Unix timestamp, in seconds.
+        since: u64,
+        /// Maximum number of events returned, oldest first.
+        limit: usize,
+    },
+    /// Request to list the other makers registered at the directory servers.
+    GetKnownMakers,
 }
 
 /// Enum representing RPC message responses.
@@ -95,6 +130,14 @@ pub enum RpcMsgResp {
     ServerError(String),
     /// Response listing all current and past fidelity bonds.
     ListBonds(String),
+    /// Response containing the wallet seed mnemonic.
+    ExportSeedResp(String),
+    /// Response containing the live offer terms.
+    GetOfferResp(OfferTerms),
+    /// Response containing the requested events, oldest first.
+    GetEventsResp(Vec<MakerEvent>),
+    /// Response containing the addresses of the other known makers.
+    GetKnownMakersResp(Vec<String>),
 }
 
 impl Display for RpcMsgResp {
@@ -112,6 +155,8 @@ impl Display for RpcMsgResp {
                         "contract": balances.contract.to_sat(),
                         "fidelity": balances.fidelity.to_sat(),
                         "spendable": balances.spendable.to_sat(),
+                        "immature": balances.immature.to_sat(),
+                        "pending_swap_out": balances.pending_swap_out.to_sat(),
                     }))
                     .unwrap()
                 )
@@ -127,6 +172,33 @@ impl Display for RpcMsgResp {
             Self::FidelitySpend(txid) => write!(f, "{}", txid),
             Self::ServerError(e) => write!(f, "{}", e),
             Self::ListBonds(v) => write!(f, "{}", v),
+            Self::ExportSeedResp(mnemonic) => write!(f, "{}", mnemonic),
+            Self::GetOfferResp(terms) => {
+                write!(
+                    f,
+                    "{}",
+                    to_string_pretty(&json!({
+                        "min_size": terms.min_size,
+                        "max_size": terms.max_size,
+                        "base_fee": terms.fee.base_fee,
+                        "amount_relative_fee_pct": terms.fee.amount_relative_fee_pct,
+                        "time_relative_fee_pct": terms.fee.time_relative_fee_pct,
+                    }))
+                    .unwrap()
+                )
+            }
+            Self::GetEventsResp(events) => {
+                for event in events {
+                    writeln!(f, "{}", serde_json::to_string(event).unwrap())?;
+                }
+                Ok(())
+            }
+            Self::GetKnownMakersResp(addresses) => {
+                for address in addresses {
+                    writeln!(f, "{}", address)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -10,7 +10,10 @@ use bitcoin::{Address, Amount};
 
 use super::messages::RpcMsgReq;
 use crate::{
-    maker::{error::MakerError, rpc::messages::RpcMsgResp, Maker},
+    maker::{
+        api::OfferTerms, error::MakerError, rpc::messages::RpcMsgResp, server::fetch_known_makers,
+        Maker,
+    },
     utill::{get_tor_hostname, read_message, send_message, ConnectionType, HEART_BEAT_INTERVAL},
     wallet::Destination,
 };
@@ -82,15 +85,10 @@ fn handle_request(maker: &Arc<Maker>, socket: &mut TcpStream) -> Result<(), Make
                 amount,
             )]);
 
-            let coins_to_send = maker.get_wallet().read()?.coin_select(amount)?;
-
-            let tx = maker.get_wallet().write()?.spend_from_wallet(
-                feerate,
-                destination,
-                &coins_to_send,
-            )?;
-
-            let txid = maker.get_wallet().read()?.send_tx(&tx)?;
+            let txid = maker
+                .get_wallet()
+                .write()?
+                .send_to_address(destination, feerate)?;
 
             maker.get_wallet().write()?.sync_no_fail();
 
@@ -123,6 +121,44 @@ fn handle_request(maker: &Arc<Maker>, socket: &mut TcpStream) -> Result<(), Make
             let list = maker.get_wallet().read()?.display_fidelity_bonds()?;
             RpcMsgResp::ListBonds(list)
         }
+        RpcMsgReq::ExportSeed { confirm } => {
+            if !maker.config.allow_seed_export {
+                log::warn!("Seed export requested, but it is disabled in the config");
+                RpcMsgResp::ServerError(
+                    "Seed export is disabled. Set `allow_seed_export = true` in the config to enable it"
+                        .to_string(),
+                )
+            } else if !confirm {
+                RpcMsgResp::ServerError(
+                    "Seed export not confirmed. Anyone with the seed can spend all the wallet funds"
+                        .to_string(),
+                )
+            } else {
+                let mnemonic = maker.get_wallet().read()?.get_mnemonic()?;
+                log::warn!("!!! THE WALLET SEED WAS EXPORTED OVER RPC. KEEP IT SAFE !!!");
+                RpcMsgResp::ExportSeedResp(mnemonic.to_string())
+            }
+        }
+        RpcMsgReq::GetOffer => RpcMsgResp::GetOfferResp(maker.get_offer_terms()?),
+        RpcMsgReq::GetEvents { since, limit } => {
+            RpcMsgResp::GetEventsResp(maker.event_log.read(since, limit)?)
+        }
+        RpcMsgReq::GetKnownMakers => RpcMsgResp::GetKnownMakersResp(fetch_known_makers(maker)?),
+        RpcMsgReq::SetOffer {
+            min_size,
+            max_size,
+            fee,
+        } => {
+            let terms = OfferTerms {
+                min_size,
+                max_size,
+                fee,
+            };
+            match maker.set_offer_terms(terms) {
+                Ok(()) => RpcMsgResp::GetOfferResp(maker.get_offer_terms()?),
+                Err(e) => RpcMsgResp::ServerError(format!("{:?}", e)),
+            }
+        }
         RpcMsgReq::SyncWallet => {
             log::info!("Initializing wallet sync");
             if let Err(e) = maker.get_wallet().write()?.sync() {
@@ -141,14 +177,11 @@ fn handle_request(maker: &Arc<Maker>, socket: &mut TcpStream) -> Result<(), Make
     Ok(())
 }
 
-pub(crate) fn start_rpc_server(maker: Arc<Maker>) -> Result<(), MakerError> {
-    let rpc_port = maker.config.rpc_port;
-    let rpc_socket = format!("127.0.0.1:{}", rpc_port);
-    let listener = Arc::new(TcpListener::bind(&rpc_socket)?);
+pub(crate) fn start_rpc_server(maker: Arc<Maker>, listener: TcpListener) -> Result<(), MakerError> {
     log::info!(
         "[{}] RPC socket binding successful at {}",
         maker.config.network_port,
-        rpc_socket
+        listener.local_addr()?
     );
 
     listener.set_nonblocking(true)?;
@@ -9,11 +9,12 @@ use bitcoin::{absolute::LockTime, Amount};
 use bitcoind::bitcoincore_rpc::RpcApi;
 use socks::Socks5Stream;
 use std::{
-    io::ErrorKind,
-    net::{Ipv4Addr, TcpListener, TcpStream},
+    collections::{HashMap, VecDeque},
+    io::{self, ErrorKind},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
     sync::{atomic::Ordering::Relaxed, Arc},
     thread::{self, sleep},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::utill::get_tor_hostname;
@@ -24,35 +25,65 @@ use crate::{
     error::NetError,
     maker::{
         api::{
-            check_for_broadcasted_contracts, check_for_idle_states,
-            restore_broadcasted_contracts_on_reboot, ConnectionState,
+            autosave_wallet, bind_port, check_for_broadcasted_contracts, check_for_idle_states,
+            consolidate_utxos, restore_broadcasted_contracts_on_reboot, top_up_fidelity_bond,
+            ConnectionState, ExpectedMessage, MakerBehavior, FIDELITY_BOND_CHECK_INTERVAL,
             FIDELITY_BOND_DNS_UPDATE_INTERVAL, SWAP_LIQUIDITY_CHECK_INTERVAL,
         },
+        events::MakerEventKind,
         handlers::handle_message,
+        proxy_protocol::read_proxy_header,
         rpc::start_rpc_server,
     },
-    protocol::messages::{DnsMetadata, DnsRequest, DnsResponse, TakerToMakerMessage},
-    utill::{read_message, send_message, ConnectionType, DEFAULT_TX_FEE_RATE, HEART_BEAT_INTERVAL},
-    wallet::WalletError,
+    protocol::{
+        messages::{
+            DeclineReason, DnsMetadata, DnsRequest, DnsResponse, MakerToTakerMessage,
+            TakerToMakerMessage,
+        },
+        trace::TracedStream,
+    },
+    taker::offers::fetch_addresses_from_directories,
+    utill::{
+        read_message, send_message, set_socket_options, ConnectionType, DEFAULT_TX_FEE_RATE,
+        HEART_BEAT_INTERVAL,
+    },
+    wallet::{FidelityError, WalletError},
 };
 
 use crate::maker::error::MakerError;
 
-/// Fetches the Maker and DNS address, and sends maker address to the DNS server.
-/// Depending upon ConnectionType and test/prod environment, different maker address and DNS addresses are returned.
-/// Return the Maker address and the DNS address.
-fn network_bootstrap(maker: Arc<Maker>) -> Result<(String, String), MakerError> {
+/// Fetches the Maker and DNS addresses, and sends maker address to the DNS servers.
+/// Depending upon ConnectionType, different maker addresses are returned.
+/// Return the Maker address and the DNS addresses.
+fn network_bootstrap(maker: Arc<Maker>) -> Result<(String, Vec<String>), MakerError> {
+    let maker_address = get_maker_address(&maker)?;
+    let dns_addresses = maker.config.directory_server_addresses.clone();
+
+    if maker.config.require_fidelity_bond {
+        setup_fidelity_bond(&maker, &maker_address)?;
+    } else {
+        log::warn!(
+            "[{}] Running without a fidelity bond, offers are only accepted by takers allowing bondless makers",
+            maker.config.network_port
+        );
+    }
+
+    manage_fidelity_bonds_and_update_dns(maker.as_ref(), &maker_address, &dns_addresses)?;
+
+    Ok((maker_address, dns_addresses))
+}
+
+/// The address the Maker advertises to the directory servers, as per its connection type.
+pub(crate) fn get_maker_address(maker: &Maker) -> Result<String, MakerError> {
     let maker_port = maker.config.network_port;
-    let (maker_address, dns_address) = match maker.config.connection_type {
+    let maker_address = match maker.config.connection_type {
         ConnectionType::CLEARNET => {
-            let maker_address = format!("127.0.0.1:{}", maker_port);
-            let dns_address = if cfg!(feature = "integration-test") {
-                format!("127.0.0.1:{}", 8080)
-            } else {
-                maker.config.directory_server_address.clone()
-            };
-
-            (maker_address, dns_address)
+            if maker.config.tor_only {
+                return Err(MakerError::General(
+                    "Tor-only maker refuses to advertise a clearnet address",
+                ));
+            }
+            format!("127.0.0.1:{}", maker_port)
         }
         ConnectionType::TOR => {
             let maker_hostname = get_tor_hostname(
@@ -61,39 +92,84 @@ fn network_bootstrap(maker: Arc<Maker>) -> Result<(String, String), MakerError>
                 maker.config.network_port,
                 &maker.config.tor_auth_password,
             )?;
-            let maker_address = format!("{}:{}", maker_hostname, maker.config.network_port);
-
-            let dns_address = maker.config.directory_server_address.clone();
-            (maker_address, dns_address)
+            format!("{}:{}", maker_hostname, maker.config.network_port)
         }
     };
+    Ok(maker_address)
+}
 
-    maker
-        .as_ref()
-        .track_and_update_unconfirmed_fidelity_bonds()?;
-
-    setup_fidelity_bond(&maker, &maker_address)?;
-
-    manage_fidelity_bonds_and_update_dns(maker.as_ref(), &maker_address, &dns_address)?;
-
-    Ok((maker_address, dns_address))
+/// Fetches the addresses of the other makers registered at the directory servers.
+///
+/// The directories are only queried on request, as the Maker itself has no use for them.
+pub(crate) fn fetch_known_makers(maker: &Maker) -> Result<Vec<String>, MakerError> {
+    let own_address = get_maker_address(maker)?;
+    let addresses = fetch_addresses_from_directories(
+        Some(maker.config.socks_port),
+        &maker.config.directory_server_addresses,
+        maker.config.connection_type,
+    )
+    .map_err(|e| {
+        log::error!(
+            "[{}] Failed fetching the maker addresses from the directory servers: {:?}",
+            maker.config.network_port,
+            e
+        );
+        MakerError::General("Could not reach any directory server")
+    })?;
+    Ok(addresses
+        .into_iter()
+        .map(|address| address.to_string())
+        .filter(|address| *address != own_address)
+        .collect())
 }
 
-/// Manages the maker's fidelity bonds and ensures the DNS server is updated with the latest bond proof and maker address.
+/// Manages the maker's fidelity bonds and ensures the DNS servers are updated with the latest bond proof and maker address.
 ///
 /// It performs the following operations:
 /// 1. Redeems all expired fidelity bonds in the maker's wallet, if any are found.
-/// 2. Creates a new fidelity bond if no valid bonds remain after redemption.
-/// 3. Sends a POST request to the DNS server containing the maker's address and the proof of the fidelity bond
-///    with the highest value.
+/// 2. Tops up the fidelity bond from the swap earnings, if `auto_bond_topup` is enabled.
+/// 3. Creates a new fidelity bond if no valid bonds remain after redemption.
+/// 4. Sends a POST request to every DNS server containing the maker's address and the proof of the fidelity bond
+///    with the highest value. This is reattempted until at least one DNS server accepts it. The unreachable
+///    ones are tried again at the next periodic update.
+///
+/// Bondless makers skip the first three steps, and post their address without a proof.
 fn manage_fidelity_bonds_and_update_dns(
     maker: &Maker,
     maker_addr: &str,
-    dns_addr: &str,
+    dns_addrs: &[String],
 ) -> Result<(), MakerError> {
-    maker.wallet.write()?.redeem_expired_fidelity_bonds()?;
+    let proof = if maker.config.require_fidelity_bond {
+        maker.wallet.write()?.redeem_expired_fidelity_bonds()?;
 
-    let proof = setup_fidelity_bond(maker, maker_addr)?;
+        // A failed top-up leaves the current bond in place, which is still advertised.
+        match top_up_fidelity_bond(maker) {
+            Ok(Some(i)) => log::info!(
+                "[{}] Topped up the fidelity bond, with the new bond at index {}",
+                maker.config.network_port,
+                i
+            ),
+            Ok(None) => {}
+            Err(e) => log::error!(
+                "[{}] Failed topping up the fidelity bond, keeping the current one: {:?}",
+                maker.config.network_port,
+                e
+            ),
+        }
+
+        let proof = setup_fidelity_bond(maker, maker_addr)?;
+
+        if !check_fidelity_bond_validity(maker, maker_addr)? {
+            log::warn!(
+                "[{}] Skipping DNS update, as we have no valid fidelity bond to advertise",
+                maker.config.network_port
+            );
+            return Ok(());
+        }
+        Some(proof)
+    } else {
+        None
+    };
 
     let dns_metadata = DnsMetadata {
         url: maker_addr.to_string(),
@@ -104,72 +180,84 @@ fn manage_fidelity_bonds_and_update_dns(
         metadata: dns_metadata,
     };
 
+    while !maker.shutdown.load(Relaxed) {
+        let mut registered = false;
+        for dns_addr in dns_addrs {
+            registered |= post_to_dns(maker, &request, dns_addr);
+        }
+        if registered {
+            break;
+        }
+        thread::sleep(HEART_BEAT_INTERVAL);
+    }
+
+    Ok(())
+}
+
+/// Sends the maker's address and fidelity proof to a single DNS server.
+///
+/// Returns `true` if the DNS server accepted it.
+fn post_to_dns(maker: &Maker, request: &DnsRequest, dns_addr: &str) -> bool {
     let network_port = maker.config.network_port;
 
     log::info!("[{}] Connecting to DNS: {}", network_port, dns_addr);
 
-    while !maker.shutdown.load(Relaxed) {
-        let stream = match maker.config.connection_type {
-            ConnectionType::CLEARNET => TcpStream::connect(dns_addr),
-            ConnectionType::TOR => {
-                Socks5Stream::connect(format!("127.0.0.1:{}", maker.config.socks_port), dns_addr)
-                    .map(|s| s.into_inner())
-            }
-        };
+    let stream = match maker.config.connection_type {
+        ConnectionType::CLEARNET => TcpStream::connect(dns_addr),
+        ConnectionType::TOR => {
+            Socks5Stream::connect(format!("127.0.0.1:{}", maker.config.socks_port), dns_addr)
+                .map(|s| s.into_inner())
+        }
+    };
 
-        match stream {
-            Ok(mut stream) => match send_message(&mut stream, &request) {
-                Ok(_) => match read_message(&mut stream) {
-                    Ok(dns_msg_bytes) => {
-                        match serde_cbor::from_slice::<DnsResponse>(&dns_msg_bytes) {
-                            Ok(dns_msg) => match dns_msg {
-                                DnsResponse::Ack => {
-                                    log::info!("[{}] <=== {}", network_port, dns_msg);
-                                    log::info!( "[{}] Successfully sent our address and fidelity proof to DNS at {}",network_port, dns_addr);
-                                    break;
-                                }
-                                DnsResponse::Nack(reason) => {
-                                    log::error!("<=== DNS Nack: {}", reason)
-                                }
-                            },
-                            Err(e) => {
-                                log::warn!("CBOR deserialization failed: {} | Reattempting...", e)
-                            }
+    match stream {
+        Ok(mut stream) => match send_message(&mut stream, request) {
+            Ok(_) => match read_message(&mut stream) {
+                Ok(dns_msg_bytes) => match serde_cbor::from_slice::<DnsResponse>(&dns_msg_bytes) {
+                    Ok(dns_msg) => match dns_msg {
+                        DnsResponse::Ack => {
+                            log::info!("[{}] <=== {}", network_port, dns_msg);
+                            log::info!(
+                                "[{}] Successfully sent our address and fidelity proof to DNS at {}",
+                                network_port,
+                                dns_addr
+                            );
+                            return true;
                         }
-                    }
-                    Err(e) => {
-                        if let NetError::IO(e) = e {
-                            if e.kind() == ErrorKind::UnexpectedEof {
-                                log::info!("[{}] Connection ended.", maker.config.network_port);
-                                break;
-                            } else {
-                                // For any other errors, report them
-                                log::error!(
-                                    "[{}] DNS Connection Error: {}",
-                                    maker.config.network_port,
-                                    e
-                                );
-                            }
+                        DnsResponse::Nack(reason) => {
+                            log::error!("<=== DNS Nack: {}", reason)
                         }
+                    },
+                    Err(e) => {
+                        log::warn!("CBOR deserialization failed: {} | Reattempting...", e)
                     }
                 },
-                Err(e) => log::warn!(
-                    "[{}] Failed to send request to DNS : {} | reattempting...",
-                    network_port,
-                    e
-                ),
+                Err(e) => {
+                    if let NetError::IO(e) = e {
+                        if e.kind() == ErrorKind::UnexpectedEof {
+                            log::info!("[{}] Connection ended.", network_port);
+                            return true;
+                        } else {
+                            // For any other errors, report them
+                            log::error!("[{}] DNS Connection Error: {}", network_port, e);
+                        }
+                    }
+                }
             },
             Err(e) => log::warn!(
-                "[{}] Failed to establish TCP connection with DNS : {} | reattempting...",
+                "[{}] Failed to send request to DNS : {} | reattempting...",
                 network_port,
                 e
             ),
-        }
-
-        thread::sleep(HEART_BEAT_INTERVAL);
+        },
+        Err(e) => log::warn!(
+            "[{}] Failed to establish TCP connection with DNS : {} | reattempting...",
+            network_port,
+            e
+        ),
     }
 
-    Ok(())
+    false
 }
 
 /// Ensures the wallet has a valid fidelity bond. If no active bond exists, it creates a new one.
@@ -180,6 +268,9 @@ fn manage_fidelity_bonds_and_update_dns(
 /// ## Returns:
 /// - The highest **FidelityProof**, proving ownership of the highest valid fidelity bond, the maker has.
 fn setup_fidelity_bond(maker: &Maker, maker_address: &str) -> Result<FidelityProof, MakerError> {
+    // Unconfirmed bonds can't be valued yet. Wait for them, so an existing bond isn't created again.
+    maker.track_and_update_unconfirmed_fidelity_bonds()?;
+
     let highest_index = maker.get_wallet().read()?.get_highest_fidelity_index()?;
     let mut proof = maker.highest_fidelity_proof.write()?;
 
@@ -221,13 +312,8 @@ fn setup_fidelity_bond(maker: &Maker, maker_address: &str) -> Result<FidelityPro
             .get_block_count()
             .map_err(WalletError::Rpc)? as u32;
 
-        // Set 950 blocks locktime for test
-        let locktime = if cfg!(feature = "integration-test") {
-            LockTime::from_height(current_height + 950).map_err(WalletError::Locktime)?
-        } else {
-            LockTime::from_height(maker.config.fidelity_timelock + current_height)
-                .map_err(WalletError::Locktime)?
-        };
+        let locktime = LockTime::from_height(maker.config.fidelity_timelock + current_height)
+            .map_err(WalletError::Locktime)?;
 
         log::info!(
             "Fidelity timelock {:?} blocks",
@@ -257,7 +343,10 @@ fn setup_fidelity_bond(maker: &Maker, maker_address: &str) -> Result<FidelityPro
                         required,
                     } = e
                     {
-                        log::warn!("Insufficient fund to create fidelity bond.");
+                        log::warn!(
+                            "Insufficient fund to create fidelity bond of {} sats (configured by `fidelity_amount`).",
+                            maker.config.fidelity_amount
+                        );
                         let amount = required - available;
                         let addr = maker.get_wallet().write()?.get_next_external_address()?;
 
@@ -280,6 +369,7 @@ fn setup_fidelity_bond(maker: &Maker, maker_address: &str) -> Result<FidelityPro
                         "[{}] Successfully created fidelity bond",
                         maker.config.network_port
                     );
+                    maker.record_event(MakerEventKind::BondRotated { index: i });
                     let highest_proof = maker
                         .get_wallet()
                         .read()?
@@ -301,6 +391,63 @@ fn setup_fidelity_bond(maker: &Maker, maker_address: &str) -> Result<FidelityPro
         .expect("Fidelity Proof must exist after creating a bond"))
 }
 
+/// Verifies that the advertised fidelity bond is still unspent, and passes the same checks takers do.
+///
+/// A bond can get spent behind the server's back, e.g. by a manual wallet operation, and takers ban
+/// makers serving such a bond. So if the check fails, the proof is dropped, which stops serving offers
+/// and advertising to the DNS until a valid bond is set up again. A spent bond is also marked as spent
+/// in the wallet, so a new one gets created on the next bond management round.
+///
+/// Returns whether the advertised bond is valid.
+fn check_fidelity_bond_validity(maker: &Maker, maker_addr: &str) -> Result<bool, MakerError> {
+    let mut proof = maker.highest_fidelity_proof.write()?;
+    let fidelity = match proof.as_ref() {
+        Some(fidelity) => fidelity,
+        None => return Ok(false),
+    };
+    let outpoint = fidelity.bond.outpoint;
+
+    let verification = maker
+        .get_wallet()
+        .read()?
+        .verify_own_fidelity_proof(fidelity, maker_addr);
+
+    match verification {
+        Ok(()) => Ok(true),
+        Err(WalletError::Fidelity(e)) => {
+            log::error!(
+                "[{}] !!! Fidelity bond {} is no longer valid: {:?} | Stopped serving offers and advertising to the DNS !!!",
+                maker.config.network_port,
+                outpoint,
+                e
+            );
+            if let FidelityError::BondAlreadySpent = e {
+                let mut wallet = maker.get_wallet().write()?;
+                if let Some((_, _, is_spent)) = wallet
+                    .store
+                    .fidelity_bond
+                    .values_mut()
+                    .find(|(bond, _, _)| bond.outpoint == outpoint)
+                {
+                    *is_spent = true;
+                }
+                wallet.save_to_disk()?;
+            }
+            *proof = None;
+            Ok(false)
+        }
+        // Don't drop a possibly valid bond over a backend hiccup. It's checked again next round.
+        Err(e) => {
+            log::warn!(
+                "[{}] Could not verify our fidelity bond: {:?}",
+                maker.config.network_port,
+                e
+            );
+            Ok(true)
+        }
+    }
+}
+
 /// Checks if the maker has enough liquidity for swaps.
 /// If funds are below the minimum required, it repeatedly prompts the user to add more
 /// until the liquidity is sufficient.
@@ -312,7 +459,7 @@ fn check_swap_liquidity(maker: &Maker) -> Result<(), MakerError> {
         maker.get_wallet().write()?.sync_no_fail();
         let offer_max_size = maker.get_wallet().read()?.store.offer_maxsize;
 
-        let min_required = maker.config.min_swap_amount;
+        let min_required = maker.offer_terms.read()?.min_size;
         if offer_max_size < min_required {
             log::warn!(
                 "Low Swap Liquidity | Min: {} sats | Available: {} sats. Add funds to {:?}",
@@ -365,32 +512,142 @@ fn check_connection_with_core(maker: &Maker) -> Result<(), MakerError> {
     Ok(())
 }
 
-/// Handle a single client connection.
-fn handle_client(maker: &Arc<Maker>, stream: &mut TcpStream) -> Result<(), MakerError> {
+/// Checks whether a connection from `peer_addr` is allowed under the maker's Tor policy.
+///
+/// Hidden service traffic is forwarded by the local Tor daemon, so in `tor_only` mode any
+/// peer that is not on the loopback interface must have arrived over clearnet.
+///
+/// Only the IP of the TCP peer is trusted here. Any other local process, like a reverse proxy
+/// on the same host, is served as if it was Tor, and a client address declared in a PROXY
+/// protocol header is not considered.
+fn is_connection_allowed(tor_only: bool, peer_addr: &SocketAddr) -> bool {
+    !tor_only || peer_addr.ip().is_loopback()
+}
+
+/// Accepts the next connection of the swap listener, dropping it if refused by the Tor policy.
+fn accept_connection(
+    listener: &TcpListener,
+    tor_only: bool,
+    network_port: u16,
+) -> io::Result<Option<(TcpStream, SocketAddr)>> {
+    let (stream, peer_addr) = listener.accept()?;
+    if !is_connection_allowed(tor_only, &peer_addr) {
+        log::warn!(
+            "[{}] Refusing clearnet connection from {} in tor-only mode",
+            network_port,
+            peer_addr
+        );
+        return Ok(None);
+    }
+    Ok(Some((stream, peer_addr)))
+}
+
+/// Time given to the reverse proxy to send the PROXY protocol header of a connection.
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns the address of the client behind a connection.
+///
+/// With `proxy_protocol` set, it is the one declared in the PROXY protocol header sent by the reverse
+/// proxy, which is read off the stream. Otherwise, or if the header declares none, it is `peer_addr`.
+fn client_address(
+    proxy_protocol: bool,
+    stream: &mut TcpStream,
+    peer_addr: SocketAddr,
+) -> Result<SocketAddr, MakerError> {
+    if !proxy_protocol {
+        return Ok(peer_addr);
+    }
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(PROXY_HEADER_TIMEOUT))?;
+    let declared = read_proxy_header(stream);
+    stream.set_read_timeout(None)?;
+    Ok(declared?.unwrap_or(peer_addr))
+}
+
+/// Limits the connections accepted from each client IP, over a sliding window of a minute.
+struct ConnectionRateLimiter {
+    max_per_minute: u32,
+    connections: HashMap<IpAddr, VecDeque<Instant>>,
+}
+
+impl ConnectionRateLimiter {
+    fn new(max_per_minute: u32) -> Self {
+        Self {
+            max_per_minute,
+            connections: HashMap::new(),
+        }
+    }
+
+    /// Records a connection from `ip` at `now`, and returns whether it is within the limit.
+    fn allow(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self.max_per_minute == 0 {
+            return true;
+        }
+        // Forget the connections out of the window, and the clients without any left.
+        self.connections.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|time| now.duration_since(*time) >= Duration::from_secs(60))
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = self.connections.entry(ip).or_default();
+        if times.len() >= self.max_per_minute as usize {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+/// Handle a single client connection, from `client_addr` as per [client_address].
+fn handle_client(
+    maker: &Arc<Maker>,
+    stream: &mut TcpStream,
+    client_addr: SocketAddr,
+) -> Result<(), MakerError> {
     stream.set_nonblocking(false)?; // Block this thread until message is read.
+    set_socket_options(
+        stream,
+        maker.config.tcp_nodelay,
+        maker.config.tcp_keepalive_interval,
+    )?;
 
     let mut connection_state = ConnectionState::default();
+    let mut stream = TracedStream::new(stream, &maker.protocol_trace);
 
     while !maker.shutdown.load(Relaxed) {
-        let mut taker_msg_bytes = Vec::new();
-        match read_message(stream) {
-            Ok(b) => taker_msg_bytes = b,
-            Err(e) => {
-                if let NetError::IO(e) = e {
-                    if e.kind() == ErrorKind::UnexpectedEof {
-                        log::info!("[{}] Connection ended.", maker.config.network_port);
-                        break;
-                    } else {
-                        // For any other errors, report them
-                        log::error!("[{}] Net Error: {}", maker.config.network_port, e);
-                        continue;
-                    }
-                }
+        let taker_msg: TakerToMakerMessage = match stream.read(connection_state.compression) {
+            Ok(msg) => msg,
+            Err(NetError::IO(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                log::info!(
+                    "[{}] Connection from {} ended.",
+                    maker.config.network_port,
+                    client_addr
+                );
+                break;
             }
-        }
-
-        let taker_msg: TakerToMakerMessage = serde_cbor::from_slice(&taker_msg_bytes)?;
-        log::info!("[{}] <=== {}", maker.config.network_port, taker_msg);
+            Err(NetError::IO(e)) => {
+                // For any other errors, report them
+                log::error!(
+                    "[{}] Net Error from {}: {}",
+                    maker.config.network_port,
+                    client_addr,
+                    e
+                );
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        log::info!(
+            "[{}] <=== {} | {}",
+            maker.config.network_port,
+            taker_msg,
+            client_addr
+        );
 
         let reply = handle_message(maker, &mut connection_state, taker_msg);
 
@@ -398,15 +655,40 @@ fn handle_client(maker: &Arc<Maker>, stream: &mut TcpStream) -> Result<(), Maker
             Ok(reply) => {
                 if let Some(message) = reply {
                     log::info!("[{}] ===> {} ", maker.config.network_port, message);
-                    if let Err(e) = send_message(stream, &message) {
+                    if let Err(e) = stream.send(&message, connection_state.compression) {
                         log::error!("Closing due to IO error in sending message: {:?}", e);
                         continue;
                     }
+                    if matches!(maker.behavior, MakerBehavior::ReplayMessages) {
+                        log::info!("[{}] ===> {} (replay)", maker.config.network_port, message);
+                        let _ = stream.send(&message, connection_state.compression);
+                    }
                 } else {
                     continue;
                 }
             }
             Err(err) => {
+                // Let the Taker know the swap is declined through no fault of its own, so it tries
+                // another Maker without holding it against this one.
+                if let MakerError::CapacityExceeded { .. } = err {
+                    let decline = MakerToTakerMessage::RespSwapDeclined(DeclineReason::Capacity);
+                    log::info!("[{}] ===> {} ", maker.config.network_port, decline);
+                    if let Err(e) = stream.send(&decline, connection_state.compression) {
+                        log::error!("Failed to send the swap decline: {:?}", e);
+                    }
+                }
+                // Errors before the Taker asked for contract signatures are not swap attempts.
+                if !matches!(
+                    connection_state.allowed_message,
+                    ExpectedMessage::TakerHello
+                        | ExpectedMessage::NewlyConnectedTaker
+                        | ExpectedMessage::ReqContractSigsForSender
+                ) {
+                    maker.record_event(MakerEventKind::SwapFailed {
+                        id: connection_state.swap_id.clone(),
+                        reason: format!("{:?}", err),
+                    });
+                }
                 match &err {
                     // Shutdown server if special behavior is set
                     MakerError::SpecialBehaviour(sp) => {
@@ -419,8 +701,9 @@ fn handle_client(maker: &Arc<Maker>, stream: &mut TcpStream) -> Result<(), Maker
                     }
                     e => {
                         log::error!(
-                            "[{}] Internal message handling error occurred: {:?}",
+                            "[{}] Internal message handling error occurred with {}: {:?}",
                             maker.config.network_port,
+                            client_addr,
                             e
                         );
                     }
@@ -440,6 +723,7 @@ fn handle_client(maker: &Arc<Maker>, stream: &mut TcpStream) -> Result<(), Maker
 /// - Checking for idle client connections.  
 /// - Detecting and handling broadcasted contract transactions.  
 /// - Running an RPC server for communication with `maker-cli`.  
+/// - Periodically saving the wallet, if `wallet_autosave_interval` is set.
 ///
 /// The server continuously listens for incoming P2P client connections.
 /// It performs periodic checks to ensure liquidity availability, update fidelity bonds,  
@@ -450,8 +734,20 @@ fn handle_client(maker: &Arc<Maker>, stream: &mut TcpStream) -> Result<(), Maker
 pub fn start_maker_server(maker: Arc<Maker>) -> Result<(), MakerError> {
     log::info!("Starting Maker Server");
 
+    let network_port = maker.config.network_port;
+
+    // Bind both listeners first, to fail fast if a port got taken since the maker was initialized.
+    let listener = bind_port(&maker.config.bind_address, network_port)?;
+    let rpc_listener = bind_port("127.0.0.1", maker.config.rpc_port)?;
+    log::info!(
+        "[{}] Swap listener bound to {}",
+        network_port,
+        listener.local_addr()?
+    );
+    listener.set_nonblocking(true)?; // Needed to not block a thread waiting for incoming connection.
+
     // Setup the wallet with fidelity bond.
-    let (maker_addr, dns_addr) = network_bootstrap(maker.clone())?;
+    let (maker_addr, dns_addrs) = network_bootstrap(maker.clone())?;
 
     // Tracks the elapsed time in heartbeat intervals to schedule periodic checks and avoid redundant executions.
     let mut interval_tracker = 0;
@@ -462,8 +758,6 @@ pub fn start_maker_server(maker: Arc<Maker>) -> Result<(), MakerError> {
     // This ensures these functions are not executed twice in quick succession.
     interval_tracker += HEART_BEAT_INTERVAL.as_secs() as u32;
 
-    let network_port = maker.config.network_port;
-
     {
         let wallet = maker.get_wallet().read()?;
         log::info!(
@@ -478,9 +772,7 @@ pub fn start_maker_server(maker: Arc<Maker>) -> Result<(), MakerError> {
         );
     }
 
-    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, maker.config.network_port))
-        .map_err(NetError::IO)?;
-    listener.set_nonblocking(true)?; // Needed to not block a thread waiting for incoming connection.
+    let mut rate_limiter = ConnectionRateLimiter::new(maker.config.max_connections_per_minute);
 
     if !maker.shutdown.load(Relaxed) {
         // 1. Idle Client connection checker thread.
@@ -524,7 +816,7 @@ pub fn start_maker_server(maker: Arc<Maker>) -> Result<(), MakerError> {
             .name("RPC Thread".to_string())
             .spawn(move || {
                 log::info!("[{}] Spawning RPC server thread", network_port);
-                match start_rpc_server(maker_clone.clone()) {
+                match start_rpc_server(maker_clone.clone(), rpc_listener) {
                     Ok(_) => (),
                     Err(e) => {
                         log::error!("Failed starting rpc server {:?}", e);
@@ -535,6 +827,22 @@ pub fn start_maker_server(maker: Arc<Maker>) -> Result<(), MakerError> {
 
         maker.thread_pool.add_thread(rpc_thread);
 
+        // 4: The wallet auto-save thread, if enabled.
+        // Periodically persists the wallet, so a crash doesn't lose the changes since the last save.
+        if maker.config.wallet_autosave_interval > 0 {
+            let maker_clone = maker.clone();
+            let autosave_thread = thread::Builder::new()
+                .name("Wallet Autosave Thread".to_string())
+                .spawn(move || {
+                    log::info!("[{}] Spawning wallet auto-save thread", network_port);
+                    if let Err(e) = autosave_wallet(maker_clone.clone()) {
+                        log::error!("Failed auto-saving the wallet {:?}", e);
+                        maker_clone.shutdown.store(true, Relaxed);
+                    }
+                })?;
+            maker.thread_pool.add_thread(autosave_thread);
+        }
+
         sleep(HEART_BEAT_INTERVAL); // wait for 1 beat, to complete spawns of all the threads.
 
         // Check if recovery is needed.
@@ -553,27 +861,69 @@ pub fn start_maker_server(maker: Arc<Maker>) -> Result<(), MakerError> {
             check_connection_with_core(maker.as_ref())?;
         }
 
+        if maker.config.require_fidelity_bond
+            && interval_tracker % FIDELITY_BOND_CHECK_INTERVAL == 0
+        {
+            check_fidelity_bond_validity(maker.as_ref(), &maker_addr)?;
+        }
+
         // Perform fidelity bond and liquidity checks only when no coinswap is in progress.
         // This prevents the server from getting blocked while creating a new bond or waiting
         // for additional funds, which could otherwise interrupt an ongoing swap.
         // Running these checks during an active swap might cause the maker to stop responding,
         // potentially aborting the swap.
         if maker.ongoing_swap_state.lock()?.is_empty() {
-            if interval_tracker % FIDELITY_BOND_DNS_UPDATE_INTERVAL == 0 {
-                manage_fidelity_bonds_and_update_dns(maker.as_ref(), &maker_addr, &dns_addr)?;
+            // Re-register right away when requested, e.g. after the offer was changed over RPC.
+            let dns_update_requested = maker.dns_update_requested.swap(false, Relaxed);
+            if dns_update_requested || interval_tracker % FIDELITY_BOND_DNS_UPDATE_INTERVAL == 0 {
+                manage_fidelity_bonds_and_update_dns(maker.as_ref(), &maker_addr, &dns_addrs)?;
                 interval_tracker = 0;
             }
 
             if interval_tracker % SWAP_LIQUIDITY_CHECK_INTERVAL == 0 {
+                // A failed consolidation is retried at the next check, it must not stop the server.
+                match consolidate_utxos(maker.as_ref()) {
+                    Ok(Some(txid)) => {
+                        log::info!("[{}] Consolidated UTXOs in tx {}", network_port, txid)
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("[{}] UTXO consolidation failed: {:?}", network_port, e),
+                }
                 check_swap_liquidity(maker.as_ref())?;
             }
         }
-        match listener.accept() {
-            Ok((mut stream, _)) => {
+        match accept_connection(&listener, maker.config.tor_only, network_port) {
+            Ok(None) => {}
+            Ok(Some((mut stream, peer_addr))) => {
                 log::info!("[{}] Received incoming connection", network_port);
 
-                if let Err(e) = handle_client(&maker, &mut stream) {
-                    log::error!("[{}] Error Handling client request {:?}", network_port, e);
+                match client_address(maker.config.proxy_protocol, &mut stream, peer_addr) {
+                    Ok(client_addr) if !rate_limiter.allow(client_addr.ip(), Instant::now()) => {
+                        log::warn!(
+                            "[{}] Refusing connection from {}, above {} connections per minute",
+                            network_port,
+                            client_addr.ip(),
+                            maker.config.max_connections_per_minute
+                        );
+                    }
+                    Ok(client_addr) => {
+                        if let Err(e) = handle_client(&maker, &mut stream, client_addr) {
+                            log::error!(
+                                "[{}] Error Handling client request from {}: {:?}",
+                                network_port,
+                                client_addr,
+                                e
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!(
+                            "[{}] Dropping connection from {} without a valid PROXY protocol header: {:?}",
+                            network_port,
+                            peer_addr,
+                            e
+                        );
+                    }
                 }
             }
 
@@ -612,3 +962,134 @@ pub fn start_maker_server(maker: Arc<Maker>) -> Result<(), MakerError> {
     log::info!("Maker Server is shut down successfully");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::UdpSocket,
+    };
+
+    #[test]
+    fn test_tor_only_connection_filter() {
+        let tor_peer: SocketAddr = "127.0.0.1:45678".parse().unwrap();
+        let clearnet_peer: SocketAddr = "203.0.113.7:45678".parse().unwrap();
+
+        assert!(is_connection_allowed(true, &tor_peer));
+        assert!(!is_connection_allowed(true, &clearnet_peer));
+
+        assert!(is_connection_allowed(false, &tor_peer));
+        assert!(is_connection_allowed(false, &clearnet_peer));
+    }
+
+    #[test]
+    fn test_tor_only_listener() {
+        // A non-loopback interface of this machine, the one routing to the outside.
+        // No packet is sent, this only selects the route.
+        let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+        socket.connect("8.8.8.8:80").unwrap();
+        let external_ip = socket.local_addr().unwrap().ip();
+        assert!(!external_ip.is_loopback());
+
+        let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // Connections forwarded by the local Tor daemon come from loopback, and are served.
+        let mut tor_client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let (mut stream, peer_addr) = accept_connection(&listener, true, port).unwrap().unwrap();
+        assert!(peer_addr.ip().is_loopback());
+        stream.write_all(b"ok").unwrap();
+        let mut response = [0u8; 2];
+        tor_client.read_exact(&mut response).unwrap();
+        assert_eq!(&response, b"ok");
+
+        // Clearnet connections are dropped.
+        let mut clearnet_client = TcpStream::connect((external_ip, port)).unwrap();
+        assert!(accept_connection(&listener, true, port).unwrap().is_none());
+        assert_eq!(clearnet_client.read(&mut response).unwrap(), 0);
+
+        // Unless the maker also serves clearnet.
+        let _clearnet_client = TcpStream::connect((external_ip, port)).unwrap();
+        let (_, peer_addr) = accept_connection(&listener, false, port).unwrap().unwrap();
+        assert_eq!(peer_addr.ip(), external_ip);
+    }
+
+    #[test]
+    fn test_proxy_protocol_rate_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let maker_addr = listener.local_addr().unwrap();
+        // A reverse proxy forwarding connections of the given client IPs.
+        let proxy = thread::spawn(move || {
+            ["203.0.113.7", "203.0.113.7", "198.51.100.9"]
+                .iter()
+                .map(|client_ip| {
+                    let mut stream = TcpStream::connect(maker_addr).unwrap();
+                    let header = format!(
+                        "PROXY TCP4 {} {} 45678 {}\r\n",
+                        client_ip,
+                        maker_addr.ip(),
+                        maker_addr.port()
+                    );
+                    stream.write_all(header.as_bytes()).unwrap();
+                    stream.write_all(b"hello").unwrap();
+                    stream
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut rate_limiter = ConnectionRateLimiter::new(1);
+        let now = Instant::now();
+        let mut allowed = Vec::new();
+        for _ in 0..3 {
+            let (mut stream, peer_addr) = listener.accept().unwrap();
+            let client_addr = client_address(true, &mut stream, peer_addr).unwrap();
+            assert!(peer_addr.ip().is_loopback());
+            assert!(!client_addr.ip().is_loopback());
+
+            // Only the header is consumed.
+            let mut message = [0u8; 5];
+            stream.read_exact(&mut message).unwrap();
+            assert_eq!(&message, b"hello");
+
+            allowed.push((client_addr.ip(), rate_limiter.allow(client_addr.ip(), now)));
+        }
+        let _streams = proxy.join().unwrap();
+
+        // The proxy's own address is shared by all, the limit applies to each declared client.
+        let first_client = "203.0.113.7".parse::<IpAddr>().unwrap();
+        let second_client = "198.51.100.9".parse::<IpAddr>().unwrap();
+        assert_eq!(
+            allowed,
+            vec![
+                (first_client, true),
+                (first_client, false),
+                (second_client, true)
+            ]
+        );
+
+        // The window slides, and no limit is set by default.
+        assert!(rate_limiter.allow(first_client, now + Duration::from_secs(60)));
+        let mut no_limit = ConnectionRateLimiter::new(0);
+        assert!((0..100).all(|_| no_limit.allow(first_client, now)));
+    }
+
+    #[test]
+    fn test_client_address_without_proxy_protocol() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client
+            .write_all(b"PROXY TCP4 203.0.113.7 127.0.0.1 45678 6102\r\n")
+            .unwrap();
+        let (mut stream, peer_addr) = listener.accept().unwrap();
+
+        // A direct connection can't spoof its address, the header is left as it is.
+        assert_eq!(
+            client_address(false, &mut stream, peer_addr).unwrap(),
+            peer_addr
+        );
+        let mut start = [0u8; 6];
+        stream.read_exact(&mut start).unwrap();
+        assert_eq!(&start, b"PROXY ");
+    }
+}
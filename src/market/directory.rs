@@ -3,7 +3,11 @@
 //! Handles market-related logic where Makers post their offers. Also provides functions to synchronize
 //! maker addresses from directory servers, post maker addresses to directory servers,
 
-use bitcoin::{transaction::ParseOutPointError, OutPoint};
+use bitcoin::{
+    hashes::{sha256d, Hash},
+    transaction::ParseOutPointError,
+    Network, OutPoint, Txid,
+};
 use bitcoind::bitcoincore_rpc::{self, Client, RpcApi};
 
 use crate::{
@@ -61,6 +65,8 @@ pub enum DirectoryServerError {
     AddressFileCorrupted(String),
     /// Error related to tor
     TorError(TorError),
+    /// Error indicating an invalid configuration, found at startup.
+    InvalidConfig(&'static str),
 }
 
 impl From<TorError> for DirectoryServerError {
@@ -120,18 +126,18 @@ impl From<ParseOutPointError> for DirectoryServerError {
 /// Directory Configuration,
 #[derive(Debug)]
 pub struct DirectoryServer {
-    /// RPC listening port
+    /// RPC listening port, for the directory-cli.
     pub rpc_port: u16,
-    /// Network listening port
-    pub network_port: u16,
+    /// Port the makers and takers connect to.
+    pub listen_port: u16,
     /// Control port
     pub control_port: u16,
     /// Socks port
     pub socks_port: u16,
     /// Authentication password
     pub tor_auth_password: String,
-    /// Connection type
-    pub connection_type: ConnectionType,
+    /// Transport served to the makers and takers, clearnet or Tor.
+    pub transport: ConnectionType,
     /// Directory server data directory
     pub data_dir: PathBuf,
     /// Shutdown flag to stop the directory server
@@ -144,11 +150,11 @@ impl Default for DirectoryServer {
     fn default() -> Self {
         Self {
             rpc_port: 4321,
-            network_port: 8080,
+            listen_port: 8080,
             socks_port: 9050,
             control_port: 9051,
             tor_auth_password: "".to_string(),
-            connection_type: if cfg!(feature = "integration-test") {
+            transport: if cfg!(feature = "integration-test") {
                 ConnectionType::CLEARNET
             } else {
                 ConnectionType::TOR
@@ -171,10 +177,12 @@ impl DirectoryServer {
     ///
     /// Default data-dir for linux: `~/.coinswap/dns`
     /// Default config locations: `~/.coinswap/dns/config.toml`.
+    ///
+    /// A given `transport` overrides the one of the config file, and is written to it.
     #[allow(unused_mut)]
     pub fn new(
         data_dir: Option<PathBuf>,
-        connection_type: Option<ConnectionType>,
+        transport: Option<ConnectionType>,
     ) -> Result<Self, DirectoryServerError> {
         let data_dir = data_dir.unwrap_or(get_dns_dir());
         let config_path = data_dir.join("config.toml");
@@ -195,12 +203,11 @@ impl DirectoryServer {
             config_path.display()
         );
 
-        // Update the connection type in config if given.
-        if let Some(conn_type) = connection_type {
-            // update the config map
-            let value = config_map.get_mut("connection_type").expect("must exist");
-            let conn_type_string = format!("{:?}", conn_type);
-            *value = conn_type_string;
+        // Update the transport in config if given.
+        if let Some(transport) = transport {
+            // update the config map, replacing the older `connection_type` key.
+            config_map.remove("connection_type");
+            config_map.insert("transport".to_string(), format!("{:?}", transport));
 
             // Update the file on disk
             let mut config_file = File::create(config_path)?;
@@ -222,7 +229,11 @@ impl DirectoryServer {
 
         let mut config = DirectoryServer {
             rpc_port: parse_field(config_map.get("rpc_port"), default_dns.rpc_port),
-            network_port: parse_field(config_map.get("network_port"), default_dns.network_port),
+            // Older configs hold a `network_port` and a `connection_type`.
+            listen_port: parse_field(
+                config_map.get("listen_port"),
+                parse_field(config_map.get("network_port"), default_dns.listen_port),
+            ),
             socks_port: parse_field(config_map.get("socks_port"), default_dns.socks_port),
             control_port: parse_field(config_map.get("control_port"), default_dns.control_port),
             tor_auth_password: parse_field(
@@ -231,19 +242,46 @@ impl DirectoryServer {
             ),
             data_dir: data_dir.clone(),
             shutdown: AtomicBool::new(false),
-            connection_type: parse_field(
-                config_map.get("connection_type"),
-                default_dns.connection_type,
+            transport: parse_field(
+                config_map.get("transport"),
+                parse_field(config_map.get("connection_type"), default_dns.transport),
             ),
             addresses,
         };
 
-        if matches!(connection_type, Some(ConnectionType::TOR)) {
+        if matches!(transport, Some(ConnectionType::TOR)) {
             check_tor_status(config.control_port, &config.tor_auth_password)?;
         }
         Ok(config)
     }
 
+    /// Checks the ports of the config, before starting the server.
+    pub fn validate(&self) -> Result<(), DirectoryServerError> {
+        if self.listen_port == 0 {
+            return Err(DirectoryServerError::InvalidConfig(
+                "listen_port must not be 0",
+            ));
+        }
+        if self.rpc_port == 0 {
+            return Err(DirectoryServerError::InvalidConfig(
+                "rpc_port must not be 0",
+            ));
+        }
+        if self.listen_port == self.rpc_port {
+            return Err(DirectoryServerError::InvalidConfig(
+                "listen_port and rpc_port must differ",
+            ));
+        }
+        if self.transport == ConnectionType::TOR
+            && (self.listen_port == self.socks_port || self.listen_port == self.control_port)
+        {
+            return Err(DirectoryServerError::InvalidConfig(
+                "listen_port must differ from the Tor socks and control ports",
+            ));
+        }
+        Ok(())
+    }
+
     /// Updates the in-memory address map. If entry already exists, updates the value. If new entry, inserts the value.
     pub fn updated_address_map(
         &self,
@@ -312,9 +350,9 @@ impl DirectoryServer {
 fn write_default_directory_config(config_path: &Path) -> Result<(), DirectoryServerError> {
     let config_string = String::from(
         "\
-            network_port = 8080\n\
+            listen_port = 8080\n\
             socks_port = 9050\n\
-            connection_type = tor\n\
+            transport = tor\n\
             rpc_port = 4321\n\
             ",
     );
@@ -364,6 +402,8 @@ pub fn start_directory_server(
     directory: Arc<DirectoryServer>,
     rpc_config: Option<RPCConfig>,
 ) -> Result<(), DirectoryServerError> {
+    directory.validate()?;
+
     let rpc_config = rpc_config.unwrap_or_default();
 
     let rpc_client = bitcoincore_rpc::Client::try_from(&rpc_config)?;
@@ -376,18 +416,20 @@ pub fn start_directory_server(
         log::info!("Bitcoin core connection successful");
     }
 
-    match directory.connection_type {
-        ConnectionType::CLEARNET => {}
+    match directory.transport {
+        ConnectionType::CLEARNET => {
+            log::info!("DNS is listening at 127.0.0.1:{}", directory.listen_port);
+        }
         ConnectionType::TOR => {
-            let network_port = directory.network_port;
+            let listen_port = directory.listen_port;
             log::info!("tor is ready!!");
             let hostname = get_tor_hostname(
                 directory.data_dir.clone(),
                 directory.control_port,
-                directory.network_port,
+                directory.listen_port,
                 &directory.tor_auth_password,
             )?;
-            log::info!("DNS is listening at {}:{}", hostname, network_port);
+            log::info!("DNS is listening at {}:{}", hostname, listen_port);
         }
     }
 
@@ -404,7 +446,7 @@ pub fn start_directory_server(
         start_address_writer_thread(directory_clone)
     });
 
-    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, directory.network_port))?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, directory.listen_port))?;
 
     while !directory.shutdown.load(Relaxed) {
         match listener.accept() {
@@ -438,6 +480,14 @@ pub fn start_directory_server(
     Ok(())
 }
 
+/// Derives the address book key of a bondless maker from its url, as it has no bond outpoint.
+fn bondless_outpoint(url: &str) -> OutPoint {
+    OutPoint {
+        txid: Txid::from_raw_hash(sha256d::Hash::hash(url.as_bytes())),
+        vout: u32::MAX,
+    }
+}
+
 // The stream should have read and write timeout set.
 fn handle_client(
     stream: &mut TcpStream,
@@ -450,25 +500,29 @@ fn handle_client(
         DnsRequest::Post { metadata } => {
             log::info!("Received POST | From {}", &metadata.url);
 
-            let txid = metadata.proof.bond.outpoint.txid;
-            let transaction = rpc.get_raw_transaction(&txid, None)?;
-            let current_height = rpc.get_block_count()?;
-
-            match verify_fidelity_checks(
-                &metadata.proof,
-                &metadata.url,
-                transaction,
-                current_height,
-            ) {
-                Ok(_) => {
+            let verified = match &metadata.proof {
+                Some(proof) => {
+                    let transaction = rpc.get_raw_transaction(&proof.bond.outpoint.txid, None)?;
+                    let current_height = rpc.get_block_count()?;
+                    verify_fidelity_checks(proof, &metadata.url, transaction, current_height)
+                        .map(|_| proof.bond.outpoint)
+                        .map_err(|e| format!("Fidelity verification failed {:?}", e))
+                }
+                // Bondless makers are only for development and test networks.
+                None if rpc.get_blockchain_info()?.chain == Network::Bitcoin => {
+                    Err("Bondless makers are not accepted on mainnet".to_string())
+                }
+                None => Ok(bondless_outpoint(&metadata.url)),
+            };
+
+            match verified {
+                Ok(outpoint) => {
                     log::info!(
                         "Fidelity verification success from {}. Adding/updating to address data.",
                         metadata.url
                     );
 
-                    match directory
-                        .updated_address_map((metadata.url.clone(), metadata.proof.bond.outpoint))
-                    {
+                    match directory.updated_address_map((metadata.url.clone(), outpoint)) {
                         Ok(_) => {
                             log::info!("Maker posting request successful from {}", metadata.url);
                             send_message(stream, &DnsResponse::Ack)?;
@@ -487,14 +541,11 @@ fn handle_client(
                 }
                 Err(e) => {
                     log::error!(
-                        "Potentially suspicious maker detected: {:?} | {:?}",
+                        "Potentially suspicious maker detected: {:?} | {}",
                         metadata.url,
                         e
                     );
-                    send_message(
-                        stream,
-                        &DnsResponse::Nack(format!("Fidelity verification failed {:?}", e)),
-                    )?;
+                    send_message(stream, &DnsResponse::Nack(e))?;
                 }
             }
         }
@@ -554,7 +605,7 @@ mod tests {
         let dns = DirectoryServer::new(Some(temp_dir.path().to_path_buf()), None).unwrap();
         let default_dns = DirectoryServer::default();
 
-        assert_eq!(dns.network_port, default_dns.network_port);
+        assert_eq!(dns.listen_port, default_dns.listen_port);
         assert_eq!(dns.socks_port, default_dns.socks_port);
 
         temp_dir.close().unwrap();
@@ -570,7 +621,7 @@ mod tests {
         create_temp_config(contents, &temp_dir);
         let dns = DirectoryServer::new(Some(temp_dir.path().to_path_buf()), None).unwrap();
 
-        assert_eq!(dns.network_port, 8080);
+        assert_eq!(dns.listen_port, 8080);
         assert_eq!(dns.socks_port, DirectoryServer::default().socks_port);
 
         temp_dir.close().unwrap();
@@ -587,7 +638,7 @@ mod tests {
         let dns = DirectoryServer::new(Some(temp_dir.path().to_path_buf()), None).unwrap();
         let default_dns = DirectoryServer::default();
 
-        assert_eq!(dns.network_port, default_dns.network_port);
+        assert_eq!(dns.listen_port, default_dns.listen_port);
         assert_eq!(dns.socks_port, default_dns.socks_port);
 
         temp_dir.close().unwrap();
@@ -599,9 +650,61 @@ mod tests {
         let dns = DirectoryServer::new(Some(temp_dir.path().to_path_buf()), None).unwrap();
         let default_dns = DirectoryServer::default();
 
-        assert_eq!(dns.network_port, default_dns.network_port);
+        assert_eq!(dns.listen_port, default_dns.listen_port);
         assert_eq!(dns.socks_port, default_dns.socks_port);
 
         temp_dir.close().unwrap();
     }
+
+    #[test]
+    fn test_listen_port_and_transport_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let contents = r#"
+            listen_port = 18090
+            rpc_port = 14322
+            transport = CLEARNET
+        "#;
+        create_temp_config(contents, &temp_dir);
+        let dns = DirectoryServer::new(Some(temp_dir.path().to_path_buf()), None).unwrap();
+        assert_eq!(dns.listen_port, 18090);
+        assert_eq!(dns.rpc_port, 14322);
+        assert_eq!(dns.transport, ConnectionType::CLEARNET);
+        assert!(dns.validate().is_ok());
+
+        // Older configs are still read.
+        let contents = r#"
+            network_port = 18091
+            connection_type = CLEARNET
+        "#;
+        create_temp_config(contents, &temp_dir);
+        let dns = DirectoryServer::new(Some(temp_dir.path().to_path_buf()), None).unwrap();
+        assert_eq!(dns.listen_port, 18091);
+        assert_eq!(dns.transport, ConnectionType::CLEARNET);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_validate_ports() {
+        let dns = DirectoryServer {
+            listen_port: 4321,
+            rpc_port: 4321,
+            ..DirectoryServer::default()
+        };
+        assert!(matches!(
+            dns.validate(),
+            Err(DirectoryServerError::InvalidConfig(_))
+        ));
+
+        let dns = DirectoryServer {
+            listen_port: 0,
+            ..DirectoryServer::default()
+        };
+        assert!(matches!(
+            dns.validate(),
+            Err(DirectoryServerError::InvalidConfig(_))
+        ));
+
+        assert!(DirectoryServer::default().validate().is_ok());
+    }
 }
@@ -2,26 +2,30 @@
 //!
 //! This module includes most of the fundamental functions defining the coinswap protocol.
 
-use std::convert::TryInto;
+use std::{convert::TryInto, fmt, str::FromStr};
 
 use bitcoin::{
     absolute::LockTime,
+    block::Header,
     blockdata::{
         opcodes::{self, all},
         script::{Builder, Instruction, Script},
     },
+    consensus::encode::deserialize_hex,
     ecdsa::Signature,
-    hashes::Hash,
-    secp256k1::{
-        rand::{rngs::OsRng, RngCore},
-        Message, Secp256k1, SecretKey,
-    },
+    hashes::{sha256, Hash},
+    secp256k1::{rand::RngCore, Message, Secp256k1, SecretKey},
     sighash::{EcdsaSighashType, SighashCache},
     transaction::Version,
-    Amount, OutPoint, PublicKey, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+    Amount, MerkleBlock, OutPoint, PublicKey, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid,
+    Witness,
 };
 
 pub(crate) use bitcoin::hashes::hash160::Hash as Hash160;
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 
 use crate::utill::redeemscript_to_scriptpubkey;
 
@@ -51,6 +55,123 @@ const PUBKEY_LENGTH: usize = 33;
 const PUBKEY1_OFFSET: usize = 2;
 const PUBKEY2_OFFSET: usize = PUBKEY1_OFFSET + PUBKEY_LENGTH + 1;
 
+/// Hash function of the contract hashlock, negotiated with the makers of a swap.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HashlockVariant {
+    /// `OP_HASH160` of the preimage, as in the original contract.
+    #[default]
+    Hash160,
+    /// `OP_SHA256` of the preimage, as in Lightning HTLCs.
+    Sha256,
+}
+
+impl FromStr for HashlockVariant {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hash160" => Ok(HashlockVariant::Hash160),
+            "sha256" => Ok(HashlockVariant::Sha256),
+            _ => Err("Invalid hashlock variant"),
+        }
+    }
+}
+
+impl fmt::Display for HashlockVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashlockVariant::Hash160 => write!(f, "hash160"),
+            HashlockVariant::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+/// Hash value of a contract hashlock.
+///
+/// Serialized as the bare hash, so the `Hash160` variant is the same on the wire as before the negotiation.
+/// The variant is told apart by the hash length when deserializing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub enum Hashvalue {
+    /// Hash160 of the preimage.
+    Hash160(Hash160),
+    /// SHA256 of the preimage.
+    Sha256(sha256::Hash),
+}
+
+impl Hashvalue {
+    /// Hashes the preimage with the hash function of the variant.
+    pub(crate) fn from_preimage(variant: HashlockVariant, preimage: &[u8]) -> Self {
+        match variant {
+            HashlockVariant::Hash160 => Hashvalue::Hash160(Hash160::hash(preimage)),
+            HashlockVariant::Sha256 => Hashvalue::Sha256(sha256::Hash::hash(preimage)),
+        }
+    }
+
+    /// The hash function of the hashlock.
+    pub fn variant(&self) -> HashlockVariant {
+        match self {
+            Hashvalue::Hash160(_) => HashlockVariant::Hash160,
+            Hashvalue::Sha256(_) => HashlockVariant::Sha256,
+        }
+    }
+
+    /// The raw bytes of the hash.
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        match self {
+            Hashvalue::Hash160(hash) => hash.as_byte_array(),
+            Hashvalue::Sha256(hash) => hash.as_byte_array(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Hashvalue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HashvalueVisitor;
+
+        impl Visitor<'_> for HashvalueVisitor {
+            type Value = Hashvalue;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a 20 or 32 bytes hash")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                match v.len() {
+                    20 => Hash160::from_slice(v).map(Hashvalue::Hash160),
+                    _ => sha256::Hash::from_slice(v).map(Hashvalue::Sha256),
+                }
+                .map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                match v.len() {
+                    40 => Hash160::from_str(v).map(Hashvalue::Hash160),
+                    _ => sha256::Hash::from_str(v).map(Hashvalue::Sha256),
+                }
+                .map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(HashvalueVisitor)
+    }
+}
+
+impl From<Hash160> for Hashvalue {
+    fn from(hash: Hash160) -> Self {
+        Hashvalue::Hash160(hash)
+    }
+}
+
+impl fmt::Display for Hashvalue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hashvalue::Hash160(hash) => write!(f, "{}", hash),
+            Hashvalue::Sha256(hash) => write!(f, "{}", hash),
+        }
+    }
+}
+
 /// Calculate the coin swap fee based on various parameters.
 /// swap_amount in sats, refund_locktime in blocks.
 pub(crate) fn calculate_coinswap_fee(
@@ -92,23 +213,31 @@ pub(crate) fn apply_two_signatures_to_2of2_multisig_spend(
 
 /// Create a multisig redeem script for a 2-of-2 setup.
 pub(crate) fn create_multisig_redeemscript(key1: &PublicKey, key2: &PublicKey) -> ScriptBuf {
-    let builder = Builder::new().push_opcode(all::OP_PUSHNUM_2);
-    (if key1.inner.serialize()[..] < key2.inner.serialize()[..] {
-        builder.push_key(key1).push_key(key2)
+    if key1.inner.serialize()[..] < key2.inner.serialize()[..] {
+        build_multisig_redeemscript(key1, key2)
     } else {
-        builder.push_key(key2).push_key(key1)
-    })
-    .push_opcode(all::OP_PUSHNUM_2)
-    .push_opcode(all::OP_CHECKMULTISIG)
-    .into_script()
+        build_multisig_redeemscript(key2, key1)
+    }
+}
+
+/// Builds a 2-of-2 multisig redeemscript with the pubkeys in the given order.
+fn build_multisig_redeemscript(first: &PublicKey, second: &PublicKey) -> ScriptBuf {
+    Builder::new()
+        .push_opcode(all::OP_PUSHNUM_2)
+        .push_key(first)
+        .push_key(second)
+        .push_opcode(all::OP_PUSHNUM_2)
+        .push_opcode(all::OP_CHECKMULTISIG)
+        .into_script()
 }
 
-/// Derive the maker's public key and nonce from a tweakable point.
-pub(crate) fn derive_maker_pubkey_and_nonce(
+/// Derive the maker's public key and nonce from a tweakable point, drawing the nonce from `rng`.
+pub(crate) fn derive_maker_pubkey_and_nonce<R: RngCore + ?Sized>(
     tweakable_point: &PublicKey,
+    rng: &mut R,
 ) -> Result<(PublicKey, SecretKey), ProtocolError> {
     let mut nonce_bytes = [0u8; 32];
-    OsRng.fill_bytes(&mut nonce_bytes);
+    rng.fill_bytes(&mut nonce_bytes);
     let nonce = SecretKey::from_slice(&nonce_bytes)?;
     let maker_pubkey = calculate_pubkey_from_nonce(tweakable_point, &nonce)?;
     Ok((maker_pubkey, nonce))
@@ -205,7 +334,7 @@ pub(crate) fn check_hashlock_has_pubkey(
 pub(crate) fn create_contract_redeemscript(
     pub_hashlock: &PublicKey,
     pub_timelock: &PublicKey,
-    hashvalue: &Hash160,
+    hashvalue: &Hashvalue,
     locktime: &u16,
 ) -> ScriptBuf {
     //avoid the malleability from OP_IF attack, see:
@@ -230,7 +359,7 @@ pub(crate) fn create_contract_redeemscript(
                              | <sig> <preimage>
     OP_SIZE                  | <sig> <preimage> <size>
     OP_SWAP                  | <sig> <size> <preimage>
-    OP_HASH160               | <sig> <size> <hash>
+    OP_HASH160|OP_SHA256     | <sig> <size> <hash>
     H(X)                     | <sig> <size> <hash> H(X)
     OP_EQUAL                 | <sig> <size> 1|0
     OP_IF                    |
@@ -255,11 +384,18 @@ pub(crate) fn create_contract_redeemscript(
     //timelock case:
     //<timelock_signature> <empty_vector>
 
-    Builder::new()
+    let builder = Builder::new()
         .push_opcode(opcodes::all::OP_SIZE)
-        .push_opcode(opcodes::all::OP_SWAP)
-        .push_opcode(opcodes::all::OP_HASH160)
-        .push_slice(hashvalue.to_byte_array())
+        .push_opcode(opcodes::all::OP_SWAP);
+    let builder = match hashvalue {
+        Hashvalue::Hash160(hash) => builder
+            .push_opcode(opcodes::all::OP_HASH160)
+            .push_slice(hash.to_byte_array()),
+        Hashvalue::Sha256(hash) => builder
+            .push_opcode(opcodes::all::OP_SHA256)
+            .push_slice(hash.to_byte_array()),
+    };
+    builder
         .push_opcode(opcodes::all::OP_EQUAL)
         .push_opcode(opcodes::all::OP_IF)
             .push_key(pub_hashlock)
@@ -281,26 +417,37 @@ pub(crate) fn create_contract_redeemscript(
 /// Read the hash value from a contract redeem script.
 pub(crate) fn read_hashvalue_from_contract(
     redeemscript: &Script,
-) -> Result<Hash160, ProtocolError> {
+) -> Result<Hashvalue, ProtocolError> {
     if redeemscript.to_bytes().len() < MIN_HASHV_LEN {
         return Err(ProtocolError::General("Contract reedemscript too short!"));
     }
     let mut instrs = redeemscript.instructions().skip(2);
-    // Unwrap Safety: length is checked
-    let Instruction::Op(opcodes::all::OP_HASH160) = instrs.next().expect("opcode expected")? else {
+    let Instruction::Op(hash_op) = instrs
+        .next()
+        .ok_or(ProtocolError::General("Hash is not present!"))??
+    else {
         return Err(ProtocolError::General("Hash is not present!"));
     };
-    let Instruction::PushBytes(hash_b) = instrs.next().expect("opcode expected")? else {
+    let Instruction::PushBytes(hash_b) = instrs
+        .next()
+        .ok_or(ProtocolError::General("Invalid script!"))??
+    else {
         return Err(ProtocolError::General("Invalid script!"));
     };
 
-    Ok(Hash160::from_slice(hash_b.as_bytes())?)
+    match hash_op {
+        opcodes::all::OP_HASH160 => Ok(Hashvalue::Hash160(Hash160::from_slice(hash_b.as_bytes())?)),
+        opcodes::all::OP_SHA256 => Ok(Hashvalue::Sha256(sha256::Hash::from_slice(
+            hash_b.as_bytes(),
+        )?)),
+        _ => Err(ProtocolError::General("Hash is not present!")),
+    }
 }
 
 /// Check that all the contract redeemscripts involve the same hashvalue.
 pub(crate) fn check_hashvalues_are_equal(
     message: &ProofOfFunding,
-) -> Result<Hash160, ProtocolError> {
+) -> Result<Hashvalue, ProtocolError> {
     let hashvalues = message
         .confirmed_funding_txes
         .iter()
@@ -316,13 +463,30 @@ pub(crate) fn check_hashvalues_are_equal(
     Ok(hashvalues[0])
 }
 
+/// Check that a contract redeemscript commits to the hashvalue, and the contract tx pays to it.
+pub(crate) fn check_contract_hashlock(
+    contract_tx: &Transaction,
+    contract_redeemscript: &ScriptBuf,
+    hashvalue: &Hashvalue,
+) -> Result<(), ProtocolError> {
+    if read_hashvalue_from_contract(contract_redeemscript)? != *hashvalue {
+        return Err(ProtocolError::HashlockMismatch);
+    }
+    let contract_spk = redeemscript_to_scriptpubkey(contract_redeemscript)?;
+    if contract_tx.output.len() != 1 || contract_tx.output[0].script_pubkey != contract_spk {
+        return Err(ProtocolError::HashlockMismatch);
+    }
+    Ok(())
+}
+
 /// Read the locktime from a contract redeem script.
 pub(crate) fn read_contract_locktime(redeemscript: &Script) -> Result<u16, ProtocolError> {
     match redeemscript
         .instructions()
         .nth(12)
-        .expect("Insctructions expected")?
-    {
+        .ok_or(ProtocolError::General(
+            "Can't read locktime value from contract reedemscript",
+        ))?? {
         Instruction::PushBytes(locktime_bytes) => match locktime_bytes.len() {
             1 => Ok(locktime_bytes[0] as u16),
             2 | 3 => {
@@ -351,35 +515,54 @@ pub(crate) fn read_contract_locktime(redeemscript: &Script) -> Result<u16, Proto
     }
 }
 
-/// Read the hashlock pubkey from a contract redeem script.
-pub(crate) fn read_hashlock_pubkey_from_contract(
+/// Read the pubkey pushed by the instruction at `index` of a contract redeem script.
+///
+/// The pubkeys are read by instruction rather than byte offset, as the hash length depends on the hashlock variant.
+fn read_pubkey_from_contract(
     redeemscript: &Script,
+    index: usize,
 ) -> Result<PublicKey, ProtocolError> {
-    if redeemscript.to_bytes().len() < 61 {
+    // The script goes on after the pubkey, so it must not end right there either.
+    let mut instructions = redeemscript.instructions().skip(index);
+    let (Some(Ok(pubkey)), Some(Ok(_))) = (instructions.next(), instructions.next()) else {
         return Err(ProtocolError::General("contract reedemscript too short"));
+    };
+    match pubkey {
+        Instruction::PushBytes(pubkey_bytes) => Ok(PublicKey::from_slice(pubkey_bytes.as_bytes())?),
+        Instruction::Op(_) => Err(ProtocolError::General(
+            "contract reedemscript pubkey not found",
+        )),
     }
-    Ok(PublicKey::from_slice(&redeemscript.to_bytes()[27..60])?)
+}
+
+/// Read the hashlock pubkey from a contract redeem script.
+pub(crate) fn read_hashlock_pubkey_from_contract(
+    redeemscript: &Script,
+) -> Result<PublicKey, ProtocolError> {
+    read_pubkey_from_contract(redeemscript, 6)
 }
 
 /// Read the timelock pubkey from a contract redeem script.
 pub(crate) fn read_timelock_pubkey_from_contract(
     redeemscript: &Script,
 ) -> Result<PublicKey, ProtocolError> {
-    if redeemscript.to_bytes().len() < 99 {
-        return Err(ProtocolError::General("contract reedemscript too short"));
-    }
-    Ok(PublicKey::from_slice(&redeemscript.to_bytes()[65..98])?)
+    read_pubkey_from_contract(redeemscript, 10)
 }
 
 /// Read the pubkeys from a multisig redeem script.
 pub(crate) fn read_pubkeys_from_multisig_redeemscript(
     redeemscript: &Script,
 ) -> Result<(PublicKey, PublicKey), ProtocolError> {
+    check_reedemscript_is_multisig(redeemscript)?;
     let ms_rs_bytes = redeemscript.to_bytes();
     let pubkey1 =
         PublicKey::from_slice(&ms_rs_bytes[PUBKEY1_OFFSET..PUBKEY1_OFFSET + PUBKEY_LENGTH])?;
     let pubkey2 =
         PublicKey::from_slice(&ms_rs_bytes[PUBKEY2_OFFSET..PUBKEY2_OFFSET + PUBKEY_LENGTH])?;
+    // Both sides sort the pubkeys, so a multisig in any other order can't be one of ours.
+    if create_multisig_redeemscript(&pubkey1, &pubkey2).as_script() != redeemscript {
+        return Err(ProtocolError::MultisigOrderMismatch);
+    }
     Ok((pubkey1, pubkey2))
 }
 
@@ -420,12 +603,122 @@ pub(crate) fn create_receivers_contract_tx(
     create_senders_contract_tx(input, input_value, contract_redeemscript, fee_rate)
 }
 
+/// Fee multipliers, over the base fee of a contract tx, of its pre-signed fee-escalated variants.
+pub(crate) const CONTRACT_FEE_ESCALATION_STEPS: [u64; 3] = [2, 4, 8];
+
+/// Number of blocks an outgoing contract tx is left unconfirmed, before broadcasting its next
+/// fee-escalated variant in its place.
+pub(crate) const CONTRACT_FEE_ESCALATION_BLOCKS: u64 = 2;
+
+/// The fee escalation schedule of contract txs paying `base_fee`.
+///
+/// These are the increasing fees of the variants both sides of a hop pre-sign at setup. A contract tx
+/// stuck under fee pressure is replaced (RBF) by the next variant, so it confirms before the timelock.
+pub(crate) fn contract_fee_escalation_schedule(base_fee: Amount) -> Vec<Amount> {
+    CONTRACT_FEE_ESCALATION_STEPS
+        .iter()
+        .map(|step| base_fee * *step)
+        .collect()
+}
+
+/// Check a proposed fee escalation schedule for a contract tx spending `funding_amount`.
+///
+/// Every fee must be above the previous one, starting from the fee of the contract tx, and leave a
+/// non-dust contract output.
+pub(crate) fn check_fee_escalation_schedule(
+    contract_tx: &Transaction,
+    funding_amount: Amount,
+    schedule: &[Amount],
+) -> Result<(), ProtocolError> {
+    let mut previous_fee = funding_amount
+        .checked_sub(contract_tx.output[0].value)
+        .ok_or(ProtocolError::General(
+            "contract tx spends more than its input",
+        ))?;
+    for &fee in schedule {
+        if fee <= previous_fee {
+            return Err(ProtocolError::InvalidFeeEscalation(fee));
+        }
+        create_fee_escalated_contract_tx(contract_tx, funding_amount, fee)?;
+        previous_fee = fee;
+    }
+    Ok(())
+}
+
+/// Create the variant of a contract tx paying `fee`, by lowering the value of its contract output.
+///
+/// The variant spends the same funding outpoint to the same contract, and replaces the original tx
+/// in the mempool if it pays more.
+pub(crate) fn create_fee_escalated_contract_tx(
+    contract_tx: &Transaction,
+    funding_amount: Amount,
+    fee: Amount,
+) -> Result<Transaction, ProtocolError> {
+    let mut escalated_tx = contract_tx.clone();
+    let contract_output = &mut escalated_tx.output[0];
+    contract_output.value = funding_amount
+        .checked_sub(fee)
+        .filter(|value| *value >= contract_output.script_pubkey.minimal_non_dust())
+        .ok_or(ProtocolError::InvalidFeeEscalation(fee))?;
+    Ok(escalated_tx)
+}
+
+/// Sign the fee-escalated variants of a contract tx, one per fee of the schedule.
+pub(crate) fn sign_fee_escalated_contract_txs(
+    contract_tx: &Transaction,
+    multisig_redeemscript: &Script,
+    funding_amount: Amount,
+    schedule: &[Amount],
+    privkey: &SecretKey,
+) -> Result<Vec<Signature>, ProtocolError> {
+    schedule
+        .iter()
+        .map(|&fee| {
+            sign_contract_tx(
+                &create_fee_escalated_contract_tx(contract_tx, funding_amount, fee)?,
+                multisig_redeemscript,
+                funding_amount,
+                privkey,
+            )
+        })
+        .collect()
+}
+
+/// Verify the signatures on the fee-escalated variants of a contract tx.
+///
+/// A signer may agree to a prefix of the schedule only, so there can be fewer signatures than fees.
+pub(crate) fn verify_fee_escalated_contract_tx_sigs(
+    contract_tx: &Transaction,
+    multisig_redeemscript: &Script,
+    funding_amount: Amount,
+    pubkey: &PublicKey,
+    schedule: &[Amount],
+    sigs: &[Signature],
+) -> Result<(), ProtocolError> {
+    if sigs.len() > schedule.len() {
+        return Err(ProtocolError::WrongNumOfSigs {
+            expected: schedule.len(),
+            received: sigs.len(),
+        });
+    }
+    for (&fee, sig) in schedule.iter().zip(sigs.iter()) {
+        verify_contract_tx_sig(
+            &create_fee_escalated_contract_tx(contract_tx, funding_amount, fee)?,
+            multisig_redeemscript,
+            funding_amount,
+            pubkey,
+            &sig.signature,
+        )?;
+    }
+    Ok(())
+}
+
 /// Check if a contract output is valid.
 pub(crate) fn is_contract_out_valid(
     contract_output: &TxOut,
     hashlock_pubkey: &PublicKey,
     timelock_pubkey: &PublicKey,
-    hashvalue: &Hash160,
+    hashvalue: &Hashvalue,
     locktime: &u16,
     minimum_locktime: &u16,
 ) -> Result<(), ProtocolError> {
@@ -470,6 +763,42 @@ pub(crate) fn validate_contract_tx(
     Ok(())
 }
 
+/// Check that a counterparty's contract transaction is the one built locally.
+///
+/// Both sides of a hop build the contract transactions independently. A disagreement on any
+/// detail, like the miner fee, changes the txid, and the signatures would only fail to validate later.
+pub(crate) fn check_contract_txid(
+    expected_contract_tx: &Transaction,
+    contract_tx: &Transaction,
+) -> Result<(), ProtocolError> {
+    let expected = expected_contract_tx.compute_txid();
+    let found = contract_tx.compute_txid();
+    if expected != found {
+        return Err(ProtocolError::ContractTxidMismatch { expected, found });
+    }
+    Ok(())
+}
+
+/// Check that a hex encoded merkle proof, as returned by `gettxoutproof`, proves the inclusion of `txid`.
+///
+/// Returns the header of the block the tx is included in. The proof only commits to that header, so the
+/// caller still has to check that the block is part of the chain.
+pub(crate) fn verify_merkle_proof(merkleproof: &str, txid: &Txid) -> Result<Header, ProtocolError> {
+    let merkle_block: MerkleBlock =
+        deserialize_hex(merkleproof).map_err(|_| ProtocolError::InvalidMerkleProof(*txid))?;
+
+    let mut matches = Vec::new();
+    let mut indexes = Vec::new();
+    merkle_block
+        .extract_matches(&mut matches, &mut indexes)
+        .map_err(|_| ProtocolError::InvalidMerkleProof(*txid))?;
+    if !matches.contains(txid) {
+        return Err(ProtocolError::InvalidMerkleProof(*txid));
+    }
+
+    Ok(merkle_block.header)
+}
+
 /// Sign a contract transaction.
 pub(crate) fn sign_contract_tx(
     contract_tx: &Transaction,
@@ -515,6 +844,51 @@ pub(crate) fn verify_contract_tx_sig(
     Ok(secp.verify_ecdsa(&sighash, sig, &pubkey.inner)?)
 }
 
+/// Verifies the 2-of-2 multisig witness of a fully signed contract transaction, like the script
+/// interpreter does once the transaction is broadcast.
+///
+/// Both signatures must be valid `SIGHASH_ALL` signatures of the pubkeys of the multisig redeemscript,
+/// in their order. A valid signature alone doesn't make a spendable contract, if e.g. it comes with
+/// another sighash flag.
+pub(crate) fn verify_signed_contract_tx(
+    signed_contract_tx: &Transaction,
+    multisig_redeemscript: &Script,
+    funding_amount: Amount,
+) -> Result<(), ProtocolError> {
+    let invalid = || ProtocolError::InvalidMakerSignature(signed_contract_tx.compute_txid());
+
+    let witness = &signed_contract_tx
+        .input
+        .first()
+        .ok_or_else(invalid)?
+        .witness;
+    if witness.len() != 4
+        || witness.nth(0) != Some(&[][..])
+        || witness.nth(3) != Some(multisig_redeemscript.as_bytes())
+    {
+        return Err(invalid());
+    }
+
+    let (pubkey1, pubkey2) = read_pubkeys_from_multisig_redeemscript(multisig_redeemscript)?;
+    for (index, pubkey) in [(1, pubkey1), (2, pubkey2)].iter() {
+        let sig = witness
+            .nth(*index)
+            .and_then(|sig| Signature::from_slice(sig).ok())
+            .filter(|sig| sig.sighash_type == EcdsaSighashType::All)
+            .ok_or_else(invalid)?;
+        verify_contract_tx_sig(
+            signed_contract_tx,
+            multisig_redeemscript,
+            funding_amount,
+            pubkey,
+            &sig.signature,
+        )
+        .map_err(|_| invalid())?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use crate::protocol::messages::NextHopInfo;
@@ -525,7 +899,7 @@ mod test {
         hashes::hex::FromHex,
         secp256k1::{
             self,
-            rand::{random, thread_rng, Rng},
+            rand::{random, rngs::OsRng, thread_rng, Rng},
         },
         PrivateKey,
     };
@@ -568,7 +942,8 @@ mod test {
         let privkey_org =
             PrivateKey::from_wif("cVt4o7BGAig1UXywgGSmARhxMdzP5qvQsxKkSsc1XEkw3tDTQFpy").unwrap();
         let pubkey_org = privkey_org.public_key(&secp);
-        let (pubkey_derived, nonce) = derive_maker_pubkey_and_nonce(&pubkey_org).unwrap();
+        let (pubkey_derived, nonce) =
+            derive_maker_pubkey_and_nonce(&pubkey_org, &mut OsRng).unwrap();
         let nonce_point = secp256k1::PublicKey::from_secret_key(&secp, &nonce);
         let expected_derivation = PublicKey {
             compressed: true,
@@ -580,7 +955,8 @@ mod test {
     #[test]
     fn test_contract_script_generation() {
         // create a random hashvalue
-        let hashvalue = Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap();
+        let hashvalue =
+            Hashvalue::Hash160(Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap());
 
         let pub_hashlock = PublicKey::from_str(
             "032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af",
@@ -651,6 +1027,49 @@ mod test {
         assert_eq!(fetched_pub2, pub2);
     }
 
+    #[test]
+    fn test_multisig_pubkey_order() {
+        let pub1 = PublicKey::from_str(
+            "032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af",
+        )
+        .unwrap();
+        let pub2 = PublicKey::from_str(
+            "039b6347398505f5ec93826dc61c19f47c66c0283ee9be980e29ce325a0f4679ef",
+        )
+        .unwrap();
+
+        // Canonicalized whatever the order the pubkeys are given in.
+        let multisig = create_multisig_redeemscript(&pub2, &pub1);
+        assert_eq!(multisig, create_multisig_redeemscript(&pub1, &pub2));
+        assert_eq!(
+            read_pubkeys_from_multisig_redeemscript(&multisig).unwrap(),
+            (pub1, pub2)
+        );
+
+        // A counterparty's multisig with the pubkeys reversed is refused clearly.
+        let reversed = build_multisig_redeemscript(&pub2, &pub1);
+        assert!(matches!(
+            read_pubkeys_from_multisig_redeemscript(&reversed),
+            Err(ProtocolError::MultisigOrderMismatch)
+        ));
+        assert!(matches!(
+            check_multisig_has_pubkey(&reversed, &pub1, &SecretKey::from_slice(&[1; 32]).unwrap()),
+            Err(ProtocolError::MultisigOrderMismatch)
+        ));
+
+        // Other scripts aren't read as a multisig, even if long enough.
+        let mut not_multisig = multisig.to_bytes();
+        *not_multisig.last_mut().unwrap() = all::OP_CHECKSIG.to_u8();
+        assert!(matches!(
+            read_pubkeys_from_multisig_redeemscript(&ScriptBuf::from(not_multisig)),
+            Err(ProtocolError::General(_))
+        ));
+        assert!(matches!(
+            read_pubkeys_from_multisig_redeemscript(&ScriptBuf::new()),
+            Err(ProtocolError::General(_))
+        ));
+    }
+
     #[test]
     fn test_find_funding_output() {
         // Create a 20f2 multi + another random spk
@@ -706,6 +1125,51 @@ mod test {
         assert_eq!(1u32, find_funding_output_index(&funding_info).unwrap());
     }
 
+    #[test]
+    fn test_contract_txid_fee_mismatch() {
+        let contract_script = ScriptBuf::from(
+            Vec::from_hex(
+                "827ca91414cdf8fe0b7b2db2bd976f27fb6f3cd5f9228633876321038cc778b555c3fe2b01d1b550a07\
+            d26e38c026c4c4e1dee2a41f0431283230ee0012051672102b6b9ab72d42fb625a24598a792fa5346aa\
+            64d728b446f7560f4ce1c29378b22c00012868b2757b88ac"
+            ).unwrap()
+        );
+        let funding_outpoint = OutPoint::from_str(
+            "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456:42",
+        )
+        .unwrap();
+
+        let build_contract_tx = |fee| {
+            create_receivers_contract_tx(
+                funding_outpoint,
+                Amount::from_sat(30000),
+                &contract_script,
+                Amount::from_sat(fee),
+            )
+            .unwrap()
+        };
+
+        let our_contract_tx = build_contract_tx(1000);
+        assert!(check_contract_txid(&our_contract_tx, &build_contract_tx(1000)).is_ok());
+
+        // The counterparty used a different fee. The tx is otherwise a valid contract tx,
+        // so only the txid check catches it.
+        let their_contract_tx = build_contract_tx(1500);
+        assert!(validate_contract_tx(
+            &their_contract_tx,
+            Some(&funding_outpoint),
+            &contract_script
+        )
+        .is_ok());
+        match check_contract_txid(&our_contract_tx, &their_contract_tx) {
+            Err(ProtocolError::ContractTxidMismatch { expected, found }) => {
+                assert_eq!(expected, our_contract_tx.compute_txid());
+                assert_eq!(found, their_contract_tx.compute_txid());
+            }
+            other => panic!("Expected a contract txid mismatch, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_contract_tx_miscellaneous() {
         let contract_script = ScriptBuf::from(
@@ -912,6 +1376,82 @@ mod test {
         .is_ok());
     }
 
+    #[test]
+    fn test_signed_contract_tx_verification() {
+        let secp = Secp256k1::new();
+        let priv_1 =
+            PrivateKey::from_wif("cVt4o7BGAig1UXywgGSmARhxMdzP5qvQsxKkSsc1XEkw3tDTQFpy").unwrap();
+        let priv_2 =
+            PrivateKey::from_wif("cVbZ8ovhye9AoAHFsqobCf7LxbXDAECy9Kb8TZdfsDYMZGBUyCnm").unwrap();
+        let pub1 = priv_1.public_key(&secp);
+        let pub2 = priv_2.public_key(&secp);
+
+        let multisig_redeemscript = create_multisig_redeemscript(&pub1, &pub2);
+        let funding_amount = Amount::from_sat(2000);
+        let funding_outpoint = OutPoint::from_str(
+            "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456:0",
+        )
+        .unwrap();
+        let contract_script = ScriptBuf::from(
+            Vec::from_hex(
+                "827ca914cdccf6695323f22d061a58c398deba38bba47148876321032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af0120516721039b6347398505f5ec93826dc61c19f47c66c0283ee9be980e29ce325a0f4679ef000812dabb690fe0fd3768b2757b88ac"
+            ).unwrap()
+        );
+        let contract_tx = create_receivers_contract_tx(
+            funding_outpoint,
+            funding_amount,
+            &contract_script,
+            Amount::from_sat(1000),
+        )
+        .unwrap();
+
+        let sign = |privkey: &PrivateKey| {
+            sign_contract_tx(
+                &contract_tx,
+                &multisig_redeemscript,
+                funding_amount,
+                &privkey.inner,
+            )
+            .unwrap()
+        };
+        let (sig1, sig2) = (sign(&priv_1), sign(&priv_2));
+
+        let assemble = |sig1: &Signature, sig2: &Signature| {
+            let mut signed_tx = contract_tx.clone();
+            apply_two_signatures_to_2of2_multisig_spend(
+                &pub1,
+                &pub2,
+                sig1,
+                sig2,
+                &mut signed_tx.input[0],
+                &multisig_redeemscript,
+            );
+            verify_signed_contract_tx(&signed_tx, &multisig_redeemscript, funding_amount)
+        };
+
+        // The assembled contract tx passes script verification.
+        assert!(assemble(&sig1, &sig2).is_ok());
+
+        // A valid signature with another sighash flag, a signature of another key, or an unsigned
+        // contract tx don't.
+        let wrong_flag = Signature {
+            sighash_type: EcdsaSighashType::None,
+            ..sig2
+        };
+        assert!(matches!(
+            assemble(&sig1, &wrong_flag),
+            Err(ProtocolError::InvalidMakerSignature(_))
+        ));
+        assert!(matches!(
+            assemble(&sig1, &sig1),
+            Err(ProtocolError::InvalidMakerSignature(_))
+        ));
+        assert!(matches!(
+            verify_signed_contract_tx(&contract_tx, &multisig_redeemscript, funding_amount),
+            Err(ProtocolError::InvalidMakerSignature(_))
+        ));
+    }
+
     #[test]
     fn test_check_multisig_has_pubkey() {
         let secp = Secp256k1::new();
@@ -924,8 +1464,10 @@ mod test {
         let pubkey_1 = privkey_1.public_key(&secp);
         let pubkey_2 = privkey_2.public_key(&secp);
 
-        let (pubkey_derived_1, nonce_1) = derive_maker_pubkey_and_nonce(&pubkey_1).unwrap();
-        let (pubkey_derived_2, nonce_2) = derive_maker_pubkey_and_nonce(&pubkey_2).unwrap();
+        let (pubkey_derived_1, nonce_1) =
+            derive_maker_pubkey_and_nonce(&pubkey_1, &mut OsRng).unwrap();
+        let (pubkey_derived_2, nonce_2) =
+            derive_maker_pubkey_and_nonce(&pubkey_2, &mut OsRng).unwrap();
 
         let script_value = create_multisig_redeemscript(&pubkey_derived_1, &pubkey_derived_2);
         let script = script_value.as_script();
@@ -944,13 +1486,15 @@ mod test {
 
     #[test]
     fn test_check_hashlock_has_pubkey() {
-        let hash_value = Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap();
+        let hash_value =
+            Hashvalue::Hash160(Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap());
 
         let secp = Secp256k1::new();
         let private_key_1 =
             PrivateKey::from_wif("cVt4o7BGAig1UXywgGSmARhxMdzP5qvQsxKkSsc1XEkw3tDTQFpy").unwrap();
         let public_key_1 = private_key_1.public_key(&secp);
-        let (pub_hashlock, nonce) = derive_maker_pubkey_and_nonce(&public_key_1).unwrap();
+        let (pub_hashlock, nonce) =
+            derive_maker_pubkey_and_nonce(&public_key_1, &mut OsRng).unwrap();
 
         let pub_timelock = PublicKey::from_str(
             "039b6347398505f5ec93826dc61c19f47c66c0283ee9be980e29ce325a0f4679ef",
@@ -982,7 +1526,8 @@ mod test {
 
     #[test]
     fn test_read_hashlock_pubkey_from_contract() {
-        let hashvalue = Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap();
+        let hashvalue =
+            Hashvalue::Hash160(Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap());
 
         let pub_hashlock = PublicKey::from_str(
             "032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af",
@@ -1022,7 +1567,8 @@ mod test {
 
     #[test]
     fn test_read_timelock_pubkey_from_contract() {
-        let hashvalue = Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap();
+        let hashvalue =
+            Hashvalue::Hash160(Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap());
 
         let pub_hashlock = PublicKey::from_str(
             "032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af",
@@ -1260,7 +1806,8 @@ mod test {
             version: Version::TWO,
         };
 
-        let hash_value_1 = Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap();
+        let hash_value_1 =
+            Hashvalue::Hash160(Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap());
 
         let pub_hashlock = PublicKey::from_str(
             "032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af",
@@ -1296,6 +1843,7 @@ mod test {
             refund_locktime: u16::default(),
             contract_feerate: u64::default(),
             id: "random".to_string(),
+            required_confirms: u32::default(),
         };
 
         // case with same hash value
@@ -1303,7 +1851,8 @@ mod test {
         assert_eq!(hash_value_from_fn, hash_value_1);
 
         // case with different hash value
-        let hash_value_2 = Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap();
+        let hash_value_2 =
+            Hashvalue::Hash160(Hash160::from_slice(&thread_rng().gen::<[u8; 20]>()).unwrap());
 
         let contract_script_2 =
             create_contract_redeemscript(&pub_hashlock, &pub_timelock, &hash_value_2, &locktime);
@@ -1326,6 +1875,7 @@ mod test {
             refund_locktime: u16::default(),
             contract_feerate: u64::default(),
             id: "random".to_string(),
+            required_confirms: u32::default(),
         };
 
         let hash_value_from_fn = check_hashvalues_are_equal(&funding_proof).unwrap_err();
@@ -1339,4 +1889,327 @@ mod test {
             "contract reedemscript doesn't have equal hashvalues"
         );
     }
+
+    #[test]
+    fn test_check_contract_hashlock() {
+        let pub_hashlock = PublicKey::from_str(
+            "032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af",
+        )
+        .unwrap();
+        let pub_timelock = PublicKey::from_str(
+            "039b6347398505f5ec93826dc61c19f47c66c0283ee9be980e29ce325a0f4679ef",
+        )
+        .unwrap();
+        let spending_utxo = OutPoint::from_str(
+            "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456:42",
+        )
+        .unwrap();
+
+        let agreed_hashvalue = Hashvalue::Hash160(Hash160::hash(&[1u8; 32]));
+        let wrong_hashvalue = Hashvalue::Hash160(Hash160::hash(&[2u8; 32]));
+        let agreed_contract =
+            create_contract_redeemscript(&pub_hashlock, &pub_timelock, &agreed_hashvalue, &20);
+        let wrong_contract =
+            create_contract_redeemscript(&pub_hashlock, &pub_timelock, &wrong_hashvalue, &20);
+
+        let contract_tx = |contract: &ScriptBuf| {
+            create_receivers_contract_tx(
+                spending_utxo,
+                Amount::from_sat(30000),
+                contract,
+                Amount::from_sat(1000),
+            )
+            .unwrap()
+        };
+
+        assert!(check_contract_hashlock(
+            &contract_tx(&agreed_contract),
+            &agreed_contract,
+            &agreed_hashvalue
+        )
+        .is_ok());
+
+        // The contract commits to another hashvalue.
+        assert!(matches!(
+            check_contract_hashlock(
+                &contract_tx(&wrong_contract),
+                &wrong_contract,
+                &agreed_hashvalue
+            ),
+            Err(ProtocolError::HashlockMismatch)
+        ));
+
+        // The contract tx pays to a contract with a substituted hashlock.
+        assert!(matches!(
+            check_contract_hashlock(
+                &contract_tx(&wrong_contract),
+                &agreed_contract,
+                &agreed_hashvalue
+            ),
+            Err(ProtocolError::HashlockMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_sha256_hashlock_variant() {
+        let pub_hashlock = PublicKey::from_str(
+            "032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af",
+        )
+        .unwrap();
+        let pub_timelock = PublicKey::from_str(
+            "039b6347398505f5ec93826dc61c19f47c66c0283ee9be980e29ce325a0f4679ef",
+        )
+        .unwrap();
+        let preimage = [7u8; 32];
+        let hashvalue = Hashvalue::from_preimage(HashlockVariant::Sha256, &preimage);
+        assert_eq!(hashvalue, Hashvalue::Sha256(sha256::Hash::hash(&preimage)));
+
+        let contract = create_contract_redeemscript(&pub_hashlock, &pub_timelock, &hashvalue, &30);
+        assert!(contract
+            .instructions()
+            .any(|i| i.unwrap() == Instruction::Op(all::OP_SHA256)));
+
+        // Everything is read back from the longer script.
+        assert_eq!(read_hashvalue_from_contract(&contract).unwrap(), hashvalue);
+        assert_eq!(
+            read_hashlock_pubkey_from_contract(&contract).unwrap(),
+            pub_hashlock
+        );
+        assert_eq!(
+            read_timelock_pubkey_from_contract(&contract).unwrap(),
+            pub_timelock
+        );
+        assert_eq!(read_contract_locktime(&contract).unwrap(), 30);
+
+        let contract_tx = create_receivers_contract_tx(
+            OutPoint::from_str(
+                "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456:42",
+            )
+            .unwrap(),
+            Amount::from_sat(30000),
+            &contract,
+            Amount::from_sat(1000),
+        )
+        .unwrap();
+        assert!(check_contract_hashlock(&contract_tx, &contract, &hashvalue).is_ok());
+
+        // The Hash160 of the same preimage doesn't unlock it.
+        let hash160_value = Hashvalue::from_preimage(HashlockVariant::Hash160, &preimage);
+        assert!(matches!(
+            check_contract_hashlock(&contract_tx, &contract, &hash160_value),
+            Err(ProtocolError::HashlockMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_hashvalue_serialization() {
+        // The Hash160 variant is serialized as the bare hash, as before the negotiation.
+        let hash = Hash160::hash(&[1u8; 32]);
+        assert_eq!(
+            serde_cbor::to_vec(&Hashvalue::Hash160(hash)).unwrap(),
+            serde_cbor::to_vec(&hash).unwrap()
+        );
+
+        for variant in [HashlockVariant::Hash160, HashlockVariant::Sha256] {
+            let hashvalue = Hashvalue::from_preimage(variant, &[1u8; 32]);
+            let decoded: Hashvalue =
+                serde_cbor::from_slice(&serde_cbor::to_vec(&hashvalue).unwrap()).unwrap();
+            assert_eq!(decoded, hashvalue);
+            assert_eq!(decoded.variant(), variant);
+        }
+    }
+
+    #[test]
+    fn test_verify_merkle_proof() {
+        use bitcoin::{block, Block, BlockHash, CompactTarget};
+
+        let txdata = (0..5)
+            .map(|i| Transaction {
+                version: Version::TWO,
+                lock_time: LockTime::from_height(i).unwrap(),
+                input: vec![],
+                output: vec![TxOut {
+                    value: Amount::from_sat(1000),
+                    script_pubkey: ScriptBuf::new(),
+                }],
+            })
+            .collect::<Vec<_>>();
+        let mut block = Block {
+            header: block::Header {
+                version: block::Version::TWO,
+                prev_blockhash: BlockHash::all_zeros(),
+                merkle_root: bitcoin::TxMerkleNode::all_zeros(),
+                time: 0,
+                bits: CompactTarget::from_consensus(0x207fffff),
+                nonce: 0,
+            },
+            txdata,
+        };
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+
+        let txid = block.txdata[2].compute_txid();
+        let other_txid = block.txdata[3].compute_txid();
+        let merkleproof = bitcoin::consensus::encode::serialize_hex(
+            &MerkleBlock::from_block_with_predicate(&block, |t| *t == txid),
+        );
+
+        // A valid proof commits to the block header.
+        assert_eq!(
+            verify_merkle_proof(&merkleproof, &txid).unwrap(),
+            block.header
+        );
+
+        // The proof doesn't prove the inclusion of another tx of the block.
+        assert!(matches!(
+            verify_merkle_proof(&merkleproof, &other_txid),
+            Err(ProtocolError::InvalidMerkleProof(t)) if t == other_txid
+        ));
+
+        // Tampering with a hash of the partial merkle tree breaks the merkle root.
+        // The hashes start after the header, the tx count and the hash count.
+        let mut tampered = merkleproof.clone().into_bytes();
+        tampered[170] = if tampered[170] == b'0' { b'1' } else { b'0' };
+        let tampered = String::from_utf8(tampered).unwrap();
+        assert!(matches!(
+            verify_merkle_proof(&tampered, &txid),
+            Err(ProtocolError::InvalidMerkleProof(_))
+        ));
+
+        // Not a merkle proof at all.
+        assert!(matches!(
+            verify_merkle_proof("deadbeef", &txid),
+            Err(ProtocolError::InvalidMerkleProof(_))
+        ));
+    }
+
+    #[test]
+    fn test_fee_escalated_contract_tx() {
+        let secp = Secp256k1::new();
+        let priv_1 =
+            PrivateKey::from_wif("cVt4o7BGAig1UXywgGSmARhxMdzP5qvQsxKkSsc1XEkw3tDTQFpy").unwrap();
+        let priv_2 =
+            PrivateKey::from_wif("5JYkZjmN7PVMjJUfJWfRFwtuXTGB439XV6faajeHPAM9Z2PT2R3").unwrap();
+        let pub1 = priv_1.public_key(&secp);
+        let pub2 = priv_2.public_key(&secp);
+        let multisig_redeemscript = create_multisig_redeemscript(&pub1, &pub2);
+
+        let contract_script = ScriptBuf::from(
+            Vec::from_hex(
+                "827ca914cdccf6695323f22d061a58c398deba38bba47148876321032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af0120516721039b6347398505f5ec93826dc61c19f47c66c0283ee9be980e29ce325a0f4679ef000812dabb690fe0fd3768b2757b88ac"
+            ).unwrap()
+        );
+        let funding_outpoint = OutPoint::from_str(
+            "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456:0",
+        )
+        .unwrap();
+        let funding_amount = Amount::from_sat(100_000);
+        let base_fee = Amount::from_sat(1000);
+        let contract_tx = create_senders_contract_tx(
+            funding_outpoint,
+            funding_amount,
+            &contract_script,
+            base_fee,
+        )
+        .unwrap();
+
+        let schedule = contract_fee_escalation_schedule(base_fee);
+        assert_eq!(schedule, [2000, 4000, 8000].map(Amount::from_sat).to_vec());
+        check_fee_escalation_schedule(&contract_tx, funding_amount, &schedule).unwrap();
+
+        // Every variant pays more to the miners, from the same input to the same contract, and signals RBF.
+        for &fee in &schedule {
+            let variant =
+                create_fee_escalated_contract_tx(&contract_tx, funding_amount, fee).unwrap();
+            assert_eq!(variant.output[0].value, funding_amount - fee);
+            assert_eq!(variant.input, contract_tx.input);
+            assert_eq!(
+                variant.output[0].script_pubkey,
+                contract_tx.output[0].script_pubkey
+            );
+            assert!(variant.is_explicitly_rbf());
+            assert_ne!(variant.compute_txid(), contract_tx.compute_txid());
+        }
+
+        // Both sides sign all the variants, and can fully sign any of them.
+        let sigs_1 = sign_fee_escalated_contract_txs(
+            &contract_tx,
+            &multisig_redeemscript,
+            funding_amount,
+            &schedule,
+            &priv_1.inner,
+        )
+        .unwrap();
+        let sigs_2 = sign_fee_escalated_contract_txs(
+            &contract_tx,
+            &multisig_redeemscript,
+            funding_amount,
+            &schedule,
+            &priv_2.inner,
+        )
+        .unwrap();
+        verify_fee_escalated_contract_tx_sigs(
+            &contract_tx,
+            &multisig_redeemscript,
+            funding_amount,
+            &pub1,
+            &schedule,
+            &sigs_1,
+        )
+        .unwrap();
+
+        let mut last_variant =
+            create_fee_escalated_contract_tx(&contract_tx, funding_amount, schedule[2]).unwrap();
+        apply_two_signatures_to_2of2_multisig_spend(
+            &pub1,
+            &pub2,
+            &sigs_1[2],
+            &sigs_2[2],
+            &mut last_variant.input[0],
+            &multisig_redeemscript,
+        );
+        assert_eq!(last_variant.input[0].witness.len(), 4);
+
+        // A signer may agree to a prefix of the schedule only.
+        verify_fee_escalated_contract_tx_sigs(
+            &contract_tx,
+            &multisig_redeemscript,
+            funding_amount,
+            &pub2,
+            &schedule,
+            &sigs_2[..1],
+        )
+        .unwrap();
+
+        // Signatures of the wrong variant, or the wrong key, are rejected.
+        assert!(verify_fee_escalated_contract_tx_sigs(
+            &contract_tx,
+            &multisig_redeemscript,
+            funding_amount,
+            &pub1,
+            &schedule,
+            &[sigs_1[1], sigs_1[0]],
+        )
+        .is_err());
+        assert!(verify_fee_escalated_contract_tx_sigs(
+            &contract_tx,
+            &multisig_redeemscript,
+            funding_amount,
+            &pub2,
+            &schedule,
+            &sigs_1,
+        )
+        .is_err());
+
+        // Fees must increase, and leave a spendable contract output.
+        for schedule in [
+            vec![Amount::from_sat(1000)],
+            vec![Amount::from_sat(4000), Amount::from_sat(2000)],
+            vec![Amount::from_sat(99_900)],
+        ] {
+            assert!(matches!(
+                check_fee_escalation_schedule(&contract_tx, funding_amount, &schedule),
+                Err(ProtocolError::InvalidFeeEscalation(_))
+            ));
+        }
+    }
 }
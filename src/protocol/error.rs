@@ -1,6 +1,6 @@
 //! All Contract related errors.
 
-use bitcoin::{secp256k1, Amount};
+use bitcoin::{secp256k1, Amount, Txid};
 
 /// Represents errors encountered during protocol operations.
 ///
@@ -57,6 +57,29 @@ pub enum ProtocolError {
     ///
     /// The protocol only supports `V0_Segwit` transactions.
     ScriptPubkey(bitcoin::script::witness_program::Error),
+    /// Error when a contract doesn't commit to the agreed hashlock, or its contract transaction
+    /// doesn't pay to the agreed contract.
+    HashlockMismatch,
+    /// Error when a counterparty's contract transaction differs from the one built locally, e.g.
+    /// because both sides disagree on the contract transaction fee.
+    ContractTxidMismatch {
+        /// The txid of the locally built contract transaction.
+        expected: Txid,
+        /// The txid of the counterparty's contract transaction.
+        found: Txid,
+    },
+    /// Error when a funding tx merkle proof doesn't prove the inclusion of the tx in a block of the
+    /// active chain.
+    InvalidMerkleProof(Txid),
+    /// Error when a fee of a proposed contract fee escalation schedule isn't above the previous one,
+    /// or leaves no spendable contract output.
+    InvalidFeeEscalation(Amount),
+    /// Error when the pubkeys of a 2-of-2 multisig redeemscript aren't in the canonical, lexicographic
+    /// order. Such a multisig never matches the scripts derived by the wallets.
+    MultisigOrderMismatch,
+    /// Error when a fully signed contract transaction, assembled with a counterparty's signature, wouldn't
+    /// pass script verification.
+    InvalidMakerSignature(Txid),
     /// General error not covered by other variants.
     General(&'static str),
 }
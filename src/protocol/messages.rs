@@ -23,6 +23,9 @@
 //! Maker1 -> Taker: [MakerToTakerMessage::RespContractSigsForSender]
 //! Taker -> Maker1: [TakerToMakerMessage::RespProofOfFunding] (Funding Tx of the hop Taker-Maker1)
 //!
+//! A Maker may answer a [TakerToMakerMessage::ReqContractSigsForSender] with [MakerToTakerMessage::RespSwapDeclined]
+//! instead, ex: for lack of capacity. The Taker then tries another Maker for the hop.
+//!
 //! ********* Initiate Second Hop *********
 //! Taker -> Maker1: Share details of next hop. (Sender: Maker1, Receiver: Maker2)
 //! Maker1 -> Taker: [MakerToTakerMessage::ReqContractSigsAsRecvrAndSender]
@@ -58,14 +61,13 @@
 use std::fmt::Display;
 
 use bitcoin::{
-    ecdsa::Signature, hashes::sha256d::Hash, secp256k1::SecretKey, Amount, PublicKey, ScriptBuf,
-    Transaction,
+    ecdsa::Signature, hashes::sha256d::Hash, secp256k1::SecretKey, Amount, OutPoint, PublicKey,
+    ScriptBuf, Transaction,
 };
 
 use serde::{Deserialize, Serialize};
 
-use bitcoin::hashes::hash160::Hash as Hash160;
-
+use super::contract::{HashlockVariant, Hashvalue};
 use crate::wallet::FidelityBond;
 
 /// Defines the length of the Preimage.
@@ -79,12 +81,22 @@ pub(crate) type Preimage = [u8; PREIMAGE_LEN];
 pub(crate) struct TakerHello {
     pub(crate) protocol_version_min: u32,
     pub(crate) protocol_version_max: u32,
+    /// Large messages can be sent zlib compressed on this connection. `false` from takers predating it.
+    #[serde(default)]
+    pub(crate) compression: bool,
 }
 
 /// Represents a request to give an offer.
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct GiveOffer;
 
+/// Challenge to a Maker, to prove control over the key of its advertised fidelity bond.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct IdentityChallenge {
+    pub(crate) bond_outpoint: OutPoint,
+    pub(crate) nonce: [u8; 32],
+}
+
 /// Contract Sigs requesting information for the Sender side of the hop.
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct ContractTxInfoForSender {
@@ -100,8 +112,11 @@ pub(crate) struct ContractTxInfoForSender {
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct ReqContractSigsForSender {
     pub(crate) txs_info: Vec<ContractTxInfoForSender>,
-    pub(crate) hashvalue: Hash160,
+    pub(crate) hashvalue: Hashvalue,
     pub(crate) locktime: u16,
+    /// Fees of the fee-escalated contract tx variants to sign too.
+    #[serde(default)]
+    pub(crate) fee_escalation: Vec<Amount>,
 }
 
 /// Contract Sigs requesting information for the Receiver side of the hop.
@@ -115,6 +130,9 @@ pub(crate) struct ContractTxInfoForRecvr {
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct ReqContractSigsForRecvr {
     pub(crate) txs: Vec<ContractTxInfoForRecvr>,
+    /// Fees of the fee-escalated contract tx variants to sign too.
+    #[serde(default)]
+    pub(crate) fee_escalation: Vec<Amount>,
 }
 
 /// Confirmed Funding Tx with extra metadata.
@@ -145,6 +163,10 @@ pub(crate) struct ProofOfFunding {
     pub(crate) refund_locktime: u16,
     pub(crate) contract_feerate: u64,
     pub(crate) id: String,
+    /// Funding confirmation depth negotiated by the Taker, at least the maker's requirement.
+    /// `0` from takers not negotiating it.
+    #[serde(default)]
+    pub(crate) required_confirms: u32,
 }
 
 /// Signatures required for an intermediate Maker to perform receiving and sending of coinswaps.
@@ -162,6 +184,15 @@ pub(crate) struct ContractSigsForRecvrAndSender {
     pub(crate) senders_sigs: Vec<Signature>,
     /// Unique ID for a swap
     pub(crate) id: String,
+    /// Fees of the fee-escalated contract tx variants, of both hops.
+    #[serde(default)]
+    pub(crate) fee_escalation: Vec<Amount>,
+    /// Sigs from the previous peer for the fee-escalated variants of each `receivers_sigs` Contract Tx.
+    #[serde(default)]
+    pub(crate) receivers_escalated_sigs: Vec<Vec<Signature>>,
+    /// Sigs from the next peer for the fee-escalated variants of each `senders_sigs` Contract Tx.
+    #[serde(default)]
+    pub(crate) senders_escalated_sigs: Vec<Vec<Signature>>,
 }
 
 /// Message to Transfer [`HashPreimage`] from Taker to Makers.
@@ -192,6 +223,8 @@ pub(crate) enum TakerToMakerMessage {
     TakerHello(TakerHello),
     /// Request the Maker's Offer advertisement.
     ReqGiveOffer(GiveOffer),
+    /// Request the Maker to sign an [IdentityChallenge] with its fidelity bond key.
+    ReqIdentityProof(IdentityChallenge),
     /// Request Contract Sigs **for** the Sender side of the hop. The Maker receiving this message is the Receiver of the hop.
     ReqContractSigsForSender(ReqContractSigsForSender),
     /// Respond with the [ProofOfFunding] message. This is sent when the funding transaction gets confirmed.
@@ -205,6 +238,8 @@ pub(crate) enum TakerToMakerMessage {
     /// Respond by handing over the Private Keys of coinswap multisig. This denotes the completion of the whole swap.
     RespPrivKeyHandover(PrivKeyHandover),
     WaitingFundingConfirmation(String),
+    /// Periodic keep-alive for the swap with the given id, sent while waiting for funding confirmations.
+    Heartbeat(String),
 }
 
 impl Display for TakerToMakerMessage {
@@ -212,6 +247,7 @@ impl Display for TakerToMakerMessage {
         match self {
             Self::TakerHello(_) => write!(f, "TakerHello"),
             Self::ReqGiveOffer(_) => write!(f, "ReqGiveOffer"),
+            Self::ReqIdentityProof(_) => write!(f, "ReqIdentityProof"),
             Self::ReqContractSigsForSender(_) => write!(f, "ReqContractSigsForSender"),
             Self::RespProofOfFunding(_) => write!(f, "RespProofOfFunding"),
             Self::RespContractSigsForRecvrAndSender(_) => {
@@ -221,6 +257,7 @@ impl Display for TakerToMakerMessage {
             Self::RespHashPreimage(_) => write!(f, "RespHashPreimage"),
             Self::RespPrivKeyHandover(_) => write!(f, "RespPrivKeyHandover"),
             Self::WaitingFundingConfirmation(_) => write!(f, "WaitingFundingConfirmation"),
+            Self::Heartbeat(_) => write!(f, "Heartbeat"),
         }
     }
 }
@@ -230,6 +267,9 @@ impl Display for TakerToMakerMessage {
 pub(crate) struct MakerHello {
     pub(crate) protocol_version_min: u32,
     pub(crate) protocol_version_max: u32,
+    /// Large messages can be sent zlib compressed on this connection. `false` from makers predating it.
+    #[serde(default)]
+    pub(crate) compression: bool,
 }
 
 /// Contains proof data related to fidelity bond.
@@ -251,13 +291,44 @@ pub(crate) struct Offer {
     pub(crate) max_size: u64,
     pub(crate) min_size: u64,
     pub(crate) tweakable_point: PublicKey,
-    pub(crate) fidelity: FidelityProof,
+    /// `None` for bondless makers, which are only served on non-mainnet networks.
+    pub(crate) fidelity: Option<FidelityProof>,
+    /// The maker rejects funding txs spending non-segwit inputs.
+    #[serde(default)]
+    pub(crate) segwit_only: bool,
+    /// Hashlock variants of the contracts supported by the maker.
+    /// Empty for makers predating the negotiation, which only support Hash160.
+    #[serde(default)]
+    pub(crate) hashlock_variants: Vec<HashlockVariant>,
+    /// Highest number of funding txs the maker accepts in a swap. `0` for no limit, and for makers
+    /// predating it.
+    #[serde(default)]
+    pub(crate) max_tx_count: u32,
+}
+
+impl Offer {
+    /// Whether the maker supports contracts with the given hashlock variant.
+    pub(crate) fn supports_hashlock(&self, variant: HashlockVariant) -> bool {
+        if self.hashlock_variants.is_empty() {
+            variant == HashlockVariant::Hash160
+        } else {
+            self.hashlock_variants.contains(&variant)
+        }
+    }
+
+    /// Whether the maker accepts a swap split into `tx_count` funding txs.
+    pub(crate) fn accepts_tx_count(&self, tx_count: u32) -> bool {
+        self.max_tx_count == 0 || tx_count <= self.max_tx_count
+    }
 }
 
 /// Contract Tx signatures provided by a Sender of a Coinswap.
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct ContractSigsForSender {
     pub(crate) sigs: Vec<Signature>,
+    /// Sigs for the agreed fee-escalated variants of each Contract Tx, in order of the fee escalation.
+    #[serde(default)]
+    pub(crate) escalated_sigs: Vec<Vec<Signature>>,
 }
 
 /// Contract Tx and extra metadata from a Sender of a Coinswap
@@ -285,6 +356,30 @@ pub(crate) struct ContractSigsAsRecvrAndSender {
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct ContractSigsForRecvr {
     pub(crate) sigs: Vec<Signature>,
+    /// Sigs for the agreed fee-escalated variants of each Contract Tx, in order of the fee escalation.
+    #[serde(default)]
+    pub(crate) escalated_sigs: Vec<Vec<Signature>>,
+}
+
+/// Signature over an [IdentityChallenge], made with the Maker's fidelity bond key.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct IdentityProof {
+    pub(crate) sig: bitcoin::secp256k1::ecdsa::Signature,
+}
+
+/// Reason of a Maker declining a swap, through no fault of the Taker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum DeclineReason {
+    /// Accepting the swap would take the Maker's value locked in live contracts above its cap.
+    Capacity,
+}
+
+impl Display for DeclineReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Capacity => write!(f, "capacity"),
+        }
+    }
 }
 
 /// All messages sent from Maker to Taker.
@@ -294,6 +389,8 @@ pub(crate) enum MakerToTakerMessage {
     MakerHello(MakerHello),
     /// Send the Maker's offer advertisement.
     RespOffer(Box<Offer>), // Add box as Offer has large size due to fidelity bond
+    /// Respond to an [IdentityChallenge] with the fidelity bond key signature.
+    RespIdentityProof(IdentityProof),
     /// Send Contract Sigs **for** the Sender side of the hop. The Maker sending this message is the Receiver of the hop.
     RespContractSigsForSender(ContractSigsForSender),
     /// Request Contract Sigs, **as** both the Sending and Receiving side of the hop.
@@ -302,6 +399,10 @@ pub(crate) enum MakerToTakerMessage {
     RespContractSigsForRecvr(ContractSigsForRecvr),
     /// Send the multisig private keys of the swap, declaring completion of the contract.
     RespPrivKeyHandover(PrivKeyHandover),
+    /// Acknowledge a [TakerToMakerMessage::Heartbeat] for the swap with the given id.
+    RespHeartbeat(String),
+    /// Decline a [TakerToMakerMessage::ReqContractSigsForSender], for the given reason.
+    RespSwapDeclined(DeclineReason),
 }
 
 impl Display for MakerToTakerMessage {
@@ -309,6 +410,7 @@ impl Display for MakerToTakerMessage {
         match self {
             Self::MakerHello(_) => write!(f, "MakerHello"),
             Self::RespOffer(_) => write!(f, "RespOffer"),
+            Self::RespIdentityProof(_) => write!(f, "RespIdentityProof"),
             Self::RespContractSigsForSender(_) => write!(f, "RespContractSigsForSender"),
             Self::ReqContractSigsAsRecvrAndSender(_) => {
                 write!(f, "ReqContractSigsAsRecvrAndSender")
@@ -317,6 +419,8 @@ impl Display for MakerToTakerMessage {
                 write!(f, "RespContractSigsForRecvr")
             }
             Self::RespPrivKeyHandover(_) => write!(f, "RespPrivKeyHandover"),
+            Self::RespHeartbeat(_) => write!(f, "RespHeartbeat"),
+            Self::RespSwapDeclined(_) => write!(f, "RespSwapDeclined"),
         }
     }
 }
@@ -345,8 +449,8 @@ impl Display for DnsResponse {
 pub struct DnsMetadata {
     /// The maker's URL.
     pub url: String,
-    /// Proof of the maker's fidelity bond funding.
-    pub proof: FidelityProof,
+    /// Proof of the maker's fidelity bond funding. `None` for bondless makers on non-mainnet networks.
+    pub proof: Option<FidelityProof>,
 }
 
 /// Enum representing DNS request message types.
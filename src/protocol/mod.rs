@@ -3,7 +3,8 @@
 pub(crate) mod contract;
 pub mod error;
 pub mod messages;
+pub(crate) mod trace;
 
-pub(crate) use contract::Hash160;
+pub use contract::{HashlockVariant, Hashvalue};
 
 pub use messages::{DnsMetadata, DnsRequest};
@@ -0,0 +1,320 @@
+//! Opt-in trace of the protocol messages, for troubleshooting.
+//!
+//! With `protocol_trace` set in the Taker or Maker config, every [TakerToMakerMessage] and
+//! [MakerToTakerMessage] sent or received is appended to `protocol_trace.log` in the data directory,
+//! one JSON object per line. Each line carries the timestamp, direction, peer, message variant, its
+//! serialized size and the fields that are safe to share. Preimages, private keys and key nonces are
+//! never written out.
+//!
+//! The messages are traced by exchanging them over a [TracedStream].
+
+use std::{
+    fmt::Display,
+    fs::OpenOptions,
+    io::Write,
+    net::TcpStream,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bitcoin::Transaction;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+use super::messages::{MakerToTakerMessage, PrivKeyHandover, TakerToMakerMessage};
+use crate::{
+    error::NetError,
+    utill::{read_message_negotiated, send_message_negotiated},
+};
+
+/// Name of the protocol trace file, in the data directory.
+pub(crate) const PROTOCOL_TRACE_FILE: &str = "protocol_trace.log";
+
+/// Written in place of the secret fields.
+const REDACTED: &str = "<redacted>";
+
+/// A protocol message, that can be traced without its secrets.
+pub(crate) trait TracedMessage: Serialize + Display {
+    /// The fields of the message that don't leak any secret, with the secret ones redacted.
+    fn trace_fields(&self) -> Value;
+}
+
+/// Append-only trace of the protocol messages, stored as JSON lines. Does nothing when disabled.
+#[derive(Debug, Default)]
+pub(crate) struct ProtocolTrace {
+    path: Option<PathBuf>,
+    // Serializes the appends, so concurrent connections never interleave their lines.
+    lock: Mutex<()>,
+}
+
+impl ProtocolTrace {
+    /// A trace written to `path`, or a disabled one for `None`.
+    pub(crate) fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    // A failure to trace is only logged, so it never interrupts a swap.
+    fn record(&self, direction: &str, socket: &TcpStream, message: &impl TracedMessage) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let line = json!({
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system time is before the unix epoch")
+                .as_millis() as u64,
+            "direction": direction,
+            "peer": socket
+                .peer_addr()
+                .map_or_else(|_| "unknown".to_string(), |addr| addr.to_string()),
+            "message": message.to_string(),
+            "size": serde_cbor::to_vec(message).map_or(0, |bytes| bytes.len()),
+            "fields": message.trace_fields(),
+        });
+
+        let _guard = match self.lock.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = result {
+            log::warn!("Failed to write the protocol trace to {:?}: {}", path, e);
+        }
+    }
+}
+
+/// A connection to a peer, tracing every protocol message sent or received on it.
+pub(crate) struct TracedStream<'a> {
+    stream: &'a mut TcpStream,
+    trace: &'a ProtocolTrace,
+}
+
+impl<'a> TracedStream<'a> {
+    pub(crate) fn new(stream: &'a mut TcpStream, trace: &'a ProtocolTrace) -> Self {
+        Self { stream, trace }
+    }
+
+    /// Sends a message like [send_message_negotiated], and traces it once sent.
+    pub(crate) fn send(
+        &mut self,
+        message: &impl TracedMessage,
+        compression: bool,
+    ) -> Result<(), NetError> {
+        send_message_negotiated(self.stream, message, compression)?;
+        self.trace.record("sent", self.stream, message);
+        Ok(())
+    }
+
+    /// Reads a message like [read_message_negotiated], and traces it once decoded.
+    pub(crate) fn read<M: TracedMessage + DeserializeOwned>(
+        &mut self,
+        compression: bool,
+    ) -> Result<M, NetError> {
+        let message: M =
+            serde_cbor::from_slice(&read_message_negotiated(self.stream, compression)?)?;
+        self.trace.record("received", self.stream, &message);
+        Ok(message)
+    }
+}
+
+fn txids<'a>(txs: impl Iterator<Item = &'a Transaction>) -> Vec<String> {
+    txs.map(|tx| tx.compute_txid().to_string()).collect()
+}
+
+fn privkey_handover_fields(handover: &PrivKeyHandover) -> Value {
+    json!({
+        "privkey_count": handover.multisig_privkeys.len(),
+        "multisig_privkeys": REDACTED,
+    })
+}
+
+impl TracedMessage for TakerToMakerMessage {
+    fn trace_fields(&self) -> Value {
+        match self {
+            Self::TakerHello(hello) => json!({
+                "protocol_version_min": hello.protocol_version_min,
+                "protocol_version_max": hello.protocol_version_max,
+                "compression": hello.compression,
+            }),
+            Self::ReqGiveOffer(_) => json!({}),
+            Self::ReqIdentityProof(challenge) => json!({
+                "bond_outpoint": challenge.bond_outpoint.to_string(),
+            }),
+            Self::ReqContractSigsForSender(req) => json!({
+                "contract_txids": txids(req.txs_info.iter().map(|info| &info.senders_contract_tx)),
+                "locktime": req.locktime,
+                "fee_escalation_steps": req.fee_escalation.len(),
+                "nonces": REDACTED,
+            }),
+            Self::RespProofOfFunding(pof) => json!({
+                "id": pof.id,
+                "funding_txids": txids(
+                    pof.confirmed_funding_txes
+                        .iter()
+                        .map(|info| &info.funding_tx)
+                ),
+                "refund_locktime": pof.refund_locktime,
+                "contract_feerate": pof.contract_feerate,
+                "required_confirms": pof.required_confirms,
+                "nonces": REDACTED,
+            }),
+            Self::RespContractSigsForRecvrAndSender(sigs) => json!({
+                "id": sigs.id,
+                "receivers_sig_count": sigs.receivers_sigs.len(),
+                "senders_sig_count": sigs.senders_sigs.len(),
+                "fee_escalation_steps": sigs.fee_escalation.len(),
+            }),
+            Self::ReqContractSigsForRecvr(req) => json!({
+                "contract_txids": txids(req.txs.iter().map(|info| &info.contract_tx)),
+                "fee_escalation_steps": req.fee_escalation.len(),
+            }),
+            Self::RespHashPreimage(hash_preimage) => json!({
+                "senders_multisig_count": hash_preimage.senders_multisig_redeemscripts.len(),
+                "receivers_multisig_count": hash_preimage.receivers_multisig_redeemscripts.len(),
+                "preimage": REDACTED,
+            }),
+            Self::RespPrivKeyHandover(handover) => privkey_handover_fields(handover),
+            Self::WaitingFundingConfirmation(id) | Self::Heartbeat(id) => json!({ "id": id }),
+        }
+    }
+}
+
+impl TracedMessage for MakerToTakerMessage {
+    fn trace_fields(&self) -> Value {
+        match self {
+            Self::MakerHello(hello) => json!({
+                "protocol_version_min": hello.protocol_version_min,
+                "protocol_version_max": hello.protocol_version_max,
+                "compression": hello.compression,
+            }),
+            Self::RespOffer(offer) => json!({
+                "base_fee": offer.base_fee,
+                "amount_relative_fee_pct": offer.amount_relative_fee_pct,
+                "time_relative_fee_pct": offer.time_relative_fee_pct,
+                "required_confirms": offer.required_confirms,
+                "minimum_locktime": offer.minimum_locktime,
+                "min_size": offer.min_size,
+                "max_size": offer.max_size,
+                "fidelity": offer.fidelity.is_some(),
+            }),
+            Self::RespIdentityProof(_) => json!({}),
+            Self::RespContractSigsForSender(sigs) => json!({
+                "sig_count": sigs.sigs.len(),
+                "fee_escalation_steps": sigs.escalated_sigs.len(),
+            }),
+            Self::ReqContractSigsAsRecvrAndSender(req) => json!({
+                "receivers_contract_txids": txids(req.receivers_contract_txs.iter()),
+                "senders_contract_txids": txids(
+                    req.senders_contract_txs_info
+                        .iter()
+                        .map(|info| &info.contract_tx)
+                ),
+            }),
+            Self::RespContractSigsForRecvr(sigs) => json!({
+                "sig_count": sigs.sigs.len(),
+                "fee_escalation_steps": sigs.escalated_sigs.len(),
+            }),
+            Self::RespPrivKeyHandover(handover) => privkey_handover_fields(handover),
+            Self::RespHeartbeat(id) => json!({ "id": id }),
+            Self::RespSwapDeclined(reason) => json!({ "reason": reason.to_string() }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        protocol::messages::{HashPreimage, MultisigPrivkey},
+        utill::send_message,
+    };
+    use bitcoin::{secp256k1::SecretKey, ScriptBuf};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_protocol_trace_redacts_secrets() {
+        let dir = std::env::temp_dir().join("coinswap_protocol_trace_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(PROTOCOL_TRACE_FILE);
+        let _ = std::fs::remove_file(&path);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut socket = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut peer, _) = listener.accept().unwrap();
+
+        let preimage = [0xab; 32];
+        let key = SecretKey::from_slice(&[0xcd; 32]).unwrap();
+        let preimage_msg = TakerToMakerMessage::RespHashPreimage(HashPreimage {
+            senders_multisig_redeemscripts: vec![ScriptBuf::new()],
+            receivers_multisig_redeemscripts: vec![ScriptBuf::new(), ScriptBuf::new()],
+            preimage,
+        });
+        let handover_msg = MakerToTakerMessage::RespPrivKeyHandover(PrivKeyHandover {
+            multisig_privkeys: vec![MultisigPrivkey {
+                multisig_redeemscript: ScriptBuf::new(),
+                key,
+            }],
+        });
+
+        // Nothing is written when disabled.
+        let disabled = ProtocolTrace::default();
+        TracedStream::new(&mut socket, &disabled)
+            .send(&preimage_msg, false)
+            .unwrap();
+        assert!(!path.exists());
+
+        // The messages exchanged over the stream are traced.
+        let trace = ProtocolTrace::new(Some(path.clone()));
+        let mut traced = TracedStream::new(&mut socket, &trace);
+        traced.send(&preimage_msg, false).unwrap();
+        send_message(&mut peer, &handover_msg).unwrap();
+        let received: MakerToTakerMessage = traced.read(false).unwrap();
+        assert!(matches!(
+            received,
+            MakerToTakerMessage::RespPrivKeyHandover(_)
+        ));
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(
+            &preimage
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        ));
+        assert!(!contents.contains(&key.display_secret().to_string()));
+
+        let lines = contents
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+
+        assert_eq!(lines[0]["direction"], "sent");
+        assert_eq!(lines[0]["message"], "RespHashPreimage");
+        assert_eq!(
+            lines[0]["peer"],
+            listener.local_addr().unwrap().to_string().as_str()
+        );
+        assert_eq!(
+            lines[0]["size"],
+            serde_cbor::to_vec(&preimage_msg).unwrap().len()
+        );
+        assert_eq!(lines[0]["fields"]["receivers_multisig_count"], 2);
+        assert_eq!(lines[0]["fields"]["preimage"], REDACTED);
+
+        assert_eq!(lines[1]["direction"], "received");
+        assert_eq!(lines[1]["message"], "RespPrivKeyHandover");
+        assert_eq!(lines[1]["fields"]["privkey_count"], 1);
+        assert_eq!(lines[1]["fields"]["multisig_privkeys"], REDACTED);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -10,56 +10,92 @@
 
 use std::{
     collections::{HashMap, HashSet},
+    convert::TryFrom,
+    fmt, fs,
     io::BufWriter,
-    net::TcpStream,
-    path::PathBuf,
-    thread::sleep,
-    time::{Duration, Instant},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        mpsc, Arc, RwLock,
+    },
+    thread::{sleep, Builder},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use bitcoind::bitcoincore_rpc::RpcApi;
 
-use socks::Socks5Stream;
-
 use bitcoin::{
     consensus::encode::deserialize,
-    hashes::{hash160::Hash as Hash160, Hash},
     hex::{Case, DisplayHex},
     secp256k1::{
-        rand::{rngs::OsRng, RngCore},
+        rand::{rngs::OsRng, Rng, RngCore},
         SecretKey,
     },
-    Amount, BlockHash, OutPoint, PublicKey, ScriptBuf, Transaction, Txid,
+    Amount, BlockHash, Network, OutPoint, PublicKey, ScriptBuf, Transaction, Txid,
 };
 
 use super::{
     error::TakerError,
-    offers::{fetch_addresses_from_dns, fetch_offer_from_makers, MakerAddress, OfferAndAddress},
+    fiat::{display_amount, NoPriceSource, PriceSource, SwapAmount},
+    history::{append_swap_record, read_swap_history, HopRecord, SwapRecord, SWAP_HISTORY_FILE},
+    offers::{
+        fetch_addresses_from_directories, fetch_offer_from_makers, read_blacklist, BadMaker,
+        BanReason, MakerAddress, OfferAndAddress,
+    },
+    recovery_bundle::{
+        read_recovery_bundle, write_recovery_bundle, PrivateSwapcoins, RecoveryBundle,
+        SwapSnapshot, SwapcoinSnapshot,
+    },
     routines::*,
 };
 use crate::{
+    maker::OfferFees,
     protocol::{
+        contract::{
+            calculate_coinswap_fee, check_contract_hashlock, contract_fee_escalation_schedule,
+            create_contract_redeemscript, create_fee_escalated_contract_tx, read_contract_locktime,
+            read_hashlock_pubkey_from_contract, read_hashvalue_from_contract,
+            read_pubkeys_from_multisig_redeemscript, read_timelock_pubkey_from_contract,
+            sign_fee_escalated_contract_txs, verify_signed_contract_tx,
+            CONTRACT_FEE_ESCALATION_BLOCKS,
+        },
         error::ProtocolError,
         messages::{
             ContractSigsAsRecvrAndSender, ContractSigsForRecvr, ContractSigsForRecvrAndSender,
-            ContractSigsForSender, FundingTxInfo, MultisigPrivkey, Preimage, PrivKeyHandover,
-            TakerToMakerMessage,
+            ContractSigsForSender, FundingTxInfo, IdentityChallenge, MakerToTakerMessage,
+            MultisigPrivkey, Preimage, PrivKeyHandover, TakerToMakerMessage,
         },
+        trace::{ProtocolTrace, TracedStream, PROTOCOL_TRACE_FILE},
+        Hashvalue,
+    },
+    taker::{
+        config::{FirstHopOrdering, MakerSelection, TakerConfig},
+        offers::OfferBook,
     },
-    taker::{config::TakerConfig, offers::OfferBook},
     utill::*,
     wallet::{
-        IncomingSwapCoin, OutgoingSwapCoin, RPCConfig, SwapCoin, Wallet, WalletError,
-        WalletSwapCoin, WatchOnlySwapCoin,
+        funding_input_sequence, verify_identity_proof, Destination, IncomingSwapCoin,
+        OutgoingSwapCoin, PreparedCoinswap, RPCConfig, SwapCoin, UTXOSpendInfo, Wallet,
+        WalletError, WalletSwapCoin, WatchOnlySwapCoin,
     },
 };
 
 // Default values for Taker configurations
 pub(crate) const REFUND_LOCKTIME: u16 = 20;
 pub(crate) const REFUND_LOCKTIME_STEP: u16 = 20;
+/// Default maximum number of makers in a swap route.
+pub const MAX_MAKER_COUNT: usize = 10;
+/// Default minimum number of makers in a mainnet swap route, when the safe-mode is on.
+pub const SAFE_MODE_MIN_MAKER_COUNT: usize = 3;
 pub(crate) const FIRST_CONNECT_ATTEMPTS: u32 = 5;
 pub(crate) const FIRST_CONNECT_SLEEP_DELAY_SEC: u64 = 1;
 pub(crate) const FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC: u64 = 30;
+// Default number of candidate makers raced for the first hop.
+pub(crate) const FIRST_HOP_RACE_WIDTH: usize = 3;
+// Default number of random bytes of a swap id, as many as a UUID.
+pub(crate) const SWAP_ID_LEN: usize = 16;
+// Fewest random bytes of a swap id, whatever the config.
+const MIN_SWAP_ID_LEN: usize = 8;
 
 // Tries reconnection by variable delay.
 // First 10 attempts at 1 sec interval.
@@ -70,6 +106,15 @@ pub(crate) const RECONNECT_SHORT_SLEEP_DELAY: u64 = 1;
 pub(crate) const RECONNECT_LONG_SLEEP_DELAY: u64 = 5;
 pub(crate) const SHORT_LONG_SLEEP_DELAY_TRANSITION: u32 = 30;
 pub(crate) const TCP_TIMEOUT_SECONDS: u64 = 300;
+// Default interval in seconds between the checks of the recovery scheduler for unfinished swapcoins.
+pub(crate) const RECOVERY_INTERVAL: u64 = 600;
+// Number of times the first hop funding is re-built at a higher fee, if the node rejects it as too low.
+pub(crate) const FUNDING_FEE_RETRIES: u32 = 3;
+// Number of times the first hop funding coins are re-selected, if the selected ones are already spent.
+pub(crate) const FUNDING_CONFLICT_RETRIES: u32 = 3;
+/// Maximum share of a received swap coin's value, in percent, spent on the fee of moving it to a
+/// fresh address. Coins too small for this are left in place.
+pub const FRESHEN_MAX_FEE_PCT: u64 = 10;
 // TODO: Maker should decide this miner fee
 // This fee is used for both funding and contract txs.
 #[cfg(feature = "integration-test")]
@@ -79,11 +124,123 @@ pub(crate) const MINER_FEE: u64 = 1000;
 #[cfg(not(feature = "integration-test"))]
 pub(crate) const MINER_FEE: u64 = 300; // around 2 sats/vb for funding tx
 
+/// Fees of the fee-escalated contract tx variants, the Taker proposes for every hop.
+pub(crate) fn contract_fee_escalation() -> Vec<Amount> {
+    contract_fee_escalation_schedule(Amount::from_sat(MINER_FEE))
+}
+
+// Parameters for swap duration estimates.
+const EXPECTED_BLOCK_INTERVAL_SEC: u64 = 600;
+// Protocol message round trips between the Taker and each Maker in the route.
+const ROUND_TRIPS_PER_HOP: u64 = 6;
+// Block intervals waited per confirmation of the received swapcoins, when no swap deadline is set.
+const SETTLEMENT_CONFIRM_BLOCK_INTERVALS: u64 = 6;
+// Best, typical and worst round trip times in milliseconds, per connection type.
+const CLEARNET_ROUND_TRIP_MS: (u64, u64, u64) = (100, 500, 5_000);
+const TOR_ROUND_TRIP_MS: (u64, u64, u64) = (1_000, 3_000, 30_000);
+
+/// Estimated wall clock time range for a swap round to complete.
+///
+/// This is informational only. The actual time depends on block arrival and network latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapDurationEstimate {
+    /// Blocks arrive fast and all peers respond promptly.
+    pub best: Duration,
+    /// Blocks arrive at the expected interval.
+    pub typical: Duration,
+    /// Blocks arrive slowly and peers respond near the timeouts.
+    pub worst: Duration,
+}
+
+/// A maker of the route previewed by [Taker::preview_route].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MakerPreview {
+    /// Position in the route. The maker at `0` receives the Taker's funding txs, the last one sends the
+    /// swapped coins back to the Taker.
+    pub position: usize,
+    /// Address of the maker.
+    pub address: String,
+    /// Fees of the maker's offer.
+    pub fees: OfferFees,
+    /// Value of the maker's fidelity bond.
+    pub bond_value: Amount,
+    /// Refund locktime in blocks of the contracts received by the maker.
+    pub incoming_locktime: u16,
+    /// Refund locktime in blocks of the contracts sent by the maker to the next peer.
+    pub outgoing_locktime: u16,
+}
+
+/// Human readable decomposition of a contract redeemscript, as returned by [Taker::contract_transaction_explainer].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContractExplanation {
+    /// Pubkey which can spend the contract with the hash preimage.
+    pub hashlock_pubkey: PublicKey,
+    /// Pubkey which can spend the contract after the locktime.
+    pub timelock_pubkey: PublicKey,
+    /// Hash of the preimage, which unlocks the hashlock branch.
+    pub hashvalue: Hashvalue,
+    /// Relative locktime (OP_CSV) of the timelock branch, in blocks after the contract tx confirms.
+    pub locktime: u16,
+}
+
+impl fmt::Display for ContractExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Hashlock branch: spendable by {} with the preimage of {} {}, 1 block after the contract tx confirms",
+            self.hashlock_pubkey,
+            self.hashvalue.variant(),
+            self.hashvalue
+        )?;
+        write!(
+            f,
+            "Timelock branch: spendable by {} {} blocks after the contract tx confirms (relative locktime)",
+            self.timelock_pubkey, self.locktime
+        )
+    }
+}
+
+/// The phase of the Taker's current swap round, as reported by [Taker::get_swap_status].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SwapPhase {
+    /// No swap is in progress.
+    #[default]
+    Idle,
+    /// Fetching and verifying offers from the makers.
+    SyncingOffers,
+    /// Negotiating with the first maker, and broadcasting the Taker's funding txs.
+    FundingFirstHop,
+    /// Waiting for the funding txs of the current hop to confirm.
+    AwaitingConfirmations,
+    /// Exchanging contract signatures with the maker at the given position in the route.
+    ExchangingSigs(usize),
+    /// Exchanging preimage and private keys to settle the swap.
+    Settling,
+    /// Recovering funds from a failed swap via the contract txs.
+    Recovering,
+}
+
+/// A cloneable handle to read the [SwapPhase] of a [Taker] from other threads, while the swap is running.
+#[derive(Debug, Clone, Default)]
+pub struct SwapStatusHandle(Arc<RwLock<SwapPhase>>);
+
+impl SwapStatusHandle {
+    /// Get the current [SwapPhase].
+    pub fn get(&self) -> SwapPhase {
+        *self.0.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set(&self, phase: SwapPhase) {
+        log::info!("Swap phase: {:?}", phase);
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = phase;
+    }
+}
+
 /// Swap specific parameters. These are user's policy and can differ among swaps.
 /// SwapParams govern the criteria to find suitable set of makers from the offerbook.
 ///
 /// If no maker matches with a given SwapParam, that coinswap round will fail.
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 pub struct SwapParams {
     /// Total Amount to Swap.
     pub send_amount: Amount,
@@ -94,6 +251,16 @@ pub struct SwapParams {
     // TODO: Following two should be moved to TakerConfig as global configuration.
     /// Confirmation count required for funding txs.
     pub required_confirms: u32,
+    /// Optional label attached to the received swapcoins, for accounting.
+    pub swap_label: Option<String>,
+    /// Optional time limit for the swap, from its start. If it passes before settlement, the swap
+    /// is aborted into recovery with [TakerError::DeadlineExceeded].
+    pub deadline: Option<Duration>,
+    /// Optional confirmation count of the received swapcoins. If set, the swap only returns once the
+    /// funding txs of the incoming swapcoins are this deep in the chain, or fails with
+    /// [TakerError::SettlementConfirmsTimeOut] if they aren't by the `deadline`. Without a deadline,
+    /// the wait is bounded to 6 expected block intervals per confirmation.
+    pub wait_for_settlement_confirms: Option<u32>,
 }
 
 // Defines the Taker's position in the current ongoing swap.
@@ -134,6 +301,9 @@ struct OngoingSwapState {
     pub(crate) taker_position: TakerPosition,
     /// Unique ID for a swap
     pub(crate) id: String,
+    /// Makers which declined the swap through no fault of ours, e.g. for lack of capacity. They aren't
+    /// banned, only skipped for the rest of the swap.
+    pub(crate) declined_makers: Vec<OfferAndAddress>,
 }
 
 /// Information for the next maker in the hop.
@@ -146,6 +316,15 @@ struct NextPeerInfo {
     contract_reedemscripts: Vec<ScriptBuf>,
 }
 
+/// A first hop swap set up with a candidate maker, not committed to the wallet yet.
+struct FirstHopSetup {
+    maker: OfferAndAddress,
+    multisig_pubkeys: Vec<PublicKey>,
+    multisig_nonces: Vec<SecretKey>,
+    hashlock_nonces: Vec<SecretKey>,
+    funding: PreparedCoinswap,
+}
+
 /// Enum representing different behaviors of the Taker in a coinswap protocol.
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 pub enum TakerBehavior {
@@ -155,6 +334,8 @@ pub enum TakerBehavior {
     DropConnectionAfterFullSetup,
     /// Behavior to broadcast the contract after the full coinswap setup.
     BroadcastContractAfterFullSetup,
+    /// Propose the highest possible refund locktime to the makers.
+    ExcessiveRefundLocktime,
 }
 
 /// The Taker structure that performs bulk of the coinswap protocol. Taker connects
@@ -169,6 +350,10 @@ pub struct Taker {
     ongoing_swap_state: OngoingSwapState,
     behavior: TakerBehavior,
     data_dir: PathBuf,
+    swap_status: SwapStatusHandle,
+    price_source: Box<dyn PriceSource>,
+    rng: Box<dyn RngCore + Send + Sync>,
+    protocol_trace: Arc<ProtocolTrace>,
 }
 
 impl Drop for Taker {
@@ -219,6 +404,9 @@ impl Taker {
         let mut rpc_config = rpc_config.unwrap_or_default();
         rpc_config.wallet_name = wallet_file_name;
 
+        // If config file doesn't exist, default config will be loaded.
+        let mut config = TakerConfig::new(Some(&data_dir.join("config.toml")))?;
+
         let mut wallet = if wallet_path.exists() {
             // wallet already exists , load the wallet
             let wallet = Wallet::load(&wallet_path, &rpc_config)?;
@@ -226,14 +414,11 @@ impl Taker {
             wallet
         } else {
             // wallet doesn't exists at the given path , create a new one
-            let wallet = Wallet::init(&wallet_path, &rpc_config)?;
+            let wallet = Wallet::init(&wallet_path, &rpc_config, config.address_strategy)?;
             log::info!("New Wallet created at : {:?}", wallet_path);
             wallet
         };
 
-        // If config file doesn't exist, default config will be loaded.
-        let mut config = TakerConfig::new(Some(&data_dir.join("config.toml")))?;
-
         if let Some(connection_type) = connection_type {
             config.connection_type = connection_type;
         }
@@ -250,8 +435,16 @@ impl Taker {
             check_tor_status(config.control_port, config.tor_auth_password.as_str())?;
         }
 
+        check_bondless_offers(wallet.store.network, &config)?;
+
         config.write_to_file(&data_dir.join("config.toml"))?;
 
+        wallet.set_anti_fee_sniping(config.anti_fee_sniping);
+        wallet.set_tx_ordering(config.tx_ordering);
+        wallet.set_allow_key_export(config.allow_key_export);
+        wallet.set_reserve_amount(Amount::from_sat(config.reserve_amount));
+        wallet.set_min_fee_rate(config.min_fee_rate);
+
         // Load offerbook. If doesn't exists, creates fresh file.
         let offerbook_path = data_dir.join("offerbook.dat");
         let offerbook = if offerbook_path.exists() {
@@ -281,6 +474,12 @@ impl Taker {
         wallet.sync()?;
         log::info!("Completed wallet sync");
 
+        let protocol_trace = Arc::new(ProtocolTrace::new(
+            config
+                .protocol_trace
+                .then(|| data_dir.join(PROTOCOL_TRACE_FILE)),
+        ));
+
         Ok(Self {
             wallet,
             config,
@@ -288,6 +487,10 @@ impl Taker {
             ongoing_swap_state: OngoingSwapState::default(),
             behavior,
             data_dir,
+            swap_status: SwapStatusHandle::default(),
+            price_source: Box::new(NoPriceSource),
+            rng: Box::new(OsRng),
+            protocol_trace,
         })
     }
 
@@ -301,9 +504,176 @@ impl Taker {
         &mut self.wallet
     }
 
+    /// Previews the route of a swap with the given [SwapParams], without committing any funds.
+    ///
+    /// The offerbook is synced and the makers are selected as by [Taker::do_coinswap], in route order.
+    /// A swap with the same params then takes the same route, unless the offers change or a maker fails
+    /// to respond and is replaced.
+    pub fn preview_route(
+        &mut self,
+        swap_params: &SwapParams,
+    ) -> Result<Vec<MakerPreview>, TakerError> {
+        self.check_route_length(swap_params)?;
+        self.sync_offerbook_for_swap(swap_params)?;
+
+        let mut route = Vec::new();
+        for _ in 0..swap_params.maker_count {
+            let maker = self.select_maker(swap_params, &route)?;
+            route.push(maker);
+        }
+
+        route
+            .into_iter()
+            .enumerate()
+            .map(|(position, maker)| {
+                let hops_after = swap_params.maker_count - position;
+                Ok(MakerPreview {
+                    position,
+                    address: maker.address.to_string(),
+                    fees: maker.fees(),
+                    bond_value: match &maker.offer.fidelity {
+                        Some(fidelity) => self.wallet.calculate_bond_value(&fidelity.bond)?,
+                        None => Amount::ZERO,
+                    },
+                    incoming_locktime: refund_locktime(hops_after)?,
+                    outgoing_locktime: refund_locktime(hops_after - 1)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Estimates how long a swap with the given [SwapParams] will take.
+    ///
+    /// Every hop waits for its funding txs to confirm, so the estimate sums the confirmation waits
+    /// over `maker_count + 1` hops, using the highest confirmation requirement among the swap params
+    /// and the good makers in the offerbook. Message round trips with each maker are added on top.
+    pub fn estimate_swap_duration(&self, swap_params: &SwapParams) -> SwapDurationEstimate {
+        let required_confirms = self
+            .offerbook
+            .all_good_makers()
+            .iter()
+            .map(|oa| oa.offer.required_confirms)
+            .chain(std::iter::once(swap_params.required_confirms))
+            .max()
+            .unwrap_or_default();
+
+        estimate_duration(
+            required_confirms,
+            swap_params.maker_count,
+            self.config.connection_type,
+        )
+    }
+
+    /// Get the [SwapPhase] of the current swap round.
+    pub fn get_swap_status(&self) -> SwapPhase {
+        self.swap_status.get()
+    }
+
+    /// Decodes a contract redeemscript, e.g. of a swapcoin, into its keys, hashvalue and locktime.
+    ///
+    /// Errors if the script isn't exactly a coinswap contract, so the explanation covers everything it does.
+    pub fn contract_transaction_explainer(
+        contract_redeemscript: &ScriptBuf,
+    ) -> Result<ContractExplanation, TakerError> {
+        let explanation = ContractExplanation {
+            hashlock_pubkey: read_hashlock_pubkey_from_contract(contract_redeemscript)?,
+            timelock_pubkey: read_timelock_pubkey_from_contract(contract_redeemscript)?,
+            hashvalue: read_hashvalue_from_contract(contract_redeemscript)?,
+            locktime: read_contract_locktime(contract_redeemscript)?,
+        };
+        let rebuilt = create_contract_redeemscript(
+            &explanation.hashlock_pubkey,
+            &explanation.timelock_pubkey,
+            &explanation.hashvalue,
+            &explanation.locktime,
+        );
+        if &rebuilt != contract_redeemscript {
+            return Err(ProtocolError::General("Not a coinswap contract redeemscript").into());
+        }
+        Ok(explanation)
+    }
+
+    /// Get a [SwapStatusHandle], to poll the [SwapPhase] from another thread while a swap is running.
+    pub fn swap_status_handle(&self) -> SwapStatusHandle {
+        self.swap_status.clone()
+    }
+
+    /// Set the [PriceSource] used to convert fiat amounts. By default no prices are known.
+    pub fn set_price_source(&mut self, price_source: Box<dyn PriceSource>) {
+        self.price_source = price_source;
+    }
+
+    /// Replace the RNG used for swap preimages, swap ids and maker nonces, which is [OsRng] by default.
+    ///
+    /// Only for reproducing swaps in tests and debugging. A predictable RNG leaks the swap secrets,
+    /// so this is never available in release builds.
+    #[cfg(any(test, feature = "integration-test"))]
+    pub fn set_rng(&mut self, rng: Box<dyn RngCore + Send + Sync>) {
+        log::warn!("Using a custom RNG for swap secrets. Only do this for testing!");
+        self.rng = rng;
+    }
+
+    /// Converts a user given [SwapAmount] into sats, with the configured [PriceSource].
+    pub fn resolve_amount(&self, amount: &SwapAmount) -> Result<Amount, TakerError> {
+        let sats = amount.to_amount(self.price_source.as_ref())?;
+        log::info!("Resolved amount {} to {} sats", amount, sats.to_sat());
+        Ok(sats)
+    }
+
+    /// Formats an amount in sats, along with its value in the fiat currency if its price is known.
+    pub fn display_amount(&self, amount: Amount, currency: &str) -> String {
+        display_amount(amount, currency, self.price_source.as_ref())
+    }
+
     ///  Does the coinswap process
     pub fn do_coinswap(&mut self, swap_params: SwapParams) -> Result<(), TakerError> {
-        self.send_coinswap(swap_params)
+        let result = self.send_coinswap(swap_params);
+        self.swap_status.set(SwapPhase::Idle);
+        result
+    }
+
+    /// Swaps `total` in several independent rounds of about `chunk` each, so that a large amount
+    /// doesn't stand out on chain.
+    ///
+    /// The total is split evenly into as few rounds as needed for none of them to exceed `chunk`, so
+    /// there is no small leftover round. Each round is a full swap, with a fresh preimage, and makers
+    /// re-selected from a fresh offerbook sync. The `send_amount` of the [SwapParams] is ignored.
+    ///
+    /// Returns the history records of the rounds, in order. Stops at the first failed round, with the
+    /// rounds before it completed. A round is failed unless it adds exactly one record to the swap
+    /// history, as a swap recovered from a failure returns without error but isn't recorded.
+    pub fn do_coinswap_chunked(
+        &mut self,
+        total: Amount,
+        chunk: Amount,
+        swap_params: SwapParams,
+    ) -> Result<Vec<SwapRecord>, TakerError> {
+        let chunks = split_into_chunks(total, chunk)?;
+        let rounds_before = self.get_swap_history()?.len();
+
+        for (round, send_amount) in chunks.iter().enumerate() {
+            log::info!(
+                "Starting swap round {} of {}, with {}",
+                round + 1,
+                chunks.len(),
+                send_amount
+            );
+            let records_before = self.get_swap_history()?.len();
+            self.do_coinswap(SwapParams {
+                send_amount: *send_amount,
+                ..swap_params.clone()
+            })?;
+            if self.get_swap_history()?.len() != records_before + 1 {
+                log::error!(
+                    "Swap round {} of {} didn't complete, stopping",
+                    round + 1,
+                    chunks.len()
+                );
+                return Err(TakerError::SwapRoundFailed { round: round + 1 });
+            }
+        }
+
+        Ok(self.get_swap_history()?.split_off(rounds_before))
     }
 
     /// Perform a coinswap round with given [SwapParams]. The Taker will try to perform swap with makers
@@ -313,10 +683,15 @@ impl Taker {
     ///
     /// Depending upon the failure situation, Taker will automatically try to recover from failed swaps
     /// by executing the contract txs. If that fails too for any reason, user should manually call the [Taker::recover_from_swap].
+    /// With the `auto_recovery` config, the recovery is left to [Taker::run_recovery_scheduler] instead.
     ///
     /// If that fails too. Open an issue at [our github](https://github.com/citadel-tech/coinswap/issues)
     pub(crate) fn send_coinswap(&mut self, swap_params: SwapParams) -> Result<(), TakerError> {
-        // Check if we have enough balance.
+        self.check_route_length(&swap_params)?;
+
+        let swap_start = Instant::now();
+
+        // Check if we have enough balance. The spendable balance excludes the wallet reserve.
         let available = self.wallet.get_balances()?.spendable;
 
         // TODO: Make more exact estimate of swap cost and ensure balance.
@@ -331,30 +706,42 @@ impl Taker {
             return Err(err.into());
         }
 
-        log::info!("Syncing Offerbook");
-        self.sync_offerbook()?;
+        self.swap_status.set(SwapPhase::SyncingOffers);
+        self.sync_offerbook_for_swap(&swap_params)?;
 
-        // Error early if hop_count > available good makers.
-        if swap_params.maker_count > self.offerbook.all_good_makers().len() {
-            log::error!(
-                "Not enough makers in the offerbook. Required {}, avaialable {}",
-                swap_params.maker_count,
-                self.offerbook.all_good_makers().len()
+        let mut swap_params = swap_params;
+        if self.config.randomize_tx_count {
+            swap_params.tx_count = self.pick_tx_count(&swap_params)?;
+            log::info!(
+                "Splitting the swap into {} funding txs",
+                swap_params.tx_count
             );
-            return Err(TakerError::NotEnoughMakersInOfferBook);
-        }
-
-        // Error early if less than 2 makers.
-        if swap_params.maker_count < 2 {
-            log::error!("Cannot swap with less than 2 makers");
-            return Err(ProtocolError::General("Swap maker count < 2").into());
         }
 
         // Generate new random preimage and initiate the first hop.
-        let mut preimage = [0u8; 32];
-        OsRng.fill_bytes(&mut preimage);
-
-        let unique_id = preimage[0..8].to_hex_string(Case::Lower);
+        let preimage = generate_preimage(self.rng.as_mut());
+
+        // The id is shared with the makers, so it's drawn separately and reveals nothing of the preimage.
+        // It's never one of the ongoing or recorded swaps. An unreadable history only skips the latter.
+        let mut used_ids = match self.get_swap_history() {
+            Ok(history) => history
+                .into_iter()
+                .map(|record| record.id)
+                .collect::<HashSet<_>>(),
+            Err(e) => {
+                log::warn!(
+                    "Could not read the swap history to check the swap id: {:?}",
+                    e
+                );
+                HashSet::new()
+            }
+        };
+        used_ids.insert(self.ongoing_swap_state.id.clone());
+        let unique_id = generate_swap_id(
+            self.rng.as_mut(),
+            self.config.swap_id_len.max(MIN_SWAP_ID_LEN),
+            &used_ids,
+        );
 
         log::info!("Initiating coinswap with id : {}", unique_id);
 
@@ -363,14 +750,18 @@ impl Taker {
         self.ongoing_swap_state.id = unique_id;
 
         // Try first hop. Abort if error happens.
+        self.swap_status.set(SwapPhase::FundingFirstHop);
         if let Err(e) = self.init_first_hop() {
             log::error!("Could not initiate first hop: {:?}", e);
-            self.recover_from_swap()?;
+            self.recover_or_defer()?;
             return Err(e);
         }
 
         // Iterate until `maker_count` numbers of Makers are found and initiate swap between them sequentially.
         for maker_index in 0..self.ongoing_swap_state.swap_params.maker_count {
+            // The deadline is only checked between protocol steps, never in the middle of a message exchange.
+            self.abort_if_deadline_passed(swap_start)?;
+
             if maker_index == 0 {
                 self.ongoing_swap_state.taker_position = TakerPosition::FirstPeer;
             } else if maker_index == self.ongoing_swap_state.swap_params.maker_count - 1 {
@@ -380,12 +771,16 @@ impl Taker {
             }
 
             // Refund lock time decreases by `refund_locktime_step` for each hop.
-            let maker_refund_locktime = REFUND_LOCKTIME
-                + REFUND_LOCKTIME_STEP
-                    * (self.ongoing_swap_state.swap_params.maker_count - maker_index - 1) as u16;
+            let maker_refund_locktime = if self.behavior == TakerBehavior::ExcessiveRefundLocktime {
+                u16::MAX
+            } else {
+                refund_locktime(self.ongoing_swap_state.swap_params.maker_count - maker_index - 1)?
+            };
 
             let funding_tx_infos = self.funding_info_for_next_maker();
 
+            self.swap_status.set(SwapPhase::ExchangingSigs(maker_index));
+
             // Attempt to initiate the next hop of the swap. If anything goes wrong, abort immediately.
             // If succeeded, collect the funding_outpoints and multisig_reedemscripts of the next hop.
             // If error then aborts from current swap. Ban the Peer.
@@ -413,7 +808,7 @@ impl Taker {
                     Err(e) => {
                         log::error!("Could not initiate next hop. Error : {:?}", e);
                         log::warn!("Starting recovery from existing swap");
-                        self.recover_from_swap()?;
+                        self.recover_or_defer()?;
                         return Ok(());
                     }
                 };
@@ -424,6 +819,7 @@ impl Taker {
             // For all cases, abort from swap immediately.
             // For the timeout case also ban the Peer.
             let txids_to_watch = funding_outpoints.iter().map(|op| op.txid).collect();
+            self.swap_status.set(SwapPhase::AwaitingConfirmations);
             match self.watch_for_txs(&txids_to_watch) {
                 Ok(r) => self.ongoing_swap_state.funding_txs.push(r),
                 Err(e) => {
@@ -431,9 +827,10 @@ impl Taker {
                     log::warn!("Starting recovery from existing swap");
                     if let TakerError::FundingTxWaitTimeOut = e {
                         let bad_maker = &self.ongoing_swap_state.peer_infos[maker_index].peer;
-                        self.offerbook.add_bad_maker(bad_maker);
+                        self.offerbook
+                            .add_bad_maker(bad_maker, BanReason::FundingTimeout);
                     }
-                    self.recover_from_swap()?;
+                    self.recover_or_defer()?;
                     return Ok(());
                 }
             }
@@ -449,7 +846,7 @@ impl Taker {
                     Err(e) => {
                         log::error!("Incoming SwapCoin Generation failed : {:?}", e);
                         log::warn!("Starting recovery from existing swap");
-                        self.recover_from_swap()?;
+                        self.recover_or_defer()?;
                         return Ok(());
                     }
                 }
@@ -467,23 +864,137 @@ impl Taker {
             return Ok(());
         }
 
+        self.abort_if_deadline_passed(swap_start)?;
+
+        self.swap_status.set(SwapPhase::Settling);
         match self.settle_all_swaps() {
             Ok(_) => (),
             Err(e) => {
                 log::error!("Swap Settlement Failed : {:?}", e);
                 log::warn!("Starting recovery from existing swap");
-                self.recover_from_swap()?;
+                self.recover_or_defer()?;
                 return Ok(());
             }
         }
 
+        // The swap is done, a failure to record it must not fail it.
+        if let Err(e) = self.record_completed_swap() {
+            log::error!("Could not record the swap in the swap history: {:?}", e);
+        }
+        if let Err(e) = self.record_maker_successes() {
+            log::error!("Could not record the makers' completed swap: {:?}", e);
+        }
+
+        // The incoming swapcoins are cleared from the swap state, along with the rest of it.
+        let incoming_funding_txids = self
+            .ongoing_swap_state
+            .incoming_swapcoins
+            .iter()
+            .map(|swapcoin| swapcoin.contract_tx.input[0].previous_output.txid)
+            .collect::<HashSet<_>>();
+        let settlement_confirms = self
+            .ongoing_swap_state
+            .swap_params
+            .wait_for_settlement_confirms;
+        let swap_deadline = self
+            .ongoing_swap_state
+            .swap_params
+            .deadline
+            .map(|deadline| swap_start + deadline);
+
         log::info!("Initializing Sync and Save.");
         self.save_and_reset_swap_round()?;
         log::info!("Completed Sync and Save.");
+        if let Some(confirms) = settlement_confirms {
+            self.wait_for_settlement_confirms(&incoming_funding_txids, confirms, swap_deadline)?;
+        }
+        if let Err(e) = self.change_linkage_warning() {
+            log::error!("Could not check the swap funding change: {:?}", e);
+        }
         log::info!("Successfully Completed Coinswap.");
         Ok(())
     }
 
+    /// Aborts the ongoing swap into recovery if its [SwapParams] deadline has passed since `swap_start`.
+    fn abort_if_deadline_passed(&mut self, swap_start: Instant) -> Result<(), TakerError> {
+        let Some(deadline) = self.ongoing_swap_state.swap_params.deadline else {
+            return Ok(());
+        };
+        if swap_start.elapsed() < deadline {
+            return Ok(());
+        }
+        log::error!(
+            "Swap deadline of {:?} exceeded, aborting the swap",
+            deadline
+        );
+        log::warn!("Starting recovery from existing swap");
+        self.recover_or_defer()?;
+        Err(TakerError::DeadlineExceeded)
+    }
+
+    /// Waits until every tx of `txids` has `confirms` confirmations, then syncs the wallet so the
+    /// received swapcoins show up as confirmed in its balances.
+    ///
+    /// Gives up once the swap's `deadline` passes, or without one, after
+    /// [SETTLEMENT_CONFIRM_BLOCK_INTERVALS] expected block intervals per confirmation.
+    fn wait_for_settlement_confirms(
+        &mut self,
+        txids: &HashSet<Txid>,
+        confirms: u32,
+        deadline: Option<Instant>,
+    ) -> Result<(), TakerError> {
+        let sleep_interval = if cfg!(feature = "integration-test") {
+            Duration::from_secs(1)
+        } else {
+            Duration::from_secs(30)
+        };
+        let wait_until = deadline.unwrap_or_else(|| {
+            Instant::now()
+                + Duration::from_secs(
+                    u64::from(confirms)
+                        * SETTLEMENT_CONFIRM_BLOCK_INTERVALS
+                        * EXPECTED_BLOCK_INTERVAL_SEC,
+                )
+        });
+
+        log::info!(
+            "Waiting for {} confirmations of the received swapcoins. Txids : {:?}",
+            confirms,
+            txids
+        );
+        loop {
+            let mut confirmed = true;
+            for txid in txids {
+                let tx_confirms = self.wallet.get_tx_confirmations(txid)?.unwrap_or(0);
+                if tx_confirms < confirms {
+                    log::info!(
+                        "Tx {} | {} of {} confirmations",
+                        txid,
+                        tx_confirms,
+                        confirms
+                    );
+                    confirmed = false;
+                }
+            }
+            if confirmed {
+                break;
+            }
+            if Instant::now() >= wait_until {
+                log::error!(
+                    "Received swapcoins not confirmed at {} in time. Txids : {:?}",
+                    confirms,
+                    txids
+                );
+                return Err(TakerError::SettlementConfirmsTimeOut);
+            }
+            std::thread::sleep(sleep_interval);
+        }
+
+        self.wallet.sync_no_fail();
+        log::info!("Received swapcoins confirmed at {}", confirms);
+        Ok(())
+    }
+
     // ######## PROTOCOL SUBROUTINES ############
 
     /// Initiate the first coinswap hop. Makers are selected from the [OfferBook], and round will
@@ -495,93 +1006,162 @@ impl Taker {
         self.ongoing_swap_state.taker_position = TakerPosition::FirstPeer;
 
         // Locktime to be used for this swap.
-        let swap_locktime = REFUND_LOCKTIME
-            + REFUND_LOCKTIME_STEP * self.ongoing_swap_state.swap_params.maker_count as u16;
-
-        // Loop until we find a live maker who responded to our signature request.
-        let (maker, funding_txs) = loop {
-            let maker = self.choose_next_maker()?.clone();
-            log::info!("Choosing next maker: {}", maker.address);
-            let (multisig_pubkeys, multisig_nonces, hashlock_pubkeys, hashlock_nonces) =
-                generate_maker_keys(
-                    &maker.offer.tweakable_point,
-                    self.ongoing_swap_state.swap_params.tx_count,
-                )?;
-            let (funding_txs, mut outgoing_swapcoins, funding_fee) =
-                self.wallet.initalize_coinswap(
-                    self.ongoing_swap_state.swap_params.send_amount,
-                    &multisig_pubkeys,
-                    &hashlock_pubkeys,
-                    self.get_preimage_hash(),
-                    swap_locktime,
-                    Amount::from_sat(MINER_FEE),
-                )?;
-
-            let contract_reedemscripts = outgoing_swapcoins
-                .iter()
-                .map(|swapcoin| swapcoin.contract_redeemscript.clone())
-                .collect();
-
-            // Request for Sender's Signatures
-            let contract_sigs = match self.req_sigs_for_sender(
-                &maker.address,
-                &outgoing_swapcoins,
-                &multisig_nonces,
-                &hashlock_nonces,
-                swap_locktime,
-            ) {
-                Ok(contract_sigs) => contract_sigs,
-                Err(e) => {
-                    // Bad maker, mark it, and try next one.
-                    self.offerbook.add_bad_maker(&maker);
-                    log::error!(
-                        "Failed to obtain sender's contract signatures from first_maker {}: {:?}",
-                        maker.address,
-                        e
+        let swap_locktime = refund_locktime(self.ongoing_swap_state.swap_params.maker_count)?;
+
+        // Fee paid by each funding tx. Raised if the node rejects the funding txs for a too low fee.
+        let mut funding_tx_fee = Amount::from_sat(MINER_FEE);
+        let mut fee_retries = 0;
+        let mut conflict_retries = 0;
+
+        // Loop until the funding txs are broadcasted.
+        let (maker, funding_txids) = loop {
+            // Loop until we find a live maker who responded to our signature request.
+            let (maker, funding_txs) = 'setup: loop {
+                // The funding txs of every candidate are only built here, nothing is committed
+                // to the wallet until a maker responds.
+                let mut setups = Vec::new();
+                let mut build_error = None;
+                for maker in self.choose_first_hop_candidates()? {
+                    log::info!("Choosing next maker: {}", maker.address);
+                    let (multisig_pubkeys, multisig_nonces, hashlock_pubkeys, hashlock_nonces) =
+                        generate_maker_keys(
+                            &maker.offer.tweakable_point,
+                            self.ongoing_swap_state.swap_params.tx_count,
+                            self.rng.as_mut(),
+                        )?;
+                    let funding = self.wallet.prepare_coinswap(
+                        self.ongoing_swap_state.swap_params.send_amount,
+                        &multisig_pubkeys,
+                        &hashlock_pubkeys,
+                        self.get_preimage_hash(),
+                        swap_locktime,
+                        funding_tx_fee,
+                        funding_input_sequence(self.config.funding_rbf),
+                        maker.offer.segwit_only,
                     );
+                    let funding = match funding {
+                        Ok(funding) => funding,
+                        // The coins were spent behind our back, ex: by another instance of the wallet.
+                        Err(WalletError::InputConflict { outpoint })
+                            if conflict_retries < FUNDING_CONFLICT_RETRIES =>
+                        {
+                            conflict_retries += 1;
+                            log::warn!(
+                                "Funding input {} is already spent. Syncing the wallet and re-selecting coins",
+                                outpoint
+                            );
+                            self.wallet.sync_no_fail();
+                            continue 'setup;
+                        }
+                        Err(e) => {
+                            log::warn!(
+                                "Failed to build the funding for maker {}, skipping it: {:?}",
+                                maker.address,
+                                e
+                            );
+                            build_error = Some(e);
+                            continue;
+                        }
+                    };
+                    setups.push(FirstHopSetup {
+                        maker,
+                        multisig_pubkeys,
+                        multisig_nonces,
+                        hashlock_nonces,
+                        funding,
+                    });
+                }
+                // No funding could be built at all, ex: for a lack of funds.
+                if let Some(e) = build_error.filter(|_| setups.is_empty()) {
+                    return Err(e.into());
+                }
+
+                // Request for Sender's Signatures
+                let Some((setup, contract_sigs)) = self.req_first_hop_sigs(setups, swap_locktime)
+                else {
+                    // Every candidate was marked bad, try the next ones.
                     continue;
+                };
+                let FirstHopSetup {
+                    maker,
+                    multisig_pubkeys,
+                    multisig_nonces,
+                    hashlock_nonces,
+                    mut funding,
+                } = setup;
+
+                contract_sigs
+                    .sigs
+                    .iter()
+                    .zip(funding.outgoing_swapcoins.iter_mut())
+                    .for_each(|(sig, outgoing_swapcoin)| {
+                        outgoing_swapcoin.others_contract_sig = Some(*sig);
+                    });
+                // Nothing is funded yet, so a contract we couldn't spend is caught before it matters.
+                if let Err(e) = self.verify_signed_contracts(&funding.outgoing_swapcoins) {
+                    log::warn!("Banning Maker : {}", maker.address);
+                    self.offerbook
+                        .add_bad_maker(&maker, BanReason::SignatureExchange);
+                    return Err(e);
                 }
-            };
 
-            // // Maker has returned a valid signature, save all the data in memory,
-            // // and persist in disk.
-            self.ongoing_swap_state.peer_infos.push(NextPeerInfo {
-                peer: maker.clone(),
-                multisig_pubkeys,
-                multisig_nonces,
-                hashlock_nonces,
-                contract_reedemscripts,
-            });
+                // Only the winner's funding is committed, the other candidates' are dropped.
+                self.wallet.commit_coinswap(&funding)?;
+                let PreparedCoinswap {
+                    funding_txes: funding_txs,
+                    mut outgoing_swapcoins,
+                    total_miner_fee: funding_fee,
+                    ..
+                } = funding;
 
-            contract_sigs
-                .sigs
-                .iter()
-                .zip(outgoing_swapcoins.iter_mut())
-                .for_each(|(sig, outgoing_swapcoin)| {
-                    outgoing_swapcoin.others_contract_sig = Some(*sig);
+                let contract_reedemscripts = outgoing_swapcoins
+                    .iter()
+                    .map(|swapcoin| swapcoin.contract_redeemscript.clone())
+                    .collect();
+
+                // // Maker has returned a valid signature, save all the data in memory,
+                // // and persist in disk.
+                self.ongoing_swap_state.peer_infos.push(NextPeerInfo {
+                    peer: maker.clone(),
+                    multisig_pubkeys,
+                    multisig_nonces,
+                    hashlock_nonces,
+                    contract_reedemscripts,
                 });
 
-            for outgoing_swapcoin in &outgoing_swapcoins {
-                self.wallet.add_outgoing_swapcoin(outgoing_swapcoin);
-            }
-            self.wallet.save_to_disk()?;
+                for (sigs, outgoing_swapcoin) in contract_sigs
+                    .escalated_sigs
+                    .iter()
+                    .zip(outgoing_swapcoins.iter_mut())
+                {
+                    outgoing_swapcoin
+                        .apply_escalated_contract_sigs(&contract_fee_escalation(), sigs)?;
+                }
 
-            self.ongoing_swap_state.outgoing_swapcoins = outgoing_swapcoins;
+                for outgoing_swapcoin in &outgoing_swapcoins {
+                    self.wallet.add_outgoing_swapcoin(outgoing_swapcoin);
+                }
+                self.wallet.save_to_disk()?;
 
-            log::info!("Total Funding Txs Fees: {}", funding_fee);
+                self.ongoing_swap_state.outgoing_swapcoins = outgoing_swapcoins;
 
-            break (maker, funding_txs);
-        };
+                log::info!("Total Funding Txs Fees: {}", funding_fee);
 
-        log::debug!(
-            "Outgoing SwapCoins: {:?}",
-            self.ongoing_swap_state.outgoing_swapcoins
-        );
+                break (maker, funding_txs);
+            };
 
-        // Broadcast and wait for funding txs to confirm
-        let funding_txids = funding_txs
-            .iter()
-            .map(|tx| {
+            log::debug!(
+                "Outgoing SwapCoins: {:?}",
+                self.ongoing_swap_state.outgoing_swapcoins
+            );
+
+            // Error clearly, if the coins got spent while exchanging the signatures.
+            self.wallet.check_input_conflicts(&funding_txs)?;
+
+            // Broadcast the funding txs
+            let mut funding_txids = Vec::new();
+            let mut required_fee = None;
+            for tx in &funding_txs {
                 // Calculate the virtual size in bytes (vbytes)
                 let tx_vbytes = tx.weight().to_vbytes_ceil();
 
@@ -590,12 +1170,49 @@ impl Taker {
 
                 log::info!("Transaction size: {} vB ({:.3} kvB)", tx_vbytes, tx_kvb);
 
-                let txid = self.wallet.send_tx(tx)?;
-                log::info!("Broadcasted Funding tx. txid: {}", txid);
-                assert_eq!(txid, tx.compute_txid());
-                Ok(txid)
-            })
-            .collect::<Result<_, TakerError>>()?;
+                match self.wallet.send_tx(tx) {
+                    Ok(txid) => {
+                        log::info!("Broadcasted Funding tx. txid: {}", txid);
+                        assert_eq!(txid, tx.compute_txid());
+                        funding_txids.push(txid);
+                    }
+                    // The funding can only be re-built if none of the funding txs are broadcasted yet.
+                    Err(WalletError::FeeTooLow { required })
+                        if funding_txids.is_empty() && fee_retries < FUNDING_FEE_RETRIES =>
+                    {
+                        required_fee = Some((required, tx_vbytes));
+                        break;
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            let Some((required, rejected_vbytes)) = required_fee else {
+                break (maker, funding_txids);
+            };
+
+            // Scale the required fee to the largest funding tx, as every funding tx pays the same fee.
+            let max_vbytes = funding_txs
+                .iter()
+                .map(|tx| tx.weight().to_vbytes_ceil())
+                .max()
+                .unwrap_or(rejected_vbytes);
+            funding_tx_fee = Amount::from_sat((required * max_vbytes).div_ceil(rejected_vbytes));
+            fee_retries += 1;
+            log::warn!(
+                "Funding fee too low, {} sats required. Re-building funding txs with {} sats fee",
+                required,
+                funding_tx_fee.to_sat()
+            );
+
+            // Discard the un-broadcasted swap setup with this maker.
+            for swapcoin in self.ongoing_swap_state.outgoing_swapcoins.drain(..) {
+                self.wallet
+                    .remove_outgoing_swapcoin(&swapcoin.get_multisig_redeemscript())?;
+            }
+            self.ongoing_swap_state.peer_infos.pop();
+            self.wallet.save_to_disk()?;
+        };
 
         // Watch for the funding transactions to be confirmed.
         // This errors in two cases.
@@ -609,7 +1226,8 @@ impl Taker {
             Err(e) => {
                 log::error!("Error: {:?}", e);
                 if let TakerError::ContractsBroadcasted(_) = e {
-                    self.offerbook.add_bad_maker(&maker);
+                    self.offerbook
+                        .add_bad_maker(&maker, BanReason::ContractsBroadcasted);
                 }
                 return Err(e);
             }
@@ -636,7 +1254,12 @@ impl Taker {
                 self.ongoing_swap_state
                     .peer_infos
                     .last()
-                    .map(|npi| npi.peer.offer.required_confirms)
+                    .map(|npi| {
+                        negotiate_required_confirms(
+                            self.ongoing_swap_state.swap_params.required_confirms,
+                            npi.peer.offer.required_confirms,
+                        )
+                    })
                     .expect("Maker information expected in swap state")
             };
 
@@ -659,19 +1282,28 @@ impl Taker {
             60 * 5 // 5mins for production
         };
 
-        // Check for funding confirmation at this frequency
+        // Check for funding confirmation at this frequency, or more often for faster heartbeats.
         let sleep_interval = if cfg!(feature = "integration-test") {
             1u64 // 1 secs for the tests
         } else {
             30 // 30 secs for production
-        };
+        }
+        .min(self.config.heartbeat_interval.max(1));
+
+        let heartbeat_interval = Duration::from_secs(self.config.heartbeat_interval);
+        let mut last_heartbeat: Option<Instant> = None;
 
         let start_time = Instant::now();
 
+        // Blocks up to this height are already checked for broadcasted contracts.
+        let mut contract_check_height = None;
+
         loop {
             // Abort if any of the contract transaction is broadcasted
             // TODO: Find the culprit Maker, and ban it's fidelity bond.
-            let contracts_broadcasted = self.check_for_broadcasted_contract_txes();
+            let (contracts_broadcasted, checked_height) =
+                self.check_for_broadcasted_contract_txes(contract_check_height);
+            contract_check_height = checked_height;
             if !contracts_broadcasted.is_empty() {
                 log::error!(
                     "Fatal! Contract txs broadcasted by makers. Txids : {:?}",
@@ -680,6 +1312,17 @@ impl Taker {
                 return Err(TakerError::ContractsBroadcasted(contracts_broadcasted));
             }
 
+            // Keep the swap alive at the makers, while waiting for the confirmations.
+            if last_heartbeat.is_none_or(|t| t.elapsed() >= heartbeat_interval) {
+                for addr in &maker_addrs {
+                    // It's safe to ignore the error here, because if the maker is actually offline, the swap will fail in the later stages.
+                    if let Err(e) = self.send_heartbeat(addr) {
+                        log::warn!("Maker {} didn't acknowledge the heartbeat | {:?}", addr, e);
+                    }
+                }
+                last_heartbeat = Some(Instant::now());
+            }
+
             // Check for each funding transactions if they are confirmed
             for txid in funding_txids {
                 if txid_tx_map.contains_key(txid) {
@@ -709,20 +1352,6 @@ impl Taker {
                         "Funding tx Seen in Mempool. Waiting for confirmation for {} secs",
                         elapsed,
                     );
-
-                    // Send wait-notif to all makers
-                    for addr in &maker_addrs {
-                        // Ignore transient network error and retry in next loop.
-                        // It's safe to ignore the error here, because if the maker is actually offline, the swap will fail in the later stages.
-                        if let Err(e) = self.send_to_maker(
-                            addr,
-                            TakerToMakerMessage::WaitingFundingConfirmation(
-                                self.ongoing_swap_state.id.clone(),
-                            ),
-                        ) {
-                            log::error!("error sending wait-notif to maker {} | {:?}", addr, e);
-                        }
-                    }
                 }
 
                 // handle confirmations
@@ -763,6 +1392,9 @@ impl Taker {
                     })
                     .collect::<Result<Vec<String>, _>>()
                     .map_err(WalletError::from)?;
+                for (tx, merkleproof) in txes.iter().zip(merkleproofs.iter()) {
+                    self.wallet.verify_funding_tx_merkleproof(tx, merkleproof)?;
+                }
                 return Ok((txes, merkleproofs));
             }
             sleep(Duration::from_secs(sleep_interval));
@@ -900,6 +1532,30 @@ impl Taker {
             ii += 1;
             match self.send_sigs_init_next_hop_once(maker_refund_locktime, funding_tx_infos) {
                 Ok(ret) => return Ok(ret),
+                // The maker substituted the hashlock. Reattempting won't help.
+                Err(TakerError::Wallet(WalletError::Protocol(ProtocolError::HashlockMismatch))) => {
+                    log::error!(
+                        "Maker {} contracts don't commit to the swap hashlock. Banning Maker",
+                        &maker_oa.address
+                    );
+                    self.offerbook
+                        .add_bad_maker(&maker_oa, BanReason::HashlockMismatch);
+                    return Err(ProtocolError::HashlockMismatch.into());
+                }
+                // The maker built different contract txs. Reattempting won't help either.
+                Err(TakerError::Wallet(WalletError::Protocol(
+                    ProtocolError::ContractTxidMismatch { expected, found },
+                ))) => {
+                    log::error!(
+                        "Maker {} contract tx {} differs from the expected {}. Banning Maker",
+                        &maker_oa.address,
+                        found,
+                        expected
+                    );
+                    self.offerbook
+                        .add_bad_maker(&maker_oa, BanReason::ContractTxidMismatch);
+                    return Err(ProtocolError::ContractTxidMismatch { expected, found }.into());
+                }
                 Err(e) => {
                     log::warn!(
                         "Failed to connect to maker {} to send signatures and init next hop, \
@@ -919,7 +1575,8 @@ impl Taker {
                         ));
                         continue;
                     } else {
-                        self.offerbook.add_bad_maker(&maker_oa);
+                        self.offerbook
+                            .add_bad_maker(&maker_oa, BanReason::Unreachable);
                         return Err(e);
                     }
                 }
@@ -933,35 +1590,35 @@ impl Taker {
         maker_refund_locktime: u16,
         funding_tx_infos: &[FundingTxInfo],
     ) -> Result<(NextPeerInfo, ContractSigsAsRecvrAndSender), TakerError> {
-        let this_maker = &self
+        let this_maker = self
             .ongoing_swap_state
             .peer_infos
             .last()
             .expect("at least one active maker expected")
-            .peer;
+            .peer
+            .clone();
 
-        let previous_maker = self.ongoing_swap_state.peer_infos.iter().rev().nth(1);
+        let previous_maker = self
+            .ongoing_swap_state
+            .peer_infos
+            .iter()
+            .rev()
+            .nth(1)
+            .cloned();
 
         log::info!(
             "Connecting to {} | Send Sigs Init Next Hop",
             this_maker.address
         );
-        let address = this_maker.address.to_string();
-        let mut socket = match self.config.connection_type {
-            ConnectionType::CLEARNET => TcpStream::connect(address)?,
-            ConnectionType::TOR => Socks5Stream::connect(
-                format!("127.0.0.1:{}", self.config.socks_port).as_str(),
-                address.as_str(),
-            )?
-            .into_inner(),
-        };
-
-        let reconnect_timeout = Duration::from_secs(TCP_TIMEOUT_SECONDS);
-
-        socket.set_read_timeout(Some(reconnect_timeout))?;
-        socket.set_write_timeout(Some(reconnect_timeout))?;
+        let mut stream = connect_to_maker(
+            &this_maker.address.to_string(),
+            &self.config,
+            Duration::from_secs(self.config.signature_exchange_timeout),
+        )?;
+        let protocol_trace = self.protocol_trace.clone();
+        let mut socket = TracedStream::new(&mut stream, &protocol_trace);
 
-        handshake_maker(&mut socket)?;
+        let compression = handshake_maker(&mut socket)?;
         let mut next_maker = this_maker.clone();
         let (
             next_peer_multisig_pubkeys,
@@ -969,7 +1626,7 @@ impl Taker {
             next_peer_hashlock_keys_or_nonces,
             contract_sigs_as_recvr_sender,
             next_swap_contract_redeemscripts,
-            senders_sigs,
+            (senders_sigs, senders_escalated_sigs),
         ) = loop {
             //loop to help error handling, allowing us to keep trying new makers until
             //we find one for which our request is successful, or until we run out of makers
@@ -1000,6 +1657,7 @@ impl Taker {
                 generate_maker_keys(
                     &next_maker.offer.tweakable_point,
                     self.ongoing_swap_state.swap_params.tx_count,
+                    self.rng.as_mut(),
                 )?
             };
 
@@ -1040,11 +1698,16 @@ impl Taker {
                 funding_tx_infos: funding_tx_infos.to_vec(),
                 this_maker_contract_txs,
                 this_maker_refund_locktime: maker_refund_locktime,
+                required_confirms: negotiate_required_confirms(
+                    self.ongoing_swap_state.swap_params.required_confirms,
+                    this_maker.offer.required_confirms,
+                ),
             };
 
             let (contract_sigs_as_recvr_sender, next_swap_contract_redeemscripts) =
                 send_proof_of_funding_and_init_next_hop(
                     &mut socket,
+                    compression,
                     this_maker_info,
                     next_maker_info,
                     self.get_preimage_hash(),
@@ -1056,10 +1719,24 @@ impl Taker {
             );
 
             // If This Maker is the Sender, and we (the Taker) are the Receiver (Last Hop). We provide the Sender's Contact Tx Sigs.
-            let senders_sigs = if self.ongoing_swap_state.taker_position == TakerPosition::LastPeer
+            let (senders_sigs, senders_escalated_sigs) = if self.ongoing_swap_state.taker_position
+                == TakerPosition::LastPeer
             {
+                // These contracts become our incoming swapcoins, they must be locked with our hash.
+                for (senders_contract_tx_info, contract_redeemscript) in
+                    contract_sigs_as_recvr_sender
+                        .senders_contract_txs_info
+                        .iter()
+                        .zip(next_swap_contract_redeemscripts.iter())
+                {
+                    check_contract_hashlock(
+                        &senders_contract_tx_info.contract_tx,
+                        contract_redeemscript,
+                        &self.get_preimage_hash(),
+                    )?;
+                }
                 log::info!("Taker is next peer. Signing Sender's Contract Txs");
-                // Sign the seder's contract transactions with our multisig privkey.
+                // Sign the seder's contract transactions, and their fee-escalated variants, with our multisig privkey.
                 next_peer_multisig_keys_or_nonces
                     .iter()
                     .zip(
@@ -1069,15 +1746,26 @@ impl Taker {
                     )
                     .map(
                         |(my_receiving_multisig_privkey, senders_contract_tx_info)| {
-                            crate::protocol::contract::sign_contract_tx(
-                                &senders_contract_tx_info.contract_tx,
-                                &senders_contract_tx_info.multisig_redeemscript,
-                                senders_contract_tx_info.funding_amount,
-                                my_receiving_multisig_privkey,
-                            )
+                            Ok((
+                                crate::protocol::contract::sign_contract_tx(
+                                    &senders_contract_tx_info.contract_tx,
+                                    &senders_contract_tx_info.multisig_redeemscript,
+                                    senders_contract_tx_info.funding_amount,
+                                    my_receiving_multisig_privkey,
+                                )?,
+                                sign_fee_escalated_contract_txs(
+                                    &senders_contract_tx_info.contract_tx,
+                                    &senders_contract_tx_info.multisig_redeemscript,
+                                    senders_contract_tx_info.funding_amount,
+                                    &contract_fee_escalation(),
+                                    my_receiving_multisig_privkey,
+                                )?,
+                            ))
                         },
                     )
-                    .collect::<Result<Vec<_>, _>>()?
+                    .collect::<Result<Vec<_>, ProtocolError>>()?
+                    .into_iter()
+                    .unzip()
             } else {
                 // If Next Maker is the Receiver, and This Maker is The Sender, Request Sender's Contract Tx Sig to Next Maker.
                 let watchonly_swapcoins = self.create_watch_only_swapcoins(
@@ -1094,9 +1782,9 @@ impl Taker {
                 ) {
                     Ok(r) => r,
                     Err(e) => {
-                        self.offerbook.add_bad_maker(&next_maker);
+                        self.mark_sender_sigs_failure(&next_maker, &e);
                         log::info!(
-                            "Failed to obtain sender's contract tx signature from next_maker {}, Banning Maker: {:?}",
+                            "Failed to obtain sender's contract tx signature from next_maker {}: {:?}",
                             next_maker.address,
                             e
                         );
@@ -1106,7 +1794,7 @@ impl Taker {
                 self.ongoing_swap_state
                     .watchonly_swapcoins
                     .push(watchonly_swapcoins);
-                sigs.sigs
+                (sigs.sigs, sigs.escalated_sigs)
             };
             break (
                 next_peer_multisig_pubkeys,
@@ -1114,66 +1802,80 @@ impl Taker {
                 next_peer_hashlock_keys_or_nonces,
                 contract_sigs_as_recvr_sender,
                 next_swap_contract_redeemscripts,
-                senders_sigs,
+                (senders_sigs, senders_escalated_sigs),
             );
         };
 
         // If This Maker is the Reciver, and We (The Taker) are the Sender (First Hop), Sign the Contract Tx.
-        let receivers_sigs = if self.ongoing_swap_state.taker_position == TakerPosition::FirstPeer {
-            log::info!("Taker is previous peer. Signing Receivers Contract Txs");
-            // Sign the receiver's contract using our [OutgoingSwapCoin].
-            contract_sigs_as_recvr_sender
-                .receivers_contract_txs
-                .iter()
-                .zip(self.ongoing_swap_state.outgoing_swapcoins.iter())
-                .map(|(receivers_contract_tx, outgoing_swapcoin)| {
-                    outgoing_swapcoin.sign_contract_tx_with_my_privkey(receivers_contract_tx)
-                })
-                .collect::<Result<Vec<_>, _>>()?
-        } else {
-            // If Next Maker is the Receiver, and Previous Maker is the Sender, request Previous Maker to sign the Reciever's Contract Tx.
-            let previous_maker = previous_maker.expect("Previous Maker should always exists");
-            let previous_maker_addr = &previous_maker.peer.address;
-            let previous_maker_watchonly_swapcoins =
-                if self.ongoing_swap_state.taker_position == TakerPosition::LastPeer {
-                    self.ongoing_swap_state
-                        .watchonly_swapcoins
-                        .last()
-                        .expect("swapcoin expected")
-                } else {
-                    //if the next peer is a maker not a taker, then that maker's swapcoins are last
-                    &self.ongoing_swap_state.watchonly_swapcoins
-                        [self.ongoing_swap_state.watchonly_swapcoins.len() - 2]
-                };
-
-            match self.req_sigs_for_recvr(
-                previous_maker_addr,
-                previous_maker_watchonly_swapcoins,
-                &contract_sigs_as_recvr_sender.receivers_contract_txs,
-            ) {
-                Ok(s) => s.sigs,
-                Err(e) => {
-                    log::error!("Could not get Receiver's signatures : {:?}", e);
-                    log::warn!("Banning Maker : {}", previous_maker.peer.address);
-                    self.offerbook.add_bad_maker(&previous_maker.peer);
-                    return Err(e);
+        let (receivers_sigs, receivers_escalated_sigs) =
+            if self.ongoing_swap_state.taker_position == TakerPosition::FirstPeer {
+                log::info!("Taker is previous peer. Signing Receivers Contract Txs");
+                // Sign the receiver's contract, and its fee-escalated variants, using our [OutgoingSwapCoin].
+                contract_sigs_as_recvr_sender
+                    .receivers_contract_txs
+                    .iter()
+                    .zip(self.ongoing_swap_state.outgoing_swapcoins.iter())
+                    .map(|(receivers_contract_tx, outgoing_swapcoin)| {
+                        Ok((
+                            outgoing_swapcoin
+                                .sign_contract_tx_with_my_privkey(receivers_contract_tx)?,
+                            outgoing_swapcoin.sign_escalated_contract_txs_with_my_privkey(
+                                receivers_contract_tx,
+                                &contract_fee_escalation(),
+                            )?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, WalletError>>()?
+                    .into_iter()
+                    .unzip()
+            } else {
+                // If Next Maker is the Receiver, and Previous Maker is the Sender, request Previous Maker to sign the Reciever's Contract Tx.
+                let previous_maker = previous_maker.expect("Previous Maker should always exists");
+                let previous_maker_addr = &previous_maker.peer.address;
+                let previous_maker_watchonly_swapcoins =
+                    if self.ongoing_swap_state.taker_position == TakerPosition::LastPeer {
+                        self.ongoing_swap_state
+                            .watchonly_swapcoins
+                            .last()
+                            .expect("swapcoin expected")
+                    } else {
+                        //if the next peer is a maker not a taker, then that maker's swapcoins are last
+                        &self.ongoing_swap_state.watchonly_swapcoins
+                            [self.ongoing_swap_state.watchonly_swapcoins.len() - 2]
+                    };
+
+                match self.req_sigs_for_recvr(
+                    previous_maker_addr,
+                    previous_maker_watchonly_swapcoins,
+                    &contract_sigs_as_recvr_sender.receivers_contract_txs,
+                ) {
+                    Ok(s) => (s.sigs, s.escalated_sigs),
+                    Err(e) => {
+                        log::error!("Could not get Receiver's signatures : {:?}", e);
+                        log::warn!("Banning Maker : {}", previous_maker.peer.address);
+                        self.offerbook
+                            .add_bad_maker(&previous_maker.peer, BanReason::SignatureExchange);
+                        return Err(e);
+                    }
                 }
-            }
-        };
+            };
         log::info!(
             "===> RespContractSigsForRecvrAndSender | {}",
             this_maker.address
         );
         let id = self.ongoing_swap_state.id.clone();
-        send_message(
-            &mut socket,
+        socket.send(
             &TakerToMakerMessage::RespContractSigsForRecvrAndSender(
                 ContractSigsForRecvrAndSender {
                     receivers_sigs,
                     senders_sigs,
                     id,
+                    fee_escalation: contract_fee_escalation(),
+                    receivers_escalated_sigs,
+                    senders_escalated_sigs,
                 },
             ),
+            compression,
         )?;
 
         let next_swap_info = NextPeerInfo {
@@ -1193,6 +1895,19 @@ impl Taker {
         next_peer_multisig_pubkeys: &[PublicKey],
         next_swap_contract_redeemscripts: &[ScriptBuf],
     ) -> Result<Vec<WatchOnlySwapCoin>, TakerError> {
+        // The maker's contract txs must pay to the contracts locked with our hash.
+        for (senders_contract_tx_info, contract_redeemscript) in contract_sigs_as_recvr_and_sender
+            .senders_contract_txs_info
+            .iter()
+            .zip(next_swap_contract_redeemscripts.iter())
+        {
+            check_contract_hashlock(
+                &senders_contract_tx_info.contract_tx,
+                contract_redeemscript,
+                &self.get_preimage_hash(),
+            )?;
+        }
+
         let next_swapcoins = contract_sigs_as_recvr_and_sender
             .senders_contract_txs_info
             .iter()
@@ -1210,8 +1925,6 @@ impl Taker {
                 },
             )
             .collect::<Result<Vec<WatchOnlySwapCoin>, _>>()?;
-        //TODO error handle here the case where next_swapcoin.contract_tx script pubkey
-        // is not equal to p2wsh(next_swap_contract_redeemscripts)
         for swapcoin in &next_swapcoins {
             self.wallet
                 .import_watchonly_redeemscript(&swapcoin.get_multisig_redeemscript())?;
@@ -1278,12 +1991,12 @@ impl Taker {
             )
             .collect::<Result<Vec<Transaction>, _>>()?;
 
-        let mut incoming_swapcoins = Vec::<IncomingSwapCoin>::new();
         let next_swap_info = self
             .ongoing_swap_state
             .peer_infos
             .last()
             .expect("next swap info expected");
+        let mut incoming_swapcoins = Vec::<IncomingSwapCoin>::new();
         for (
             (
                 (
@@ -1317,9 +2030,7 @@ impl Taker {
             .zip(funding_txs_merkleproofs.iter())
         {
             let (o_ms_pubkey1, o_ms_pubkey2) =
-                crate::protocol::contract::read_pubkeys_from_multisig_redeemscript(
-                    multisig_redeemscript,
-                )?;
+                read_pubkeys_from_multisig_redeemscript(multisig_redeemscript)?;
             let maker_funded_other_multisig_pubkey = if o_ms_pubkey1 == maker_funded_multisig_pubkey
             {
                 o_ms_pubkey2
@@ -1330,7 +2041,11 @@ impl Taker {
                 o_ms_pubkey1
             };
 
-            self.wallet.sync()?;
+            check_contract_hashlock(
+                my_receivers_contract_tx,
+                next_contract_redeemscript,
+                &self.get_preimage_hash(),
+            )?;
 
             let mut incoming_swapcoin = IncomingSwapCoin::new(
                 maker_funded_multisig_privkey,
@@ -1344,9 +2059,59 @@ impl Taker {
             incoming_swapcoins.push(incoming_swapcoin);
         }
 
+        // The wallet is synced once for all the incoming swapcoins.
+        self.wallet.sync()?;
+
         Ok(incoming_swapcoins)
     }
 
+    /// Checks that the incoming swap coins add up to at least the send amount, minus the fees agreed
+    /// with each maker of the route. The last maker is banned if it shortchanged us.
+    fn check_incoming_amount(&mut self) -> Result<(), TakerError> {
+        let swap_params = &self.ongoing_swap_state.swap_params;
+        let miner_fees_per_hop = swap_params.tx_count as u64 * MINER_FEE;
+
+        let mut expected = swap_params.send_amount.to_sat();
+        for (index, peer_info) in self.ongoing_swap_state.peer_infos[..swap_params.maker_count]
+            .iter()
+            .enumerate()
+        {
+            let offer = &peer_info.peer.offer;
+            let coinswap_fee = calculate_coinswap_fee(
+                expected,
+                refund_locktime(swap_params.maker_count - index - 1)?,
+                offer.base_fee,
+                offer.amount_relative_fee_pct,
+                offer.time_relative_fee_pct,
+            );
+            expected = expected.saturating_sub(coinswap_fee + miner_fees_per_hop);
+        }
+        let expected = Amount::from_sat(expected);
+
+        let found = self
+            .ongoing_swap_state
+            .incoming_swapcoins
+            .iter()
+            .map(|swapcoin| swapcoin.funding_amount)
+            .sum::<Amount>();
+
+        if found < expected {
+            let last_maker = self.ongoing_swap_state.peer_infos[swap_params.maker_count - 1]
+                .peer
+                .clone();
+            log::error!(
+                "Incoming swap coins of {} are short of the expected {}. Banning Maker : {}",
+                found,
+                expected,
+                last_maker.address
+            );
+            self.offerbook
+                .add_bad_maker(&last_maker, BanReason::ShortchangedAmount);
+            return Err(ProtocolError::IncorrectFundingAmount { expected, found }.into());
+        }
+        Ok(())
+    }
+
     /// Request signatures for the [IncomingSwapCoin] from the last maker of the swap round.
     fn request_sigs_for_incoming_swap(&mut self) -> Result<(), TakerError> {
         // Intermediate hops completed. Perform the last receiving hop.
@@ -1372,7 +2137,8 @@ impl Taker {
             Ok(s) => s,
             Err(e) => {
                 log::warn!("Banning Maker : {}", last_maker.address);
-                self.offerbook.add_bad_maker(&last_maker);
+                self.offerbook
+                    .add_bad_maker(&last_maker, BanReason::SignatureExchange);
                 return Err(e);
             }
         };
@@ -1384,8 +2150,26 @@ impl Taker {
         {
             incoming_swapcoin.others_contract_sig = Some(receiver_contract_sig);
         }
+        if let Err(e) = self.verify_signed_contracts(&self.ongoing_swap_state.incoming_swapcoins) {
+            log::warn!("Banning Maker : {}", last_maker.address);
+            self.offerbook
+                .add_bad_maker(&last_maker, BanReason::SignatureExchange);
+            return Err(e);
+        }
+        for (incoming_swapcoin, sigs) in self
+            .ongoing_swap_state
+            .incoming_swapcoins
+            .iter_mut()
+            .zip(receiver_contract_sig.escalated_sigs.iter())
+        {
+            incoming_swapcoin.apply_escalated_contract_sigs(&contract_fee_escalation(), sigs)?;
+        }
         for incoming_swapcoin in &self.ongoing_swap_state.incoming_swapcoins {
             self.wallet.add_incoming_swapcoin(incoming_swapcoin);
+            if let Some(label) = &self.ongoing_swap_state.swap_params.swap_label {
+                self.wallet
+                    .add_swap_label(&incoming_swapcoin.get_multisig_redeemscript(), label);
+            }
         }
 
         self.wallet.save_to_disk()?;
@@ -1393,92 +2177,171 @@ impl Taker {
         Ok(())
     }
 
-    /// Request signatures for sender side of the swap.
-    /// Keep trying until `first_connect_attempts` limit, with time delay of `first_connect_sleep_delay_sec`.
-    fn req_sigs_for_sender<S: SwapCoin>(
+    /// Assembles the fully signed contract txs of the swapcoins, with the maker's signatures, and checks that
+    /// they pass script verification, before relying on them. Skipped without `verify_signed_contracts`.
+    fn verify_signed_contracts<S: WalletSwapCoin>(
         &self,
-        maker_address: &MakerAddress,
-        outgoing_swapcoins: &[S],
-        maker_multisig_nonces: &[SecretKey],
-        maker_hashlock_nonces: &[SecretKey],
-        locktime: u16,
-    ) -> Result<ContractSigsForSender, TakerError> {
-        let reconnect_time_out = Duration::from_secs(FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC);
-        // Configurable reconnection attempts for testing
-        let first_connect_attempts = if cfg!(feature = "integration-test") {
-            10
-        } else {
-            FIRST_CONNECT_ATTEMPTS
-        };
-
-        // Custom sleep delay for testing.
-        let sleep_delay = if cfg!(feature = "integration-test") {
-            1
-        } else {
-            FIRST_CONNECT_SLEEP_DELAY_SEC
-        };
-
-        let mut ii = 0;
-
-        let maker_addr_str = maker_address.to_string();
-
-        let mut socket = match self.config.connection_type {
-            ConnectionType::CLEARNET => TcpStream::connect(maker_addr_str.clone())?,
-            ConnectionType::TOR => Socks5Stream::connect(
-                format!("127.0.0.1:{}", self.config.socks_port).as_str(),
-                &*maker_addr_str,
-            )?
-            .into_inner(),
-        };
-
-        socket.set_read_timeout(Some(reconnect_time_out))?;
-        socket.set_write_timeout(Some(reconnect_time_out))?;
+        swapcoins: &[S],
+    ) -> Result<(), TakerError> {
+        if !self.config.verify_signed_contracts {
+            return Ok(());
+        }
+        for swapcoin in swapcoins {
+            verify_signed_contract_tx(
+                &swapcoin.get_fully_signed_contract_tx()?,
+                &swapcoin.get_multisig_redeemscript(),
+                swapcoin.get_funding_amount(),
+            )?;
+        }
+        Ok(())
+    }
 
-        loop {
-            ii += 1;
-            log::info!("===> ReqContractSigsForSender | {}", maker_addr_str);
-            match req_sigs_for_sender_once(
-                &mut socket,
-                outgoing_swapcoins,
-                maker_multisig_nonces,
-                maker_hashlock_nonces,
+    /// Requests the sender's contract signatures of the first hop from the candidate makers.
+    ///
+    /// A single candidate is requested like any other hop. Several candidates are raced: they are all
+    /// requested at the same time, and the first one to respond wins. The requests still running are
+    /// cancelled, and their responses ignored.
+    ///
+    /// The candidates which failed are marked bad. Returns `None` if none of them responded.
+    fn req_first_hop_sigs(
+        &mut self,
+        mut setups: Vec<FirstHopSetup>,
+        locktime: u16,
+    ) -> Option<(FirstHopSetup, ContractSigsForSender)> {
+        if setups.len() == 1 {
+            let setup = setups.pop().expect("one candidate expected");
+            return match self.req_sigs_for_sender(
+                &setup.maker.address,
+                &setup.funding.outgoing_swapcoins,
+                &setup.multisig_nonces,
+                &setup.hashlock_nonces,
                 locktime,
             ) {
-                Ok(ret) => {
-                    return {
-                        log::info!("<=== RespContractSigsForSender | {}", maker_addr_str);
-                        Ok(ret)
-                    }
+                Ok(contract_sigs) => Some((setup, contract_sigs)),
+                Err(e) => {
+                    // Bad maker, mark it, and try next one.
+                    self.mark_sender_sigs_failure(&setup.maker, &e);
+                    log::error!(
+                        "Failed to obtain sender's contract signatures from first_maker {}: {:?}",
+                        setup.maker.address,
+                        e
+                    );
+                    None
+                }
+            };
+        }
+
+        log::info!(
+            "Racing the first hop signature request across {} makers",
+            setups.len()
+        );
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sigs_writer, sigs_reader) =
+            mpsc::channel::<(usize, Result<ContractSigsForSender, TakerError>)>();
+        for (index, setup) in setups.iter().enumerate() {
+            let sigs_writer = sigs_writer.clone();
+            let taker_config = self.config.clone();
+            let protocol_trace = self.protocol_trace.clone();
+            let cancelled = cancelled.clone();
+            let maker_address = setup.maker.address.clone();
+            let outgoing_swapcoins = setup.funding.outgoing_swapcoins.clone();
+            let multisig_nonces = setup.multisig_nonces.clone();
+            let hashlock_nonces = setup.hashlock_nonces.clone();
+            // The threads are never joined, so a slow loser doesn't hold up the swap.
+            let spawned = Builder::new()
+                .name(format!("first_hop_race_thread_{}", index))
+                .spawn(move || {
+                    let contract_sigs = connect_to_maker(
+                        &maker_address.to_string(),
+                        &taker_config,
+                        Duration::from_secs(FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC),
+                    )
+                    .and_then(|mut stream| {
+                        req_sigs_for_sender_with_retries(
+                            &mut TracedStream::new(&mut stream, &protocol_trace),
+                            &maker_address,
+                            &outgoing_swapcoins,
+                            &multisig_nonces,
+                            &hashlock_nonces,
+                            locktime,
+                            &cancelled,
+                        )
+                    });
+                    // The receiver is gone once a winner is found.
+                    let _ = sigs_writer.send((index, contract_sigs));
+                });
+            if let Err(e) = spawned {
+                log::error!("Error while spawning first hop race thread: {:?}", e);
+            }
+        }
+        drop(sigs_writer);
+
+        let mut winner = None;
+        for (index, contract_sigs) in sigs_reader.iter() {
+            match contract_sigs {
+                Ok(contract_sigs) => {
+                    winner = Some((index, contract_sigs));
+                    break;
                 }
                 Err(e) => {
-                    log::warn!(
-                        "Failed to connect to maker {} to request signatures for receiver, \
-                                reattempting {} of {} | error={:?}",
-                        &maker_addr_str,
-                        ii,
-                        first_connect_attempts,
+                    let maker = &setups[index].maker;
+                    self.mark_sender_sigs_failure(maker, &e);
+                    log::error!(
+                        "Failed to obtain sender's contract signatures from first_maker {}: {:?}",
+                        maker.address,
                         e
                     );
-                    if ii <= first_connect_attempts {
-                        sleep(Duration::from_secs(
-                            if ii <= SHORT_LONG_SLEEP_DELAY_TRANSITION {
-                                sleep_delay
-                            } else {
-                                RECONNECT_LONG_SLEEP_DELAY
-                            },
-                        ));
-                        continue;
-                    } else {
-                        log::warn!(
-                            "Failed to connect to maker {} to request signatures for receiver, \
-                                    reattempt limit exceeded",
-                            &maker_addr_str,
-                        );
-                        return Err(e);
-                    }
                 }
             }
         }
+        cancelled.store(true, Relaxed);
+
+        let (index, contract_sigs) = winner?;
+        let setup = setups.swap_remove(index);
+        log::info!("Maker {} won the first hop race", setup.maker.address);
+        Some((setup, contract_sigs))
+    }
+
+    /// Marks a maker which failed to provide the sender's contract signatures as bad. A maker which
+    /// declined the swap isn't at fault, and is only skipped for the rest of the swap.
+    fn mark_sender_sigs_failure(&mut self, maker: &OfferAndAddress, error: &TakerError) {
+        if let TakerError::SwapDeclined(reason) = error {
+            log::info!(
+                "Maker {} declined the swap for {}, trying another one",
+                maker.address,
+                reason
+            );
+            self.ongoing_swap_state.declined_makers.push(maker.clone());
+        } else {
+            self.offerbook
+                .add_bad_maker(maker, BanReason::SignatureExchange);
+        }
+    }
+
+    /// Request signatures for sender side of the swap.
+    /// Keep trying until `first_connect_attempts` limit, with time delay of `first_connect_sleep_delay_sec`.
+    fn req_sigs_for_sender<S: SwapCoin>(
+        &self,
+        maker_address: &MakerAddress,
+        outgoing_swapcoins: &[S],
+        maker_multisig_nonces: &[SecretKey],
+        maker_hashlock_nonces: &[SecretKey],
+        locktime: u16,
+    ) -> Result<ContractSigsForSender, TakerError> {
+        let mut stream = connect_to_maker(
+            &maker_address.to_string(),
+            &self.config,
+            Duration::from_secs(FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC),
+        )?;
+        req_sigs_for_sender_with_retries(
+            &mut TracedStream::new(&mut stream, &self.protocol_trace),
+            maker_address,
+            outgoing_swapcoins,
+            maker_multisig_nonces,
+            maker_hashlock_nonces,
+            locktime,
+            &AtomicBool::new(false),
+        )
     }
 
     /// Request signatures for receiver side of the swap.
@@ -1491,8 +2354,6 @@ impl Taker {
         incoming_swapcoins: &[S],
         receivers_contract_txes: &[Transaction],
     ) -> Result<ContractSigsForRecvr, TakerError> {
-        let reconnect_time_out = Duration::from_secs(TCP_TIMEOUT_SECONDS);
-
         // Configurable reconnection attempts for testing
         let reconnect_attempts = if cfg!(feature = "integration-test") {
             10
@@ -1510,17 +2371,12 @@ impl Taker {
         let mut ii = 0;
 
         let maker_addr_str = maker_address.to_string();
-        let mut socket = match self.config.connection_type {
-            ConnectionType::CLEARNET => TcpStream::connect(maker_addr_str.clone())?,
-            ConnectionType::TOR => Socks5Stream::connect(
-                format!("127.0.0.1:{}", self.config.socks_port).as_str(),
-                &*maker_addr_str,
-            )?
-            .into_inner(),
-        };
-
-        socket.set_read_timeout(Some(reconnect_time_out))?;
-        socket.set_write_timeout(Some(reconnect_time_out))?;
+        let mut stream = connect_to_maker(
+            &maker_addr_str,
+            &self.config,
+            Duration::from_secs(self.config.signature_exchange_timeout),
+        )?;
+        let mut socket = TracedStream::new(&mut stream, &self.protocol_trace);
 
         loop {
             ii += 1;
@@ -1566,6 +2422,9 @@ impl Taker {
     /// Pass around the Maker's multisig privatekeys. Saves all the data in wallet file. This marks
     /// the ends of swap round.
     fn settle_all_swaps(&mut self) -> Result<(), TakerError> {
+        // The preimage releases our outgoing coins, so make sure we get paid in full first.
+        self.check_incoming_amount()?;
+
         let mut outgoing_privkeys: Option<Vec<MultisigPrivkey>> = None;
 
         // Because the last peer info is the Taker, we take upto (0..n-1), where n = peer_info.len()
@@ -1667,7 +2526,8 @@ impl Taker {
                                         reattempt limit exceeded",
                                 &maker_address.address,
                             );
-                            self.offerbook.add_bad_maker(maker_address);
+                            self.offerbook
+                                .add_bad_maker(maker_address, BanReason::Unreachable);
                             return Err(e);
                         }
                     }
@@ -1686,23 +2546,18 @@ impl Taker {
         senders_multisig_redeemscripts: &[ScriptBuf],
         receivers_multisig_redeemscripts: &[ScriptBuf],
     ) -> Result<(), TakerError> {
-        let maker_addr_str = maker_address.to_string();
-        let mut socket = match self.config.connection_type {
-            ConnectionType::CLEARNET => TcpStream::connect(maker_addr_str.clone())?,
-            ConnectionType::TOR => Socks5Stream::connect(
-                format!("127.0.0.1:{}", self.config.socks_port).as_str(),
-                &*maker_addr_str,
-            )?
-            .into_inner(),
-        };
-
-        socket.set_read_timeout(Some(Duration::from_secs(TCP_TIMEOUT_SECONDS)))?;
-        socket.set_write_timeout(Some(Duration::from_secs(TCP_TIMEOUT_SECONDS)))?;
-        handshake_maker(&mut socket)?;
+        let mut stream = connect_to_maker(
+            &maker_address.to_string(),
+            &self.config,
+            Duration::from_secs(self.config.settlement_timeout),
+        )?;
+        let mut socket = TracedStream::new(&mut stream, &self.protocol_trace);
+        let compression = handshake_maker(&mut socket)?;
 
         log::info!("===> HashPreimage | {}", maker_address);
         let maker_private_key_handover = send_hash_preimage_and_get_private_keys(
             &mut socket,
+            compression,
             senders_multisig_redeemscripts,
             receivers_multisig_redeemscripts,
             &self.ongoing_swap_state.active_preimage,
@@ -1744,40 +2599,191 @@ impl Taker {
             ret
         })?;
         log::info!("===> PrivateKeyHandover | {}", maker_address);
-        send_message(
-            &mut socket,
+        socket.send(
             &TakerToMakerMessage::RespPrivKeyHandover(PrivKeyHandover {
                 multisig_privkeys: privkeys_reply,
             }),
+            false,
         )?;
         Ok(())
     }
 
     // ######## UTILITY AND HELPERS ############
 
+    /// Chooses the candidate makers of the first hop, as per the `first_hop_ordering` config.
+    fn choose_first_hop_candidates(&self) -> Result<Vec<OfferAndAddress>, TakerError> {
+        let width = match self.config.first_hop_ordering {
+            FirstHopOrdering::Sequential => 1,
+            FirstHopOrdering::Race => self.config.first_hop_race_width.max(1),
+        };
+        let mut candidates = vec![self.choose_next_maker()?.clone()];
+        while candidates.len() < width {
+            let chosen = candidates
+                .iter()
+                .chain(&self.ongoing_swap_state.declined_makers)
+                .collect::<Vec<_>>();
+            // Race as many makers as the offerbook has, if fewer.
+            let Ok(next) = self.select_maker(&self.ongoing_swap_state.swap_params, &chosen) else {
+                break;
+            };
+            candidates.push(next.clone());
+        }
+        Ok(candidates)
+    }
+
     /// Choose a suitable **untried** maker address from the offerbook that fits the swap params.
     fn choose_next_maker(&self) -> Result<&OfferAndAddress, TakerError> {
-        let send_amount = self.ongoing_swap_state.swap_params.send_amount;
-        if send_amount == Amount::ZERO {
+        let chosen = self
+            .ongoing_swap_state
+            .peer_infos
+            .iter()
+            .map(|pi| &pi.peer)
+            .chain(&self.ongoing_swap_state.declined_makers)
+            .collect::<Vec<_>>();
+        self.select_maker(&self.ongoing_swap_state.swap_params, &chosen)
+    }
+
+    /// Good makers whose offer accepts the send amount and the hashlock variant of the swap.
+    fn makers_for_swap(&self, send_amount: Amount) -> Vec<&OfferAndAddress> {
+        self.offerbook
+            .all_good_makers()
+            .into_iter()
+            .filter(|oa| {
+                send_amount >= Amount::from_sat(oa.offer.min_size)
+                    && send_amount <= Amount::from_sat(oa.offer.max_size)
+                    && oa.offer.supports_hashlock(self.config.hashlock_variant)
+            })
+            .collect()
+    }
+
+    /// Selects the next maker of a route as per the `maker_selection` config, among the good makers
+    /// accepting the send amount and split count of the [SwapParams]. The makers already `chosen` for
+    /// the route are skipped. With `prefer_known_good`, the maker with the most completed swaps goes first.
+    fn select_maker(
+        &self,
+        swap_params: &SwapParams,
+        chosen: &[&OfferAndAddress],
+    ) -> Result<&OfferAndAddress, TakerError> {
+        if swap_params.send_amount == Amount::ZERO {
             return Err(TakerError::SendAmountNotSet);
         }
 
         // Ensure that we don't select a maker we are already swaping with.
-        Ok(self
-            .offerbook
-            .all_good_makers()
+        let candidates = self
+            .makers_for_swap(swap_params.send_amount)
+            .into_iter()
+            .filter(|oa| oa.offer.accepts_tx_count(swap_params.tx_count) && !chosen.contains(oa))
+            .collect::<Vec<_>>();
+
+        let known_good = if self.config.prefer_known_good {
+            self.offerbook.pick_known_good(&candidates)
+        } else {
+            None
+        };
+
+        let next_maker = known_good.or_else(|| match self.config.maker_selection {
+            MakerSelection::FirstFit => candidates.first().copied(),
+            MakerSelection::LatencyDiversity => {
+                let chosen = chosen.iter().map(|oa| &oa.address).collect::<Vec<_>>();
+                self.offerbook.pick_latency_diverse(&candidates, &chosen)
+            }
+        });
+
+        next_maker.ok_or(TakerError::NotEnoughMakersInOfferBook)
+    }
+
+    /// Picks the number of funding txs of a swap round, within the `min_tx_count` and `max_tx_count` config.
+    ///
+    /// The highest count is lowered so that at least `maker_count` makers accept it, and the wallet has
+    /// a coin to fund each funding tx with.
+    fn pick_tx_count(&mut self, swap_params: &SwapParams) -> Result<u32, TakerError> {
+        let mut maker_limits = self
+            .makers_for_swap(swap_params.send_amount)
             .iter()
-            .find(|oa| {
-                send_amount >= Amount::from_sat(oa.offer.min_size)
-                    && send_amount <= Amount::from_sat(oa.offer.max_size)
-                    && !self
-                        .ongoing_swap_state
-                        .peer_infos
-                        .iter()
-                        .map(|pi| &pi.peer)
-                        .any(|noa| noa == **oa)
+            .map(|oa| match oa.offer.max_tx_count {
+                0 => u32::MAX,
+                limit => limit,
             })
-            .ok_or(TakerError::NotEnoughMakersInOfferBook)?)
+            .collect::<Vec<_>>();
+        maker_limits.sort_unstable_by(|a, b| b.cmp(a));
+        let maker_cap = swap_params
+            .maker_count
+            .checked_sub(1)
+            .and_then(|i| maker_limits.get(i).copied())
+            .unwrap_or(u32::MAX);
+        let coin_cap = self.wallet.list_selectable_coins()?.len() as u32;
+
+        Ok(random_tx_count(
+            self.rng.as_mut(),
+            self.config.min_tx_count,
+            self.config.max_tx_count,
+            maker_cap.min(coin_cap),
+        ))
+    }
+
+    /// Errors early if the route of the [SwapParams] is too long or too short for the config, or its
+    /// locktimes don't fit.
+    fn check_route_length(&self, swap_params: &SwapParams) -> Result<(), TakerError> {
+        if swap_params.maker_count > self.config.max_maker_count {
+            log::error!(
+                "Cannot swap with more than {} makers, requested {}",
+                self.config.max_maker_count,
+                swap_params.maker_count
+            );
+            return Err(TakerError::MakerCountTooHigh {
+                maker_count: swap_params.maker_count,
+                max_maker_count: self.config.max_maker_count,
+            });
+        }
+        refund_locktime(swap_params.maker_count)?;
+        check_safe_mode(self.wallet.store.network, &self.config, swap_params)
+    }
+
+    /// Syncs the offerbook for a swap with the [SwapParams], and errors if not enough good makers are left.
+    ///
+    /// On mainnet with the safe-mode, the makers failing the fidelity bond identity check are marked as bad.
+    fn sync_offerbook_for_swap(&mut self, swap_params: &SwapParams) -> Result<(), TakerError> {
+        log::info!("Syncing Offerbook");
+        self.sync_offerbook()?;
+
+        // On mainnet, only swap with makers proving they hold their fidelity bond right now.
+        if self.wallet.store.network == Network::Bitcoin && self.config.safe_mode {
+            for offer in self
+                .offerbook
+                .all_good_makers()
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+            {
+                if let Err(e) = self.verify_maker_identity(&offer) {
+                    log::warn!(
+                        "Safe-mode: maker {} failed the fidelity bond identity check: {:?}",
+                        offer.address,
+                        e
+                    );
+                    self.offerbook
+                        .add_bad_maker(&offer, BanReason::FailedIdentityCheck);
+                }
+            }
+        }
+
+        // Error early if hop_count > available good makers.
+        if swap_params.maker_count > self.offerbook.all_good_makers().len() {
+            log::error!(
+                "Not enough makers in the offerbook. Required {}, avaialable {}",
+                swap_params.maker_count,
+                self.offerbook.all_good_makers().len()
+            );
+            return Err(TakerError::NotEnoughMakersInOfferBook);
+        }
+
+        // Error early if less than 2 makers.
+        if swap_params.maker_count < 2 {
+            log::error!("Cannot swap with less than 2 makers");
+            return Err(ProtocolError::General("Swap maker count < 2").into());
+        }
+
+        Ok(())
     }
 
     /// Get the [Preimage] of the ongoing swap. If no swap is in progress will return a `[0u8; 32]`.
@@ -1786,8 +2792,8 @@ impl Taker {
     }
 
     /// Get the [Preimage] hash for the ongoing swap. If no swap is in progress will return `hash160([0u8; 32])`.
-    fn get_preimage_hash(&self) -> Hash160 {
-        Hash160::hash(self.get_preimage())
+    fn get_preimage_hash(&self) -> Hashvalue {
+        Hashvalue::from_preimage(self.config.hashlock_variant, self.get_preimage())
     }
 
     /// Clear the [OngoingSwapState].
@@ -1800,6 +2806,26 @@ impl Taker {
         self.offerbook.get_bad_makers()
     }
 
+    /// Lists the banned makers, with the reason and time of each ban.
+    ///
+    /// Bans are kept across offerbook syncs, and lifted after `bad_maker_ban_duration`.
+    pub fn list_bad_makers(&self) -> &[BadMaker] {
+        self.offerbook.list_bad_makers()
+    }
+
+    /// Lifts the ban of the maker at `address` (`<host>:<port>`), to give it another chance.
+    ///
+    /// Blacklisted makers are banned again at the next sync. Returns `false` if the maker wasn't banned.
+    pub fn unban_maker(&mut self, address: &str) -> Result<bool, TakerError> {
+        let unbanned = self.offerbook.unban_maker(&MakerAddress::new(address)?);
+        if unbanned {
+            self.offerbook
+                .write_to_disk(&self.data_dir.join("offerbook.dat"))?;
+            log::info!("Unbanned maker {}", address);
+        }
+        Ok(unbanned)
+    }
+
     /// Save all the finalized swap data and reset the [OngoingSwapState].
     fn save_and_reset_swap_round(&mut self) -> Result<(), TakerError> {
         // Mark incoiming swapcoins as done
@@ -1827,43 +2853,359 @@ impl Taker {
         Ok(())
     }
 
+    /// Appends the terms agreed with every maker of the settled swap to the swap history file.
+    fn record_completed_swap(&self) -> Result<(), TakerError> {
+        let state = &self.ongoing_swap_state;
+
+        // The funding amount and refund locktime of every hop, from the Taker to the Taker.
+        let mut hop_terms = vec![hop_terms_of(&state.outgoing_swapcoins)?];
+        for watchonly_swapcoins in &state.watchonly_swapcoins {
+            hop_terms.push(hop_terms_of(watchonly_swapcoins)?);
+        }
+        hop_terms.push(hop_terms_of(&state.incoming_swapcoins)?);
+
+        let hops = state
+            .peer_infos
+            .iter()
+            .take(state.swap_params.maker_count)
+            .zip(hop_terms.windows(2))
+            .map(|(peer_info, terms)| HopRecord {
+                maker_address: peer_info.peer.address.to_string(),
+                fees: peer_info.peer.fees(),
+                contract_fee: MINER_FEE,
+                required_confirms: negotiate_required_confirms(
+                    state.swap_params.required_confirms,
+                    peer_info.peer.offer.required_confirms,
+                ),
+                incoming_locktime: terms[0].1,
+                outgoing_locktime: terms[1].1,
+                incoming_amount: terms[0].0,
+                outgoing_amount: terms[1].0,
+            })
+            .collect();
+
+        let record = SwapRecord {
+            id: state.id.clone(),
+            completed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            send_amount: state.swap_params.send_amount,
+            tx_count: state.swap_params.tx_count,
+            hops,
+            funding_outpoints: state
+                .outgoing_swapcoins
+                .iter()
+                .map(|swapcoin| swapcoin.contract_tx.input[0].previous_output)
+                .collect(),
+            received_outpoints: state
+                .incoming_swapcoins
+                .iter()
+                .map(|swapcoin| swapcoin.contract_tx.input[0].previous_output)
+                .collect(),
+        };
+        append_swap_record(&self.data_dir.join(SWAP_HISTORY_FILE), &record)
+    }
+
+    /// Counts the settled swap as a success of every maker of the route, and saves the offerbook.
+    fn record_maker_successes(&mut self) -> Result<(), TakerError> {
+        let makers = self
+            .ongoing_swap_state
+            .peer_infos
+            .iter()
+            .take(self.ongoing_swap_state.swap_params.maker_count)
+            .map(|peer_info| peer_info.peer.address.clone())
+            .collect::<Vec<_>>();
+        for address in &makers {
+            self.offerbook.record_swap_success(address);
+        }
+        self.offerbook
+            .write_to_disk(&self.data_dir.join("offerbook.dat"))
+    }
+
+    /// Returns all the completed swaps, oldest first, with the terms agreed with each maker.
+    pub fn get_swap_history(&self) -> Result<Vec<SwapRecord>, TakerError> {
+        read_swap_history(&self.data_dir.join(SWAP_HISTORY_FILE))
+    }
+
+    /// Checks on-chain that the coins received in the swap `swap_id` are unlinkable from the Taker's
+    /// original coins, spent into the swap.
+    ///
+    /// A received output is linked if its transaction is one of the Taker's funding txs, or spends one
+    /// of them, one of the original coins, or another output of a transaction the original coins came
+    /// from. Returns the linked received outputs: an empty list means the swap did its job.
+    pub fn verify_swap_unlinkability(&self, swap_id: &str) -> Result<Vec<OutPoint>, TakerError> {
+        let record = self
+            .get_swap_history()?
+            .into_iter()
+            .find(|record| record.id == swap_id)
+            .ok_or_else(|| TakerError::UnknownSwap(swap_id.to_string()))?;
+
+        // The Taker's funding txs, the original coins they spent, and the txs those came from.
+        let funding_txids = record
+            .funding_outpoints
+            .iter()
+            .map(|outpoint| outpoint.txid)
+            .collect::<HashSet<_>>();
+        let mut original_inputs = HashSet::new();
+        for txid in &funding_txids {
+            let funding_tx = self
+                .wallet
+                .rpc
+                .get_raw_transaction(txid, None)
+                .map_err(WalletError::Rpc)?;
+            original_inputs.extend(funding_tx.input.iter().map(|input| input.previous_output));
+        }
+        let original_parents = original_inputs
+            .iter()
+            .map(|outpoint| outpoint.txid)
+            .collect::<HashSet<_>>();
+
+        let mut linked = Vec::new();
+        for outpoint in &record.received_outpoints {
+            let received_tx = self
+                .wallet
+                .rpc
+                .get_raw_transaction(&outpoint.txid, None)
+                .map_err(WalletError::Rpc)?;
+            let shares_tx =
+                funding_txids.contains(&outpoint.txid) || original_parents.contains(&outpoint.txid);
+            let shares_parent = received_tx.input.iter().any(|input| {
+                original_inputs.contains(&input.previous_output)
+                    || funding_txids.contains(&input.previous_output.txid)
+                    || original_parents.contains(&input.previous_output.txid)
+            });
+            if shares_tx || shares_parent {
+                log::warn!(
+                    "Received output {} of swap {} is linked to the Taker's original coins",
+                    outpoint,
+                    swap_id
+                );
+                linked.push(*outpoint);
+            }
+        }
+        Ok(linked)
+    }
+
+    /// Exports the ongoing swap state and the unfinished swapcoins of the wallet into a recovery bundle
+    /// file, for a maintainer to diagnose a stuck swap. The bundle only holds the txids, scripts, locktimes
+    /// and states of the swapcoins, and no private key.
+    ///
+    /// **WARNING:** with `include_private_keys`, the bundle also holds the private keys of the swapcoins,
+    /// so that [Taker::import_recovery_bundle] into a fresh Taker recovers the coins. Anyone getting hold
+    /// of such a file can take the coins. It's refused unless `allow_key_export` is set in the config.
+    pub fn export_recovery_bundle(
+        &self,
+        path: &Path,
+        include_private_keys: bool,
+    ) -> Result<(), TakerError> {
+        if include_private_keys && !self.config.allow_key_export {
+            return Err(WalletError::General(
+                "Private key export is disabled. Set `allow_key_export = true` in the config to enable it"
+                    .to_string(),
+            )
+            .into());
+        }
+
+        let (mut incoming_swapcoins, mut outgoing_swapcoins) =
+            self.wallet.find_unfinished_swapcoins();
+        let state = &self.ongoing_swap_state;
+
+        // Swapcoins of the ongoing swap not yet stored in the wallet.
+        for incoming in &state.incoming_swapcoins {
+            if self
+                .wallet
+                .find_incoming_swapcoin(&incoming.get_multisig_redeemscript())
+                .is_none()
+            {
+                incoming_swapcoins.push(incoming.clone());
+            }
+        }
+        for outgoing in &state.outgoing_swapcoins {
+            if self
+                .wallet
+                .find_outgoing_swapcoin(&outgoing.get_multisig_redeemscript())
+                .is_none()
+            {
+                outgoing_swapcoins.push(outgoing.clone());
+            }
+        }
+
+        let ongoing_swap = (!state.id.is_empty()).then(|| SwapSnapshot {
+            id: state.id.clone(),
+            send_amount: state.swap_params.send_amount,
+            maker_count: state.swap_params.maker_count,
+            tx_count: state.swap_params.tx_count,
+            required_confirms: state.swap_params.required_confirms,
+            maker_addresses: state
+                .peer_infos
+                .iter()
+                .map(|info| info.peer.address.to_string())
+                .collect(),
+            funding_txids: state
+                .funding_txs
+                .iter()
+                .map(|(txs, _)| txs.iter().map(|tx| tx.compute_txid()).collect())
+                .collect(),
+            watchonly_contract_txs: state
+                .watchonly_swapcoins
+                .iter()
+                .map(|hop| hop.iter().map(|sc| sc.contract_tx.clone()).collect())
+                .collect(),
+        });
+
+        let swapcoins = incoming_swapcoins
+            .iter()
+            .map(SwapcoinSnapshot::from)
+            .chain(outgoing_swapcoins.iter().map(SwapcoinSnapshot::from))
+            .collect::<Vec<_>>();
+        let bundle = RecoveryBundle {
+            network: self.wallet.store.network,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            ongoing_swap,
+            swapcoins,
+            private_swapcoins: include_private_keys.then_some(PrivateSwapcoins {
+                incoming: incoming_swapcoins,
+                outgoing: outgoing_swapcoins,
+            }),
+        };
+        write_recovery_bundle(path, &bundle)?;
+        log::info!(
+            "Exported a recovery bundle with {} swapcoins to {:?}",
+            bundle.swapcoins.len(),
+            path
+        );
+        if include_private_keys {
+            log::warn!(
+                "The recovery bundle at {:?} holds the PRIVATE KEYS of the swapcoins. Anyone with the file can take the coins, only hand it over to someone you trust",
+                path
+            );
+        }
+        Ok(())
+    }
+
+    /// Imports the swapcoins of a recovery bundle exported by [Taker::export_recovery_bundle] with the
+    /// private keys, so that [Taker::recover_from_swap] recovers them.
+    ///
+    /// Swapcoins already in the wallet are skipped. Returns the number of imported swapcoins.
+    pub fn import_recovery_bundle(&mut self, path: &Path) -> Result<usize, TakerError> {
+        let bundle = read_recovery_bundle(path)?;
+        if bundle.network != self.wallet.store.network {
+            return Err(TakerError::InvalidRecoveryBundle(format!(
+                "Bundle is for {}, wallet is on {}",
+                bundle.network, self.wallet.store.network
+            )));
+        }
+        if let Some(swap) = &bundle.ongoing_swap {
+            log::info!(
+                "Recovery bundle of swap {} | amount: {} | makers: {:?} | funding txids: {:?}",
+                swap.id,
+                swap.send_amount,
+                swap.maker_addresses,
+                swap.funding_txids
+            );
+        }
+        let Some(private_swapcoins) = bundle.private_swapcoins else {
+            return Err(TakerError::InvalidRecoveryBundle(
+                "The bundle holds no private keys, it's only meant for diagnosis".to_string(),
+            ));
+        };
+
+        let mut imported = 0;
+        for incoming in &private_swapcoins.incoming {
+            if self
+                .wallet
+                .find_incoming_swapcoin(&incoming.get_multisig_redeemscript())
+                .is_none()
+            {
+                self.wallet.add_incoming_swapcoin(incoming);
+                imported += 1;
+            }
+        }
+        for outgoing in &private_swapcoins.outgoing {
+            if self
+                .wallet
+                .find_outgoing_swapcoin(&outgoing.get_multisig_redeemscript())
+                .is_none()
+            {
+                self.wallet.add_outgoing_swapcoin(outgoing);
+                imported += 1;
+            }
+        }
+        self.wallet.save_to_disk()?;
+        // Imports the descriptors of the new swapcoins into the node.
+        self.wallet.sync()?;
+        log::info!("Imported {} swapcoins from {:?}", imported, path);
+        Ok(imported)
+    }
+
     /// Checks if any contreact transactions have been broadcasted.
     /// Returns the txid list of all the broadcasted contract transaction.
     /// Empty vector if nothing is nothing is broadcasted. (usual case).
-    pub(crate) fn check_for_broadcasted_contract_txes(&self) -> Vec<Txid> {
+    ///
+    /// With a `from_height`, only the blocks above it and the mempool are scanned. The height checked up to
+    /// is returned along, to be passed to the next call. On RPC errors, nothing is reported as broadcasted and
+    /// the given height is returned, so the next call checks again.
+    pub(crate) fn check_for_broadcasted_contract_txes(
+        &self,
+        from_height: Option<u64>,
+    ) -> (Vec<Txid>, Option<u64>) {
         let contract_txids = self
             .ongoing_swap_state
             .incoming_swapcoins
             .iter()
-            .map(|sc| sc.contract_tx.compute_txid())
+            .flat_map(|sc| {
+                std::iter::once(sc.contract_tx.compute_txid())
+                    .chain(sc.get_escalated_contract_txids())
+            })
             .chain(
                 self.ongoing_swap_state
                     .outgoing_swapcoins
                     .iter()
-                    .map(|sc| sc.contract_tx.compute_txid()),
+                    .flat_map(|sc| {
+                        std::iter::once(sc.contract_tx.compute_txid())
+                            .chain(sc.get_escalated_contract_txids())
+                    }),
             )
             .chain(
                 self.ongoing_swap_state
                     .watchonly_swapcoins
                     .iter()
                     .flatten()
-                    .map(|sc| sc.contract_tx.compute_txid()),
+                    .flat_map(|sc| {
+                        // The makers between themselves hold the signatures of the fee-escalated variants.
+                        std::iter::once(sc.contract_tx.compute_txid()).chain(
+                            contract_fee_escalation()
+                                .into_iter()
+                                .filter_map(move |fee| {
+                                    create_fee_escalated_contract_tx(
+                                        &sc.contract_tx,
+                                        sc.funding_amount,
+                                        fee,
+                                    )
+                                    .ok()
+                                    .map(|tx| tx.compute_txid())
+                                }),
+                        )
+                    }),
             )
             .collect::<Vec<_>>();
 
         // TODO: Find out which txid was boradcasted first
         // This requires -txindex to be enabled in the node.
-        let seen_txids = contract_txids
-            .iter()
-            .filter(|txid| self.wallet.rpc.get_raw_transaction_info(txid, None).is_ok())
-            .cloned()
-            .collect::<Vec<Txid>>();
-
-        seen_txids
+        match find_broadcasted_txids(&self.wallet.rpc, &contract_txids, from_height) {
+            Ok((seen_txids, checked_height)) => (seen_txids, Some(checked_height)),
+            Err(e) => {
+                log::warn!("Could not check for broadcasted contract txs: {:?}", e);
+                (Vec::new(), from_height)
+            }
+        }
     }
 
     /// Recover from a bad swap
     pub fn recover_from_swap(&mut self) -> Result<(), TakerError> {
+        self.swap_status.set(SwapPhase::Recovering);
         let (incomings, outgoings) = self.wallet.find_unfinished_swapcoins();
 
         let incoming_contracts = incomings
@@ -1880,8 +3222,7 @@ impl Taker {
         for (contract_tx, redeemscript) in &incoming_contracts {
             if self
                 .wallet
-                .rpc
-                .get_raw_transaction_info(&contract_tx.compute_txid(), None)
+                .get_tx_confirmations(&contract_tx.compute_txid())
                 .is_ok()
             {
                 log::info!(
@@ -1908,11 +3249,11 @@ impl Taker {
         self.get_wallet_mut().sync()?;
 
         for outgoing in outgoings {
-            let contract_tx = outgoing.get_fully_signed_contract_tx()?;
+            let mut contract_tx = outgoing.get_fully_signed_contract_tx()?;
+            let reedemscript = outgoing.get_multisig_redeemscript();
             if self
                 .wallet
-                .rpc
-                .get_raw_transaction_info(&contract_tx.compute_txid(), None)
+                .get_tx_confirmations(&contract_tx.compute_txid())
                 .is_ok()
             {
                 log::info!(
@@ -1920,22 +3261,52 @@ impl Taker {
                     contract_tx.compute_txid()
                 );
             } else {
-                self.wallet.send_tx(&contract_tx)?;
-                log::info!(
-                    "Broadcasted Outgoing Contract | txid : {}",
-                    contract_tx.compute_txid()
-                );
+                match self.wallet.send_tx(&contract_tx) {
+                    Ok(_) => log::info!(
+                        "Broadcasted Outgoing Contract | txid : {}",
+                        contract_tx.compute_txid()
+                    ),
+                    // Too low fee for the current mempool, go for a fee-escalated variant.
+                    Err(WalletError::FeeTooLow { required }) => {
+                        log::warn!(
+                            "Outgoing Contract rejected, {} sats fee required. Bumping its fee | txid : {}",
+                            required,
+                            contract_tx.compute_txid()
+                        );
+                        match self
+                            .wallet
+                            .bump_outgoing_contract_fee(&reedemscript, DEFAULT_TX_FEE_RATE)?
+                        {
+                            Some((escalated_tx, _)) => contract_tx = escalated_tx,
+                            None => return Err(WalletError::FeeTooLow { required }.into()),
+                        }
+                    }
+                    Err(e) => return Err(e.into()),
+                }
             }
-            let reedemscript = outgoing.get_multisig_redeemscript();
             let timelock = outgoing.get_timelock()?;
             let next_internal = &self.wallet.get_next_internal_addresses(1)?[0];
 
             self.get_wallet_mut().sync()?;
 
+            // The wallet holds the contract tx actually broadcasted.
+            let outgoing = self
+                .wallet
+                .find_outgoing_swapcoin(&reedemscript)
+                .expect("outgoing swapcoin expected")
+                .clone();
             let timelock_spend =
                 self.wallet
                     .create_timelock_spend(&outgoing, next_internal, DEFAULT_TX_FEE_RATE)?;
-            outgoing_infos.push(((reedemscript, contract_tx), (timelock, timelock_spend)));
+            let broadcast_height = self
+                .wallet
+                .rpc
+                .get_block_count()
+                .map_err(WalletError::Rpc)?;
+            outgoing_infos.push((
+                (reedemscript, contract_tx, broadcast_height),
+                (timelock, timelock_spend),
+            ));
         }
 
         // Check for contract confirmations and broadcast timelocked transaction
@@ -1953,25 +3324,52 @@ impl Taker {
             if outgoing_infos.is_empty() {
                 break;
             }
-            for ((reedemscript, contract), (timelock, timelocked_tx)) in outgoing_infos.iter() {
+            for ((reedemscript, contract, broadcast_height), (timelock, timelocked_tx)) in
+                outgoing_infos.iter_mut()
+            {
                 // We have already broadcasted this tx, so skip
-                if timelock_boardcasted.contains(&timelocked_tx) {
+                if timelock_boardcasted.contains(&timelocked_tx.compute_txid()) {
                     continue;
                 }
                 // Check if the contract tx has reached required maturity
                 // Failure here means the transaction hasn't been broadcasted yet. So do nothing and try again.
-                if let Ok(result) = self
-                    .wallet
-                    .rpc
-                    .get_raw_transaction_info(&contract.compute_txid(), None)
+                if let Ok(confirmations) =
+                    self.wallet.get_tx_confirmations(&contract.compute_txid())
                 {
                     log::info!(
                         "Contract Tx : {}, reached confirmation : {:?}, required : {}",
                         contract.compute_txid(),
-                        result.confirmations,
+                        confirmations,
                         timelock
                     );
-                    if let Some(confirmation) = result.confirmations {
+                    let current_height = self
+                        .wallet
+                        .rpc
+                        .get_block_count()
+                        .map_err(WalletError::Rpc)?;
+                    if confirmations.is_none()
+                        && current_height >= *broadcast_height + CONTRACT_FEE_ESCALATION_BLOCKS
+                    {
+                        // Still stuck in the mempool, replace it by the next fee-escalated variant.
+                        match self
+                            .wallet
+                            .bump_outgoing_contract_fee(reedemscript, DEFAULT_TX_FEE_RATE)
+                        {
+                            Ok(Some((escalated_tx, timelock_spend))) => {
+                                log::info!(
+                                    "Replaced unconfirmed Contract Tx : {} by fee-escalated Contract Tx : {}",
+                                    contract.compute_txid(),
+                                    escalated_tx.compute_txid()
+                                );
+                                *contract = escalated_tx;
+                                *timelocked_tx = timelock_spend;
+                            }
+                            Ok(None) => {}
+                            Err(e) => log::warn!("Contract Tx fee bump failed : {:?}", e),
+                        }
+                        *broadcast_height = current_height;
+                    }
+                    if let Some(confirmation) = confirmations {
                         // Now the transaction is confirmed in a block, check for required maturity
                         if confirmation > (*timelock as u32) {
                             log::info!(
@@ -1984,7 +3382,7 @@ impl Taker {
                                 timelocked_tx.compute_txid()
                             );
                             self.wallet.send_tx(timelocked_tx)?;
-                            timelock_boardcasted.push(timelocked_tx);
+                            timelock_boardcasted.push(timelocked_tx.compute_txid());
 
                             let outgoing_removed = self
                                 .wallet
@@ -2025,39 +3423,105 @@ impl Taker {
             std::thread::sleep(block_wait_time);
         }
         log::info!("Recovery completed.");
+        self.swap_status.set(SwapPhase::Idle);
 
         Ok(())
     }
 
-    /// Synchronizes the offer book with addresses obtained from directory servers and local configurations.
-    pub fn sync_offerbook(&mut self) -> Result<(), TakerError> {
-        let dns_addr = match self.config.connection_type {
-            ConnectionType::CLEARNET => {
-                if cfg!(feature = "integration-test") {
-                    format!("127.0.0.1:{}", 8080)
-                } else {
-                    self.config.directory_server_address.clone()
+    /// Recovers from the failed swap inline, or leaves it to the recovery scheduler with `auto_recovery`.
+    fn recover_or_defer(&mut self) -> Result<(), TakerError> {
+        if self.config.auto_recovery {
+            // The swapcoins are already in the wallet file, the scheduler picks them up from there.
+            self.wallet.save_to_disk()?;
+            log::warn!("Recovery of the failed swap is left to the recovery scheduler");
+            Ok(())
+        } else {
+            self.recover_from_swap()
+        }
+    }
+
+    /// Runs the recovery of unfinished swaps in the background, until none is left.
+    ///
+    /// Checks the wallet for unfinished swapcoins right away, and then every `recovery_interval` seconds.
+    /// Each check drives [Taker::recover_from_swap] to completion, and a failed recovery is retried at the
+    /// next check. The progress is kept in the wallet file, so calling this again after a restart resumes
+    /// the recovery where it stopped.
+    ///
+    /// Does nothing unless `auto_recovery` is set in the config.
+    pub fn run_recovery_scheduler(&mut self) -> Result<(), TakerError> {
+        if !self.config.auto_recovery {
+            log::info!("Auto recovery disabled, recovery scheduler not started");
+            return Ok(());
+        }
+        let interval = Duration::from_secs(self.config.recovery_interval);
+        log::info!(
+            "Recovery scheduler started, checking every {}s",
+            interval.as_secs()
+        );
+        loop {
+            if let Err(e) = self.wallet.sync() {
+                log::warn!("Recovery scheduler could not sync the wallet : {:?}", e);
+            } else {
+                let (incomings, outgoings) = self.wallet.find_unfinished_swapcoins();
+                if incomings.is_empty() && outgoings.is_empty() {
+                    log::info!("No unfinished swapcoins left, recovery scheduler stopped");
+                    return Ok(());
+                }
+                log::info!(
+                    "Recovery scheduler found {} incoming and {} outgoing unfinished swapcoins",
+                    incomings.len(),
+                    outgoings.len()
+                );
+                match self.recover_from_swap() {
+                    // Checked again right away, to stop as soon as the recovery completes.
+                    Ok(()) => continue,
+                    Err(e) => log::warn!("Scheduled recovery failed, retrying later : {:?}", e),
                 }
             }
-            ConnectionType::TOR => self.config.directory_server_address.clone(),
-        };
+            std::thread::sleep(interval);
+        }
+    }
 
+    /// Synchronizes the offer book with addresses obtained from directory servers and local configurations.
+    pub fn sync_offerbook(&mut self) -> Result<(), TakerError> {
         #[cfg(not(feature = "integration-test"))]
         let socks_port = Some(self.config.socks_port);
 
         #[cfg(feature = "integration-test")]
         let socks_port = None;
 
-        log::info!("Fetching addresses from DNS: {}", dns_addr);
+        // Merge the makers from all directory servers, and fall back to the known makers if none is reachable.
+        let dns_result = fetch_addresses_from_directories(
+            socks_port,
+            &self.config.directory_server_addresses,
+            self.config.connection_type,
+        );
 
-        let addresses_from_dns =
-            match fetch_addresses_from_dns(socks_port, dns_addr, self.config.connection_type) {
-                Ok(dns_addrs) => dns_addrs,
-                Err(e) => {
-                    log::error!("Could not connect to DNS Server: {:?}", e);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let addresses_from_dns = match dns_result {
+            Ok(dns_addrs) => {
+                self.offerbook.directory_synced_at = Some(now);
+                dns_addrs
+            }
+            Err(e) => {
+                let cached_addrs = self.offerbook.cached_maker_addresses();
+                if cached_addrs.is_empty() {
                     return Err(e);
                 }
-            };
+                let staleness = match self.offerbook.directory_synced_at {
+                    Some(synced_at) => format!("{} secs ago", now.saturating_sub(synced_at)),
+                    None => "never".to_string(),
+                };
+                log::warn!(
+                    "No directory server is reachable. Using {} cached makers, last fetched from a directory server: {}",
+                    cached_addrs.len(),
+                    staleness
+                );
+                cached_addrs
+            }
+        };
 
         // For now, ask offers from everyone,
         // Because we don not have any smart update mechanism, not asking again could cause problem.
@@ -2065,29 +3529,59 @@ impl Taker {
         // TODO: Add smarter update mechanism, where DNS would keep a flag for every update of maker offers and taker
         // will selectively redownload the offer from those makers only.
         // Further TODO: The Offer book needs to be restructured to store a unqiue value per fidelity bond. Similar to DNS.
-        let offers = fetch_offer_from_makers(addresses_from_dns, &self.config)?;
+        let offers =
+            fetch_offer_from_makers(addresses_from_dns, &self.config, &self.protocol_trace)?;
 
         // TODO: Use better logic to update offerbook than to just rewrite everything.
-        self.offerbook = OfferBook::default();
+        self.offerbook.reset();
 
-        for offer in offers {
+        let expired = self
+            .offerbook
+            .expire_bans(now, self.config.bad_maker_ban_duration);
+        if expired > 0 {
+            log::info!("Lifted {} expired bad maker bans", expired);
+        }
+
+        // The config is checked at init, but it can be changed afterwards.
+        let allow_bondless =
+            self.config.allow_bondless_offers && self.wallet.store.network != Network::Bitcoin;
+
+        for (offer, rtt) in offers {
+            self.offerbook.record_rtt(&offer.address, rtt);
+            log::debug!("{:?}", offer);
+            let Some(fidelity) = &offer.offer.fidelity else {
+                if allow_bondless {
+                    log::info!(
+                        "Found bondless offer from {}. Adding it to our OfferBook",
+                        offer.address
+                    );
+                    self.offerbook.add_new_offer(&offer);
+                } else {
+                    log::warn!(
+                        "Skipping bondless offer from {}, bondless offers are not allowed",
+                        offer.address
+                    );
+                }
+                continue;
+            };
             log::info!(
                 "Found offer from {}. Verifying Fidelity Proof",
                 offer.address.to_string()
             );
-            log::debug!("{:?}", offer);
             if let Err(e) = self
                 .wallet
-                .verify_fidelity_proof(&offer.offer.fidelity, &offer.address.to_string())
+                .verify_fidelity_proof(fidelity, &offer.address.to_string())
             {
                 log::warn!(
                     "Fidelity Proof Verification failed with error: {:?}. Adding this to bad maker list : {}",
                     e,
                     offer.address.to_string()
                 );
-                self.offerbook.add_bad_maker(&offer);
+                self.offerbook
+                    .record_fidelity_failure(&offer, self.config.fidelity_failure_threshold);
             } else {
                 log::info!("Fideity Bond verification succes. Adding offer to our OfferBook");
+                self.offerbook.record_fidelity_success(&offer.address);
                 self.offerbook.add_new_offer(&offer);
             }
         }
@@ -2101,36 +3595,246 @@ impl Taker {
         Ok(&self.offerbook)
     }
 
-    /// Send any message to a maker
-    fn send_to_maker(
+    /// Moves the received swap coins to freshly derived internal addresses, after settlement.
+    ///
+    /// Every coin is sent in its own transaction, so the coins aren't linked together on-chain.
+    /// With `freshen_combined` set in the config, they are swept into a single transaction instead.
+    /// Coins whose fee would take more than [FRESHEN_MAX_FEE_PCT] of their value are skipped.
+    ///
+    /// Returns the txids of the broadcasted transactions.
+    pub fn post_swap_freshen(&mut self, fee_rate: f64) -> Result<Vec<Txid>, TakerError> {
+        let swap_coins = self.wallet.list_incoming_swap_coin_utxo_spend_info()?;
+        if swap_coins.is_empty() {
+            log::info!("No received swap coins to freshen");
+            return Ok(Vec::new());
+        }
+
+        let coin_groups = if self.config.freshen_combined {
+            vec![swap_coins]
+        } else {
+            swap_coins.into_iter().map(|coin| vec![coin]).collect()
+        };
+        // Derived in one go, as the next unused address only changes once a tx is seen.
+        let fresh_addresses = self
+            .wallet
+            .get_next_internal_addresses(coin_groups.len() as u32)?;
+
+        let mut txids = Vec::new();
+        for (coins, address) in coin_groups.iter().zip(fresh_addresses) {
+            let value = coins.iter().map(|(utxo, _)| utxo.amount).sum::<Amount>();
+            let tx =
+                match self
+                    .wallet
+                    .spend_from_wallet(fee_rate, Destination::Sweep(address), coins)
+                {
+                    Ok(tx) => tx,
+                    Err(WalletError::InsufficientFund { .. }) => {
+                        log::warn!("Skipping freshen of {}, it can't pay the fee", value);
+                        continue;
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+            let fee = value - tx.output[0].value;
+            if fee.to_sat() * 100 > value.to_sat() * FRESHEN_MAX_FEE_PCT {
+                log::warn!(
+                    "Skipping freshen of {}, the fee of {} is more than {}% of it",
+                    value,
+                    fee,
+                    FRESHEN_MAX_FEE_PCT
+                );
+                continue;
+            }
+            let txid = self.wallet.send_tx(&tx)?;
+            log::info!("Freshened {} of swap coins in tx {}", value, txid);
+            txids.push(txid);
+        }
+
+        self.wallet.sync_no_fail();
+        Ok(txids)
+    }
+
+    /// Lists the change outputs of the past swap funding txs, that can be spent along with the received
+    /// swap coins.
+    ///
+    /// Coin selection picks from the regular and the received swap coins alike, so a later spend of both
+    /// the change and a swap coin links the two sides of the swap on-chain. If any is found, a warning
+    /// suggesting [Taker::post_swap_freshen] is logged. Empty once no received swap coin is left to spend.
+    pub fn change_linkage_warning(&self) -> Result<Vec<OutPoint>, TakerError> {
+        let selectable = self.wallet.list_selectable_coins()?;
+        if !selectable
+            .iter()
+            .any(|(_, spend_info)| matches!(spend_info, UTXOSpendInfo::IncomingSwapCoin { .. }))
+        {
+            return Ok(Vec::new());
+        }
+
+        let funding_outpoints = self
+            .get_swap_history()?
+            .into_iter()
+            .flat_map(|record| record.funding_outpoints)
+            .collect::<HashSet<_>>();
+        let funding_txids = funding_outpoints
+            .iter()
+            .map(|outpoint| outpoint.txid)
+            .collect::<HashSet<_>>();
+
+        let at_risk = selectable
+            .iter()
+            .filter(|(_, spend_info)| matches!(spend_info, UTXOSpendInfo::SeedCoin { .. }))
+            .map(|(utxo, _)| OutPoint::new(utxo.txid, utxo.vout))
+            .filter(|outpoint| {
+                funding_txids.contains(&outpoint.txid) && !funding_outpoints.contains(outpoint)
+            })
+            .collect::<Vec<_>>();
+
+        if !at_risk.is_empty() {
+            log::warn!(
+                "{} change output(s) of swap funding txs can be spent along with the received swap \
+                coins, linking both sides of the swap. Freshen the swap coins first (freshen-swapcoins): {:?}",
+                at_risk.len(),
+                at_risk
+            );
+        }
+        Ok(at_risk)
+    }
+
+    /// Moves the whole data directory (wallets, offerbook, config and logs) to `new_dir`.
+    ///
+    /// The wallet and offerbook are saved first, then the directory is renamed in one step, or copied
+    /// over if it is on another filesystem. A failed copy is rolled back, leaving the old directory in use.
+    /// `new_dir` must not exist, or be empty. Useful to migrate the taker to an encrypted volume.
+    pub fn relocate_data_dir(&mut self, new_dir: PathBuf) -> Result<(), TakerError> {
+        if new_dir.exists() {
+            if fs::read_dir(&new_dir)?.next().is_some() {
+                return Err(TakerError::DataDirNotEmpty(new_dir));
+            }
+            fs::remove_dir(&new_dir)?;
+        }
+        if let Some(parent) = new_dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        self.offerbook
+            .write_to_disk(&self.data_dir.join("offerbook.dat"))?;
+        self.wallet.save_to_disk()?;
+
+        move_dir(&self.data_dir, &new_dir)?;
+
+        let wallet_path = match self.wallet.wallet_file_path().strip_prefix(&self.data_dir) {
+            Ok(relative_path) => new_dir.join(relative_path),
+            Err(_) => self.wallet.wallet_file_path().to_path_buf(),
+        };
+        self.wallet.set_wallet_file_path(wallet_path);
+        log::info!(
+            "Moved data directory from {:?} to {:?}",
+            self.data_dir,
+            new_dir
+        );
+        self.data_dir = new_dir;
+        Ok(())
+    }
+
+    /// Imports a blacklist of makers from a file, with one maker address (`<host>:<port>`) or
+    /// fidelity bond outpoint (`<txid>:<vout>`) per line. Lines starting with `#` are ignored.
+    ///
+    /// Blacklisted makers are always treated as bad, and are kept across offerbook syncs.
+    /// Returns the number of newly blacklisted entries.
+    pub fn import_bad_makers(&mut self, path: &Path) -> Result<usize, TakerError> {
+        let entries = read_blacklist(path)?;
+        let imported = self.offerbook.import_blacklist(entries);
+        self.offerbook
+            .write_to_disk(&self.data_dir.join("offerbook.dat"))?;
+        log::info!("Imported {} blacklisted makers from {:?}", imported, path);
+        Ok(imported)
+    }
+
+    /// Verifies that the maker at the offer's address controls the key of the fidelity bond
+    /// committed in that offer.
+    ///
+    /// The maker is sent a random challenge, which it must sign with its fidelity bond key. This catches
+    /// stale or spoofed offerbook entries, whose address is dead or hosts a different maker.
+    pub fn verify_maker_identity(
         &self,
-        maker_addr: &MakerAddress,
-        msg: TakerToMakerMessage,
+        offer_and_address: &OfferAndAddress,
     ) -> Result<(), TakerError> {
-        // Notify the maker that we are waiting for funding confirmation
-        let address = maker_addr.to_string();
-        let mut socket = match self.config.connection_type {
-            ConnectionType::CLEARNET => TcpStream::connect(address)?,
-            ConnectionType::TOR => Socks5Stream::connect(
-                format!("127.0.0.1:{}", self.config.socks_port).as_str(),
-                address.as_str(),
-            )?
-            .into_inner(),
+        // Bondless makers have no bond key to prove.
+        let bond = &offer_and_address
+            .offer
+            .fidelity
+            .as_ref()
+            .ok_or_else(|| WalletError::General("The maker has no fidelity bond".to_string()))?
+            .bond;
+        let address = offer_and_address.address.to_string();
+        let mut stream = connect_to_maker(
+            &address,
+            &self.config,
+            Duration::from_secs(self.config.offer_fetch_timeout),
+        )?;
+        let mut socket = TracedStream::new(&mut stream, &self.protocol_trace);
+        let compression = handshake_maker(&mut socket)?;
+
+        let mut nonce = [0u8; 32];
+        OsRng.fill_bytes(&mut nonce);
+
+        let msg = TakerToMakerMessage::ReqIdentityProof(IdentityChallenge {
+            bond_outpoint: bond.outpoint,
+            nonce,
+        });
+        log::info!("===> {} | {}", msg, address);
+        socket.send(&msg, false)?;
+
+        let proof = match read_maker_reply(&mut socket, compression)? {
+            MakerToTakerMessage::RespIdentityProof(proof) => proof,
+            any => {
+                return Err(ProtocolError::WrongMessage {
+                    expected: "RespIdentityProof".to_string(),
+                    received: format!("{}", any),
+                }
+                .into())
+            }
         };
+        log::info!("<=== RespIdentityProof | {}", address);
 
-        let reconnect_timeout = Duration::from_secs(TCP_TIMEOUT_SECONDS);
+        verify_identity_proof(bond, &nonce, &proof.sig)?;
+        log::info!("Maker identity verified | {}", address);
+        Ok(())
+    }
 
-        socket.set_write_timeout(Some(reconnect_timeout))?;
+    /// Send any message to a maker
+    /// Sends a [TakerToMakerMessage::Heartbeat] for the ongoing swap, and waits for the maker's acknowledgement.
+    fn send_heartbeat(&self, maker_addr: &MakerAddress) -> Result<(), TakerError> {
+        let mut stream = connect_to_maker(
+            &maker_addr.to_string(),
+            &self.config,
+            Duration::from_secs(self.config.signature_exchange_timeout),
+        )?;
+        let mut socket = TracedStream::new(&mut stream, &self.protocol_trace);
 
-        send_message(&mut socket, &msg)?;
+        let msg = TakerToMakerMessage::Heartbeat(self.ongoing_swap_state.id.clone());
+        socket.send(&msg, false)?;
         log::info!("===> {} | {}", msg, maker_addr);
 
-        Ok(())
+        match socket.read::<MakerToTakerMessage>(false)? {
+            MakerToTakerMessage::RespHeartbeat(id) if id == self.ongoing_swap_state.id => {
+                log::info!("<=== RespHeartbeat | {}", maker_addr);
+                Ok(())
+            }
+            msg => Err(ProtocolError::WrongMessage {
+                expected: "RespHeartbeat".to_string(),
+                received: msg.to_string(),
+            }
+            .into()),
+        }
     }
     /// Displays offer
     pub fn display_offer(&self, offer_and_address: &OfferAndAddress) -> String {
-        let bond = offer_and_address.offer.fidelity.bond.clone();
-        let bond_value = self.get_wallet().calculate_bond_value(&bond).unwrap();
+        let bond_value = match &offer_and_address.offer.fidelity {
+            Some(fidelity) => self
+                .get_wallet()
+                .calculate_bond_value(&fidelity.bond)
+                .unwrap(),
+            None => Amount::ZERO,
+        };
         format!(
             "offer data received:\n\
             - Base fee: {}\n\
@@ -2154,3 +3858,497 @@ impl Taker {
         )
     }
 }
+
+/// Requests signatures for the sender side of a hop, over the connection to the maker.
+/// Keeps trying until the `first_connect_attempts` limit, unless `cancelled` is set in between.
+fn req_sigs_for_sender_with_retries<S: SwapCoin>(
+    socket: &mut TracedStream,
+    maker_address: &MakerAddress,
+    outgoing_swapcoins: &[S],
+    maker_multisig_nonces: &[SecretKey],
+    maker_hashlock_nonces: &[SecretKey],
+    locktime: u16,
+    cancelled: &AtomicBool,
+) -> Result<ContractSigsForSender, TakerError> {
+    // Configurable reconnection attempts for testing
+    let first_connect_attempts = if cfg!(feature = "integration-test") {
+        10
+    } else {
+        FIRST_CONNECT_ATTEMPTS
+    };
+
+    // Custom sleep delay for testing.
+    let sleep_delay = if cfg!(feature = "integration-test") {
+        1
+    } else {
+        FIRST_CONNECT_SLEEP_DELAY_SEC
+    };
+
+    let mut ii = 0;
+
+    let maker_addr_str = maker_address.to_string();
+
+    loop {
+        ii += 1;
+        log::info!("===> ReqContractSigsForSender | {}", maker_addr_str);
+        match req_sigs_for_sender_once(
+            socket,
+            outgoing_swapcoins,
+            maker_multisig_nonces,
+            maker_hashlock_nonces,
+            locktime,
+        ) {
+            Ok(ret) => {
+                return {
+                    log::info!("<=== RespContractSigsForSender | {}", maker_addr_str);
+                    Ok(ret)
+                }
+            }
+            // The maker answered, there is no point retrying.
+            Err(TakerError::SwapDeclined(reason)) => {
+                log::info!("<=== RespSwapDeclined | {} | {}", maker_addr_str, reason);
+                return Err(TakerError::SwapDeclined(reason));
+            }
+            Err(e) => {
+                log::warn!(
+                    "Failed to connect to maker {} to request signatures for receiver, \
+                            reattempting {} of {} | error={:?}",
+                    &maker_addr_str,
+                    ii,
+                    first_connect_attempts,
+                    e
+                );
+                if cancelled.load(Relaxed) {
+                    log::info!("Request to maker {} cancelled", &maker_addr_str);
+                    return Err(e);
+                }
+                if ii <= first_connect_attempts {
+                    sleep(Duration::from_secs(
+                        if ii <= SHORT_LONG_SLEEP_DELAY_TRANSITION {
+                            sleep_delay
+                        } else {
+                            RECONNECT_LONG_SLEEP_DELAY
+                        },
+                    ));
+                    continue;
+                } else {
+                    log::warn!(
+                        "Failed to connect to maker {} to request signatures for receiver, \
+                                reattempt limit exceeded",
+                        &maker_addr_str,
+                    );
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// Splits `total` evenly into the fewest amounts not exceeding `chunk`. The amounts differ by at most a sat.
+fn split_into_chunks(total: Amount, chunk: Amount) -> Result<Vec<Amount>, TakerError> {
+    if total == Amount::ZERO || chunk == Amount::ZERO {
+        return Err(TakerError::SendAmountNotSet);
+    }
+    let count = total.to_sat().div_ceil(chunk.to_sat());
+    let (base, remainder) = (total.to_sat() / count, total.to_sat() % count);
+    Ok((0..count)
+        .map(|i| Amount::from_sat(base + u64::from(i < remainder)))
+        .collect())
+}
+
+/// Total funding amount and refund locktime of a hop's swapcoins.
+fn hop_terms_of<S: SwapCoin>(swapcoins: &[S]) -> Result<(Amount, u16), TakerError> {
+    let amount = swapcoins.iter().map(SwapCoin::get_funding_amount).sum();
+    let locktime = match swapcoins.first() {
+        Some(swapcoin) => swapcoin.get_timelock()?,
+        None => 0,
+    };
+    Ok((amount, locktime))
+}
+
+/// Refund locktime of a contract with `hops_after` further hops after it in the route.
+///
+/// Each later hop needs an earlier refund, so the locktime grows by [REFUND_LOCKTIME_STEP] per hop.
+/// Errors if the locktime doesn't fit the contract's `u16` relative locktime.
+pub(crate) fn refund_locktime(hops_after: usize) -> Result<u16, TakerError> {
+    u16::try_from(hops_after)
+        .ok()
+        .and_then(|hops| REFUND_LOCKTIME_STEP.checked_mul(hops))
+        .and_then(|step| REFUND_LOCKTIME.checked_add(step))
+        .ok_or(TakerError::MakerCountTooHigh {
+            maker_count: hops_after,
+            max_maker_count: ((u16::MAX - REFUND_LOCKTIME) / REFUND_LOCKTIME_STEP) as usize,
+        })
+}
+
+fn estimate_duration(
+    required_confirms: u32,
+    maker_count: usize,
+    connection_type: ConnectionType,
+) -> SwapDurationEstimate {
+    let hops = maker_count as u64 + 1;
+    let blocks = required_confirms.max(1) as u64 * hops;
+    let round_trips = ROUND_TRIPS_PER_HOP * maker_count as u64;
+
+    let (best_rtt, typical_rtt, worst_rtt) = match connection_type {
+        ConnectionType::CLEARNET => CLEARNET_ROUND_TRIP_MS,
+        ConnectionType::TOR => TOR_ROUND_TRIP_MS,
+    };
+
+    // Block arrival is a poisson process. A block arrives within half the interval in ~40% of cases,
+    // and takes longer than thrice the interval in only ~5% of cases.
+    let estimate = |block_wait_sec: u64, rtt_ms: u64| {
+        Duration::from_secs(blocks * block_wait_sec) + Duration::from_millis(round_trips * rtt_ms)
+    };
+
+    SwapDurationEstimate {
+        best: estimate(EXPECTED_BLOCK_INTERVAL_SEC / 2, best_rtt),
+        typical: estimate(EXPECTED_BLOCK_INTERVAL_SEC, typical_rtt),
+        worst: estimate(EXPECTED_BLOCK_INTERVAL_SEC * 3, worst_rtt),
+    }
+}
+
+/// The funding confirmation depth agreed with a maker, sent in the proof of funding.
+///
+/// Negotiated upward: the higher of the Taker's desired depth and the maker's advertised requirement,
+/// so both sides wait for the same depth.
+fn negotiate_required_confirms(desired: u32, maker_required: u32) -> u32 {
+    desired.max(maker_required)
+}
+
+/// Refuses mainnet swaps below the safe-mode's minimum maker count, unless the safe-mode is off.
+///
+/// Other networks are never restricted.
+fn check_safe_mode(
+    network: Network,
+    config: &TakerConfig,
+    swap_params: &SwapParams,
+) -> Result<(), TakerError> {
+    if network != Network::Bitcoin || !config.safe_mode {
+        return Ok(());
+    }
+    if swap_params.maker_count < config.safe_mode_min_makers {
+        log::error!(
+            "Safe-mode: refusing a mainnet swap with {} makers, at least {} are required. Override the safe-mode to proceed anyway",
+            swap_params.maker_count,
+            config.safe_mode_min_makers
+        );
+        return Err(TakerError::SafeModeMakerCount {
+            maker_count: swap_params.maker_count,
+            min_maker_count: config.safe_mode_min_makers,
+        });
+    }
+    Ok(())
+}
+
+/// Refuses bondless offers on mainnet, where every maker needs a fidelity bond.
+fn check_bondless_offers(network: Network, config: &TakerConfig) -> Result<(), TakerError> {
+    if network == Network::Bitcoin && config.allow_bondless_offers {
+        log::error!("allow_bondless_offers can't be enabled on mainnet");
+        return Err(TakerError::BondlessOffersOnMainnet);
+    }
+    Ok(())
+}
+
+/// Picks a random number of funding txs in `min..=max`, with `max` lowered to `cap`.
+///
+/// At least one funding tx is always picked, and never less than `min`, even above the `cap`.
+fn random_tx_count<R: RngCore + ?Sized>(rng: &mut R, min: u32, max: u32, cap: u32) -> u32 {
+    let min = min.max(1);
+    let max = max.min(cap).max(min);
+    rng.gen_range(min..=max)
+}
+
+/// Generate a fresh swap preimage from `rng`.
+fn generate_preimage<R: RngCore + ?Sized>(rng: &mut R) -> Preimage {
+    let mut preimage = [0u8; 32];
+    rng.fill_bytes(&mut preimage);
+    preimage
+}
+
+/// Generate a fresh random swap id of `len` bytes, unrelated to the preimage and not in `used_ids`.
+fn generate_swap_id<R: RngCore + ?Sized>(
+    rng: &mut R,
+    len: usize,
+    used_ids: &HashSet<String>,
+) -> String {
+    loop {
+        let mut id = vec![0u8; len];
+        rng.fill_bytes(&mut id);
+        let id = id.to_hex_string(Case::Lower);
+        if !used_ids.contains(&id) {
+            return id;
+        }
+        log::warn!("Swap id {} is already used, generating another one", id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{maker::MAX_ACCEPTED_LOCKTIME, protocol::HashlockVariant};
+
+    #[test]
+    fn test_bondless_offers_rejected_on_mainnet() {
+        let config = TakerConfig {
+            allow_bondless_offers: true,
+            ..TakerConfig::default()
+        };
+        assert!(matches!(
+            check_bondless_offers(Network::Bitcoin, &config),
+            Err(TakerError::BondlessOffersOnMainnet)
+        ));
+        assert!(check_bondless_offers(Network::Regtest, &config).is_ok());
+        assert!(check_bondless_offers(Network::Bitcoin, &TakerConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn test_safe_mode_on_mainnet() {
+        let swap_params = SwapParams {
+            send_amount: Amount::from_sat(500000),
+            maker_count: 2,
+            tx_count: 3,
+            required_confirms: 1,
+            ..Default::default()
+        };
+        let config = TakerConfig::default();
+
+        // A 2 hop swap is below the mainnet minimum.
+        assert!(matches!(
+            check_safe_mode(Network::Bitcoin, &config, &swap_params),
+            Err(TakerError::SafeModeMakerCount {
+                maker_count: 2,
+                min_maker_count: 3
+            })
+        ));
+
+        // It proceeds with the override.
+        let overridden = TakerConfig {
+            safe_mode: false,
+            ..config.clone()
+        };
+        assert!(check_safe_mode(Network::Bitcoin, &overridden, &swap_params).is_ok());
+
+        // Long enough routes, and other networks aren't restricted.
+        let three_hops = SwapParams {
+            maker_count: 3,
+            ..swap_params.clone()
+        };
+        assert!(check_safe_mode(Network::Bitcoin, &config, &three_hops).is_ok());
+        assert!(check_safe_mode(Network::Regtest, &config, &swap_params).is_ok());
+    }
+
+    #[test]
+    fn test_random_tx_count() {
+        use bitcoin::secp256k1::rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let counts = (0..50)
+            .map(|_| random_tx_count(&mut rng, 2, 5, u32::MAX))
+            .collect::<HashSet<_>>();
+        assert_eq!(counts, HashSet::from([2, 3, 4, 5]));
+
+        // Capped by the makers or the wallet coins.
+        assert!((0..50).all(|_| (2..=3).contains(&random_tx_count(&mut rng, 2, 5, 3))));
+
+        // Never below the minimum, nor zero.
+        assert_eq!(random_tx_count(&mut rng, 2, 5, 1), 2);
+        assert_eq!(random_tx_count(&mut rng, 0, 0, u32::MAX), 1);
+    }
+
+    #[test]
+    fn test_swap_id_independent_of_preimage() {
+        use bitcoin::secp256k1::rand::{rngs::StdRng, SeedableRng};
+
+        // Swaps draw the preimage, then the id, from the same RNG. No 8 bytes of an id appear anywhere
+        // in its preimage, and the ids are unique.
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut ids = HashSet::new();
+        for _ in 0..1000 {
+            let preimage = generate_preimage(&mut rng).to_hex_string(Case::Lower);
+            let id = generate_swap_id(&mut rng, SWAP_ID_LEN, &HashSet::new());
+            assert_eq!(id.len(), SWAP_ID_LEN * 2);
+            assert!((0..=id.len() - 16)
+                .step_by(2)
+                .all(|i| !preimage.contains(&id[i..i + 16])));
+            assert!(ids.insert(id));
+        }
+    }
+
+    #[test]
+    fn test_swap_id_skips_used_ids() {
+        use bitcoin::secp256k1::rand::{rngs::StdRng, SeedableRng};
+
+        // The same seed draws the same id first, which is used, so another one is drawn.
+        let used_id = generate_swap_id(&mut StdRng::seed_from_u64(42), 8, &HashSet::new());
+        let used_ids = HashSet::from([used_id.clone()]);
+        let id = generate_swap_id(&mut StdRng::seed_from_u64(42), 8, &used_ids);
+        assert_eq!(id.len(), 16);
+        assert_ne!(id, used_id);
+    }
+
+    #[test]
+    fn test_seeded_rng_reproduces_swap_secrets() {
+        use bitcoin::secp256k1::rand::{rngs::StdRng, SeedableRng};
+        use std::str::FromStr;
+
+        let tweakable_point = PublicKey::from_str(
+            "032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af",
+        )
+        .unwrap();
+
+        // The secrets drawn by a swap with 2 makers and 3 txs: the preimage and the swap id, then the
+        // nonces of each maker.
+        let swap_secrets = |rng: &mut dyn RngCore| {
+            let preimage = generate_preimage(rng);
+            let id = generate_swap_id(rng, SWAP_ID_LEN, &HashSet::new());
+            let nonces = (0..2)
+                .map(|_| {
+                    let (_, multisig_nonces, _, hashlock_nonces) =
+                        generate_maker_keys(&tweakable_point, 3, rng).unwrap();
+                    (multisig_nonces, hashlock_nonces)
+                })
+                .collect::<Vec<_>>();
+            (preimage, id, nonces)
+        };
+
+        let first_swap = swap_secrets(&mut StdRng::seed_from_u64(42));
+        let second_swap = swap_secrets(&mut StdRng::seed_from_u64(42));
+        assert_eq!(first_swap, second_swap);
+
+        let other_seed = swap_secrets(&mut StdRng::seed_from_u64(43));
+        assert_ne!(first_swap.0, other_seed.0);
+        assert_ne!(first_swap.1, other_seed.1);
+        assert_ne!(first_swap.2, other_seed.2);
+    }
+
+    #[test]
+    fn test_contract_transaction_explainer() {
+        use std::str::FromStr;
+
+        let hashlock_pubkey = PublicKey::from_str(
+            "032e58afe51f9ed8ad3cc7897f634d881fdbe49a81564629ded8156bebd2ffd1af",
+        )
+        .unwrap();
+        let timelock_pubkey = PublicKey::from_str(
+            "039b6347398505f5ec93826dc61c19f47c66c0283ee9be980e29ce325a0f4679ef",
+        )
+        .unwrap();
+        let hashvalue = Hashvalue::from_preimage(HashlockVariant::Hash160, b"coinswap preimage");
+        let locktime = 300;
+        let contract =
+            create_contract_redeemscript(&hashlock_pubkey, &timelock_pubkey, &hashvalue, &locktime);
+
+        let explanation = Taker::contract_transaction_explainer(&contract).unwrap();
+        assert_eq!(
+            explanation,
+            ContractExplanation {
+                hashlock_pubkey,
+                timelock_pubkey,
+                hashvalue,
+                locktime,
+            }
+        );
+        assert!(explanation.to_string().contains("300 blocks"));
+        assert!(explanation.to_string().contains(&hashvalue.to_string()));
+
+        // A script with anything appended isn't explained.
+        let mut tampered = contract.into_bytes();
+        tampered.push(0x75); // OP_DROP
+        assert!(Taker::contract_transaction_explainer(&ScriptBuf::from_bytes(tampered)).is_err());
+
+        // Neither is an arbitrary script of the same size.
+        let push_only = ScriptBuf::builder()
+            .push_slice([1u8; 64])
+            .push_slice([1u8; 64])
+            .into_script();
+        assert!(Taker::contract_transaction_explainer(&push_only).is_err());
+    }
+
+    #[test]
+    fn test_swap_duration_estimate_scaling() {
+        let base = estimate_duration(1, 2, ConnectionType::CLEARNET);
+        assert!(base.best < base.typical && base.typical < base.worst);
+
+        let more_confirms = estimate_duration(3, 2, ConnectionType::CLEARNET);
+        assert!(more_confirms.best > base.best);
+        assert!(more_confirms.typical > base.typical);
+        assert!(more_confirms.worst > base.worst);
+        // Three confirmations for each of the three hops.
+        assert!(more_confirms.typical >= Duration::from_secs(9 * EXPECTED_BLOCK_INTERVAL_SEC));
+
+        let more_makers = estimate_duration(1, 4, ConnectionType::CLEARNET);
+        assert!(more_makers.best > base.best);
+        assert!(more_makers.typical > base.typical);
+        assert!(more_makers.worst > base.worst);
+
+        let tor = estimate_duration(1, 2, ConnectionType::TOR);
+        assert!(tor.typical > base.typical);
+    }
+
+    #[test]
+    fn test_split_into_chunks() {
+        let sats = |amounts: Vec<Amount>| amounts.iter().map(|a| a.to_sat()).collect::<Vec<_>>();
+
+        assert_eq!(
+            sats(
+                split_into_chunks(Amount::from_sat(1_000_000), Amount::from_sat(500_000)).unwrap()
+            ),
+            vec![500_000, 500_000]
+        );
+        // No small leftover round, the remainder is spread over the rounds.
+        assert_eq!(
+            sats(
+                split_into_chunks(Amount::from_sat(1_000_001), Amount::from_sat(300_000)).unwrap()
+            ),
+            vec![250_001, 250_000, 250_000, 250_000]
+        );
+        assert_eq!(
+            sats(split_into_chunks(Amount::from_sat(100_000), Amount::from_sat(300_000)).unwrap()),
+            vec![100_000]
+        );
+        assert!(matches!(
+            split_into_chunks(Amount::from_sat(100_000), Amount::ZERO),
+            Err(TakerError::SendAmountNotSet)
+        ));
+        assert!(matches!(
+            split_into_chunks(Amount::ZERO, Amount::from_sat(100_000)),
+            Err(TakerError::SendAmountNotSet)
+        ));
+    }
+
+    #[test]
+    fn test_negotiate_required_confirms() {
+        // The maker requires more, the Taker waits for it.
+        assert_eq!(negotiate_required_confirms(1, 3), 3);
+        // The Taker wants more, the maker accepts the deeper target.
+        assert_eq!(negotiate_required_confirms(6, 3), 6);
+        assert_eq!(negotiate_required_confirms(2, 2), 2);
+    }
+
+    #[test]
+    fn test_refund_locktime_overflow() {
+        assert_eq!(refund_locktime(0).unwrap(), REFUND_LOCKTIME);
+        assert_eq!(refund_locktime(2).unwrap(), 60);
+
+        // The first maker of the longest default route is within the default maker cap.
+        assert!(refund_locktime(MAX_MAKER_COUNT - 1).unwrap() <= MAX_ACCEPTED_LOCKTIME);
+
+        let max_maker_count = ((u16::MAX - REFUND_LOCKTIME) / REFUND_LOCKTIME_STEP) as usize;
+        assert!(refund_locktime(max_maker_count).is_ok());
+
+        // Large enough to wrap the u16 locktime, or even the u16 hop count.
+        for maker_count in [max_maker_count + 1, 1 << 16, usize::MAX] {
+            match refund_locktime(maker_count) {
+                Err(TakerError::MakerCountTooHigh {
+                    maker_count: count,
+                    max_maker_count: max,
+                }) => {
+                    assert_eq!(count, maker_count);
+                    assert_eq!(max, max_maker_count);
+                }
+                other => panic!("Expected MakerCountTooHigh, got {:?}", other),
+            }
+        }
+    }
+}
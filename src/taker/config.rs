@@ -3,8 +3,86 @@
 //!  Represents the configuration options for the Taker module, controlling behaviors
 //! such as refund locktime, connection attempts, sleep delays, and timeouts.
 
-use crate::utill::{get_taker_dir, parse_field, parse_toml, ConnectionType};
-use std::{io, io::Write, path::Path};
+use crate::{
+    protocol::HashlockVariant,
+    taker::{
+        api::{
+            FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC, FIRST_HOP_RACE_WIDTH, MAX_MAKER_COUNT,
+            RECOVERY_INTERVAL, SAFE_MODE_MIN_MAKER_COUNT, SWAP_ID_LEN, TCP_TIMEOUT_SECONDS,
+        },
+        offers::{BAD_MAKER_BAN_DURATION, FIDELITY_FAILURE_THRESHOLD, OFFER_FETCH_CONCURRENCY},
+    },
+    utill::{
+        get_taker_dir, parse_field, parse_list, parse_toml, ConnectionType, MIN_FEE_RATE,
+        TCP_KEEPALIVE_INTERVAL,
+    },
+    wallet::{AddressStrategy, AntiFeeSniping, TxOrdering},
+};
+use std::{fmt, io, io::Write, path::Path, str::FromStr};
+
+/// How the Taker picks the next maker for a swap route from the offerbook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MakerSelection {
+    /// Pick the first suitable maker in the offerbook.
+    #[default]
+    FirstFit,
+    /// Prefer makers with a spread of measured round-trip latencies, so that the route
+    /// doesn't cluster on makers sitting close to each other (or to the taker).
+    LatencyDiversity,
+}
+
+impl FromStr for MakerSelection {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "first-fit" => Ok(MakerSelection::FirstFit),
+            "latency-diversity" => Ok(MakerSelection::LatencyDiversity),
+            _ => Err("Invalid maker selection mode"),
+        }
+    }
+}
+
+impl fmt::Display for MakerSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MakerSelection::FirstFit => write!(f, "first-fit"),
+            MakerSelection::LatencyDiversity => write!(f, "latency-diversity"),
+        }
+    }
+}
+
+/// How the Taker requests the contract signatures of the first maker of a swap route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FirstHopOrdering {
+    /// Try one maker at a time, until one responds.
+    #[default]
+    Sequential,
+    /// Request the signatures of the top candidate makers at the same time, and go on with the
+    /// first one to respond.
+    Race,
+}
+
+impl FromStr for FirstHopOrdering {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sequential" => Ok(FirstHopOrdering::Sequential),
+            "race" => Ok(FirstHopOrdering::Race),
+            _ => Err("Invalid first hop ordering"),
+        }
+    }
+}
+
+impl fmt::Display for FirstHopOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FirstHopOrdering::Sequential => write!(f, "sequential"),
+            FirstHopOrdering::Race => write!(f, "race"),
+        }
+    }
+}
 
 /// Taker configuration with refund, connection, and sleep settings.
 #[derive(Debug, Clone, PartialEq)]
@@ -15,10 +93,84 @@ pub struct TakerConfig {
     pub socks_port: u16,
     /// Authentication password
     pub tor_auth_password: String,
-    /// Directory server address (can be clearnet or onion)
-    pub directory_server_address: String,
+    /// Directory server addresses (can be clearnet or onion). Maker addresses are merged across all of them.
+    pub directory_server_addresses: Vec<String>,
     /// Connection type
     pub connection_type: ConnectionType,
+    /// Signal BIP125 opt-in RBF on funding tx inputs. If `false`, funding txs are non-replaceable.
+    pub funding_rbf: bool,
+    /// Maker selection mode for the swap route.
+    pub maker_selection: MakerSelection,
+    /// nLockTime policy of funding and regular spend txs.
+    pub anti_fee_sniping: AntiFeeSniping,
+    /// Ordering of the inputs and outputs of funding and regular spend txs.
+    pub tx_ordering: TxOrdering,
+    /// Keychain layout of the change addresses. Only applies when the wallet is created.
+    pub address_strategy: AddressStrategy,
+    /// Maximum number of makers allowed in a swap route.
+    pub max_maker_count: usize,
+    /// Interval in seconds of the heartbeats sent to the makers, while waiting for funding confirmations.
+    pub heartbeat_interval: u64,
+    /// Freshen the received swap coins in a single tx, instead of one tx per coin.
+    pub freshen_combined: bool,
+    /// Timeout in seconds for establishing a TCP connection to a maker (clearnet only).
+    pub connect_timeout: u64,
+    /// Read/write timeout in seconds while fetching offers and verifying maker identities.
+    pub offer_fetch_timeout: u64,
+    /// Maximum number of makers offers are fetched from at the same time.
+    pub offer_fetch_concurrency: usize,
+    /// Read/write timeout in seconds while exchanging contract signatures with a maker.
+    pub signature_exchange_timeout: u64,
+    /// Read/write timeout in seconds while settling a swap with a maker.
+    pub settlement_timeout: u64,
+    /// Refuse mainnet swaps below a minimum privacy configuration. Has no effect on other networks.
+    pub safe_mode: bool,
+    /// Minimum number of makers in a mainnet swap route, enforced by the safe-mode.
+    pub safe_mode_min_makers: usize,
+    /// Allow exporting the private keys of wallet addresses.
+    pub allow_key_export: bool,
+    /// Seconds a swap connection is idle before TCP keepalive probes are sent. `0` disables the keepalive.
+    pub tcp_keepalive_interval: u64,
+    /// Set `TCP_NODELAY` on swap connections, so the protocol messages are sent without delay.
+    pub tcp_nodelay: bool,
+    /// Seconds after which a bad maker ban is lifted, at the next offerbook sync. `0` keeps bans forever.
+    pub bad_maker_ban_duration: u64,
+    /// Consecutive fidelity proof failures, across offerbook syncs, after which a maker is blacklisted.
+    /// `0` never blacklists.
+    pub fidelity_failure_threshold: u32,
+    /// Balance in sats never spent by swaps or automatic coin selection. `0` reserves nothing.
+    pub reserve_amount: u64,
+    /// Floor in sats/vByte of the fee rate of every transaction, applied after any fee estimate.
+    pub min_fee_rate: f64,
+    /// Leave the recovery of failed swaps to [Taker::run_recovery_scheduler](crate::taker::Taker::run_recovery_scheduler),
+    /// instead of running it inline.
+    pub auto_recovery: bool,
+    /// Interval in seconds between the checks of the recovery scheduler for unfinished swapcoins.
+    pub recovery_interval: u64,
+    /// Hash function of the contract hashlocks. Only makers supporting it are selected for a swap.
+    pub hashlock_variant: HashlockVariant,
+    /// Accept offers of makers without a fidelity bond. Never honored on mainnet.
+    pub allow_bondless_offers: bool,
+    /// How the contract signatures of the first maker of a swap route are requested.
+    pub first_hop_ordering: FirstHopOrdering,
+    /// Number of candidate makers raced for the first hop, with [FirstHopOrdering::Race].
+    pub first_hop_race_width: usize,
+    /// Pick a random number of funding txs for every swap round, instead of the `tx_count` of the swap params.
+    pub randomize_tx_count: bool,
+    /// Lowest number of funding txs picked, with `randomize_tx_count`.
+    pub min_tx_count: u32,
+    /// Highest number of funding txs picked, with `randomize_tx_count`.
+    pub max_tx_count: u32,
+    /// Prefer makers that completed earlier swaps, before falling back to `maker_selection`.
+    pub prefer_known_good: bool,
+    /// Check that the contract txs, fully signed with the maker's signatures, pass script verification, as
+    /// soon as the signatures are received.
+    pub verify_signed_contracts: bool,
+    /// Trace every protocol message exchanged with the makers to `protocol_trace.log` in the data
+    /// directory, with the secrets redacted. For troubleshooting.
+    pub protocol_trace: bool,
+    /// Number of random bytes of a swap id, never less than 8.
+    pub swap_id_len: usize,
 }
 
 impl Default for TakerConfig {
@@ -27,13 +179,55 @@ impl Default for TakerConfig {
             control_port: 9051,
             socks_port: 9050,
             tor_auth_password: "".to_string(),
-            directory_server_address:
-                "ri3t5m2na2eestaigqtxm3f4u7njy65aunxeh7aftgid3bdeo3bz65qd.onion:8080".to_string(),
+            directory_server_addresses: vec![if cfg!(feature = "integration-test") {
+                "127.0.0.1:8080".to_string()
+            } else {
+                "ri3t5m2na2eestaigqtxm3f4u7njy65aunxeh7aftgid3bdeo3bz65qd.onion:8080".to_string()
+            }],
             connection_type: if cfg!(feature = "integration-test") {
                 ConnectionType::CLEARNET
             } else {
                 ConnectionType::TOR
             },
+            funding_rbf: true,
+            maker_selection: MakerSelection::default(),
+            anti_fee_sniping: AntiFeeSniping::default(),
+            tx_ordering: TxOrdering::default(),
+            address_strategy: AddressStrategy::default(),
+            max_maker_count: MAX_MAKER_COUNT,
+            heartbeat_interval: if cfg!(feature = "integration-test") {
+                3
+            } else {
+                30
+            },
+            freshen_combined: false,
+            connect_timeout: FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC,
+            offer_fetch_timeout: FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC,
+            offer_fetch_concurrency: OFFER_FETCH_CONCURRENCY,
+            signature_exchange_timeout: TCP_TIMEOUT_SECONDS,
+            settlement_timeout: TCP_TIMEOUT_SECONDS,
+            safe_mode: true,
+            safe_mode_min_makers: SAFE_MODE_MIN_MAKER_COUNT,
+            allow_key_export: false,
+            tcp_keepalive_interval: TCP_KEEPALIVE_INTERVAL,
+            tcp_nodelay: true,
+            bad_maker_ban_duration: BAD_MAKER_BAN_DURATION,
+            fidelity_failure_threshold: FIDELITY_FAILURE_THRESHOLD,
+            reserve_amount: 0,
+            min_fee_rate: MIN_FEE_RATE,
+            auto_recovery: false,
+            recovery_interval: RECOVERY_INTERVAL,
+            hashlock_variant: HashlockVariant::default(),
+            allow_bondless_offers: false,
+            first_hop_ordering: FirstHopOrdering::default(),
+            first_hop_race_width: FIRST_HOP_RACE_WIDTH,
+            randomize_tx_count: false,
+            min_tx_count: 1,
+            max_tx_count: 3,
+            prefer_known_good: false,
+            verify_signed_contracts: true,
+            protocol_trace: false,
+            swap_id_len: SWAP_ID_LEN,
         }
     }
 }
@@ -78,14 +272,134 @@ impl TakerConfig {
                 config_map.get("tor_auth_password"),
                 default_config.tor_auth_password,
             ),
-            directory_server_address: parse_field(
-                config_map.get("directory_server_address"),
-                default_config.directory_server_address,
+            // Older configs hold a single `directory_server_address`.
+            directory_server_addresses: parse_list(
+                config_map.get("directory_server_addresses"),
+                parse_list(
+                    config_map.get("directory_server_address"),
+                    default_config.directory_server_addresses,
+                ),
             ),
             connection_type: parse_field(
                 config_map.get("connection_type"),
                 default_config.connection_type,
             ),
+            funding_rbf: parse_field(config_map.get("funding_rbf"), default_config.funding_rbf),
+            maker_selection: parse_field(
+                config_map.get("maker_selection"),
+                default_config.maker_selection,
+            ),
+            anti_fee_sniping: parse_field(
+                config_map.get("anti_fee_sniping"),
+                default_config.anti_fee_sniping,
+            ),
+            tx_ordering: parse_field(config_map.get("tx_ordering"), default_config.tx_ordering),
+            address_strategy: parse_field(
+                config_map.get("address_strategy"),
+                default_config.address_strategy,
+            ),
+            max_maker_count: parse_field(
+                config_map.get("max_maker_count"),
+                default_config.max_maker_count,
+            ),
+            heartbeat_interval: parse_field(
+                config_map.get("heartbeat_interval"),
+                default_config.heartbeat_interval,
+            ),
+            freshen_combined: parse_field(
+                config_map.get("freshen_combined"),
+                default_config.freshen_combined,
+            ),
+            connect_timeout: parse_field(
+                config_map.get("connect_timeout"),
+                default_config.connect_timeout,
+            ),
+            offer_fetch_timeout: parse_field(
+                config_map.get("offer_fetch_timeout"),
+                default_config.offer_fetch_timeout,
+            ),
+            offer_fetch_concurrency: parse_field(
+                config_map.get("offer_fetch_concurrency"),
+                default_config.offer_fetch_concurrency,
+            ),
+            signature_exchange_timeout: parse_field(
+                config_map.get("signature_exchange_timeout"),
+                default_config.signature_exchange_timeout,
+            ),
+            settlement_timeout: parse_field(
+                config_map.get("settlement_timeout"),
+                default_config.settlement_timeout,
+            ),
+            safe_mode: parse_field(config_map.get("safe_mode"), default_config.safe_mode),
+            safe_mode_min_makers: parse_field(
+                config_map.get("safe_mode_min_makers"),
+                default_config.safe_mode_min_makers,
+            ),
+            allow_key_export: parse_field(
+                config_map.get("allow_key_export"),
+                default_config.allow_key_export,
+            ),
+            tcp_keepalive_interval: parse_field(
+                config_map.get("tcp_keepalive_interval"),
+                default_config.tcp_keepalive_interval,
+            ),
+            tcp_nodelay: parse_field(config_map.get("tcp_nodelay"), default_config.tcp_nodelay),
+            bad_maker_ban_duration: parse_field(
+                config_map.get("bad_maker_ban_duration"),
+                default_config.bad_maker_ban_duration,
+            ),
+            fidelity_failure_threshold: parse_field(
+                config_map.get("fidelity_failure_threshold"),
+                default_config.fidelity_failure_threshold,
+            ),
+            reserve_amount: parse_field(
+                config_map.get("reserve_amount"),
+                default_config.reserve_amount,
+            ),
+            min_fee_rate: parse_field(config_map.get("min_fee_rate"), default_config.min_fee_rate),
+            auto_recovery: parse_field(
+                config_map.get("auto_recovery"),
+                default_config.auto_recovery,
+            ),
+            recovery_interval: parse_field(
+                config_map.get("recovery_interval"),
+                default_config.recovery_interval,
+            ),
+            hashlock_variant: parse_field(
+                config_map.get("hashlock_variant"),
+                default_config.hashlock_variant,
+            ),
+            allow_bondless_offers: parse_field(
+                config_map.get("allow_bondless_offers"),
+                default_config.allow_bondless_offers,
+            ),
+            first_hop_ordering: parse_field(
+                config_map.get("first_hop_ordering"),
+                default_config.first_hop_ordering,
+            ),
+            first_hop_race_width: parse_field(
+                config_map.get("first_hop_race_width"),
+                default_config.first_hop_race_width,
+            ),
+            randomize_tx_count: parse_field(
+                config_map.get("randomize_tx_count"),
+                default_config.randomize_tx_count,
+            ),
+            min_tx_count: parse_field(config_map.get("min_tx_count"), default_config.min_tx_count),
+            max_tx_count: parse_field(config_map.get("max_tx_count"), default_config.max_tx_count),
+            prefer_known_good: parse_field(
+                config_map.get("prefer_known_good"),
+                default_config.prefer_known_good,
+            ),
+            verify_signed_contracts: parse_field(
+                config_map.get("verify_signed_contracts"),
+                default_config.verify_signed_contracts,
+            ),
+            protocol_trace: parse_field(
+                config_map.get("protocol_trace"),
+                default_config.protocol_trace,
+            ),
+            swap_id_len: parse_field(config_map.get("swap_id_len"), default_config.swap_id_len),
         })
     }
 
@@ -95,13 +409,83 @@ impl TakerConfig {
             "control_port = {}
 socks_port = {}
 tor_auth_password = {}
-directory_server_address = {}
-connection_type = {:?}",
+directory_server_addresses = {}
+connection_type = {:?}
+funding_rbf = {}
+maker_selection = {}
+anti_fee_sniping = {}
+tx_ordering = {}
+address_strategy = {}
+max_maker_count = {}
+heartbeat_interval = {}
+freshen_combined = {}
+connect_timeout = {}
+offer_fetch_timeout = {}
+offer_fetch_concurrency = {}
+signature_exchange_timeout = {}
+settlement_timeout = {}
+safe_mode = {}
+safe_mode_min_makers = {}
+allow_key_export = {}
+tcp_keepalive_interval = {}
+tcp_nodelay = {}
+bad_maker_ban_duration = {}
+fidelity_failure_threshold = {}
+reserve_amount = {}
+min_fee_rate = {}
+auto_recovery = {}
+recovery_interval = {}
+hashlock_variant = {}
+allow_bondless_offers = {}
+first_hop_ordering = {}
+first_hop_race_width = {}
+randomize_tx_count = {}
+min_tx_count = {}
+max_tx_count = {}
+prefer_known_good = {}
+verify_signed_contracts = {}
+protocol_trace = {}
+swap_id_len = {}",
             self.control_port,
             self.socks_port,
             self.tor_auth_password,
-            self.directory_server_address,
-            self.connection_type
+            self.directory_server_addresses.join(","),
+            self.connection_type,
+            self.funding_rbf,
+            self.maker_selection,
+            self.anti_fee_sniping,
+            self.tx_ordering,
+            self.address_strategy,
+            self.max_maker_count,
+            self.heartbeat_interval,
+            self.freshen_combined,
+            self.connect_timeout,
+            self.offer_fetch_timeout,
+            self.offer_fetch_concurrency,
+            self.signature_exchange_timeout,
+            self.settlement_timeout,
+            self.safe_mode,
+            self.safe_mode_min_makers,
+            self.allow_key_export,
+            self.tcp_keepalive_interval,
+            self.tcp_nodelay,
+            self.bad_maker_ban_duration,
+            self.fidelity_failure_threshold,
+            self.reserve_amount,
+            self.min_fee_rate,
+            self.auto_recovery,
+            self.recovery_interval,
+            self.hashlock_variant,
+            self.allow_bondless_offers,
+            self.first_hop_ordering,
+            self.first_hop_race_width,
+            self.randomize_tx_count,
+            self.min_tx_count,
+            self.max_tx_count,
+            self.prefer_known_good,
+            self.verify_signed_contracts,
+            self.protocol_trace,
+            self.swap_id_len
         );
         std::fs::create_dir_all(path.parent().expect("Path should NOT be root!"))?;
         let mut file = std::fs::File::create(path)?;
@@ -196,6 +580,431 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_funding_rbf_config() {
+        assert!(TakerConfig::default().funding_rbf);
+
+        let contents = r#"
+            funding_rbf = false
+        "#;
+        let config_path = create_temp_config(contents, "funding_rbf_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(
+            TakerConfig {
+                funding_rbf: false,
+                ..TakerConfig::default()
+            },
+            config
+        );
+    }
+
+    #[test]
+    fn test_maker_selection_config() {
+        assert_eq!(
+            TakerConfig::default().maker_selection,
+            MakerSelection::FirstFit
+        );
+
+        let contents = r#"
+            maker_selection = latency-diversity
+        "#;
+        let config_path = create_temp_config(contents, "maker_selection_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.maker_selection, MakerSelection::LatencyDiversity);
+    }
+
+    #[test]
+    fn test_prefer_known_good_config() {
+        assert!(!TakerConfig::default().prefer_known_good);
+
+        let contents = r#"
+            prefer_known_good = true
+        "#;
+        let config_path = create_temp_config(contents, "prefer_known_good_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert!(config.prefer_known_good);
+    }
+
+    #[test]
+    fn test_verify_signed_contracts_config() {
+        assert!(TakerConfig::default().verify_signed_contracts);
+
+        let contents = r#"
+            verify_signed_contracts = false
+        "#;
+        let config_path = create_temp_config(contents, "verify_signed_contracts_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert!(!config.verify_signed_contracts);
+    }
+
+    #[test]
+    fn test_protocol_trace_config() {
+        assert!(!TakerConfig::default().protocol_trace);
+
+        let contents = r#"
+            protocol_trace = true
+        "#;
+        let config_path = create_temp_config(contents, "protocol_trace_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert!(config.protocol_trace);
+    }
+
+    #[test]
+    fn test_swap_id_len_config() {
+        assert_eq!(TakerConfig::default().swap_id_len, SWAP_ID_LEN);
+
+        let contents = r#"
+            swap_id_len = 32
+        "#;
+        let config_path = create_temp_config(contents, "swap_id_len_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.swap_id_len, 32);
+    }
+
+    #[test]
+    fn test_first_hop_ordering_config() {
+        let default_config = TakerConfig::default();
+        assert_eq!(
+            default_config.first_hop_ordering,
+            FirstHopOrdering::Sequential
+        );
+        assert_eq!(default_config.first_hop_race_width, FIRST_HOP_RACE_WIDTH);
+
+        let contents = r#"
+            first_hop_ordering = race
+            first_hop_race_width = 5
+        "#;
+        let config_path = create_temp_config(contents, "first_hop_ordering_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.first_hop_ordering, FirstHopOrdering::Race);
+        assert_eq!(config.first_hop_race_width, 5);
+    }
+
+    #[test]
+    fn test_randomize_tx_count_config() {
+        let default_config = TakerConfig::default();
+        assert!(!default_config.randomize_tx_count);
+        assert_eq!(default_config.min_tx_count, 1);
+        assert_eq!(default_config.max_tx_count, 3);
+
+        let contents = r#"
+            randomize_tx_count = true
+            min_tx_count = 2
+            max_tx_count = 5
+        "#;
+        let config_path = create_temp_config(contents, "randomize_tx_count_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert!(config.randomize_tx_count);
+        assert_eq!(config.min_tx_count, 2);
+        assert_eq!(config.max_tx_count, 5);
+    }
+
+    #[test]
+    fn test_tx_ordering_config() {
+        assert_eq!(TakerConfig::default().tx_ordering, TxOrdering::Random);
+
+        let contents = r#"
+            tx_ordering = bip69
+        "#;
+        let config_path = create_temp_config(contents, "tx_ordering_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.tx_ordering, TxOrdering::Bip69);
+    }
+
+    #[test]
+    fn test_address_strategy_config() {
+        assert_eq!(
+            TakerConfig::default().address_strategy,
+            AddressStrategy::SplitChain
+        );
+
+        let contents = r#"
+            address_strategy = singlechain
+        "#;
+        let config_path = create_temp_config(contents, "address_strategy_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.address_strategy, AddressStrategy::SingleChain);
+    }
+
+    #[test]
+    fn test_freshen_combined_config() {
+        assert!(!TakerConfig::default().freshen_combined);
+
+        let contents = r#"
+            freshen_combined = true
+        "#;
+        let config_path = create_temp_config(contents, "freshen_combined_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert!(config.freshen_combined);
+    }
+
+    #[test]
+    fn test_safe_mode_config() {
+        let default_config = TakerConfig::default();
+        assert!(default_config.safe_mode);
+        assert_eq!(default_config.safe_mode_min_makers, 3);
+
+        let contents = r#"
+            safe_mode = false
+            safe_mode_min_makers = 4
+        "#;
+        let config_path = create_temp_config(contents, "safe_mode_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert!(!config.safe_mode);
+        assert_eq!(config.safe_mode_min_makers, 4);
+    }
+
+    #[test]
+    fn test_allow_key_export_config() {
+        assert!(!TakerConfig::default().allow_key_export);
+
+        let contents = r#"
+            allow_key_export = true
+        "#;
+        let config_path = create_temp_config(contents, "key_export_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert!(config.allow_key_export);
+    }
+
+    #[test]
+    fn test_tcp_socket_config() {
+        let default_config = TakerConfig::default();
+        assert_eq!(
+            default_config.tcp_keepalive_interval,
+            TCP_KEEPALIVE_INTERVAL
+        );
+        assert!(default_config.tcp_nodelay);
+
+        let contents = r#"
+            tcp_keepalive_interval = 120
+            tcp_nodelay = false
+        "#;
+        let config_path = create_temp_config(contents, "tcp_socket_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.tcp_keepalive_interval, 120);
+        assert!(!config.tcp_nodelay);
+    }
+
+    #[test]
+    fn test_bad_maker_ban_duration_config() {
+        assert_eq!(
+            TakerConfig::default().bad_maker_ban_duration,
+            BAD_MAKER_BAN_DURATION
+        );
+
+        let contents = r#"
+            bad_maker_ban_duration = 3600
+        "#;
+        let config_path = create_temp_config(contents, "ban_duration_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.bad_maker_ban_duration, 3600);
+    }
+
+    #[test]
+    fn test_fidelity_failure_threshold_config() {
+        assert_eq!(
+            TakerConfig::default().fidelity_failure_threshold,
+            FIDELITY_FAILURE_THRESHOLD
+        );
+
+        let contents = r#"
+            fidelity_failure_threshold = 5
+        "#;
+        let config_path = create_temp_config(contents, "fidelity_failure_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.fidelity_failure_threshold, 5);
+    }
+
+    #[test]
+    fn test_reserve_amount_config() {
+        assert_eq!(TakerConfig::default().reserve_amount, 0);
+
+        let contents = r#"
+            reserve_amount = 100000
+        "#;
+        let config_path = create_temp_config(contents, "reserve_amount_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.reserve_amount, 100_000);
+    }
+
+    #[test]
+    fn test_min_fee_rate_config() {
+        assert_eq!(TakerConfig::default().min_fee_rate, MIN_FEE_RATE);
+
+        let contents = r#"
+            min_fee_rate = 5.5
+        "#;
+        let config_path = create_temp_config(contents, "min_fee_rate_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+
+        assert_eq!(config.min_fee_rate, 5.5);
+    }
+
+    #[test]
+    fn test_hashlock_variant_config() {
+        assert_eq!(
+            TakerConfig::default().hashlock_variant,
+            HashlockVariant::Hash160
+        );
+
+        let contents = r#"
+            hashlock_variant = sha256
+        "#;
+        let config_path = create_temp_config(contents, "hashlock_variant_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.hashlock_variant, HashlockVariant::Sha256);
+    }
+
+    #[test]
+    fn test_allow_bondless_offers_config() {
+        assert!(!TakerConfig::default().allow_bondless_offers);
+
+        let contents = r#"
+            allow_bondless_offers = true
+        "#;
+        let config_path = create_temp_config(contents, "bondless_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert!(config.allow_bondless_offers);
+    }
+
+    #[test]
+    fn test_auto_recovery_config() {
+        let default_config = TakerConfig::default();
+        assert!(!default_config.auto_recovery);
+        assert_eq!(default_config.recovery_interval, RECOVERY_INTERVAL);
+
+        let contents = r#"
+            auto_recovery = true
+            recovery_interval = 30
+        "#;
+        let config_path = create_temp_config(contents, "auto_recovery_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(
+            TakerConfig {
+                auto_recovery: true,
+                recovery_interval: 30,
+                ..TakerConfig::default()
+            },
+            config
+        );
+    }
+
+    #[test]
+    fn test_timeouts_config() {
+        let default_config = TakerConfig::default();
+        assert_eq!(default_config.offer_fetch_timeout, 30);
+        assert_eq!(default_config.signature_exchange_timeout, 300);
+
+        let contents = r#"
+            connect_timeout = 5
+            offer_fetch_timeout = 10
+            signature_exchange_timeout = 60
+            settlement_timeout = 120
+        "#;
+        let config_path = create_temp_config(contents, "timeouts_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(
+            TakerConfig {
+                connect_timeout: 5,
+                offer_fetch_timeout: 10,
+                signature_exchange_timeout: 60,
+                settlement_timeout: 120,
+                ..TakerConfig::default()
+            },
+            config
+        );
+    }
+
+    #[test]
+    fn test_offer_fetch_concurrency_config() {
+        assert_eq!(
+            TakerConfig::default().offer_fetch_concurrency,
+            OFFER_FETCH_CONCURRENCY
+        );
+
+        let contents = r#"
+            offer_fetch_concurrency = 4
+        "#;
+        let config_path = create_temp_config(contents, "offer_fetch_concurrency_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.offer_fetch_concurrency, 4);
+    }
+
+    #[test]
+    fn test_max_maker_count_config() {
+        assert_eq!(TakerConfig::default().max_maker_count, MAX_MAKER_COUNT);
+
+        let contents = r#"
+            max_maker_count = 4
+        "#;
+        let config_path = create_temp_config(contents, "max_maker_count_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.max_maker_count, 4);
+    }
+
+    #[test]
+    fn test_directory_server_addresses_config() {
+        assert_eq!(TakerConfig::default().directory_server_addresses.len(), 1);
+
+        let contents = r#"
+            directory_server_addresses = abc.onion:8080, 127.0.0.1:8081
+        "#;
+        let config_path = create_temp_config(contents, "dns_list_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(
+            config.directory_server_addresses,
+            vec!["abc.onion:8080".to_string(), "127.0.0.1:8081".to_string()]
+        );
+
+        // A single address from an older config is still read.
+        let contents = r#"
+            directory_server_address = abc.onion:8080
+        "#;
+        let config_path = create_temp_config(contents, "dns_legacy_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(
+            config.directory_server_addresses,
+            vec!["abc.onion:8080".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_heartbeat_interval_config() {
+        let contents = r#"
+            heartbeat_interval = 2
+        "#;
+        let config_path = create_temp_config(contents, "heartbeat_taker_config.toml");
+        let config = TakerConfig::new(Some(&config_path)).unwrap();
+        remove_temp_config(&config_path);
+        assert_eq!(config.heartbeat_interval, 2);
+    }
+
     #[test]
     fn test_missing_file() {
         let config_path = get_taker_dir().join("taker.toml");
@@ -27,6 +27,9 @@ pub enum TakerError {
     SendAmountNotSet,
     /// Error indicating a timeout while waiting for the funding transaction.
     FundingTxWaitTimeOut,
+    /// Error indicating the received swapcoins didn't reach the `wait_for_settlement_confirms` depth
+    /// in time. The swap itself is complete.
+    SettlementConfirmsTimeOut,
     /// Error deserializing data, typically related to CBOR-encoded data.
     Deserialize(String),
     /// Error indicating an MPSC channel failure.
@@ -35,6 +38,40 @@ pub enum TakerError {
     MPSC(String),
     /// Tor error
     TorError(TorError),
+    /// Error indicating no BTC price is known for the fiat currency.
+    PriceUnavailable(String),
+    /// Error indicating the swap route has more makers than allowed.
+    MakerCountTooHigh {
+        /// The requested maker count.
+        maker_count: usize,
+        /// The maximum allowed maker count.
+        max_maker_count: usize,
+    },
+    /// Error indicating the target directory of a data directory relocation is not empty.
+    DataDirNotEmpty(std::path::PathBuf),
+    /// Error indicating a mainnet swap was refused by the safe-mode, as its route is too short.
+    SafeModeMakerCount {
+        /// The requested maker count.
+        maker_count: usize,
+        /// The minimum maker count required by the safe-mode.
+        min_maker_count: usize,
+    },
+    /// Error indicating bondless offers were allowed on mainnet, where every maker needs a fidelity bond.
+    BondlessOffersOnMainnet,
+    /// Error indicating a recovery bundle can't be read, or doesn't match this Taker.
+    InvalidRecoveryBundle(String),
+    /// Error indicating no completed swap has the given id in the swap history.
+    UnknownSwap(String),
+    /// Error indicating the swap deadline passed before settlement, and the swap was aborted into recovery.
+    DeadlineExceeded,
+    /// Error indicating a round of a chunked swap didn't complete, e.g. it failed and was recovered.
+    SwapRoundFailed {
+        /// The 1-based number of the failed round.
+        round: usize,
+    },
+    /// Error indicating a maker declined the swap through no fault of the Taker, with the given reason,
+    /// e.g. for lack of capacity.
+    SwapDeclined(String),
 }
 
 impl From<TorError> for TakerError {
@@ -0,0 +1,251 @@
+//! Fiat denominated amounts, for the user facing boundary of the Taker.
+//!
+//! Swaps always work with sats internally. Fiat amounts are converted into sats through an injectable
+//! [PriceSource], before they are used in [SwapParams](super::SwapParams) or wallet spends.
+
+use std::{fmt, str::FromStr};
+
+use bitcoin::Amount;
+
+use super::error::TakerError;
+
+/// A source of BTC prices in fiat currencies.
+pub trait PriceSource: Send + Sync {
+    /// Returns the price of one BTC in the given currency code (ex: `USD`), if known.
+    fn btc_price(&self, currency: &str) -> Option<f64>;
+}
+
+/// The default [PriceSource], which knows no prices. Only sat amounts can be used with it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoPriceSource;
+
+impl PriceSource for NoPriceSource {
+    fn btc_price(&self, _currency: &str) -> Option<f64> {
+        None
+    }
+}
+
+/// A [PriceSource] with a single fixed BTC price, ex: set manually by the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedPriceSource {
+    /// The currency code of the price.
+    pub currency: String,
+    /// The price of one BTC in the currency.
+    pub price: f64,
+}
+
+impl PriceSource for FixedPriceSource {
+    fn btc_price(&self, currency: &str) -> Option<f64> {
+        self.currency
+            .eq_ignore_ascii_case(currency)
+            .then_some(self.price)
+    }
+}
+
+impl FromStr for FixedPriceSource {
+    type Err = &'static str;
+
+    /// Parses a `<CURRENCY>:<PRICE>` pair, ex: `USD:65000`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (currency, price) = s
+            .split_once(':')
+            .ok_or("Expected <CURRENCY>:<PRICE>, ex: USD:65000")?;
+        let currency = parse_currency(currency).ok_or("Invalid currency code")?;
+        let price = price
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|p| p.is_finite() && *p > 0.0)
+            .ok_or("Invalid BTC price")?;
+        Ok(FixedPriceSource { currency, price })
+    }
+}
+
+/// An amount given by the user, either in sats or in a fiat currency.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SwapAmount {
+    /// An amount in sats.
+    Sats(Amount),
+    /// An amount in a fiat currency, identified by its currency code.
+    Fiat {
+        /// The fiat value.
+        value: f64,
+        /// The currency code, ex: `USD`.
+        currency: String,
+    },
+}
+
+impl SwapAmount {
+    /// Converts the amount into sats, using the price source for fiat amounts.
+    pub fn to_amount(&self, price_source: &dyn PriceSource) -> Result<Amount, TakerError> {
+        match self {
+            SwapAmount::Sats(amount) => Ok(*amount),
+            SwapAmount::Fiat { value, currency } => {
+                let price = price_source
+                    .btc_price(currency)
+                    .filter(|p| p.is_finite() && *p > 0.0)
+                    .ok_or_else(|| TakerError::PriceUnavailable(currency.clone()))?;
+                Ok(Amount::from_sat(
+                    (value / price * Amount::ONE_BTC.to_sat() as f64).round() as u64,
+                ))
+            }
+        }
+    }
+}
+
+impl FromStr for SwapAmount {
+    type Err = &'static str;
+
+    /// Parses amounts like `20000`, `20000sat`, `$500`, `500 USD` or `12.5eur`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let symbol_currency = match s.chars().next() {
+            Some('$') => Some("USD"),
+            Some('€') => Some("EUR"),
+            Some('£') => Some("GBP"),
+            _ => None,
+        };
+
+        let (value, unit) = if let Some(currency) = symbol_currency {
+            (&s[s.chars().next().map_or(0, char::len_utf8)..], currency)
+        } else {
+            let unit_start = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+            (&s[..unit_start], &s[unit_start..])
+        };
+        let value = value.trim();
+
+        match unit.to_ascii_lowercase().as_str() {
+            "" | "sat" | "sats" => value
+                .parse::<u64>()
+                .map(|sats| SwapAmount::Sats(Amount::from_sat(sats)))
+                .map_err(|_| "Invalid sat amount"),
+            _ => {
+                let currency = parse_currency(unit).ok_or("Invalid currency code")?;
+                let value = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|v| v.is_finite() && *v > 0.0)
+                    .ok_or("Invalid fiat amount")?;
+                Ok(SwapAmount::Fiat { value, currency })
+            }
+        }
+    }
+}
+
+impl fmt::Display for SwapAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwapAmount::Sats(amount) => write!(f, "{} sats", amount.to_sat()),
+            SwapAmount::Fiat { value, currency } => write!(f, "{:.2} {}", value, currency),
+        }
+    }
+}
+
+/// Formats a sat amount, followed by its fiat value if the price source knows the currency.
+pub fn display_amount(amount: Amount, currency: &str, price_source: &dyn PriceSource) -> String {
+    let currency = currency.to_ascii_uppercase();
+    match price_source.btc_price(&currency) {
+        Some(price) => format!(
+            "{} sats (~{:.2} {})",
+            amount.to_sat(),
+            amount.to_btc() * price,
+            currency
+        ),
+        None => format!("{} sats", amount.to_sat()),
+    }
+}
+
+/// Validates a 3 letter currency code, and returns it in upper case.
+fn parse_currency(s: &str) -> Option<String> {
+    let s = s.trim();
+    (s.len() == 3 && s.chars().all(|c| c.is_ascii_alphabetic())).then(|| s.to_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::taker::SwapParams;
+
+    #[test]
+    fn test_parse_swap_amount() {
+        let usd = |value| SwapAmount::Fiat {
+            value,
+            currency: "USD".to_string(),
+        };
+        assert_eq!(
+            "20000".parse(),
+            Ok(SwapAmount::Sats(Amount::from_sat(20000)))
+        );
+        assert_eq!(
+            "20000 sats".parse(),
+            Ok(SwapAmount::Sats(Amount::from_sat(20000)))
+        );
+        assert_eq!("$500".parse(), Ok(usd(500.0)));
+        assert_eq!("500 USD".parse(), Ok(usd(500.0)));
+        assert_eq!("12.5usd".parse(), Ok(usd(12.5)));
+        assert_eq!(
+            "€20".parse(),
+            Ok(SwapAmount::Fiat {
+                value: 20.0,
+                currency: "EUR".to_string()
+            })
+        );
+
+        for invalid in ["", "$", "-5 USD", "0.5", "500 dollars", "1e400 USD"] {
+            assert!(
+                invalid.parse::<SwapAmount>().is_err(),
+                "{} should not parse",
+                invalid
+            );
+        }
+    }
+
+    struct MockPriceSource;
+
+    impl PriceSource for MockPriceSource {
+        fn btc_price(&self, currency: &str) -> Option<f64> {
+            (currency == "USD").then_some(50_000.0)
+        }
+    }
+
+    #[test]
+    fn test_fiat_amount_conversion() {
+        let amount = "$500".parse::<SwapAmount>().unwrap();
+        let swap_params = SwapParams {
+            send_amount: amount.to_amount(&MockPriceSource).unwrap(),
+            maker_count: 2,
+            tx_count: 1,
+            required_confirms: 1,
+            ..Default::default()
+        };
+        assert_eq!(swap_params.send_amount, Amount::from_sat(1_000_000));
+
+        // Sat amounts are never converted.
+        let sats = "20000".parse::<SwapAmount>().unwrap();
+        assert_eq!(
+            sats.to_amount(&NoPriceSource).unwrap(),
+            Amount::from_sat(20000)
+        );
+
+        // Unknown currencies can't be converted.
+        let eur = "500 EUR".parse::<SwapAmount>().unwrap();
+        assert!(matches!(
+            eur.to_amount(&MockPriceSource),
+            Err(TakerError::PriceUnavailable(currency)) if currency == "EUR"
+        ));
+        assert!(amount.to_amount(&NoPriceSource).is_err());
+
+        assert_eq!(
+            display_amount(Amount::from_sat(1_000_000), "usd", &MockPriceSource),
+            "1000000 sats (~500.00 USD)"
+        );
+        assert_eq!(
+            display_amount(Amount::from_sat(1_000_000), "EUR", &MockPriceSource),
+            "1000000 sats"
+        );
+
+        let fixed = "usd:65000.5".parse::<FixedPriceSource>().unwrap();
+        assert_eq!(fixed.btc_price("USD"), Some(65000.5));
+        assert_eq!(fixed.btc_price("EUR"), None);
+    }
+}
@@ -0,0 +1,135 @@
+//! Persisted history of the completed swaps, for audits and dispute resolution.
+//!
+//! Every completed swap appends one [SwapRecord] to the `swap_history.jsonl` file in the Taker's data
+//! directory, holding the terms agreed with each maker of the route. The file has one JSON record per line.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use bitcoin::{Amount, OutPoint};
+use serde::{Deserialize, Serialize};
+
+use super::error::TakerError;
+use crate::maker::OfferFees;
+
+/// File name of the swap history, in the Taker's data directory.
+pub(crate) const SWAP_HISTORY_FILE: &str = "swap_history.jsonl";
+
+/// The terms agreed with one maker of a completed swap.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HopRecord {
+    /// Address of the maker.
+    pub maker_address: String,
+    /// Fees of the maker's offer, the swap was made with.
+    pub fees: OfferFees,
+    /// Miner fee in sats of the contract txs, as sent in the proof of funding.
+    pub contract_fee: u64,
+    /// Funding confirmation depth negotiated with the maker.
+    #[serde(default)]
+    pub required_confirms: u32,
+    /// Refund locktime in blocks of the contracts received by the maker.
+    pub incoming_locktime: u16,
+    /// Refund locktime in blocks of the contracts sent by the maker to the next peer.
+    pub outgoing_locktime: u16,
+    /// Total funding amount received by the maker.
+    pub incoming_amount: Amount,
+    /// Total funding amount sent by the maker to the next peer.
+    pub outgoing_amount: Amount,
+}
+
+impl HopRecord {
+    /// What the maker kept of the swap, including the miner fees of its funding txs.
+    pub fn fee_earned(&self) -> Amount {
+        self.incoming_amount
+            .checked_sub(self.outgoing_amount)
+            .unwrap_or(Amount::ZERO)
+    }
+}
+
+/// A completed swap, with the terms of every hop.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SwapRecord {
+    /// Unique ID of the swap.
+    pub id: String,
+    /// Unix timestamp of the swap completion.
+    pub completed_at: u64,
+    /// Amount sent into the swap.
+    pub send_amount: Amount,
+    /// Number of funding txs per hop.
+    pub tx_count: u32,
+    /// One entry per maker, in route order.
+    pub hops: Vec<HopRecord>,
+    /// Multisig outputs funded by the Taker, spending its original coins.
+    #[serde(default)]
+    pub funding_outpoints: Vec<OutPoint>,
+    /// Multisig outputs received by the Taker from the last maker.
+    #[serde(default)]
+    pub received_outpoints: Vec<OutPoint>,
+}
+
+/// Appends a record to the swap history file, creating it if needed.
+pub(crate) fn append_swap_record(path: &Path, record: &SwapRecord) -> Result<(), TakerError> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Reads all the records of the swap history file. A missing file is an empty history.
+pub(crate) fn read_swap_history(path: &Path) -> Result<Vec<SwapRecord>, TakerError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoind::tempfile::tempdir;
+
+    fn record(id: &str, outgoing_sats: u64) -> SwapRecord {
+        SwapRecord {
+            id: id.to_string(),
+            completed_at: 1_700_000_000,
+            send_amount: Amount::from_sat(500_000),
+            tx_count: 3,
+            hops: vec![HopRecord {
+                maker_address: "127.0.0.1:6102".to_string(),
+                fees: OfferFees::default(),
+                contract_fee: 1000,
+                required_confirms: 1,
+                incoming_locktime: 40,
+                outgoing_locktime: 20,
+                incoming_amount: Amount::from_sat(500_000),
+                outgoing_amount: Amount::from_sat(outgoing_sats),
+            }],
+            funding_outpoints: Vec::new(),
+            received_outpoints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_swap_history_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(SWAP_HISTORY_FILE);
+        assert!(read_swap_history(&path).unwrap().is_empty());
+
+        let first = record("aaaa", 460_000);
+        let second = record("bbbb", 470_000);
+        append_swap_record(&path, &first).unwrap();
+        append_swap_record(&path, &second).unwrap();
+
+        assert_eq!(read_swap_history(&path).unwrap(), vec![first, second]);
+        assert_eq!(
+            read_swap_history(&path).unwrap()[0].hops[0].fee_earned(),
+            Amount::from_sat(40_000)
+        );
+    }
+}
@@ -7,9 +7,18 @@
 pub mod api;
 mod config;
 pub mod error;
+mod fiat;
+mod history;
 pub(crate) mod offers;
+mod recovery_bundle;
 mod routines;
 
 pub use self::api::TakerBehavior;
-pub use api::{SwapParams, Taker};
-pub use config::TakerConfig;
+pub use api::{
+    ContractExplanation, MakerPreview, SwapDurationEstimate, SwapParams, SwapPhase,
+    SwapStatusHandle, Taker,
+};
+pub use config::{FirstHopOrdering, MakerSelection, TakerConfig};
+pub use fiat::{display_amount, FixedPriceSource, NoPriceSource, PriceSource, SwapAmount};
+pub use history::{HopRecord, SwapRecord};
+pub use offers::{BadMaker, BanReason};
@@ -6,28 +6,43 @@
 //! It uses asynchronous channels for concurrent processing of maker offers.
 
 use std::{
+    collections::{HashMap, HashSet, VecDeque},
     convert::TryFrom,
     fmt,
-    fs::read,
+    fs::{read, read_to_string},
     io::BufWriter,
     net::TcpStream,
     path::Path,
-    sync::mpsc,
+    str::FromStr,
+    sync::{mpsc, Arc, Mutex, PoisonError},
     thread::{self, Builder},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use bitcoin::OutPoint;
 use serde::{Deserialize, Serialize};
 
 use socks::Socks5Stream;
 
 use crate::{
     error::NetError,
-    protocol::messages::{DnsRequest, Offer},
+    maker::OfferFees,
+    protocol::{
+        messages::{DnsRequest, Offer},
+        trace::ProtocolTrace,
+    },
     utill::{read_message, send_message, ConnectionType, GLOBAL_PAUSE, NET_TIMEOUT},
+    wallet::FidelityBond,
 };
 
 use super::{config::TakerConfig, error::TakerError, routines::download_maker_offer};
 
+/// Number of attempts to fetch the maker addresses from a directory server, before giving up on it.
+#[cfg(feature = "integration-test")]
+const DNS_FETCH_ATTEMPTS: u32 = 2;
+#[cfg(not(feature = "integration-test"))]
+const DNS_FETCH_ATTEMPTS: u32 = 5;
+
 /// Represents an offer along with the corresponding maker address.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OfferAndAddress {
@@ -36,6 +51,34 @@ pub struct OfferAndAddress {
     pub address: MakerAddress,
 }
 
+impl OfferAndAddress {
+    /// The fees advertised in the offer.
+    pub fn fees(&self) -> OfferFees {
+        OfferFees {
+            base_fee: self.offer.base_fee,
+            amount_relative_fee_pct: self.offer.amount_relative_fee_pct,
+            time_relative_fee_pct: self.offer.time_relative_fee_pct,
+        }
+    }
+
+    /// The fidelity bond advertised in the offer, `None` for bondless makers.
+    pub fn fidelity_bond(&self) -> Option<&FidelityBond> {
+        self.offer.fidelity.as_ref().map(|proof| &proof.bond)
+    }
+}
+
+/// Smallest swap size an offer may advertise. Anything lower is dust for a P2WSH output.
+pub(crate) const MIN_OFFER_SIZE: u64 = 330;
+
+/// Default number of makers offers are fetched from at the same time.
+pub(crate) const OFFER_FETCH_CONCURRENCY: usize = 16;
+
+/// Default duration in seconds of a bad maker ban.
+pub(crate) const BAD_MAKER_BAN_DURATION: u64 = 24 * 60 * 60;
+
+/// Default number of consecutive fidelity proof failures after which a maker is blacklisted.
+pub(crate) const FIDELITY_FAILURE_THRESHOLD: u32 = 3;
+
 const _REGTEST_MAKER_ADDRESSES_PORT: &[&str] = &["6102", "16102", "26102", "36102", "46102"];
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -78,13 +121,124 @@ impl TryFrom<&mut TcpStream> for MakerAddress {
     }
 }
 
+/// Why a maker was marked as bad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BanReason {
+    /// The maker is listed in an imported blacklist.
+    Blacklisted,
+    /// The offer advertised inconsistent size bounds.
+    InvalidOffer,
+    /// The fidelity bond proof of the offer didn't verify.
+    InvalidFidelityProof,
+    /// The maker failed the fidelity bond identity check of the safe-mode.
+    FailedIdentityCheck,
+    /// The maker's funding txs weren't confirmed in time.
+    FundingTimeout,
+    /// The maker's contract txs were broadcasted during the swap.
+    ContractsBroadcasted,
+    /// The maker's contracts don't commit to the swap hashlock.
+    HashlockMismatch,
+    /// The maker's contract txs differ from the ones built by the Taker.
+    ContractTxidMismatch,
+    /// The maker failed to exchange the contract signatures.
+    SignatureExchange,
+    /// The maker couldn't be reached, after all the reattempts.
+    Unreachable,
+    /// The swap coins received from the maker are short of the agreed amount.
+    ShortchangedAmount,
+}
+
+impl fmt::Display for BanReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            BanReason::Blacklisted => "blacklisted",
+            BanReason::InvalidOffer => "invalid offer",
+            BanReason::InvalidFidelityProof => "invalid fidelity proof",
+            BanReason::FailedIdentityCheck => "failed identity check",
+            BanReason::FundingTimeout => "funding timeout",
+            BanReason::ContractsBroadcasted => "contracts broadcasted",
+            BanReason::HashlockMismatch => "hashlock mismatch",
+            BanReason::ContractTxidMismatch => "contract txid mismatch",
+            BanReason::SignatureExchange => "signature exchange failed",
+            BanReason::Unreachable => "unreachable",
+            BanReason::ShortchangedAmount => "shortchanged amount",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// A maker marked as bad, with the reason and time of the ban.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BadMaker {
+    /// The maker's offer, at the time of the ban.
+    pub offer: OfferAndAddress,
+    /// Why the maker was banned.
+    pub reason: BanReason,
+    /// Unix timestamp of the ban.
+    pub banned_at: u64,
+}
+
+/// A maker identity listed in an imported blacklist.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) enum BlacklistEntry {
+    /// The maker's network address.
+    Address(MakerAddress),
+    /// The outpoint of the maker's fidelity bond.
+    Bond(OutPoint),
+}
+
+impl FromStr for BlacklistEntry {
+    type Err = TakerError;
+
+    /// Parses a bond outpoint (`<txid>:<vout>`), or else a maker address (`<host>:<port>`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(outpoint) = OutPoint::from_str(s) {
+            Ok(BlacklistEntry::Bond(outpoint))
+        } else {
+            Ok(BlacklistEntry::Address(MakerAddress::new(s)?))
+        }
+    }
+}
+
+/// Reads a blacklist file, with one maker address or bond outpoint per line.
+///
+/// Empty lines and lines starting with `#` are ignored.
+pub(crate) fn read_blacklist(path: &Path) -> Result<Vec<BlacklistEntry>, TakerError> {
+    read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            line.parse::<BlacklistEntry>()
+                .map_err(|_| TakerError::Deserialize(format!("Invalid blacklist entry: {}", line)))
+        })
+        .collect()
+}
+
 /// An ephemeral Offerbook tracking good and bad makers. Currently, Offerbook is initiated
 /// at start of every swap. So good and bad maker list will ot be persisted.
 // TODO: Persist the offerbook in disk.
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct OfferBook {
     pub(super) all_makers: Vec<OfferAndAddress>,
-    pub(super) bad_makers: Vec<OfferAndAddress>,
+    /// Banned makers. Kept across offerbook syncs, until the ban expires.
+    #[serde(default)]
+    pub(super) bans: Vec<BadMaker>,
+    /// Round-trip latency measured for each maker during the last offer download.
+    #[serde(default)]
+    pub(super) rtts: HashMap<MakerAddress, Duration>,
+    /// Imported maker identities, that are always treated as bad. Kept across offerbook syncs.
+    #[serde(default)]
+    pub(super) blacklist: HashSet<BlacklistEntry>,
+    /// Unix time of the last maker list fetched from a directory server. Kept across offerbook syncs.
+    #[serde(default)]
+    pub(super) directory_synced_at: Option<u64>,
+    /// Consecutive fidelity proof failures of each maker. Kept across offerbook syncs.
+    #[serde(default)]
+    pub(super) fidelity_failures: HashMap<MakerAddress, u32>,
+    /// Number of swaps each maker completed. Kept across offerbook syncs.
+    #[serde(default)]
+    pub(super) swap_successes: HashMap<MakerAddress, u32>,
 }
 
 impl OfferBook {
@@ -98,7 +252,7 @@ impl OfferBook {
     pub fn all_good_makers(&self) -> Vec<&OfferAndAddress> {
         self.all_makers
             .iter()
-            .filter(|offer| !self.bad_makers.contains(offer))
+            .filter(|offer| !self.is_banned(&offer.address))
             .collect()
     }
     ///Gets all offers.
@@ -107,7 +261,27 @@ impl OfferBook {
     }
 
     /// Adds a new offer to the offer book.
+    ///
+    /// Offers advertising inconsistent size bounds, or from blacklisted makers, are not added,
+    /// and the maker is marked as bad.
     pub(crate) fn add_new_offer(&mut self, offer: &OfferAndAddress) -> bool {
+        if self.is_blacklisted(offer) {
+            log::warn!(
+                "Maker {} is blacklisted. Adding this to bad maker list",
+                offer.address
+            );
+            self.add_bad_maker(offer, BanReason::Blacklisted);
+            return false;
+        }
+        if let Err(reason) = check_offer_size_bounds(&offer.offer) {
+            log::warn!(
+                "Invalid offer size bounds from {}: {}. Adding this to bad maker list",
+                offer.address,
+                reason
+            );
+            self.add_bad_maker(offer, BanReason::InvalidOffer);
+            return false;
+        }
         if !self.all_makers.contains(offer) {
             self.all_makers.push(offer.clone());
             true
@@ -116,19 +290,232 @@ impl OfferBook {
         }
     }
 
-    /// Adds a bad maker to the offer book.
-    pub(crate) fn add_bad_maker(&mut self, bad_maker: &OfferAndAddress) -> bool {
-        if !self.bad_makers.contains(bad_maker) {
-            self.bad_makers.push(bad_maker.clone());
-            true
+    /// Bans a maker. Returns `false` if its address is already banned.
+    pub(crate) fn add_bad_maker(&mut self, bad_maker: &OfferAndAddress, reason: BanReason) -> bool {
+        if self.is_banned(&bad_maker.address) {
+            return false;
+        }
+        let banned_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.bans.push(BadMaker {
+            offer: bad_maker.clone(),
+            reason,
+            banned_at,
+        });
+        true
+    }
+
+    /// Checks if the maker at this address is banned.
+    fn is_banned(&self, address: &MakerAddress) -> bool {
+        self.bans.iter().any(|ban| ban.offer.address == *address)
+    }
+
+    /// Lifts the ban of the maker at this address. Returns `false` if it wasn't banned.
+    pub(crate) fn unban_maker(&mut self, address: &MakerAddress) -> bool {
+        let count = self.bans.len();
+        self.bans.retain(|ban| ban.offer.address != *address);
+        self.bans.len() != count
+    }
+
+    /// Lifts the bans older than `ban_duration` seconds at unix time `now`. `0` never expires a ban.
+    ///
+    /// Bans of blacklisted makers never expire, they are renewed at every sync anyway.
+    /// Returns the number of lifted bans.
+    pub(crate) fn expire_bans(&mut self, now: u64, ban_duration: u64) -> usize {
+        if ban_duration == 0 {
+            return 0;
+        }
+        let count = self.bans.len();
+        self.bans.retain(|ban| {
+            ban.reason == BanReason::Blacklisted || ban.banned_at.saturating_add(ban_duration) > now
+        });
+        count - self.bans.len()
+    }
+
+    /// Records a fidelity proof verification failure of the maker, and bans it.
+    ///
+    /// After `threshold` consecutive failures, the maker's address is added to the blacklist, and its
+    /// ban never expires. `0` never blacklists. Returns `true` if the maker got blacklisted.
+    pub(crate) fn record_fidelity_failure(
+        &mut self,
+        offer: &OfferAndAddress,
+        threshold: u32,
+    ) -> bool {
+        let failures = {
+            let failures = self
+                .fidelity_failures
+                .entry(offer.address.clone())
+                .or_default();
+            *failures += 1;
+            *failures
+        };
+
+        if threshold == 0 || failures < threshold || self.is_blacklisted(offer) {
+            self.add_bad_maker(offer, BanReason::InvalidFidelityProof);
+            return false;
+        }
+
+        log::warn!(
+            "Maker {} failed fidelity verification {} times in a row. Blacklisting it",
+            offer.address,
+            failures
+        );
+        self.blacklist
+            .insert(BlacklistEntry::Address(offer.address.clone()));
+        // The ban turns into a permanent one.
+        self.unban_maker(&offer.address);
+        self.add_bad_maker(offer, BanReason::Blacklisted);
+        true
+    }
+
+    /// Clears the fidelity proof failures of the maker, as its proof verified.
+    pub(crate) fn record_fidelity_success(&mut self, address: &MakerAddress) {
+        self.fidelity_failures.remove(address);
+    }
+
+    /// Records the measured round-trip latency of a maker.
+    pub(crate) fn record_rtt(&mut self, address: &MakerAddress, rtt: Duration) {
+        self.rtts.insert(address.clone(), rtt);
+    }
+
+    /// Gets the measured round-trip latency of a maker, if any.
+    pub fn get_rtt(&self, address: &MakerAddress) -> Option<Duration> {
+        self.rtts.get(address).copied()
+    }
+
+    /// Picks the candidate whose RTT is furthest from the RTTs of the already chosen makers.
+    ///
+    /// With no measured maker chosen yet, the lowest latency candidate is picked. Candidates
+    /// without a measured RTT are only considered when none of the others have one.
+    pub(crate) fn pick_latency_diverse<'a>(
+        &self,
+        candidates: &[&'a OfferAndAddress],
+        chosen: &[&MakerAddress],
+    ) -> Option<&'a OfferAndAddress> {
+        let chosen_rtts = chosen
+            .iter()
+            .filter_map(|addr| self.get_rtt(addr))
+            .collect::<Vec<_>>();
+
+        let measured = candidates
+            .iter()
+            .filter_map(|oa| self.get_rtt(&oa.address).map(|rtt| (*oa, rtt)));
+
+        let picked = if chosen_rtts.is_empty() {
+            measured.min_by_key(|(_, rtt)| *rtt)
         } else {
-            false
+            // Maximize the distance to the nearest chosen RTT. Ties go to the earliest candidate.
+            measured
+                .map(|(oa, rtt)| {
+                    let distance = chosen_rtts
+                        .iter()
+                        .map(|c| rtt.abs_diff(*c))
+                        .min()
+                        .unwrap_or_default();
+                    (oa, distance)
+                })
+                .rev()
+                .max_by_key(|(_, distance)| *distance)
+        };
+
+        picked
+            .map(|(oa, _)| oa)
+            .or_else(|| candidates.first().copied())
+    }
+
+    /// Records a swap the maker completed.
+    pub(crate) fn record_swap_success(&mut self, address: &MakerAddress) {
+        *self.swap_successes.entry(address.clone()).or_default() += 1;
+    }
+
+    /// Gets the number of swaps the maker completed.
+    pub fn get_swap_successes(&self, address: &MakerAddress) -> u32 {
+        self.swap_successes
+            .get(address)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Picks the candidate that completed the most swaps, if any of them completed one.
+    /// Ties go to the earliest candidate.
+    pub(crate) fn pick_known_good<'a>(
+        &self,
+        candidates: &[&'a OfferAndAddress],
+    ) -> Option<&'a OfferAndAddress> {
+        candidates
+            .iter()
+            .map(|oa| (*oa, self.get_swap_successes(&oa.address)))
+            .filter(|(_, successes)| *successes > 0)
+            .rev()
+            .max_by_key(|(_, successes)| *successes)
+            .map(|(oa, _)| oa)
+    }
+
+    /// Adds the entries to the blacklist, and marks the matching known makers as bad.
+    ///
+    /// Returns the number of newly blacklisted entries.
+    pub(crate) fn import_blacklist(&mut self, entries: Vec<BlacklistEntry>) -> usize {
+        let imported = entries
+            .into_iter()
+            .filter(|entry| self.blacklist.insert(entry.clone()))
+            .count();
+
+        let blacklisted = self
+            .all_makers
+            .iter()
+            .filter(|offer| self.is_blacklisted(offer))
+            .cloned()
+            .collect::<Vec<_>>();
+        for offer in &blacklisted {
+            self.add_bad_maker(offer, BanReason::Blacklisted);
         }
+
+        imported
+    }
+
+    /// Checks if the offer's maker address or fidelity bond is blacklisted.
+    fn is_blacklisted(&self, offer: &OfferAndAddress) -> bool {
+        self.blacklist
+            .contains(&BlacklistEntry::Address(offer.address.clone()))
+            || offer.offer.fidelity.as_ref().is_some_and(|fidelity| {
+                self.blacklist
+                    .contains(&BlacklistEntry::Bond(fidelity.bond.outpoint))
+            })
+    }
+
+    /// Clears all offers and latencies, to be re-populated by a fresh sync.
+    /// The bans, the blacklist, the fidelity proof failures and the swap successes are kept.
+    pub(crate) fn reset(&mut self) {
+        *self = OfferBook {
+            bans: std::mem::take(&mut self.bans),
+            blacklist: std::mem::take(&mut self.blacklist),
+            directory_synced_at: self.directory_synced_at,
+            fidelity_failures: std::mem::take(&mut self.fidelity_failures),
+            swap_successes: std::mem::take(&mut self.swap_successes),
+            ..OfferBook::default()
+        };
+    }
+
+    /// Gets the addresses of all known makers, to be used when no directory server is reachable.
+    pub(crate) fn cached_maker_addresses(&self) -> Vec<MakerAddress> {
+        let mut addresses = Vec::new();
+        for offer in &self.all_makers {
+            if !addresses.contains(&offer.address) {
+                addresses.push(offer.address.clone());
+            }
+        }
+        addresses
     }
 
     /// Gets the list of bad makers.
     pub(crate) fn get_bad_makers(&self) -> Vec<&OfferAndAddress> {
-        self.bad_makers.iter().collect()
+        self.bans.iter().map(|ban| &ban.offer).collect()
+    }
+
+    /// Gets the bans, with their reason and time.
+    pub(crate) fn list_bad_makers(&self) -> &[BadMaker] {
+        &self.bans
     }
 
     /// Load existing file, updates it, writes it back (errors if path doesn't exist).
@@ -165,33 +552,76 @@ impl OfferBook {
     }
 }
 
+/// Checks that the `min_size`/`max_size` bounds advertised in an offer are sane.
+fn check_offer_size_bounds(offer: &Offer) -> Result<(), &'static str> {
+    if offer.max_size == 0 {
+        Err("max_size is zero")
+    } else if offer.min_size < MIN_OFFER_SIZE {
+        Err("min_size is below dust")
+    } else if offer.min_size > offer.max_size {
+        Err("min_size is greater than max_size")
+    } else {
+        Ok(())
+    }
+}
+
 /// Synchronizes the offer book with specific maker addresses.
+///
+/// The makers are fetched in parallel by a pool of at most `offer_fetch_concurrency` threads,
+/// each taking the next maker from a shared queue once done with the previous one.
+/// Each offer is returned along with the round-trip latency measured while downloading it, in the
+/// order of the given addresses.
 pub(crate) fn fetch_offer_from_makers(
     maker_addresses: Vec<MakerAddress>,
     config: &TakerConfig,
-) -> Result<Vec<OfferAndAddress>, TakerError> {
-    let (offers_writer, offers_reader) = mpsc::channel::<Option<OfferAndAddress>>();
+    trace: &Arc<ProtocolTrace>,
+) -> Result<Vec<(OfferAndAddress, Duration)>, TakerError> {
+    let (offers_writer, offers_reader) =
+        mpsc::channel::<(usize, Option<(OfferAndAddress, Duration)>)>();
+    let maker_addresses_len = maker_addresses.len();
+    let worker_count = config
+        .offer_fetch_concurrency
+        .clamp(1, maker_addresses_len.max(1));
+    let queue = Arc::new(Mutex::new(
+        maker_addresses
+            .into_iter()
+            .enumerate()
+            .collect::<VecDeque<_>>(),
+    ));
+
     // Thread pool for all connections to fetch maker offers.
     let mut thread_pool = Vec::new();
-    let maker_addresses_len = maker_addresses.len();
-    for addr in maker_addresses {
+    for i in 0..worker_count {
         let offers_writer = offers_writer.clone();
         let taker_config = config.clone();
+        let queue = queue.clone();
+        let trace = trace.clone();
         let thread = Builder::new()
-            .name(format!("maker_offer_fetch_thread_{}", addr))
+            .name(format!("maker_offer_fetch_thread_{}", i))
             .spawn(move || -> Result<(), TakerError> {
-                let offer = download_maker_offer(addr, taker_config);
-                Ok(offers_writer.send(offer)?)
+                loop {
+                    let next = queue
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .pop_front();
+                    let Some((index, addr)) = next else {
+                        return Ok(());
+                    };
+                    let offer = download_maker_offer(addr, taker_config.clone(), &trace);
+                    offers_writer.send((index, offer))?;
+                }
             })?;
 
         thread_pool.push(thread);
     }
     let mut result = Vec::new();
     for _ in 0..maker_addresses_len {
-        if let Some(offer_addr) = offers_reader.recv()? {
-            result.push(offer_addr);
+        if let (index, Some(offer_addr)) = offers_reader.recv()? {
+            result.push((index, offer_addr));
         }
     }
+    // Keep the order of the addresses whatever the download order, so the maker selection is repeatable.
+    result.sort_by_key(|(index, _)| *index);
 
     for thread in thread_pool {
         let join_result = thread.join();
@@ -200,22 +630,65 @@ pub(crate) fn fetch_offer_from_makers(
             log::error!("Error while joining thread: {:?}", e);
         }
     }
-    Ok(result)
+    Ok(result
+        .into_iter()
+        .map(|(_, offer_addr)| offer_addr)
+        .collect())
+}
+
+/// Retrieves advertised maker addresses from all the given directory servers, in order.
+///
+/// Addresses are merged and deduplicated across the directory servers, so a single unreachable or
+/// incomplete directory doesn't hide makers. Fails only if none of the directory servers respond.
+pub fn fetch_addresses_from_directories(
+    socks_port: Option<u16>,
+    dns_addrs: &[String],
+    connection_type: ConnectionType,
+) -> Result<Vec<MakerAddress>, TakerError> {
+    let mut addresses = Vec::new();
+    let mut reached_any = false;
+    let mut last_error = None;
+    for dns_addr in dns_addrs {
+        log::info!("Fetching addresses from DNS: {}", dns_addr);
+        match fetch_addresses_from_dns(socks_port, dns_addr.clone(), connection_type) {
+            Ok(dns_addresses) => {
+                reached_any = true;
+                for address in dns_addresses {
+                    if !addresses.contains(&address) {
+                        addresses.push(address);
+                    }
+                }
+            }
+            Err(e) => {
+                log::error!("Could not connect to DNS Server {}: {:?}", dns_addr, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    if reached_any {
+        Ok(addresses)
+    } else {
+        Err(last_error.unwrap_or_else(|| NetError::ConnectionTimedOut.into()))
+    }
 }
 
 #[allow(unused_variables)]
-/// Retrieves advertised maker addresses from directory servers based on the specified network.
+/// Retrieves advertised maker addresses from a directory server based on the specified network.
+///
+/// Gives up with [NetError::ConnectionTimedOut] after a few failed attempts.
 pub fn fetch_addresses_from_dns(
     socks_port: Option<u16>,
     dns_addr: String,
     connection_type: ConnectionType,
 ) -> Result<Vec<MakerAddress>, TakerError> {
-    loop {
+    for attempt in 1..=DNS_FETCH_ATTEMPTS {
+        if attempt > 1 {
+            thread::sleep(GLOBAL_PAUSE);
+        }
         let mut stream = match connection_type {
             ConnectionType::CLEARNET => match TcpStream::connect(dns_addr.as_str()) {
                 Err(e) => {
                     log::error!("Error connecting to DNS: {:?}", e);
-                    thread::sleep(GLOBAL_PAUSE);
                     continue;
                 }
                 Ok(s) => s,
@@ -225,7 +698,6 @@ pub fn fetch_addresses_from_dns(
                 match Socks5Stream::connect(socket_addrs, dns_addr.as_str()) {
                     Err(e) => {
                         log::error!("Error connecting to DNS: {:?}", e);
-                        thread::sleep(GLOBAL_PAUSE);
                         continue;
                     }
                     Ok(s) => s.into_inner(),
@@ -239,7 +711,6 @@ pub fn fetch_addresses_from_dns(
 
         if let Err(e) = send_message(&mut stream, &DnsRequest::Get) {
             log::error!("Failed to send request. Retrying...{}", e);
-            thread::sleep(GLOBAL_PAUSE);
             continue;
         }
 
@@ -248,7 +719,6 @@ pub fn fetch_addresses_from_dns(
             Ok(resp) => serde_cbor::de::from_slice(&resp[..])?,
             Err(e) => {
                 log::error!("Error reading DNS response: {}. Retrying...", e);
-                thread::sleep(GLOBAL_PAUSE);
                 continue;
             }
         };
@@ -264,9 +734,448 @@ pub fn fetch_addresses_from_dns(
             }
             Err(e) => {
                 log::error!("Error decoding DNS response: {:?}. Retrying...", e);
-                thread::sleep(GLOBAL_PAUSE);
                 continue;
             }
         }
     }
+    Err(NetError::ConnectionTimedOut.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{protocol::messages::FidelityProof, wallet::FidelityBond};
+    use bitcoin::{
+        absolute::LockTime,
+        hashes::{sha256d, Hash},
+        secp256k1::{Message, Secp256k1, SecretKey},
+        Amount, OutPoint, PublicKey,
+    };
+
+    fn dummy_offer(min_size: u64, max_size: u64, port: &str) -> OfferAndAddress {
+        let secp = Secp256k1::new();
+        let privkey = SecretKey::from_slice(&[1; 32]).unwrap();
+        let pubkey = PublicKey {
+            compressed: true,
+            inner: privkey.public_key(&secp),
+        };
+        let cert_hash = sha256d::Hash::hash(b"cert");
+        let cert_sig = secp.sign_ecdsa(
+            &Message::from_digest_slice(cert_hash.as_byte_array()).unwrap(),
+            &privkey,
+        );
+        OfferAndAddress {
+            offer: Offer {
+                base_fee: 100,
+                amount_relative_fee_pct: 0.1,
+                time_relative_fee_pct: 0.005,
+                required_confirms: 1,
+                minimum_locktime: 20,
+                max_size,
+                min_size,
+                tweakable_point: pubkey,
+                fidelity: Some(FidelityProof {
+                    bond: FidelityBond {
+                        outpoint: OutPoint::null(),
+                        amount: Amount::from_sat(50_000),
+                        lock_time: LockTime::from_height(1000).unwrap(),
+                        pubkey,
+                        conf_height: None,
+                        cert_expiry: None,
+                    },
+                    cert_hash,
+                    cert_sig,
+                }),
+                segwit_only: false,
+                hashlock_variants: Vec::new(),
+                max_tx_count: 0,
+            },
+            address: MakerAddress::new(&format!("127.0.0.1:{}", port)).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_offer_with_inconsistent_bounds_is_rejected() {
+        let mut offerbook = OfferBook::default();
+
+        let good_offer = dummy_offer(10_000, 1_000_000, "6102");
+        let inverted_offer = dummy_offer(1_000_000, 10_000, "16102");
+        let zero_max_offer = dummy_offer(0, 0, "26102");
+        let dust_offer = dummy_offer(100, 1_000_000, "36102");
+
+        assert!(offerbook.add_new_offer(&good_offer));
+        assert!(!offerbook.add_new_offer(&inverted_offer));
+        assert!(!offerbook.add_new_offer(&zero_max_offer));
+        assert!(!offerbook.add_new_offer(&dust_offer));
+
+        assert_eq!(offerbook.all_good_makers(), vec![&good_offer]);
+        assert_eq!(offerbook.all_makers(), vec![&good_offer]);
+        assert_eq!(
+            offerbook.get_bad_makers(),
+            vec![&inverted_offer, &zero_max_offer, &dust_offer]
+        );
+    }
+
+    #[test]
+    fn test_latency_diverse_selection() {
+        let mut offerbook = OfferBook::default();
+
+        // Three makers sitting close together, and two far apart.
+        let rtts_ms = [
+            ("6102", 12),
+            ("16102", 10),
+            ("26102", 15),
+            ("36102", 150),
+            ("46102", 300),
+        ];
+        let offers = rtts_ms
+            .iter()
+            .map(|(port, rtt)| {
+                let offer = dummy_offer(10_000, 1_000_000, port);
+                offerbook.add_new_offer(&offer);
+                offerbook.record_rtt(&offer.address, Duration::from_millis(*rtt));
+                offer
+            })
+            .collect::<Vec<_>>();
+
+        // Pick a 3-maker route, the way the taker does, one maker at a time.
+        let mut route = Vec::<&OfferAndAddress>::new();
+        for _ in 0..3 {
+            let candidates = offerbook
+                .all_good_makers()
+                .into_iter()
+                .filter(|oa| !route.contains(oa))
+                .collect::<Vec<_>>();
+            let chosen = route.iter().map(|oa| &oa.address).collect::<Vec<_>>();
+            route.push(
+                offerbook
+                    .pick_latency_diverse(&candidates, &chosen)
+                    .unwrap(),
+            );
+        }
+
+        // Lowest latency first, then the makers furthest away from the chosen ones.
+        assert_eq!(route, vec![&offers[1], &offers[4], &offers[3]]);
+        let low_latency_picks = route
+            .iter()
+            .filter(|oa| offerbook.get_rtt(&oa.address).unwrap() < Duration::from_millis(100))
+            .count();
+        assert_eq!(low_latency_picks, 1);
+
+        // Makers without a measured RTT are used only as a last resort.
+        let unmeasured = dummy_offer(10_000, 1_000_000, "56102");
+        assert_eq!(
+            offerbook.pick_latency_diverse(&[&unmeasured, &offers[0]], &[]),
+            Some(&offers[0])
+        );
+        assert_eq!(
+            offerbook.pick_latency_diverse(&[&unmeasured], &[&offers[0].address]),
+            Some(&unmeasured)
+        );
+    }
+
+    #[test]
+    fn test_known_good_selection() {
+        let mut offerbook = OfferBook::default();
+        let untried = dummy_offer(10_000, 1_000_000, "6102");
+        let good = dummy_offer(10_000, 1_000_000, "16102");
+        let better = dummy_offer(10_000, 1_000_000, "26102");
+
+        // No maker completed a swap yet, so the selection falls back to `maker_selection`.
+        assert_eq!(offerbook.pick_known_good(&[&untried, &good]), None);
+
+        offerbook.record_swap_success(&good.address);
+        assert_eq!(offerbook.get_swap_successes(&good.address), 1);
+        assert_eq!(offerbook.get_swap_successes(&untried.address), 0);
+
+        // The previously-good maker is preferred over the untried one, wherever it is listed.
+        assert_eq!(offerbook.pick_known_good(&[&untried, &good]), Some(&good));
+
+        // The counts survive an offerbook sync, and the most successful maker goes first.
+        offerbook.reset();
+        offerbook.record_swap_success(&better.address);
+        offerbook.record_swap_success(&better.address);
+        assert_eq!(offerbook.get_swap_successes(&good.address), 1);
+        assert_eq!(
+            offerbook.pick_known_good(&[&untried, &good, &better]),
+            Some(&better)
+        );
+
+        // A maker already chosen for the route isn't a candidate anymore.
+        assert_eq!(offerbook.pick_known_good(&[&untried, &good]), Some(&good));
+    }
+
+    #[test]
+    fn test_blacklist_import() {
+        let mut offerbook = OfferBook::default();
+
+        let listed_by_address = dummy_offer(10_000, 1_000_000, "6102");
+        let mut listed_by_bond = dummy_offer(10_000, 1_000_000, "16102");
+        listed_by_bond
+            .offer
+            .fidelity
+            .as_mut()
+            .unwrap()
+            .bond
+            .outpoint
+            .vout = 1;
+        let mut good_offer = dummy_offer(10_000, 1_000_000, "26102");
+        good_offer
+            .offer
+            .fidelity
+            .as_mut()
+            .unwrap()
+            .bond
+            .outpoint
+            .vout = 2;
+        let local_bad_offer = dummy_offer(1_000_000, 10_000, "36102");
+
+        for offer in [&listed_by_address, &listed_by_bond, &good_offer] {
+            assert!(offerbook.add_new_offer(offer));
+        }
+        assert!(!offerbook.add_new_offer(&local_bad_offer));
+
+        let temp_dir = bitcoind::tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("blacklist.txt");
+        std::fs::write(
+            &path,
+            format!(
+                "# Known bad makers\n{}\n\n{}\n",
+                listed_by_address.address,
+                listed_by_bond
+                    .offer
+                    .fidelity
+                    .as_ref()
+                    .unwrap()
+                    .bond
+                    .outpoint
+            ),
+        )
+        .unwrap();
+        let entries = read_blacklist(&path).unwrap();
+        assert_eq!(offerbook.import_blacklist(entries.clone()), 2);
+        assert_eq!(offerbook.import_blacklist(entries), 0);
+
+        // Listed makers are marked bad, and locally found bad makers are kept.
+        assert_eq!(offerbook.all_good_makers(), vec![&good_offer]);
+        assert_eq!(
+            offerbook.get_bad_makers(),
+            vec![&local_bad_offer, &listed_by_address, &listed_by_bond]
+        );
+
+        // A fresh sync re-advertising the listed makers doesn't bring them back.
+        offerbook.reset();
+        for offer in [&listed_by_address, &listed_by_bond] {
+            assert!(!offerbook.add_new_offer(offer));
+        }
+        assert!(offerbook.add_new_offer(&good_offer));
+        assert_eq!(offerbook.all_good_makers(), vec![&good_offer]);
+
+        // Invalid entries are rejected.
+        std::fs::write(&path, "not a maker\n").unwrap();
+        assert!(read_blacklist(&path).is_err());
+    }
+
+    #[test]
+    fn test_bad_maker_ban_expiry() {
+        let mut offerbook = OfferBook::default();
+        let offer = dummy_offer(10_000, 1_000_000, "6102");
+        let mut listed = dummy_offer(10_000, 1_000_000, "16102");
+        listed.offer.fidelity.as_mut().unwrap().bond.outpoint.vout = 1;
+        let ban_duration = 3600;
+
+        // Simulates an offerbook sync at unix time `now`, re-advertising both makers.
+        let sync = |offerbook: &mut OfferBook, now: u64| {
+            offerbook.reset();
+            offerbook.expire_bans(now, ban_duration);
+            for offer in [&offer, &listed] {
+                offerbook.add_new_offer(offer);
+            }
+        };
+
+        assert!(offerbook.add_new_offer(&offer));
+        assert!(offerbook.add_bad_maker(&offer, BanReason::Unreachable));
+        assert!(!offerbook.add_bad_maker(&offer, BanReason::SignatureExchange));
+        offerbook.import_blacklist(vec![BlacklistEntry::Address(listed.address.clone())]);
+
+        let bans = offerbook.list_bad_makers();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].offer, offer);
+        assert_eq!(bans[0].reason, BanReason::Unreachable);
+        let banned_at = bans[0].banned_at;
+        assert!(banned_at > 0);
+
+        // The ban survives a sync before its expiry.
+        sync(&mut offerbook, banned_at + ban_duration - 1);
+        assert!(offerbook.all_good_makers().is_empty());
+        assert_eq!(offerbook.list_bad_makers().len(), 2);
+
+        // Past the expiry, the maker is eligible again. The blacklisted one stays banned.
+        sync(&mut offerbook, banned_at + ban_duration);
+        assert_eq!(offerbook.all_good_makers(), vec![&offer]);
+        assert_eq!(offerbook.get_bad_makers(), vec![&listed]);
+        assert_eq!(
+            offerbook.list_bad_makers()[0].reason,
+            BanReason::Blacklisted
+        );
+
+        // A manual unban takes effect right away.
+        assert!(offerbook.add_bad_maker(&offer, BanReason::FundingTimeout));
+        assert!(offerbook.unban_maker(&offer.address));
+        assert!(!offerbook.unban_maker(&offer.address));
+        assert_eq!(offerbook.all_good_makers(), vec![&offer]);
+
+        // With no duration, bans never expire.
+        assert!(offerbook.add_bad_maker(&offer, BanReason::FundingTimeout));
+        assert_eq!(offerbook.expire_bans(u64::MAX, 0), 0);
+        assert!(offerbook.all_good_makers().is_empty());
+    }
+
+    #[test]
+    fn test_fidelity_failure_blacklist() {
+        let mut offerbook = OfferBook::default();
+        let offer = dummy_offer(10_000, 1_000_000, "6102");
+        let ban_duration = 3600;
+        let threshold = 3;
+
+        // Simulates an offerbook sync at unix time `now`, where the maker's fidelity proof fails.
+        let failing_sync = |offerbook: &mut OfferBook, now: u64| {
+            offerbook.reset();
+            offerbook.expire_bans(now, ban_duration);
+            offerbook.record_fidelity_failure(&offer, threshold)
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // Failures below the threshold are ordinary bans, which expire.
+        assert!(!failing_sync(&mut offerbook, now));
+        assert_eq!(
+            offerbook.list_bad_makers()[0].reason,
+            BanReason::InvalidFidelityProof
+        );
+        assert!(!failing_sync(&mut offerbook, now + 2 * ban_duration));
+        assert_eq!(offerbook.fidelity_failures[&offer.address], 2);
+
+        // A verified proof breaks the streak.
+        offerbook.expire_bans(now + 4 * ban_duration, ban_duration);
+        offerbook.record_fidelity_success(&offer.address);
+        assert!(offerbook.add_new_offer(&offer));
+        assert!(offerbook.fidelity_failures.is_empty());
+
+        for sync in 1..threshold {
+            assert!(!failing_sync(
+                &mut offerbook,
+                now + (4 + 2 * sync as u64) * ban_duration
+            ));
+        }
+
+        // The threshold failure blacklists the maker, for good.
+        assert!(failing_sync(&mut offerbook, now + 20 * ban_duration));
+        let bans = offerbook.list_bad_makers();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].reason, BanReason::Blacklisted);
+        assert!(offerbook
+            .blacklist
+            .contains(&BlacklistEntry::Address(offer.address.clone())));
+
+        // The ban survives the expiry, and a later valid proof doesn't bring the maker back.
+        offerbook.reset();
+        offerbook.expire_bans(now + 100 * ban_duration, ban_duration);
+        offerbook.record_fidelity_success(&offer.address);
+        assert!(!offerbook.add_new_offer(&offer));
+        assert!(offerbook.all_good_makers().is_empty());
+
+        // With no threshold, makers are never blacklisted.
+        let mut offerbook = OfferBook::default();
+        for _ in 0..10 {
+            assert!(!offerbook.record_fidelity_failure(&offer, 0));
+        }
+        assert!(offerbook.blacklist.is_empty());
+    }
+
+    #[test]
+    fn test_cached_maker_addresses() {
+        let mut offerbook = OfferBook::default();
+        let first = dummy_offer(10_000, 1_000_000, "6102");
+        let mut second = dummy_offer(10_000, 1_000_000, "16102");
+        second.offer.fidelity.as_mut().unwrap().bond.outpoint.vout = 1;
+        offerbook.all_makers = vec![first.clone(), second.clone(), first.clone()];
+        offerbook.directory_synced_at = Some(1_700_000_000);
+
+        assert_eq!(
+            offerbook.cached_maker_addresses(),
+            vec![first.address, second.address]
+        );
+
+        // The last directory sync time survives a reset.
+        offerbook.reset();
+        assert!(offerbook.cached_maker_addresses().is_empty());
+        assert_eq!(offerbook.directory_synced_at, Some(1_700_000_000));
+    }
+
+    /// Spawns a mock maker on a local port, serving `offer` after `latency` to every offer request.
+    fn spawn_mock_maker(offer: Offer, latency: Duration) -> MakerAddress {
+        use crate::protocol::messages::{MakerHello, MakerToTakerMessage};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = MakerAddress::new(&listener.local_addr().unwrap().to_string()).unwrap();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let offer = offer.clone();
+                thread::spawn(move || {
+                    // TakerHello
+                    read_message(&mut stream).unwrap();
+                    let hello = MakerToTakerMessage::MakerHello(MakerHello {
+                        protocol_version_min: 1,
+                        protocol_version_max: 1,
+                        compression: false,
+                    });
+                    send_message(&mut stream, &hello).unwrap();
+                    // ReqGiveOffer
+                    read_message(&mut stream).unwrap();
+                    thread::sleep(latency);
+                    send_message(
+                        &mut stream,
+                        &MakerToTakerMessage::RespOffer(Box::new(offer)),
+                    )
+                    .unwrap();
+                });
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn test_fetch_offers_from_many_makers() {
+        const MAKER_COUNT: usize = 50;
+        let latency = Duration::from_millis(200);
+
+        let addresses = (0..MAKER_COUNT)
+            .map(|_| spawn_mock_maker(dummy_offer(10_000, 1_000_000, "6102").offer, latency))
+            .collect::<Vec<_>>();
+
+        let config = TakerConfig {
+            connection_type: ConnectionType::CLEARNET,
+            ..TakerConfig::default()
+        };
+
+        let start = std::time::Instant::now();
+        let offers = fetch_offer_from_makers(addresses.clone(), &config, &Arc::default()).unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(offers.len(), MAKER_COUNT);
+        let fetched = offers
+            .iter()
+            .map(|(offer, _)| offer.address.to_string())
+            .collect::<HashSet<_>>();
+        assert_eq!(fetched.len(), MAKER_COUNT);
+        assert!(addresses
+            .iter()
+            .all(|address| fetched.contains(&address.to_string())));
+
+        // One maker at a time would take at least 50 * 200ms = 10s.
+        assert!(elapsed < Duration::from_secs(5));
+    }
 }
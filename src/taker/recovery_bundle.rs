@@ -0,0 +1,209 @@
+//! Recovery bundles of a stuck swap, to hand over to a maintainer for diagnosis.
+//!
+//! By default, a bundle only describes the swap: the state of the ongoing swap, if any, and the txids,
+//! scripts, locktimes and states of all the unfinished swapcoins of the wallet. It holds no private key,
+//! and is safe to share.
+//!
+//! Only on explicit request, a bundle also carries the swapcoins with their private keys. Importing it into
+//! any Taker on the same network is then enough to run the recovery, and so is it to take the coins.
+//!
+//! The file is `magic | version | CBOR encoded [RecoveryBundle]`, unencrypted like the wallet file.
+
+use std::{fs, path::Path};
+
+use bitcoin::{Amount, Network, OutPoint, ScriptBuf, Transaction, Txid};
+use serde::{Deserialize, Serialize};
+
+use super::error::TakerError;
+use crate::wallet::{IncomingSwapCoin, OutgoingSwapCoin, SwapCoin, WalletSwapCoin};
+
+/// Magic bytes of a recovery bundle file.
+const BUNDLE_MAGIC: &[u8; 4] = b"CSRB";
+/// Version of the bundle file format.
+const BUNDLE_VERSION: u8 = 1;
+const HEADER_LEN: usize = BUNDLE_MAGIC.len() + 1;
+
+/// State of the swap in progress when the bundle was exported, for diagnosis only.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SwapSnapshot {
+    /// Unique ID of the swap.
+    pub(crate) id: String,
+    /// Amount sent into the swap.
+    pub(crate) send_amount: Amount,
+    /// Number of makers of the route.
+    pub(crate) maker_count: usize,
+    /// Number of funding txs per hop.
+    pub(crate) tx_count: u32,
+    /// Confirmation depth asked for the funding txs.
+    pub(crate) required_confirms: u32,
+    /// Addresses of the makers reached so far, in route order.
+    pub(crate) maker_addresses: Vec<String>,
+    /// Txids of the funding txs of each hop reached so far.
+    pub(crate) funding_txids: Vec<Vec<Txid>>,
+    /// Contract txs of the hops between makers, watched by the Taker.
+    pub(crate) watchonly_contract_txs: Vec<Vec<Transaction>>,
+}
+
+/// An unfinished swapcoin of the wallet, without its keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SwapcoinSnapshot {
+    /// Whether the Taker receives the coin, or sends it.
+    pub(crate) incoming: bool,
+    /// Output of the funding tx, locked in the multisig.
+    pub(crate) funding_outpoint: OutPoint,
+    /// Value of the funding output.
+    pub(crate) funding_amount: Amount,
+    /// The 2-of-2 multisig redeemscript of the funding output.
+    pub(crate) multisig_redeemscript: ScriptBuf,
+    /// The hashlock/timelock contract redeemscript.
+    pub(crate) contract_redeemscript: ScriptBuf,
+    /// Txid of the contract tx, spending the funding output into the contract.
+    pub(crate) contract_txid: Txid,
+    /// Relative locktime of the contract timelock branch.
+    pub(crate) locktime: Option<u16>,
+    /// Whether the other party's signature of the contract tx is held.
+    pub(crate) contract_signed: bool,
+    /// Whether the hash preimage is known.
+    pub(crate) hash_preimage_known: bool,
+    /// Whether the other party's multisig private key was handed over. Always false for outgoing coins.
+    pub(crate) other_privkey_known: bool,
+}
+
+impl From<&IncomingSwapCoin> for SwapcoinSnapshot {
+    fn from(swapcoin: &IncomingSwapCoin) -> Self {
+        SwapcoinSnapshot {
+            incoming: true,
+            funding_outpoint: swapcoin.contract_tx.input[0].previous_output,
+            funding_amount: swapcoin.funding_amount,
+            multisig_redeemscript: swapcoin.get_multisig_redeemscript(),
+            contract_redeemscript: swapcoin.contract_redeemscript.clone(),
+            contract_txid: swapcoin.contract_tx.compute_txid(),
+            locktime: swapcoin.get_timelock().ok(),
+            contract_signed: swapcoin.others_contract_sig.is_some(),
+            hash_preimage_known: swapcoin.is_hash_preimage_known(),
+            other_privkey_known: swapcoin.other_privkey.is_some(),
+        }
+    }
+}
+
+impl From<&OutgoingSwapCoin> for SwapcoinSnapshot {
+    fn from(swapcoin: &OutgoingSwapCoin) -> Self {
+        SwapcoinSnapshot {
+            incoming: false,
+            funding_outpoint: swapcoin.contract_tx.input[0].previous_output,
+            funding_amount: swapcoin.funding_amount,
+            multisig_redeemscript: swapcoin.get_multisig_redeemscript(),
+            contract_redeemscript: swapcoin.contract_redeemscript.clone(),
+            contract_txid: swapcoin.contract_tx.compute_txid(),
+            locktime: swapcoin.get_timelock().ok(),
+            contract_signed: swapcoin.others_contract_sig.is_some(),
+            hash_preimage_known: swapcoin.is_hash_preimage_known(),
+            other_privkey_known: false,
+        }
+    }
+}
+
+/// The unfinished swapcoins of the wallet, with their private keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PrivateSwapcoins {
+    /// Incoming swapcoins, not yet claimed.
+    pub(crate) incoming: Vec<IncomingSwapCoin>,
+    /// Outgoing swapcoins, not yet settled.
+    pub(crate) outgoing: Vec<OutgoingSwapCoin>,
+}
+
+/// Everything needed to diagnose a stuck swap, and, with the private keys, to recover from it in another
+/// Taker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RecoveryBundle {
+    /// Network of the exporting wallet.
+    pub(crate) network: Network,
+    /// Unix timestamp of the export.
+    pub(crate) created_at: u64,
+    /// The swap in progress at the export, if any.
+    pub(crate) ongoing_swap: Option<SwapSnapshot>,
+    /// The unfinished swapcoins of the wallet.
+    pub(crate) swapcoins: Vec<SwapcoinSnapshot>,
+    /// The same swapcoins with their private keys, only if explicitly exported.
+    pub(crate) private_swapcoins: Option<PrivateSwapcoins>,
+}
+
+/// Writes the bundle to the file.
+pub(crate) fn write_recovery_bundle(
+    path: &Path,
+    bundle: &RecoveryBundle,
+) -> Result<(), TakerError> {
+    let mut file = Vec::with_capacity(HEADER_LEN);
+    file.extend_from_slice(BUNDLE_MAGIC);
+    file.push(BUNDLE_VERSION);
+    file.extend_from_slice(&serde_cbor::to_vec(bundle)?);
+    fs::write(path, file)?;
+    Ok(())
+}
+
+/// Reads a bundle file.
+pub(crate) fn read_recovery_bundle(path: &Path) -> Result<RecoveryBundle, TakerError> {
+    let file = fs::read(path)?;
+    if file.len() < HEADER_LEN || !file.starts_with(BUNDLE_MAGIC) {
+        return Err(TakerError::InvalidRecoveryBundle(
+            "Not a recovery bundle".to_string(),
+        ));
+    }
+    let (header, bundle) = file.split_at(HEADER_LEN);
+    let version = header[BUNDLE_MAGIC.len()];
+    if version != BUNDLE_VERSION {
+        return Err(TakerError::InvalidRecoveryBundle(format!(
+            "Unsupported bundle version {}",
+            version
+        )));
+    }
+    Ok(serde_cbor::from_slice(bundle)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoind::tempfile::tempdir;
+
+    fn bundle() -> RecoveryBundle {
+        RecoveryBundle {
+            network: Network::Regtest,
+            created_at: 1_700_000_000,
+            ongoing_swap: Some(SwapSnapshot {
+                id: "aaaa".to_string(),
+                send_amount: Amount::from_sat(500_000),
+                maker_count: 2,
+                tx_count: 3,
+                required_confirms: 1,
+                maker_addresses: vec!["127.0.0.1:6102".to_string()],
+                funding_txids: Vec::new(),
+                watchonly_contract_txs: Vec::new(),
+            }),
+            swapcoins: Vec::new(),
+            private_swapcoins: None,
+        }
+    }
+
+    #[test]
+    fn test_recovery_bundle_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("bundle.dat");
+        write_recovery_bundle(&path, &bundle()).unwrap();
+        assert_eq!(read_recovery_bundle(&path).unwrap(), bundle());
+
+        // Files of another format, or version, are refused.
+        let mut file = fs::read(&path).unwrap();
+        file[BUNDLE_MAGIC.len()] += 1;
+        fs::write(&path, &file).unwrap();
+        assert!(matches!(
+            read_recovery_bundle(&path),
+            Err(TakerError::InvalidRecoveryBundle(_))
+        ));
+        file[0] ^= 1;
+        fs::write(&path, file).unwrap();
+        assert!(matches!(
+            read_recovery_bundle(&path),
+            Err(TakerError::InvalidRecoveryBundle(_))
+        ));
+    }
+}
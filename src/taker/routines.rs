@@ -8,13 +8,18 @@
 
 use serde::{Deserialize, Serialize};
 use socks::Socks5Stream;
-use std::{net::TcpStream, thread::sleep, time::Duration};
+use std::{
+    collections::HashSet,
+    net::{TcpStream, ToSocketAddrs},
+    thread::sleep,
+    time::{Duration, Instant},
+};
 
 use crate::{
     protocol::{
         contract::{
-            calculate_coinswap_fee, create_contract_redeemscript, find_funding_output_index,
-            validate_contract_tx,
+            calculate_coinswap_fee, check_contract_txid, create_contract_redeemscript,
+            create_senders_contract_tx, find_funding_output_index, validate_contract_tx,
         },
         error::ProtocolError,
         messages::{
@@ -24,13 +29,15 @@ use crate::{
             ProofOfFunding, ReqContractSigsForRecvr, ReqContractSigsForSender, TakerHello,
             TakerToMakerMessage,
         },
-        Hash160,
+        trace::{ProtocolTrace, TracedStream},
+        Hashvalue,
     },
-    taker::api::MINER_FEE,
-    utill::{read_message, send_message, ConnectionType},
+    taker::api::{contract_fee_escalation, MINER_FEE},
+    utill::{set_socket_options, ConnectionType},
     wallet::WalletError,
 };
-use bitcoin::{secp256k1::SecretKey, Amount, PublicKey, ScriptBuf, Transaction};
+use bitcoin::{secp256k1::SecretKey, Amount, PublicKey, ScriptBuf, Transaction, Txid};
+use bitcoind::bitcoincore_rpc::{Client, RpcApi};
 
 use super::{
     config::TakerConfig,
@@ -38,9 +45,7 @@ use super::{
     offers::{MakerAddress, OfferAndAddress},
 };
 
-use crate::taker::api::{
-    FIRST_CONNECT_ATTEMPTS, FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC, FIRST_CONNECT_SLEEP_DELAY_SEC,
-};
+use crate::taker::api::{FIRST_CONNECT_ATTEMPTS, FIRST_CONNECT_SLEEP_DELAY_SEC};
 
 use crate::wallet::SwapCoin;
 
@@ -59,26 +64,142 @@ pub(crate) struct ContractsInfo {
     pub(crate) wallet_label: String,
 }
 
+/// Open a connection to a maker, with the given read/write timeout.
+///
+/// Clearnet connections give up after `config.connect_timeout`. Tor connections go through
+/// the SOCKS proxy, which has no connect timeout of its own.
+pub(crate) fn connect_to_maker(
+    address: &str,
+    config: &TakerConfig,
+    timeout: Duration,
+) -> Result<TcpStream, TakerError> {
+    let socket = match config.connection_type {
+        ConnectionType::CLEARNET => {
+            let connect_timeout = Duration::from_secs(config.connect_timeout);
+            let mut last_err = None;
+            let mut socket = None;
+            for socket_addr in address.to_socket_addrs()? {
+                match TcpStream::connect_timeout(&socket_addr, connect_timeout) {
+                    Ok(s) => {
+                        socket = Some(s);
+                        break;
+                    }
+                    Err(e) => last_err = Some(e),
+                }
+            }
+            match socket {
+                Some(s) => s,
+                None => {
+                    return Err(last_err
+                        .unwrap_or_else(|| {
+                            std::io::Error::new(
+                                std::io::ErrorKind::InvalidInput,
+                                format!("Could not resolve maker address {}", address),
+                            )
+                        })
+                        .into())
+                }
+            }
+        }
+        ConnectionType::TOR => {
+            Socks5Stream::connect(format!("127.0.0.1:{}", config.socks_port).as_str(), address)?
+                .into_inner()
+        }
+    };
+
+    socket.set_read_timeout(Some(timeout))?;
+    socket.set_write_timeout(Some(timeout))?;
+    set_socket_options(&socket, config.tcp_nodelay, config.tcp_keepalive_interval)?;
+    Ok(socket)
+}
+
+/// Chain queries used to look for broadcasted contract txs. Implemented by the node's RPC client.
+pub(crate) trait ContractTxLookup {
+    /// Height of the chain tip.
+    fn tip_height(&self) -> Result<u64, WalletError>;
+    /// Txids of all the txs in the block at `height`.
+    fn block_txids(&self, height: u64) -> Result<Vec<Txid>, WalletError>;
+    /// Txids of all the txs in the mempool.
+    fn mempool_txids(&self) -> Result<Vec<Txid>, WalletError>;
+    /// Whether the node knows the tx, either in the mempool or on chain.
+    fn is_tx_known(&self, txid: &Txid) -> bool;
+}
+
+impl ContractTxLookup for Client {
+    fn tip_height(&self) -> Result<u64, WalletError> {
+        Ok(self.get_block_count()?)
+    }
+
+    fn block_txids(&self, height: u64) -> Result<Vec<Txid>, WalletError> {
+        let hash = self.get_block_hash(height)?;
+        Ok(self.get_block_info(&hash)?.tx)
+    }
+
+    fn mempool_txids(&self) -> Result<Vec<Txid>, WalletError> {
+        Ok(self.get_raw_mempool()?)
+    }
+
+    fn is_tx_known(&self, txid: &Txid) -> bool {
+        self.get_raw_transaction_info(txid, None).is_ok()
+    }
+}
+
+/// Finds which of the `contract_txids` have been broadcasted.
+///
+/// Without a `from_height`, every contract txid is looked up on its own. Otherwise only the blocks
+/// above `from_height` and the mempool are scanned, so repeated checks don't query the same txs again.
+/// Returns the broadcasted txids, and the height checked up to, for the next call.
+pub(crate) fn find_broadcasted_txids<L: ContractTxLookup + ?Sized>(
+    lookup: &L,
+    contract_txids: &[Txid],
+    from_height: Option<u64>,
+) -> Result<(Vec<Txid>, u64), WalletError> {
+    // The tip is read first, so a tx mined while scanning is caught by the next call.
+    let tip_height = lookup.tip_height()?;
+
+    let seen_txids = match from_height {
+        None => contract_txids
+            .iter()
+            .filter(|txid| lookup.is_tx_known(txid))
+            .cloned()
+            .collect(),
+        Some(from_height) => {
+            let mut new_txids = lookup.mempool_txids()?.into_iter().collect::<HashSet<_>>();
+            for height in (from_height + 1)..=tip_height {
+                new_txids.extend(lookup.block_txids(height)?);
+            }
+            contract_txids
+                .iter()
+                .filter(|txid| new_txids.contains(*txid))
+                .cloned()
+                .collect()
+        }
+    };
+
+    Ok((seen_txids, tip_height))
+}
+
 /// Make a handshake with a maker.
-/// Ensures that the Maker is alive and responding.
+/// Ensures that the Maker is alive and responding. Returns whether large messages can be sent
+/// compressed on this connection, which older makers don't support.
 ///
 // In future, handshake can be used to find protocol compatibility across multiple versions.
-pub(crate) fn handshake_maker(socket: &mut TcpStream) -> Result<(), TakerError> {
-    send_message(
-        socket,
+pub(crate) fn handshake_maker(socket: &mut TracedStream) -> Result<bool, TakerError> {
+    socket.send(
         &TakerToMakerMessage::TakerHello(TakerHello {
             protocol_version_min: 1,
             protocol_version_max: 1,
+            compression: true,
         }),
+        false,
     )?;
-    let msg_bytes = read_message(socket)?;
-    let msg: MakerToTakerMessage = serde_cbor::from_slice(&msg_bytes)?;
+    let msg: MakerToTakerMessage = socket.read(false)?;
 
     // Check that protocol version is always 1.
     match msg {
         MakerToTakerMessage::MakerHello(m) => {
             if m.protocol_version_max == 1 && m.protocol_version_min == 1 {
-                Ok(())
+                Ok(m.compression)
             } else {
                 Err(ProtocolError::WrongMessage {
                     expected: "Only protocol version 1 is allowed".to_string(),
@@ -98,15 +219,38 @@ pub(crate) fn handshake_maker(socket: &mut TcpStream) -> Result<(), TakerError>
     }
 }
 
+/// Maximum number of replayed messages ignored while waiting for a maker's reply.
+const MAX_REPLAYED_MESSAGES: usize = 3;
+
+/// Reads the maker's reply to a request sent after the handshake.
+///
+/// A buggy or malicious maker may resend a message it already sent on the connection. The handshake
+/// is done by then, so a replayed [MakerToTakerMessage::MakerHello] is ignored instead of failing the
+/// swap step. The reply itself is read once, so its replays are never applied.
+pub(crate) fn read_maker_reply(
+    socket: &mut TracedStream,
+    compression: bool,
+) -> Result<MakerToTakerMessage, TakerError> {
+    for _ in 0..=MAX_REPLAYED_MESSAGES {
+        let msg: MakerToTakerMessage = socket.read(compression)?;
+        if let MakerToTakerMessage::MakerHello(_) = msg {
+            log::warn!("Ignoring a replayed MakerHello");
+            continue;
+        }
+        return Ok(msg);
+    }
+    Err(ProtocolError::General("Too many replayed messages from the maker").into())
+}
+
 /// Request signatures for sender side of the hop. Attempt once.
 pub(crate) fn req_sigs_for_sender_once<S: SwapCoin>(
-    socket: &mut TcpStream,
+    socket: &mut TracedStream,
     outgoing_swapcoins: &[S],
     maker_multisig_nonces: &[SecretKey],
     maker_hashlock_nonces: &[SecretKey],
     locktime: u16,
 ) -> Result<ContractSigsForSender, TakerError> {
-    handshake_maker(socket)?;
+    let compression = handshake_maker(socket)?;
     let txs_info = maker_multisig_nonces
         .iter()
         .zip(maker_hashlock_nonces.iter())
@@ -125,17 +269,17 @@ pub(crate) fn req_sigs_for_sender_once<S: SwapCoin>(
         )
         .collect::<Result<Vec<ContractTxInfoForSender>, WalletError>>()?;
 
-    send_message(
-        socket,
+    socket.send(
         &TakerToMakerMessage::ReqContractSigsForSender(ReqContractSigsForSender {
             txs_info,
             hashvalue: outgoing_swapcoins[0].get_hashvalue()?,
             locktime,
+            fee_escalation: contract_fee_escalation(),
         }),
+        compression,
     )?;
 
-    let msg_bytes = read_message(socket)?;
-    let msg: MakerToTakerMessage = serde_cbor::from_slice(&msg_bytes)?;
+    let msg = read_maker_reply(socket, compression)?;
     let contract_sigs_for_sender = match msg {
         MakerToTakerMessage::RespContractSigsForSender(m) => {
             if m.sigs.len() != outgoing_swapcoins.len() {
@@ -148,6 +292,9 @@ pub(crate) fn req_sigs_for_sender_once<S: SwapCoin>(
                 m
             }
         }
+        MakerToTakerMessage::RespSwapDeclined(reason) => {
+            return Err(TakerError::SwapDeclined(reason.to_string()));
+        }
         any => {
             return Err((ProtocolError::WrongMessage {
                 expected: "RespContractSigsForSender".to_string(),
@@ -164,16 +311,35 @@ pub(crate) fn req_sigs_for_sender_once<S: SwapCoin>(
     {
         outgoing_swapcoin.verify_contract_tx_sender_sig(sig)?;
     }
+
+    // Older makers don't sign fee-escalated variants.
+    if !contract_sigs_for_sender.escalated_sigs.is_empty() {
+        if contract_sigs_for_sender.escalated_sigs.len() != outgoing_swapcoins.len() {
+            return Err((ProtocolError::WrongNumOfSigs {
+                expected: outgoing_swapcoins.len(),
+                received: contract_sigs_for_sender.escalated_sigs.len(),
+            })
+            .into());
+        }
+        for (sigs, outgoing_swapcoin) in contract_sigs_for_sender
+            .escalated_sigs
+            .iter()
+            .zip(outgoing_swapcoins.iter())
+        {
+            outgoing_swapcoin
+                .verify_escalated_contract_tx_sender_sigs(&contract_fee_escalation(), sigs)?;
+        }
+    }
     Ok(contract_sigs_for_sender)
 }
 
 /// Request signatures for receiver side of the hop. Attempt once.
 pub(crate) fn req_sigs_for_recvr_once<S: SwapCoin>(
-    socket: &mut TcpStream,
+    socket: &mut TracedStream,
     incoming_swapcoins: &[S],
     receivers_contract_txes: &[Transaction],
 ) -> Result<ContractSigsForRecvr, TakerError> {
-    handshake_maker(socket)?;
+    let compression = handshake_maker(socket)?;
 
     let txs_info = incoming_swapcoins
         .iter()
@@ -184,13 +350,15 @@ pub(crate) fn req_sigs_for_recvr_once<S: SwapCoin>(
         })
         .collect::<Vec<ContractTxInfoForRecvr>>();
 
-    send_message(
-        socket,
-        &TakerToMakerMessage::ReqContractSigsForRecvr(ReqContractSigsForRecvr { txs: txs_info }),
+    socket.send(
+        &TakerToMakerMessage::ReqContractSigsForRecvr(ReqContractSigsForRecvr {
+            txs: txs_info,
+            fee_escalation: contract_fee_escalation(),
+        }),
+        compression,
     )?;
 
-    let msg_bytes = read_message(socket)?;
-    let msg: MakerToTakerMessage = serde_cbor::from_slice(&msg_bytes)?;
+    let msg = read_maker_reply(socket, compression)?;
     let contract_sigs_for_recvr = match msg {
         MakerToTakerMessage::RespContractSigsForRecvr(m) => {
             if m.sigs.len() != incoming_swapcoins.len() {
@@ -219,6 +387,24 @@ pub(crate) fn req_sigs_for_recvr_once<S: SwapCoin>(
     {
         swapcoin.verify_contract_tx_receiver_sig(sig)?;
     }
+
+    if !contract_sigs_for_recvr.escalated_sigs.is_empty() {
+        if contract_sigs_for_recvr.escalated_sigs.len() != incoming_swapcoins.len() {
+            return Err((ProtocolError::WrongNumOfSigs {
+                expected: incoming_swapcoins.len(),
+                received: contract_sigs_for_recvr.escalated_sigs.len(),
+            })
+            .into());
+        }
+        for (sigs, swapcoin) in contract_sigs_for_recvr
+            .escalated_sigs
+            .iter()
+            .zip(incoming_swapcoins.iter())
+        {
+            swapcoin
+                .verify_escalated_contract_tx_receiver_sigs(&contract_fee_escalation(), sigs)?;
+        }
+    }
     Ok(contract_sigs_for_recvr)
 }
 
@@ -230,6 +416,7 @@ pub(crate) struct ThisMakerInfo {
     pub(crate) funding_tx_infos: Vec<FundingTxInfo>,
     pub(crate) this_maker_contract_txs: Vec<Transaction>,
     pub this_maker_refund_locktime: u16,
+    pub(crate) required_confirms: u32,
 }
 
 // Type for information related to the next peer // why not next Maker?
@@ -240,11 +427,13 @@ pub struct NextMakerInfo {
 }
 
 /// [Internal] Send a Proof funding to the maker and init next hop.
+/// `compression` is the one negotiated at the handshake on the socket.
 pub(crate) fn send_proof_of_funding_and_init_next_hop(
-    socket: &mut TcpStream,
+    socket: &mut TracedStream,
+    compression: bool,
     tmi: ThisMakerInfo,
     npi: NextMakerInfo,
-    hashvalue: Hash160,
+    hashvalue: Hashvalue,
     id: String,
 ) -> Result<(ContractSigsAsRecvrAndSender, Vec<ScriptBuf>), TakerError> {
     // Send POF
@@ -266,13 +455,13 @@ pub(crate) fn send_proof_of_funding_and_init_next_hop(
         refund_locktime: tmi.this_maker_refund_locktime,
         contract_feerate: MINER_FEE,
         id,
+        required_confirms: tmi.required_confirms,
     });
 
-    send_message(socket, &pof_msg)?;
+    socket.send(&pof_msg, compression)?;
 
     // Recv ContractSigsAsRecvrAndSender.
-    let msg_bytes = read_message(socket)?;
-    let msg: MakerToTakerMessage = serde_cbor::from_slice(&msg_bytes)?;
+    let msg = read_maker_reply(socket, compression)?;
     let contract_sigs_as_recvr_and_sender = match msg {
         MakerToTakerMessage::ReqContractSigsAsRecvrAndSender(m) => {
             if m.receivers_contract_txs.len() != tmi.funding_tx_infos.len() {
@@ -366,6 +555,7 @@ pub(crate) fn send_proof_of_funding_and_init_next_hop(
             Some(&contract_tx.input[0].previous_output),
             contract_redeemscript,
         )?;
+        check_contract_txid(contract_tx, receivers_contract_tx)?;
     }
     let next_swap_contract_redeemscripts = npi
         .next_peer_hashlock_pubkeys
@@ -384,6 +574,24 @@ pub(crate) fn send_proof_of_funding_and_init_next_hop(
             )
         })
         .collect::<Vec<_>>();
+
+    // Rebuild the Maker's outgoing contract txs with the agreed fee, before signing anything for them.
+    for (senders_contract_tx_info, contract_redeemscript) in contract_sigs_as_recvr_and_sender
+        .senders_contract_txs_info
+        .iter()
+        .zip(next_swap_contract_redeemscripts.iter())
+    {
+        let contract_tx = &senders_contract_tx_info.contract_tx;
+        validate_contract_tx(contract_tx, None, contract_redeemscript)?;
+        let expected_contract_tx = create_senders_contract_tx(
+            contract_tx.input[0].previous_output,
+            senders_contract_tx_info.funding_amount,
+            contract_redeemscript,
+            Amount::from_sat(MINER_FEE),
+        )?;
+        check_contract_txid(&expected_contract_tx, contract_tx)?;
+    }
+
     Ok((
         contract_sigs_as_recvr_and_sender,
         next_swap_contract_redeemscripts,
@@ -391,8 +599,10 @@ pub(crate) fn send_proof_of_funding_and_init_next_hop(
 }
 
 /// Send hash preimage via the writer and read the response.
+/// `compression` is the one negotiated at the handshake on the socket.
 pub(crate) fn send_hash_preimage_and_get_private_keys(
-    socket: &mut TcpStream,
+    socket: &mut TracedStream,
+    compression: bool,
     senders_multisig_redeemscripts: &[ScriptBuf],
     receivers_multisig_redeemscripts: &[ScriptBuf],
     preimage: &Preimage,
@@ -403,10 +613,9 @@ pub(crate) fn send_hash_preimage_and_get_private_keys(
         preimage: *preimage,
     });
 
-    send_message(socket, &hash_preimage_msg)?;
+    socket.send(&hash_preimage_msg, false)?;
 
-    let msg_bytes = read_message(socket)?;
-    let msg: MakerToTakerMessage = serde_cbor::from_slice(&msg_bytes)?;
+    let msg = read_maker_reply(socket, compression)?;
     let privkey_handover = match msg {
         MakerToTakerMessage::RespPrivKeyHandover(m) => {
             if m.multisig_privkeys.len() != receivers_multisig_redeemscripts.len() {
@@ -434,27 +643,25 @@ pub(crate) fn send_hash_preimage_and_get_private_keys(
 fn download_maker_offer_attempt_once(
     addr: &MakerAddress,
     config: &TakerConfig,
-) -> Result<Offer, TakerError> {
+    trace: &ProtocolTrace,
+) -> Result<(Offer, Duration), TakerError> {
     let maker_addr = addr.to_string();
     log::info!("Attempting to download Offer from {}", maker_addr);
-    let mut socket = match config.connection_type {
-        ConnectionType::CLEARNET => TcpStream::connect(&maker_addr)?,
-        ConnectionType::TOR => Socks5Stream::connect(
-            format!("127.0.0.1:{}", config.socks_port).as_str(),
-            maker_addr.as_ref(),
-        )?
-        .into_inner(),
-    };
-
-    socket.set_read_timeout(Some(Duration::from_secs(FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC)))?;
-    socket.set_write_timeout(Some(Duration::from_secs(FIRST_CONNECT_ATTEMPT_TIMEOUT_SEC)))?;
+    let mut stream = connect_to_maker(
+        &maker_addr,
+        config,
+        Duration::from_secs(config.offer_fetch_timeout),
+    )?;
+    let mut socket = TracedStream::new(&mut stream, trace);
 
-    handshake_maker(&mut socket)?;
+    let compression = handshake_maker(&mut socket)?;
 
-    send_message(&mut socket, &TakerToMakerMessage::ReqGiveOffer(GiveOffer))?;
+    // The offer request is a single round trip, so its timing doubles as the maker's RTT.
+    let request_time = Instant::now();
+    socket.send(&TakerToMakerMessage::ReqGiveOffer(GiveOffer), false)?;
 
-    let msg_bytes = read_message(&mut socket)?;
-    let msg: MakerToTakerMessage = serde_cbor::from_slice(&msg_bytes)?;
+    let msg = read_maker_reply(&mut socket, compression)?;
+    let rtt = request_time.elapsed();
     let offer = match msg {
         MakerToTakerMessage::RespOffer(offer) => offer,
         msg => {
@@ -466,21 +673,22 @@ fn download_maker_offer_attempt_once(
         }
     };
 
-    log::info!("Got offer from : {} ", maker_addr);
+    log::info!("Got offer from : {} | RTT : {:?}", maker_addr, rtt);
 
-    Ok(*offer)
+    Ok((*offer, rtt))
 }
 
 pub(crate) fn download_maker_offer(
     address: MakerAddress,
     config: TakerConfig,
-) -> Option<OfferAndAddress> {
+    trace: &ProtocolTrace,
+) -> Option<(OfferAndAddress, Duration)> {
     let mut ii = 0;
 
     loop {
         ii += 1;
-        match download_maker_offer_attempt_once(&address, &config) {
-            Ok(offer) => return Some(OfferAndAddress { offer, address }),
+        match download_maker_offer_attempt_once(&address, &config, trace) {
+            Ok((offer, rtt)) => return Some((OfferAndAddress { offer, address }, rtt)),
             Err(e) => {
                 if ii <= FIRST_CONNECT_ATTEMPTS {
                     log::warn!(
@@ -503,3 +711,101 @@ pub(crate) fn download_maker_offer(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+    use std::{cell::RefCell, net::TcpListener, thread};
+
+    /// A chain of blocks with a mempool, recording the queries made to it.
+    #[derive(Default)]
+    struct MockChain {
+        blocks: Vec<Vec<Txid>>,
+        mempool: Vec<Txid>,
+        queried_heights: RefCell<Vec<u64>>,
+        queried_txids: RefCell<Vec<Txid>>,
+    }
+
+    impl ContractTxLookup for MockChain {
+        fn tip_height(&self) -> Result<u64, WalletError> {
+            Ok(self.blocks.len() as u64 - 1)
+        }
+
+        fn block_txids(&self, height: u64) -> Result<Vec<Txid>, WalletError> {
+            self.queried_heights.borrow_mut().push(height);
+            Ok(self.blocks[height as usize].clone())
+        }
+
+        fn mempool_txids(&self) -> Result<Vec<Txid>, WalletError> {
+            Ok(self.mempool.clone())
+        }
+
+        fn is_tx_known(&self, txid: &Txid) -> bool {
+            self.queried_txids.borrow_mut().push(*txid);
+            self.blocks.iter().flatten().any(|t| t == txid) || self.mempool.contains(txid)
+        }
+    }
+
+    #[test]
+    fn test_find_broadcasted_txids_from_height() {
+        let txid = |n: u8| Txid::from_byte_array([n; 32]);
+        let contract_txids = [txid(1), txid(2), txid(3)];
+
+        let mut chain = MockChain {
+            blocks: vec![vec![txid(10)], vec![txid(11)]],
+            ..Default::default()
+        };
+
+        // The first check looks up every contract tx.
+        let (seen, height) = find_broadcasted_txids(&chain, &contract_txids, None).unwrap();
+        assert!(seen.is_empty());
+        assert_eq!(height, 1);
+        assert_eq!(*chain.queried_txids.borrow(), contract_txids);
+
+        // Later checks only scan the new blocks and the mempool.
+        chain.blocks.push(vec![txid(12)]);
+        chain.blocks.push(vec![txid(2)]);
+        chain.mempool.push(txid(3));
+        chain.queried_txids.borrow_mut().clear();
+
+        let (seen, height) = find_broadcasted_txids(&chain, &contract_txids, Some(height)).unwrap();
+        assert_eq!(seen, vec![txid(2), txid(3)]);
+        assert_eq!(height, 3);
+        assert_eq!(*chain.queried_heights.borrow(), vec![2, 3]);
+        assert!(chain.queried_txids.borrow().is_empty());
+
+        // Nothing new to scan.
+        chain.queried_heights.borrow_mut().clear();
+        chain.mempool.clear();
+        let (seen, height) = find_broadcasted_txids(&chain, &contract_txids, Some(height)).unwrap();
+        assert!(seen.is_empty());
+        assert_eq!(height, 3);
+        assert!(chain.queried_heights.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_offer_fetch_timeout() {
+        // A maker that accepts the connection, but never answers.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let _hanging_maker = thread::spawn(move || {
+            let (_socket, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_secs(10));
+        });
+
+        let config = TakerConfig {
+            connection_type: ConnectionType::CLEARNET,
+            offer_fetch_timeout: 1,
+            ..TakerConfig::default()
+        };
+        let addr = MakerAddress::new(&format!("127.0.0.1:{}", port)).unwrap();
+
+        let start = Instant::now();
+        let result = download_maker_offer_attempt_once(&addr, &config, &ProtocolTrace::default());
+        assert!(result.is_err());
+        // The offer-fetch timeout fires, instead of the signature exchange one.
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(config.signature_exchange_timeout > 5);
+    }
+}
@@ -3,11 +3,15 @@
 use bitcoin::{
     absolute::LockTime,
     hashes::Hash,
-    key::{rand::thread_rng, Keypair},
+    key::{
+        rand::{thread_rng, RngCore},
+        Keypair,
+    },
     secp256k1::{Message, Secp256k1, SecretKey},
     Address, Amount, PublicKey, ScriptBuf, Transaction, WitnessProgram, WitnessVersion,
 };
 use bitcoind::bitcoincore_rpc::json::ListUnspentResultEntry;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
 use log::LevelFilter;
 use log4rs::{
     append::{console::ConsoleAppender, file::FileAppender},
@@ -15,6 +19,7 @@ use log4rs::{
     Config,
 };
 use serde::{Deserialize, Serialize};
+use socket2::{SockRef, TcpKeepalive};
 use std::{
     env, fmt, fs,
     io::{BufReader, BufWriter, ErrorKind, Read},
@@ -59,12 +64,25 @@ pub(crate) const GLOBAL_PAUSE: Duration = Duration::from_secs(10);
 /// Global heartbeat interval used during waiting periods in critical situations.
 pub(crate) const HEART_BEAT_INTERVAL: Duration = Duration::from_secs(3);
 
+/// Default idle time in seconds, before TCP keepalive probes are sent on swap connections.
+pub(crate) const TCP_KEEPALIVE_INTERVAL: u64 = 60;
+
 /// Number of confirmation required funding transaction.
 pub const REQUIRED_CONFIRMS: u32 = 1;
 
 /// Default Transaction Fees in sats/vByte
 pub const DEFAULT_TX_FEE_RATE: f64 = 2.0;
 
+/// Default floor of all fee rates in sats/vByte, the default minimum relay fee rate of Bitcoin Core.
+pub const MIN_FEE_RATE: f64 = 1.0;
+
+/// The fee rate to use for an `estimatesmartfee` result in sats/vByte, never below `min_fee_rate`.
+///
+/// Without an estimate, as on regtest and signet, [DEFAULT_TX_FEE_RATE] is assumed.
+pub(crate) fn floor_fee_rate(estimate: Option<f64>, min_fee_rate: f64) -> f64 {
+    estimate.unwrap_or(DEFAULT_TX_FEE_RATE).max(min_fee_rate)
+}
+
 /// Specifies the type of connection: TOR or Clearnet.
 ///
 /// This enum is used to distinguish between different types of network connections
@@ -235,14 +253,78 @@ pub fn setup_logger(filter: LevelFilter, data_dir: Option<PathBuf>) {
     });
 }
 
+/// Leading byte of compressed message payloads. It never starts a CBOR item, so compressed
+/// payloads are told apart from plain ones on reading.
+const COMPRESSED_PAYLOAD_MARKER: u8 = 0xff;
+
+/// Largest message payload in bytes read from a peer, before and after decompression.
+pub(crate) const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Message payloads smaller than this many bytes are always sent uncompressed.
+pub(crate) const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Zlib compresses a serialized message payload, if it's large enough and compression makes it smaller.
+/// Otherwise returns the payload as is.
+pub(crate) fn compress_payload(msg_bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+    if msg_bytes.len() < COMPRESSION_THRESHOLD {
+        return Ok(msg_bytes);
+    }
+    let mut encoder = ZlibEncoder::new(vec![COMPRESSED_PAYLOAD_MARKER], Compression::default());
+    encoder.write_all(&msg_bytes)?;
+    let compressed = encoder.finish()?;
+    Ok(if compressed.len() < msg_bytes.len() {
+        compressed
+    } else {
+        msg_bytes
+    })
+}
+
+/// Decompresses a message payload made by [compress_payload]. Plain payloads are returned as is.
+///
+/// Errors if the payload decompresses to more than [MAX_MESSAGE_SIZE] bytes, so a small zlib bomb
+/// can't exhaust the memory.
+pub(crate) fn decompress_payload(payload: Vec<u8>) -> io::Result<Vec<u8>> {
+    match payload.split_first() {
+        Some((&COMPRESSED_PAYLOAD_MARKER, compressed)) => {
+            let mut msg_bytes = Vec::new();
+            ZlibDecoder::new(compressed)
+                .take(MAX_MESSAGE_SIZE as u64 + 1)
+                .read_to_end(&mut msg_bytes)?;
+            if msg_bytes.len() > MAX_MESSAGE_SIZE {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "decompressed message exceeds the maximum message size",
+                ));
+            }
+            Ok(msg_bytes)
+        }
+        _ => Ok(payload),
+    }
+}
+
 /// Send a length-appended Protocol or RPC Message through a stream.
 /// The first byte sent is the length of the actual message.
 pub fn send_message(
     socket_writer: &mut TcpStream,
     message: &impl serde::Serialize,
+) -> Result<(), NetError> {
+    send_message_negotiated(socket_writer, message, false)
+}
+
+/// Send a Protocol Message like [send_message], compressing large messages if the peer negotiated
+/// `compression` at the handshake.
+pub(crate) fn send_message_negotiated(
+    socket_writer: &mut TcpStream,
+    message: &impl serde::Serialize,
+    compression: bool,
 ) -> Result<(), NetError> {
     let mut writer = BufWriter::new(socket_writer);
     let msg_bytes = serde_cbor::ser::to_vec(message)?;
+    let msg_bytes = if compression {
+        compress_payload(msg_bytes)?
+    } else {
+        msg_bytes
+    };
     let msg_len = (msg_bytes.len() as u32).to_be_bytes();
     let mut to_send = Vec::with_capacity(msg_bytes.len() + msg_len.len());
     to_send.extend(msg_len);
@@ -255,11 +337,29 @@ pub fn send_message(
 /// Reads a response byte_array from a given stream.
 /// Response can be any length-appended data, where the first byte is the length of the actual message.
 pub fn read_message(reader: &mut TcpStream) -> Result<Vec<u8>, NetError> {
+    read_message_negotiated(reader, false)
+}
+
+/// Reads a Protocol Message like [read_message], decompressing it if the peer negotiated `compression`
+/// at the handshake.
+///
+/// Errors on messages larger than [MAX_MESSAGE_SIZE], before and after decompression.
+pub(crate) fn read_message_negotiated(
+    reader: &mut TcpStream,
+    compression: bool,
+) -> Result<Vec<u8>, NetError> {
     let mut reader = BufReader::new(reader);
     // length of incoming data
     let mut len_buff = [0u8; 4];
     reader.read_exact(&mut len_buff)?; // This can give UnexpectedEOF error if theres no data to read
     let length = u32::from_be_bytes(len_buff);
+    if length as usize > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "message exceeds the maximum message size",
+        )
+        .into());
+    }
 
     // the actual data
     let mut buffer = vec![0; length as usize];
@@ -275,7 +375,32 @@ pub fn read_message(reader: &mut TcpStream) -> Result<Vec<u8>, NetError> {
             Err(e) => return Err(e.into()),
         }
     }
-    Ok(buffer)
+    if compression {
+        Ok(decompress_payload(buffer)?)
+    } else {
+        Ok(buffer)
+    }
+}
+
+/// Sets the TCP options of a swap connection.
+///
+/// `nodelay` sends the small protocol messages right away, instead of batching them. A non-zero
+/// `keepalive_interval` sends keepalive probes after that many idle seconds, so NATs and Tor circuits
+/// don't silently drop the connection during long confirmation waits. `0` disables the keepalive.
+pub(crate) fn set_socket_options(
+    stream: &TcpStream,
+    nodelay: bool,
+    keepalive_interval: u64,
+) -> io::Result<()> {
+    stream.set_nodelay(nodelay)?;
+    let socket = SockRef::from(stream);
+    if keepalive_interval == 0 {
+        socket.set_keepalive(false)
+    } else {
+        socket.set_tcp_keepalive(
+            &TcpKeepalive::new().with_time(Duration::from_secs(keepalive_interval)),
+        )
+    }
 }
 
 /// Apply the maker's privatekey to swapcoins, and check it's the correct privkey for corresponding pubkey.
@@ -292,11 +417,12 @@ pub(crate) fn check_and_apply_maker_private_keys<S: SwapCoin>(
 /// Generate The Maker's Multisig and HashLock keys and respective nonce values.
 /// Nonce values are random integers and resulting Pubkeys are derived by tweaking
 ///
-/// the Maker's advertised Pubkey with these two nonces.
+/// the Maker's advertised Pubkey with these two nonces. The nonces are drawn from `rng`.
 #[allow(clippy::type_complexity)]
-pub(crate) fn generate_maker_keys(
+pub(crate) fn generate_maker_keys<R: RngCore + ?Sized>(
     tweakable_point: &PublicKey,
     count: u32,
+    rng: &mut R,
 ) -> Result<
     (
         Vec<PublicKey>,
@@ -307,9 +433,9 @@ pub(crate) fn generate_maker_keys(
     ProtocolError,
 > {
     // Closure to derive public keys and nonces
-    let derive_keys = |count: u32| {
+    let mut derive_keys = |count: u32| {
         (0..count)
-            .map(|_| derive_maker_pubkey_and_nonce(tweakable_point))
+            .map(|_| derive_maker_pubkey_and_nonce(tweakable_point, rng))
             .collect::<Result<Vec<_>, _>>()
     };
 
@@ -381,6 +507,36 @@ pub(crate) fn redeemscript_to_scriptpubkey(
     Ok(ScriptBuf::new_witness_program(&witness_program))
 }
 
+/// Moves a directory with all its content to a new location.
+///
+/// A plain rename is tried first. If that fails, ex: across filesystems, the content is copied over
+/// and the source removed. A failed copy is rolled back, leaving the source untouched.
+pub(crate) fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if let Err(e) = copy_dir(from, to) {
+        let _ = fs::remove_dir_all(to);
+        return Err(e);
+    }
+    fs::remove_dir_all(from)
+}
+
+/// Recursively copies a directory.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
 /// Parses a TOML file into a HashMap of key-value pairs.
 pub(crate) fn parse_toml<P: AsRef<Path>>(path: P) -> io::Result<HashMap<String, String>> {
     let content = fs::read_to_string(path)?;
@@ -403,6 +559,25 @@ pub(crate) fn parse_field<T: std::str::FromStr>(value: Option<&String>, default:
         .unwrap_or(default)
 }
 
+/// Parses a comma separated list from an Option<&String>, returning the default if the list is empty or None
+pub(crate) fn parse_list(value: Option<&String>, default: Vec<String>) -> Vec<String> {
+    let list = value
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if list.is_empty() {
+        default
+    } else {
+        list
+    }
+}
+
 fn polynomial_modulus(mut checksum: u64, value: u64) -> u64 {
     let upper_bits = checksum >> SHIFT_FOR_C0;
     checksum = ((checksum & MASK_LOW_35_BITS) << 5) ^ value;
@@ -766,7 +941,10 @@ mod tests {
         PubkeyHash,
     };
 
-    use crate::protocol::messages::{MakerHello, MakerToTakerMessage};
+    use crate::protocol::messages::{
+        FundingTxInfo, MakerHello, MakerToTakerMessage, NextHopInfo, ProofOfFunding,
+        TakerToMakerMessage,
+    };
 
     use super::*;
 
@@ -778,6 +956,7 @@ mod tests {
         let message = MakerToTakerMessage::MakerHello(MakerHello {
             protocol_version_min: 1,
             protocol_version_max: 100,
+            compression: false,
         });
 
         thread::spawn(move || {
@@ -799,6 +978,132 @@ mod tests {
         send_message(&mut stream, &message).unwrap();
     }
 
+    #[test]
+    fn test_compressed_proof_of_funding() {
+        let secp = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(&[1; 32]).unwrap();
+        let pubkey = PublicKey::new(secret_key.public_key(&secp));
+        let funding_tx = Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: (0..20)
+                .map(|i| bitcoin::TxOut {
+                    value: Amount::from_sat(100_000 + i),
+                    script_pubkey: ScriptBuf::new_p2wsh(&ScriptBuf::new().wscript_hash()),
+                })
+                .collect(),
+        };
+        let funding_tx_info = FundingTxInfo {
+            funding_tx,
+            funding_tx_merkleproof: "00".repeat(1000),
+            multisig_redeemscript: ScriptBuf::new(),
+            multisig_nonce: secret_key,
+            contract_redeemscript: ScriptBuf::new(),
+            hashlock_nonce: secret_key,
+        };
+        let message = TakerToMakerMessage::RespProofOfFunding(ProofOfFunding {
+            confirmed_funding_txes: vec![funding_tx_info; 3],
+            next_coinswap_info: vec![NextHopInfo {
+                next_multisig_pubkey: pubkey,
+                next_hashlock_pubkey: pubkey,
+            }],
+            refund_locktime: 20,
+            contract_feerate: 1000,
+            id: "swap".to_string(),
+            required_confirms: 1,
+        });
+        let msg_bytes = serde_cbor::ser::to_vec(&message).unwrap();
+
+        // Large enough to be compressed, and smaller once compressed.
+        let payload = compress_payload(msg_bytes.clone()).unwrap();
+        assert_eq!(payload[0], COMPRESSED_PAYLOAD_MARKER);
+        assert!(payload.len() < msg_bytes.len());
+        assert_eq!(decompress_payload(payload).unwrap(), msg_bytes);
+
+        // Small messages are left as is.
+        let hello = serde_cbor::ser::to_vec(&MakerToTakerMessage::MakerHello(MakerHello {
+            protocol_version_min: 1,
+            protocol_version_max: 1,
+            compression: true,
+        }))
+        .unwrap();
+        assert_eq!(compress_payload(hello.clone()).unwrap(), hello);
+
+        // Both the compressed and the plain path read back byte for byte.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut accepted, _) = listener.accept().unwrap();
+        for compression in [true, false] {
+            send_message_negotiated(&mut stream, &message, compression).unwrap();
+            assert_eq!(
+                read_message_negotiated(&mut accepted, compression).unwrap(),
+                msg_bytes
+            );
+        }
+
+        // Without negotiated compression, a compressed payload is not decompressed.
+        send_message_negotiated(&mut stream, &message, true).unwrap();
+        assert_ne!(read_message(&mut accepted).unwrap(), msg_bytes);
+    }
+
+    #[test]
+    fn test_decompression_bomb() {
+        // A few KB of zeros compressing down from over the maximum message size.
+        let mut encoder = ZlibEncoder::new(vec![COMPRESSED_PAYLOAD_MARKER], Compression::best());
+        encoder.write_all(&vec![0u8; MAX_MESSAGE_SIZE + 1]).unwrap();
+        let bomb = encoder.finish().unwrap();
+        assert!(bomb.len() < 64 * 1024);
+
+        let err = decompress_payload(bomb.clone()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // Read off the wire, the bomb is refused too.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut accepted, _) = listener.accept().unwrap();
+        stream
+            .write_all(&(bomb.len() as u32).to_be_bytes())
+            .unwrap();
+        stream.write_all(&bomb).unwrap();
+        assert!(matches!(
+            read_message_negotiated(&mut accepted, true),
+            Err(NetError::IO(e)) if e.kind() == ErrorKind::InvalidData
+        ));
+
+        // A payload exactly at the limit decompresses.
+        let mut encoder = ZlibEncoder::new(vec![COMPRESSED_PAYLOAD_MARKER], Compression::best());
+        encoder.write_all(&vec![0u8; MAX_MESSAGE_SIZE]).unwrap();
+        let payload = encoder.finish().unwrap();
+        assert_eq!(decompress_payload(payload).unwrap().len(), MAX_MESSAGE_SIZE);
+
+        // An announced length above the limit is refused before reading the payload.
+        stream
+            .write_all(&(MAX_MESSAGE_SIZE as u32 + 1).to_be_bytes())
+            .unwrap();
+        assert!(matches!(
+            read_message(&mut accepted),
+            Err(NetError::IO(e)) if e.kind() == ErrorKind::InvalidData
+        ));
+    }
+
+    #[test]
+    fn test_set_socket_options() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (accepted, _) = listener.accept().unwrap();
+
+        for socket in [&stream, &accepted] {
+            set_socket_options(socket, true, 30).unwrap();
+            assert!(socket.nodelay().unwrap());
+            assert!(SockRef::from(socket).keepalive().unwrap());
+
+            set_socket_options(socket, false, 0).unwrap();
+            assert!(!socket.nodelay().unwrap());
+            assert!(!SockRef::from(socket).keepalive().unwrap());
+        }
+    }
+
     #[test]
     fn test_redeemscript_to_scriptpubkey_custom() {
         // Create a custom puzzle script
@@ -908,7 +1213,7 @@ mod tests {
         )
         .unwrap();
         let (multisig_pubkeys, multisig_nonces, hashlock_pubkeys, hashlock_nonces) =
-            generate_maker_keys(&tweak_point, 1).unwrap();
+            generate_maker_keys(&tweak_point, 1, &mut thread_rng()).unwrap();
         // test returned multisg part
         let returned_nonce = multisig_nonces[0];
         let returned_pubkey = multisig_pubkeys[0];
@@ -932,4 +1237,40 @@ mod tests {
             .unwrap();
         assert_eq!(returned_pubkey.to_string(), tweaked_pubkey.to_string());
     }
+
+    #[test]
+    fn test_move_dir() {
+        use bitcoind::tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let from = temp_dir.path().join("from");
+        fs::create_dir_all(from.join("wallets")).unwrap();
+        fs::write(from.join("config.toml"), "a = 1").unwrap();
+        fs::write(from.join("wallets").join("wallet"), [1u8, 2, 3]).unwrap();
+
+        let to = temp_dir.path().join("to");
+        move_dir(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(to.join("config.toml")).unwrap(), "a = 1");
+        assert_eq!(
+            fs::read(to.join("wallets").join("wallet")).unwrap(),
+            [1, 2, 3]
+        );
+
+        // Copying a missing source fails, without leaving anything behind.
+        let missing = temp_dir.path().join("missing");
+        let target = temp_dir.path().join("target");
+        assert!(move_dir(&missing, &target).is_err());
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_floor_fee_rate() {
+        // No estimate, as on regtest: the floor wins over the default fee rate.
+        assert_eq!(floor_fee_rate(None, 5.0), 5.0);
+        assert_eq!(floor_fee_rate(None, MIN_FEE_RATE), DEFAULT_TX_FEE_RATE);
+        // A near-zero estimate is raised to the floor, higher estimates are kept.
+        assert_eq!(floor_fee_rate(Some(0.01), MIN_FEE_RATE), MIN_FEE_RATE);
+        assert_eq!(floor_fee_rate(Some(12.5), MIN_FEE_RATE), 12.5);
+    }
 }
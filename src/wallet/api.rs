@@ -5,32 +5,35 @@
 
 use std::{convert::TryFrom, fmt::Display, path::PathBuf, str::FromStr};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use bip39::Mnemonic;
 use bitcoin::{
     bip32::{ChildNumber, DerivationPath, Xpriv, Xpub},
-    hashes::hash160::Hash as Hash160,
     secp256k1,
     secp256k1::{Secp256k1, SecretKey},
     sighash::{EcdsaSighashType, SighashCache},
-    Address, Amount, OutPoint, PublicKey, Script, ScriptBuf, Transaction, Txid,
+    Address, Amount, OutPoint, PrivateKey, PublicKey, Script, ScriptBuf, Sequence, Transaction,
+    Txid, Weight,
 };
 use bitcoind::bitcoincore_rpc::{bitcoincore_rpc_json::ListUnspentResultEntry, Client, RpcApi};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::path::Path;
 
 use crate::{
-    protocol::contract,
+    protocol::{contract, error::ProtocolError, Hashvalue},
     utill::{
-        compute_checksum, generate_keypair, get_hd_path_from_descriptor,
-        redeemscript_to_scriptpubkey,
+        compute_checksum, floor_fee_rate, generate_keypair, get_hd_path_from_descriptor,
+        redeemscript_to_scriptpubkey, MIN_FEE_RATE,
     },
 };
 
 use super::{
     error::WalletError,
-    rpc::RPCConfig,
+    funding::PreparedCoinswap,
+    rpc::{check_txindex, RPCConfig},
+    spend::{AntiFeeSniping, TxOrdering},
     storage::WalletStore,
     swapcoin::{IncomingSwapCoin, OutgoingSwapCoin, SwapCoin, WalletSwapCoin},
 };
@@ -41,12 +44,23 @@ use super::{
 
 const HARDENDED_DERIVATION: &str = "m/84'/1'/0'";
 
+/// Number of confirmations after which a coinbase output can be spent.
+pub(crate) const COINBASE_MATURITY: u64 = 100;
+
 /// Represents a Bitcoin wallet with associated functionality and data.
 #[derive(Debug)]
 pub struct Wallet {
     pub(crate) rpc: Client,
     wallet_file_path: PathBuf,
     pub(crate) store: WalletStore,
+    pub(crate) anti_fee_sniping: AntiFeeSniping,
+    pub(crate) avoid_swap_clustering: bool,
+    pub(crate) tx_ordering: TxOrdering,
+    pub(crate) allow_key_export: bool,
+    pub(crate) reserve_amount: Amount,
+    pub(crate) min_fee_rate: f64,
+    // Inside a [`Wallet::batch`], whether a sync was requested and deferred to the end of the batch.
+    pub(crate) deferred_sync: Option<bool>,
 }
 
 /// Speicfy the keychain derivation path from [`HARDENDED_DERIVATION`]
@@ -72,6 +86,38 @@ impl KeychainKind {
     }
 }
 
+/// Keychain layout of the wallet's change addresses, for compatibility with other wallets restored
+/// from the same seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AddressStrategy {
+    /// Receive addresses on the external chain (`/0/*`), change on the internal chain (`/1/*`).
+    #[default]
+    SplitChain,
+    /// Receive and change addresses both on the external chain (`/0/*`), as with single-chain wallets.
+    SingleChain,
+}
+
+impl FromStr for AddressStrategy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "splitchain" => Ok(AddressStrategy::SplitChain),
+            "singlechain" => Ok(AddressStrategy::SingleChain),
+            _ => Err("Invalid address strategy"),
+        }
+    }
+}
+
+impl Display for AddressStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressStrategy::SplitChain => write!(f, "splitchain"),
+            AddressStrategy::SingleChain => write!(f, "singlechain"),
+        }
+    }
+}
+
 const WATCH_ONLY_SWAPCOIN_LABEL: &str = "watchonly_swapcoin_label";
 
 /// Enum representing different types of addresses to display.
@@ -119,6 +165,25 @@ impl FromStr for DisplayAddressType {
     }
 }
 
+/// The target of a user label.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelTarget {
+    /// An address. The label applies to every UTXO paying to it, including future ones.
+    Address(Address),
+    /// A single UTXO.
+    Outpoint(OutPoint),
+}
+
+/// An output the wallet can spend, but doesn't track in any UTXO category, e.g. funds sent by mistake to
+/// the address of an old fidelity bond.
+#[derive(Debug, Clone)]
+pub struct RecoverableOutput {
+    /// The output, as listed by Bitcoin Core.
+    pub utxo: ListUnspentResultEntry,
+    /// How the wallet spends it. Pass both to [Wallet::spend_coins] to reclaim the funds.
+    pub spend_info: UTXOSpendInfo,
+}
+
 /// Enum representing additional data needed to spend a UTXO, in addition to `ListUnspentResultEntry`.
 // data needed to find information  in addition to ListUnspentResultEntry
 // about a UTXO required to spend it
@@ -161,6 +226,15 @@ impl UTXOSpendInfo {
             Self::FidelityBondCoin { .. } => FIDELITY_BOND_WITNESS_SIZE,
         }
     }
+
+    /// Estimates the weight of an input spending the UTXO: the outpoint, empty script_sig and sequence,
+    /// plus the witness of its spend path.
+    pub fn estimate_input_weight(&self) -> Weight {
+        // 36 bytes outpoint, 1 byte empty script_sig, 4 bytes sequence.
+        const TXIN_BASE_SIZE: u64 = 41;
+        Weight::from_non_witness_data_size(TXIN_BASE_SIZE)
+            + Weight::from_witness_data_size(self.estimate_witness_size() as u64)
+    }
 }
 
 impl Display for UTXOSpendInfo {
@@ -187,8 +261,15 @@ pub struct Balances {
     pub contract: Amount,
     /// All coins locked in fidelity bonds.
     pub fidelity: Amount,
-    /// Spendable amount in wallet (regular + swap balance).
+    /// Spendable amount in wallet (regular + swap balance), minus the reserve.
     pub spendable: Amount,
+    /// Coinbase outputs which can't be spent yet. They are not counted in any other balance.
+    #[serde(default)]
+    pub immature: Amount,
+    /// Coins committed to broadcasted funding txs of ongoing swaps, not yet settled.
+    /// They are neither spendable, nor ours anymore once the swap settles.
+    #[serde(default)]
+    pub pending_swap_out: Amount,
 }
 
 impl Wallet {
@@ -196,17 +277,23 @@ impl Wallet {
     ///
     /// The path should include the full path for a wallet file.
     /// If the wallet file doesn't exist it will create a new wallet file.
-    pub fn init(path: &Path, rpc_config: &RPCConfig) -> Result<Self, WalletError> {
+    /// The `address_strategy` is stored in the wallet file, and kept for the wallet's lifetime.
+    pub fn init(
+        path: &Path,
+        rpc_config: &RPCConfig,
+        address_strategy: AddressStrategy,
+    ) -> Result<Self, WalletError> {
         let rpc = Client::try_from(rpc_config)?;
         let network = rpc.get_blockchain_info()?.chain;
+        check_txindex(&rpc)?;
 
         // Generate Master key
-        let master_key = {
+        let (master_key, seed) = {
             let mnemonic = Mnemonic::generate(12)?;
             let words = mnemonic.words().collect::<Vec<_>>();
             log::info!("Backup the Wallet Mnemonics. \n {:?}", words);
             let seed = mnemonic.to_entropy();
-            Xpriv::new_master(network, &seed)?
+            (Xpriv::new_master(network, &seed)?, seed)
         };
 
         // Initialise wallet
@@ -218,12 +305,27 @@ impl Wallet {
             .to_string();
 
         let wallet_birthday = rpc.get_block_count()?;
-        let store = WalletStore::init(file_name, path, network, master_key, Some(wallet_birthday))?;
+        let store = WalletStore::init(
+            file_name,
+            path,
+            network,
+            master_key,
+            Some(seed),
+            Some(wallet_birthday),
+            address_strategy,
+        )?;
 
         Ok(Self {
             rpc,
             wallet_file_path: path.to_path_buf(),
             store,
+            anti_fee_sniping: AntiFeeSniping::default(),
+            avoid_swap_clustering: false,
+            tx_ordering: TxOrdering::default(),
+            allow_key_export: false,
+            reserve_amount: Amount::ZERO,
+            min_fee_rate: MIN_FEE_RATE,
+            deferred_sync: None,
         })
     }
 
@@ -249,6 +351,7 @@ impl Wallet {
             );
             return Err(WalletError::General("Wrong Bitcoin Network".to_string()));
         }
+        check_txindex(&rpc)?;
         log::debug!(
             "Loaded wallet file {} | External Index = {} | Incoming Swapcoins = {} | Outgoing Swapcoins = {}",
             store.file_name,
@@ -257,11 +360,27 @@ impl Wallet {
             store.outgoing_swapcoins.len()
         );
 
-        Ok(Self {
+        let mut wallet = Self {
             rpc,
             wallet_file_path: path.to_path_buf(),
             store,
-        })
+            anti_fee_sniping: AntiFeeSniping::default(),
+            avoid_swap_clustering: false,
+            tx_ordering: TxOrdering::default(),
+            allow_key_export: false,
+            reserve_amount: Amount::ZERO,
+            min_fee_rate: MIN_FEE_RATE,
+            deferred_sync: None,
+        };
+
+        // A freshly created Core wallet knows nothing yet, so the next sync rescans from the birthday.
+        if wallet.load_core_wallet()? {
+            wallet.store.last_synced_height = None;
+        }
+        // Swapcoin scripts imported during a swap may be missing, ex: in a restored Core wallet.
+        wallet.reimport_swapcoin_scripts()?;
+
+        Ok(wallet)
     }
 
     /// Update external index and saves to disk.
@@ -273,6 +392,16 @@ impl Wallet {
         self.save_to_disk()
     }
 
+    /// Returns the path of the wallet file.
+    pub(crate) fn wallet_file_path(&self) -> &Path {
+        &self.wallet_file_path
+    }
+
+    /// Points the wallet to a new file path, ex: after its data directory was moved.
+    pub(crate) fn set_wallet_file_path(&mut self, path: PathBuf) {
+        self.wallet_file_path = path;
+    }
+
     /// Update the existing file. Error if path does not exist.
     pub(crate) fn save_to_disk(&self) -> Result<(), WalletError> {
         self.store.write_to_disk(&self.wallet_file_path)
@@ -312,6 +441,14 @@ impl Wallet {
 
     /// Adds an incoming swap coin to the wallet.
     pub(crate) fn add_incoming_swapcoin(&mut self, coin: &IncomingSwapCoin) {
+        if self.avoid_swap_clustering {
+            if let (Ok(hashvalue), Ok(script_pubkey)) = (
+                coin.get_hashvalue(),
+                redeemscript_to_scriptpubkey(&coin.get_multisig_redeemscript()),
+            ) {
+                self.record_swap_scripts(hashvalue, vec![script_pubkey]);
+            }
+        }
         self.store
             .incoming_swapcoins
             .insert(coin.get_multisig_redeemscript(), coin.clone());
@@ -340,32 +477,58 @@ impl Wallet {
         Ok(self.store.outgoing_swapcoins.remove(multisig_redeemscript))
     }
 
+    /// Returns the BIP39 mnemonic of the wallet seed, for backups.
+    ///
+    /// Errors for older wallet files, which don't store the seed. The mnemonic must never be logged.
+    pub(crate) fn get_mnemonic(&self) -> Result<Mnemonic, WalletError> {
+        let entropy = self.store.seed_entropy.as_ref().ok_or_else(|| {
+            WalletError::General("The wallet file doesn't store the seed mnemonic".to_string())
+        })?;
+        let mnemonic = Mnemonic::from_entropy(entropy)?;
+        if Xpriv::new_master(self.store.network, &mnemonic.to_entropy())? != self.store.master_key {
+            return Err(WalletError::General(
+                "Stored seed doesn't match the wallet master key".to_string(),
+            ));
+        }
+        Ok(mnemonic)
+    }
+
     /// Gets the total count of swap coins in the wallet.
     pub fn get_swapcoins_count(&self) -> usize {
         self.store.incoming_swapcoins.len() + self.store.outgoing_swapcoins.len()
     }
 
     /// Calculates the total balances of different categories in the wallet.
-    /// Includes regular, swap, contract, fidelitly and spendable (regular + swap) utxos.
+    /// Includes regular, swap, contract, fidelitly and spendable (regular + swap, minus the reserve) utxos.
     /// Optionally takes in a list of UTXOs to reduce rpc call. If None is provided, the full list is fetched from core rpc.
+    ///
+    /// The balances are computed in a single pass over the UTXO cache, without any script derivation.
     pub fn get_balances(&self) -> Result<Balances, WalletError> {
-        let regular = self
-            .list_descriptor_utxo_spend_info()?
-            .iter()
-            .fold(Amount::ZERO, |sum, (utxo, _)| sum + utxo.amount);
-        let contract = self
-            .list_live_timelock_contract_spend_info()?
-            .iter()
-            .fold(Amount::ZERO, |sum, (utxo, _)| sum + utxo.amount);
-        let swap = self
-            .list_incoming_swap_coin_utxo_spend_info()?
-            .iter()
-            .fold(Amount::ZERO, |sum, (utxo, _)| sum + utxo.amount);
-        let fidelity = self
-            .list_fidelity_spend_info()?
-            .iter()
-            .fold(Amount::ZERO, |sum, (utxo, _)| sum + utxo.amount);
-        let spendable = regular + swap;
+        let (mut regular, mut contract, mut swap, mut fidelity, mut immature) = (
+            Amount::ZERO,
+            Amount::ZERO,
+            Amount::ZERO,
+            Amount::ZERO,
+            Amount::ZERO,
+        );
+        for (outpoint, (utxo, spend_info)) in &self.store.utxo_cache {
+            if self.is_immature_coinbase(outpoint) {
+                immature += utxo.amount;
+                continue;
+            }
+            match spend_info {
+                UTXOSpendInfo::SeedCoin { .. } => regular += utxo.amount,
+                UTXOSpendInfo::TimelockContract { .. } => contract += utxo.amount,
+                UTXOSpendInfo::IncomingSwapCoin { .. } => swap += utxo.amount,
+                UTXOSpendInfo::FidelityBondCoin { .. } => fidelity += utxo.amount,
+                UTXOSpendInfo::OutgoingSwapCoin { .. } | UTXOSpendInfo::HashlockContract { .. } => {
+                }
+            }
+        }
+        let spendable = (regular + swap)
+            .checked_sub(self.reserve_amount)
+            .unwrap_or(Amount::ZERO);
+        let pending_swap_out = self.get_pending_swap_out()?;
 
         Ok(Balances {
             regular,
@@ -373,9 +536,32 @@ impl Wallet {
             contract,
             fidelity,
             spendable,
+            immature,
+            pending_swap_out,
         })
     }
 
+    /// Total value of the funding outputs of the outgoing swapcoins whose swap hasn't settled yet.
+    ///
+    /// Only funding txs seen by the node, in the mempool or on chain, are counted. Once the hash preimage
+    /// is known the swap is settled, and the coins belong to the other party.
+    fn get_pending_swap_out(&self) -> Result<Amount, WalletError> {
+        let mut pending = Amount::ZERO;
+        for swapcoin in self.store.outgoing_swapcoins.values() {
+            if swapcoin.hash_preimage.is_some() {
+                continue;
+            }
+            let funding_outpoint = swapcoin.contract_tx.input[0].previous_output;
+            if let Some(txout) =
+                self.rpc
+                    .get_tx_out(&funding_outpoint.txid, funding_outpoint.vout, Some(true))?
+            {
+                pending += txout.value;
+            }
+        }
+        Ok(pending)
+    }
+
     /// Checks if the previous output (prevout) matches the cached contract in the wallet.
     ///
     /// This function is used in two scenarios:
@@ -560,6 +746,138 @@ impl Wallet {
             })
     }
 
+    /// Finds the HD keychain and index from which a P2WPKH scriptpubkey was derived.
+    /// ### Note
+    /// This searches the whole imported address range and should be used with care.
+    pub(crate) fn find_seed_keychain_index(
+        &self,
+        script_pubkey: &Script,
+    ) -> Result<Option<(KeychainKind, u32)>, WalletError> {
+        let secp = Secp256k1::new();
+        let wallet_xpub = Xpub::from_priv(
+            &secp,
+            &self
+                .store
+                .master_key
+                .derive_priv(&secp, &DerivationPath::from_str(HARDENDED_DERIVATION)?)?,
+        );
+
+        for keychain in [KeychainKind::External, KeychainKind::Internal] {
+            let keychain_xpub = wallet_xpub.derive_pub(
+                &secp,
+                &[ChildNumber::from_normal_idx(keychain.index_num())?],
+            )?;
+            for index in 0..self.get_addrss_import_count() {
+                let pubkey = keychain_xpub
+                    .derive_pub(&secp, &[ChildNumber::from_normal_idx(index)?])?
+                    .to_pub();
+                if ScriptBuf::new_p2wpkh(&pubkey.wpubkey_hash()).as_script() == script_pubkey {
+                    return Ok(Some((keychain, index)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the [UTXOSpendInfo] of a wallet output, even if it was already spent in the mempool.
+    ///
+    /// Cached UTXOs are looked up first, by outpoint and then by script. Otherwise the output is
+    /// matched against the seed keychains and the completed incoming swapcoins.
+    pub(crate) fn find_spend_info_for_txout(
+        &self,
+        outpoint: &OutPoint,
+        txout: &bitcoin::TxOut,
+    ) -> Result<Option<UTXOSpendInfo>, WalletError> {
+        if let Some((_, spend_info)) = self.store.utxo_cache.get(outpoint) {
+            return Ok(Some(spend_info.clone()));
+        }
+
+        if let Some(path) = self.store.indexed_seed_path(&txout.script_pubkey) {
+            return Ok(Some(UTXOSpendInfo::SeedCoin {
+                path,
+                input_value: txout.value,
+            }));
+        }
+
+        if let Some((keychain, index)) = self.find_seed_keychain_index(&txout.script_pubkey)? {
+            return Ok(Some(UTXOSpendInfo::SeedCoin {
+                path: format!("m/{}/{}", keychain.index_num(), index),
+                input_value: txout.value,
+            }));
+        }
+
+        for (multisig_redeemscript, incoming_swapcoin) in &self.store.incoming_swapcoins {
+            if incoming_swapcoin.other_privkey.is_some()
+                && redeemscript_to_scriptpubkey(multisig_redeemscript)? == txout.script_pubkey
+            {
+                return Ok(Some(UTXOSpendInfo::IncomingSwapCoin {
+                    multisig_redeemscript: multisig_redeemscript.clone(),
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Finds the outputs at wallet scripts that are left out of every UTXO category, with the path to spend them.
+    ///
+    /// Funds sent by mistake to a script the wallet controls, like another payment to a fidelity bond address,
+    /// are seen by Bitcoin Core but never categorized, so they are missing from the balances and can't be
+    /// spent by coin selection. Outputs the wallet has no spend path for are not returned.
+    /// Outputs of a fidelity bond script are only spendable once the bond's timelock has expired.
+    ///
+    /// The wallet should be synced first.
+    pub fn scan_for_recoverable_misc(&self) -> Result<Vec<RecoverableOutput>, WalletError> {
+        let mut recoverable = Vec::new();
+        for utxo in self.get_all_utxo()? {
+            let outpoint = OutPoint::new(utxo.txid, utxo.vout);
+            if self.store.utxo_cache.contains_key(&outpoint) {
+                continue;
+            }
+            if let Some(spend_info) = self.find_misc_spend_info(&outpoint, &utxo)? {
+                log::info!(
+                    "Found recoverable output {} of {} as {}",
+                    outpoint,
+                    utxo.amount,
+                    spend_info
+                );
+                recoverable.push(RecoverableOutput { utxo, spend_info });
+            }
+        }
+        Ok(recoverable)
+    }
+
+    /// Derives the spend path of an uncategorized output, matching its script against the fidelity bonds,
+    /// the seed keychains and the stored swapcoins.
+    fn find_misc_spend_info(
+        &self,
+        outpoint: &OutPoint,
+        utxo: &ListUnspentResultEntry,
+    ) -> Result<Option<UTXOSpendInfo>, WalletError> {
+        // Unlike the bond itself, the output is matched by script only.
+        if let Some((index, _)) = self
+            .store
+            .fidelity_bond
+            .iter()
+            .find(|(_, (bond, _, _))| bond.script_pub_key() == utxo.script_pub_key)
+        {
+            return Ok(Some(UTXOSpendInfo::FidelityBondCoin {
+                index: *index,
+                input_value: utxo.amount,
+            }));
+        }
+
+        let txout = bitcoin::TxOut {
+            value: utxo.amount,
+            script_pubkey: utxo.script_pub_key.clone(),
+        };
+        if let Some(spend_info) = self.find_spend_info_for_txout(outpoint, &txout)? {
+            return Ok(Some(spend_info));
+        }
+
+        self.check_and_derive_live_contract_spend_info(utxo)
+    }
+
     /// Checks if a UTXO belongs to live contracts, and then returns corresponding UTXOSpendInfo
     /// ### Note
     /// This is a costly search and should be used with care.
@@ -658,14 +976,71 @@ impl Wallet {
     }
 
     /// Returns a list of all UTXOs tracked by the wallet. Including fidelity, live_contracts and swap coins.
+    ///
+    /// Immature coinbase outputs are included, on nodes that can report them (v25 and later).
     pub fn get_all_utxo(&self) -> Result<Vec<ListUnspentResultEntry>, WalletError> {
         self.rpc.unlock_unspent_all()?;
-        let all_utxos = self
-            .rpc
-            .list_unspent(Some(0), Some(9999999), None, None, None)?;
+        if self.rpc.version()? < 250_000 {
+            return Ok(self
+                .rpc
+                .list_unspent(Some(0), Some(9999999), None, None, None)?);
+        }
+        let all_utxos = self.rpc.call(
+            "listunspent",
+            &[
+                json!(0),
+                json!(9999999),
+                json!([]),
+                json!(true),
+                json!({ "include_immature_coinbase": true }),
+            ],
+        )?;
         Ok(all_utxos)
     }
 
+    /// Records the block height of the new coinbase UTXOs which are not mature yet at `tip_height`.
+    ///
+    /// Older UTXOs are already mature, and are not looked up.
+    pub(crate) fn track_coinbase_maturity(
+        &mut self,
+        utxos: &[ListUnspentResultEntry],
+        tip_height: u64,
+    ) -> Result<(), WalletError> {
+        for utxo in utxos {
+            let outpoint = OutPoint::new(utxo.txid, utxo.vout);
+            let confirmations = utxo.confirmations as u64;
+            if confirmations == 0
+                || confirmations >= COINBASE_MATURITY
+                || self.store.utxo_cache.contains_key(&outpoint)
+            {
+                continue;
+            }
+            if self
+                .rpc
+                .get_raw_transaction(&utxo.txid, None)?
+                .is_coinbase()
+            {
+                let height = (tip_height + 1).saturating_sub(confirmations);
+                log::debug!("Tracking coinbase UTXO {} mined at {}", outpoint, height);
+                self.store.coinbase_heights.insert(outpoint, height);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks if a UTXO is a coinbase output which can't be spent yet, as of the last sync.
+    pub fn is_immature_coinbase(&self, outpoint: &OutPoint) -> bool {
+        match (
+            self.store.coinbase_heights.get(outpoint),
+            self.store.last_synced_height,
+        ) {
+            (Some(height), Some(tip_height)) => {
+                (tip_height + 1).saturating_sub(*height) < COINBASE_MATURITY
+            }
+            _ => false,
+        }
+    }
+
     /// Returns a list all utxos with their spend info tracked by the wallet.
     /// Optionally takes in an Utxo list to reduce RPC calls. If None is given, the
     /// full list of utxo is fetched from core rpc.
@@ -779,6 +1154,159 @@ impl Wallet {
         Ok(filtered_utxos)
     }
 
+    /// Attach a swap label to the incoming swapcoin identified by its multisig redeemscript.
+    pub(crate) fn add_swap_label(&mut self, multisig_redeemscript: &ScriptBuf, label: &str) {
+        self.store
+            .swap_labels
+            .insert(multisig_redeemscript.clone(), label.to_string());
+    }
+
+    /// Returns the label of a UTXO listed by [Wallet::list_all_utxo_spend_info], if any.
+    ///
+    /// The label of the UTXO itself takes precedence, then the label of its address, then the label of
+    /// the swap it was received in.
+    pub fn get_utxo_label(
+        &self,
+        utxo: &ListUnspentResultEntry,
+        spend_info: &UTXOSpendInfo,
+    ) -> Option<&String> {
+        let swap_label = || match spend_info {
+            UTXOSpendInfo::IncomingSwapCoin {
+                multisig_redeemscript,
+            } => self.store.swap_labels.get(multisig_redeemscript),
+            _ => None,
+        };
+        self.store
+            .utxo_labels
+            .get(&OutPoint::new(utxo.txid, utxo.vout))
+            .or_else(|| self.store.address_labels.get(&utxo.script_pub_key))
+            .or_else(swap_label)
+    }
+
+    /// Sets a user label on an address or a UTXO, and saves it to the wallet file.
+    /// An empty label removes the label.
+    pub fn set_label(&mut self, target: LabelTarget, label: String) -> Result<(), WalletError> {
+        match (target, label.is_empty()) {
+            (LabelTarget::Address(address), false) => {
+                self.store
+                    .address_labels
+                    .insert(address.script_pubkey(), label);
+            }
+            (LabelTarget::Address(address), true) => {
+                self.store.address_labels.remove(&address.script_pubkey());
+            }
+            (LabelTarget::Outpoint(outpoint), false) => {
+                self.store.utxo_labels.insert(outpoint, label);
+            }
+            (LabelTarget::Outpoint(outpoint), true) => {
+                self.store.utxo_labels.remove(&outpoint);
+            }
+        }
+        self.save_to_disk()
+    }
+
+    /// Returns the user label of an address or a UTXO, if any.
+    pub fn get_label(&self, target: &LabelTarget) -> Option<&String> {
+        match target {
+            LabelTarget::Address(address) => {
+                self.store.address_labels.get(&address.script_pubkey())
+            }
+            LabelTarget::Outpoint(outpoint) => self.store.utxo_labels.get(outpoint),
+        }
+    }
+
+    /// Exports the wallet labels in the [BIP329](https://github.com/bitcoin/bips/blob/master/bip-0329.mediawiki)
+    /// JSON Lines format, for import into other wallets.
+    ///
+    /// Every UTXO gets an `output` record labeled with its category (`regular`, `incoming-swap`,
+    /// `fidelity-bond`, ...), followed by its own, address or swap label if any. Only regular and
+    /// incoming swap coins are marked `spendable`, unless they are protected or immature coinbase outputs. The txs which created swap coins and fidelity bonds
+    /// get a `tx` record with their category, and the labeled addresses an `addr` record.
+    pub fn export_bip329_labels(&self) -> String {
+        let mut records = Vec::new();
+
+        let mut addresses = self
+            .store
+            .address_labels
+            .iter()
+            .filter_map(|(script_pubkey, label)| {
+                Address::from_script(script_pubkey, self.store.network)
+                    .ok()
+                    .map(|address| (address.to_string(), label))
+            })
+            .collect::<Vec<_>>();
+        addresses.sort();
+        for (address, label) in addresses {
+            records.push(json!({ "type": "addr", "ref": address, "label": label }));
+        }
+
+        let mut tx_labels = BTreeMap::new();
+        for (bond, _, _) in self.store.fidelity_bond.values() {
+            tx_labels.insert(bond.outpoint.txid, "fidelity-bond".to_string());
+        }
+
+        let mut utxos = self.store.utxo_cache.iter().collect::<Vec<_>>();
+        utxos.sort_by_key(|(outpoint, _)| **outpoint);
+        for (outpoint, (utxo, spend_info)) in utxos {
+            let category = spend_info.to_string();
+            if !matches!(spend_info, UTXOSpendInfo::SeedCoin { .. }) {
+                tx_labels.insert(outpoint.txid, category.clone());
+            }
+            let label = match self.get_utxo_label(utxo, spend_info) {
+                Some(label) => format!("{}: {}", category, label),
+                None => category,
+            };
+            let spendable = matches!(
+                spend_info,
+                UTXOSpendInfo::SeedCoin { .. } | UTXOSpendInfo::IncomingSwapCoin { .. }
+            ) && !self.store.protected_utxos.contains(outpoint)
+                && !self.is_immature_coinbase(outpoint);
+            records.push(json!({
+                "type": "output",
+                "ref": outpoint.to_string(),
+                "label": label,
+                "spendable": spendable,
+            }));
+        }
+
+        records.extend(
+            tx_labels.into_iter().map(
+                |(txid, label)| json!({ "type": "tx", "ref": txid.to_string(), "label": label }),
+            ),
+        );
+
+        records
+            .iter()
+            .map(|record| record.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Lists the UTXOs the wallet can spend on its own, along with their [UTXOSpendInfo] and estimated
+    /// input weight.
+    ///
+    /// The weight depends on the spend path: a single signature for seed coins, two for swap coins, and
+    /// the contract script for contracts. Outgoing swap coins, which need the other party, and fidelity
+    /// bonds are left out.
+    pub fn list_spendable_with_weight(
+        &self,
+    ) -> Result<Vec<(ListUnspentResultEntry, UTXOSpendInfo, Weight)>, WalletError> {
+        let all_valid_utxo = self.list_all_utxo_spend_info()?;
+        let spendable_utxos = all_valid_utxo
+            .into_iter()
+            .filter(|(utxo, spend_info)| {
+                !matches!(spend_info, UTXOSpendInfo::OutgoingSwapCoin { .. })
+                    && !self.is_fidelity_bond_coin(utxo, spend_info)
+                    && !self.is_immature_coinbase(&OutPoint::new(utxo.txid, utxo.vout))
+            })
+            .map(|(utxo, spend_info)| {
+                let weight = spend_info.estimate_input_weight();
+                (utxo, spend_info, weight)
+            })
+            .collect();
+        Ok(spendable_utxos)
+    }
+
     /// A simplification of `find_incomplete_coinswaps` function
     pub(crate) fn find_unfinished_swapcoins(
         &self,
@@ -853,35 +1381,86 @@ impl Wallet {
 
     /// Gets the next external address from the HD keychain.
     pub fn get_next_external_address(&mut self) -> Result<Address, WalletError> {
+        let index = match self.store.address_strategy {
+            AddressStrategy::SplitChain => self.store.external_index,
+            // Skip the change addresses already used on the same chain.
+            AddressStrategy::SingleChain => std::cmp::max(
+                self.store.external_index,
+                self.find_hd_next_index(KeychainKind::External)?,
+            ),
+        };
         let descriptors = self.get_wallet_descriptors()?;
         let receive_branch_descriptor = descriptors
             .get(&KeychainKind::External)
             .expect("external keychain expected");
-        let receive_address = self.rpc.derive_addresses(
-            receive_branch_descriptor,
-            Some([self.store.external_index, self.store.external_index]),
-        )?[0]
+        let receive_address = self
+            .rpc
+            .derive_addresses(receive_branch_descriptor, Some([index, index]))?[0]
             .clone();
-        self.update_external_index(self.store.external_index + 1)?;
+        self.update_external_index(index + 1)?;
         Ok(receive_address.assume_checked()) // TODO: should we check the network or just assume_checked?
     }
 
-    /// Gets the next internal addresses from the HD keychain.
-    pub fn get_next_internal_addresses(&self, count: u32) -> Result<Vec<Address>, WalletError> {
-        let next_change_addr_index = self.find_hd_next_index(KeychainKind::Internal)?;
+    /// Gets the next change addresses from the HD keychain, as per the wallet's [AddressStrategy].
+    ///
+    /// With [AddressStrategy::SingleChain], the external index is moved past the returned addresses,
+    /// so they aren't handed out again as receive addresses.
+    pub fn get_next_internal_addresses(&mut self, count: u32) -> Result<Vec<Address>, WalletError> {
+        let (addresses, next_index) = self.peek_next_internal_addresses(count)?;
+        self.reserve_internal_addresses(next_index)?;
+        Ok(addresses)
+    }
+
+    /// Derives the next change addresses like [Wallet::get_next_internal_addresses], without handing
+    /// them out. Also returns the index to reserve them with, see [Wallet::reserve_internal_addresses].
+    pub(crate) fn peek_next_internal_addresses(
+        &self,
+        count: u32,
+    ) -> Result<(Vec<Address>, u32), WalletError> {
+        let (keychain, next_change_addr_index) = match self.store.address_strategy {
+            AddressStrategy::SplitChain => (
+                KeychainKind::Internal,
+                self.find_hd_next_index(KeychainKind::Internal)?,
+            ),
+            // Change follows the receive addresses already handed out.
+            AddressStrategy::SingleChain => (
+                KeychainKind::External,
+                std::cmp::max(
+                    self.store.external_index,
+                    self.find_hd_next_index(KeychainKind::External)?,
+                ),
+            ),
+        };
         let descriptors = self.get_wallet_descriptors()?;
-        let change_branch_descriptor = descriptors
-            .get(&KeychainKind::Internal)
-            .expect("Internal Keychain expected");
+        let change_branch_descriptor = descriptors.get(&keychain).expect("Keychain expected");
+        let last_index = next_change_addr_index + count.max(1) - 1;
         let addresses = self.rpc.derive_addresses(
             change_branch_descriptor,
-            Some([next_change_addr_index, next_change_addr_index + count]),
+            Some([next_change_addr_index, last_index]),
         )?;
 
-        Ok(addresses
-            .into_iter()
-            .map(|addrs| addrs.assume_checked())
-            .collect())
+        Ok((
+            addresses
+                .into_iter()
+                .map(|addrs| addrs.assume_checked())
+                .collect(),
+            last_index + 1,
+        ))
+    }
+
+    /// Hands out the change addresses peeked below `next_index`. With [AddressStrategy::SingleChain],
+    /// the external index is moved past them. Split chain change addresses are only used up once
+    /// they receive coins.
+    pub(crate) fn reserve_internal_addresses(
+        &mut self,
+        next_index: u32,
+    ) -> Result<(), WalletError> {
+        if self.store.address_strategy == AddressStrategy::SingleChain
+            && next_index > self.store.external_index
+        {
+            self.update_external_index(next_index)?;
+        }
+        Ok(())
     }
 
     /// Refreshes the offer maximum size cache based on the current wallet's unspent transaction outputs (UTXOs).
@@ -891,6 +1470,81 @@ impl Wallet {
         Ok(())
     }
 
+    /// Set a balance which is never spent by swaps or automatic coin selection.
+    /// It is left out of the spendable balance.
+    pub fn set_reserve_amount(&mut self, reserve: Amount) {
+        self.reserve_amount = reserve;
+    }
+
+    /// Set the floor in sats/vByte of the fee rate of every transaction built by the wallet, whatever
+    /// the fee estimate or the requested fee rate.
+    pub fn set_min_fee_rate(&mut self, min_fee_rate: f64) {
+        self.min_fee_rate = min_fee_rate;
+    }
+
+    /// Estimates the fee rate in sats/vByte for a confirmation within `target_blocks`, never below the
+    /// configured floor.
+    pub fn estimate_fee_rate(&self, target_blocks: u16) -> f64 {
+        let estimate = self
+            .rpc
+            .estimate_smart_fee(target_blocks, None)
+            .ok()
+            .and_then(|estimate| estimate.fee_rate)
+            .map(|per_kvb| per_kvb.to_sat() as f64 / 1000.0);
+        floor_fee_rate(estimate, self.min_fee_rate)
+    }
+
+    /// Allow exporting the private keys of wallet addresses, with [Wallet::dump_private_key].
+    pub fn set_allow_key_export(&mut self, allow: bool) {
+        self.allow_key_export = allow;
+    }
+
+    /// Returns the private key of a wallet address, e.g. for recovery with another wallet.
+    ///
+    /// Only single key addresses derived from the wallet seed have a private key. Addresses not owned
+    /// by the wallet, and the contract or multisig scripts of swaps are refused. Refused altogether,
+    /// unless enabled with [Wallet::set_allow_key_export].
+    pub fn dump_private_key(&self, addr: &Address) -> Result<PrivateKey, WalletError> {
+        if !self.allow_key_export {
+            return Err(WalletError::General(
+                "Private key export is disabled. Set `allow_key_export = true` in the config to enable it"
+                    .to_string(),
+            ));
+        }
+
+        let not_derived = || {
+            WalletError::General(format!(
+                "Address {} isn't a single key address derived from this wallet",
+                addr
+            ))
+        };
+
+        let path = self
+            .rpc
+            .get_address_info(addr)?
+            .hd_key_path
+            .ok_or_else(not_derived)?;
+
+        let secp = Secp256k1::new();
+        let privkey = self
+            .store
+            .master_key
+            .derive_priv(&secp, &DerivationPath::from_str(HARDENDED_DERIVATION)?)?
+            .derive_priv(&secp, &path)?
+            .private_key;
+        let pubkey = PublicKey {
+            compressed: true,
+            inner: privkey.public_key(&secp),
+        };
+
+        // The node only tells the derivation path, make sure it's our key for this address.
+        if ScriptBuf::new_p2wpkh(&pubkey.wpubkey_hash()?) != addr.script_pubkey() {
+            return Err(not_derived());
+        }
+
+        Ok(PrivateKey::new(privkey, self.store.network))
+    }
+
     /// Gets a tweakable key pair from the master key of the wallet.
     pub(crate) fn get_tweakable_keypair(&self) -> Result<(SecretKey, PublicKey), WalletError> {
         let secp = Secp256k1::new();
@@ -908,6 +1562,9 @@ impl Wallet {
     }
 
     /// Refreshes the UTXO cache by adding only new UTXOs while preserving existing ones.
+    ///
+    /// UTXOs no longer reported by the node, ex: spent or reorged out, are dropped from the cache
+    /// and the script index. New UTXOs paying to an already indexed seed script reuse its path.
     pub(crate) fn update_utxo_cache(&mut self, utxos: Vec<ListUnspentResultEntry>) {
         let mut new_entries = Vec::new();
         let existing_outpoints: std::collections::HashSet<OutPoint> = utxos
@@ -918,20 +1575,6 @@ impl Wallet {
             })
             .collect();
 
-        // Identify UTXOs to be removed (present in store but missing in utxos parameter passed)
-        let mut to_remove = Vec::new();
-        for existing_outpoint in self.store.utxo_cache.keys().cloned().collect::<Vec<_>>() {
-            if !existing_outpoints.contains(&existing_outpoint) {
-                to_remove.push(existing_outpoint);
-            }
-        }
-
-        // Remove UTXOs that no longer exis in the received utxos list
-        for outpoint in to_remove {
-            self.store.utxo_cache.remove(&outpoint);
-            log::debug!("[UTXO Cache] Removed UTXO: {:?}", outpoint);
-        }
-
         // Process and add only new UTXOs
         for utxo in utxos {
             let outpoint = OutPoint {
@@ -946,7 +1589,13 @@ impl Wallet {
 
             // Process UTXOs to pair each with its spend info using the wallet's private methods.
             let spend_info = self
-                .check_if_fidelity(&utxo)
+                .store
+                .indexed_seed_path(&utxo.script_pub_key)
+                .map(|path| UTXOSpendInfo::SeedCoin {
+                    path,
+                    input_value: utxo.amount,
+                })
+                .or_else(|| self.check_if_fidelity(&utxo))
                 .or_else(|| {
                     self.check_and_derive_live_contract_spend_info(&utxo)
                         .unwrap()
@@ -963,9 +1612,24 @@ impl Wallet {
             }
         }
 
+        // Identify UTXOs to be removed (present in store but missing in utxos parameter passed).
+        // This is done after processing the new UTXOs, so they can reuse the indexed scripts of the removed ones.
+        let mut to_remove = Vec::new();
+        for existing_outpoint in self.store.utxo_cache.keys().cloned().collect::<Vec<_>>() {
+            if !existing_outpoints.contains(&existing_outpoint) {
+                to_remove.push(existing_outpoint);
+            }
+        }
+
+        // Remove UTXOs that no longer exis in the received utxos list
+        for outpoint in to_remove {
+            self.store.remove_utxo(&outpoint);
+            log::debug!("[UTXO Cache] Removed UTXO: {:?}", outpoint);
+        }
+
         // Insert only new entries into the cache
-        for (outpoint, entry) in new_entries {
-            self.store.utxo_cache.insert(outpoint, entry);
+        for (outpoint, (utxo, spend_info)) in new_entries {
+            self.store.insert_utxo(outpoint, utxo, spend_info);
         }
     }
 
@@ -1061,10 +1725,20 @@ impl Wallet {
     }
 
     /// Largerst to lowest coinselect algorithm
+    ///
+    /// Protected UTXOs, and UTXOs older than the configured max input age, are never selected.
+    /// Errors if the amount can only be covered by spending into the reserve.
     // TODO: Fix Coin Selection algorithm for Dynamic Feerate
     pub fn coin_select(
         &self,
         amount: Amount,
+    ) -> Result<Vec<(ListUnspentResultEntry, UTXOSpendInfo)>, WalletError> {
+        self.select_coins(amount, false, false, &HashSet::new())
+    }
+
+    /// Lists the regular and incoming swap coins automatic coin selection can pick from.
+    pub(crate) fn list_selectable_coins(
+        &self,
     ) -> Result<Vec<(ListUnspentResultEntry, UTXOSpendInfo)>, WalletError> {
         // Get UTXOs from the descriptor and swap coin methods.
         let mut seed_coin_utxo = self.list_descriptor_utxo_spend_info()?;
@@ -1075,16 +1749,83 @@ impl Wallet {
         // Assume that self.rpc.listlockunspent() returns a Vec<OutPoint>.
         let locked_utxos: Vec<OutPoint> = self.list_lock_unspent()?;
 
-        // Filter out UTXOs that are already locked and exclude fidelity coins.
-        let mut unspents = seed_coin_utxo
+        // Filter out UTXOs that are already locked, protected or too old, and exclude fidelity coins.
+        Ok(seed_coin_utxo
             .into_iter()
             .filter(|(utxo, spend_info)| {
                 let outpoint = OutPoint::new(utxo.txid, utxo.vout);
                 !locked_utxos.contains(&outpoint)
-                    && !matches!(spend_info, UTXOSpendInfo::FidelityBondCoin { .. })
+                    && !self.store.protected_utxos.contains(&outpoint)
+                    && !self.is_immature_coinbase(&outpoint)
+                    && self
+                        .store
+                        .max_input_age
+                        .is_none_or(|max_age| utxo.confirmations <= max_age)
+                    && !self.is_fidelity_bond_coin(utxo, spend_info)
+            })
+            .collect())
+    }
+
+    /// Errors if spending `amount` out of the `selectable` coins would leave less than the reserve.
+    pub(crate) fn check_reserve(
+        &self,
+        selectable: &[(ListUnspentResultEntry, UTXOSpendInfo)],
+        amount: Amount,
+    ) -> Result<(), WalletError> {
+        if self.reserve_amount == Amount::ZERO {
+            return Ok(());
+        }
+        let total = selectable
+            .iter()
+            .fold(Amount::ZERO, |acc, (utxo, _)| acc + utxo.amount);
+        if total < amount + self.reserve_amount {
+            return Err(WalletError::InsufficientFund {
+                available: total
+                    .checked_sub(self.reserve_amount)
+                    .unwrap_or(Amount::ZERO)
+                    .to_sat(),
+                required: amount.to_sat(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Largest to lowest coin selection, optionally holding back the outputs of recent swaps, and
+    /// selecting only native segwit coins when `segwit_only` is set. The `excluded` coins are skipped,
+    /// ex: the ones already picked for other txs of the same funding.
+    ///
+    /// Errors if the amount can only be covered by spending outputs of recent swaps, as combining them
+    /// would cluster those outputs together.
+    pub(crate) fn select_coins(
+        &self,
+        amount: Amount,
+        avoid_recent_swaps: bool,
+        segwit_only: bool,
+        excluded: &HashSet<OutPoint>,
+    ) -> Result<Vec<(ListUnspentResultEntry, UTXOSpendInfo)>, WalletError> {
+        let recent_swap_scripts = if avoid_recent_swaps {
+            self.recent_swap_scripts()
+        } else {
+            HashSet::new()
+        };
+        let mut held_back = Amount::ZERO;
+
+        let mut unspents = self
+            .list_selectable_coins()?
+            .into_iter()
+            .filter(|(utxo, _)| !excluded.contains(&OutPoint::new(utxo.txid, utxo.vout)))
+            .filter(|(utxo, _)| !segwit_only || utxo.script_pub_key.is_witness_program())
+            .filter(|(utxo, _)| {
+                let from_recent_swap = recent_swap_scripts.contains(&utxo.script_pub_key);
+                if from_recent_swap {
+                    held_back += utxo.amount;
+                }
+                !from_recent_swap
             })
             .collect::<Vec<_>>();
 
+        self.check_reserve(&unspents, amount)?;
+
         // Sort in descending order so that we use the largest UTXOs first.
         unspents.sort_by(|a, b| b.0.amount.cmp(&a.0.amount));
 
@@ -1101,9 +1842,60 @@ impl Wallet {
                 selected_utxo.push(unspent);
             }
         }
+
+        let selected_amount = selected_utxo
+            .iter()
+            .fold(Amount::ZERO, |acc, (utxo, _)| acc + utxo.amount);
+        if selected_amount < amount && selected_amount + held_back >= amount {
+            return Err(WalletError::General(
+                "Can't fund without spending outputs of recent swaps, which would cluster them"
+                    .to_string(),
+            ));
+        }
         Ok(selected_utxo)
     }
 
+    /// Protects a UTXO from automatic coin selection, e.g. for swap funding.
+    /// A protected UTXO is only spent when explicitly selected via coin control.
+    pub fn protect_utxo(&mut self, outpoint: OutPoint) -> Result<(), WalletError> {
+        self.store.protected_utxos.insert(outpoint);
+        self.save_to_disk()
+    }
+
+    /// Removes the protection of a UTXO. Returns `false` if the UTXO wasn't protected.
+    pub fn unprotect_utxo(&mut self, outpoint: &OutPoint) -> Result<bool, WalletError> {
+        let removed = self.store.protected_utxos.remove(outpoint);
+        self.save_to_disk()?;
+        Ok(removed)
+    }
+
+    /// Lists the confirmed regular UTXOs that can be consolidated, leaving out the locked and protected ones,
+    /// and the fidelity bonds.
+    pub(crate) fn list_consolidation_utxo_spend_info(
+        &self,
+    ) -> Result<Vec<(ListUnspentResultEntry, UTXOSpendInfo)>, WalletError> {
+        let locked_utxos = self.list_lock_unspent()?;
+        Ok(self
+            .list_descriptor_utxo_spend_info()?
+            .into_iter()
+            .filter(|(utxo, spend_info)| {
+                let outpoint = OutPoint::new(utxo.txid, utxo.vout);
+                utxo.confirmations > 0
+                    && !locked_utxos.contains(&outpoint)
+                    && !self.store.protected_utxos.contains(&outpoint)
+                    && !self.is_immature_coinbase(&outpoint)
+                    && !self.is_fidelity_bond_coin(utxo, spend_info)
+            })
+            .collect())
+    }
+
+    /// Sets the maximum age, in confirmations, of UTXOs picked by automatic coin selection.
+    /// Older UTXOs are only spent via coin control. `None` removes the limit.
+    pub fn set_max_input_age(&mut self, max_input_age: Option<u32>) -> Result<(), WalletError> {
+        self.store.max_input_age = max_input_age;
+        self.save_to_disk()
+    }
+
     pub(crate) fn get_utxo(
         &self,
         (txid, vout): (Txid, u32),
@@ -1121,53 +1913,95 @@ impl Wallet {
         Ok(None)
     }
 
-    fn create_and_import_coinswap_address(
-        &mut self,
-        other_pubkey: &PublicKey,
-    ) -> Result<(Address, SecretKey), WalletError> {
+    /// Derives the address of a coinswap multisig with a fresh key of ours. Nothing is imported into
+    /// the wallet yet, see [Wallet::commit_coinswap].
+    fn create_coinswap_address(&self, other_pubkey: &PublicKey) -> (Address, SecretKey) {
         let (my_pubkey, my_privkey) = generate_keypair();
-
-        let descriptor = self
-            .rpc
-            .get_descriptor_info(&format!(
-                "wsh(sortedmulti(2,{},{}))",
-                my_pubkey, other_pubkey
-            ))?
-            .descriptor;
-        self.import_descriptors(&[descriptor.clone()], None)?;
-
-        //redeemscript and descriptor show up in `getaddressinfo` only after
-        // the address gets outputs on it-
-        Ok((
-            //TODO should completely avoid derive_addresses
-            //because its slower and provides no benefit over using rust-bitcoin
-            self.rpc.derive_addresses(&descriptor[..], None)?[0]
-                .clone()
-                .assume_checked(),
+        let multisig_redeemscript =
+            contract::create_multisig_redeemscript(&my_pubkey, other_pubkey);
+        (
+            Address::p2wsh(&multisig_redeemscript, self.store.network),
             my_privkey,
-        ))
+        )
     }
 
-    /// Initialize a Coinswap with the Other party.
+    /// Initialize a Coinswap with the Other party. With `segwit_only`, the funding txs spend native segwit
+    /// coins only, for makers rejecting other funding inputs.
     /// Returns, the Funding Transactions, [`OutgoingSwapCoin`]s and the Total Miner fees.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn initalize_coinswap(
         &mut self,
         total_coinswap_amount: Amount,
         other_multisig_pubkeys: &[PublicKey],
         hashlock_pubkeys: &[PublicKey],
-        hashvalue: Hash160,
+        hashvalue: Hashvalue,
         locktime: u16,
         fee_rate: Amount,
+        funding_sequence: Sequence,
+        segwit_only: bool,
     ) -> Result<(Vec<Transaction>, Vec<OutgoingSwapCoin>, Amount), WalletError> {
+        let prepared = self.prepare_coinswap(
+            total_coinswap_amount,
+            other_multisig_pubkeys,
+            hashlock_pubkeys,
+            hashvalue,
+            locktime,
+            fee_rate,
+            funding_sequence,
+            segwit_only,
+        )?;
+        self.commit_coinswap(&prepared)?;
+        Ok((
+            prepared.funding_txes,
+            prepared.outgoing_swapcoins,
+            prepared.total_miner_fee,
+        ))
+    }
+
+    /// Builds the funding of a coinswap like [Wallet::initalize_coinswap], without touching the wallet:
+    /// no multisig is imported, no change address handed out, and no input locked. The funding must be
+    /// committed with [Wallet::commit_coinswap] before it's broadcasted, or can be dropped as it is.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn prepare_coinswap(
+        &self,
+        total_coinswap_amount: Amount,
+        other_multisig_pubkeys: &[PublicKey],
+        hashlock_pubkeys: &[PublicKey],
+        hashvalue: Hashvalue,
+        locktime: u16,
+        fee_rate: Amount,
+        funding_sequence: Sequence,
+        segwit_only: bool,
+    ) -> Result<PreparedCoinswap, WalletError> {
         let (coinswap_addresses, my_multisig_privkeys): (Vec<_>, Vec<_>) = other_multisig_pubkeys
             .iter()
-            .map(|other_key| self.create_and_import_coinswap_address(other_key))
-            .collect::<Result<Vec<(Address, SecretKey)>, WalletError>>()?
-            .into_iter()
+            .map(|other_key| self.create_coinswap_address(other_key))
             .unzip();
-
-        let create_funding_txes_result =
-            self.create_funding_txes(total_coinswap_amount, &coinswap_addresses, fee_rate)?;
+        let (change_addresses, next_change_index) =
+            self.peek_next_internal_addresses(coinswap_addresses.len() as u32)?;
+
+        let create_funding_txes_result = self.create_funding_txes(
+            total_coinswap_amount,
+            &coinswap_addresses,
+            &change_addresses,
+            fee_rate,
+            funding_sequence,
+            segwit_only,
+        )?;
+        // The utxo cache can be stale, so make sure the selected coins aren't spent yet.
+        self.check_input_conflicts(&create_funding_txes_result.funding_txes)?;
+        let change_scripts = create_funding_txes_result
+            .funding_txes
+            .iter()
+            .zip(create_funding_txes_result.payment_output_positions.iter())
+            .flat_map(|(tx, &payment_pos)| {
+                tx.output
+                    .iter()
+                    .enumerate()
+                    .filter(move |(vout, _)| *vout != payment_pos as usize)
+                    .map(|(_, txout)| txout.script_pubkey.clone())
+            })
+            .collect();
         //for sweeping there would be another function, probably
         //probably have an enum called something like SendAmount which can be
         // an integer but also can be Sweep
@@ -1218,11 +2052,54 @@ impl Wallet {
             )?);
         }
 
-        Ok((
-            create_funding_txes_result.funding_txes,
+        Ok(PreparedCoinswap {
+            funding_txes: create_funding_txes_result.funding_txes,
             outgoing_swapcoins,
-            Amount::from_sat(create_funding_txes_result.total_miner_fee),
-        ))
+            total_miner_fee: Amount::from_sat(create_funding_txes_result.total_miner_fee),
+            hashvalue,
+            change_scripts,
+            next_change_index,
+        })
+    }
+
+    /// Commits a funding built by [Wallet::prepare_coinswap] to the wallet: imports the multisigs,
+    /// hands out the change addresses, locks the inputs and records the swap's scripts.
+    pub(crate) fn commit_coinswap(
+        &mut self,
+        prepared: &PreparedCoinswap,
+    ) -> Result<(), WalletError> {
+        let descriptors = prepared
+            .outgoing_swapcoins
+            .iter()
+            .map(|sc| {
+                let descriptor_without_checksum = format!(
+                    "wsh(sortedmulti(2,{},{}))",
+                    sc.get_my_pubkey(),
+                    sc.get_other_pubkey()
+                );
+                Ok(format!(
+                    "{}#{}",
+                    descriptor_without_checksum,
+                    compute_checksum(&descriptor_without_checksum)?
+                ))
+            })
+            .collect::<Result<Vec<String>, WalletError>>()?;
+        self.import_descriptors(&descriptors, None)?;
+
+        self.reserve_internal_addresses(prepared.next_change_index)?;
+
+        self.rpc.lock_unspent(
+            &prepared
+                .funding_txes
+                .iter()
+                .flat_map(|tx| tx.input.iter().map(|vin| vin.previous_output))
+                .collect::<Vec<OutPoint>>(),
+        )?;
+
+        if self.avoid_swap_clustering {
+            self.record_swap_scripts(prepared.hashvalue, prepared.change_scripts.clone());
+        }
+        Ok(())
     }
 
     /// Imports a watch-only redeem script into the wallet.
@@ -1238,12 +2115,11 @@ impl Wallet {
         self.import_descriptors(&[descriptor], Some(WATCH_ONLY_SWAPCOIN_LABEL.to_string()))
     }
 
-    pub(crate) fn descriptors_to_import(&self) -> Result<Vec<String>, WalletError> {
-        let mut descriptors_to_import = Vec::new();
-
-        descriptors_to_import.extend(self.get_unimported_wallet_desc()?);
+    /// Descriptors of the multisig and contract scripts of all stored swapcoins.
+    pub(crate) fn swapcoin_descriptors(&self) -> Result<Vec<String>, WalletError> {
+        let mut descriptors = Vec::new();
 
-        descriptors_to_import.extend(
+        descriptors.extend(
             self.store
                 .incoming_swapcoins
                 .values()
@@ -1262,7 +2138,7 @@ impl Wallet {
                 .collect::<Result<Vec<String>, WalletError>>()?,
         );
 
-        descriptors_to_import.extend(
+        descriptors.extend(
             self.store
                 .outgoing_swapcoins
                 .values()
@@ -1281,7 +2157,7 @@ impl Wallet {
                 .collect::<Result<Vec<String>, WalletError>>()?,
         );
 
-        descriptors_to_import.extend(
+        descriptors.extend(
             self.store
                 .incoming_swapcoins
                 .values()
@@ -1296,7 +2172,7 @@ impl Wallet {
                 })
                 .collect::<Result<Vec<String>, WalletError>>()?,
         );
-        descriptors_to_import.extend(
+        descriptors.extend(
             self.store
                 .outgoing_swapcoins
                 .values()
@@ -1311,6 +2187,14 @@ impl Wallet {
                 })
                 .collect::<Result<Vec<String>, WalletError>>()?,
         );
+        Ok(descriptors)
+    }
+
+    pub(crate) fn descriptors_to_import(&self) -> Result<Vec<String>, WalletError> {
+        let mut descriptors_to_import = Vec::new();
+
+        descriptors_to_import.extend(self.get_unimported_wallet_desc()?);
+        descriptors_to_import.extend(self.swapcoin_descriptors()?);
 
         descriptors_to_import.extend(
             self.store
@@ -1329,8 +2213,641 @@ impl Wallet {
         Ok(descriptors_to_import)
     }
 
+    /// Checks that every input of the given transactions is still unspent, in the mempool and on chain.
+    ///
+    /// Errors with [WalletError::InputConflict] at the first input already spent by another transaction.
+    pub fn check_input_conflicts(&self, txs: &[Transaction]) -> Result<(), WalletError> {
+        for outpoint in txs
+            .iter()
+            .flat_map(|tx| tx.input.iter().map(|input| input.previous_output))
+        {
+            if self
+                .rpc
+                .get_tx_out(&outpoint.txid, outpoint.vout, Some(true))?
+                .is_none()
+            {
+                return Err(WalletError::InputConflict { outpoint });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a funding tx merkle proof proves the inclusion of `tx` in a block of the active chain.
+    ///
+    /// Errors with [ProtocolError::InvalidMerkleProof] for a malformed or forged proof, or one whose
+    /// block isn't known to the node, or was reorged out.
+    pub(crate) fn verify_funding_tx_merkleproof(
+        &self,
+        tx: &Transaction,
+        merkleproof: &str,
+    ) -> Result<(), WalletError> {
+        let txid = tx.compute_txid();
+        let header = contract::verify_merkle_proof(merkleproof, &txid)?;
+        match self.rpc.get_block_header_info(&header.block_hash()) {
+            Ok(info) if info.confirmations >= 0 => Ok(()),
+            _ => Err(ProtocolError::InvalidMerkleProof(txid).into()),
+        }
+    }
+
     /// Uses internal RPC client to braodcast a transaction
+    ///
+    /// Returns [WalletError::FeeTooLow] if the node rejects the transaction for paying less than its
+    /// minimum relay or mempool fee.
     pub fn send_tx(&self, tx: &Transaction) -> Result<Txid, WalletError> {
-        Ok(self.rpc.send_raw_transaction(tx)?)
+        self.rpc
+            .send_raw_transaction(tx)
+            .map_err(|e| match parse_required_fee(&e.to_string()) {
+                Some(required) => WalletError::FeeTooLow { required },
+                None => e.into(),
+            })
+    }
+}
+
+/// Parses the required fee in sats from a node's fee related reject reason.
+///
+/// The reject reasons are of the form `min relay fee not met, <fee> < <required>`, and
+/// `mempool min fee not met, <fee> < <required>`.
+fn parse_required_fee(reject_reason: &str) -> Option<u64> {
+    let (_, fees) = reject_reason
+        .split_once("min relay fee not met")
+        .or_else(|| reject_reason.split_once("mempool min fee not met"))?;
+    let required = fees.split_once('<')?.1.trim_start();
+    let digits = required
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(required.len());
+    required[..digits].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::HashlockVariant;
+    use bitcoin::Network;
+    use bitcoind::bitcoincore_rpc::Auth;
+
+    /// A regtest wallet store from a fixed seed, named after its file.
+    fn test_store(wallet_file_path: &Path) -> WalletStore {
+        let master_key = Xpriv::new_master(Network::Regtest, &[7u8; 16]).unwrap();
+        WalletStore::init(
+            wallet_file_path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+            wallet_file_path,
+            Network::Regtest,
+            master_key,
+            None,
+            None,
+            AddressStrategy::default(),
+        )
+        .unwrap()
+    }
+
+    /// A wallet of `store` with the default settings, whose RPC client never connects.
+    fn test_wallet(store: WalletStore, wallet_file_path: &Path) -> Wallet {
+        Wallet {
+            rpc: Client::new("http://127.0.0.1:1", Auth::None).unwrap(),
+            wallet_file_path: wallet_file_path.to_path_buf(),
+            store,
+            anti_fee_sniping: AntiFeeSniping::default(),
+            avoid_swap_clustering: false,
+            tx_ordering: TxOrdering::default(),
+            allow_key_export: false,
+            reserve_amount: Amount::ZERO,
+            min_fee_rate: MIN_FEE_RATE,
+            deferred_sync: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_required_fee() {
+        let rpc_error = r#"JSON-RPC error: RPC error response: RpcError { code: -26, message: "min relay fee not met, 0 < 110", data: None }"#;
+        assert_eq!(parse_required_fee(rpc_error), Some(110));
+        assert_eq!(
+            parse_required_fee("mempool min fee not met, 1000 < 2345"),
+            Some(2345)
+        );
+        assert_eq!(
+            parse_required_fee("insufficient fee, rejecting replacement"),
+            None
+        );
+        assert_eq!(parse_required_fee("bad-txns-inputs-missingorspent"), None);
+    }
+
+    #[test]
+    fn test_script_index_avoids_derivation() {
+        use bitcoin::{hashes::Hash, Txid};
+        use bitcoind::tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let wallet_file_path = temp_dir.path().join("index-wallet");
+        let store = test_store(&wallet_file_path);
+        // The client never connects, all lookups below must be answered from the wallet store.
+        let mut wallet = test_wallet(store, &wallet_file_path);
+
+        // A script which isn't derivable from the seed. Only the index can classify it.
+        let script_pubkey = ScriptBuf::new_p2wsh(&bitcoin::WScriptHash::all_zeros());
+        let utxo = |vout: u32, sats: u64| ListUnspentResultEntry {
+            txid: Txid::all_zeros(),
+            vout,
+            address: None,
+            label: None,
+            redeem_script: None,
+            witness_script: None,
+            script_pub_key: script_pubkey.clone(),
+            amount: Amount::from_sat(sats),
+            confirmations: 1,
+            spendable: true,
+            solvable: true,
+            descriptor: None,
+            safe: true,
+        };
+        let outpoint = |vout| OutPoint {
+            txid: Txid::all_zeros(),
+            vout,
+        };
+        wallet.store.insert_utxo(
+            outpoint(0),
+            utxo(0, 1000),
+            UTXOSpendInfo::SeedCoin {
+                path: "m/0/7".to_string(),
+                input_value: Amount::from_sat(1000),
+            },
+        );
+
+        for _ in 0..100 {
+            assert_eq!(
+                wallet.get_balances().unwrap().regular,
+                Amount::from_sat(1000)
+            );
+        }
+
+        // A spent output paying to the indexed script is found without searching the keychains.
+        let txout = bitcoin::TxOut {
+            value: Amount::from_sat(2000),
+            script_pubkey: script_pubkey.clone(),
+        };
+        assert_eq!(
+            wallet
+                .find_spend_info_for_txout(&outpoint(1), &txout)
+                .unwrap(),
+            Some(UTXOSpendInfo::SeedCoin {
+                path: "m/0/7".to_string(),
+                input_value: Amount::from_sat(2000),
+            })
+        );
+
+        // A new UTXO to the indexed script reuses its path, and the dropped UTXO leaves the index.
+        wallet.update_utxo_cache(vec![utxo(1, 2000)]);
+        assert_eq!(
+            wallet.store.script_index.get(&script_pubkey),
+            Some(&vec![outpoint(1)])
+        );
+        assert_eq!(
+            wallet.get_balances().unwrap().regular,
+            Amount::from_sat(2000)
+        );
+
+        wallet.update_utxo_cache(vec![]);
+        assert!(wallet.store.script_index.is_empty());
+        assert!(wallet.store.utxo_cache.is_empty());
+    }
+
+    #[test]
+    fn test_list_spendable_with_weight() {
+        use bitcoin::{hashes::Hash, Txid};
+        use bitcoind::tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let wallet_file_path = temp_dir.path().join("weight-wallet");
+        let store = test_store(&wallet_file_path);
+        let mut wallet = test_wallet(store, &wallet_file_path);
+
+        let utxo = |vout: u32| ListUnspentResultEntry {
+            txid: Txid::all_zeros(),
+            vout,
+            address: None,
+            label: None,
+            redeem_script: None,
+            witness_script: None,
+            script_pub_key: ScriptBuf::new(),
+            amount: Amount::from_sat(10_000),
+            confirmations: 1,
+            spendable: true,
+            solvable: true,
+            descriptor: None,
+            safe: true,
+        };
+        let seed_coin = UTXOSpendInfo::SeedCoin {
+            path: "m/0/7".to_string(),
+            input_value: Amount::from_sat(10_000),
+        };
+        let swap_coin = UTXOSpendInfo::IncomingSwapCoin {
+            multisig_redeemscript: ScriptBuf::new(),
+        };
+        let outgoing_swap_coin = UTXOSpendInfo::OutgoingSwapCoin {
+            multisig_redeemscript: ScriptBuf::new(),
+        };
+        for (vout, spend_info) in [&seed_coin, &swap_coin, &outgoing_swap_coin]
+            .iter()
+            .enumerate()
+        {
+            wallet.store.insert_utxo(
+                OutPoint::new(Txid::all_zeros(), vout as u32),
+                utxo(vout as u32),
+                (*spend_info).clone(),
+            );
+        }
+
+        // The outgoing swap coin needs the other party's signature, and isn't listed.
+        let spendable = wallet.list_spendable_with_weight().unwrap();
+        assert_eq!(spendable.len(), 2);
+        let weight_of = |wanted: &UTXOSpendInfo| {
+            spendable
+                .iter()
+                .find(|(_, spend_info, _)| spend_info == wanted)
+                .map(|(_, _, weight)| *weight)
+                .unwrap()
+        };
+
+        // A P2WPKH input is 41 vbytes plus its witness, a 2-of-2 multisig input is heavier.
+        assert_eq!(weight_of(&seed_coin), Weight::from_wu(41 * 4 + 107));
+        assert!(weight_of(&swap_coin) > weight_of(&seed_coin));
+        assert_eq!(weight_of(&swap_coin), swap_coin.estimate_input_weight());
+    }
+
+    #[test]
+    fn test_immature_coinbase_balance() {
+        use bitcoin::{hashes::Hash, Txid};
+        use bitcoind::tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let wallet_file_path = temp_dir.path().join("coinbase-wallet");
+        let store = test_store(&wallet_file_path);
+        let mut wallet = test_wallet(store, &wallet_file_path);
+
+        let outpoint = |vout| OutPoint::new(Txid::all_zeros(), vout);
+        for vout in 0..2 {
+            wallet.store.insert_utxo(
+                outpoint(vout),
+                ListUnspentResultEntry {
+                    txid: Txid::all_zeros(),
+                    vout,
+                    address: None,
+                    label: None,
+                    redeem_script: None,
+                    witness_script: None,
+                    script_pub_key: ScriptBuf::new(),
+                    amount: Amount::from_sat(10_000),
+                    confirmations: 1,
+                    spendable: true,
+                    solvable: true,
+                    descriptor: None,
+                    safe: true,
+                },
+                UTXOSpendInfo::SeedCoin {
+                    path: format!("m/0/{}", vout),
+                    input_value: Amount::from_sat(10_000),
+                },
+            );
+        }
+        // The first coin is a coinbase mined at height 200.
+        wallet.store.coinbase_heights.insert(outpoint(0), 200);
+
+        // 99 confirmations, one short of maturity.
+        wallet.store.last_synced_height = Some(298);
+        assert!(wallet.is_immature_coinbase(&outpoint(0)));
+        assert!(!wallet.is_immature_coinbase(&outpoint(1)));
+        let balances = wallet.get_balances().unwrap();
+        assert_eq!(balances.immature, Amount::from_sat(10_000));
+        assert_eq!(balances.regular, Amount::from_sat(10_000));
+        assert_eq!(balances.spendable, Amount::from_sat(10_000));
+        let spendable = wallet.list_spendable_with_weight().unwrap();
+        assert_eq!(spendable.len(), 1);
+        assert_eq!(spendable[0].0.vout, 1);
+
+        // 100 confirmations, it can be spent in the next block.
+        wallet.store.last_synced_height = Some(299);
+        assert!(!wallet.is_immature_coinbase(&outpoint(0)));
+        let balances = wallet.get_balances().unwrap();
+        assert_eq!(balances.immature, Amount::ZERO);
+        assert_eq!(balances.regular, Amount::from_sat(20_000));
+        assert_eq!(wallet.list_spendable_with_weight().unwrap().len(), 2);
+
+        // Spent coins are no longer tracked.
+        wallet.store.remove_utxo(&outpoint(0));
+        assert!(wallet.store.coinbase_heights.is_empty());
+    }
+
+    #[test]
+    fn test_labels_persist() {
+        use bitcoin::hashes::Hash;
+        use bitcoind::tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let wallet_file_path = temp_dir.path().join("labels-wallet");
+        let store = test_store(&wallet_file_path);
+        let mut wallet = test_wallet(store, &wallet_file_path);
+
+        let script_pubkey = ScriptBuf::new_p2wsh(&bitcoin::WScriptHash::all_zeros());
+        let address = Address::from_script(&script_pubkey, Network::Regtest).unwrap();
+        let utxo = |txid_byte: u8| ListUnspentResultEntry {
+            txid: Txid::from_byte_array([txid_byte; 32]),
+            vout: 0,
+            address: None,
+            label: None,
+            redeem_script: None,
+            witness_script: None,
+            script_pub_key: script_pubkey.clone(),
+            amount: Amount::from_sat(1000),
+            confirmations: 1,
+            spendable: true,
+            solvable: true,
+            descriptor: None,
+            safe: true,
+        };
+        let spend_info = UTXOSpendInfo::SeedCoin {
+            path: "m/0/7".to_string(),
+            input_value: Amount::from_sat(1000),
+        };
+        let outpoint = |txid_byte: u8| OutPoint::new(Txid::from_byte_array([txid_byte; 32]), 0);
+        wallet
+            .store
+            .insert_utxo(outpoint(1), utxo(1), spend_info.clone());
+
+        wallet
+            .set_label(LabelTarget::Address(address.clone()), "savings".to_string())
+            .unwrap();
+        wallet
+            .set_label(LabelTarget::Outpoint(outpoint(1)), "payday".to_string())
+            .unwrap();
+
+        // The labels are read back from the wallet file.
+        let mut wallet = test_wallet(
+            WalletStore::read_from_disk(&wallet_file_path).unwrap(),
+            &wallet_file_path,
+        );
+        assert_eq!(
+            wallet.get_label(&LabelTarget::Address(address.clone())),
+            Some(&"savings".to_string())
+        );
+        assert_eq!(
+            wallet.get_label(&LabelTarget::Outpoint(outpoint(1))),
+            Some(&"payday".to_string())
+        );
+        let labels = |wallet: &Wallet| {
+            wallet
+                .list_all_utxo_spend_info()
+                .unwrap()
+                .iter()
+                .map(|(utxo, spend_info)| wallet.get_utxo_label(utxo, spend_info).cloned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(&wallet), vec![Some("payday".to_string())]);
+
+        // The UTXO is spent, and a new one is received at the same address. It gets the address label.
+        wallet.update_utxo_cache(vec![utxo(2)]);
+        assert_eq!(labels(&wallet), vec![Some("savings".to_string())]);
+
+        wallet
+            .set_label(LabelTarget::Address(address.clone()), String::new())
+            .unwrap();
+        assert_eq!(wallet.get_label(&LabelTarget::Address(address)), None);
+        assert_eq!(labels(&wallet), vec![None]);
+    }
+
+    #[test]
+    fn test_export_bip329_labels() {
+        use bitcoin::{hashes::Hash, Txid};
+        use bitcoind::tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let wallet_file_path = temp_dir.path().join("bip329-wallet");
+        let store = test_store(&wallet_file_path);
+        let mut wallet = test_wallet(store, &wallet_file_path);
+
+        let script = |i: u8| ScriptBuf::new_p2wsh(&bitcoin::WScriptHash::from_byte_array([i; 32]));
+        let outpoint = |i: u8| OutPoint::new(Txid::from_byte_array([i; 32]), 0);
+        let utxo = |i: u8| ListUnspentResultEntry {
+            txid: Txid::from_byte_array([i; 32]),
+            vout: 0,
+            address: None,
+            label: None,
+            redeem_script: None,
+            witness_script: None,
+            script_pub_key: script(i),
+            amount: Amount::from_sat(1000),
+            confirmations: 1,
+            spendable: true,
+            solvable: true,
+            descriptor: None,
+            safe: true,
+        };
+        wallet.store.insert_utxo(
+            outpoint(1),
+            utxo(1),
+            UTXOSpendInfo::SeedCoin {
+                path: "m/0/7".to_string(),
+                input_value: Amount::from_sat(1000),
+            },
+        );
+        let multisig_redeemscript = script(9);
+        wallet.store.insert_utxo(
+            outpoint(2),
+            utxo(2),
+            UTXOSpendInfo::IncomingSwapCoin {
+                multisig_redeemscript: multisig_redeemscript.clone(),
+            },
+        );
+        wallet.add_swap_label(&multisig_redeemscript, "rent");
+        // A protected coin, and an immature coinbase output, can't be spent on their own.
+        for i in [3, 4] {
+            wallet.store.insert_utxo(
+                outpoint(i),
+                utxo(i),
+                UTXOSpendInfo::SeedCoin {
+                    path: format!("m/0/{}", i),
+                    input_value: Amount::from_sat(1000),
+                },
+            );
+        }
+        wallet.protect_utxo(outpoint(3)).unwrap();
+        wallet.store.coinbase_heights.insert(outpoint(4), 100);
+        wallet.store.last_synced_height = Some(150);
+        let address = Address::from_script(&script(1), Network::Regtest).unwrap();
+        wallet
+            .set_label(LabelTarget::Address(address.clone()), "savings".to_string())
+            .unwrap();
+
+        let records = wallet
+            .export_bip329_labels()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        // Every record has a BIP329 type, a reference and a label.
+        assert!(records.iter().all(|record| {
+            ["addr", "tx", "output"].contains(&record["type"].as_str().unwrap())
+                && record["ref"].is_string()
+                && record["label"].is_string()
+        }));
+        let record = |kind: &str, reference: String| {
+            records
+                .iter()
+                .find(|record| record["type"] == kind && record["ref"] == reference.as_str())
+                .cloned()
+                .unwrap()
+        };
+
+        assert_eq!(
+            record("addr", address.to_string())["label"],
+            "savings".to_string()
+        );
+        let regular = record("output", outpoint(1).to_string());
+        assert_eq!(regular["label"], "regular: savings");
+        assert_eq!(regular["spendable"], true);
+        let swap = record("output", outpoint(2).to_string());
+        assert_eq!(swap["label"], "incoming-swap: rent");
+        assert_eq!(swap["spendable"], true);
+        assert_eq!(
+            record("output", outpoint(3).to_string())["spendable"],
+            false
+        );
+        assert_eq!(
+            record("output", outpoint(4).to_string())["spendable"],
+            false
+        );
+
+        // Only the tx which created the swap coin is labeled.
+        assert_eq!(
+            record("tx", outpoint(2).txid.to_string())["label"],
+            "incoming-swap"
+        );
+        assert_eq!(records.len(), 6);
+    }
+
+    #[test]
+    fn test_recent_swap_scripts_window() {
+        use bitcoin::hashes::Hash;
+        use bitcoind::tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let wallet_file_path = temp_dir.path().join("clustering-wallet");
+        let store = test_store(&wallet_file_path);
+        let mut wallet = test_wallet(store, &wallet_file_path);
+        wallet.avoid_swap_clustering = true;
+
+        let script = |i: u8| ScriptBuf::new_p2wsh(&bitcoin::WScriptHash::from_byte_array([i; 32]));
+        let hashvalue = |i: u8| Hashvalue::from_preimage(HashlockVariant::Hash160, &[i]);
+
+        // Outputs of the same swap are grouped together.
+        wallet.record_swap_scripts(hashvalue(1), vec![script(1)]);
+        wallet.record_swap_scripts(hashvalue(1), vec![script(2)]);
+        assert_eq!(wallet.store.recent_swap_scripts.len(), 1);
+        assert_eq!(wallet.recent_swap_scripts().len(), 2);
+
+        // Only the last few swaps are remembered.
+        for i in 2..=4 {
+            wallet.record_swap_scripts(hashvalue(i), vec![script(i + 1)]);
+        }
+        let recent = wallet.recent_swap_scripts();
+        assert_eq!(recent.len(), 3);
+        assert!(!recent.contains(&script(1)));
+        assert!(!recent.contains(&script(2)));
+        assert!(recent.contains(&script(5)));
+    }
+
+    #[test]
+    fn test_batch_coalesces_syncs() {
+        use bitcoind::tempfile::tempdir;
+        use std::{
+            io::{BufRead, BufReader, Read, Write},
+            net::TcpListener,
+            panic::{self, AssertUnwindSafe},
+            sync::{
+                atomic::{AtomicUsize, Ordering::SeqCst},
+                Arc,
+            },
+            thread,
+        };
+
+        // A node answering every RPC call with an error, counting the syncs by their first call.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let node_url = format!("http://{}", listener.local_addr().unwrap());
+        let syncs = Arc::new(AtomicUsize::new(0));
+        let node_syncs = syncs.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                loop {
+                    let mut content_length = 0;
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                        if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                            content_length = len.trim().parse().unwrap();
+                        }
+                        line.clear();
+                    }
+                    if line.is_empty() {
+                        break;
+                    }
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).unwrap();
+                    if String::from_utf8_lossy(&body).contains("listwallets") {
+                        node_syncs.fetch_add(1, SeqCst);
+                    }
+                    let response = r#"{"result":null,"error":{"code":-1,"message":"test"},"id":0}"#;
+                    write!(
+                        reader.get_mut(),
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                        response.len(),
+                        response
+                    )
+                    .unwrap();
+                }
+            }
+        });
+
+        let temp_dir = tempdir().unwrap();
+        let wallet_file_path = temp_dir.path().join("batch-wallet");
+        let store = test_store(&wallet_file_path);
+        let mut wallet = test_wallet(store, &wallet_file_path);
+        wallet.rpc = Client::new(&node_url, Auth::None).unwrap();
+
+        // Unbatched, every sync hits the node.
+        for _ in 0..3 {
+            assert!(wallet.sync().is_err());
+        }
+        assert_eq!(syncs.load(SeqCst), 3);
+
+        // Batched, the syncs, including those of a nested batch, are done once at the end.
+        let result = wallet.batch(|wallet| {
+            for _ in 0..3 {
+                wallet.sync()?;
+            }
+            wallet.batch(|wallet| wallet.sync())?;
+            assert_eq!(syncs.load(SeqCst), 3);
+            Ok::<_, WalletError>(())
+        });
+        assert!(result.is_err());
+        assert_eq!(syncs.load(SeqCst), 4);
+
+        // A batch without any sync requested doesn't sync.
+        wallet.batch(|_| Ok::<_, WalletError>(())).unwrap();
+        assert_eq!(syncs.load(SeqCst), 4);
+        assert!(wallet.deferred_sync.is_none());
+
+        // A panicking batch doesn't sync, and the syncs after it hit the node again.
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            wallet.batch(|wallet| -> Result<(), WalletError> {
+                wallet.sync()?;
+                panic!("batch step panicked");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert!(wallet.deferred_sync.is_none());
+        assert!(wallet.sync().is_err());
+        assert_eq!(syncs.load(SeqCst), 5);
     }
 }
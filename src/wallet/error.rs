@@ -73,6 +73,39 @@ pub enum WalletError {
         /// The amount of funds needed to complete the operation.
         required: u64,
     },
+
+    /// Represents a transaction rejected by the node, as its fee is below the node's
+    /// minimum relay or mempool fee.
+    ///
+    /// - `required`: The minimum fee in sats the node accepts for this transaction.
+    FeeTooLow {
+        /// The minimum fee in sats the node accepts for this transaction.
+        required: u64,
+    },
+
+    /// Represents a transaction input, which is already spent by another transaction in the
+    /// mempool or on chain. Ex: by another instance using the same wallet.
+    ///
+    /// - `outpoint`: The conflicting input.
+    InputConflict {
+        /// The conflicting input.
+        outpoint: bitcoin::OutPoint,
+    },
+
+    /// Represents a Bitcoin Core node running without a transaction index.
+    ///
+    /// The contract and funding txs of swaps are looked up by txid, which needs `-txindex=1`.
+    TxindexRequired,
+
+    /// Represents an attempt to spend a fidelity bond outside of its redemption.
+    ///
+    /// Fidelity bonds are never picked by coin selection, and only spent by redeeming them.
+    ///
+    /// - `outpoint`: The fidelity bond.
+    CannotSpendFidelityBond {
+        /// The fidelity bond.
+        outpoint: bitcoin::OutPoint,
+    },
 }
 
 impl From<std::io::Error> for WalletError {
@@ -7,21 +7,22 @@ use bitcoin::{
     absolute::LockTime,
     bip32::{ChildNumber, DerivationPath},
     hashes::{sha256d, Hash},
+    hex::DisplayHex,
     opcodes::all::{OP_CHECKSIGVERIFY, OP_CLTV},
     script::{Builder, Instruction},
-    secp256k1::{Keypair, Message, Secp256k1},
+    secp256k1::{ecdsa::Signature, Keypair, Message, Secp256k1},
     Address, Amount, OutPoint, PublicKey, ScriptBuf, Txid,
 };
-use bitcoind::bitcoincore_rpc::RpcApi;
+use bitcoind::bitcoincore_rpc::{json::ListUnspentResultEntry, RpcApi};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     str::FromStr,
     thread,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use super::{Destination, WalletError};
+use super::{api::UTXOSpendInfo, Destination, WalletError};
 
 // To (strongly) disincentivize Sybil behavior, the value assessment of the bond
 // is based on the (time value of the bond)^x here x is the bond_value_exponent,
@@ -47,6 +48,7 @@ pub enum FidelityError {
     BondLocktimeExpired,
     CertExpired,
     InvalidCertHash,
+    InvalidIdentityProof,
     General(String),
 }
 
@@ -157,6 +159,17 @@ impl FidelityBond {
         })
     }
 
+    /// Generate the hash of an identity challenge for this bond, signed by the bond key.
+    pub(crate) fn generate_identity_challenge_hash(&self, nonce: &[u8; 32]) -> sha256d::Hash {
+        let challenge_msg = format!(
+            "fidelity-bond-identity|{}|{}|{}",
+            self.outpoint,
+            self.pubkey,
+            nonce.to_lower_hex_string()
+        );
+        sha256d::Hash::hash(challenge_msg.as_bytes())
+    }
+
     /// Calculate the expiry value. This depends on the bond's confirmation height
     pub(crate) fn get_fidelity_expiry(conf_height: u32) -> u32 {
         (conf_height + 2) /* safety buffer */ / 2016 + 5
@@ -170,6 +183,49 @@ impl Wallet {
         &self.store.fidelity_bond
     }
 
+    /// Get the outpoints of the unspent fidelity bonds.
+    pub fn fidelity_bond_outpoints(&self) -> HashSet<OutPoint> {
+        self.store
+            .fidelity_bond
+            .values()
+            .filter(|(_, _, is_spent)| !is_spent)
+            .map(|(bond, _, _)| bond.outpoint)
+            .collect()
+    }
+
+    /// Whether the UTXO is a fidelity bond, or another output locked at a bond's script.
+    ///
+    /// Those are left out of every coin selection, and only spent through [Wallet::redeem_fidelity].
+    pub(crate) fn is_fidelity_bond_coin(
+        &self,
+        utxo: &ListUnspentResultEntry,
+        spend_info: &UTXOSpendInfo,
+    ) -> bool {
+        let outpoint = OutPoint::new(utxo.txid, utxo.vout);
+        matches!(spend_info, UTXOSpendInfo::FidelityBondCoin { .. })
+            || self
+                .store
+                .fidelity_bond
+                .values()
+                .any(|(bond, spk, _)| bond.outpoint == outpoint || *spk == utxo.script_pub_key)
+    }
+
+    /// Errors with [WalletError::CannotSpendFidelityBond] at the first fidelity bond among the coins.
+    pub(crate) fn check_no_fidelity_bond(
+        &self,
+        coins: &[(ListUnspentResultEntry, UTXOSpendInfo)],
+    ) -> Result<(), WalletError> {
+        match coins
+            .iter()
+            .find(|(utxo, spend_info)| self.is_fidelity_bond_coin(utxo, spend_info))
+        {
+            Some((utxo, _)) => Err(WalletError::CannotSpendFidelityBond {
+                outpoint: OutPoint::new(utxo.txid, utxo.vout),
+            }),
+            None => Ok(()),
+        }
+    }
+
     /// Display the fidelity bonds
     pub fn display_fidelity_bonds(&self) -> Result<String, WalletError> {
         let current_block = self.rpc.get_block_count()? as u32;
@@ -490,6 +546,31 @@ impl Wallet {
         })
     }
 
+    /// Sign an identity challenge with the key of the unspent bond at `outpoint`.
+    ///
+    /// This proves that the signer controls the bond committed in its offer.
+    pub(crate) fn sign_identity_challenge(
+        &self,
+        outpoint: &OutPoint,
+        nonce: &[u8; 32],
+    ) -> Result<Signature, WalletError> {
+        let (index, bond) = self
+            .store
+            .fidelity_bond
+            .iter()
+            .find(|(_, (bond, _, is_spent))| bond.outpoint == *outpoint && !is_spent)
+            .map(|(index, (bond, _, _))| (*index, bond))
+            .ok_or(FidelityError::BondDoesNotExist)?;
+
+        let fidelity_privkey = self.get_fidelity_keypair(index)?.secret_key();
+        let challenge_hash = bond.generate_identity_challenge_hash(nonce);
+
+        Ok(Secp256k1::new().sign_ecdsa(
+            &Message::from_digest_slice(challenge_hash.as_byte_array())?,
+            &fidelity_privkey,
+        ))
+    }
+
     /// Verify a [FidelityProof] received from the directory servers.
     pub(crate) fn verify_fidelity_proof(
         &self,
@@ -502,6 +583,37 @@ impl Wallet {
 
         verify_fidelity_checks(proof, onion_addr, transaction, current_height)
     }
+
+    /// Verify our own [FidelityProof] the same way takers do, and check that the bond is still unspent.
+    pub(crate) fn verify_own_fidelity_proof(
+        &self,
+        proof: &FidelityProof,
+        maker_addr: &str,
+    ) -> Result<(), WalletError> {
+        let outpoint = proof.bond.outpoint;
+        if self
+            .rpc
+            .get_tx_out(&outpoint.txid, outpoint.vout, Some(true))?
+            .is_none()
+        {
+            return Err(FidelityError::BondAlreadySpent.into());
+        }
+
+        self.verify_fidelity_proof(proof, maker_addr)
+    }
+}
+
+/// Verify that an identity challenge `sig` is made by the key of the given bond.
+pub(crate) fn verify_identity_proof(
+    bond: &FidelityBond,
+    nonce: &[u8; 32],
+    sig: &Signature,
+) -> Result<(), WalletError> {
+    let challenge_hash = bond.generate_identity_challenge_hash(nonce);
+    let message = Message::from_digest_slice(challenge_hash.as_byte_array())?;
+    Secp256k1::new()
+        .verify_ecdsa(&message, sig, &bond.pubkey.inner)
+        .map_err(|_| FidelityError::InvalidIdentityProof.into())
 }
 
 #[cfg(test)]
@@ -630,6 +742,40 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_identity_proof() {
+        let secp = Secp256k1::new();
+        let bond_key = Keypair::from_seckey_slice(&secp, &[1; 32]).unwrap();
+        let bond = FidelityBond {
+            outpoint: OutPoint::null(),
+            amount: Amount::from_sat(50_000),
+            lock_time: LockTime::from_height(1000).unwrap(),
+            pubkey: PublicKey::new(bond_key.public_key()),
+            conf_height: None,
+            cert_expiry: None,
+        };
+        let nonce = [7; 32];
+        let sign = |key: &Keypair, nonce: &[u8; 32]| {
+            let hash = bond.generate_identity_challenge_hash(nonce);
+            secp.sign_ecdsa(
+                &Message::from_digest_slice(hash.as_byte_array()).unwrap(),
+                &key.secret_key(),
+            )
+        };
+
+        assert!(verify_identity_proof(&bond, &nonce, &sign(&bond_key, &nonce)).is_ok());
+
+        // Signed by a key other than the bond's.
+        let other_key = Keypair::from_seckey_slice(&secp, &[2; 32]).unwrap();
+        assert!(matches!(
+            verify_identity_proof(&bond, &nonce, &sign(&other_key, &nonce)),
+            Err(WalletError::Fidelity(FidelityError::InvalidIdentityProof))
+        ));
+
+        // Replayed signature, for a different challenge.
+        assert!(verify_identity_proof(&bond, &[8; 32], &sign(&bond_key, &nonce)).is_err());
+    }
 }
 
 #[test]
@@ -4,23 +4,39 @@
 //! This module contains routines for creating funding transactions within a wallet. It leverages
 //! Bitcoin Core's RPC methods for wallet interactions, including `walletcreatefundedpsbt`
 
-use std::{collections::HashMap, iter};
+use std::collections::{HashMap, HashSet};
 
 use bitcoin::{
-    absolute::LockTime, transaction::Version, Address, Amount, OutPoint, ScriptBuf, Sequence,
-    Transaction, TxIn, TxOut, Txid, Witness,
+    transaction::Version, Address, Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut,
+    Txid, Witness,
 };
 
-use bitcoind::bitcoincore_rpc::{json::CreateRawTransactionInput, RpcApi};
+use bitcoind::bitcoincore_rpc::json::CreateRawTransactionInput;
 
 use bitcoin::secp256k1::rand::{rngs::OsRng, RngCore};
 
-use crate::taker::api::MINER_FEE;
+use crate::{protocol::Hashvalue, taker::api::MINER_FEE};
 
-use super::Wallet;
+use super::{OutgoingSwapCoin, Wallet};
 
 use super::error::WalletError;
 
+/// Returns the nSequence of funding tx inputs.
+///
+/// Funding txs signal BIP125 opt-in RBF by default, so they can be fee bumped. Otherwise the inputs
+/// don't signal RBF, and the funding txs are non-replaceable. Either way the nLockTime, set against
+/// fee sniping, stays enforced.
+pub(crate) fn funding_input_sequence(rbf: bool) -> Sequence {
+    if rbf {
+        Sequence::ENABLE_RBF_NO_LOCKTIME
+    } else {
+        Sequence::ENABLE_LOCKTIME_NO_RBF
+    }
+}
+
+/// Number of recent swaps, whose outputs are held back from the funding of new swaps.
+const RECENT_SWAPS_TRACKED: usize = 3;
+
 #[derive(Debug)]
 pub(crate) struct CreateFundingTxesResult {
     pub(crate) funding_txes: Vec<Transaction>,
@@ -28,16 +44,79 @@ pub(crate) struct CreateFundingTxesResult {
     pub(crate) total_miner_fee: u64,
 }
 
+/// The funding of a coinswap, built but not yet committed to the wallet.
+/// See [Wallet::prepare_coinswap] and [Wallet::commit_coinswap].
+#[derive(Debug)]
+pub(crate) struct PreparedCoinswap {
+    pub(crate) funding_txes: Vec<Transaction>,
+    pub(crate) outgoing_swapcoins: Vec<OutgoingSwapCoin>,
+    pub(crate) total_miner_fee: Amount,
+    pub(super) hashvalue: Hashvalue,
+    pub(super) change_scripts: Vec<ScriptBuf>,
+    pub(super) next_change_index: u32,
+}
+
 impl Wallet {
+    /// Hold back the outputs of recent swaps from the funding of new swaps, so they aren't clustered
+    /// together by being spent in the same swap.
+    pub fn set_avoid_swap_clustering(&mut self, avoid: bool) {
+        self.avoid_swap_clustering = avoid;
+    }
+
+    /// Records the scriptpubkeys of outputs created by the swap with the given hashvalue.
+    /// Only the last [RECENT_SWAPS_TRACKED] swaps are remembered.
+    pub(crate) fn record_swap_scripts(&mut self, hashvalue: Hashvalue, scripts: Vec<ScriptBuf>) {
+        // SHA256 hashvalues are truncated to the key size, which is plenty to tell recent swaps apart.
+        let mut key = [0u8; 20];
+        key.copy_from_slice(&hashvalue.as_bytes()[..20]);
+        let recent_swaps = &mut self.store.recent_swap_scripts;
+        match recent_swaps.iter_mut().find(|(swap, _)| *swap == key) {
+            Some((_, swap_scripts)) => swap_scripts.extend(scripts),
+            None => {
+                recent_swaps.push_back((key, scripts));
+                if recent_swaps.len() > RECENT_SWAPS_TRACKED {
+                    recent_swaps.pop_front();
+                }
+            }
+        }
+    }
+
+    /// The scriptpubkeys of all outputs created by the recent swaps.
+    pub(crate) fn recent_swap_scripts(&self) -> HashSet<&ScriptBuf> {
+        self.store
+            .recent_swap_scripts
+            .iter()
+            .flat_map(|(_, scripts)| scripts)
+            .collect()
+    }
+
     // Attempts to create the funding transactions.
     /// Returns Ok(None) if there was no error but the wallet was unable to create funding txes
+    ///
+    /// The txs pay their change to the given `change_addresses`, one per destination. Their inputs
+    /// aren't locked, so the funding can be dropped without leaving anything behind.
     pub(crate) fn create_funding_txes(
         &self,
         coinswap_amount: Amount,
         destinations: &[Address],
+        change_addresses: &[Address],
         fee_rate: Amount,
+        sequence: Sequence,
+        segwit_only: bool,
     ) -> Result<CreateFundingTxesResult, WalletError> {
-        let ret = self.create_funding_txes_random_amounts(coinswap_amount, destinations, fee_rate);
+        let ret = self
+            .create_funding_txes_random_amounts(
+                coinswap_amount,
+                destinations,
+                change_addresses,
+                fee_rate,
+                sequence,
+                segwit_only,
+            )
+            .and_then(|mut result| {
+                self.apply_min_fee_rate(&mut result)?;
+                Ok(result)
+            });
         if ret.is_ok() {
             log::info!(target: "wallet", "created funding txes with random amounts");
             return ret;
@@ -45,14 +124,14 @@ impl Wallet {
 
         // TODO: Unlock this code when we are sure that the routines actually works.
 
-        // let ret = self.create_funding_txes_utxo_max_sends(coinswap_amount, destinations, fee_rate);
+        // let ret = self.create_funding_txes_utxo_max_sends(coinswap_amount, destinations, fee_rate, sequence);
         // if ret.is_ok() {
         //     log::info!(target: "wallet", "created funding txes with fully-spending utxos");
         //     return ret;
         // }
 
         // let ret =
-        //     self.create_funding_txes_use_biggest_utxos(coinswap_amount, destinations, fee_rate);
+        //     self.create_funding_txes_use_biggest_utxos(coinswap_amount, destinations, fee_rate, sequence, segwit_only);
         // if ret.is_ok() {
         //     log::info!(target: "wallet", "created funding txes with using the biggest utxos");
         //     return ret;
@@ -62,6 +141,68 @@ impl Wallet {
         ret
     }
 
+    /// Raises the fee of the funding txs up to the `min_fee_rate` floor, out of their change.
+    ///
+    /// Txs without change, or whose change can't cover the difference, are left as they are.
+    fn apply_min_fee_rate(&self, result: &mut CreateFundingTxesResult) -> Result<(), WalletError> {
+        for (funding_tx, &payment_pos) in result
+            .funding_txes
+            .iter_mut()
+            .zip(result.payment_output_positions.iter())
+        {
+            let Some(inputs) = funding_tx
+                .input
+                .iter()
+                .map(|input| self.store.utxo_cache.get(&input.previous_output).cloned())
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let input_amount = inputs
+                .iter()
+                .fold(Amount::ZERO, |acc, (utxo, _)| acc + utxo.amount);
+            let output_amount = funding_tx
+                .output
+                .iter()
+                .fold(Amount::ZERO, |acc, txout| acc + txout.value);
+            let actual_fee = input_amount - output_amount;
+            let tx_size = funding_tx.weight().to_vbytes_ceil();
+            let min_fee = Amount::from_sat((self.min_fee_rate * tx_size as f64).ceil() as u64);
+            if actual_fee >= min_fee {
+                continue;
+            }
+
+            let change_output = funding_tx
+                .output
+                .iter_mut()
+                .enumerate()
+                .find(|(vout, _)| *vout != payment_pos as usize)
+                .map(|(_, txout)| txout);
+            let Some(change_output) = change_output else {
+                continue;
+            };
+            let Some(change) = change_output.value.checked_sub(min_fee - actual_fee) else {
+                continue;
+            };
+            log::info!(
+                "Raising the funding tx fee from {} to {} sats, for the minimum fee rate",
+                actual_fee.to_sat(),
+                min_fee.to_sat()
+            );
+            change_output.value = change;
+            result.total_miner_fee += (min_fee - actual_fee).to_sat();
+            funding_tx
+                .input
+                .iter_mut()
+                .for_each(|input| input.witness = Witness::new());
+            self.sign_transaction(
+                funding_tx,
+                inputs.into_iter().map(|(_, spend_info)| spend_info),
+            )?;
+        }
+        Ok(())
+    }
+
     fn generate_amount_fractions_without_correction(
         count: usize,
         total_amount: Amount,
@@ -126,17 +267,21 @@ impl Wallet {
         &self,
         coinswap_amount: Amount,
         destinations: &[Address],
+        change_addresses: &[Address],
         fee_rate: Amount,
+        sequence: Sequence,
+        segwit_only: bool,
     ) -> Result<CreateFundingTxesResult, WalletError> {
         // Lock UTXOs that are not meant for spending (e.g. fidelity coins)
         self.lock_unspendable_utxos()?;
 
-        let change_addresses = self.get_next_internal_addresses(destinations.len() as u32)?;
         let output_values = Wallet::generate_amount_fractions(destinations.len(), coinswap_amount)?;
 
         let mut funding_txes = Vec::<Transaction>::new();
         let mut payment_output_positions = Vec::<u32>::new();
         let mut total_miner_fee = 0;
+        // Coins picked by the previous txs, as the inputs are only locked once the funding is committed.
+        let mut selected_outpoints = HashSet::<OutPoint>::new();
 
         for ((address, &output_value), change_address) in destinations
             .iter()
@@ -151,8 +296,13 @@ impl Wallet {
             let fee = fee_rate;
             let remaining = Amount::from_sat(output_value);
 
-            // Select UTXOs (assume coin_select now filters out already locked UTXOs)
-            let selected_utxo = self.coin_select(remaining)?;
+            // Select UTXOs, not locked by other swaps nor picked for the previous txs.
+            let selected_utxo = self.select_coins(
+                remaining,
+                self.avoid_swap_clustering,
+                segwit_only,
+                &selected_outpoints,
+            )?;
             let total_input_amount =
                 selected_utxo
                     .iter()
@@ -180,15 +330,13 @@ impl Wallet {
                 .iter()
                 .map(|(unspent, _)| TxIn {
                     previous_output: OutPoint::new(unspent.txid, unspent.vout),
-                    sequence: Sequence(0),
+                    sequence,
                     witness: Witness::new(),
                     script_sig: ScriptBuf::new(),
                 })
                 .collect::<Vec<_>>();
 
-            // Set Anti-Fee-Snipping locktime based on current block height
-            let current_height = self.rpc.get_block_count()?;
-            let lock_time = LockTime::from_height(current_height as u32)?;
+            let lock_time = self.anti_fee_sniping_locktime()?;
 
             // Compute the actual fee (difference between inputs and outputs)
             let actual_fee = total_input_amount
@@ -205,14 +353,18 @@ impl Wallet {
                 version: Version::TWO,
             };
 
-            // Sign the transaction with the corresponding UTXO spending info
-            let mut input_info = selected_utxo
-                .iter()
-                .map(|(_, spend_info)| spend_info.clone());
-            self.sign_transaction(&mut funding_tx, &mut input_info)?;
+            // Sign the transaction with the corresponding UTXO spending info, in the final input order.
+            let input_info = self.order_tx(
+                &mut funding_tx,
+                selected_utxo
+                    .iter()
+                    .map(|(_, spend_info)| spend_info.clone())
+                    .collect(),
+            );
+            self.sign_transaction(&mut funding_tx, input_info.into_iter())?;
 
-            // Optionally, log the virtual size and effective fee rate
             let tx_size = funding_tx.weight().to_vbytes_ceil();
+            // Optionally, log the virtual size and effective fee rate
             let effective_feerate = actual_fee.to_sat() as f32 / tx_size as f32;
             log::info!(
                 "Created Funding tx, txid : {} | Feerate: {:.2} sats/vb",
@@ -220,20 +372,17 @@ impl Wallet {
                 effective_feerate
             );
 
-            // Lock the UTXOs used by this funding transaction.
-            self.rpc.lock_unspent(
-                &funding_tx
-                    .input
-                    .iter()
-                    .map(|vin| vin.previous_output)
-                    .collect::<Vec<OutPoint>>(),
-            )?;
+            selected_outpoints.extend(funding_tx.input.iter().map(|vin| vin.previous_output));
 
             // Record this transaction in our results.
-            let payment_pos = 0; // assuming the payment output position is 0
+            let payment_pos = funding_tx
+                .output
+                .iter()
+                .position(|txout| txout.script_pubkey == address.script_pubkey())
+                .expect("payment output expected") as u32;
             funding_txes.push(funding_tx);
             payment_output_positions.push(payment_pos);
-            total_miner_fee += fee_rate.to_sat();
+            total_miner_fee += fee.to_sat();
         }
 
         Ok(CreateFundingTxesResult {
@@ -248,6 +397,7 @@ impl Wallet {
         coinswap_amount: Amount,
         destinations: &[Address],
         fee_rate: Amount,
+        sequence: Sequence,
         change_address: &Address,
         utxos: &mut dyn Iterator<Item = (Txid, u32, u64)>, //utxos item is (txid, vout, value)
                                                            //utxos should be sorted by size, largest first
@@ -260,9 +410,7 @@ impl Wallet {
         let mut destinations_iter = destinations.iter();
         let first_tx_input = utxos.next().unwrap();
 
-        // Set the Anti-Fee-Snipping locktime
-        let current_height = self.rpc.get_block_count()?;
-        let lock_time = LockTime::from_height(current_height as u32)?;
+        let lock_time = self.anti_fee_sniping_locktime()?;
 
         for _ in 0..destinations.len() - 2 {
             let (txid, vout, value) = utxos.next().unwrap();
@@ -271,11 +419,11 @@ impl Wallet {
             outputs.insert(destinations_iter.next().unwrap(), value);
             let tx_inputs = vec![TxIn {
                 previous_output: OutPoint::new(txid, vout),
-                sequence: Sequence(0),
+                sequence,
                 witness: Witness::new(),
                 script_sig: ScriptBuf::new(),
             }];
-            let mut input_info = iter::once(self.get_utxo((txid, vout))?.unwrap());
+            let input_info = vec![self.get_utxo((txid, vout))?.unwrap()];
 
             let mut tx_outs = Vec::new();
             for (address, value) in outputs {
@@ -291,7 +439,8 @@ impl Wallet {
                 lock_time,
                 version: Version::TWO,
             };
-            self.sign_transaction(&mut funding_tx, &mut input_info)?;
+            let input_info = self.order_tx(&mut funding_tx, input_info);
+            self.sign_transaction(&mut funding_tx, input_info.into_iter())?;
 
             leftover_coinswap_amount -= funding_tx.output[0].value;
 
@@ -306,7 +455,7 @@ impl Wallet {
             .map(|(txid, vout, value)| {
                 tx_inputs.push(TxIn {
                     previous_output: OutPoint::new(txid, vout),
-                    sequence: Sequence(0),
+                    sequence,
                     witness: Witness::new(),
                     script_sig: ScriptBuf::new(),
                 });
@@ -340,8 +489,8 @@ impl Wallet {
             lock_time,
             version: Version::TWO,
         };
-        let mut info = input_info.iter().cloned();
-        self.sign_transaction(&mut funding_tx, &mut info)?;
+        let input_info = self.order_tx(&mut funding_tx, input_info);
+        self.sign_transaction(&mut funding_tx, input_info.into_iter())?;
 
         leftover_coinswap_amount -= funding_tx.output[0].value;
 
@@ -351,18 +500,16 @@ impl Wallet {
         payment_output_positions.push(0);
 
         let (first_txid, first_vout, first_value) = first_tx_input;
+        let last_destination = destinations_iter.next().unwrap();
         let mut outputs = HashMap::<&Address, u64>::new();
-        outputs.insert(
-            destinations_iter.next().unwrap(),
-            leftover_coinswap_amount.to_sat(),
-        );
+        outputs.insert(last_destination, leftover_coinswap_amount.to_sat());
 
         tx_inputs = Vec::new();
         tx_outs = Vec::new();
         let mut change_amount = first_value;
         tx_inputs.push(TxIn {
             previous_output: OutPoint::new(first_txid, first_vout),
-            sequence: Sequence(0),
+            sequence,
             witness: Witness::new(),
             script_sig: ScriptBuf::new(),
         });
@@ -383,13 +530,22 @@ impl Wallet {
             lock_time,
             version: Version::TWO,
         };
-        let mut info = iter::once(self.get_utxo((first_txid, first_vout))?.unwrap());
-        self.sign_transaction(&mut funding_tx, &mut info)?;
+        let input_info = self.order_tx(
+            &mut funding_tx,
+            vec![self.get_utxo((first_txid, first_vout))?.unwrap()],
+        );
+        self.sign_transaction(&mut funding_tx, input_info.into_iter())?;
 
         total_miner_fee += fee_rate.to_sat();
 
+        // The change isn't always the last output, as per the wallet's ordering policy.
+        let payment_pos = funding_tx
+            .output
+            .iter()
+            .position(|txout| txout.script_pubkey == last_destination.script_pubkey())
+            .expect("payment output expected") as u32;
         funding_txes.push(funding_tx);
-        payment_output_positions.push(1);
+        payment_output_positions.push(payment_pos);
 
         Ok(CreateFundingTxesResult {
             funding_txes,
@@ -399,10 +555,11 @@ impl Wallet {
     }
 
     fn create_funding_txes_utxo_max_sends(
-        &self,
+        &mut self,
         coinswap_amount: Amount,
         destinations: &[Address],
         fee_rate: Amount,
+        sequence: Sequence,
     ) -> Result<CreateFundingTxesResult, WalletError> {
         //this function creates funding txes by
         //using walletcreatefundedpsbt for the total amount, and if
@@ -444,15 +601,13 @@ impl Wallet {
             .iter()
             .map(|(unspent, _)| TxIn {
                 previous_output: OutPoint::new(unspent.txid, unspent.vout),
-                sequence: Sequence(0),
+                sequence,
                 witness: Witness::new(),
                 script_sig: ScriptBuf::new(),
             })
             .collect::<Vec<_>>();
 
-        // Set the Anti-Fee-Snipping locktime
-        let current_height = self.rpc.get_block_count()?;
-        let lock_time = LockTime::from_height(current_height as u32)?;
+        let lock_time = self.anti_fee_sniping_locktime()?;
 
         let mut funding_tx = Transaction {
             input: tx_inputs,
@@ -477,6 +632,7 @@ impl Wallet {
             coinswap_amount,
             destinations,
             fee_rate,
+            sequence,
             &change_address,
             &mut selected_utxo
                 .iter()
@@ -485,19 +641,22 @@ impl Wallet {
     }
 
     fn create_funding_txes_use_biggest_utxos(
-        &self,
+        &mut self,
         coinswap_amount: Amount,
         destinations: &[Address],
         fee_rate: Amount,
+        sequence: Sequence,
+        segwit_only: bool,
     ) -> Result<CreateFundingTxesResult, WalletError> {
         //this function will pick the top most valuable UTXOs and use them
         //to create funding transactions
 
-        let mut seed_coin_utxo = self.list_descriptor_utxo_spend_info()?;
-        let mut swap_coin_utxo = self.list_swap_coin_utxo_spend_info()?;
-        seed_coin_utxo.append(&mut swap_coin_utxo);
-
-        let mut list_unspent_result = seed_coin_utxo;
+        let mut list_unspent_result = self
+            .list_selectable_coins()?
+            .into_iter()
+            .filter(|(utxo, _)| !segwit_only || utxo.script_pub_key.is_witness_program())
+            .collect::<Vec<_>>();
+        self.check_reserve(&list_unspent_result, coinswap_amount)?;
         if list_unspent_result.len() < destinations.len() {
             return Err(WalletError::General(
                 "Not enough UTXOs to create this many funding txes".to_string(),
@@ -545,6 +704,7 @@ impl Wallet {
                 coinswap_amount,
                 destinations,
                 fee_rate,
+                sequence,
                 change_address,
                 &mut inputs.iter().map(|(list_unspent_entry, _spend_info)| {
                     (
@@ -557,3 +717,20 @@ impl Wallet {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_funding_input_sequence() {
+        let rbf = funding_input_sequence(true);
+        assert!(rbf.is_rbf());
+        assert!(!rbf.is_final());
+
+        let non_rbf = funding_input_sequence(false);
+        assert_eq!(non_rbf, Sequence::ENABLE_LOCKTIME_NO_RBF);
+        assert!(!non_rbf.is_rbf());
+        assert!(non_rbf.enables_absolute_lock_time());
+    }
+}
@@ -0,0 +1,394 @@
+//! Wallet integrity self-check.
+//!
+//! Scans the stored swapcoins against the chain, and reports entries which are orphaned, double-counted
+//! or don't match their scripts. This is a maintenance tool, nothing is modified in the wallet.
+
+use std::collections::HashMap;
+
+use bitcoin::{OutPoint, ScriptBuf, TxOut};
+use bitcoind::bitcoincore_rpc::RpcApi;
+
+use super::{swapcoin::SwapCoin, Wallet, WalletError};
+use crate::utill::redeemscript_to_scriptpubkey;
+
+/// An inconsistency found in the stored swapcoins by [Wallet::check_integrity].
+///
+/// Swapcoins are identified by their multisig redeemscript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// The funding transaction of the swapcoin is not known to the node.
+    OrphanedFunding {
+        /// The multisig redeemscript of the swapcoin.
+        multisig_redeemscript: ScriptBuf,
+        /// The funding outpoint spent by the contract transaction.
+        funding_outpoint: OutPoint,
+    },
+    /// The funding output was spent by a transaction other than the swapcoin's contract.
+    FundingSpent {
+        /// The multisig redeemscript of the swapcoin.
+        multisig_redeemscript: ScriptBuf,
+        /// The spent funding outpoint.
+        funding_outpoint: OutPoint,
+    },
+    /// The same funding output is claimed by more than one stored swapcoin.
+    DoubleCounted {
+        /// The shared funding outpoint.
+        funding_outpoint: OutPoint,
+        /// The multisig redeemscripts of all the swapcoins claiming it.
+        multisig_redeemscripts: Vec<ScriptBuf>,
+    },
+    /// The swapcoin's keys, contract or funding output don't match its stored scripts.
+    ScriptMismatch {
+        /// The multisig redeemscript the swapcoin is stored under.
+        multisig_redeemscript: ScriptBuf,
+        /// What doesn't match.
+        reason: &'static str,
+    },
+}
+
+/// The on-chain state of a swapcoin's funding output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FundingStatus {
+    /// The funding output is unspent.
+    Unspent(TxOut),
+    /// The funding output is spent by the swapcoin's contract transaction.
+    SpentByContract,
+    /// The funding output is spent by some other transaction.
+    Spent,
+    /// The funding transaction is not known.
+    Missing,
+}
+
+impl Wallet {
+    /// Checks the stored swapcoins against the chain, and reports all the inconsistencies found.
+    ///
+    /// Swapcoins whose funding transaction is unknown, whose funding was spent outside of their contract,
+    /// which share a funding output with another swapcoin, or whose scripts don't match are reported.
+    /// The wallet is not modified.
+    pub fn check_integrity(&self) -> Result<Vec<IntegrityIssue>, WalletError> {
+        let swapcoins = self
+            .store
+            .incoming_swapcoins
+            .iter()
+            .map(|(rs, coin)| (rs, coin as &dyn SwapCoin))
+            .chain(
+                self.store
+                    .outgoing_swapcoins
+                    .iter()
+                    .map(|(rs, coin)| (rs, coin as &dyn SwapCoin)),
+            )
+            .collect::<Vec<_>>();
+
+        let issues = find_integrity_issues(&swapcoins, |funding_outpoint, coin| {
+            self.funding_status(funding_outpoint, coin)
+        })?;
+        for issue in &issues {
+            log::warn!("Wallet integrity issue: {:?}", issue);
+        }
+        Ok(issues)
+    }
+
+    /// Looks up the funding output of a swapcoin on the chain, including the mempool.
+    fn funding_status(
+        &self,
+        funding_outpoint: &OutPoint,
+        coin: &dyn SwapCoin,
+    ) -> Result<FundingStatus, WalletError> {
+        if let Some(txout) =
+            self.rpc
+                .get_tx_out(&funding_outpoint.txid, funding_outpoint.vout, Some(true))?
+        {
+            return Ok(FundingStatus::Unspent(TxOut {
+                value: txout.value,
+                script_pubkey: ScriptBuf::from(txout.script_pub_key.hex),
+            }));
+        }
+
+        let contract_txid = coin.get_contract_tx().compute_txid();
+        if self
+            .rpc
+            .get_raw_transaction_info(&contract_txid, None)
+            .is_ok()
+        {
+            return Ok(FundingStatus::SpentByContract);
+        }
+
+        let is_funding_known = self
+            .rpc
+            .get_transaction(&funding_outpoint.txid, Some(true))
+            .is_ok()
+            || self
+                .rpc
+                .get_raw_transaction_info(&funding_outpoint.txid, None)
+                .is_ok();
+        if is_funding_known {
+            Ok(FundingStatus::Spent)
+        } else {
+            Ok(FundingStatus::Missing)
+        }
+    }
+}
+
+/// Finds the integrity issues of the swapcoins, stored by their multisig redeemscripts.
+///
+/// The on-chain state of each funding output is provided by `funding_status`.
+pub(crate) fn find_integrity_issues<F>(
+    swapcoins: &[(&ScriptBuf, &dyn SwapCoin)],
+    mut funding_status: F,
+) -> Result<Vec<IntegrityIssue>, WalletError>
+where
+    F: FnMut(&OutPoint, &dyn SwapCoin) -> Result<FundingStatus, WalletError>,
+{
+    let mut issues = Vec::new();
+    let mut funding_claims = HashMap::<OutPoint, Vec<ScriptBuf>>::new();
+
+    for (stored_rs, coin) in swapcoins {
+        let multisig_redeemscript = (*stored_rs).clone();
+        let mismatch = |reason| IntegrityIssue::ScriptMismatch {
+            multisig_redeemscript: multisig_redeemscript.clone(),
+            reason,
+        };
+
+        if coin.get_multisig_redeemscript() != multisig_redeemscript {
+            issues.push(mismatch(
+                "multisig redeemscript doesn't match the swapcoin keys",
+            ));
+        }
+
+        let contract_tx = coin.get_contract_tx();
+        let contract_spk = redeemscript_to_scriptpubkey(&coin.get_contract_redeemscript())?;
+        if contract_tx.output.first().map(|o| &o.script_pubkey) != Some(&contract_spk) {
+            issues.push(mismatch(
+                "contract tx doesn't pay to the contract redeemscript",
+            ));
+        }
+
+        let funding_outpoint = match contract_tx.input.as_slice() {
+            [input] => input.previous_output,
+            _ => {
+                issues.push(mismatch(
+                    "contract tx doesn't spend a single funding output",
+                ));
+                continue;
+            }
+        };
+        funding_claims
+            .entry(funding_outpoint)
+            .or_default()
+            .push(multisig_redeemscript.clone());
+
+        match funding_status(&funding_outpoint, *coin)? {
+            FundingStatus::Unspent(txout) => {
+                if txout.script_pubkey != redeemscript_to_scriptpubkey(&multisig_redeemscript)? {
+                    issues.push(mismatch(
+                        "funding output doesn't pay to the multisig redeemscript",
+                    ));
+                }
+                if txout.value != coin.get_funding_amount() {
+                    issues.push(mismatch("funding output amount doesn't match"));
+                }
+            }
+            FundingStatus::SpentByContract => {}
+            FundingStatus::Spent => issues.push(IntegrityIssue::FundingSpent {
+                multisig_redeemscript,
+                funding_outpoint,
+            }),
+            FundingStatus::Missing => issues.push(IntegrityIssue::OrphanedFunding {
+                multisig_redeemscript,
+                funding_outpoint,
+            }),
+        }
+    }
+
+    issues.extend(
+        funding_claims
+            .into_iter()
+            .filter(|(_, claims)| claims.len() > 1)
+            .map(
+                |(funding_outpoint, multisig_redeemscripts)| IntegrityIssue::DoubleCounted {
+                    funding_outpoint,
+                    multisig_redeemscripts,
+                },
+            ),
+    );
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        protocol::contract::create_multisig_redeemscript,
+        wallet::{IncomingSwapCoin, OutgoingSwapCoin},
+    };
+    use bitcoin::{
+        absolute::LockTime,
+        hashes::Hash,
+        secp256k1::{Secp256k1, SecretKey},
+        transaction::Version,
+        Amount, PublicKey, Sequence, Transaction, TxIn, Txid, Witness,
+    };
+
+    fn contract_tx(funding_outpoint: OutPoint, contract_redeemscript: &ScriptBuf) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: funding_outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ZERO,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(99_000),
+                script_pubkey: redeemscript_to_scriptpubkey(contract_redeemscript).unwrap(),
+            }],
+        }
+    }
+
+    fn keys(seed: u8) -> (SecretKey, PublicKey) {
+        let secp = Secp256k1::new();
+        let privkey = SecretKey::from_slice(&[seed; 32]).unwrap();
+        let pubkey = PublicKey {
+            compressed: true,
+            inner: privkey.public_key(&secp),
+        };
+        (privkey, pubkey)
+    }
+
+    fn incoming_swapcoin(seed: u8, funding_outpoint: OutPoint) -> IncomingSwapCoin {
+        let (my_privkey, _) = keys(seed);
+        let (_, other_pubkey) = keys(seed + 1);
+        let contract_redeemscript = ScriptBuf::from(vec![seed; 10]);
+        IncomingSwapCoin {
+            my_privkey,
+            other_pubkey,
+            other_privkey: None,
+            contract_tx: contract_tx(funding_outpoint, &contract_redeemscript),
+            contract_redeemscript,
+            hashlock_privkey: my_privkey,
+            funding_amount: Amount::from_sat(100_000),
+            others_contract_sig: None,
+            others_escalated_contract_sigs: Vec::new(),
+            hash_preimage: None,
+        }
+    }
+
+    fn outgoing_swapcoin(seed: u8, funding_outpoint: OutPoint) -> OutgoingSwapCoin {
+        let incoming = incoming_swapcoin(seed, funding_outpoint);
+        OutgoingSwapCoin {
+            my_privkey: incoming.my_privkey,
+            other_pubkey: incoming.other_pubkey,
+            contract_tx: incoming.contract_tx,
+            contract_redeemscript: incoming.contract_redeemscript,
+            timelock_privkey: incoming.my_privkey,
+            funding_amount: incoming.funding_amount,
+            others_contract_sig: None,
+            others_escalated_contract_sigs: Vec::new(),
+            hash_preimage: None,
+        }
+    }
+
+    fn outpoint(n: u8) -> OutPoint {
+        OutPoint {
+            txid: Txid::from_byte_array([n; 32]),
+            vout: 0,
+        }
+    }
+
+    #[test]
+    fn test_integrity_check() {
+        let healthy = incoming_swapcoin(1, outpoint(1));
+        let orphaned = incoming_swapcoin(3, outpoint(3));
+        let swept = incoming_swapcoin(5, outpoint(5));
+        let broadcasted = outgoing_swapcoin(7, outpoint(7));
+        let duplicate = outgoing_swapcoin(9, outpoint(1));
+        let mut bad_contract = outgoing_swapcoin(11, outpoint(11));
+        bad_contract.contract_tx.output[0].script_pubkey = ScriptBuf::new();
+
+        let healthy_rs = healthy.get_multisig_redeemscript();
+        // Stored under another swapcoin's redeemscript.
+        let (_, other_pubkey) = keys(42);
+        let misfiled_rs = create_multisig_redeemscript(&other_pubkey, &keys(43).1);
+
+        let stored = [
+            (healthy_rs.clone(), &healthy as &dyn SwapCoin),
+            (orphaned.get_multisig_redeemscript(), &orphaned),
+            (swept.get_multisig_redeemscript(), &swept),
+            (broadcasted.get_multisig_redeemscript(), &broadcasted),
+            (duplicate.get_multisig_redeemscript(), &duplicate),
+            (bad_contract.get_multisig_redeemscript(), &bad_contract),
+        ];
+        let mut swapcoins = stored
+            .iter()
+            .map(|(rs, coin)| (rs, *coin))
+            .collect::<Vec<_>>();
+        let misfiled = outgoing_swapcoin(13, outpoint(13));
+        swapcoins.push((&misfiled_rs, &misfiled));
+
+        // All other funding outputs are unspent, and pay to the swapcoin's multisig.
+        let issues = find_integrity_issues(&swapcoins, |funding_outpoint, coin| {
+            Ok(match funding_outpoint.txid.to_byte_array()[0] {
+                3 => FundingStatus::Missing,
+                5 => FundingStatus::Spent,
+                7 => FundingStatus::SpentByContract,
+                _ => FundingStatus::Unspent(TxOut {
+                    value: coin.get_funding_amount(),
+                    script_pubkey: redeemscript_to_scriptpubkey(&coin.get_multisig_redeemscript())
+                        .unwrap(),
+                }),
+            })
+        })
+        .unwrap();
+
+        assert!(issues.contains(&IntegrityIssue::OrphanedFunding {
+            multisig_redeemscript: orphaned.get_multisig_redeemscript(),
+            funding_outpoint: outpoint(3),
+        }));
+        assert!(issues.contains(&IntegrityIssue::FundingSpent {
+            multisig_redeemscript: swept.get_multisig_redeemscript(),
+            funding_outpoint: outpoint(5),
+        }));
+        assert!(issues.contains(&IntegrityIssue::DoubleCounted {
+            funding_outpoint: outpoint(1),
+            multisig_redeemscripts: vec![healthy_rs.clone(), duplicate.get_multisig_redeemscript()],
+        }));
+        assert!(issues.contains(&IntegrityIssue::ScriptMismatch {
+            multisig_redeemscript: bad_contract.get_multisig_redeemscript(),
+            reason: "contract tx doesn't pay to the contract redeemscript",
+        }));
+        assert!(issues.contains(&IntegrityIssue::ScriptMismatch {
+            multisig_redeemscript: misfiled_rs.clone(),
+            reason: "multisig redeemscript doesn't match the swapcoin keys",
+        }));
+        assert!(issues.contains(&IntegrityIssue::ScriptMismatch {
+            multisig_redeemscript: misfiled_rs.clone(),
+            reason: "funding output doesn't pay to the multisig redeemscript",
+        }));
+
+        // Nothing else is reported. The healthy swapcoin and the broadcasted contract are fine.
+        let duplicate_rs = duplicate.get_multisig_redeemscript();
+        let flagged = |rs: &ScriptBuf| {
+            issues.iter().any(|issue| match issue {
+                IntegrityIssue::OrphanedFunding {
+                    multisig_redeemscript,
+                    ..
+                }
+                | IntegrityIssue::FundingSpent {
+                    multisig_redeemscript,
+                    ..
+                }
+                | IntegrityIssue::ScriptMismatch {
+                    multisig_redeemscript,
+                    ..
+                } => multisig_redeemscript == rs,
+                IntegrityIssue::DoubleCounted { .. } => false,
+            })
+        };
+        assert!(!flagged(&healthy_rs));
+        assert!(!flagged(&duplicate_rs));
+        assert!(!flagged(&broadcasted.get_multisig_redeemscript()));
+        assert_eq!(issues.len(), 6);
+    }
+}
@@ -4,16 +4,22 @@ mod api;
 mod error;
 mod fidelity;
 mod funding;
+mod integrity;
 mod rpc;
 mod spend;
 mod storage;
 mod swapcoin;
 
+pub use api::{AddressStrategy, LabelTarget, RecoverableOutput};
 pub(crate) use api::{Balances, UTXOSpendInfo, Wallet};
 pub use error::WalletError;
-pub(crate) use fidelity::{fidelity_redeemscript, FidelityBond, FidelityError};
-pub use rpc::RPCConfig;
-pub use spend::Destination;
+pub(crate) use fidelity::{
+    fidelity_redeemscript, verify_identity_proof, FidelityBond, FidelityError,
+};
+pub(crate) use funding::{funding_input_sequence, PreparedCoinswap};
+pub use integrity::IntegrityIssue;
+pub use rpc::{RPCConfig, SyncProgress, RPC_PASSWORD_ENV, RPC_URL_ENV, RPC_USER_ENV};
+pub use spend::{AntiFeeSniping, Destination, TxOrdering};
 pub(crate) use swapcoin::{
     IncomingSwapCoin, OutgoingSwapCoin, SwapCoin, WalletSwapCoin, WatchOnlySwapCoin,
 };
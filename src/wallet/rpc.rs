@@ -1,11 +1,21 @@
 //! Manages connection with a Bitcoin Core RPC.
 //!
-use std::{convert::TryFrom, thread};
+use std::{
+    convert::TryFrom,
+    env,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    thread,
+};
 
+use bitcoin::Txid;
 use bitcoind::bitcoincore_rpc::{Auth, Client, RpcApi};
 use serde_json::{json, Value};
 
-use crate::{utill::HEART_BEAT_INTERVAL, wallet::api::KeychainKind};
+use crate::{
+    utill::{parse_toml, HEART_BEAT_INTERVAL},
+    wallet::api::KeychainKind,
+};
 
 use serde::Deserialize;
 
@@ -24,6 +34,30 @@ pub struct RPCConfig {
 
 const RPC_HOSTPORT: &str = "localhost:18443";
 
+/// Environment variable overriding the RPC `host:port` address.
+pub const RPC_URL_ENV: &str = "COINSWAP_RPC_URL";
+/// Environment variable overriding the RPC user name.
+pub const RPC_USER_ENV: &str = "COINSWAP_RPC_USER";
+/// Environment variable overriding the RPC password.
+pub const RPC_PASSWORD_ENV: &str = "COINSWAP_RPC_PASSWORD";
+
+/// Number of blocks rescanned in a single sync batch.
+#[cfg(feature = "integration-test")]
+pub(crate) const SYNC_BATCH_SIZE: u64 = 50;
+#[cfg(not(feature = "integration-test"))]
+pub(crate) const SYNC_BATCH_SIZE: u64 = 2016; // ~2 weeks of blocks
+
+/// Progress report of an ongoing wallet sync, emitted after every rescanned batch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncProgress {
+    /// Height up to which the blockchain has been rescanned.
+    pub current_height: u64,
+    /// Height of the node's chain tip at the start of the sync.
+    pub target_height: u64,
+    /// Percentage of the sync range completed.
+    pub percentage: f64,
+}
+
 impl Default for RPCConfig {
     fn default() -> Self {
         Self {
@@ -34,6 +68,72 @@ impl Default for RPCConfig {
     }
 }
 
+impl RPCConfig {
+    /// Loads the RPC connection details from a `bitcoin.conf` style file.
+    ///
+    /// Reads `rpcuser`, `rpcpassword`, `rpcconnect` and `rpcport`. Missing address fields fall back
+    /// to the defaults. Section headers are ignored, so the last value of a key wins.
+    /// The result is then overridden by the [RPC_URL_ENV], [RPC_USER_ENV] and [RPC_PASSWORD_ENV]
+    /// environment variables, if they are set.
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> Result<Self, WalletError> {
+        let config_map = parse_toml(path)?;
+        let (default_host, default_port) = RPC_HOSTPORT.split_once(':').expect("valid default");
+
+        let host = config_map
+            .get("rpcconnect")
+            .map_or(default_host, String::as_str);
+        let port = config_map
+            .get("rpcport")
+            .map_or(default_port, String::as_str);
+
+        let auth = match (config_map.get("rpcuser"), config_map.get("rpcpassword")) {
+            (Some(user), Some(password)) => Auth::UserPass(user.clone(), password.clone()),
+            _ => Auth::None,
+        };
+
+        let config = RPCConfig {
+            url: format!("{}:{}", host, port),
+            auth,
+            ..Default::default()
+        }
+        .with_env_overrides();
+
+        if let Auth::None = config.auth {
+            return Err(WalletError::General(
+                "RPC config file is missing rpcuser/rpcpassword".to_string(),
+            ));
+        }
+
+        Ok(config)
+    }
+
+    /// Applies the [RPC_URL_ENV], [RPC_USER_ENV] and [RPC_PASSWORD_ENV] environment variables over this config.
+    pub fn with_env_overrides(self) -> Self {
+        self.with_overrides(|key| env::var(key).ok())
+    }
+
+    fn with_overrides<F: Fn(&str) -> Option<String>>(mut self, lookup: F) -> Self {
+        if let Some(url) = lookup(RPC_URL_ENV) {
+            self.url = url;
+        }
+
+        let user = lookup(RPC_USER_ENV);
+        let password = lookup(RPC_PASSWORD_ENV);
+        if user.is_some() || password.is_some() {
+            let (current_user, current_password) = match &self.auth {
+                Auth::UserPass(user, password) => (user.clone(), password.clone()),
+                _ => (String::new(), String::new()),
+            };
+            self.auth = Auth::UserPass(
+                user.unwrap_or(current_user),
+                password.unwrap_or(current_password),
+            );
+        }
+
+        self
+    }
+}
+
 impl TryFrom<&RPCConfig> for Client {
     type Error = WalletError;
     fn try_from(config: &RPCConfig) -> Result<Self, WalletError> {
@@ -50,6 +150,28 @@ impl TryFrom<&RPCConfig> for Client {
     }
 }
 
+/// Checks that the node maintains a transaction index, or fails with [WalletError::TxindexRequired].
+pub(crate) fn check_txindex(client: &Client) -> Result<(), WalletError> {
+    let index_info: Value = client.call("getindexinfo", &[json!("txindex")])?;
+    match index_info.get("txindex") {
+        None => {
+            log::error!(
+                "Bitcoin Core runs without a transaction index. Restart it with `-txindex=1`, or set `txindex=1` in bitcoin.conf"
+            );
+            Err(WalletError::TxindexRequired)
+        }
+        Some(txindex) => {
+            if txindex["synced"] == false {
+                log::warn!(
+                    "Bitcoin Core is still building the transaction index, at height {}",
+                    txindex["best_block_height"]
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
 fn list_wallet_dir(client: &Client) -> Result<Vec<String>, WalletError> {
     #[derive(Deserialize)]
     struct Name {
@@ -67,13 +189,102 @@ fn list_wallet_dir(client: &Client) -> Result<Vec<String>, WalletError> {
 impl Wallet {
     /// Sync the wallet with the configured Bitcoin Core RPC. Save data to disk.
     pub fn sync(&mut self) -> Result<(), WalletError> {
-        // Create or load the watch-only bitcoin core wallet
+        self.sync_with_progress(|_| {})
+    }
+
+    /// Sync the wallet, rescanning the blockchain in batches of [`SYNC_BATCH_SIZE`] blocks.
+    ///
+    /// `progress` is called after every batch. The last synced height is checkpointed to disk
+    /// after each batch, so an interrupted sync resumes from where it stopped.
+    ///
+    /// Inside a [`Wallet::batch`], the sync is deferred to the end of the batch, and `progress` isn't called.
+    pub fn sync_with_progress<F: FnMut(SyncProgress)>(
+        &mut self,
+        mut progress: F,
+    ) -> Result<(), WalletError> {
+        if let Some(pending) = &mut self.deferred_sync {
+            log::debug!("Wallet sync deferred to the end of the batch");
+            *pending = true;
+            return Ok(());
+        }
+
+        self.load_core_wallet()?;
+
+        let descriptors_to_import = self.descriptors_to_import()?;
+
+        if descriptors_to_import.is_empty() {
+            return Ok(());
+        }
+
+        log::debug!("Importing Wallet spks/descriptors");
+
+        self.import_descriptors(&descriptors_to_import, None)?;
+
+        // Now run the scan
+        log::debug!("Initializing TxOut scan. This may take a while.");
+
+        let start_height = self
+            .store
+            .last_synced_height
+            .unwrap_or(0)
+            .max(self.store.wallet_birthday.unwrap_or(0));
+        let target_height = self.rpc.get_block_count()?;
+        log::debug!(
+            "Re-scanning Blockchain from:{} to:{}",
+            start_height,
+            target_height
+        );
+
+        let mut batch_start = start_height;
+        while batch_start <= target_height {
+            let batch_end = (batch_start + SYNC_BATCH_SIZE - 1).min(target_height);
+
+            // Sometimes in test multiple wallet scans can occur at same time, resulting in error.
+            // Just retry after 3 sec.
+            while let Err(e) = self
+                .rpc
+                .rescan_blockchain(Some(batch_start as usize), Some(batch_end as usize))
+            {
+                log::warn!("Sync Error, Retrying: {}", e);
+                thread::sleep(HEART_BEAT_INTERVAL);
+            }
+
+            // Checkpoint the progress, so an interrupted sync can resume from here.
+            self.store.last_synced_height = Some(batch_end);
+            self.save_to_disk()?;
+
+            let scanned = batch_end - start_height + 1;
+            let total = target_height - start_height + 1;
+            progress(SyncProgress {
+                current_height: batch_end,
+                target_height,
+                percentage: scanned as f64 * 100.0 / total as f64,
+            });
+
+            batch_start = batch_end + 1;
+        }
+
+        let utxos = self.get_all_utxo()?;
+        self.track_coinbase_maturity(&utxos, target_height)?;
+        self.update_utxo_cache(utxos);
+
+        let max_external_index = self.find_hd_next_index(KeychainKind::External)?;
+        self.update_external_index(max_external_index)?;
+        self.refresh_offer_maxsize_cache()?;
+        Ok(())
+    }
+
+    /// Loads the watch-only Core wallet backing this wallet, creating a blank one if it doesn't exist.
+    /// Returns `true` if it had to be created.
+    pub(crate) fn load_core_wallet(&self) -> Result<bool, WalletError> {
         let wallet_name = &self.store.file_name;
-        if self.rpc.list_wallets()?.contains(wallet_name) {
+        let created = if self.rpc.list_wallets()?.contains(wallet_name) {
             log::debug!("wallet already loaded: {}", wallet_name);
+            false
         } else if list_wallet_dir(&self.rpc)?.contains(wallet_name) {
             self.rpc.load_wallet(wallet_name)?;
             log::debug!("wallet loaded: {}", wallet_name);
+            false
         } else {
             // pre-0.21 use legacy wallets
             if self.rpc.version()? < 210_000 {
@@ -93,58 +304,99 @@ impl Wallet {
             }
 
             log::debug!("wallet created: {}", wallet_name);
-        }
-
-        let descriptors_to_import = self.descriptors_to_import()?;
+            true
+        };
+        Ok(created)
+    }
 
-        if descriptors_to_import.is_empty() {
-            return Ok(());
+    /// Re-imports the multisig and contract scripts of all stored swapcoins, that are missing
+    /// in the backing Core wallet. Returns the number of imported scripts.
+    ///
+    /// Scripts are checked first, so a reload with nothing missing doesn't slow the startup.
+    /// Missing ones are imported with a rescan from the wallet birthday, so the outputs already
+    /// on chain are seen at the next sync.
+    pub(crate) fn reimport_swapcoin_scripts(&self) -> Result<usize, WalletError> {
+        let mut missing = Vec::new();
+        for descriptor in self.swapcoin_descriptors()? {
+            let address = self.rpc.derive_addresses(&descriptor, None)?[0]
+                .clone()
+                .assume_checked();
+            if !self
+                .rpc
+                .get_address_info(&address)?
+                .is_watchonly
+                .unwrap_or(false)
+            {
+                missing.push(descriptor);
+            }
+        }
+        if missing.is_empty() {
+            return Ok(0);
         }
 
-        log::debug!("Importing Wallet spks/descriptors");
+        let timestamp = match self.store.wallet_birthday {
+            Some(height) => {
+                let block_hash = self.rpc.get_block_hash(height)?;
+                json!(self.rpc.get_block_header_info(&block_hash)?.time)
+            }
+            None => json!(0),
+        };
+        log::info!(
+            "Re-importing {} missing swapcoin scripts into the Core wallet",
+            missing.len()
+        );
+        self.import_descriptors_from(&missing, None, timestamp)?;
+        Ok(missing.len())
+    }
 
-        self.import_descriptors(&descriptors_to_import, None)?;
+    /// Returns the confirmations of a transaction, or `None` while it's unconfirmed in the mempool.
+    ///
+    /// Wallet transactions, like our funding txs and the contract txs spending our swapcoins, are looked
+    /// up with `gettransaction`, which doesn't need the transaction index. Other transactions, like the
+    /// contract txs of counterparties, fall back to `getrawtransaction`. Errors if the node doesn't know
+    /// the transaction.
+    pub fn get_tx_confirmations(&self, txid: &Txid) -> Result<Option<u32>, WalletError> {
+        // Unconfirmed or conflicted wallet txs may be out of the mempool, so only trust confirmed ones.
+        if let Ok(tx) = self.rpc.get_transaction(txid, Some(true)) {
+            if tx.info.confirmations > 0 {
+                return Ok(Some(tx.info.confirmations as u32));
+            }
+        }
+        Ok(self.rpc.get_raw_transaction_info(txid, None)?.confirmations)
+    }
 
-        // Now run the scan
-        log::debug!("Initializing TxOut scan. This may take a while.");
+    /// Runs `f` with the syncs requested inside it coalesced into a single sync, done once `f` returns.
+    ///
+    /// Avoids a rescan per step of bulk operations. The wallet is synced even if `f` fails, in which case
+    /// the error of `f` is returned. Nested batches are part of the outermost one.
+    ///
+    /// Reads inside `f` see the wallet as of before the batch: a sync requested in `f` returns right
+    /// away, without updating the UTXO cache or the balances. Only batch steps that don't rely on a
+    /// previous step's sync. If `f` panics, the batch is ended without syncing.
+    pub fn batch<T, E, F>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Self) -> Result<T, E>,
+        E: From<WalletError>,
+    {
+        if self.deferred_sync.is_some() {
+            return f(self);
+        }
 
-        // Sometimes in test multiple wallet scans can occur at same time, resulting in error.
-        // Just retry after 3 sec.
-        loop {
-            let last_synced_height = self
-                .store
-                .last_synced_height
-                .unwrap_or(0)
-                .max(self.store.wallet_birthday.unwrap_or(0));
-            let node_synced = self.rpc.get_block_count()?;
-            log::debug!(
-                "Re-scanning Blockchain from:{} to:{}",
-                last_synced_height,
-                node_synced
-            );
-            match self.rpc.rescan_blockchain(
-                Some(last_synced_height as usize),
-                Some(node_synced as usize),
-            ) {
-                Ok(_) => {
-                    self.store.last_synced_height = Some(node_synced);
-                    break;
-                }
+        self.deferred_sync = Some(false);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+        let sync_requested = self.deferred_sync.take() == Some(true);
+        let result = result.unwrap_or_else(|payload| panic::resume_unwind(payload));
 
-                Err(e) => {
-                    log::warn!("Sync Error, Retrying: {}", e);
-                    thread::sleep(HEART_BEAT_INTERVAL);
-                    continue;
+        if sync_requested {
+            match (&result, self.sync()) {
+                (Ok(_), Err(e)) => return Err(e.into()),
+                (Err(_), Err(e)) => {
+                    log::error!("Wallet sync after a failed batch failed | {:?}", e)
                 }
+                _ => {}
             }
         }
-
-        self.update_utxo_cache(self.get_all_utxo()?);
-
-        let max_external_index = self.find_hd_next_index(KeychainKind::External)?;
-        self.update_external_index(max_external_index)?;
-        self.refresh_offer_maxsize_cache()?;
-        Ok(())
+        result
     }
 
     /// Keep retrying sync until success and log failure.
@@ -160,6 +412,16 @@ impl Wallet {
         &self,
         descriptors_to_import: &[String],
         address_label: Option<String>,
+    ) -> Result<(), WalletError> {
+        self.import_descriptors_from(descriptors_to_import, address_label, json!("now"))
+    }
+
+    /// Import watch addresses into core wallet, rescanning the blocks since `timestamp`.
+    fn import_descriptors_from(
+        &self,
+        descriptors_to_import: &[String],
+        address_label: Option<String>,
+        timestamp: Value,
     ) -> Result<(), WalletError> {
         let address_label = address_label.unwrap_or(self.get_core_wallet_label());
 
@@ -168,13 +430,13 @@ impl Wallet {
             .map(|desc| {
                 if desc.contains("/*") {
                     return json!({
-                        "timestamp": "now",
+                        "timestamp": timestamp,
                         "desc": desc,
                         "range": (self.get_addrss_import_count() - 1)
                     });
                 }
                 json!({
-                    "timestamp": "now",
+                    "timestamp": timestamp,
                     "desc": desc,
                     "label": address_label
                 })
@@ -184,3 +446,39 @@ impl Wallet {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_rpc_config_file() {
+        let path = env::temp_dir().join("coinswap_rpc_config_test.conf");
+        fs::write(
+            &path,
+            "# credentials\nrpcuser=alice\nrpcpassword=secret\n[regtest]\nrpcport=18999\n",
+        )
+        .unwrap();
+        let config = RPCConfig::from_config_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.url, "localhost:18999");
+        assert_eq!(
+            config.auth,
+            Auth::UserPass("alice".to_string(), "secret".to_string())
+        );
+
+        // Environment overrides win over the file.
+        let config = config.with_overrides(|key| match key {
+            RPC_URL_ENV => Some("10.0.0.1:8332".to_string()),
+            RPC_PASSWORD_ENV => Some("hunter2".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.url, "10.0.0.1:8332");
+        assert_eq!(
+            config.auth,
+            Auth::UserPass("alice".to_string(), "hunter2".to_string())
+        );
+    }
+}
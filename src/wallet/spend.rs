@@ -4,15 +4,30 @@
 //! direct sends. It leverages Bitcoin Core's RPC for wallet synchronization and implements various
 //! parsing mechanisms for transaction inputs and outputs.
 
+use std::{fmt, str::FromStr};
+
 use bitcoin::{
-    absolute::LockTime, transaction::Version, Address, Amount, OutPoint, ScriptBuf, Sequence,
-    Transaction, TxIn, TxOut, Witness,
+    absolute::LockTime,
+    hashes::Hash,
+    secp256k1::rand::{seq::SliceRandom, thread_rng, Rng},
+    transaction::Version,
+    Address, Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+};
+use bitcoind::bitcoincore_rpc::{
+    json::{Bip125Replaceable, ListUnspentResultEntry},
+    RawTx, RpcApi,
 };
-use bitcoind::bitcoincore_rpc::{json::ListUnspentResultEntry, RawTx, RpcApi};
 
-use crate::wallet::{api::UTXOSpendInfo, FidelityError};
+use crate::wallet::{
+    api::{KeychainKind, UTXOSpendInfo},
+    FidelityError,
+};
 
-use super::{error::WalletError, swapcoin::SwapCoin, IncomingSwapCoin, OutgoingSwapCoin, Wallet};
+use super::{
+    error::WalletError,
+    swapcoin::{SwapCoin, WalletSwapCoin},
+    IncomingSwapCoin, OutgoingSwapCoin, Wallet,
+};
 
 /// Represents different destination options for a transaction.
 #[derive(Debug, Clone, PartialEq)]
@@ -23,7 +38,166 @@ pub enum Destination {
     Multi(Vec<(Address, Amount)>),
 }
 
+// Like Bitcoin Core, 1 in 10 txs get their anti-fee-sniping locktime set up to 100 blocks back,
+// so txs that were delayed in broadcast don't stand out.
+const LOCKTIME_BACKOFF_CHANCE: f64 = 0.1;
+const LOCKTIME_MAX_BACKOFF: u32 = 100;
+
+/// nLockTime policy for funding and regular spend transactions.
+///
+/// Setting the nLockTime to the current height discourages fee sniping, and makes the txs blend in
+/// with the ones created by common wallets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AntiFeeSniping {
+    /// Leave the nLockTime at zero.
+    Off,
+    /// Set the nLockTime to the current tip height.
+    Tip,
+    /// Set the nLockTime to the current tip height, occasionally backed off by a random amount, like Bitcoin Core does.
+    #[default]
+    TipWithBackoff,
+}
+
+impl FromStr for AntiFeeSniping {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(AntiFeeSniping::Off),
+            "tip" => Ok(AntiFeeSniping::Tip),
+            "tip-with-backoff" => Ok(AntiFeeSniping::TipWithBackoff),
+            _ => Err("Invalid anti fee sniping policy"),
+        }
+    }
+}
+
+impl fmt::Display for AntiFeeSniping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AntiFeeSniping::Off => write!(f, "off"),
+            AntiFeeSniping::Tip => write!(f, "tip"),
+            AntiFeeSniping::TipWithBackoff => write!(f, "tip-with-backoff"),
+        }
+    }
+}
+
+/// Ordering of the inputs and outputs of funding and regular spend transactions.
+///
+/// Always putting e.g. the change last is a fingerprint. The order is applied before signing,
+/// and the position of an output is always looked up by its scriptpubkey afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TxOrdering {
+    /// Keep the order in which the inputs and outputs were added.
+    Insertion,
+    /// Sort lexicographically, as per BIP69.
+    Bip69,
+    /// Shuffle the inputs and outputs randomly.
+    #[default]
+    Random,
+}
+
+impl FromStr for TxOrdering {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "insertion" => Ok(TxOrdering::Insertion),
+            "bip69" => Ok(TxOrdering::Bip69),
+            "random" => Ok(TxOrdering::Random),
+            _ => Err("Invalid tx ordering"),
+        }
+    }
+}
+
+impl fmt::Display for TxOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxOrdering::Insertion => write!(f, "insertion"),
+            TxOrdering::Bip69 => write!(f, "bip69"),
+            TxOrdering::Random => write!(f, "random"),
+        }
+    }
+}
+
+/// Orders the inputs and outputs of an unsigned tx as per the [TxOrdering] policy.
+///
+/// The spend infos are given and returned in the order of the tx inputs, so they stay paired with them.
+fn order_tx<R: Rng>(
+    ordering: TxOrdering,
+    tx: &mut Transaction,
+    spend_infos: Vec<UTXOSpendInfo>,
+    rng: &mut R,
+) -> Vec<UTXOSpendInfo> {
+    let mut inputs = tx.input.drain(..).zip(spend_infos).collect::<Vec<_>>();
+    match ordering {
+        TxOrdering::Insertion => {}
+        TxOrdering::Bip69 => {
+            // Txids are compared in their displayed (reversed) byte order.
+            inputs.sort_by_key(|(txin, _)| {
+                let mut txid = txin.previous_output.txid.to_byte_array();
+                txid.reverse();
+                (txid, txin.previous_output.vout)
+            });
+            tx.output
+                .sort_by(|a, b| (a.value, &a.script_pubkey).cmp(&(b.value, &b.script_pubkey)));
+        }
+        TxOrdering::Random => {
+            inputs.shuffle(rng);
+            tx.output.shuffle(rng);
+        }
+    }
+    let (txins, spend_infos) = inputs.into_iter().unzip();
+    tx.input = txins;
+    spend_infos
+}
+
+/// Picks the nLockTime for a new tx at the given tip height, as per the [AntiFeeSniping] policy.
+fn anti_fee_sniping_locktime<R: Rng>(
+    policy: AntiFeeSniping,
+    current_height: u32,
+    rng: &mut R,
+) -> Result<LockTime, WalletError> {
+    let height = match policy {
+        AntiFeeSniping::Off => return Ok(LockTime::ZERO),
+        AntiFeeSniping::Tip => current_height,
+        AntiFeeSniping::TipWithBackoff => {
+            if rng.gen_bool(LOCKTIME_BACKOFF_CHANCE) {
+                current_height.saturating_sub(rng.gen_range(0..LOCKTIME_MAX_BACKOFF))
+            } else {
+                current_height
+            }
+        }
+    };
+    Ok(LockTime::from_height(height)?)
+}
+
 impl Wallet {
+    /// Set the [AntiFeeSniping] policy for the nLockTime of funding and regular spend txs.
+    pub fn set_anti_fee_sniping(&mut self, policy: AntiFeeSniping) {
+        self.anti_fee_sniping = policy;
+    }
+
+    /// Set the [TxOrdering] policy for the inputs and outputs of funding and regular spend txs.
+    pub fn set_tx_ordering(&mut self, ordering: TxOrdering) {
+        self.tx_ordering = ordering;
+    }
+
+    /// Orders the inputs and outputs of an unsigned funding or spend tx, as per the wallet's [TxOrdering] policy.
+    /// Returns the spend infos in the new order of the inputs.
+    pub(crate) fn order_tx(
+        &self,
+        tx: &mut Transaction,
+        spend_infos: Vec<UTXOSpendInfo>,
+    ) -> Vec<UTXOSpendInfo> {
+        order_tx(self.tx_ordering, tx, spend_infos, &mut thread_rng())
+    }
+
+    /// The nLockTime for a new funding or spend tx, as per the wallet's [AntiFeeSniping] policy.
+    pub(crate) fn anti_fee_sniping_locktime(&self) -> Result<LockTime, WalletError> {
+        let current_height = self.rpc.get_block_count()? as u32;
+        anti_fee_sniping_locktime(self.anti_fee_sniping, current_height, &mut thread_rng())
+    }
+
     /// API to perform spending from wallet UTXOs, including descriptor coins and swap coins.
     ///
     /// The caller needs to specify a list of UTXO data and their corresponding `spend_info`.
@@ -31,8 +205,9 @@ impl Wallet {
     ///
     /// ### Note
     /// This function should not be used to spend Fidelity Bonds or contract UTXOs
-    /// (e.g., Hashlock or Timelock contracts). These UTXOs will be automatically skipped
-    /// and not considered when creating the transaction.
+    /// (e.g., Hashlock or Timelock contracts). Contract UTXOs will be automatically skipped
+    /// and not considered when creating the transaction. Fidelity Bonds are refused with
+    /// [WalletError::CannotSpendFidelityBond], they are only spent by [Wallet::redeem_fidelity].
     ///
     /// ### Behavior
     /// - If [Destination::Sweep] is used, the function creates a transaction for the maximum possible
@@ -50,12 +225,11 @@ impl Wallet {
         let mut coins = Vec::<(ListUnspentResultEntry, UTXOSpendInfo)>::new();
 
         for coin in coins_to_spend {
-            // filter all contract and fidelity utxos.
-            if let UTXOSpendInfo::FidelityBondCoin { .. }
-            | UTXOSpendInfo::HashlockContract { .. }
-            | UTXOSpendInfo::TimelockContract { .. } = coin.1
+            // filter all contract utxos.
+            if let UTXOSpendInfo::HashlockContract { .. } | UTXOSpendInfo::TimelockContract { .. } =
+                coin.1
             {
-                log::warn!("Skipping Fidelity Bond or Contract UTXO.");
+                log::warn!("Skipping Contract UTXO.");
                 continue;
             } else {
                 coins.push(coin.to_owned());
@@ -71,19 +245,20 @@ impl Wallet {
     /// This functions creates a spending transaction from the fidelity bond, signs and broadcasts it.
     /// Returns the txid of the spending tx, and mark the bond as spent.
     pub fn redeem_fidelity(&mut self, idx: u32, feerate: f64) -> Result<(), WalletError> {
-        let (bond, _, is_spent) = self
+        let (bond_amount, is_spent) = self
             .store
             .fidelity_bond
             .get(&idx)
+            .map(|(bond, _, is_spent)| (bond.amount, *is_spent))
             .ok_or(FidelityError::BondDoesNotExist)?;
 
-        if *is_spent {
+        if is_spent {
             log::info!("Fidelity bond already spent.");
             return Ok(());
         }
         let utxo_spend_info = UTXOSpendInfo::FidelityBondCoin {
             index: idx,
-            input_value: bond.amount,
+            input_value: bond_amount,
         };
         let change_addr = &self.get_next_internal_addresses(1)?[0];
         let destination = Destination::Sweep(change_addr.clone());
@@ -91,7 +266,7 @@ impl Wallet {
         let mut utxo: Option<ListUnspentResultEntry> = None;
         for (utxo_data, spend_info) in all_utxo {
             if let UTXOSpendInfo::FidelityBondCoin { index, input_value } = spend_info.clone() {
-                if index == idx && input_value == bond.amount {
+                if index == idx && input_value == bond_amount {
                     utxo = Some(utxo_data)
                 }
             }
@@ -104,7 +279,7 @@ impl Wallet {
             }
         };
 
-        let tx = self.spend_coins(&vec![(utxo, utxo_spend_info)], destination, feerate)?;
+        let tx = self.spend_coins_unchecked(&[(utxo, utxo_spend_info)], destination, feerate)?;
 
         let txid = self.send_tx(&tx)?;
 
@@ -126,8 +301,128 @@ impl Wallet {
         Ok(())
     }
 
+    /// Bumps the fee of an unconfirmed wallet transaction via Replace-By-Fee.
+    ///
+    /// The replacement spends the same inputs and pays the same recipients. The additional fee
+    /// is taken from the change output, so transactions without a change output can't be bumped.
+    /// The replacement pays `new_fee_rate` per vByte, and is signed and broadcasted. Returns the txid of
+    /// the replacement.
+    pub fn bump_fee(&mut self, txid: Txid, new_fee_rate: Amount) -> Result<Txid, WalletError> {
+        let tx_info = self.rpc.get_transaction(&txid, Some(true))?;
+
+        if tx_info.info.confirmations > 0 {
+            return Err(WalletError::General(format!(
+                "Transaction {} is already confirmed",
+                txid
+            )));
+        }
+
+        let mut tx = tx_info.transaction()?;
+
+        if tx_info.info.bip125_replaceable == Bip125Replaceable::No || !tx.is_explicitly_rbf() {
+            return Err(WalletError::General(format!(
+                "Transaction {} is not replaceable",
+                txid
+            )));
+        }
+
+        let mut spend_infos = Vec::new();
+        let mut total_input_value = Amount::ZERO;
+        let mut total_witness_size = 0;
+        for input in &tx.input {
+            let prevout = input.previous_output;
+            let prev_txout = self
+                .rpc
+                .get_transaction(&prevout.txid, Some(true))?
+                .transaction()?
+                .output
+                .get(prevout.vout as usize)
+                .cloned()
+                .ok_or_else(|| {
+                    WalletError::General(format!("Previous output {} not found", prevout))
+                })?;
+            let spend_info = self
+                .find_spend_info_for_txout(&prevout, &prev_txout)?
+                .ok_or_else(|| {
+                    WalletError::General(format!(
+                        "Input {} is not spendable by the wallet",
+                        prevout
+                    ))
+                })?;
+
+            total_input_value += prev_txout.value;
+            total_witness_size += spend_info.estimate_witness_size();
+            spend_infos.push(spend_info);
+        }
+
+        let mut change_index = None;
+        for (index, txout) in tx.output.iter().enumerate() {
+            if let Some((KeychainKind::Internal, _)) =
+                self.find_seed_keychain_index(&txout.script_pubkey)?
+            {
+                change_index = Some(index);
+                break;
+            }
+        }
+        let change_index = change_index.ok_or_else(|| {
+            WalletError::General(format!(
+                "Transaction {} has no change output to pay the extra fee",
+                txid
+            ))
+        })?;
+
+        let total_output_value = tx.output.iter().map(|o| o.value).sum::<Amount>();
+        let old_fee = total_input_value
+            .checked_sub(total_output_value)
+            .ok_or_else(|| WalletError::General("Outputs exceed inputs".to_string()))?;
+
+        tx.input
+            .iter_mut()
+            .for_each(|input| input.witness = Witness::new());
+        let vsize = (tx.base_size() * 4 + total_witness_size).div_ceil(4);
+        let new_fee_rate = (new_fee_rate.to_sat() as f64).max(self.min_fee_rate);
+        let new_fee = Amount::from_sat((new_fee_rate * vsize as f64).ceil() as u64);
+
+        // BIP125: The replacement must pay for its own relay bandwidth, at 1 sat/vB.
+        let min_fee = old_fee + Amount::from_sat(vsize as u64);
+        if new_fee < min_fee {
+            return Err(WalletError::General(format!(
+                "New fee {} sats is too low to replace the current fee {} sats. Required at least {} sats",
+                new_fee.to_sat(),
+                old_fee.to_sat(),
+                min_fee.to_sat()
+            )));
+        }
+
+        let change_output = &mut tx.output[change_index];
+        let minimal_nondust = change_output.script_pubkey.minimal_non_dust();
+        match change_output.value.checked_sub(new_fee - old_fee) {
+            Some(change) if change > minimal_nondust => change_output.value = change,
+            _ => {
+                return Err(WalletError::InsufficientFund {
+                    available: change_output.value.to_sat(),
+                    required: (new_fee - old_fee + minimal_nondust).to_sat(),
+                })
+            }
+        }
+
+        self.sign_transaction(&mut tx, spend_infos.into_iter())?;
+
+        let new_txid = self.send_tx(&tx)?;
+
+        log::info!(
+            "Bumped fee of {} from {} sats to {} sats. Replacement txid: {}",
+            txid,
+            old_fee.to_sat(),
+            new_fee.to_sat(),
+            new_txid
+        );
+
+        Ok(new_txid)
+    }
+
     pub(crate) fn create_timelock_spend(
-        &self,
+        &mut self,
         og_sc: &OutgoingSwapCoin,
         destination_address: &Address,
         feerate: f64,
@@ -153,9 +448,48 @@ impl Wallet {
         Err(WalletError::General("Contract Does not exist".to_string()))
     }
 
+    /// Replaces an unconfirmed outgoing contract tx by its next pre-signed fee-escalated variant (RBF).
+    ///
+    /// Variants still rejected by the node for a too low fee are skipped. Returns the broadcasted variant,
+    /// with the timelock spend of its contract output, or `None` once the agreed fee escalation is used up.
+    pub(crate) fn bump_outgoing_contract_fee(
+        &mut self,
+        multisig_redeemscript: &ScriptBuf,
+        feerate: f64,
+    ) -> Result<Option<(Transaction, Transaction)>, WalletError> {
+        let contract_tx = loop {
+            let escalated_tx = self
+                .find_outgoing_swapcoin_mut(multisig_redeemscript)
+                .ok_or_else(|| WalletError::General("Outgoing swapcoin not found".to_string()))?
+                .escalate_contract_tx()?;
+            let Some(escalated_tx) = escalated_tx else {
+                return Ok(None);
+            };
+            self.save_to_disk()?;
+            match self.send_tx(&escalated_tx) {
+                Ok(_) => break escalated_tx,
+                Err(WalletError::FeeTooLow { required }) => log::warn!(
+                    "Fee-escalated contract tx {} rejected, {} sats fee required",
+                    escalated_tx.compute_txid(),
+                    required
+                ),
+                Err(e) => return Err(e),
+            }
+        };
+
+        self.sync()?;
+        let destination_address = &self.get_next_internal_addresses(1)?[0];
+        let outgoing = self
+            .find_outgoing_swapcoin(multisig_redeemscript)
+            .expect("outgoing swapcoin expected")
+            .clone();
+        let timelock_spend = self.create_timelock_spend(&outgoing, destination_address, feerate)?;
+        Ok(Some((contract_tx, timelock_spend)))
+    }
+
     #[allow(unused)]
     pub(crate) fn create_hashlock_spend(
-        &self,
+        &mut self,
         ic_sc: &IncomingSwapCoin,
         destination_address: &Address,
         feerate: f64,
@@ -181,16 +515,140 @@ impl Wallet {
         Err(WalletError::General("Contract Does not exist".to_string()))
     }
 
-    #[allow(unused)]
-    pub fn spend_coins(
+    /// Builds a spend of the wallet coins to the [Destination], paying the fee rate, without broadcasting it.
+    ///
+    /// The coins are selected like [Wallet::send_to_address] does, so callers can inspect the size, fee and
+    /// outputs of the transaction before sending. The transaction is returned unsigned, e.g. to be passed
+    /// on to a PSBT workflow.
+    pub fn build_spend(
+        &mut self,
+        destination: Destination,
+        feerate: f64,
+    ) -> Result<Transaction, WalletError> {
+        let coins = self.select_spend_coins(&destination)?;
+        let (tx, _, _) = self.build_unsigned_spend(&coins, destination, feerate)?;
+        Ok(tx)
+    }
+
+    /// Sends the wallet coins to the [Destination], paying the fee rate. Returns the txid of the broadcasted tx.
+    ///
+    /// The tx is built like [Wallet::build_spend] does, and signed.
+    pub fn send_to_address(
+        &mut self,
+        destination: Destination,
+        feerate: f64,
+    ) -> Result<Txid, WalletError> {
+        let coins = self.select_spend_coins(&destination)?;
+        let tx = self.spend_from_wallet(feerate, destination, &coins)?;
+        self.send_tx(&tx)
+    }
+
+    /// Combines up to `max_inputs` of the smallest regular UTXOs into a single output to a fresh internal
+    /// address, paying `fee_rate` per vByte. Returns the txid of the broadcasted tx.
+    ///
+    /// Only confirmed seed coins are consolidated: fidelity bonds, swap and contract coins, and the locked
+    /// and protected UTXOs are left alone. Fewer spends of bigger UTXOs keep the fees of future txs down.
+    pub fn consolidate(
+        &mut self,
+        max_inputs: usize,
+        fee_rate: Amount,
+    ) -> Result<Txid, WalletError> {
+        let mut coins = self
+            .list_consolidation_utxo_spend_info()?
+            .into_iter()
+            .filter(|(_, spend_info)| matches!(spend_info, UTXOSpendInfo::SeedCoin { .. }))
+            .collect::<Vec<_>>();
+        coins.sort_by_key(|(utxo, _)| utxo.amount);
+        coins.truncate(max_inputs);
+
+        if coins.len() < 2 {
+            return Err(WalletError::General(format!(
+                "Nothing to consolidate, {} regular UTXO(s) selected",
+                coins.len()
+            )));
+        }
+
+        let address = self.get_next_internal_addresses(1)?[0].clone();
+        let tx = self.spend_from_wallet(
+            fee_rate.to_sat() as f64,
+            Destination::Sweep(address),
+            &coins,
+        )?;
+        let txid = self.send_tx(&tx)?;
+
+        self.sync_no_fail();
+        self.save_to_disk()?;
+
+        Ok(txid)
+    }
+
+    /// Selects the coins to spend to the [Destination]. A sweep spends all the selectable coins.
+    fn select_spend_coins(
         &self,
-        coins: &Vec<(ListUnspentResultEntry, UTXOSpendInfo)>,
+        destination: &Destination,
+    ) -> Result<Vec<(ListUnspentResultEntry, UTXOSpendInfo)>, WalletError> {
+        let amount = match destination {
+            Destination::Sweep(_) => self.get_balances()?.spendable,
+            Destination::Multi(outputs) => outputs.iter().map(|(_, amount)| *amount).sum(),
+        };
+        self.coin_select(amount)
+    }
+
+    /// Builds and signs the spend of the coins to the [Destination], paying the fee rate.
+    ///
+    /// Fidelity bonds are refused with [WalletError::CannotSpendFidelityBond], they are only spent by
+    /// [Wallet::redeem_fidelity].
+    pub fn spend_coins(
+        &mut self,
+        coins: &[(ListUnspentResultEntry, UTXOSpendInfo)],
         destination: Destination,
         feerate: f64,
     ) -> Result<Transaction, WalletError> {
-        // Set the Anti-Fee-Snipping locktime
-        let current_height = self.rpc.get_block_count()?;
-        let lock_time = LockTime::from_height(current_height as u32)?;
+        self.check_no_fidelity_bond(coins)?;
+        self.spend_coins_unchecked(coins, destination, feerate)
+    }
+
+    /// Builds and signs the spend like [Wallet::spend_coins], without refusing the fidelity bonds.
+    fn spend_coins_unchecked(
+        &mut self,
+        coins: &[(ListUnspentResultEntry, UTXOSpendInfo)],
+        destination: Destination,
+        feerate: f64,
+    ) -> Result<Transaction, WalletError> {
+        let (mut tx, spend_infos, total_witness_size) =
+            self.build_unsigned_spend(coins, destination, feerate)?;
+        self.sign_transaction(&mut tx, spend_infos.into_iter())?;
+        let calc_vsize = (tx.base_size() * 4 + total_witness_size).div_ceil(4);
+        let signed_tx_vsize = tx.vsize();
+
+        // As signature size can vary between 71-73 bytes we have a tolerance
+        let tolerance_per_input = 2; // Allow a 2-byte difference per input
+        let total_tolerance = tolerance_per_input * tx.input.len();
+
+        assert!(
+            (calc_vsize as isize - signed_tx_vsize as isize).abs() <= total_tolerance as isize,
+            "Calculated vsize {} didn't match signed tx vsize {} (tolerance: {})",
+            calc_vsize,
+            signed_tx_vsize,
+            total_tolerance
+        );
+
+        log::debug!("Signed Transaction : {:?}", tx.raw_hex());
+        Ok(tx)
+    }
+
+    /// Builds the ordered, unsigned spend of the coins. Returns it along with the spend infos in the order
+    /// of its inputs, and the estimated total witness size.
+    // The fixed fees of the integration tests leave the fee rate computations unused.
+    #[cfg_attr(feature = "integration-test", allow(unused_variables))]
+    fn build_unsigned_spend(
+        &mut self,
+        coins: &[(ListUnspentResultEntry, UTXOSpendInfo)],
+        destination: Destination,
+        feerate: f64,
+    ) -> Result<(Transaction, Vec<UTXOSpendInfo>, usize), WalletError> {
+        let feerate = feerate.max(self.min_fee_rate);
+        let lock_time = self.anti_fee_sniping_locktime()?;
 
         let mut tx = Transaction {
             version: Version::TWO,
@@ -230,12 +688,19 @@ impl Wallet {
                         .get(index)
                         .ok_or(FidelityError::BondDoesNotExist)?;
 
-                    if *is_spent {
+                    let outpoint = OutPoint::new(utxo_data.txid, utxo_data.vout);
+                    // Other outputs at the bond's script, e.g. sent by mistake, are spent the same way.
+                    if *is_spent && outpoint == bond.outpoint {
                         return Err(FidelityError::BondAlreadySpent.into());
                     }
 
+                    // The bond's timelock must be satisfied, regardless of the anti fee sniping locktime.
+                    if tx.lock_time.to_consensus_u32() < bond.lock_time.to_consensus_u32() {
+                        tx.lock_time = bond.lock_time;
+                    }
+
                     tx.input.push(TxIn {
-                        previous_output: bond.outpoint,
+                        previous_output: outpoint,
                         sequence: Sequence::ZERO,
                         script_sig: ScriptBuf::new(),
                         witness: Witness::new(),
@@ -383,23 +848,145 @@ impl Wallet {
             }
         }
 
-        self.sign_transaction(&mut tx, &mut coins.iter().map(|(_, usi)| usi.clone()))?;
-        let calc_vsize = (tx.base_size() * 4 + total_witness_size).div_ceil(4);
-        let signed_tx_vsize = tx.vsize();
+        let spend_infos =
+            self.order_tx(&mut tx, coins.iter().map(|(_, usi)| usi.clone()).collect());
+        Ok((tx, spend_infos, total_witness_size))
+    }
+}
 
-        // As signature size can vary between 71-73 bytes we have a tolerance
-        let tolerance_per_input = 2; // Allow a 2-byte difference per input
-        let total_tolerance = tolerance_per_input * tx.input.len();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
 
-        assert!(
-            (calc_vsize as isize - signed_tx_vsize as isize).abs() <= total_tolerance as isize,
-            "Calculated vsize {} didn't match signed tx vsize {} (tolerance: {})",
-            calc_vsize,
-            signed_tx_vsize,
-            total_tolerance
+    #[test]
+    fn test_anti_fee_sniping_locktime() {
+        let mut rng = thread_rng();
+        let height = 850_000;
+
+        assert_eq!(
+            anti_fee_sniping_locktime(AntiFeeSniping::Off, height, &mut rng).unwrap(),
+            LockTime::ZERO
+        );
+        assert_eq!(
+            anti_fee_sniping_locktime(AntiFeeSniping::Tip, height, &mut rng).unwrap(),
+            LockTime::from_height(height).unwrap()
         );
 
-        log::debug!("Signed Transaction : {:?}", tx.raw_hex());
-        Ok(tx)
+        // With back-off, the locktime stays within 100 blocks of the tip. Mostly it is the tip.
+        let locktimes = (0..1000)
+            .map(|_| {
+                anti_fee_sniping_locktime(AntiFeeSniping::TipWithBackoff, height, &mut rng)
+                    .unwrap()
+                    .to_consensus_u32()
+            })
+            .collect::<Vec<_>>();
+        assert!(locktimes
+            .iter()
+            .all(|lt| *lt <= height && *lt > height - LOCKTIME_MAX_BACKOFF));
+        let at_tip = locktimes.iter().filter(|lt| **lt == height).count();
+        assert!(at_tip > 800 && at_tip < 1000);
+
+        // Near genesis, the back-off doesn't underflow.
+        for _ in 0..100 {
+            anti_fee_sniping_locktime(AntiFeeSniping::TipWithBackoff, 5, &mut rng).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_order_tx() {
+        use bitcoin::{hashes::Hash, secp256k1::rand::RngCore, WScriptHash};
+
+        let mut rng = thread_rng();
+        let multisig_spk = ScriptBuf::new_p2wsh(&WScriptHash::from_byte_array([1; 32]));
+        let build_tx = |rng: &mut bitcoin::secp256k1::rand::rngs::ThreadRng| {
+            let input = (0..4)
+                .map(|i| TxIn {
+                    previous_output: OutPoint::new(
+                        Txid::from_byte_array({
+                            let mut txid = [0u8; 32];
+                            rng.fill_bytes(&mut txid);
+                            txid
+                        }),
+                        i,
+                    ),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>();
+            let spend_infos = (0..4)
+                .map(|i| UTXOSpendInfo::SeedCoin {
+                    path: format!("m/84'/1'/0'/0/{}", i),
+                    input_value: Amount::from_sat(i as u64),
+                })
+                .collect::<Vec<_>>();
+            // The multisig output comes first, as the funding tx builder adds it.
+            let output = vec![(multisig_spk.clone(), 50_000), (ScriptBuf::new(), 20_000)]
+                .into_iter()
+                .chain((2..5u8).map(|i| {
+                    (
+                        ScriptBuf::new_p2wsh(&WScriptHash::from_byte_array([i; 32])),
+                        10_000 * i as u64,
+                    )
+                }))
+                .map(|(script_pubkey, sats)| TxOut {
+                    script_pubkey,
+                    value: Amount::from_sat(sats),
+                })
+                .collect();
+            let tx = Transaction {
+                version: Version::TWO,
+                lock_time: LockTime::ZERO,
+                input,
+                output,
+            };
+            (tx, spend_infos)
+        };
+        // Each input must stay paired with its spend info, which carries the input's vout here.
+        let assert_paired = |tx: &Transaction, spend_infos: &[UTXOSpendInfo]| {
+            assert_eq!(tx.input.len(), spend_infos.len());
+            for (txin, spend_info) in tx.input.iter().zip(spend_infos) {
+                let UTXOSpendInfo::SeedCoin { input_value, .. } = spend_info else {
+                    panic!("seed coin expected");
+                };
+                assert_eq!(txin.previous_output.vout as u64, input_value.to_sat());
+            }
+        };
+
+        // With random ordering, the multisig output moves around, and is still found by its scriptpubkey.
+        let mut multisig_positions = HashSet::new();
+        for _ in 0..50 {
+            let (mut tx, spend_infos) = build_tx(&mut rng);
+            let spend_infos = order_tx(TxOrdering::Random, &mut tx, spend_infos, &mut rng);
+            assert_paired(&tx, &spend_infos);
+            let position = tx
+                .output
+                .iter()
+                .position(|txout| txout.script_pubkey == multisig_spk)
+                .unwrap();
+            assert_eq!(tx.output[position].value, Amount::from_sat(50_000));
+            multisig_positions.insert(position);
+        }
+        assert!(multisig_positions.len() > 1);
+
+        // BIP69 sorts the inputs by txid (in displayed byte order) and vout, and the outputs by value.
+        let (mut tx, spend_infos) = build_tx(&mut rng);
+        let spend_infos = order_tx(TxOrdering::Bip69, &mut tx, spend_infos, &mut rng);
+        assert_paired(&tx, &spend_infos);
+        let txids = tx
+            .input
+            .iter()
+            .map(|txin| txin.previous_output.txid.to_string())
+            .collect::<Vec<_>>();
+        assert!(txids.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(tx
+            .output
+            .windows(2)
+            .all(|pair| pair[0].value <= pair[1].value));
+
+        // Insertion keeps the order.
+        let (mut tx, spend_infos) = build_tx(&mut rng);
+        let original = tx.clone();
+        order_tx(TxOrdering::Insertion, &mut tx, spend_infos, &mut rng);
+        assert_eq!(tx, original);
     }
 }
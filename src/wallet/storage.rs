@@ -2,21 +2,25 @@
 //!
 //! Wallet data is currently written in unencrypted CBOR files which are not directly human readable.
 
-use bitcoin::{bip32::Xpriv, Network, OutPoint, ScriptBuf};
+use bitcoin::{bip32::Xpriv, Network, OutPoint, Script, ScriptBuf};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     fs::{self, read, File},
-    io::BufWriter,
+    io::{BufWriter, Write},
     path::Path,
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
 };
 
-use super::{error::WalletError, fidelity::FidelityBond};
+use super::{api::AddressStrategy, error::WalletError, fidelity::FidelityBond};
 
 use super::swapcoin::{IncomingSwapCoin, OutgoingSwapCoin};
 use crate::wallet::UTXOSpendInfo;
 use bitcoind::bitcoincore_rpc::bitcoincore_rpc_json::ListUnspentResultEntry;
 
+/// Tells apart the temporary files of concurrent saves.
+static SAVE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
 /// Represents the internal data store for a Bitcoin wallet.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub(crate) struct WalletStore {
@@ -45,6 +49,48 @@ pub(crate) struct WalletStore {
     /// Maps transaction outpoints to their associated UTXO and spend information.
     #[serde(default)] // Ensures deserialization works if `utxo_cache` is missing
     pub(super) utxo_cache: HashMap<OutPoint, (ListUnspentResultEntry, UTXOSpendInfo)>,
+
+    /// Index of scriptpubkeys to the outpoints of the cached UTXOs paying to them.
+    /// Kept in sync with the `utxo_cache`, so UTXOs can be looked up by script without re-deriving it.
+    #[serde(default)]
+    pub(super) script_index: HashMap<ScriptBuf, Vec<OutPoint>>,
+
+    /// Block heights of the cached coinbase UTXOs which were immature when first seen, to track their maturity.
+    #[serde(default)]
+    pub(super) coinbase_heights: HashMap<OutPoint, u64>,
+
+    /// Map of incoming swapcoin multisig redeemscript to user provided swap label.
+    #[serde(default)]
+    pub(super) swap_labels: HashMap<ScriptBuf, String>,
+
+    /// UTXOs excluded from automatic coin selection. These are only spent via coin control.
+    #[serde(default)]
+    pub(super) protected_utxos: HashSet<OutPoint>,
+
+    /// Maximum confirmation count of UTXOs picked by automatic coin selection.
+    #[serde(default)]
+    pub(super) max_input_age: Option<u32>,
+
+    /// The BIP39 entropy of the wallet seed, for mnemonic backups. Not known for older wallet files.
+    #[serde(default)]
+    pub(super) seed_entropy: Option<Vec<u8>>,
+
+    /// Scriptpubkeys of the outputs created in the most recent swaps, keyed by the swap's hashvalue.
+    /// Oldest swap first.
+    #[serde(default)]
+    pub(super) recent_swap_scripts: VecDeque<([u8; 20], Vec<ScriptBuf>)>,
+
+    /// User labels of addresses, keyed by scriptpubkey.
+    #[serde(default)]
+    pub(super) address_labels: HashMap<ScriptBuf, String>,
+
+    /// User labels of UTXOs.
+    #[serde(default)]
+    pub(super) utxo_labels: HashMap<OutPoint, String>,
+
+    /// Keychain layout of the change addresses. Older wallet files use the split chain.
+    #[serde(default)]
+    pub(super) address_strategy: AddressStrategy,
 }
 
 impl WalletStore {
@@ -54,7 +100,9 @@ impl WalletStore {
         path: &Path,
         network: Network,
         master_key: Xpriv,
+        seed_entropy: Option<Vec<u8>>,
         wallet_birthday: Option<u64>,
+        address_strategy: AddressStrategy,
     ) -> Result<Self, WalletError> {
         let store = Self {
             file_name,
@@ -69,6 +117,16 @@ impl WalletStore {
             last_synced_height: None,
             wallet_birthday,
             utxo_cache: HashMap::new(),
+            script_index: HashMap::new(),
+            coinbase_heights: HashMap::new(),
+            swap_labels: HashMap::new(),
+            protected_utxos: HashSet::new(),
+            max_input_age: None,
+            seed_entropy,
+            recent_swap_scripts: VecDeque::new(),
+            address_labels: HashMap::new(),
+            utxo_labels: HashMap::new(),
+            address_strategy,
         };
 
         std::fs::create_dir_all(path.parent().expect("Path should NOT be root!"))?;
@@ -82,17 +140,35 @@ impl WalletStore {
     }
 
     /// Load existing file, updates it, writes it back (errors if path doesn't exist).
+    ///
+    /// The store is written into a temporary file, renamed over the wallet file once complete. So concurrent
+    /// saves can't interleave, and a crash mid-save leaves the previous file intact.
     pub(crate) fn write_to_disk(&self, path: &Path) -> Result<(), WalletError> {
-        let wallet_file = fs::OpenOptions::new().write(true).open(path)?;
-        let writer = BufWriter::new(wallet_file);
-        Ok(serde_cbor::to_writer(writer, &self)?)
+        fs::metadata(path)?;
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            self.file_name,
+            SAVE_COUNTER.fetch_add(1, Relaxed)
+        ));
+        let written = serde_cbor::to_vec(&self)
+            .map_err(WalletError::from)
+            .and_then(|store| {
+                let mut temp_file = File::create(&temp_path)?;
+                temp_file.write_all(&store)?;
+                temp_file.sync_all()?;
+                Ok(fs::rename(&temp_path, path)?)
+            });
+        if written.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        written
     }
 
     /// Reads from a path (errors if path doesn't exist).
     pub(crate) fn read_from_disk(path: &Path) -> Result<Self, WalletError> {
         //let wallet_file = File::open(path)?;
         let mut reader = read(path)?;
-        let store = match serde_cbor::from_slice::<Self>(&reader) {
+        let mut store = match serde_cbor::from_slice::<Self>(&reader) {
             Ok(store) => store,
             Err(e) => {
                 let err_string = format!("{:?}", e);
@@ -111,14 +187,82 @@ impl WalletStore {
                 }
             }
         };
+        // Older wallet files don't have the index.
+        if store.script_index.is_empty() && !store.utxo_cache.is_empty() {
+            store.rebuild_script_index();
+        }
         Ok(store)
     }
+
+    /// Adds a UTXO to the cache, and indexes it by its scriptpubkey.
+    pub(super) fn insert_utxo(
+        &mut self,
+        outpoint: OutPoint,
+        utxo: ListUnspentResultEntry,
+        spend_info: UTXOSpendInfo,
+    ) {
+        let outpoints = self
+            .script_index
+            .entry(utxo.script_pub_key.clone())
+            .or_default();
+        if !outpoints.contains(&outpoint) {
+            outpoints.push(outpoint);
+        }
+        self.utxo_cache.insert(outpoint, (utxo, spend_info));
+    }
+
+    /// Removes a UTXO from the cache and the script index.
+    pub(super) fn remove_utxo(&mut self, outpoint: &OutPoint) {
+        let Some((utxo, _)) = self.utxo_cache.remove(outpoint) else {
+            return;
+        };
+        self.coinbase_heights.remove(outpoint);
+        if let Some(outpoints) = self.script_index.get_mut(&utxo.script_pub_key) {
+            outpoints.retain(|op| op != outpoint);
+            if outpoints.is_empty() {
+                self.script_index.remove(&utxo.script_pub_key);
+            }
+        }
+    }
+
+    /// Rebuilds the script index from the UTXO cache.
+    pub(super) fn rebuild_script_index(&mut self) {
+        self.script_index.clear();
+        for (outpoint, (utxo, _)) in &self.utxo_cache {
+            self.script_index
+                .entry(utxo.script_pub_key.clone())
+                .or_default()
+                .push(*outpoint);
+        }
+    }
+
+    /// Returns the cached UTXOs paying to a scriptpubkey.
+    pub(super) fn utxos_for_script<'a>(
+        &'a self,
+        script_pubkey: &Script,
+    ) -> impl Iterator<Item = &'a (ListUnspentResultEntry, UTXOSpendInfo)> {
+        self.script_index
+            .get(script_pubkey)
+            .into_iter()
+            .flatten()
+            .filter_map(move |outpoint| self.utxo_cache.get(outpoint))
+    }
+
+    /// Returns the HD path of a seed scriptpubkey, if a cached UTXO already pays to it.
+    pub(super) fn indexed_seed_path(&self, script_pubkey: &Script) -> Option<String> {
+        self.utxos_for_script(script_pubkey)
+            .find_map(|(_, spend_info)| match spend_info {
+                UTXOSpendInfo::SeedCoin { path, .. } => Some(path.clone()),
+                _ => None,
+            })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use bip39::rand::{thread_rng, Rng};
+    use bitcoin::{hashes::Hash, Txid};
     use bitcoind::tempfile::tempdir;
 
     #[test]
@@ -137,6 +281,8 @@ mod tests {
             Network::Bitcoin,
             master_key,
             None,
+            None,
+            AddressStrategy::default(),
         )
         .unwrap();
 
@@ -145,4 +291,45 @@ mod tests {
         let read_wallet = WalletStore::read_from_disk(&file_path).unwrap();
         assert_eq!(original_wallet_store, read_wallet);
     }
+
+    #[test]
+    fn test_concurrent_and_shrinking_saves() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("test_wallet.cbor");
+
+        let mut store = WalletStore::init(
+            "test_wallet".to_string(),
+            &file_path,
+            Network::Regtest,
+            Xpriv::new_master(Network::Regtest, &[1u8; 16]).unwrap(),
+            None,
+            None,
+            AddressStrategy::default(),
+        )
+        .unwrap();
+        store.protected_utxos = (0..1000)
+            .map(|vout| OutPoint::new(Txid::from_byte_array([7u8; 32]), vout))
+            .collect();
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| store.write_to_disk(&file_path).unwrap());
+            }
+        });
+        assert_eq!(
+            fs::read(&file_path).unwrap(),
+            serde_cbor::to_vec(&store).unwrap()
+        );
+
+        // A smaller store leaves no trailing bytes of the larger one.
+        store.protected_utxos.clear();
+        store.write_to_disk(&file_path).unwrap();
+        assert_eq!(
+            fs::read(&file_path).unwrap(),
+            serde_cbor::to_vec(&store).unwrap()
+        );
+
+        // Only the wallet file is left.
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
 }
@@ -12,20 +12,21 @@ use bitcoin::{
     ecdsa::Signature,
     secp256k1::{self, Secp256k1, SecretKey},
     sighash::{EcdsaSighashType, SighashCache},
-    Amount, PublicKey, Script, ScriptBuf, Transaction, TxIn,
+    Amount, PublicKey, Script, ScriptBuf, Transaction, TxIn, Txid,
 };
 
 use super::WalletError;
 use crate::protocol::{
     contract::{
-        apply_two_signatures_to_2of2_multisig_spend, create_multisig_redeemscript,
-        read_contract_locktime, read_hashlock_pubkey_from_contract, read_hashvalue_from_contract,
-        read_pubkeys_from_multisig_redeemscript, read_timelock_pubkey_from_contract,
-        sign_contract_tx, verify_contract_tx_sig,
+        apply_two_signatures_to_2of2_multisig_spend, create_fee_escalated_contract_tx,
+        create_multisig_redeemscript, read_contract_locktime, read_hashlock_pubkey_from_contract,
+        read_hashvalue_from_contract, read_pubkeys_from_multisig_redeemscript,
+        read_timelock_pubkey_from_contract, sign_contract_tx, sign_fee_escalated_contract_txs,
+        verify_contract_tx_sig, verify_fee_escalated_contract_tx_sigs,
     },
     error::ProtocolError,
     messages::Preimage,
-    Hash160,
+    Hashvalue,
 };
 
 /// Defines an incoming swapcoin, which can either be currently active or successfully completed.
@@ -51,6 +52,9 @@ pub(crate) struct IncomingSwapCoin {
     pub(crate) hashlock_privkey: SecretKey,
     pub(crate) funding_amount: Amount,
     pub(crate) others_contract_sig: Option<Signature>,
+    /// Other's sigs on the agreed fee-escalated variants of the contract tx, with the fee of each variant.
+    #[serde(default)]
+    pub(crate) others_escalated_contract_sigs: Vec<(Amount, Signature)>,
     pub(crate) hash_preimage: Option<Preimage>,
 }
 
@@ -76,6 +80,9 @@ pub(crate) struct OutgoingSwapCoin {
     pub(crate) timelock_privkey: SecretKey,
     pub(crate) funding_amount: Amount,
     pub(crate) others_contract_sig: Option<Signature>,
+    /// Other's sigs on the agreed fee-escalated variants of the contract tx, with the fee of each variant.
+    #[serde(default)]
+    pub(crate) others_escalated_contract_sigs: Vec<(Amount, Signature)>,
     pub(crate) hash_preimage: Option<Preimage>,
 }
 
@@ -109,13 +116,25 @@ pub(crate) trait SwapCoin {
     /// Get the timelock value.
     fn get_timelock(&self) -> Result<u16, WalletError>;
     /// Get the hash value.
-    fn get_hashvalue(&self) -> Result<Hash160, WalletError>;
+    fn get_hashvalue(&self) -> Result<Hashvalue, WalletError>;
     /// Get the funding amount.
     fn get_funding_amount(&self) -> Amount;
     /// Verify the receiver's signature on the contract transaction.
     fn verify_contract_tx_receiver_sig(&self, sig: &Signature) -> Result<(), WalletError>;
     /// Verify the sender's signature on the contract transaction.
     fn verify_contract_tx_sender_sig(&self, sig: &Signature) -> Result<(), WalletError>;
+    /// Verify the receiver's signatures on the fee-escalated variants of the contract transaction.
+    fn verify_escalated_contract_tx_receiver_sigs(
+        &self,
+        fee_escalation: &[Amount],
+        sigs: &[Signature],
+    ) -> Result<(), WalletError>;
+    /// Verify the sender's signatures on the fee-escalated variants of the contract transaction.
+    fn verify_escalated_contract_tx_sender_sigs(
+        &self,
+        fee_escalation: &[Amount],
+        sigs: &[Signature],
+    ) -> Result<(), WalletError>;
     /// Apply a private key to the swap coin.
     fn apply_privkey(&mut self, privkey: SecretKey) -> Result<(), ProtocolError>;
 }
@@ -126,6 +145,17 @@ pub(crate) trait WalletSwapCoin: SwapCoin {
     fn get_other_pubkey(&self) -> &PublicKey;
     fn get_fully_signed_contract_tx(&self) -> Result<Transaction, ProtocolError>;
     fn is_hash_preimage_known(&self) -> bool;
+    /// Verify and store the other's signatures on the fee-escalated variants of the contract tx.
+    fn apply_escalated_contract_sigs(
+        &mut self,
+        fee_escalation: &[Amount],
+        sigs: &[Signature],
+    ) -> Result<(), WalletError>;
+    /// Txids of the fee-escalated variants of the contract tx, which can be broadcast in its place.
+    fn get_escalated_contract_txids(&self) -> Vec<Txid>;
+    /// Replace the contract tx by its next fee-escalated variant, and return it fully signed.
+    /// Returns `None` once all the agreed variants are used.
+    fn escalate_contract_tx(&mut self) -> Result<Option<Transaction>, ProtocolError>;
 }
 
 macro_rules! impl_walletswapcoin {
@@ -187,6 +217,53 @@ macro_rules! impl_walletswapcoin {
             fn is_hash_preimage_known(&self) -> bool {
                 self.hash_preimage.is_some()
             }
+
+            fn apply_escalated_contract_sigs(
+                &mut self,
+                fee_escalation: &[Amount],
+                sigs: &[Signature],
+            ) -> Result<(), WalletError> {
+                verify_fee_escalated_contract_tx_sigs(
+                    &self.contract_tx,
+                    &self.get_multisig_redeemscript(),
+                    self.funding_amount,
+                    &self.other_pubkey,
+                    fee_escalation,
+                    sigs,
+                )?;
+                self.others_escalated_contract_sigs = fee_escalation
+                    .iter()
+                    .copied()
+                    .zip(sigs.iter().copied())
+                    .collect();
+                Ok(())
+            }
+
+            fn get_escalated_contract_txids(&self) -> Vec<Txid> {
+                self.others_escalated_contract_sigs
+                    .iter()
+                    .filter_map(|(fee, _)| {
+                        create_fee_escalated_contract_tx(
+                            &self.contract_tx,
+                            self.funding_amount,
+                            *fee,
+                        )
+                        .ok()
+                    })
+                    .map(|tx| tx.compute_txid())
+                    .collect()
+            }
+
+            fn escalate_contract_tx(&mut self) -> Result<Option<Transaction>, ProtocolError> {
+                if self.others_escalated_contract_sigs.is_empty() {
+                    return Ok(None);
+                }
+                let (fee, sig) = self.others_escalated_contract_sigs.remove(0);
+                self.contract_tx =
+                    create_fee_escalated_contract_tx(&self.contract_tx, self.funding_amount, fee)?;
+                self.others_contract_sig = Some(sig);
+                self.get_fully_signed_contract_tx().map(Some)
+            }
         }
     };
 }
@@ -210,7 +287,7 @@ macro_rules! impl_swapcoin_getters {
         //     )?)
         // }
 
-        fn get_hashvalue(&self) -> Result<Hash160, WalletError> {
+        fn get_hashvalue(&self) -> Result<Hashvalue, WalletError> {
             Ok(read_hashvalue_from_contract(&self.contract_redeemscript)?)
         }
 
@@ -252,6 +329,7 @@ impl IncomingSwapCoin {
             hashlock_privkey,
             funding_amount,
             others_contract_sig: None,
+            others_escalated_contract_sigs: Vec::new(),
             hash_preimage: None,
         })
     }
@@ -389,6 +467,7 @@ impl OutgoingSwapCoin {
             timelock_privkey,
             funding_amount,
             others_contract_sig: None,
+            others_escalated_contract_sigs: Vec::new(),
             hash_preimage: None,
         })
     }
@@ -437,6 +516,21 @@ impl OutgoingSwapCoin {
         )?)
     }
 
+    /// Sign the fee-escalated variants of a contract tx, one per fee of `fee_escalation`.
+    pub(crate) fn sign_escalated_contract_txs_with_my_privkey(
+        &self,
+        contract_tx: &Transaction,
+        fee_escalation: &[Amount],
+    ) -> Result<Vec<Signature>, WalletError> {
+        Ok(sign_fee_escalated_contract_txs(
+            contract_tx,
+            &self.get_multisig_redeemscript(),
+            self.funding_amount,
+            fee_escalation,
+            &self.my_privkey,
+        )?)
+    }
+
     pub(crate) fn verify_contract_tx_sig(&self, sig: &Signature) -> Result<(), WalletError> {
         Ok(verify_contract_tx_sig(
             &self.contract_tx,
@@ -502,6 +596,29 @@ impl SwapCoin for IncomingSwapCoin {
         self.verify_contract_tx_sig(sig)
     }
 
+    fn verify_escalated_contract_tx_receiver_sigs(
+        &self,
+        fee_escalation: &[Amount],
+        sigs: &[Signature],
+    ) -> Result<(), WalletError> {
+        self.verify_escalated_contract_tx_sender_sigs(fee_escalation, sigs)
+    }
+
+    fn verify_escalated_contract_tx_sender_sigs(
+        &self,
+        fee_escalation: &[Amount],
+        sigs: &[Signature],
+    ) -> Result<(), WalletError> {
+        Ok(verify_fee_escalated_contract_tx_sigs(
+            &self.contract_tx,
+            &self.get_multisig_redeemscript(),
+            self.funding_amount,
+            &self.other_pubkey,
+            fee_escalation,
+            sigs,
+        )?)
+    }
+
     fn apply_privkey(&mut self, privkey: SecretKey) -> Result<(), ProtocolError> {
         let secp = Secp256k1::new();
         let pubkey = PublicKey {
@@ -538,6 +655,29 @@ impl SwapCoin for OutgoingSwapCoin {
         self.verify_contract_tx_sig(sig)
     }
 
+    fn verify_escalated_contract_tx_receiver_sigs(
+        &self,
+        fee_escalation: &[Amount],
+        sigs: &[Signature],
+    ) -> Result<(), WalletError> {
+        self.verify_escalated_contract_tx_sender_sigs(fee_escalation, sigs)
+    }
+
+    fn verify_escalated_contract_tx_sender_sigs(
+        &self,
+        fee_escalation: &[Amount],
+        sigs: &[Signature],
+    ) -> Result<(), WalletError> {
+        Ok(verify_fee_escalated_contract_tx_sigs(
+            &self.contract_tx,
+            &self.get_multisig_redeemscript(),
+            self.funding_amount,
+            &self.other_pubkey,
+            fee_escalation,
+            sigs,
+        )?)
+    }
+
     fn apply_privkey(&mut self, privkey: SecretKey) -> Result<(), ProtocolError> {
         let secp = Secp256k1::new();
         let pubkey = PublicKey {
@@ -596,6 +736,36 @@ impl SwapCoin for WatchOnlySwapCoin {
             &sig.signature,
         )?)
     }
+
+    fn verify_escalated_contract_tx_sender_sigs(
+        &self,
+        fee_escalation: &[Amount],
+        sigs: &[Signature],
+    ) -> Result<(), WalletError> {
+        Ok(verify_fee_escalated_contract_tx_sigs(
+            &self.contract_tx,
+            &self.get_multisig_redeemscript(),
+            self.funding_amount,
+            &self.receiver_pubkey,
+            fee_escalation,
+            sigs,
+        )?)
+    }
+
+    fn verify_escalated_contract_tx_receiver_sigs(
+        &self,
+        fee_escalation: &[Amount],
+        sigs: &[Signature],
+    ) -> Result<(), WalletError> {
+        Ok(verify_fee_escalated_contract_tx_sigs(
+            &self.contract_tx,
+            &self.get_multisig_redeemscript(),
+            self.funding_amount,
+            &self.sender_pubkey,
+            fee_escalation,
+            sigs,
+        )?)
+    }
 }
 
 #[cfg(test)]
@@ -698,6 +868,7 @@ mod tests {
             .unwrap(),
             funding_amount: Amount::ZERO,
             others_contract_sig: None,
+            others_escalated_contract_sigs: Vec::new(),
             hash_preimage: None,
         };
 
@@ -749,6 +920,7 @@ mod tests {
             .unwrap(),
             funding_amount: Amount::ZERO,
             others_contract_sig: None,
+            others_escalated_contract_sigs: Vec::new(),
             hash_preimage: None,
         };
         let secret_key_1 =
@@ -818,6 +990,7 @@ mod tests {
             .unwrap(),
             funding_amount: Amount::from_sat(100_000),
             others_contract_sig: None,
+            others_escalated_contract_sigs: Vec::new(),
             hash_preimage: None,
         };
         // Intentionally failing to sign with incomplete swapcoin
@@ -873,6 +1046,7 @@ mod tests {
             .unwrap(),
             funding_amount: Amount::from_sat(100_000),
             others_contract_sig: None,
+            others_escalated_contract_sigs: Vec::new(),
             hash_preimage: Some(Preimage::from([0; 32])),
         };
         let destination_address: Address = Address::from_str("32iVBEu4dxkUQk9dJbZUiBiQdmypcEyJRf")
@@ -953,6 +1127,7 @@ mod tests {
             .unwrap(),
             funding_amount: Amount::from_sat(100_000),
             others_contract_sig: None,
+            others_escalated_contract_sigs: Vec::new(),
             hash_preimage: Some(Preimage::from([0; 32])),
         };
         let destination_address: Address = Address::from_str("32iVBEu4dxkUQk9dJbZUiBiQdmypcEyJRf")
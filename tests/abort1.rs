@@ -108,6 +108,7 @@ fn test_stop_taker_after_setup() {
         maker_count: 2,
         tx_count: 3,
         required_confirms: 1,
+        ..Default::default()
     };
     taker.do_coinswap(swap_params).unwrap();
 
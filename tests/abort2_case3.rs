@@ -106,6 +106,7 @@ fn maker_drops_after_sending_senders_sigs() {
         maker_count: 2,
         tx_count: 3,
         required_confirms: 1,
+        ..Default::default()
     };
     taker.do_coinswap(swap_params).unwrap();
 
@@ -104,6 +104,7 @@ fn abort3_case2_close_at_contract_sigs_for_recvr() {
         maker_count: 2,
         tx_count: 3,
         required_confirms: 1,
+        ..Default::default()
     };
     taker.do_coinswap(swap_params).unwrap();
 
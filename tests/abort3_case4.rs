@@ -0,0 +1,212 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, Maker, MakerBehavior},
+    taker::{BanReason, SwapParams, TakerBehavior},
+    utill::ConnectionType,
+    wallet::RPCConfig,
+};
+use std::{env, sync::Arc};
+
+mod test_framework;
+use test_framework::*;
+
+use log::{info, warn};
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// ABORT 3: Maker Drops After Setup
+/// Case 4: DropAtSettlement
+///
+/// Maker receives and records the hash preimage, but drops the connection before sending its private key
+/// handover. The preimage is now known to the Maker, but the Taker never receives the multisig privkeys.
+/// Taker retries the settlement until the reconnect limit, then recovers via the contract txs.
+/// All funds must end up back in the participant wallets, with no pending contract balance.
+#[test]
+fn abort3_case4_drop_at_settlement() {
+    // ---- Setup ----
+
+    // 6102 is naughty. And theres not enough makers.
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::DropAtSettlement),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    // Initiate test framework, Makers.
+    // Taker has normal behavior.
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    warn!("Running Test: Maker drops connection at settlement, after receiving the preimage");
+
+    // Fund the Taker  with 3 utxos of 0.05 btc each and do basic checks on the balance
+    let org_taker_spend_balance = fund_and_verify_taker(
+        &mut taker,
+        &test_framework.bitcoind,
+        3,
+        Amount::from_btc(0.05).unwrap(),
+    );
+
+    // Fund the Maker with 4 utxos of 0.05 btc each and do basic checks on the balance.
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(
+        makers_ref,
+        &test_framework.bitcoind,
+        4,
+        Amount::from_btc(0.05).unwrap(),
+    );
+
+    //  Start the Maker Server threads
+    info!("Initiating Maker...");
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Makers take time to fully setup.
+    let org_maker_spend_balances = makers
+        .iter()
+        .map(|maker| {
+            while !maker.is_setup_complete.load(Relaxed) {
+                info!("Waiting for maker setup completion");
+                // Introduce a delay of 10 seconds to prevent write lock starvation.
+                thread::sleep(Duration::from_secs(10));
+                continue;
+            }
+
+            // Check balance after setting up maker server.
+            let wallet = maker.wallet.read().unwrap();
+
+            let balances = wallet.get_balances().unwrap();
+
+            assert_eq!(balances.regular, Amount::from_btc(0.14999).unwrap());
+            assert_eq!(balances.fidelity, Amount::from_btc(0.05).unwrap());
+            assert_eq!(balances.swap, Amount::ZERO);
+            assert_eq!(balances.contract, Amount::ZERO);
+
+            balances.spendable
+        })
+        .collect::<Vec<_>>();
+
+    // Initiate Coinswap
+    info!("Initiating coinswap protocol");
+
+    // Swap params for coinswap.
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    // After Swap is done,  wait for maker threads to conclude.
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    // The swap never completed, and the faulty maker is banned for not finishing the settlement.
+    assert!(taker.get_swap_history().unwrap().is_empty());
+    let bad_makers = taker.list_bad_makers();
+    assert_eq!(bad_makers.len(), 1);
+    assert_eq!(bad_makers[0].reason, BanReason::Unreachable);
+    assert!(bad_makers[0].offer.address.to_string().ends_with(":6102"));
+
+    // The Taker never received the handover, so no swapcoin can be left pending in its wallet.
+    let taker_wallet = taker.get_wallet_mut();
+    taker_wallet.sync().unwrap();
+    let taker_balances = taker_wallet.get_balances().unwrap();
+    assert_eq!(taker_balances.swap, Amount::ZERO);
+    assert_eq!(taker_balances.contract, Amount::ZERO);
+
+    // Restart the faulty maker from its wallet file. It picks up the contracts left from the dropped
+    // settlement on reboot, and recovers them.
+    info!("Restarting the faulty maker");
+    let restarted_maker = Arc::new(
+        Maker::init(
+            Some(env::temp_dir().join("coinswap").join("6102")),
+            Some("maker6102".to_string()),
+            Some(RPCConfig::from(test_framework.as_ref())),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(ConnectionType::CLEARNET),
+            MakerBehavior::Normal,
+        )
+        .unwrap(),
+    );
+    let maker_clone = restarted_maker.clone();
+    let restarted_thread = thread::spawn(move || start_maker_server(maker_clone).unwrap());
+    while !restarted_maker.is_setup_complete.load(Relaxed) {
+        info!("Waiting for the restarted maker setup completion");
+        thread::sleep(Duration::from_secs(10));
+    }
+    loop {
+        let mut wallet = restarted_maker.get_wallet().write().unwrap();
+        wallet.sync().unwrap();
+        let balances = wallet.get_balances().unwrap();
+        if balances.swap == Amount::ZERO && balances.contract == Amount::ZERO {
+            break;
+        }
+        drop(wallet);
+        info!("Waiting for the restarted maker to recover its contracts");
+        thread::sleep(Duration::from_secs(10));
+    }
+    restarted_maker.shutdown.store(true, Relaxed);
+    restarted_thread.join().unwrap();
+
+    info!("All coinswaps processed. Transaction complete.");
+
+    // Shutdown Directory Server
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    thread::sleep(Duration::from_secs(10));
+
+    // Synchronize the wallet of the other maker.
+    makers[1].get_wallet().write().unwrap().sync().unwrap();
+
+    //-------- Fee Tracking and Workflow:--------------------------------------------------------------------------
+    //
+    // Case 1: Maker6102 is the first maker
+    // Workflow: Taker -> Maker6102(DropAtSettlement) -> Maker16102
+    //
+    // Case 2: Maker6102 is the last maker.
+    // Workflow: Taker -> Maker16102 -> Maker6102(DropAtSettlement)
+    //
+    // In both cases the settlement stops at Maker6102, before any private key reached the next hop, so every
+    // hop is settled on-chain with the contract txs: by timelock, or by hashlock for the participants knowing
+    // the preimage. Each participant either gets back its funding minus the contract and recovery fees, or
+    // ends up with the swapped coins, as checked by `verify_swap_results`.
+    //-----------------------------------------------------------------------------------------------------------------------------------------------
+
+    let makers = [restarted_maker, makers[1].clone()];
+
+    // After Swap checks:
+    verify_swap_results(
+        &taker,
+        &makers,
+        org_taker_spend_balance,
+        org_maker_spend_balances,
+    );
+
+    info!("All checks successful. Terminating integration test case");
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,182 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::{Amount, OutPoint};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test the fee escalation of stuck contract txs during recovery.
+///
+/// The Taker drops the connection after the full setup, and recovers via its outgoing contracts. Fee
+/// pressure is simulated by deprioritizing the base contract txs in the node's mempool, so they are
+/// never mined. The Taker must replace them by the fee-escalated variants pre-signed by the Makers,
+/// which confirm long before the timelock, and then claim its coins back via the timelock spends.
+#[test]
+fn test_contract_fee_escalation() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::DropConnectionAfterFullSetup,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    // From here on, blocks are mined by the test only.
+    test_framework.stop_block_generation();
+    block_generation_handle.join().unwrap();
+
+    // The funding outpoints of the Taker's outgoing contracts, with their amounts.
+    taker.get_wallet_mut().sync().unwrap();
+    let wallet = taker.get_wallet();
+    let incoming_fundings = wallet
+        .list_incoming_swap_coin_utxo_spend_info()
+        .unwrap()
+        .into_iter()
+        .map(|(utxo, _)| OutPoint::new(utxo.txid, utxo.vout))
+        .collect::<HashSet<_>>();
+    let outgoing_fundings = wallet
+        .list_swap_coin_utxo_spend_info()
+        .unwrap()
+        .into_iter()
+        .map(|(utxo, _)| (OutPoint::new(utxo.txid, utxo.vout), utxo.amount))
+        .filter(|(outpoint, _)| !incoming_fundings.contains(outpoint))
+        .collect::<HashMap<_, _>>();
+    assert_eq!(outgoing_fundings.len(), 3);
+
+    // ----- Test -----
+    let recovery_start_height = bitcoind.client.get_block_count().unwrap();
+    let recovery = thread::spawn(move || {
+        taker.recover_from_swap().unwrap();
+        taker
+    });
+
+    // Wait for the base contract txs in the mempool, and keep them out of any block.
+    let mut base_contract_txids = Vec::new();
+    while base_contract_txids.len() < outgoing_fundings.len() {
+        thread::sleep(Duration::from_millis(500));
+        base_contract_txids = bitcoind
+            .client
+            .get_raw_mempool()
+            .unwrap()
+            .into_iter()
+            .filter(|txid| {
+                bitcoind
+                    .client
+                    .get_raw_transaction(txid, None)
+                    .unwrap()
+                    .input
+                    .iter()
+                    .any(|input| outgoing_fundings.contains_key(&input.previous_output))
+            })
+            .collect();
+    }
+    for txid in &base_contract_txids {
+        assert!(bitcoind
+            .client
+            .call::<bool>(
+                "prioritisetransaction",
+                &[txid.to_string().into(), 0.into(), (-1_000_000).into()],
+            )
+            .unwrap());
+    }
+
+    // Mine slowly, so the Taker observes the stuck contracts and bumps them.
+    while !recovery.is_finished() {
+        generate_blocks(bitcoind, 1);
+        thread::sleep(Duration::from_secs(3));
+    }
+    let mut taker = recovery.join().unwrap();
+
+    // Every funding outpoint is spent by a confirmed fee-escalated variant.
+    for txid in &base_contract_txids {
+        assert!(bitcoind
+            .client
+            .get_raw_transaction_info(txid, None)
+            .is_err());
+    }
+    let mut escalated_contracts = 0;
+    for block_height in recovery_start_height + 1..=bitcoind.client.get_block_count().unwrap() {
+        let block_hash = bitcoind.client.get_block_hash(block_height).unwrap();
+        for tx in bitcoind.client.get_block(&block_hash).unwrap().txdata {
+            let Some(funding_amount) = tx
+                .input
+                .iter()
+                .find_map(|input| outgoing_fundings.get(&input.previous_output))
+            else {
+                continue;
+            };
+            assert!(!base_contract_txids.contains(&tx.compute_txid()));
+            let fee = *funding_amount - tx.output[0].value;
+            assert!(fee > Amount::from_sat(1000));
+            // Confirmed well before the refund timelock of the first hop.
+            assert!(block_height - recovery_start_height < 20);
+            escalated_contracts += 1;
+        }
+    }
+    assert_eq!(escalated_contracts, outgoing_fundings.len());
+
+    // All the coins are claimed back via the timelock spends.
+    taker.get_wallet_mut().sync().unwrap();
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+
+    // ----- Clean up -----
+    test_framework.stop();
+}
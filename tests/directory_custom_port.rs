@@ -0,0 +1,117 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use bitcoind::bitcoincore_rpc::Auth;
+use coinswap::{
+    maker::{start_maker_server, Maker, MakerBehavior},
+    market::directory::{start_directory_server, DirectoryServer},
+    taker::TakerBehavior,
+    utill::ConnectionType,
+    wallet::RPCConfig,
+};
+use std::{
+    env, fs,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test a directory server listening on a custom port.
+///
+/// - A second directory server is configured with `listen_port = 18090` and a clearnet transport.
+/// - A bondless maker registers there, and a Taker configured with that port only fetches its address.
+#[test]
+fn test_directory_custom_port() {
+    // ---- Setup ----
+
+    let (test_framework, mut taker, _, directory_server_instance, block_generation_handle) =
+        TestFramework::init(Vec::new(), TakerBehavior::Normal, ConnectionType::CLEARNET);
+    taker.config.allow_bondless_offers = true;
+
+    let bitcoind = &test_framework.bitcoind;
+
+    let custom_dns_dir = env::temp_dir().join("coinswap").join("dns-custom");
+    fs::create_dir_all(&custom_dns_dir).unwrap();
+    fs::write(
+        custom_dns_dir.join("config.toml"),
+        "listen_port = 18090\nrpc_port = 14322\ntransport = CLEARNET",
+    )
+    .unwrap();
+    let custom_dns = Arc::new(DirectoryServer::new(Some(custom_dns_dir), None).unwrap());
+    assert_eq!(custom_dns.listen_port, 18090);
+    assert_eq!(custom_dns.rpc_port, 14322);
+    assert_eq!(custom_dns.transport, ConnectionType::CLEARNET);
+
+    let rpc_config = RPCConfig {
+        url: bitcoind.rpc_url().split_at(7).1.to_string(),
+        auth: Auth::CookieFile(bitcoind.params.cookie_file.clone()),
+        ..Default::default()
+    };
+    let custom_dns_clone = custom_dns.clone();
+    let custom_dns_thread = thread::spawn(move || {
+        start_directory_server(custom_dns_clone, Some(rpc_config)).unwrap();
+    });
+
+    // The maker only knows the custom directory server.
+    let maker_dir = env::temp_dir().join("coinswap").join("6102");
+    fs::create_dir_all(&maker_dir).unwrap();
+    fs::write(
+        maker_dir.join("config.toml"),
+        "require_fidelity_bond = false\ndirectory_server_addresses = 127.0.0.1:18090\n",
+    )
+    .unwrap();
+    let maker = Arc::new(
+        Maker::init(
+            Some(maker_dir),
+            Some("maker6102".to_string()),
+            Some(RPCConfig::from(test_framework.as_ref())),
+            Some(6102),
+            Some(3501),
+            None,
+            None,
+            None,
+            Some(ConnectionType::CLEARNET),
+            MakerBehavior::Normal,
+        )
+        .unwrap(),
+    );
+
+    fund_and_verify_maker(
+        vec![maker.as_ref()],
+        bitcoind,
+        4,
+        Amount::from_btc(0.05).unwrap(),
+    );
+
+    let maker_clone = maker.clone();
+    let maker_thread = thread::spawn(move || {
+        start_maker_server(maker_clone).unwrap();
+    });
+    while !maker.is_setup_complete.load(Relaxed) {
+        log::info!("Waiting for maker setup completion");
+        thread::sleep(Duration::from_secs(10));
+    }
+
+    // ----- Test -----
+
+    taker.config.directory_server_addresses = vec!["127.0.0.1:18090".to_string()];
+    let offerbook = taker.fetch_offers().unwrap();
+    let addresses = offerbook
+        .all_good_makers()
+        .iter()
+        .map(|offer| offer.address.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(addresses, vec!["127.0.0.1:6102"]);
+
+    // ----- Clean up -----
+    maker.shutdown.store(true, Relaxed);
+    maker_thread.join().unwrap();
+    custom_dns.shutdown.store(true, Relaxed);
+    custom_dns_thread.join().unwrap();
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
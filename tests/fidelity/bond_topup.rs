@@ -0,0 +1,130 @@
+use bitcoin::{absolute::LockTime, Amount};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{top_up_fidelity_bond, MakerBehavior},
+    utill::DEFAULT_TX_FEE_RATE,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// Test the automatic fidelity bond top-up of the Maker.
+///
+/// - The Maker starts with a small bond, and the auto top-up enabled via the maker config.
+/// - Earnings are simulated by sending coins to the Maker's wallet.
+/// - Each top-up creates a bigger bond, up to the target, while the spendable balance never drops
+///   below the reserve.
+#[test]
+fn test_maker_bond_topup() {
+    // ---- Setup ----
+    let test_env = Arc::new(TestEnv::init());
+    let bitcoind = &test_env.bitcoind;
+
+    let target = Amount::from_btc(0.08).unwrap();
+    let reserve = Amount::from_btc(0.02).unwrap();
+
+    let maker = test_env.init_maker(
+        6102,
+        6103,
+        Some(&format!(
+            "auto_bond_topup = true\nbond_topup_target = {}\nbond_topup_reserve = {}\n",
+            target.to_sat(),
+            reserve.to_sat()
+        )),
+        MakerBehavior::Normal,
+    );
+
+    // Keep mining, so the bond txs confirm.
+    let stop_mining = Arc::new(AtomicBool::new(false));
+    let miner = {
+        let test_env = test_env.clone();
+        let stop_mining = stop_mining.clone();
+        thread::spawn(move || {
+            while !stop_mining.load(Relaxed) {
+                generate_blocks(&test_env.bitcoind, 1);
+                thread::sleep(Duration::from_secs(1));
+            }
+        })
+    };
+
+    let earn = |amount: Amount| {
+        let address = maker
+            .get_wallet()
+            .write()
+            .unwrap()
+            .get_next_external_address()
+            .unwrap();
+        send_to_address(bitcoind, &address, amount);
+        generate_blocks(bitcoind, 1);
+        maker.get_wallet().write().unwrap().sync().unwrap();
+    };
+
+    let highest_bond_amount = || {
+        let wallet = maker.get_wallet().read().unwrap();
+        let i = wallet.get_highest_fidelity_index().unwrap().unwrap();
+        wallet.get_fidelity_bonds()[&i].0.amount
+    };
+
+    let spendable = || {
+        maker
+            .get_wallet()
+            .read()
+            .unwrap()
+            .get_balances()
+            .unwrap()
+            .spendable
+    };
+
+    // Without a bond, the top-up leaves the initial bond creation to the server setup.
+    earn(Amount::from_btc(0.05).unwrap());
+    assert_eq!(top_up_fidelity_bond(&maker).unwrap(), None);
+
+    let initial_bond = Amount::from_btc(0.01).unwrap();
+    {
+        let current_height = bitcoind.client.get_block_count().unwrap() as u32;
+        let mut wallet = maker.get_wallet().write().unwrap();
+        wallet
+            .create_fidelity(
+                initial_bond,
+                LockTime::from_height(current_height + 950).unwrap(),
+                DEFAULT_TX_FEE_RATE,
+            )
+            .unwrap();
+        wallet.sync().unwrap();
+    }
+    assert_eq!(highest_bond_amount(), initial_bond);
+
+    // ----- Test -----
+
+    // The earnings above the reserve go into a bigger bond.
+    assert!(top_up_fidelity_bond(&maker).unwrap().is_some());
+    let first_topup = highest_bond_amount();
+    assert!(first_topup > initial_bond);
+    assert!(first_topup < target);
+    assert!(spendable() >= reserve);
+
+    // Not enough new earnings for a bigger bond.
+    assert_eq!(top_up_fidelity_bond(&maker).unwrap(), None);
+
+    // More earnings grow the bond up to the target.
+    earn(Amount::from_btc(0.1).unwrap());
+    assert!(top_up_fidelity_bond(&maker).unwrap().is_some());
+    assert_eq!(highest_bond_amount(), target);
+    assert!(spendable() >= reserve);
+
+    // The target is reached.
+    earn(Amount::from_btc(0.1).unwrap());
+    assert_eq!(top_up_fidelity_bond(&maker).unwrap(), None);
+
+    // ----- Clean up -----
+    stop_mining.store(true, Relaxed);
+    miner.join().unwrap();
+    let _ = bitcoind.client.stop().unwrap();
+}
@@ -6,9 +6,14 @@ use coinswap::{
     taker::TakerBehavior,
     utill::{ConnectionType, DEFAULT_TX_FEE_RATE},
 };
+#[path = "../test_framework/mod.rs"]
 mod test_framework;
 use test_framework::*;
 
+mod bond_topup;
+mod maker_check;
+mod wallet_guard;
+
 use std::{assert_eq, sync::atomic::Ordering::Relaxed, thread, time::Duration};
 
 /// Test Fidelity Bond Creation and Redemption
@@ -18,7 +23,8 @@ use std::{assert_eq, sync::atomic::Ordering::Relaxed, thread, time::Duration};
 /// - The Maker starts with insufficient funds to create a fidelity bond (0.04 BTC),
 ///   triggering log messages requesting more funds.
 /// - Once provided with sufficient funds (1 BTC), the Maker creates the first fidelity bond (0.05 BTC).
-/// - A second fidelity bond (0.08 BTC) is created and its higher value is verified.
+/// - A second fidelity bond (0.08 BTC) is created and its higher value is verified. The running Maker
+///   then advertises it in its offer.
 /// - The test simulates bond maturity by advancing the blockchain height and redeems them sequentially,
 ///   verifying correct balances and proper bond status updates after redemption.
 #[test]
@@ -26,7 +32,7 @@ fn test_fidelity() {
     // ---- Setup ----
     let makers_config_map = [((6102, None), MakerBehavior::Normal)];
 
-    let (test_framework, _, makers, directory_server_instance, block_generation_handle) =
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
         TestFramework::init(
             makers_config_map.into(),
             TakerBehavior::Normal,
@@ -64,10 +70,6 @@ fn test_fidelity() {
     generate_blocks(bitcoind, 1);
 
     thread::sleep(Duration::from_secs(6));
-    // stop the maker server
-    maker.shutdown.store(true, Relaxed);
-
-    let _ = maker_thread.join().unwrap();
 
     // Verify that the fidelity bond is created correctly.
     let first_maturity_height = {
@@ -93,6 +95,12 @@ fn test_fidelity() {
         assert_eq!(bond.amount, Amount::from_sat(5000000));
         assert!(!is_spent);
 
+        // The auto-created bond is reflected in the balances.
+        assert_eq!(
+            wallet_read.get_balances().unwrap().fidelity,
+            Amount::from_sat(5000000)
+        );
+
         bond.lock_time.to_consensus_u32()
     };
 
@@ -128,6 +136,38 @@ fn test_fidelity() {
         bond.lock_time.to_consensus_u32()
     };
 
+    // The running Maker advertises the new bond in its offer, from its next periodic bond check.
+    let mut attempts = 0;
+    let advertised_bond = loop {
+        let offerbook = taker.fetch_offers().unwrap();
+        let offers = offerbook.all_good_makers();
+        assert_eq!(offers.len(), 1);
+        let bond = offers[0].fidelity_bond().unwrap().clone();
+        if bond.amount == Amount::from_sat(8000000) {
+            break bond;
+        }
+        attempts += 1;
+        assert!(attempts < 20, "The Maker kept advertising its first bond");
+        log::info!("Waiting for the Maker to advertise its new bond");
+        thread::sleep(Duration::from_secs(5));
+    };
+    // It is the Maker's highest value bond.
+    {
+        let wallet_read = maker.get_wallet().read().unwrap();
+        let highest_bond_index = wallet_read.get_highest_fidelity_index().unwrap().unwrap();
+        let (bond, _, _) = wallet_read
+            .get_fidelity_bonds()
+            .get(&highest_bond_index)
+            .unwrap();
+        assert_eq!(&advertised_bond, bond);
+        assert!(wallet_read.calculate_bond_value(&advertised_bond).unwrap() > Amount::ZERO);
+    }
+
+    // Stop the maker server, so it doesn't redeem the bonds itself.
+    maker.shutdown.store(true, Relaxed);
+
+    let _ = maker_thread.join().unwrap();
+
     // Verify balances
     {
         let wallet_read = maker.get_wallet().read().unwrap();
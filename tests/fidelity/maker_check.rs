@@ -0,0 +1,134 @@
+use bitcoin::Amount;
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{start_maker_server, Maker, MakerBehavior},
+    utill::{ConnectionType, DEFAULT_TX_FEE_RATE},
+};
+use std::{
+    sync::atomic::Ordering::Relaxed,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::test_framework::*;
+
+/// Test the Maker's periodic self-check of its fidelity bond.
+///
+/// - The Maker sets up a short timelocked bond, and serves valid offers.
+/// - Once the bond matures, it is redeemed out-of-band by a second Maker instance of the same wallet
+///   file, bypassing the running Maker's bond management.
+/// - The Maker must detect the spent bond, mark it as spent in the wallet, and stop serving offers.
+///
+/// Blocks are mined manually here, so the bond can be spent before the Maker redeems it by itself.
+#[test]
+fn test_maker_detects_spent_fidelity_bond() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+    let temp_dir = &test_env.temp_dir;
+    let rpc_config = test_env.rpc_config();
+
+    let directory_server_instance = test_env.start_directory_server();
+
+    let maker = test_env.init_maker(
+        6102,
+        6103,
+        Some("fidelity_timelock = 30\n"),
+        MakerBehavior::Normal,
+    );
+
+    let mut taker = test_env.init_taker(None);
+
+    // Enough for the 0.05 BTC bond and some swap liquidity, but not for a second bond.
+    let maker_addr = maker
+        .get_wallet()
+        .write()
+        .unwrap()
+        .get_next_external_address()
+        .unwrap();
+    send_to_address(bitcoind, &maker_addr, Amount::from_btc(0.08).unwrap());
+    generate_blocks(bitcoind, 1);
+
+    let maker_clone = maker.clone();
+    let maker_thread = thread::spawn(move || start_maker_server(maker_clone));
+
+    // Confirm the bond, until the server setup completes.
+    while !maker.is_setup_complete.load(Relaxed) {
+        generate_blocks(bitcoind, 1);
+        thread::sleep(Duration::from_secs(2));
+    }
+
+    // ----- Test -----
+
+    // The offer is served with a valid bond.
+    let offerbook = taker.fetch_offers().unwrap();
+    assert_eq!(offerbook.all_good_makers().len(), 1);
+
+    // A second instance of the same wallet file, e.g. a manual wallet operation, doesn't share the
+    // running Maker's bond state.
+    let out_of_band_maker = Maker::init(
+        Some(temp_dir.join("6102")),
+        Some("maker6102".to_string()),
+        Some(rpc_config),
+        Some(16102),
+        Some(13502),
+        None,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+        MakerBehavior::Normal,
+    )
+    .unwrap();
+
+    // Mature and redeem the bond out-of-band. The wallet lock keeps the Maker from
+    // noticing the maturity, and redeeming the bond itself in between.
+    {
+        let _maker_wallet_lock = maker.get_wallet().write().unwrap();
+        let mut out_of_band_wallet = out_of_band_maker.get_wallet().write().unwrap();
+        let (bond, _, is_spent) = out_of_band_wallet.get_fidelity_bonds().get(&0).unwrap();
+        assert!(!is_spent);
+        let maturity_height = bond.lock_time.to_consensus_u32() as u64;
+
+        let current_height = bitcoind.client.get_block_count().unwrap();
+        generate_blocks(bitcoind, maturity_height - current_height + 1);
+
+        out_of_band_wallet.sync().unwrap();
+        out_of_band_wallet
+            .redeem_fidelity(0, DEFAULT_TX_FEE_RATE)
+            .unwrap();
+        assert!(out_of_band_wallet.get_fidelity_bonds().get(&0).unwrap().2);
+    }
+    generate_blocks(bitcoind, 1);
+
+    // The Maker detects the spent bond, and marks it in the wallet.
+    let start = Instant::now();
+    loop {
+        let is_spent = maker
+            .get_wallet()
+            .read()
+            .unwrap()
+            .get_fidelity_bonds()
+            .get(&0)
+            .unwrap()
+            .2;
+        if is_spent {
+            break;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(60),
+            "Maker did not detect the spent fidelity bond"
+        );
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    // The Maker no longer serves offers, not even ones with the invalid bond.
+    let offerbook = taker.fetch_offers().unwrap();
+    assert!(offerbook.all_makers().is_empty());
+    assert!(taker.get_bad_makers().is_empty());
+
+    // ----- Clean up -----
+    maker.shutdown.store(true, Relaxed);
+    maker_thread.join().unwrap().unwrap();
+    directory_server_instance.shutdown.store(true, Relaxed);
+    let _ = bitcoind.client.stop().unwrap();
+}
@@ -0,0 +1,152 @@
+use bitcoin::{absolute::LockTime, Amount, Network, OutPoint};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    utill::DEFAULT_TX_FEE_RATE,
+    wallet::{Destination, WalletError},
+};
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// Test that no spend path of the wallet touches a fidelity bond.
+///
+/// - The wallet creates a fidelity bond, and sends all its other coins away.
+/// - Coin selection, spends, sweeps and consolidation find nothing to spend, and refuse.
+/// - Explicitly spending the bond, as a payment or a sweep, fails with `CannotSpendFidelityBond`, both
+///   through `spend_from_wallet` and `spend_coins`.
+/// - The bond is left untouched.
+#[test]
+fn test_wallet_fidelity_guard() {
+    // ---- Setup ----
+    let test_env = Arc::new(TestEnv::init());
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(None);
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    // Keep mining, so the bond tx confirms.
+    let stop_mining = Arc::new(AtomicBool::new(false));
+    let miner = {
+        let test_env = test_env.clone();
+        let stop_mining = stop_mining.clone();
+        thread::spawn(move || {
+            while !stop_mining.load(Relaxed) {
+                generate_blocks(&test_env.bitcoind, 1);
+                thread::sleep(Duration::from_secs(1));
+            }
+        })
+    };
+
+    let bond_amount = Amount::from_btc(0.01).unwrap();
+    let current_height = bitcoind.client.get_block_count().unwrap() as u32;
+    let wallet = taker.get_wallet_mut();
+    wallet
+        .create_fidelity(
+            bond_amount,
+            LockTime::from_height(current_height + 500).unwrap(),
+            DEFAULT_TX_FEE_RATE,
+        )
+        .unwrap();
+    stop_mining.store(true, Relaxed);
+    miner.join().unwrap();
+    wallet.sync().unwrap();
+
+    // Send every other coin away, leaving only the bond.
+    let external_address = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+    wallet
+        .send_to_address(
+            Destination::Sweep(external_address.clone()),
+            DEFAULT_TX_FEE_RATE,
+        )
+        .unwrap();
+    generate_blocks(bitcoind, 1);
+    wallet.sync().unwrap();
+
+    let balances = wallet.get_balances().unwrap();
+    assert_eq!(balances.spendable, Amount::ZERO);
+    assert_eq!(balances.fidelity, bond_amount);
+
+    // ----- Test -----
+
+    let bond_coins = wallet.list_fidelity_spend_info().unwrap();
+    assert_eq!(bond_coins.len(), 1);
+    let bond_outpoint = OutPoint::new(bond_coins[0].0.txid, bond_coins[0].0.vout);
+    assert_eq!(
+        wallet.fidelity_bond_outpoints(),
+        HashSet::from([bond_outpoint])
+    );
+
+    // Coin selection, for swap funding and spends, never picks the bond.
+    assert!(wallet
+        .coin_select(Amount::from_sat(10_000))
+        .unwrap()
+        .is_empty());
+    assert!(wallet.list_spendable_with_weight().unwrap().is_empty());
+
+    // Without the bond, the wallet has nothing to spend.
+    let payment = Destination::Multi(vec![(external_address.clone(), Amount::from_sat(10_000))]);
+    let no_funds = |result: Result<(), WalletError>| match result {
+        Err(WalletError::InsufficientFund { available: 0, .. }) => {}
+        other => panic!("Expected InsufficientFund error, got {:?}", other),
+    };
+    no_funds(
+        wallet
+            .build_spend(payment.clone(), DEFAULT_TX_FEE_RATE)
+            .map(|_| ()),
+    );
+    no_funds(
+        wallet
+            .send_to_address(payment.clone(), DEFAULT_TX_FEE_RATE)
+            .map(|_| ()),
+    );
+    no_funds(
+        wallet
+            .send_to_address(
+                Destination::Sweep(external_address.clone()),
+                DEFAULT_TX_FEE_RATE,
+            )
+            .map(|_| ()),
+    );
+    match wallet.consolidate(10, Amount::from_sat(DEFAULT_TX_FEE_RATE as u64)) {
+        Err(WalletError::General(msg)) if msg.starts_with("Nothing to consolidate, 0") => {}
+        other => panic!("Expected nothing to consolidate, got {:?}", other),
+    }
+
+    // Explicit spends of the bond are refused.
+    for destination in [payment, Destination::Sweep(external_address)] {
+        let results = [
+            wallet.spend_from_wallet(DEFAULT_TX_FEE_RATE, destination.clone(), &bond_coins),
+            wallet.spend_coins(&bond_coins, destination, DEFAULT_TX_FEE_RATE),
+        ];
+        for result in results {
+            match result {
+                Err(WalletError::CannotSpendFidelityBond { outpoint }) => {
+                    assert_eq!(outpoint, bond_outpoint)
+                }
+                other => panic!("Expected CannotSpendFidelityBond error, got {:?}", other),
+            }
+        }
+    }
+
+    // The bond is untouched.
+    wallet.sync().unwrap();
+    assert_eq!(wallet.get_balances().unwrap().fidelity, bond_amount);
+    assert_eq!(wallet.list_fidelity_spend_info().unwrap(), bond_coins);
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
@@ -36,6 +36,7 @@ impl MakerCli {
 
         let data_dir = temp_dir.join("maker");
         fs::create_dir_all(&data_dir).unwrap();
+        fs::write(data_dir.join("config.toml"), "allow_seed_export = true\n").unwrap();
 
         MakerCli { data_dir, bitcoind }
     }
@@ -96,12 +97,18 @@ impl MakerCli {
     }
 
     /// Starts the maker server, performs initial setup, and waits for key events.  
-    /// Returns the maker process and a `Receiver<String>` for stdout messages.
-    fn start_and_configure_makerd(&self) -> (Receiver<String>, Child) {
+    /// Returns the maker process, a `Receiver<String>` for stdout messages, and the wallet mnemonic
+    /// shown at wallet creation.
+    fn start_and_configure_makerd(&self) -> (Receiver<String>, Child, String) {
         let (rx, makerd) = self.start_makerd();
 
+        let mut mnemonic = None;
         let (amount, addrs) = loop {
             let log_message = rx.recv().unwrap();
+            if log_message.contains("Backup the Wallet Mnemonics") {
+                let words = serde_json::from_str::<Vec<String>>(rx.recv().unwrap().trim()).unwrap();
+                mnemonic = Some(words.join(" "));
+            }
             if log_message.contains("Send at least 0.05001000 BTC") {
                 let parts: Vec<&str> = log_message.split_whitespace().collect();
                 let amount = Amount::from_str_in(parts[7], bitcoin::Denomination::Bitcoin).unwrap();
@@ -137,7 +144,11 @@ impl MakerCli {
         // sync the wallet cache
         // maker_cli.execute_maker_cli(&["sync-wallet"])
 
-        (rx, makerd)
+        (
+            rx,
+            makerd,
+            mnemonic.expect("wallet mnemonic should be shown"),
+        )
     }
 
     /// Executes the maker CLI command with given arguments and returns the output.
@@ -169,14 +180,14 @@ fn test_maker() {
     let dns_dir = maker_cli.data_dir.parent().unwrap();
     let mut dns = start_dns(dns_dir, &maker_cli.bitcoind);
 
-    let (rx, maker) = maker_cli.start_and_configure_makerd();
+    let (rx, maker, mnemonic) = maker_cli.start_and_configure_makerd();
 
     println!("testing for fidelity bond being registered even in mempool");
 
     let (rx, mut maker) = test_bond_registration_before_confirmation(&maker_cli, maker, rx);
 
     println!("Testing maker cli");
-    test_maker_cli(&maker_cli, &rx);
+    test_maker_cli(&maker_cli, &rx, &mnemonic);
 
     maker.wait().unwrap();
 
@@ -221,7 +232,7 @@ fn test_bond_registration_before_confirmation(
     (rx, maker)
 }
 
-fn test_maker_cli(maker_cli: &MakerCli, rx: &Receiver<String>) {
+fn test_maker_cli(maker_cli: &MakerCli, rx: &Receiver<String>, mnemonic: &str) {
     // Ping check
     let ping_resp = maker_cli.execute_maker_cli(&["send-ping"]);
     await_message(rx, "RPC request received: Ping");
@@ -325,6 +336,16 @@ fn test_maker_cli(maker_cli: &MakerCli, rx: &Receiver<String>) {
     let seed_utxo = maker_cli.execute_maker_cli(&["list-utxo"]);
     assert_eq!(seed_utxo.matches("ListUnspentResultEntry").count(), 3);
 
+    // Seed export is refused without confirmation.
+    let refused = maker_cli.execute_maker_cli(&["export-seed"]);
+    await_message(rx, "RPC request received: ExportSeed { confirm: false }");
+    assert!(refused.contains("Seed export not confirmed"));
+
+    // Confirmed export returns the mnemonic shown at wallet creation.
+    let exported = maker_cli.execute_maker_cli(&["export-seed", "--confirm"]);
+    await_message(rx, "THE WALLET SEED WAS EXPORTED OVER RPC");
+    assert_eq!(exported, mnemonic);
+
     // Shutdown check
     let stop = maker_cli.execute_maker_cli(&["stop"]);
     await_message(rx, "RPC request received: Stop");
@@ -0,0 +1,158 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, Taker, TakerBehavior},
+    utill::ConnectionType,
+    wallet::RPCConfig,
+};
+use std::{
+    env, fs,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Cap on the value locked in live contracts, fitting a single swap.
+const MAX_CONTRACT_EXPOSURE: u64 = 700_000;
+
+/// Test the maker cap on the value locked in the contracts of live swaps.
+///
+/// - The Makers accept up to 700k sats in live contracts, and recover unfinished swaps after 60 secs.
+/// - A first Taker does a 500k sats swap, and drops it after the full setup, leaving it live at the Makers.
+/// - A second Taker's 500k sats swap would take the Makers above the cap, and is declined for capacity,
+///   before anything is funded. The Makers aren't banned for it.
+/// - Once the first swap is recovered, the second Taker's swap goes through.
+#[test]
+fn test_maker_contract_exposure() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (
+        test_framework,
+        mut dropping_taker,
+        makers,
+        directory_server_instance,
+        block_generation_handle,
+    ) = TestFramework::init_with_config_overrides(
+        makers_config_map.into(),
+        TakerBehavior::DropConnectionAfterFullSetup,
+        ConnectionType::CLEARNET,
+        ConfigOverrides {
+            maker: Some(format!(
+                "max_contract_exposure = {}\nidle_connection_timeout = 3600\nswap_timeout = 60\n",
+                MAX_CONTRACT_EXPOSURE
+            )),
+            ..Default::default()
+        },
+    );
+
+    let bitcoind = &test_framework.bitcoind;
+    let temp_dir = env::temp_dir().join("coinswap");
+
+    let mut taker = Taker::init(
+        Some(temp_dir.join("taker2")),
+        Some("taker2-wallet".to_string()),
+        Some(RPCConfig::from(test_framework.as_ref())),
+        TakerBehavior::Normal,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+    )
+    .unwrap();
+
+    fund_and_verify_taker(
+        &mut dropping_taker,
+        bitcoind,
+        3,
+        Amount::from_btc(0.05).unwrap(),
+    );
+    let org_taker_spend_balance =
+        fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || start_maker_server(maker_clone).unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    for maker in &makers {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(5));
+        }
+    }
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+
+    // The first swap is left live at the Makers, within the cap.
+    dropping_taker.do_coinswap(swap_params.clone()).unwrap();
+    for maker in &makers {
+        let exposure = maker.contract_exposure().unwrap();
+        assert!(exposure > Amount::ZERO);
+        assert!(exposure <= Amount::from_sat(MAX_CONTRACT_EXPOSURE));
+    }
+
+    // The next one would go above it.
+    assert!(taker.do_coinswap(swap_params.clone()).is_err());
+    for port in [6102, 16102] {
+        let events =
+            fs::read_to_string(temp_dir.join(port.to_string()).join("events.log")).unwrap();
+        assert!(events.contains("CapacityExceeded"));
+    }
+
+    // Declined before the Taker funded anything, and without banning the Makers.
+    let wallet = taker.get_wallet_mut();
+    wallet.sync().unwrap();
+    let balances = wallet.get_balances().unwrap();
+    assert_eq!(balances.spendable, org_taker_spend_balance);
+    assert_eq!(balances.contract, Amount::ZERO);
+    assert!(taker.list_bad_makers().is_empty());
+
+    // The Makers recover the first swap, freeing up their capacity.
+    let wait_start = Instant::now();
+    while !makers
+        .iter()
+        .all(|maker| maker.contract_exposure().unwrap() == Amount::ZERO)
+    {
+        assert!(
+            wait_start.elapsed() < Duration::from_secs(600),
+            "Makers did not recover the first swap"
+        );
+        thread::sleep(Duration::from_secs(5));
+    }
+
+    taker.do_coinswap(swap_params).unwrap();
+    assert_eq!(taker.get_swap_history().unwrap().len(), 1);
+    for maker in &makers {
+        assert_eq!(maker.contract_exposure().unwrap(), Amount::ZERO);
+    }
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,64 @@
+use bitcoin::Amount;
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::maker::{autosave_wallet, MakerBehavior};
+use std::{fs, sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+use crate::test_framework::*;
+
+/// Test the periodic wallet auto-save of the Maker.
+///
+/// - The auto-save is enabled via the maker config.
+/// - The wallet is mutated in memory, without an explicit save.
+/// - After an auto-save interval, the wallet file on disk must reflect the change.
+#[test]
+fn test_maker_wallet_autosave() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+    let temp_dir = &test_env.temp_dir;
+
+    let maker = test_env.init_maker(
+        6102,
+        6103,
+        Some("wallet_autosave_interval = 1\n"),
+        MakerBehavior::Normal,
+    );
+    let wallet_path = temp_dir.join("6102").join("wallets").join("maker6102");
+
+    let maker_clone = maker.clone();
+    let autosave_thread = thread::spawn(move || autosave_wallet(maker_clone).unwrap());
+
+    // ----- Test -----
+    let address = maker
+        .get_wallet()
+        .write()
+        .unwrap()
+        .get_next_external_address()
+        .unwrap();
+    send_to_address(bitcoind, &address, Amount::from_btc(0.05).unwrap());
+    generate_blocks(bitcoind, 1);
+
+    // The sync updates the wallet's cached offer size only in memory. The file is read while
+    // still holding the write lock, so the auto-save can't run in between.
+    let stale_file = {
+        let mut wallet = maker.get_wallet().write().unwrap();
+        wallet.sync().unwrap();
+        fs::read(&wallet_path).unwrap()
+    };
+
+    thread::sleep(Duration::from_secs(3));
+    let autosaved_file = fs::read(&wallet_path).unwrap();
+    assert_ne!(
+        stale_file, autosaved_file,
+        "Wallet change was not auto-saved"
+    );
+
+    // With no further changes, subsequent auto-saves write the same data.
+    thread::sleep(Duration::from_secs(3));
+    assert_eq!(fs::read(&wallet_path).unwrap(), autosaved_file);
+
+    // ----- Clean up -----
+    maker.shutdown.store(true, Relaxed);
+    autosave_thread.join().unwrap();
+    let _ = bitcoind.client.stop().unwrap();
+}
@@ -0,0 +1,127 @@
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, Maker, MakerBehavior, MakerError},
+    taker::TakerBehavior,
+    utill::ConnectionType,
+    wallet::RPCConfig,
+};
+use std::{
+    env, fs,
+    net::{IpAddr, SocketAddr, TcpStream, UdpSocket},
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// The address of a non-loopback interface of this machine, the one routing to the outside.
+fn external_interface_ip() -> IpAddr {
+    let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+    // No packet is sent, this only selects the route.
+    socket.connect("8.8.8.8:80").unwrap();
+    socket.local_addr().unwrap().ip()
+}
+
+/// Test the bind address of the Maker's swap listener.
+///
+/// - A Maker bound to loopback accepts connections on loopback, and refuses them on an external interface.
+/// - An invalid bind address fails the Maker startup, and so does a non-loopback one over Tor.
+#[test]
+fn test_maker_bind_address() {
+    // ---- Setup ----
+    let makers_config_map = [((6102, None), MakerBehavior::Normal)];
+
+    let (test_framework, _, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    // The Makers bind to loopback by default.
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || start_maker_server(maker_clone).unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    let timeout = Duration::from_secs(5);
+
+    let loopback = SocketAddr::from(([127, 0, 0, 1], 6102));
+    assert!(TcpStream::connect_timeout(&loopback, timeout).is_ok());
+
+    let external_ip = external_interface_ip();
+    assert!(!external_ip.is_loopback());
+    let external = SocketAddr::new(external_ip, 6102);
+    assert!(TcpStream::connect_timeout(&external, timeout).is_err());
+
+    // An invalid bind address is refused at startup.
+    let bad_maker_dir = env::temp_dir().join("coinswap").join("bad-maker");
+    fs::create_dir_all(&bad_maker_dir).unwrap();
+    fs::write(
+        bad_maker_dir.join("config.toml"),
+        "bind_address = not-an-ip\n",
+    )
+    .unwrap();
+    let bad_maker = Maker::init(
+        Some(bad_maker_dir.clone()),
+        Some("bad-maker".to_string()),
+        Some(RPCConfig::from(test_framework.as_ref())),
+        Some(6202),
+        Some(3600),
+        None,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+        MakerBehavior::Normal,
+    );
+    assert!(matches!(bad_maker, Err(MakerError::General(_))));
+
+    // A Tor maker listening on all interfaces is refused at startup.
+    fs::write(
+        bad_maker_dir.join("config.toml"),
+        "bind_address = 0.0.0.0\n",
+    )
+    .unwrap();
+    let exposed_tor_maker = Maker::init(
+        Some(bad_maker_dir),
+        Some("bad-maker".to_string()),
+        Some(RPCConfig::from(test_framework.as_ref())),
+        Some(6202),
+        Some(3600),
+        None,
+        None,
+        None,
+        Some(ConnectionType::TOR),
+        MakerBehavior::Normal,
+    );
+    assert!(matches!(exposed_tor_maker, Err(MakerError::General(_))));
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,96 @@
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// Test a coinswap with bondless makers on regtest.
+///
+/// - Both makers run with `require_fidelity_bond = false`, and never create a fidelity bond.
+/// - The directory accepts their addresses without a proof, and the Taker, allowing bondless offers,
+///   completes the swap through them.
+#[test]
+fn test_maker_bondless() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init_with_config_overrides(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+            ConfigOverrides {
+                maker: Some("require_fidelity_bond = false\n".to_string()),
+                ..Default::default()
+            },
+        );
+    taker.config.allow_bondless_offers = true;
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    // ----- Test -----
+
+    assert_eq!(taker.get_swap_history().unwrap().len(), 1);
+    assert!(taker.list_bad_makers().is_empty());
+
+    // The swap went through without any fidelity bond.
+    for maker in &makers {
+        let wallet = maker.get_wallet().read().unwrap();
+        assert!(wallet.get_fidelity_bonds().is_empty());
+        assert_eq!(wallet.get_balances().unwrap().fidelity, Amount::ZERO);
+    }
+
+    // ----- Clean up -----
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
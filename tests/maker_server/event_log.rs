@@ -0,0 +1,137 @@
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior, MakerEvent, MakerEventKind, RpcMsgReq, RpcMsgResp},
+    taker::{SwapParams, TakerBehavior},
+    utill::{read_message, send_message, ConnectionType},
+};
+use std::{
+    env, fs,
+    net::TcpStream,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// Reads the event log of the maker over RPC.
+fn get_events(rpc_addr: &str, since: u64, limit: usize) -> Vec<MakerEvent> {
+    let mut stream = TcpStream::connect(rpc_addr).unwrap();
+    send_message(&mut stream, &RpcMsgReq::GetEvents { since, limit }).unwrap();
+    let response_bytes = read_message(&mut stream).unwrap();
+    match serde_cbor::from_slice(&response_bytes).unwrap() {
+        RpcMsgResp::GetEventsResp(events) => events,
+        resp => panic!("Unexpected response: {:?}", resp),
+    }
+}
+
+/// Test the event log of the Maker.
+///
+/// - A coinswap is completed through two makers.
+/// - The start and completion of the swap are written to `events.log` in the maker's data directory,
+///   and can be read over RPC, filtered by their timestamp.
+#[test]
+fn test_maker_event_log() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    // ----- Test -----
+
+    // The first maker's RPC server, as assigned by the test framework.
+    let rpc_addr = "127.0.0.1:3501";
+
+    let events = get_events(rpc_addr, 0, 100);
+    let started = events
+        .iter()
+        .find(|e| matches!(e.kind, MakerEventKind::SwapStarted { .. }))
+        .expect("swap started event");
+    let swap_id = match &started.kind {
+        MakerEventKind::SwapStarted { id } => id.clone(),
+        _ => unreachable!(),
+    };
+    let completed = events
+        .iter()
+        .find(|e| {
+            e.kind
+                == MakerEventKind::SwapCompleted {
+                    id: swap_id.clone(),
+                }
+        })
+        .expect("swap completed event");
+    assert!(completed.timestamp >= started.timestamp);
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e.kind, MakerEventKind::SwapFailed { .. })));
+
+    // The since filter.
+    let since_start = get_events(rpc_addr, started.timestamp, 100);
+    assert!(since_start.contains(started));
+    assert!(since_start.contains(completed));
+    assert!(get_events(rpc_addr, completed.timestamp + 1, 100)
+        .iter()
+        .all(|e| e.timestamp > completed.timestamp));
+    assert_eq!(get_events(rpc_addr, 0, 1), events[..1].to_vec());
+
+    // Persisted as JSON lines in the data directory.
+    let log = fs::read_to_string(env::temp_dir().join("coinswap/6102/events.log")).unwrap();
+    assert_eq!(log.lines().count(), events.len());
+    assert!(log.contains(&format!(
+        "\"event\":\"swap_completed\",\"id\":\"{}\"",
+        swap_id
+    )));
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,90 @@
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::TakerBehavior,
+    utill::ConnectionType,
+};
+use std::sync::Arc;
+
+use crate::test_framework::*;
+
+use log::info;
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test Maker identity verification by the Taker.
+///
+/// Each Maker must prove control over the fidelity bond key committed in its own offer.
+/// An offer pointed at a different Maker's address must fail the verification, as that
+/// Maker can't sign for a bond it doesn't hold.
+#[test]
+fn test_verify_maker_identity() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(
+        makers_ref,
+        &test_framework.bitcoind,
+        4,
+        Amount::from_btc(0.05).unwrap(),
+    );
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for maker in &makers {
+        while !maker.is_setup_complete.load(Relaxed) {
+            info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    }
+
+    // ----- Test -----
+    let offers = taker
+        .fetch_offers()
+        .unwrap()
+        .all_good_makers()
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(offers.len(), 2);
+
+    for offer in &offers {
+        taker.verify_maker_identity(offer).unwrap();
+    }
+
+    // Point the first maker's offer at the second maker's address.
+    let mut spoofed_offer = offers[0].clone();
+    spoofed_offer.address = offers[1].address.clone();
+    assert!(taker.verify_maker_identity(&spoofed_offer).is_err());
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,87 @@
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior, RpcMsgReq, RpcMsgResp},
+    taker::TakerBehavior,
+    utill::{read_message, send_message, ConnectionType},
+};
+use std::{
+    net::TcpStream,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// Lists the makers known to the maker, over RPC.
+fn get_known_makers(rpc_addr: &str) -> Vec<String> {
+    let mut stream = TcpStream::connect(rpc_addr).unwrap();
+    send_message(&mut stream, &RpcMsgReq::GetKnownMakers).unwrap();
+    let response_bytes = read_message(&mut stream).unwrap();
+    match serde_cbor::from_slice(&response_bytes).unwrap() {
+        RpcMsgResp::GetKnownMakersResp(addresses) => addresses,
+        resp => panic!("Unexpected response: {:?}", resp),
+    }
+}
+
+/// Test listing the other makers registered at the directory server.
+///
+/// - Two makers register at the directory server.
+/// - Each of them sees the other over RPC, but not itself.
+#[test]
+fn test_maker_known_makers() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, _, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // The makers register at the directory server during their setup.
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+
+    // The RPC servers of the makers, as assigned by the test framework.
+    assert_eq!(get_known_makers("127.0.0.1:3501"), vec!["127.0.0.1:16102"]);
+    assert_eq!(get_known_makers("127.0.0.1:3502"), vec!["127.0.0.1:6102"]);
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
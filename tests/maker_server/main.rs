@@ -0,0 +1,18 @@
+//! Maker server functional tests, sharing one test binary.
+//!
+//! The tests are serialized on the shared regtest data directory by the [test_framework].
+#![cfg(feature = "integration-test")]
+#[path = "../test_framework/mod.rs"]
+mod test_framework;
+
+mod autosave;
+mod bind_address;
+mod bondless;
+mod event_log;
+mod identity;
+mod known_makers;
+mod max_locktime;
+mod node_sync;
+mod offer_rpc;
+mod port_in_use;
+mod utxo_consolidation;
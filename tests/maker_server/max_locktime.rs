@@ -0,0 +1,99 @@
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// Test the maker cap on the refund locktime proposed by the Taker.
+///
+/// - The Taker proposes a refund locktime far above the default `max_accepted_locktime` of the makers.
+/// - The first maker declines the proof of funding, and the Taker recovers its funding txs via the timelock.
+#[test]
+fn test_maker_max_accepted_locktime() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::ExcessiveRefundLocktime,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    let org_taker_spend_balance =
+        fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    // ----- Test -----
+
+    // The swap was declined by the first maker.
+    assert!(taker.get_swap_history().unwrap().is_empty());
+    assert_eq!(taker.list_bad_makers().len(), 1);
+
+    // No maker funded an outgoing contract.
+    for maker in &makers {
+        let balances = maker.get_wallet().read().unwrap().get_balances().unwrap();
+        assert_eq!(balances.contract, Amount::ZERO);
+        assert_eq!(balances.swap, Amount::ZERO);
+    }
+
+    // The Taker claims its funding back via the timelock, for only the mining fees.
+    taker.get_wallet_mut().sync().unwrap();
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+    assert!(balances.spendable > org_taker_spend_balance - Amount::from_sat(10_000));
+
+    // ----- Clean up -----
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
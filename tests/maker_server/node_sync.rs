@@ -0,0 +1,63 @@
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{Maker, MakerBehavior, MakerError},
+    utill::ConnectionType,
+};
+
+use crate::test_framework::*;
+
+/// Test the Maker startup self-diagnostic against a node in initial block download.
+///
+/// - A fresh regtest node, with no block mined yet, is in initial block download.
+/// - The Maker refuses to start with [MakerError::NodeNotSynced], so it never serves offers.
+/// - Once the node has synced, the same Maker starts.
+#[test]
+fn test_maker_requires_synced_node() {
+    // ---- Setup ----
+    let test_env = TestEnv::init_with_node(|dir| start_bitcoind(dir, true));
+    let bitcoind = &test_env.bitcoind;
+    let temp_dir = &test_env.temp_dir;
+    assert!(
+        bitcoind
+            .client
+            .get_blockchain_info()
+            .unwrap()
+            .initial_block_download
+    );
+
+    let rpc_config = test_env.rpc_config();
+    let init_maker = || {
+        Maker::init(
+            Some(temp_dir.join("maker")),
+            None,
+            Some(rpc_config.clone()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(ConnectionType::CLEARNET),
+            MakerBehavior::Normal,
+        )
+    };
+
+    // ----- Test -----
+    assert!(matches!(
+        init_maker(),
+        Err(MakerError::NodeNotSynced { .. })
+    ));
+
+    // Mining a fresh block ends the initial block download.
+    generate_blocks(bitcoind, 101);
+    assert!(
+        !bitcoind
+            .client
+            .get_blockchain_info()
+            .unwrap()
+            .initial_block_download
+    );
+    init_maker().unwrap();
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
@@ -0,0 +1,134 @@
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior, OfferFees, RpcMsgReq, RpcMsgResp},
+    taker::TakerBehavior,
+    utill::{read_message, send_message, ConnectionType},
+};
+use std::{net::TcpStream, sync::Arc};
+
+use crate::test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Sends a request to the maker RPC server, and returns its response.
+fn send_rpc_req(rpc_addr: &str, req: RpcMsgReq) -> RpcMsgResp {
+    let mut stream = TcpStream::connect(rpc_addr).unwrap();
+    send_message(&mut stream, &req).unwrap();
+    let response_bytes = read_message(&mut stream).unwrap();
+    serde_cbor::from_slice(&response_bytes).unwrap()
+}
+
+/// Test adjusting the Maker's offer over RPC, while it is running.
+///
+/// - Inconsistent bounds are rejected, and the offer is left untouched.
+/// - A new fee is set over RPC.
+/// - The next offerbook sync of the Taker must reflect the new fee.
+#[test]
+fn test_maker_set_offer_over_rpc() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // The first maker's RPC server, as assigned by the test framework.
+    let rpc_addr = "127.0.0.1:3501";
+
+    let old_terms = match send_rpc_req(rpc_addr, RpcMsgReq::GetOffer) {
+        RpcMsgResp::GetOfferResp(terms) => terms,
+        resp => panic!("Unexpected response: {:?}", resp),
+    };
+
+    // ----- Test -----
+
+    // Inconsistent bounds are rejected.
+    let resp = send_rpc_req(
+        rpc_addr,
+        RpcMsgReq::SetOffer {
+            min_size: 200_000,
+            max_size: 100_000,
+            fee: old_terms.fee,
+        },
+    );
+    assert!(matches!(resp, RpcMsgResp::ServerError(_)));
+    match send_rpc_req(rpc_addr, RpcMsgReq::GetOffer) {
+        RpcMsgResp::GetOfferResp(terms) => assert_eq!(terms, old_terms),
+        resp => panic!("Unexpected response: {:?}", resp),
+    }
+
+    let new_fee = OfferFees {
+        base_fee: old_terms.fee.base_fee * 2,
+        ..old_terms.fee
+    };
+    let resp = send_rpc_req(
+        rpc_addr,
+        RpcMsgReq::SetOffer {
+            min_size: old_terms.min_size,
+            max_size: 1_000_000,
+            fee: new_fee,
+        },
+    );
+    match resp {
+        RpcMsgResp::GetOfferResp(terms) => {
+            assert_eq!(terms.fee, new_fee);
+            assert_eq!(terms.max_size, 1_000_000);
+        }
+        resp => panic!("Unexpected response: {:?}", resp),
+    }
+
+    // The next offerbook sync picks up the new fee, while the other maker is unchanged.
+    let offerbook = taker.fetch_offers().unwrap();
+    let offers = offerbook.all_good_makers();
+    assert_eq!(offers.len(), 2);
+    for offer in offers {
+        if offer.address.to_string() == "127.0.0.1:6102" {
+            assert_eq!(offer.fees(), new_fee);
+        } else {
+            assert_eq!(offer.fees(), old_terms.fee);
+        }
+    }
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    thread::sleep(Duration::from_secs(10));
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,118 @@
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, Maker, MakerBehavior, MakerError},
+    taker::TakerBehavior,
+    utill::ConnectionType,
+    wallet::RPCConfig,
+};
+use std::{env, fs, path::Path, sync::Arc};
+
+use crate::test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Reads the value of `key` in the saved config file of `maker_dir`.
+fn read_config_port(maker_dir: &Path, key: &str) -> u16 {
+    fs::read_to_string(maker_dir.join("config.toml"))
+        .unwrap()
+        .lines()
+        .find_map(|line| line.strip_prefix(&format!("{} = ", key)))
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+/// Initializes a second maker in `dir_name`, with the given ports.
+fn init_maker(
+    rpc_config: &RPCConfig,
+    dir_name: &str,
+    network_port: u16,
+    rpc_port: u16,
+) -> Result<Maker, MakerError> {
+    Maker::init(
+        Some(env::temp_dir().join("coinswap").join(dir_name)),
+        Some(dir_name.to_string()),
+        Some(rpc_config.clone()),
+        Some(network_port),
+        Some(rpc_port),
+        None,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+        MakerBehavior::Normal,
+    )
+}
+
+/// Test the detection of maker ports already in use.
+///
+/// - A first maker runs its server on the swap port 6102 and the RPC port 3501.
+/// - A second maker configured with either of these ports fails to initialize with `PortInUse`.
+/// - With `auto_select_ports` set, the second maker picks free ports and saves them to its config.
+#[test]
+fn test_maker_port_in_use() {
+    // ---- Setup ----
+    let makers_config_map = [((6102, None), MakerBehavior::Normal)];
+
+    let (test_framework, _taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+    let rpc_config = RPCConfig::from(test_framework.as_ref());
+
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker = makers[0].clone();
+    let maker_thread = thread::spawn(move || start_maker_server(maker).unwrap());
+
+    while !makers[0].is_setup_complete.load(Relaxed) {
+        log::info!("Waiting for maker setup completion");
+        thread::sleep(Duration::from_secs(10));
+    }
+
+    // ----- Test -----
+
+    // The swap port is taken.
+    let err = init_maker(&rpc_config, "second-maker", 6102, 3602)
+        .err()
+        .unwrap();
+    assert!(
+        matches!(err, MakerError::PortInUse { port: 6102 }),
+        "Unexpected error: {:?}",
+        err
+    );
+
+    // The RPC port is taken.
+    let err = init_maker(&rpc_config, "second-maker", 16102, 3501)
+        .err()
+        .unwrap();
+    assert!(
+        matches!(err, MakerError::PortInUse { port: 3501 }),
+        "Unexpected error: {:?}",
+        err
+    );
+
+    // Free ports are picked instead, and saved to the config.
+    let maker_dir = env::temp_dir().join("coinswap").join("auto-maker");
+    fs::create_dir_all(&maker_dir).unwrap();
+    fs::write(maker_dir.join("config.toml"), "auto_select_ports = true\n").unwrap();
+    init_maker(&rpc_config, "auto-maker", 6102, 3501).unwrap();
+
+    let network_port = read_config_port(&maker_dir, "network_port");
+    let rpc_port = read_config_port(&maker_dir, "rpc_port");
+    assert_ne!(network_port, 6102);
+    assert_ne!(rpc_port, 3501);
+    assert_ne!(network_port, rpc_port);
+
+    // ----- Clean up -----
+    makers[0].shutdown.store(true, Relaxed);
+    maker_thread.join().unwrap();
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,104 @@
+use bitcoin::{absolute::LockTime, Amount};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{consolidate_utxos, MakerBehavior},
+    utill::DEFAULT_TX_FEE_RATE,
+};
+
+use crate::test_framework::*;
+
+/// Test the automatic UTXO consolidation of the Maker.
+///
+/// - The Maker holds a fidelity bond, and the auto consolidation enabled via the maker config.
+/// - Below `consolidation_min_utxos` regular UTXOs, nothing is done.
+/// - Once enough small UTXOs are received, they are combined into a single one. The regular balance
+///   is preserved, minus the tx fee, and the fidelity bond is left untouched.
+#[test]
+fn test_maker_utxo_consolidation() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    let maker = test_env.init_maker(
+        6102,
+        6103,
+        Some("auto_consolidate = true\nconsolidation_min_utxos = 10\nconsolidation_max_feerate = 2.0\n"),
+        MakerBehavior::Normal,
+    );
+
+    let fund = |count: usize, amount: Amount| {
+        for _ in 0..count {
+            let address = maker
+                .get_wallet()
+                .write()
+                .unwrap()
+                .get_next_external_address()
+                .unwrap();
+            send_to_address(bitcoind, &address, amount);
+        }
+        generate_blocks(bitcoind, 1);
+        maker.get_wallet().write().unwrap().sync().unwrap();
+    };
+
+    let regular_utxo_count = || {
+        maker
+            .get_wallet()
+            .read()
+            .unwrap()
+            .list_descriptor_utxo_spend_info()
+            .unwrap()
+            .len()
+    };
+
+    // A fidelity bond, whose change output is a regular UTXO.
+    fund(1, Amount::from_btc(0.05).unwrap());
+    let bond_amount = Amount::from_btc(0.01).unwrap();
+    {
+        let current_height = bitcoind.client.get_block_count().unwrap() as u32;
+        let mut wallet = maker.get_wallet().write().unwrap();
+        wallet
+            .create_fidelity(
+                bond_amount,
+                LockTime::from_height(current_height + 950).unwrap(),
+                DEFAULT_TX_FEE_RATE,
+            )
+            .unwrap();
+    }
+    generate_blocks(bitcoind, 1);
+    maker.get_wallet().write().unwrap().sync().unwrap();
+
+    // ----- Test -----
+
+    // Not enough UTXOs yet.
+    fund(5, Amount::from_sat(100_000));
+    assert_eq!(regular_utxo_count(), 6);
+    assert_eq!(consolidate_utxos(&maker).unwrap(), None);
+
+    fund(6, Amount::from_sat(100_000));
+    assert_eq!(regular_utxo_count(), 12);
+    let before = maker.get_wallet().read().unwrap().get_balances().unwrap();
+
+    let txid = consolidate_utxos(&maker).unwrap().unwrap();
+    generate_blocks(bitcoind, 1);
+    maker.get_wallet().write().unwrap().sync().unwrap();
+
+    let tx = bitcoind.client.get_raw_transaction(&txid, None).unwrap();
+    assert_eq!(tx.input.len(), 12);
+    assert_eq!(tx.output.len(), 1);
+    let fee = before.regular - tx.output[0].value;
+    assert!(fee > Amount::ZERO);
+    assert!(fee < Amount::from_sat(10_000));
+
+    // Fewer UTXOs, holding the same value minus the fee.
+    let after = maker.get_wallet().read().unwrap().get_balances().unwrap();
+    assert_eq!(regular_utxo_count(), 1);
+    assert_eq!(after.regular, before.regular - fee);
+    assert_eq!(after.fidelity, bond_amount);
+    assert_eq!(after.fidelity, before.fidelity);
+
+    // A single UTXO is left, nothing more to consolidate.
+    assert_eq!(consolidate_utxos(&maker).unwrap(), None);
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
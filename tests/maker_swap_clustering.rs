@@ -0,0 +1,136 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::{Amount, OutPoint};
+use coinswap::{
+    maker::{start_maker_server, Maker, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{collections::HashSet, sync::Arc};
+
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// The outpoints of all UTXOs of the Maker's wallet.
+fn maker_utxos(maker: &Maker) -> HashSet<OutPoint> {
+    let mut wallet = maker.get_wallet().write().unwrap();
+    wallet.sync().unwrap();
+    wallet
+        .list_all_utxo_spend_info()
+        .unwrap()
+        .into_iter()
+        .map(|(utxo, _)| OutPoint::new(utxo.txid, utxo.vout))
+        .collect()
+}
+
+/// Test that Makers avoiding swap clustering don't fund a swap with outputs of the previous swap.
+///
+/// - The Makers hold back the outputs of recent swaps from swap funding.
+/// - The Taker does two consecutive swaps through the same Makers.
+/// - No input of the Makers' funding in the second swap may be an output created by the first swap.
+#[test]
+fn test_maker_avoids_swap_clustering() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    // Extra coins, so the second swap can be funded without the outputs of the first one.
+    for maker in &makers {
+        let mut wallet = maker.get_wallet().write().unwrap();
+        wallet.set_avoid_swap_clustering(true);
+        for _ in 0..3 {
+            let address = wallet.get_next_external_address().unwrap();
+            send_to_address(bitcoind, &address, Amount::from_btc(0.05).unwrap());
+        }
+    }
+    generate_blocks(bitcoind, 1);
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 1,
+        required_confirms: 1,
+        ..Default::default()
+    };
+
+    // ----- Test -----
+
+    let before_first_swap = makers
+        .iter()
+        .map(|maker| maker_utxos(maker))
+        .collect::<Vec<_>>();
+    taker.do_coinswap(swap_params.clone()).unwrap();
+    let after_first_swap = makers
+        .iter()
+        .map(|maker| maker_utxos(maker))
+        .collect::<Vec<_>>();
+
+    taker.do_coinswap(swap_params).unwrap();
+    let after_second_swap = makers
+        .iter()
+        .map(|maker| maker_utxos(maker))
+        .collect::<Vec<_>>();
+
+    for ((before_first, after_first), after_second) in before_first_swap
+        .iter()
+        .zip(after_first_swap.iter())
+        .zip(after_second_swap.iter())
+    {
+        let created_by_first = after_first.difference(before_first).collect::<HashSet<_>>();
+        let spent_by_second = after_first.difference(after_second).collect::<HashSet<_>>();
+
+        assert!(!created_by_first.is_empty());
+        assert!(!spent_by_second.is_empty());
+        assert!(created_by_first.is_disjoint(&spent_by_second));
+    }
+
+    assert!(taker.get_bad_makers().is_empty());
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    thread::sleep(Duration::from_secs(10));
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
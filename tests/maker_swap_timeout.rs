@@ -0,0 +1,123 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    env, fs,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Upper bound of the mining fees a Maker pays for its funding, contract and timelock txs in this swap.
+const MAX_RECOVERY_FEES: Amount = Amount::from_sat(10_000);
+
+/// Test that the Makers recover a swap the Taker stopped progressing, once the swap timeout passes.
+///
+/// - The Makers drop idle swaps only after an hour, but recover any swap 60 secs after the proof of funding.
+/// - The Taker drops the connections after the full setup, and never settles.
+/// - The Makers must time out the swap, and reclaim their contracted funds via timelock.
+#[test]
+fn test_maker_swap_timeout() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init_with_config_overrides(
+            makers_config_map.into(),
+            TakerBehavior::DropConnectionAfterFullSetup,
+            ConnectionType::CLEARNET,
+            ConfigOverrides {
+                maker: Some("idle_connection_timeout = 3600\nswap_timeout = 60\n".to_string()),
+                ..Default::default()
+            },
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || start_maker_server(maker_clone).unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    let org_maker_regular_balances = makers
+        .iter()
+        .map(|maker| {
+            while !maker.is_setup_complete.load(Relaxed) {
+                log::info!("Waiting for maker setup completion");
+                thread::sleep(Duration::from_secs(5));
+            }
+            let wallet = maker.get_wallet().read().unwrap();
+            wallet.get_balances().unwrap().regular
+        })
+        .collect::<Vec<_>>();
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    // The Makers time out the swap, while the Taker is gone.
+    let maker_dirs = makers_config_map
+        .iter()
+        .map(|((port, _), _)| env::temp_dir().join("coinswap").join(port.to_string()))
+        .collect::<Vec<_>>();
+    let timed_out = |maker_dir: &std::path::Path| {
+        fs::read_to_string(maker_dir.join("events.log"))
+            .unwrap_or_default()
+            .contains("Swap not completed")
+    };
+    let wait_start = Instant::now();
+    while !maker_dirs.iter().all(|maker_dir| timed_out(maker_dir)) {
+        assert!(
+            wait_start.elapsed() < Duration::from_secs(300),
+            "Makers did not time out the swap"
+        );
+        thread::sleep(Duration::from_secs(5));
+    }
+
+    // Shutting down waits for the recovery threads to claim the contracts via timelock.
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    // The Makers got back all their funds, less the mining fees of the funding, contract and timelock txs.
+    for (maker, org_regular_balance) in makers.iter().zip(org_maker_regular_balances) {
+        let mut wallet = maker.get_wallet().write().unwrap();
+        wallet.sync().unwrap();
+        let balances = wallet.get_balances().unwrap();
+        assert!(balances.regular < org_regular_balance);
+        assert!(balances.regular > org_regular_balance - MAX_RECOVERY_FEES);
+        assert_eq!(balances.swap, Amount::ZERO);
+        assert_eq!(balances.contract, Amount::ZERO);
+    }
+
+    // ----- Clean up -----
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
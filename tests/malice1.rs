@@ -101,6 +101,7 @@ fn malice1_taker_broadcast_contract_prematurely() {
         maker_count: 2,
         tx_count: 3,
         required_confirms: 1,
+        ..Default::default()
     };
     taker.do_coinswap(swap_params).unwrap();
 
@@ -0,0 +1,122 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::sync::Arc;
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Malice 3: Maker substitutes the hashlock of its outgoing contracts.
+///
+/// The Maker locks its outgoing contracts with a hash of its own choice, instead of the Taker's hash.
+/// The Taker checks every Maker's contract transaction against the agreed hashlock, before building the
+/// watch-only or incoming swapcoins. It detects the substitution, bans the Maker and recovers from the swap.
+#[test]
+fn malice3_maker_uses_wrong_hashlock() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::WrongHashlock),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    // Initiate test framework, Makers.
+    // Taker has normal behavior.
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    // Fund the Taker  with 3 utxos of 0.05 btc each and do basic checks on the balance
+    fund_and_verify_taker(
+        &mut taker,
+        &test_framework.bitcoind,
+        3,
+        Amount::from_btc(0.05).unwrap(),
+    );
+
+    // Fund the Maker with 4 utxos of 0.05 btc each and do basic checks on the balance.
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(
+        makers_ref,
+        &test_framework.bitcoind,
+        4,
+        Amount::from_btc(0.05).unwrap(),
+    );
+
+    //  Start the Maker Server threads
+    log::info!("Initiating Maker...");
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Makers take time to fully setup.
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            // Introduce a delay of 10 seconds to prevent write lock starvation.
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // Initiate Coinswap
+    log::info!("Initiating coinswap protocol");
+
+    // Swap params for coinswap.
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    // The Taker aborts the swap, and recovers.
+    taker.do_coinswap(swap_params).unwrap();
+
+    // After Swap is done,  wait for maker threads to conclude.
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    // Shutdown Directory Server
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    thread::sleep(Duration::from_secs(10));
+
+    // ----- Checks -----
+
+    // The Maker with the wrong hashlock is detected and banned.
+    let bad_makers = taker.get_bad_makers();
+    assert_eq!(bad_makers.len(), 1);
+    assert_eq!(bad_makers[0].address.to_string(), "127.0.0.1:6102");
+
+    // The Taker got nothing out of the aborted swap, and holds no contracts.
+    let taker_wallet = taker.get_wallet_mut();
+    taker_wallet.sync().unwrap();
+    let balances = taker_wallet.get_balances().unwrap();
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+
+    log::info!("All checks successful. Terminating integration test case");
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
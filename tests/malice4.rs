@@ -0,0 +1,105 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{BanReason, SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::sync::Arc;
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Malice 4: Maker builds its incoming contract txs with a different miner fee than agreed.
+///
+/// Both Makers build the contract txs of their incoming swap with the wrong fee. The Taker compares them
+/// with its own contract txs as soon as it gets them from the first hop Maker. It detects the mismatch
+/// without reattempting, bans the Maker and recovers, before the next hop is ever initiated.
+#[test]
+fn malice4_maker_returns_mismatched_contract_txid() {
+    // ---- Setup ----
+
+    let ports = [6102, 16102];
+    let makers_config_map = [
+        ((ports[0], None), MakerBehavior::WrongContractFee),
+        ((ports[1], None), MakerBehavior::WrongContractFee),
+    ];
+
+    // Initiate test framework, Makers.
+    // Taker has normal behavior.
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Makers take time to fully setup.
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    // The Taker aborts the swap, and recovers.
+    taker.do_coinswap(swap_params).unwrap();
+    assert!(taker.get_swap_history().unwrap().is_empty());
+
+    // The first hop Maker is banned at once for the mismatch, not as unreachable after the reattempts.
+    let bad_makers = taker.list_bad_makers();
+    assert_eq!(bad_makers.len(), 1);
+    assert_eq!(bad_makers[0].reason, BanReason::ContractTxidMismatch);
+    let first_hop = bad_makers[0].offer.address.to_string();
+
+    // The other Maker was never asked into the swap, as the next hop.
+    for (maker, port) in makers.iter().zip(ports) {
+        if first_hop != format!("127.0.0.1:{}", port) {
+            assert_eq!(maker.get_wallet().read().unwrap().get_swapcoins_count(), 0);
+        }
+    }
+
+    // The Taker got nothing out of the aborted swap, and holds no contracts.
+    let taker_wallet = taker.get_wallet_mut();
+    taker_wallet.sync().unwrap();
+    let balances = taker_wallet.get_balances().unwrap();
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
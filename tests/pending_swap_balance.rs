@@ -0,0 +1,134 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::sync::{atomic::AtomicBool, Arc};
+
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test the accounting of coins locked in the funding txs of an ongoing swap.
+///
+/// A standard coinswap round with 2 Makers is performed, while a second thread polls the
+/// Makers' balances. While the swap is ongoing, each Maker must report its broadcasted funding
+/// amount as `pending_swap_out`. The last Maker's pending amount is what the Taker receives.
+/// After settlement nothing is pending anymore.
+#[test]
+fn test_pending_swap_out_balance() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    for maker in &makers {
+        let balances = maker.get_wallet().read().unwrap().get_balances().unwrap();
+        assert_eq!(balances.pending_swap_out, Amount::ZERO);
+    }
+
+    // Record the highest pending amount seen at each maker during the swap.
+    let stop_polling = Arc::new(AtomicBool::new(false));
+    let poller = {
+        let makers = makers.clone();
+        let stop_polling = stop_polling.clone();
+        thread::spawn(move || {
+            let mut max_pending = vec![Amount::ZERO; makers.len()];
+            while !stop_polling.load(Relaxed) {
+                for (maker, max) in makers.iter().zip(max_pending.iter_mut()) {
+                    let pending = maker
+                        .get_wallet()
+                        .read()
+                        .unwrap()
+                        .get_balances()
+                        .unwrap()
+                        .pending_swap_out;
+                    *max = (*max).max(pending);
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            max_pending
+        })
+    };
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    stop_polling.store(true, Relaxed);
+    let max_pending = poller.join().unwrap();
+    log::info!("Observed pending swap out amounts: {:?}", max_pending);
+
+    // Every maker funded its outgoing hop, for less than what the Taker sent.
+    for pending in &max_pending {
+        assert!(*pending > Amount::ZERO);
+        assert!(*pending < Amount::from_sat(500000));
+    }
+
+    // The last maker's funding txs are the Taker's incoming swap coins.
+    taker.get_wallet_mut().sync().unwrap();
+    let taker_swap_balance = taker.get_wallet().get_balances().unwrap().swap;
+    assert!(max_pending.contains(&taker_swap_balance));
+
+    // Nothing is pending once the swap is settled.
+    for maker in &makers {
+        let balances = maker.get_wallet().read().unwrap().get_balances().unwrap();
+        assert_eq!(balances.pending_swap_out, Amount::ZERO);
+    }
+    assert_eq!(
+        taker.get_wallet().get_balances().unwrap().pending_swap_out,
+        Amount::ZERO
+    );
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
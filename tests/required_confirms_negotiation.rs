@@ -0,0 +1,113 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::SwapParams,
+};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering::Relaxed},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test the negotiation of the funding confirmation target between the Taker and the Makers.
+///
+/// - The Makers require 3 confirmations, while the Taker only asks for 1.
+/// - The Taker negotiates the target upward to the Makers' requirement, and sends it in the proof of funding.
+/// - Both sides wait for the same depth, so the Makers accept the funding and the swap completes.
+#[test]
+fn test_required_confirms_negotiation() {
+    // ---- Setup ----
+    let test_env = Arc::new(TestEnv::init());
+    let bitcoind = &test_env.bitcoind;
+
+    let directory_server_instance = test_env.start_directory_server();
+
+    let mut taker = test_env.init_taker(None);
+
+    let makers = [(6102, 3501), (16102, 3502)]
+        .iter()
+        .map(|&(network_port, rpc_port)| {
+            test_env.init_maker(
+                network_port,
+                rpc_port,
+                Some("required_confirms = 3\n"),
+                MakerBehavior::Normal,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Mine a block at this interval (in secs). Zero stops the miner.
+    let block_interval = Arc::new(AtomicU64::new(1));
+    let miner_interval = block_interval.clone();
+    let miner_env = test_env.clone();
+    let miner_thread = thread::spawn(move || loop {
+        let interval = miner_interval.load(Relaxed);
+        if interval == 0 {
+            return;
+        }
+        thread::sleep(Duration::from_secs(interval));
+        generate_blocks(&miner_env.bitcoind, 1);
+    });
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || start_maker_server(maker_clone).unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    for maker in &makers {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(5));
+        }
+    }
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 1,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    // The swap completed, without the Makers recovering from it.
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert!(balances.swap > Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+
+    // Every hop was funded with the Makers' higher requirement.
+    let history = taker.get_swap_history().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].hops.len(), 2);
+    for hop in &history[0].hops {
+        assert_eq!(hop.required_confirms, 3);
+    }
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    block_interval.store(0, Relaxed);
+    miner_thread.join().unwrap();
+    directory_server_instance.shutdown.store(true, Relaxed);
+    let _ = bitcoind.client.stop().unwrap();
+}
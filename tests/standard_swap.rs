@@ -96,6 +96,8 @@ fn test_standard_coinswap() {
         maker_count: 2,
         tx_count: 3,
         required_confirms: 1,
+        swap_label: Some("standard-swap".to_string()),
+        ..Default::default()
     };
     taker.do_coinswap(swap_params).unwrap();
 
@@ -154,6 +156,17 @@ fn test_standard_coinswap() {
 
     info!("Balance check successful.");
 
+    // Check that the received swapcoins carry the swap label.
+    let taker_wallet = taker.get_wallet();
+    let labeled_utxos = taker_wallet
+        .list_all_utxo_spend_info()
+        .unwrap()
+        .iter()
+        .filter_map(|(utxo, spend_info)| taker_wallet.get_utxo_label(utxo, spend_info).cloned())
+        .collect::<Vec<_>>();
+    assert_eq!(labeled_utxos.len(), 3);
+    assert!(labeled_utxos.iter().all(|label| label == "standard-swap"));
+
     // Check spending from swapcoins.
     info!("Checking Spend from Swapcoin");
 
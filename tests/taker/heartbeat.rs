@@ -0,0 +1,107 @@
+use bitcoin::Amount;
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::SwapParams,
+};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering::Relaxed},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// Test that the Taker's heartbeats keep a swap alive during long confirmation waits.
+///
+/// - The Makers drop idle swaps after 8 secs, while blocks are only mined every 20 secs during the swap.
+/// - The Taker sends heartbeats every 2 secs while waiting for the funding confirmations.
+/// - The Makers must keep the swap alive, and the swap must complete.
+#[test]
+fn test_heartbeat_keeps_swap_alive() {
+    // ---- Setup ----
+    let test_env = Arc::new(TestEnv::init());
+    let bitcoind = &test_env.bitcoind;
+
+    let directory_server_instance = test_env.start_directory_server();
+
+    let mut taker = test_env.init_taker(Some("heartbeat_interval = 2\n"));
+
+    let makers = [(6102, 3501), (16102, 3502)]
+        .iter()
+        .map(|&(network_port, rpc_port)| {
+            test_env.init_maker(
+                network_port,
+                rpc_port,
+                Some("idle_connection_timeout = 8\n"),
+                MakerBehavior::Normal,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Mine a block at this interval (in secs). Zero stops the miner.
+    let block_interval = Arc::new(AtomicU64::new(1));
+    let miner_interval = block_interval.clone();
+    let miner_env = test_env.clone();
+    let miner_thread = thread::spawn(move || loop {
+        let interval = miner_interval.load(Relaxed);
+        if interval == 0 {
+            return;
+        }
+        thread::sleep(Duration::from_secs(interval));
+        generate_blocks(&miner_env.bitcoind, 1);
+    });
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || start_maker_server(maker_clone).unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    for maker in &makers {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(5));
+        }
+    }
+
+    // ----- Test -----
+
+    // Confirmations now take much longer than the Makers' idle timeout.
+    block_interval.store(20, Relaxed);
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 1,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    // The swap completed, without the Makers recovering from it.
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert!(balances.swap > Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    block_interval.store(0, Relaxed);
+    miner_thread.join().unwrap();
+    directory_server_instance.shutdown.store(true, Relaxed);
+    let _ = bitcoind.client.stop().unwrap();
+}
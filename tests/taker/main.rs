@@ -0,0 +1,11 @@
+//! Taker functional tests, sharing one test binary.
+//!
+//! The tests are serialized on the shared regtest data directory by the [test_framework].
+#![cfg(feature = "integration-test")]
+#[path = "../test_framework/mod.rs"]
+mod test_framework;
+
+mod dns_fallback;
+mod heartbeat;
+mod multi_dns;
+mod relocate_data_dir;
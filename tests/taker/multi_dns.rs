@@ -0,0 +1,108 @@
+use bitcoin::Amount;
+use bitcoind::bitcoincore_rpc::Auth;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    market::directory::{start_directory_server, DirectoryServer},
+    taker::TakerBehavior,
+    utill::ConnectionType,
+    wallet::RPCConfig,
+};
+use std::{env, fs, sync::Arc};
+
+use crate::test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test fetching maker addresses from multiple directory servers.
+///
+/// - A second directory server is started, and then stopped.
+/// - The Taker is configured with the stopped directory server first, and the running one second.
+/// - The Taker must skip the stopped directory server, and fetch the makers from the running one.
+#[test]
+fn test_fetch_from_second_directory_server() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    // Start the second directory server, and stop it right away.
+    let down_dns_dir = env::temp_dir().join("coinswap").join("dns-down");
+    fs::create_dir_all(&down_dns_dir).unwrap();
+    fs::write(
+        down_dns_dir.join("config.toml"),
+        "listen_port = 18080\nrpc_port = 14321\ntransport = CLEARNET",
+    )
+    .unwrap();
+    let down_dns =
+        Arc::new(DirectoryServer::new(Some(down_dns_dir), Some(ConnectionType::CLEARNET)).unwrap());
+    let rpc_config = RPCConfig {
+        url: bitcoind.rpc_url().split_at(7).1.to_string(),
+        auth: Auth::CookieFile(bitcoind.params.cookie_file.clone()),
+        ..Default::default()
+    };
+    let down_dns_clone = down_dns.clone();
+    let down_dns_thread = thread::spawn(move || {
+        start_directory_server(down_dns_clone, Some(rpc_config)).unwrap();
+    });
+    down_dns.shutdown.store(true, Relaxed);
+    down_dns_thread.join().unwrap();
+
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+
+    taker.config.directory_server_addresses =
+        vec!["127.0.0.1:18080".to_string(), "127.0.0.1:8080".to_string()];
+
+    let offerbook = taker.fetch_offers().unwrap();
+    let mut addresses = offerbook
+        .all_good_makers()
+        .iter()
+        .map(|offer| offer.address.to_string())
+        .collect::<Vec<_>>();
+    addresses.sort();
+    assert_eq!(addresses, vec!["127.0.0.1:16102", "127.0.0.1:6102"]);
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    thread::sleep(Duration::from_secs(10));
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,78 @@
+use bitcoin::{Amount, OutPoint};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    taker::{error::TakerError, Taker, TakerBehavior},
+    utill::ConnectionType,
+    wallet::LabelTarget,
+};
+use std::fs;
+
+use crate::test_framework::*;
+
+/// Test relocating the Taker's data directory.
+///
+/// - Relocating into a non-empty directory is refused.
+/// - After the relocation the old directory is gone, and the wallet, offerbook and config are in the new one.
+/// - Later saves go to the new location, and the Taker reloads from there.
+#[test]
+fn test_taker_relocate_data_dir() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+    let temp_dir = &test_env.temp_dir;
+    let rpc_config = test_env.rpc_config();
+
+    let old_dir = temp_dir.join("taker");
+    let new_dir = temp_dir.join("encrypted").join("taker-data");
+
+    let mut taker = test_env.init_taker(None);
+
+    fund_and_verify_taker(&mut taker, bitcoind, 1, Amount::from_btc(0.05).unwrap());
+
+    // ----- Test -----
+
+    // A non-empty target is refused.
+    let busy_dir = temp_dir.join("busy");
+    fs::create_dir_all(&busy_dir).unwrap();
+    fs::write(busy_dir.join("file"), "data").unwrap();
+    assert!(matches!(
+        taker.relocate_data_dir(busy_dir),
+        Err(TakerError::DataDirNotEmpty(_))
+    ));
+    assert!(old_dir.join("wallets").join("taker-wallet").exists());
+
+    taker.relocate_data_dir(new_dir.clone()).unwrap();
+    assert!(!old_dir.exists());
+    assert!(new_dir.join("wallets").join("taker-wallet").exists());
+    assert!(new_dir.join("offerbook.dat").exists());
+    assert!(new_dir.join("config.toml").exists());
+
+    // A wallet change after the relocation is saved in the new directory.
+    let wallet = taker.get_wallet_mut();
+    let (utxo, _) = wallet.list_descriptor_utxo_spend_info().unwrap()[0].clone();
+    let target = LabelTarget::Outpoint(OutPoint::new(utxo.txid, utxo.vout));
+    wallet
+        .set_label(target.clone(), "relocated".to_string())
+        .unwrap();
+    let balances = wallet.get_balances().unwrap();
+    drop(taker);
+    assert!(!old_dir.exists());
+
+    let taker = Taker::init(
+        Some(new_dir.clone()),
+        None,
+        Some(rpc_config),
+        TakerBehavior::Normal,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+    )
+    .unwrap();
+    let wallet = taker.get_wallet();
+    assert_eq!(wallet.get_label(&target), Some(&"relocated".to_string()));
+    assert_eq!(wallet.get_balances().unwrap().spendable, balances.spendable);
+
+    // ----- Clean up -----
+    drop(taker);
+    let _ = bitcoind.client.stop().unwrap();
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test exporting the wallet labels after a swap, in the BIP329 format.
+///
+/// - A labeled swap completes through 2 makers.
+/// - The exported JSON Lines are valid BIP329 records.
+/// - The received swap outputs are labeled with their category and the swap label.
+#[test]
+fn test_taker_bip329_labels() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        swap_label: Some("rent".to_string()),
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+    taker.get_wallet_mut().sync().unwrap();
+
+    let wallet = taker.get_wallet();
+    let records = wallet
+        .export_bip329_labels()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    // Every line is a BIP329 record, with a known type, a reference and a label.
+    assert!(!records.is_empty());
+    assert!(records.iter().all(|record| {
+        ["tx", "addr", "pubkey", "input", "output", "xpub"]
+            .contains(&record["type"].as_str().unwrap())
+            && record["ref"].is_string()
+            && record["label"].is_string()
+    }));
+
+    // Each received swap output is labeled as such, with the swap label.
+    let swap_outputs = wallet
+        .list_incoming_swap_coin_utxo_spend_info()
+        .unwrap()
+        .into_iter()
+        .map(|(utxo, _)| format!("{}:{}", utxo.txid, utxo.vout))
+        .collect::<Vec<_>>();
+    assert!(!swap_outputs.is_empty());
+    for outpoint in swap_outputs {
+        let record = records
+            .iter()
+            .find(|record| record["type"] == "output" && record["ref"] == outpoint.as_str())
+            .unwrap();
+        assert_eq!(record["label"], "incoming-swap: rent");
+        assert_eq!(record["spendable"], true);
+    }
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::{Amount, OutPoint};
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::{ConnectionType, DEFAULT_TX_FEE_RATE},
+};
+use std::{collections::HashSet, sync::Arc};
+
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test the warning about swap funding change, that can be spent along with the received swap coins.
+///
+/// - The Taker does a swap, whose funding txs leave change in its wallet.
+/// - The change outputs are flagged, as coin selection could spend them with the received swap coins.
+/// - Once the swap coins are freshened, nothing is flagged anymore.
+#[test]
+fn test_taker_change_linkage_warning() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // No swap, nothing to flag.
+    assert!(taker.change_linkage_warning().unwrap().is_empty());
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    thread::sleep(Duration::from_secs(10));
+
+    // ----- Test -----
+
+    taker.get_wallet_mut().sync().unwrap();
+    let funding_outpoints = taker.get_swap_history().unwrap()[0]
+        .funding_outpoints
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+    let funding_txids = funding_outpoints
+        .iter()
+        .map(|outpoint| outpoint.txid)
+        .collect::<HashSet<_>>();
+
+    // The change outputs of the funding txs, back in the wallet.
+    let change_outpoints = taker
+        .get_wallet()
+        .list_descriptor_utxo_spend_info()
+        .unwrap()
+        .into_iter()
+        .map(|(utxo, _)| OutPoint::new(utxo.txid, utxo.vout))
+        .filter(|outpoint| funding_txids.contains(&outpoint.txid))
+        .collect::<HashSet<_>>();
+    assert!(!change_outpoints.is_empty());
+    assert!(change_outpoints.is_disjoint(&funding_outpoints));
+
+    let at_risk = taker
+        .change_linkage_warning()
+        .unwrap()
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert_eq!(at_risk, change_outpoints);
+
+    // The swap coins are moved out of the coin selection's reach.
+    let txids = taker.post_swap_freshen(DEFAULT_TX_FEE_RATE).unwrap();
+    assert_eq!(txids.len(), 3);
+    generate_blocks(bitcoind, 1);
+    taker.get_wallet_mut().sync().unwrap();
+    assert!(taker.change_linkage_warning().unwrap().is_empty());
+
+    // ----- Clean up -----
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
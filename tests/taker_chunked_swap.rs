@@ -0,0 +1,97 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test splitting a swap into independent rounds.
+///
+/// - A total of 800,000 sats is swapped in chunks of 400,000 sats, through 2 makers.
+/// - Two swap rounds complete, each adding one record to the swap history, and they add up to the total.
+#[test]
+fn test_taker_chunked_swap() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    let total = Amount::from_sat(800000);
+    let swap_params = SwapParams {
+        send_amount: Amount::ZERO,
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    assert!(taker.get_swap_history().unwrap().is_empty());
+    let rounds = taker
+        .do_coinswap_chunked(total, Amount::from_sat(400000), swap_params)
+        .unwrap();
+
+    // Each round completed, and left exactly one record in the history.
+    let history = taker.get_swap_history().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history, rounds);
+    assert!(history.iter().all(|record| record.hops.len() == 2));
+    assert_eq!(
+        history
+            .iter()
+            .map(|record| record.send_amount)
+            .collect::<Vec<_>>(),
+        vec![Amount::from_sat(400000); 2]
+    );
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
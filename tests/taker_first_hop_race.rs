@@ -0,0 +1,179 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use bitcoind::bitcoincore_rpc::{Auth, Client, RpcApi};
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{FirstHopOrdering, SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    env, fs,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Whether the maker on `port` received a proof of funding, as per its event log.
+fn received_funding(port: u16) -> bool {
+    let path = env::temp_dir()
+        .join("coinswap")
+        .join(port.to_string())
+        .join("events.log");
+    fs::read_to_string(path)
+        .map(|log| log.contains("\"event\":\"swap_started\""))
+        .unwrap_or(false)
+}
+
+/// Test racing the first hop signature request.
+///
+/// - Two makers are slow to sign, one closes the connection, and one responds right away.
+/// - With `first_hop_ordering = race`, all four are requested at the same time, and the fast maker
+///   becomes the first hop.
+/// - Only the fast maker's funding is committed: no other maker receives a proof of funding from the
+///   taker, and only the maker which closed is marked bad.
+/// - The losers' fundings leave nothing behind in the taker's wallet: no imported multisig, locked coin
+///   or handed out change address, which never receives coins.
+#[test]
+fn test_taker_first_hop_race() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::SlowAtReqContractSigsForSender),
+        ((16102, None), MakerBehavior::SlowAtReqContractSigsForSender),
+        (
+            (26102, None),
+            MakerBehavior::CloseAtReqContractSigsForSender,
+        ),
+        ((36102, None), MakerBehavior::Normal),
+    ];
+
+    // Change addresses come from the external chain, so the ones handed out are tracked by the wallet.
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init_with_config_overrides(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+            ConfigOverrides {
+                taker: Some("address_strategy = singlechain\n".to_string()),
+                ..Default::default()
+            },
+        );
+    taker.config.first_hop_ordering = FirstHopOrdering::Race;
+    taker.config.first_hop_race_width = 4;
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    // ----- Test -----
+
+    // The fast maker won the race.
+    let history = taker.get_swap_history().unwrap();
+    assert_eq!(history.len(), 1);
+    let hops = &history[0].hops;
+    assert!(hops[0].maker_address.ends_with(":36102"));
+    assert!(received_funding(36102));
+
+    // Only the second hop maker was funded, by the first maker.
+    let second_hop_port = [6102, 16102]
+        .iter()
+        .copied()
+        .find(|port| hops[1].maker_address.ends_with(&format!(":{}", port)))
+        .expect("a slow maker is the second hop");
+    assert!(received_funding(second_hop_port));
+    let unused_port = if second_hop_port == 6102 { 16102 } else { 6102 };
+    assert!(!received_funding(unused_port));
+    assert!(!received_funding(26102));
+
+    // The losers of the race aren't banned, unless they failed.
+    let bad_makers = taker.list_bad_makers();
+    assert_eq!(bad_makers.len(), 1);
+    assert!(bad_makers[0].offer.address.to_string().ends_with(":26102"));
+
+    // Nothing of the losers' fundings is left in the Taker's wallet.
+    let taker_wallet_rpc = Client::new(
+        &bitcoind.rpc_url_with_wallet("taker-wallet"),
+        Auth::CookieFile(bitcoind.params.cookie_file.clone()),
+    )
+    .unwrap();
+    let received = |address: &bitcoin::Address<bitcoin::address::NetworkUnchecked>| {
+        taker_wallet_rpc
+            .get_received_by_address(&address.clone().assume_checked(), Some(0))
+            .unwrap()
+            > Amount::ZERO
+    };
+
+    // Only the spent inputs of the winner's funding were locked.
+    let locked: Vec<serde_json::Value> = taker_wallet_rpc.call("listlockunspent", &[]).unwrap();
+    for outpoint in locked {
+        let txid = outpoint["txid"].as_str().unwrap().parse().unwrap();
+        let vout = outpoint["vout"].as_u64().unwrap() as u32;
+        assert!(taker_wallet_rpc
+            .get_tx_out(&txid, vout, Some(true))
+            .unwrap()
+            .is_none());
+    }
+
+    // Every imported multisig, and every external address handed out, received coins.
+    let external_index = *taker.get_wallet().get_external_index();
+    let descriptors: serde_json::Value = taker_wallet_rpc.call("listdescriptors", &[]).unwrap();
+    for descriptor in descriptors["descriptors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|descriptor| descriptor["desc"].as_str())
+    {
+        if descriptor.starts_with("wsh(sortedmulti(") {
+            let multisig_address = &taker_wallet_rpc.derive_addresses(descriptor, None).unwrap()[0];
+            assert!(received(multisig_address));
+        } else if descriptor.starts_with("wpkh(") && descriptor.contains("/0/*") {
+            let addresses = taker_wallet_rpc
+                .derive_addresses(descriptor, Some([0, external_index - 1]))
+                .unwrap();
+            assert!(addresses.iter().all(received));
+        }
+    }
+
+    // ----- Clean up -----
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
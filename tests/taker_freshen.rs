@@ -0,0 +1,137 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::{Amount, OutPoint};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::{ConnectionType, DEFAULT_TX_FEE_RATE},
+};
+use std::{collections::HashSet, sync::Arc};
+
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test moving the received swap coins to fresh addresses after a swap.
+///
+/// - The Taker does a swap, receiving 3 swap coins.
+/// - The swap coins are freshened, each in its own tx.
+/// - Every swap coin must have moved to a new, distinct address of the Taker's wallet.
+#[test]
+fn test_post_swap_freshen() {
+    // ---- Setup ----
+
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    thread::sleep(Duration::from_secs(10));
+
+    // ----- Test -----
+
+    taker.get_wallet_mut().sync().unwrap();
+    let swap_coins = taker
+        .get_wallet()
+        .list_incoming_swap_coin_utxo_spend_info()
+        .unwrap();
+    assert_eq!(swap_coins.len(), 3);
+    let swap_outpoints = swap_coins
+        .iter()
+        .map(|(utxo, _)| OutPoint::new(utxo.txid, utxo.vout))
+        .collect::<HashSet<_>>();
+    let swap_scripts = swap_coins
+        .iter()
+        .map(|(utxo, _)| utxo.script_pub_key.clone())
+        .collect::<HashSet<_>>();
+
+    let txids = taker.post_swap_freshen(DEFAULT_TX_FEE_RATE).unwrap();
+    assert_eq!(txids.len(), 3);
+    generate_blocks(bitcoind, 1);
+
+    // Each tx moves a single swap coin to a new address.
+    let mut fresh_scripts = HashSet::new();
+    for txid in &txids {
+        let tx = bitcoind.client.get_raw_transaction(txid, None).unwrap();
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.output.len(), 1);
+        assert!(swap_outpoints.contains(&tx.input[0].previous_output));
+        assert!(!swap_scripts.contains(&tx.output[0].script_pubkey));
+        fresh_scripts.insert(tx.output[0].script_pubkey.clone());
+    }
+    assert_eq!(fresh_scripts.len(), 3);
+
+    // The freshened coins are regular wallet coins now.
+    let wallet = taker.get_wallet_mut();
+    wallet.sync().unwrap();
+    assert!(wallet
+        .list_incoming_swap_coin_utxo_spend_info()
+        .unwrap()
+        .is_empty());
+    let wallet_scripts = wallet
+        .list_all_utxo_spend_info()
+        .unwrap()
+        .into_iter()
+        .map(|(utxo, _)| utxo.script_pub_key)
+        .collect::<HashSet<_>>();
+    assert!(fresh_scripts.is_subset(&wallet_scripts));
+    assert_eq!(wallet.get_balances().unwrap().swap, Amount::ZERO);
+
+    // Nothing is left to freshen.
+    assert!(taker
+        .post_swap_freshen(DEFAULT_TX_FEE_RATE)
+        .unwrap()
+        .is_empty());
+
+    // ----- Clean up -----
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::{Amount, Network, OutPoint};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::{ConnectionType, DEFAULT_TX_FEE_RATE},
+    wallet::{Destination, WalletError},
+};
+use std::sync::Arc;
+
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test the detection of already spent funding inputs, before the Taker broadcasts its funding.
+///
+/// - Two of the Taker's coins are spent in the mempool, without syncing the Taker's wallet.
+/// - A tx spending one of them is reported as [WalletError::InputConflict].
+/// - The swap still succeeds, re-selecting coins if the stale ones were selected first.
+#[test]
+fn test_taker_funding_input_conflict() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+
+    // Spend two coins behind the wallet's back, leaving its utxo cache stale.
+    let recipient = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+    let wallet = taker.get_wallet_mut();
+    let coins = wallet.list_descriptor_utxo_spend_info().unwrap();
+    let conflicted = coins[..2]
+        .iter()
+        .map(|(utxo, _)| OutPoint::new(utxo.txid, utxo.vout))
+        .collect::<Vec<_>>();
+    let spend_tx = wallet
+        .spend_from_wallet(
+            DEFAULT_TX_FEE_RATE,
+            Destination::Sweep(recipient.clone()),
+            &coins[..2],
+        )
+        .unwrap();
+    let spend_txid = wallet.send_tx(&spend_tx).unwrap();
+
+    // The stale coins are still listed, but spending them is a conflict.
+    assert_eq!(wallet.list_descriptor_utxo_spend_info().unwrap().len(), 3);
+    let double_spend = wallet
+        .spend_from_wallet(
+            DEFAULT_TX_FEE_RATE,
+            Destination::Sweep(recipient),
+            &coins[..1],
+        )
+        .unwrap();
+    match wallet.check_input_conflicts(&[double_spend]) {
+        Err(WalletError::InputConflict { outpoint }) => assert_eq!(outpoint, conflicted[0]),
+        other => panic!("Expected InputConflict error, got {:?}", other),
+    }
+
+    // The swap detects the conflicts before broadcasting, and funds from the remaining coin.
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 1,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    // The coins were spent by our tx only, which got mined.
+    let spend_tx_info = bitcoind
+        .client
+        .get_raw_transaction_info(&spend_txid, None)
+        .unwrap();
+    assert!(spend_tx_info.confirmations.unwrap_or(0) > 0);
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    thread::sleep(Duration::from_secs(10));
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,107 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    protocol::error::ProtocolError,
+    taker::{error::TakerError, BanReason, SwapParams, TakerBehavior},
+    utill::ConnectionType,
+    wallet::WalletError,
+};
+use std::{
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test that the Taker rejects maker contract signatures, that wouldn't pass script verification.
+///
+/// - Both makers sign the Taker's contracts with valid signatures, carrying the wrong sighash flag.
+/// - The Taker assembles the fully signed contracts as soon as it gets the signatures, and errors with
+///   `InvalidMakerSignature`, instead of finding out at recovery time.
+/// - Nothing is funded, and the first hop maker is banned.
+#[test]
+fn test_taker_invalid_maker_signature() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::WrongSighashFlag),
+        ((16102, None), MakerBehavior::WrongSighashFlag),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    let org_taker_spend_balance =
+        fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    let err = taker.do_coinswap(swap_params).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            TakerError::Wallet(WalletError::Protocol(ProtocolError::InvalidMakerSignature(
+                _
+            )))
+        ),
+        "Unexpected error: {:?}",
+        err
+    );
+
+    // The first hop maker is banned, before any funding tx was broadcasted.
+    let bad_makers = taker.list_bad_makers();
+    assert_eq!(bad_makers.len(), 1);
+    assert_eq!(bad_makers[0].reason, BanReason::SignatureExchange);
+
+    let wallet = taker.get_wallet_mut();
+    wallet.sync().unwrap();
+    let balances = wallet.get_balances().unwrap();
+    assert_eq!(balances.spendable, org_taker_spend_balance);
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
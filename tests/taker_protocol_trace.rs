@@ -0,0 +1,219 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    env, fs,
+    path::Path,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Reads the protocol trace in the given data directory.
+fn read_trace(data_dir: &Path) -> (String, Vec<Value>) {
+    let contents = fs::read_to_string(data_dir.join("protocol_trace.log")).unwrap();
+    let lines = contents
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect();
+    (contents, lines)
+}
+
+/// Asserts that no 32 byte hex string, like a preimage or a private key, is traced, other than the txids.
+fn assert_no_secrets(contents: &str, lines: &[Value]) {
+    let mut txids = HashSet::new();
+    for line in lines {
+        for (name, value) in line["fields"].as_object().unwrap() {
+            if name == "bond_outpoint" {
+                let outpoint = value.as_str().unwrap();
+                txids.insert(outpoint.split(':').next().unwrap().to_string());
+            } else if name.ends_with("txids") {
+                txids.extend(
+                    value
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|txid| txid.as_str().unwrap().to_string()),
+                );
+            }
+        }
+    }
+
+    let hex_words = contents
+        .split(|c: char| !c.is_ascii_hexdigit())
+        .filter(|word| word.len() >= 64);
+    for word in hex_words {
+        assert!(txids.contains(word), "Untraced hex string {}", word);
+    }
+}
+
+/// Test the protocol trace of a swap.
+///
+/// - The Taker and the Makers run with `protocol_trace` enabled.
+/// - After a standard swap, the Taker's trace records the swap messages in protocol order, with their
+///   direction, peer and size.
+/// - The preimage, private keys and nonces are redacted, on both sides.
+#[test]
+fn test_taker_protocol_trace() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init_with_config_overrides(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+            ConfigOverrides {
+                taker: Some("protocol_trace = true\n".to_string()),
+                maker: Some("protocol_trace = true\n".to_string()),
+            },
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+    let temp_dir = env::temp_dir().join("coinswap");
+    let taker_dir = temp_dir.join("taker");
+    let maker_dirs = makers_config_map
+        .iter()
+        .map(|((port, _), _)| temp_dir.join(port.to_string()))
+        .collect::<Vec<_>>();
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || start_maker_server(maker_clone).unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    for maker in &makers {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(5));
+        }
+    }
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    let (contents, lines) = read_trace(&taker_dir);
+    for line in &lines {
+        assert!(line["timestamp"].as_u64().unwrap() > 0);
+        assert!(line["size"].as_u64().unwrap() > 0);
+        assert!(line["peer"].as_str().unwrap().starts_with("127.0.0.1:"));
+    }
+
+    // The swap messages, leaving out the handshakes, offer downloads and heartbeats.
+    let swap_messages = lines
+        .iter()
+        .map(|line| {
+            format!(
+                "{} {}",
+                line["direction"].as_str().unwrap(),
+                line["message"].as_str().unwrap()
+            )
+        })
+        .filter(|message| {
+            ![
+                "TakerHello",
+                "MakerHello",
+                "ReqGiveOffer",
+                "RespOffer",
+                "ReqIdentityProof",
+                "RespIdentityProof",
+                "Heartbeat",
+                "RespHeartbeat",
+                "WaitingFundingConfirmation",
+            ]
+            .iter()
+            .any(|skipped| message.ends_with(&format!(" {}", skipped)))
+        })
+        .collect::<Vec<_>>();
+
+    let expected_order = [
+        // First hop contract sigs, from the first Maker.
+        "sent ReqContractSigsForSender",
+        "received RespContractSigsForSender",
+        // First Maker's hop, with the second Maker signing its outgoing contracts.
+        "sent RespProofOfFunding",
+        "received ReqContractSigsAsRecvrAndSender",
+        "sent ReqContractSigsForSender",
+        "received RespContractSigsForSender",
+        "sent RespContractSigsForRecvrAndSender",
+        // Second Maker's hop, with the first Maker signing its incoming contracts.
+        "sent RespProofOfFunding",
+        "received ReqContractSigsAsRecvrAndSender",
+        "sent ReqContractSigsForRecvr",
+        "received RespContractSigsForRecvr",
+        "sent RespContractSigsForRecvrAndSender",
+        // The Taker's incoming contracts, signed by the second Maker.
+        "sent ReqContractSigsForRecvr",
+        "received RespContractSigsForRecvr",
+        // Settlement with both Makers.
+        "sent RespHashPreimage",
+        "received RespPrivKeyHandover",
+        "sent RespPrivKeyHandover",
+        "sent RespHashPreimage",
+        "received RespPrivKeyHandover",
+        "sent RespPrivKeyHandover",
+    ];
+    assert_eq!(swap_messages, expected_order);
+
+    for line in &lines {
+        let fields = &line["fields"];
+        match line["message"].as_str().unwrap() {
+            "RespHashPreimage" => assert_eq!(fields["preimage"], "<redacted>"),
+            "RespPrivKeyHandover" => {
+                assert_eq!(fields["privkey_count"], 3);
+                assert_eq!(fields["multisig_privkeys"], "<redacted>");
+            }
+            "ReqContractSigsForSender" | "RespProofOfFunding" => {
+                assert_eq!(fields["nonces"], "<redacted>")
+            }
+            _ => {}
+        }
+    }
+    assert_no_secrets(&contents, &lines);
+
+    // The Makers trace their side of the swap too.
+    for maker_dir in &maker_dirs {
+        let (contents, lines) = read_trace(maker_dir);
+        assert!(lines.iter().any(|line| {
+            line["direction"] == "received" && line["message"] == "RespHashPreimage"
+        }));
+        assert_no_secrets(&contents, &lines);
+    }
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
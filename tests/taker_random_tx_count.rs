@@ -0,0 +1,106 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::{
+    secp256k1::rand::{rngs::StdRng, SeedableRng},
+    Amount,
+};
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    collections::HashSet,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test randomizing the number of funding txs of every swap round.
+///
+/// - Five swap rounds run with `randomize_tx_count`, between 1 and 3 funding txs.
+/// - The `tx_count` of the swap params is ignored, every round picks its own split count.
+/// - The split counts stay within the configured bounds, and vary across the rounds.
+#[test]
+fn test_taker_random_tx_count() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    taker.config.randomize_tx_count = true;
+    taker.config.min_tx_count = 1;
+    taker.config.max_tx_count = 3;
+    // A seeded RNG, for the picked split counts to be reproducible.
+    taker.set_rng(Box::new(StdRng::seed_from_u64(7)));
+
+    let swap_params = SwapParams {
+        send_amount: Amount::ZERO,
+        maker_count: 2,
+        tx_count: 10,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    let rounds = taker
+        .do_coinswap_chunked(
+            Amount::from_sat(1500000),
+            Amount::from_sat(300000),
+            swap_params,
+        )
+        .unwrap();
+    assert_eq!(rounds.len(), 5);
+
+    let tx_counts = rounds
+        .iter()
+        .map(|round| round.tx_count)
+        .collect::<Vec<_>>();
+    log::info!("Split counts of the swap rounds: {:?}", tx_counts);
+    assert!(tx_counts.iter().all(|count| (1..=3).contains(count)));
+    assert!(tx_counts.iter().collect::<HashSet<_>>().len() > 1);
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,138 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{error::TakerError, SwapParams, Taker, TakerBehavior},
+    utill::ConnectionType,
+    wallet::RPCConfig,
+};
+use std::{
+    env,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test the export of a stuck swap into a recovery bundle, and the recovery from it in another Taker.
+///
+/// The Taker drops the connection after the full setup, and exports a recovery bundle. The default bundle
+/// only describes the swap, and can't be imported. The keys are only exported when allowed in the config.
+/// A fresh Taker, with another wallet, imports the bundle with the keys and claims the outgoing contracts
+/// back via the timelock.
+#[test]
+fn test_taker_recovery_bundle() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::DropConnectionAfterFullSetup,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    // ----- Test -----
+    let temp_dir = env::temp_dir().join("coinswap");
+    let diagnosis_bundle_path = temp_dir.join("diagnosis_bundle.dat");
+    taker
+        .export_recovery_bundle(&diagnosis_bundle_path, false)
+        .unwrap();
+
+    // The keys are only exported on explicit request, allowed by the config.
+    let bundle_path = temp_dir.join("recovery_bundle.dat");
+    assert!(taker.export_recovery_bundle(&bundle_path, true).is_err());
+    assert!(!bundle_path.exists());
+    taker.config.allow_key_export = true;
+    taker.export_recovery_bundle(&bundle_path, true).unwrap();
+
+    let mut rescue_taker = Taker::init(
+        Some(temp_dir.join("rescue-taker")),
+        Some("rescue-taker-wallet".to_string()),
+        Some(RPCConfig::from(test_framework.as_ref())),
+        TakerBehavior::Normal,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+    )
+    .unwrap();
+    let balances = rescue_taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert_eq!(balances.regular, Amount::ZERO);
+
+    // A bundle without the keys can't recover anything.
+    assert!(matches!(
+        rescue_taker.import_recovery_bundle(&diagnosis_bundle_path),
+        Err(TakerError::InvalidRecoveryBundle(_))
+    ));
+
+    let imported = rescue_taker.import_recovery_bundle(&bundle_path).unwrap();
+    assert!(imported >= 3);
+    // Importing twice adds nothing.
+    assert_eq!(
+        rescue_taker.import_recovery_bundle(&bundle_path).unwrap(),
+        0
+    );
+
+    // The rescue Taker sees the outgoing swapcoins, and recovers them.
+    let balances = rescue_taker.get_wallet().get_balances().unwrap();
+    assert!(balances.swap > Amount::ZERO);
+
+    rescue_taker.recover_from_swap().unwrap();
+
+    rescue_taker.get_wallet_mut().sync().unwrap();
+    let balances = rescue_taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+    // The timelock spends pay into the rescue Taker's wallet.
+    assert!(balances.regular > Amount::from_sat(400_000));
+
+    // ----- Clean up -----
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, Taker, TakerBehavior},
+    utill::ConnectionType,
+    wallet::RPCConfig,
+};
+use std::{
+    env, fs,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test the recovery of a failed swap by the recovery scheduler, across a Taker restart.
+///
+/// - The Taker has `auto_recovery` set, so the failed swap is not recovered inline, and the swapcoins stay stuck.
+/// - The Taker is restarted from its data directory, and the recovery scheduler claims the outgoing contracts back,
+///   without an explicit call to `recover_from_swap`.
+#[test]
+fn test_taker_recovery_scheduler() {
+    // ---- Setup ----
+
+    // 6102 closes the connection at the hash preimage, after the funding txs are broadcasted.
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::CloseAtHashPreimage),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+    taker.config.auto_recovery = true;
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    // ----- Test -----
+
+    // The failed swap is left to the scheduler.
+    taker.get_wallet_mut().sync().unwrap();
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert!(balances.swap > Amount::ZERO);
+
+    // Restart the Taker, with the auto recovery set in its config file.
+    drop(taker);
+    let taker_dir = env::temp_dir().join("coinswap").join("taker");
+    fs::write(
+        taker_dir.join("config.toml"),
+        "auto_recovery = true\nrecovery_interval = 5\n",
+    )
+    .unwrap();
+    let mut taker = Taker::init(
+        Some(taker_dir),
+        None,
+        Some(RPCConfig::from(test_framework.as_ref())),
+        TakerBehavior::Normal,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+    )
+    .unwrap();
+    assert!(taker.config.auto_recovery);
+
+    // Returns once no unfinished swapcoin is left.
+    taker.run_recovery_scheduler().unwrap();
+
+    taker.get_wallet_mut().sync().unwrap();
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+
+    // ----- Clean up -----
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::sync::Arc;
+
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test the route preview before a swap.
+///
+/// - With 3 Makers available, a 2 Maker route is previewed, without funding anything.
+/// - The swap with the same params takes the previewed route, in the same order, with the same
+///   fees and refund locktimes.
+#[test]
+fn test_route_preview() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+        ((26102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+
+    let balance_before = taker.get_wallet().get_balances().unwrap();
+    let preview = taker.preview_route(&swap_params).unwrap();
+    assert_eq!(preview.len(), 2);
+    for (position, maker) in preview.iter().enumerate() {
+        assert_eq!(maker.position, position);
+        assert!(maker.bond_value > Amount::ZERO);
+    }
+    assert_ne!(preview[0].address, preview[1].address);
+
+    // Nothing is funded by the preview.
+    taker.get_wallet_mut().sync().unwrap();
+    let balance_after = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balance_after.regular, balance_before.regular);
+    assert_eq!(balance_after.swap, Amount::ZERO);
+
+    taker.do_coinswap(swap_params).unwrap();
+
+    let history = taker.get_swap_history().unwrap();
+    assert_eq!(history.len(), 1);
+    let hops = &history[0].hops;
+    assert_eq!(hops.len(), preview.len());
+    for (hop, maker) in hops.iter().zip(preview.iter()) {
+        assert_eq!(hop.maker_address, maker.address);
+        assert_eq!(hop.fees, maker.fees);
+        assert_eq!(hop.incoming_locktime, maker.incoming_locktime);
+        assert_eq!(hop.outgoing_locktime, maker.outgoing_locktime);
+    }
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    thread::sleep(Duration::from_secs(10));
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
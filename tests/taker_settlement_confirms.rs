@@ -0,0 +1,108 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Number of confirmations the Taker waits for on the received swapcoins.
+const SETTLEMENT_CONFIRMS: u32 = 5;
+
+/// Test a swap waiting for its received swapcoins to confirm, before returning.
+///
+/// - The swap is done with `wait_for_settlement_confirms`, deeper than the `required_confirms` of the
+///   funding txs.
+/// - As soon as the swap returns, every received swapcoin has at least that many confirmations, and
+///   shows up in the swap balance.
+#[test]
+fn test_taker_settlement_confirms() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        wait_for_settlement_confirms: Some(SETTLEMENT_CONFIRMS),
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    // Checked before any other sync, or block mined by the test.
+    let incoming_utxos = taker
+        .get_wallet()
+        .list_incoming_swap_coin_utxo_spend_info()
+        .unwrap();
+    assert_eq!(incoming_utxos.len(), 3);
+    for (utxo, _) in &incoming_utxos {
+        assert!(
+            utxo.confirmations >= SETTLEMENT_CONFIRMS,
+            "Swapcoin {}:{} returned with {} confirmations",
+            utxo.txid,
+            utxo.vout,
+            utxo.confirmations
+        );
+    }
+
+    let received = incoming_utxos
+        .iter()
+        .map(|(utxo, _)| utxo.amount)
+        .sum::<Amount>();
+    assert_eq!(taker.get_wallet().get_balances().unwrap().swap, received);
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
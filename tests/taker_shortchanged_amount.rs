@@ -0,0 +1,99 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{BanReason, SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test the check of the received amount before settlement.
+///
+/// - The last maker of the route funds the Taker short, while claiming the agreed amount.
+/// - The Taker refuses to release the preimage, bans the maker, and recovers via the timelock instead.
+#[test]
+fn test_taker_shortchanged_amount() {
+    // ---- Setup ----
+
+    // Only the maker in the last hop shortchanges, whatever the route order.
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::ShortchangeLastHop),
+        ((16102, None), MakerBehavior::ShortchangeLastHop),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    let org_taker_spend_balance =
+        fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    // ----- Test -----
+
+    // The swap was not settled.
+    assert!(taker.get_swap_history().unwrap().is_empty());
+
+    // The last maker is banned for the short amount.
+    let bad_makers = taker.list_bad_makers();
+    assert_eq!(bad_makers.len(), 1);
+    assert_eq!(bad_makers[0].reason, BanReason::ShortchangedAmount);
+
+    // The outgoing contracts are claimed back via the timelock, for only the mining fees.
+    taker.get_wallet_mut().sync().unwrap();
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert_eq!(balances.contract, Amount::ZERO);
+    assert!(balances.spendable > org_taker_spend_balance - Amount::from_sat(10_000));
+
+    // ----- Clean up -----
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{error::TakerError, SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test a swap whose deadline passes before settlement.
+///
+/// - Both makers take 20 seconds to sign the sender's contracts, the swap deadline is 10 seconds.
+/// - The deadline is checked once the first hop is funded, the Taker aborts there and recovers its funds.
+/// - The swap fails with `TakerError::DeadlineExceeded`, and nothing is recorded in the swap history.
+#[test]
+fn test_taker_swap_deadline() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::SlowAtReqContractSigsForSender),
+        ((16102, None), MakerBehavior::SlowAtReqContractSigsForSender),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    let org_taker_spend_balance =
+        fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        deadline: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+    let result = taker.do_coinswap(swap_params);
+    assert!(matches!(result, Err(TakerError::DeadlineExceeded)));
+
+    // The swap was aborted into recovery, it is not recorded as completed.
+    assert!(taker.get_swap_history().unwrap().is_empty());
+
+    // The recovery got the funding back, less the mining fees.
+    taker.get_wallet_mut().sync().unwrap();
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.contract, Amount::ZERO);
+    assert_eq!(balances.swap, Amount::ZERO);
+    assert!(balances.spendable < org_taker_spend_balance);
+    assert!(balances.spendable > org_taker_spend_balance - Amount::from_sat(20000));
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
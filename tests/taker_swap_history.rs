@@ -0,0 +1,122 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::sync::Arc;
+
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test the swap history record of a completed swap.
+///
+/// - A standard coinswap round with 2 Makers is performed.
+/// - The history holds one record, with one hop per Maker in route order.
+/// - Each hop records the Maker's offer fees, the contract fee, and the refund locktimes
+///   of its incoming and outgoing contracts, which step down along the route.
+#[test]
+fn test_swap_history_record() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    assert!(taker.get_swap_history().unwrap().is_empty());
+
+    let send_amount = Amount::from_sat(500000);
+    let swap_params = SwapParams {
+        send_amount,
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    let history = taker.get_swap_history().unwrap();
+    assert_eq!(history.len(), 1);
+    let record = &history[0];
+    assert_eq!(record.send_amount, send_amount);
+    assert_eq!(record.tx_count, 3);
+    assert_eq!(record.hops.len(), 2);
+
+    // Refund locktimes step down by 20 blocks per hop, from the Taker's 60 to 20 for the last hop.
+    assert_eq!(record.hops[0].incoming_locktime, 60);
+    assert_eq!(record.hops[0].outgoing_locktime, 40);
+    assert_eq!(record.hops[1].incoming_locktime, 40);
+    assert_eq!(record.hops[1].outgoing_locktime, 20);
+
+    // Each maker forwards what it received, minus its fees.
+    assert_eq!(record.hops[0].incoming_amount, send_amount);
+    assert_eq!(
+        record.hops[1].incoming_amount,
+        record.hops[0].outgoing_amount
+    );
+    taker.get_wallet_mut().sync().unwrap();
+    assert_eq!(
+        record.hops[1].outgoing_amount,
+        taker.get_wallet().get_balances().unwrap().swap
+    );
+
+    let offerbook = taker.fetch_offers().unwrap();
+    for hop in &record.hops {
+        assert!(hop.fee_earned() > Amount::ZERO);
+        assert_eq!(hop.contract_fee, 1000);
+        let offer = offerbook
+            .all_good_makers()
+            .into_iter()
+            .find(|offer| offer.address.to_string() == hop.maker_address)
+            .expect("maker of the route in the offerbook");
+        assert_eq!(hop.fees, offer.fees());
+    }
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    thread::sleep(Duration::from_secs(10));
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::Amount;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, SwapPhase, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::sync::{atomic::AtomicBool, Arc};
+
+mod test_framework;
+use test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test the Taker's swap status reporting.
+///
+/// A standard coinswap round with 2 Makers is performed, while a second thread polls the
+/// [SwapPhase] through a status handle. The observed phases must follow the swap workflow,
+/// and the Taker must be back to `Idle` after the swap.
+#[test]
+fn test_taker_swap_status() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    // ----- Test -----
+    assert_eq!(taker.get_swap_status(), SwapPhase::Idle);
+
+    // Record every phase change seen by the poller.
+    let status = taker.swap_status_handle();
+    let stop_polling = Arc::new(AtomicBool::new(false));
+    let stop_polling_clone = stop_polling.clone();
+    let poller = thread::spawn(move || {
+        let mut observed = vec![status.get()];
+        while !stop_polling_clone.load(Relaxed) {
+            let phase = status.get();
+            if observed.last() != Some(&phase) {
+                observed.push(phase);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        observed
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    stop_polling.store(true, Relaxed);
+    let observed = poller.join().unwrap();
+    log::info!("Observed swap phases: {:?}", observed);
+
+    assert_eq!(taker.get_swap_status(), SwapPhase::Idle);
+
+    // Phases are seen in the order of the workflow. Short phases might be missed by the
+    // poller, but the long ones waiting on the network and the chain can't be.
+    let workflow = [
+        SwapPhase::Idle,
+        SwapPhase::SyncingOffers,
+        SwapPhase::FundingFirstHop,
+        SwapPhase::ExchangingSigs(0),
+        SwapPhase::AwaitingConfirmations,
+        SwapPhase::ExchangingSigs(1),
+        SwapPhase::AwaitingConfirmations,
+        SwapPhase::Settling,
+        SwapPhase::Idle,
+    ];
+    let mut workflow_iter = workflow.iter();
+    for phase in &observed {
+        assert!(
+            workflow_iter.any(|p| p == phase),
+            "Unexpected phase transition to {:?} in {:?}",
+            phase,
+            observed
+        );
+    }
+    for phase in [
+        SwapPhase::FundingFirstHop,
+        SwapPhase::AwaitingConfirmations,
+        SwapPhase::Settling,
+    ] {
+        assert!(observed.contains(&phase), "{:?} not observed", phase);
+    }
+    assert_eq!(observed.last(), Some(&SwapPhase::Idle));
+    assert!(!observed.contains(&SwapPhase::Recovering));
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
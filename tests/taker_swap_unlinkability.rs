@@ -0,0 +1,122 @@
+#![cfg(feature = "integration-test")]
+use bitcoin::{Amount, Txid};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{error::TakerError, SwapParams, TakerBehavior},
+    utill::ConnectionType,
+};
+use std::{
+    collections::HashSet,
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test the on-chain unlinkability check of a completed swap.
+///
+/// - A swap through 2 makers completes, recording the Taker's funding outputs and the received ones.
+/// - The received outputs share no transaction, and no direct parent, with the Taker's funding inputs.
+#[test]
+fn test_taker_swap_unlinkability() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    // ----- Test -----
+    let history = taker.get_swap_history().unwrap();
+    let record = &history[0];
+    assert_eq!(record.funding_outpoints.len(), 3);
+    assert_eq!(record.received_outpoints.len(), 3);
+
+    assert!(taker
+        .verify_swap_unlinkability(&record.id)
+        .unwrap()
+        .is_empty());
+    assert!(matches!(
+        taker.verify_swap_unlinkability("unknown"),
+        Err(TakerError::UnknownSwap(_))
+    ));
+
+    // The same check, straight from the chain: the swap's txs and their direct parents, on each side.
+    let txs_and_parents = |txids: Vec<Txid>| {
+        let mut set = HashSet::new();
+        for txid in txids {
+            let tx = bitcoind.client.get_raw_transaction(&txid, None).unwrap();
+            set.extend(tx.input.iter().map(|input| input.previous_output.txid));
+            set.insert(txid);
+        }
+        set
+    };
+    let funding_side = txs_and_parents(
+        record
+            .funding_outpoints
+            .iter()
+            .map(|outpoint| outpoint.txid)
+            .collect(),
+    );
+    let received_side = txs_and_parents(
+        record
+            .received_outpoints
+            .iter()
+            .map(|outpoint| outpoint.txid)
+            .collect(),
+    );
+    assert!(funding_side.is_disjoint(&received_side));
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -5,11 +5,15 @@
 //! Spawns one Taker and multiple Makers, with/without special behavior, connect them to bitcoind regtest node,
 //! and initializes the database.
 //!
-//! The tests data are stored in the `tests/temp-files` directory, which is auto-removed after each successful test.
-//! Do not invoke [TestFramework::stop] function at the end of the test, to persis this data for debugging.
+//! The tests data are stored in the `coinswap` directory of the system temp dir, which is removed at the start of each test.
+//! With `COINSWAP_KEEP_DATA=on_failure` set, it's also removed at the end of each successful test, and only kept
+//! for debugging when the test fails. See [KeepData].
 //!
 //! The test data also includes the backend bitcoind data-directory, which is useful for observing the blockchain states after a swap.
 //!
+//! Tests needing only a node, and setting up their own Takers and Makers, start from a [TestEnv] instead.
+//! Small related tests are grouped in one binary (e.g. `tests/wallet/`), where they run one at a time, see [TestLockGuard].
+//!
 //! Checkout `tests/standard_swap.rs` for example of simple coinswap simulation test between 1 Taker and 2 Makers.
 use bitcoin::Amount;
 use std::{
@@ -21,7 +25,7 @@ use std::{
     sync::{
         atomic::{AtomicBool, Ordering::Relaxed},
         mpsc::{self, Receiver, Sender},
-        Arc,
+        Arc, Condvar, Mutex,
     },
     thread::{self, JoinHandle},
     time::Duration,
@@ -128,8 +132,27 @@ fn get_bitcoind_filename(os: &str, arch: &str) -> String {
 
 /// Initiate the bitcoind backend.
 pub(crate) fn init_bitcoind(datadir: &std::path::Path) -> BitcoinD {
+    init_bitcoind_with_txindex(datadir, true)
+}
+
+/// Initiate the bitcoind backend, optionally without the transaction index the wallets require.
+pub(crate) fn init_bitcoind_with_txindex(datadir: &std::path::Path, txindex: bool) -> BitcoinD {
+    let bitcoind = start_bitcoind(datadir, txindex);
+
+    // Generate initial 101 blocks
+    generate_blocks(&bitcoind, 101);
+    log::info!("bitcoind initiated!!");
+
+    bitcoind
+}
+
+/// Start the bitcoind backend without mining any block. The node stays in initial block download
+/// until its first block is mined.
+pub(crate) fn start_bitcoind(datadir: &std::path::Path, txindex: bool) -> BitcoinD {
     let mut conf = bitcoind::Conf::default();
-    conf.args.push("-txindex=1"); //txindex is must, or else wallet sync won't work.
+    if txindex {
+        conf.args.push("-txindex=1"); //txindex is must, or else wallet sync won't work.
+    }
     conf.staticdir = Some(datadir.join(".bitcoin"));
     log::info!("bitcoind datadir: {:?}", conf.staticdir.as_ref().unwrap());
     log::info!("bitcoind configuration: {:?}", conf.args);
@@ -177,13 +200,7 @@ pub(crate) fn init_bitcoind(datadir: &std::path::Path) -> BitcoinD {
 
     log::info!("Executable path: {:?}", exe_path);
 
-    let bitcoind = BitcoinD::with_conf(exe_path, &conf).unwrap();
-
-    // Generate initial 101 blocks
-    generate_blocks(&bitcoind, 101);
-    log::info!("bitcoind initiated!!");
-
-    bitcoind
+    BitcoinD::with_conf(exe_path, &conf).unwrap()
 }
 
 /// Generate Blocks in regtest node.
@@ -459,6 +476,206 @@ pub fn verify_swap_results(
         });
 }
 
+/// Environment variable selecting when the test data directory is kept, as per [KeepData].
+pub const KEEP_DATA_ENV: &str = "COINSWAP_KEEP_DATA";
+
+/// When the test data directory is kept at the end of a test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeepData {
+    /// Always keep the data, until the next test starts.
+    #[default]
+    Always,
+    /// Keep the data only if the test fails, and remove it on success.
+    OnFailure,
+}
+
+impl KeepData {
+    /// Reads the policy from the [KEEP_DATA_ENV] environment variable. Unset or unknown values keep the data.
+    pub fn from_env() -> Self {
+        match env::var(KEEP_DATA_ENV).as_deref() {
+            Ok("on_failure") => KeepData::OnFailure,
+            _ => KeepData::Always,
+        }
+    }
+}
+
+/// Removes the test data directory when dropped, unless it's kept as per the [KeepData] policy.
+///
+/// A failing test is detected by the thread panicking while the guard is dropped.
+pub struct DataDirGuard {
+    data_dir: PathBuf,
+    keep: KeepData,
+}
+
+impl DataDirGuard {
+    /// Guards the data directory, keeping it as per the policy.
+    pub fn new(data_dir: PathBuf, keep: KeepData) -> Self {
+        Self { data_dir, keep }
+    }
+}
+
+impl Drop for DataDirGuard {
+    fn drop(&mut self) {
+        if self.keep == KeepData::OnFailure && !thread::panicking() {
+            log::info!("Removing test data : {}", self.data_dir.display());
+            let _ = fs::remove_dir_all(&self.data_dir);
+        }
+    }
+}
+
+/// Whether a test of this binary holds the test data directory, see [TestLockGuard].
+static TEST_LOCK: Mutex<bool> = Mutex::new(false);
+static TEST_LOCK_RELEASED: Condvar = Condvar::new();
+
+/// Runs the tests of a binary one at a time, as they share the test data directory and network ports.
+///
+/// Unlike a `MutexGuard`, it can be moved into the threads of a test.
+pub struct TestLockGuard(());
+
+impl TestLockGuard {
+    /// Waits for the other tests of the binary to be done.
+    pub fn acquire() -> Self {
+        let mut locked = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        while *locked {
+            locked = TEST_LOCK_RELEASED
+                .wait(locked)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *locked = true;
+        TestLockGuard(())
+    }
+}
+
+impl Drop for TestLockGuard {
+    fn drop(&mut self) {
+        *TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner()) = false;
+        TEST_LOCK_RELEASED.notify_one();
+    }
+}
+
+/// A fresh test data directory with a regtest node, for tests setting up their Takers and Makers by hand.
+///
+/// Unlike the [TestFramework], nothing else is started, and no block is mined in the background.
+pub struct TestEnv {
+    pub bitcoind: BitcoinD,
+    pub temp_dir: PathBuf,
+    // Dropped after bitcoind, so its process is gone before the data is removed.
+    _data_dir_guard: DataDirGuard,
+    _test_lock: TestLockGuard,
+}
+
+#[allow(dead_code)]
+impl TestEnv {
+    /// Starts a regtest node with the initial blocks mined, see [init_bitcoind].
+    pub fn init() -> Self {
+        Self::init_with_node(init_bitcoind)
+    }
+
+    /// Starts the node with `start_node`, from the test data directory.
+    pub fn init_with_node(start_node: impl FnOnce(&Path) -> BitcoinD) -> Self {
+        let test_lock = TestLockGuard::acquire();
+        let temp_dir = env::temp_dir().join("coinswap");
+        setup_logger(log::LevelFilter::Info, Some(temp_dir.clone()));
+        if temp_dir.exists() {
+            fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        log::info!("temporary directory : {}", temp_dir.display());
+
+        Self {
+            bitcoind: start_node(&temp_dir),
+            temp_dir: temp_dir.clone(),
+            _data_dir_guard: DataDirGuard::new(temp_dir, KeepData::from_env()),
+            _test_lock: test_lock,
+        }
+    }
+
+    /// RPC config of the node.
+    pub fn rpc_config(&self) -> RPCConfig {
+        RPCConfig {
+            url: self.bitcoind.rpc_url().split_at(7).1.to_string(),
+            auth: Auth::CookieFile(self.bitcoind.params.cookie_file.clone()),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a Taker in the `taker` directory, started from the given config.
+    pub fn init_taker(&self, config: Option<&str>) -> Taker {
+        let taker_dir = self.temp_dir.join("taker");
+        write_config(&taker_dir, config);
+        Taker::init(
+            Some(taker_dir),
+            None,
+            Some(self.rpc_config()),
+            TakerBehavior::Normal,
+            None,
+            None,
+            Some(ConnectionType::CLEARNET),
+        )
+        .unwrap()
+    }
+
+    /// Creates a Maker listening on `port`, in a directory named after it, started from the given config.
+    pub fn init_maker(
+        &self,
+        port: u16,
+        rpc_port: u16,
+        config: Option<&str>,
+        behavior: MakerBehavior,
+    ) -> Arc<Maker> {
+        let maker_dir = self.temp_dir.join(port.to_string());
+        write_config(&maker_dir, config);
+        Arc::new(
+            Maker::init(
+                Some(maker_dir),
+                Some(format!("maker{}", port)),
+                Some(self.rpc_config()),
+                Some(port),
+                Some(rpc_port),
+                None,
+                None,
+                None,
+                Some(ConnectionType::CLEARNET),
+                behavior,
+            )
+            .unwrap(),
+        )
+    }
+
+    /// Starts a directory server on a thread of its own.
+    pub fn start_directory_server(&self) -> Arc<DirectoryServer> {
+        let directory_server = Arc::new(
+            DirectoryServer::new(
+                Some(self.temp_dir.join("dns")),
+                Some(ConnectionType::CLEARNET),
+            )
+            .unwrap(),
+        );
+        let directory_server_clone = directory_server.clone();
+        let rpc_config = self.rpc_config();
+        thread::spawn(move || {
+            start_directory_server(directory_server_clone, Some(rpc_config)).unwrap();
+        });
+        directory_server
+    }
+}
+
+/// Extra `config.toml` contents, written into the Taker's and every Maker's data directory before they start.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    /// Config of the Taker.
+    pub taker: Option<String>,
+    /// Config of each Maker.
+    pub maker: Option<String>,
+}
+
+/// Writes the given config into the data directory, if any.
+fn write_config(data_dir: &Path, config: Option<&str>) {
+    if let Some(config) = config {
+        create_dir_all(data_dir).unwrap();
+        fs::write(data_dir.join("config.toml"), config).unwrap();
+    }
+}
+
 /// The Test Framework.
 ///
 /// Handles initializing, operating and cleaning up of all backend processes. Bitcoind, Taker and Makers.
@@ -467,6 +684,9 @@ pub struct TestFramework {
     pub(super) bitcoind: BitcoinD,
     temp_dir: PathBuf,
     shutdown: AtomicBool,
+    // Dropped after bitcoind, so its process is gone before the data is removed.
+    data_dir_guard: DataDirGuard,
+    test_lock: TestLockGuard,
 }
 
 impl TestFramework {
@@ -493,6 +713,29 @@ impl TestFramework {
         Arc<DirectoryServer>,
         JoinHandle<()>,
     ) {
+        Self::init_with_config_overrides(
+            makers_config_map,
+            taker_behavior,
+            connection_type,
+            ConfigOverrides::default(),
+        )
+    }
+
+    /// Same as [TestFramework::init], with the Taker and Makers started from the given [ConfigOverrides].
+    #[allow(clippy::type_complexity)]
+    pub fn init_with_config_overrides(
+        makers_config_map: Vec<((u16, Option<u16>), MakerBehavior)>,
+        taker_behavior: TakerBehavior,
+        connection_type: ConnectionType,
+        config_overrides: ConfigOverrides,
+    ) -> (
+        Arc<Self>,
+        Taker,
+        Vec<Arc<Maker>>,
+        Arc<DirectoryServer>,
+        JoinHandle<()>,
+    ) {
+        let test_lock = TestLockGuard::acquire();
         // Setup directory
         let temp_dir = env::temp_dir().join("coinswap");
         setup_logger(log::LevelFilter::Info, Some(temp_dir.clone()));
@@ -509,6 +752,8 @@ impl TestFramework {
             bitcoind,
             temp_dir: temp_dir.clone(),
             shutdown,
+            data_dir_guard: DataDirGuard::new(temp_dir.clone(), KeepData::from_env()),
+            test_lock,
         });
 
         log::info!("Initiating Directory Server .....");
@@ -530,6 +775,7 @@ impl TestFramework {
 
         // Create the Taker.
         let taker_rpc_config = rpc_config.clone();
+        write_config(&temp_dir.join("taker"), config_overrides.taker.as_deref());
         let taker = Taker::init(
             Some(temp_dir.join("taker")),
             None,
@@ -550,6 +796,10 @@ impl TestFramework {
                 let maker_id = format!("maker{}", port.0); // ex: "maker6102"
                 let maker_rpc_config = rpc_config.clone();
                 thread::sleep(Duration::from_secs(5)); // Sleep for some time avoid resource unavailable error.
+                write_config(
+                    &temp_dir.join(port.0.to_string()),
+                    config_overrides.maker.as_deref(),
+                );
                 Arc::new(
                     Maker::init(
                         Some(temp_dir.join(port.0.to_string())),
@@ -591,6 +841,12 @@ impl TestFramework {
         )
     }
 
+    /// Stop the block generation thread, leaving bitcoind running.
+    #[allow(dead_code)]
+    pub fn stop_block_generation(&self) {
+        self.shutdown.store(true, Relaxed);
+    }
+
     /// Stop bitcoind and clean up all test data.
     pub fn stop(&self) {
         log::info!("Stopping Test Framework");
@@ -0,0 +1,44 @@
+#![cfg(feature = "integration-test")]
+use std::{env, fs, panic};
+
+mod test_framework;
+use test_framework::*;
+
+/// Test the [KeepData] policy of the test data directory.
+///
+/// - With `on_failure`, the data is kept when the test fails, simulated by a panic.
+/// - It's removed when the test succeeds.
+/// - By default, the data is always kept.
+#[test]
+fn test_keep_data_on_failure() {
+    let data_dir = env::temp_dir().join("coinswap-keep-data");
+    let create_data_dir = || {
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::write(data_dir.join("debug.log"), "test data").unwrap();
+    };
+
+    env::set_var(KEEP_DATA_ENV, "on_failure");
+    assert_eq!(KeepData::from_env(), KeepData::OnFailure);
+
+    // A simulated failure keeps the data.
+    create_data_dir();
+    let result = panic::catch_unwind(|| {
+        let _guard = DataDirGuard::new(data_dir.clone(), KeepData::from_env());
+        panic!("simulated test failure");
+    });
+    assert!(result.is_err());
+    assert!(data_dir.join("debug.log").exists());
+
+    // A success removes it.
+    drop(DataDirGuard::new(data_dir.clone(), KeepData::from_env()));
+    assert!(!data_dir.exists());
+
+    // Kept by default.
+    env::remove_var(KEEP_DATA_ENV);
+    assert_eq!(KeepData::from_env(), KeepData::Always);
+    create_data_dir();
+    drop(DataDirGuard::new(data_dir.clone(), KeepData::from_env()));
+    assert!(data_dir.join("debug.log").exists());
+
+    fs::remove_dir_all(&data_dir).unwrap();
+}
@@ -0,0 +1,96 @@
+use bitcoin::{bip32::DerivationPath, Address, Amount, Network};
+use bitcoind::bitcoincore_rpc::{Auth, Client, RpcApi};
+use coinswap::wallet::Destination;
+use std::str::FromStr;
+
+use crate::test_framework::*;
+
+/// Test a wallet created with the single-chain address strategy.
+///
+/// - Change addresses derive from the external chain `m/84'/1'/0'/0/*`, right after the receive
+///   addresses handed out, instead of the internal chain.
+/// - Receive addresses handed out afterwards skip the change addresses handed out, used or not.
+/// - A spend sends its change to a fresh address of the external chain.
+#[test]
+fn test_wallet_single_chain_address_strategy() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    // The strategy is picked from the config, when the wallet is created.
+    let mut taker = test_env.init_taker(Some("address_strategy = singlechain\n"));
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    // The derivation paths, as known to the Taker's Core wallet.
+    let taker_wallet_rpc = Client::new(
+        &format!("{}/wallet/taker-wallet", bitcoind.rpc_url()),
+        Auth::CookieFile(bitcoind.params.cookie_file.clone()),
+    )
+    .unwrap();
+    let path_of = |address: &Address| {
+        taker_wallet_rpc
+            .get_address_info(address)
+            .unwrap()
+            .hd_key_path
+            .unwrap()
+    };
+
+    // ----- Test -----
+    let wallet = taker.get_wallet_mut();
+
+    // The 3 receive addresses took indexes 0 to 2 of the external chain.
+    let change_address = wallet.get_next_internal_addresses(1).unwrap()[0].clone();
+    assert_eq!(
+        path_of(&change_address),
+        DerivationPath::from_str("m/84'/1'/0'/0/3").unwrap()
+    );
+
+    // The next receive address skips the change address, even though it's unused.
+    let receive_address = wallet.get_next_external_address().unwrap();
+    assert_eq!(
+        path_of(&receive_address),
+        DerivationPath::from_str("m/84'/1'/0'/0/4").unwrap()
+    );
+
+    // A spend sends its change to the next address.
+    let destination_address = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+    let txid = wallet
+        .send_to_address(
+            Destination::Multi(vec![(
+                destination_address.clone(),
+                Amount::from_btc(0.01).unwrap(),
+            )]),
+            2.0,
+        )
+        .unwrap();
+    let sent_tx = bitcoind.client.get_raw_transaction(&txid, None).unwrap();
+    let spend_change = sent_tx
+        .output
+        .iter()
+        .find(|txout| txout.script_pubkey != destination_address.script_pubkey())
+        .map(|txout| Address::from_script(&txout.script_pubkey, Network::Regtest).unwrap())
+        .unwrap();
+    assert_eq!(
+        path_of(&spend_change),
+        DerivationPath::from_str("m/84'/1'/0'/0/5").unwrap()
+    );
+
+    generate_blocks(bitcoind, 1);
+    wallet.sync().unwrap();
+
+    // Receive addresses keep skipping the used change.
+    let receive_address = wallet.get_next_external_address().unwrap();
+    assert_eq!(
+        path_of(&receive_address),
+        DerivationPath::from_str("m/84'/1'/0'/0/6").unwrap()
+    );
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
@@ -0,0 +1,83 @@
+use bitcoin::{Amount, Network, OutPoint};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::wallet::{AntiFeeSniping, Destination, TxOrdering};
+use std::collections::HashMap;
+
+use crate::test_framework::*;
+
+/// Test building a spend without broadcasting it.
+///
+/// - `build_spend` returns an unsigned tx, and nothing reaches the mempool.
+/// - `send_to_address` then broadcasts a tx spending the same inputs, with the same outputs and fee.
+#[test]
+fn test_wallet_build_spend() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(None);
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    // Deterministic nLockTime and ordering, so both txs can be compared as built.
+    let wallet = taker.get_wallet_mut();
+    wallet.set_anti_fee_sniping(AntiFeeSniping::Tip);
+    wallet.set_tx_ordering(TxOrdering::Bip69);
+
+    let destination_address = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+    let destination = Destination::Multi(vec![(
+        destination_address.clone(),
+        Amount::from_btc(0.07).unwrap(),
+    )]);
+
+    let input_values = wallet
+        .coin_select(Amount::from_btc(0.07).unwrap())
+        .unwrap()
+        .into_iter()
+        .map(|(utxo, _)| (OutPoint::new(utxo.txid, utxo.vout), utxo.amount))
+        .collect::<HashMap<_, _>>();
+    let fee_of = |tx: &bitcoin::Transaction| {
+        let input_value = tx
+            .input
+            .iter()
+            .map(|txin| input_values[&txin.previous_output])
+            .sum::<Amount>();
+        input_value - tx.output.iter().map(|txout| txout.value).sum::<Amount>()
+    };
+
+    // ----- Test -----
+
+    let built_tx = wallet.build_spend(destination.clone(), 2.0).unwrap();
+
+    // Unsigned, and not broadcasted.
+    assert!(built_tx.input.iter().all(|txin| txin.witness.is_empty()));
+    assert!(bitcoind.client.get_raw_mempool().unwrap().is_empty());
+    assert!(built_tx
+        .output
+        .iter()
+        .any(|txout| txout.script_pubkey == destination_address.script_pubkey()));
+
+    let txid = wallet.send_to_address(destination, 2.0).unwrap();
+    let sent_tx = bitcoind.client.get_raw_transaction(&txid, None).unwrap();
+    assert!(sent_tx.input.iter().all(|txin| !txin.witness.is_empty()));
+
+    // Same inputs, outputs and fee.
+    let outpoints = |tx: &bitcoin::Transaction| {
+        tx.input
+            .iter()
+            .map(|txin| txin.previous_output)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(outpoints(&built_tx), outpoints(&sent_tx));
+    assert_eq!(built_tx.output, sent_tx.output);
+    assert_eq!(built_tx.lock_time, sent_tx.lock_time);
+    assert_eq!(fee_of(&built_tx), fee_of(&sent_tx));
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
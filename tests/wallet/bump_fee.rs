@@ -0,0 +1,95 @@
+use bitcoin::{Amount, Network};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{utill::DEFAULT_TX_FEE_RATE, wallet::Destination};
+
+use crate::test_framework::*;
+
+/// Test fee bumping of a regular wallet send via Replace-By-Fee.
+///
+/// - The Taker wallet sends a low-fee payment with a change output.
+/// - The fee is bumped, and the replacement must pay the same recipient, with a higher
+///   absolute fee taken out of the change.
+/// - The original transaction is evicted from the mempool.
+/// - Once confirmed, the replacement can't be bumped any further.
+///
+/// Blocks are mined manually here, so the transaction stays unconfirmed until the bump.
+#[test]
+fn test_bump_fee() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(None);
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    // ----- Test -----
+    let wallet = taker.get_wallet_mut();
+    let coins = wallet.list_descriptor_utxo_spend_info().unwrap();
+
+    let recipient = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+    let send_amount = Amount::from_btc(0.01).unwrap();
+
+    let original_tx = wallet
+        .spend_from_wallet(
+            DEFAULT_TX_FEE_RATE,
+            Destination::Multi(vec![(recipient.clone(), send_amount)]),
+            &coins[..1],
+        )
+        .unwrap();
+    let original_txid = wallet.send_tx(&original_tx).unwrap();
+
+    let new_txid = wallet
+        .bump_fee(original_txid, Amount::from_sat(20))
+        .unwrap();
+    assert_ne!(new_txid, original_txid);
+
+    let replacement_tx = bitcoind
+        .client
+        .get_raw_transaction(&new_txid, None)
+        .unwrap();
+
+    // Same inputs, so a lower total output means a higher absolute fee.
+    assert_eq!(replacement_tx.input.len(), original_tx.input.len());
+    let original_out = original_tx.output.iter().map(|o| o.value).sum::<Amount>();
+    let replacement_out = replacement_tx
+        .output
+        .iter()
+        .map(|o| o.value)
+        .sum::<Amount>();
+    assert!(replacement_out < original_out);
+
+    // Recipient is paid the same amount, and the change is reduced.
+    let is_payment = |o: &&bitcoin::TxOut| o.script_pubkey == recipient.script_pubkey();
+    assert_eq!(
+        replacement_tx.output.iter().find(is_payment).unwrap().value,
+        send_amount
+    );
+    let original_change = original_tx.output.iter().find(|o| !is_payment(o)).unwrap();
+    let replacement_change = replacement_tx
+        .output
+        .iter()
+        .find(|o| !is_payment(o))
+        .unwrap();
+    assert_eq!(
+        original_change.value - replacement_change.value,
+        original_out - replacement_out
+    );
+
+    // The original is replaced in the mempool.
+    let mempool = bitcoind.client.get_raw_mempool().unwrap();
+    assert!(mempool.contains(&new_txid));
+    assert!(!mempool.contains(&original_txid));
+
+    // Confirmed transactions can't be bumped.
+    generate_blocks(bitcoind, 1);
+    assert!(wallet.bump_fee(new_txid, Amount::from_sat(40)).is_err());
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
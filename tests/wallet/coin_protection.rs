@@ -0,0 +1,81 @@
+use bitcoin::{Amount, OutPoint};
+use bitcoind::bitcoincore_rpc::RpcApi;
+
+use crate::test_framework::*;
+
+/// Test the exclusion of protected and old UTXOs from automatic coin selection,
+/// which is used for swap funding.
+///
+/// - A large UTXO is selected first by the largest-first coin selection.
+/// - Once protected, it's skipped, even though it would otherwise be selected.
+/// - With a max input age, the older UTXOs are skipped.
+///
+/// Blocks are mined manually here, so the confirmation counts are deterministic.
+#[test]
+fn test_coin_selection_skips_protected_and_old_utxos() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(None);
+
+    // Three older coins, and a younger but larger coin.
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    generate_blocks(bitcoind, 10);
+    let large_coin_address = taker.get_wallet_mut().get_next_external_address().unwrap();
+    send_to_address(
+        bitcoind,
+        &large_coin_address,
+        Amount::from_btc(0.2).unwrap(),
+    );
+    generate_blocks(bitcoind, 1);
+    taker.get_wallet_mut().sync().unwrap();
+
+    // Outpoints picked by the automatic coin selection for the given amount.
+    macro_rules! selected {
+        ($wallet:expr, $amount:expr) => {
+            $wallet
+                .coin_select($amount)
+                .unwrap()
+                .iter()
+                .map(|(utxo, _)| (OutPoint::new(utxo.txid, utxo.vout), utxo.confirmations))
+                .collect::<Vec<_>>()
+        };
+    }
+
+    // ----- Test -----
+    let wallet = taker.get_wallet_mut();
+    let small_amount = Amount::from_btc(0.01).unwrap();
+    let total_amount = Amount::from_btc(0.3).unwrap();
+
+    // Largest first, the large coin is selected.
+    let (large_coin, large_coin_confirmations) = selected!(wallet, small_amount)[0];
+
+    // Protected, it's skipped even though it would otherwise be selected.
+    wallet.protect_utxo(large_coin).unwrap();
+    let selection = selected!(wallet, small_amount);
+    assert_eq!(selection.len(), 1);
+    assert_ne!(selection[0].0, large_coin);
+    assert!(selected!(wallet, total_amount)
+        .iter()
+        .all(|(outpoint, _)| *outpoint != large_coin));
+
+    // Unprotected, it's selected again.
+    assert!(wallet.unprotect_utxo(&large_coin).unwrap());
+    assert_eq!(selected!(wallet, small_amount)[0].0, large_coin);
+
+    // With a max input age, only the young large coin is selectable.
+    wallet
+        .set_max_input_age(Some(large_coin_confirmations))
+        .unwrap();
+    let selection = selected!(wallet, total_amount);
+    assert_eq!(selection.len(), 1);
+    assert_eq!(selection[0].0, large_coin);
+
+    // Without a max input age, all the coins are selectable.
+    wallet.set_max_input_age(None).unwrap();
+    assert_eq!(selected!(wallet, total_amount).len(), 4);
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
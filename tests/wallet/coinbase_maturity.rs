@@ -0,0 +1,77 @@
+use bitcoin::Amount;
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::MakerBehavior, taker::TakerBehavior, utill::ConnectionType, wallet::WalletError,
+};
+
+use crate::test_framework::*;
+
+use std::sync::atomic::Ordering::Relaxed;
+
+/// Test a wallet funded from a recent coinbase.
+///
+/// - The coinbase output is reported as immature, and left out of the regular and spendable balances.
+/// - Coin selection doesn't pick it, so it can't end up in swap funding.
+/// - Once 100 blocks deep, it's counted as a regular coin and can be spent.
+#[test]
+fn test_wallet_coinbase_maturity() {
+    // ---- Setup ----
+    let makers_config_map = [((6102, None), MakerBehavior::Normal)];
+
+    let (test_framework, mut taker, _, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    // 0.15 BTC in 3 utxos.
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let regular = Amount::from_btc(0.15).unwrap();
+
+    // ----- Test -----
+    // Mine a block paying its reward to the wallet.
+    let mining_address = taker.get_wallet_mut().get_next_external_address().unwrap();
+    bitcoind
+        .client
+        .generate_to_address(1, &mining_address)
+        .unwrap();
+    taker.get_wallet_mut().sync().unwrap();
+
+    let balances = taker.get_wallet().get_balances().unwrap();
+    let reward = balances.immature;
+    assert!(reward > Amount::ZERO);
+    assert_eq!(balances.regular, regular);
+    assert_eq!(balances.spendable, regular);
+
+    // The immature reward isn't used to fund more than the regular coins.
+    let wallet = taker.get_wallet();
+    assert!(matches!(
+        wallet.coin_select(regular + Amount::from_sat(1000)),
+        Err(WalletError::InsufficientFund { .. })
+    ));
+    assert!(wallet
+        .list_spendable_with_weight()
+        .unwrap()
+        .iter()
+        .all(|(utxo, _, _)| utxo.amount != reward));
+
+    // Once mature, the reward is a regular coin.
+    generate_blocks(bitcoind, 100);
+    taker.get_wallet_mut().sync().unwrap();
+
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.immature, Amount::ZERO);
+    assert_eq!(balances.regular, regular + reward);
+    assert!(taker
+        .get_wallet()
+        .coin_select(regular + Amount::from_sat(1000))
+        .is_ok());
+
+    // ----- Clean up -----
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
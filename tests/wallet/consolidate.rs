@@ -0,0 +1,86 @@
+use bitcoin::{Amount, OutPoint};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use std::collections::HashSet;
+
+use crate::test_framework::*;
+
+/// Test consolidating the smallest regular UTXOs of the wallet.
+///
+/// - The wallet holds three big UTXOs, and three small ones.
+/// - Consolidating three inputs spends the three small UTXOs only.
+/// - The wallet ends up with the big UTXOs, and a single UTXO of the small ones' value, less the fee.
+#[test]
+fn test_wallet_consolidate() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(None);
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    let wallet = taker.get_wallet_mut();
+    let small_amounts = [100_000, 200_000, 300_000].map(Amount::from_sat);
+    for amount in small_amounts.iter() {
+        let address = wallet.get_next_external_address().unwrap();
+        send_to_address(bitcoind, &address, *amount);
+    }
+    generate_blocks(bitcoind, 1);
+    wallet.sync().unwrap();
+
+    let small_utxos = wallet
+        .get_all_utxo()
+        .unwrap()
+        .into_iter()
+        .filter(|utxo| small_amounts.contains(&utxo.amount))
+        .map(|utxo| OutPoint::new(utxo.txid, utxo.vout))
+        .collect::<HashSet<_>>();
+    assert_eq!(small_utxos.len(), 3);
+    let regular_before = wallet.get_balances().unwrap().regular;
+
+    // ----- Test -----
+    let fee_rate = Amount::from_sat(2);
+    let txid = wallet.consolidate(3, fee_rate).unwrap();
+
+    let tx = bitcoind.client.get_raw_transaction(&txid, None).unwrap();
+    let spent = tx
+        .input
+        .iter()
+        .map(|txin| txin.previous_output)
+        .collect::<HashSet<_>>();
+    assert_eq!(spent, small_utxos);
+    assert_eq!(tx.output.len(), 1);
+
+    let small_total = small_amounts.iter().copied().sum::<Amount>();
+    let fee = small_total - tx.output[0].value;
+    // The fee is estimated before signing, the signatures can be a few bytes off.
+    let expected_fee = fee_rate * tx.vsize() as u64;
+    assert!(
+        fee.to_sat().abs_diff(expected_fee.to_sat()) <= 2 * 3 * fee_rate.to_sat(),
+        "Fee {} too far from {}",
+        fee,
+        expected_fee
+    );
+
+    generate_blocks(bitcoind, 1);
+    wallet.sync().unwrap();
+
+    let utxos = wallet.get_all_utxo().unwrap();
+    assert_eq!(utxos.len(), 4);
+    assert!(utxos
+        .iter()
+        .all(|utxo| !spent.contains(&OutPoint::new(utxo.txid, utxo.vout))));
+    let consolidated = utxos
+        .iter()
+        .filter(|utxo| utxo.txid == txid)
+        .collect::<Vec<_>>();
+    assert_eq!(consolidated.len(), 1);
+    assert_eq!(consolidated[0].amount, small_total - fee);
+    assert_eq!(wallet.get_balances().unwrap().regular, regular_before - fee);
+
+    // A single input is nothing to consolidate.
+    assert!(wallet.consolidate(1, fee_rate).is_err());
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
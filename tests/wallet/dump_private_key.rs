@@ -0,0 +1,98 @@
+use bitcoin::{
+    absolute::LockTime,
+    hashes::Hash,
+    secp256k1::{Message, Secp256k1},
+    sign_message::{signed_msg_hash, MessageSignature},
+    Address, Amount, Network,
+};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::MakerBehavior,
+    taker::TakerBehavior,
+    utill::{ConnectionType, DEFAULT_TX_FEE_RATE},
+};
+
+use crate::test_framework::*;
+
+use std::sync::atomic::Ordering::Relaxed;
+
+/// Test dumping the private key of a wallet address.
+///
+/// - Key export is refused, until enabled.
+/// - The dumped key of a derived address signs a message, verifiable against that address.
+/// - Addresses not owned by the wallet, and the fidelity bond script are refused.
+#[test]
+fn test_dump_private_key() {
+    // ---- Setup ----
+    let makers_config_map = [((6102, None), MakerBehavior::Normal)];
+
+    let (test_framework, mut taker, _, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 1, Amount::from_btc(0.05).unwrap());
+
+    let wallet = taker.get_wallet_mut();
+    let address = wallet.get_next_external_address().unwrap();
+
+    // ----- Test -----
+
+    // Disabled by default.
+    assert!(wallet.dump_private_key(&address).is_err());
+
+    wallet.set_allow_key_export(true);
+
+    // The key of a derived address signs for it.
+    let secp = Secp256k1::new();
+    let msg_hash = signed_msg_hash("coinswap key export");
+    for address in [
+        address,
+        wallet.get_next_internal_addresses(1).unwrap()[0].clone(),
+    ] {
+        let privkey = wallet.dump_private_key(&address).unwrap();
+        assert_eq!(privkey.network, Network::Regtest.into());
+        assert!(privkey.compressed);
+
+        let signature = MessageSignature::new(
+            secp.sign_ecdsa_recoverable(
+                &Message::from_digest(msg_hash.to_byte_array()),
+                &privkey.inner,
+            ),
+            true,
+        );
+        assert!(signature
+            .is_signed_by_address(&secp, &address, msg_hash)
+            .unwrap());
+    }
+
+    // An address of another wallet.
+    let foreign_address = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .assume_checked();
+    assert!(wallet.dump_private_key(&foreign_address).is_err());
+
+    // The fidelity bond script has no single key.
+    let current_height = bitcoind.client.get_block_count().unwrap() as u32;
+    let index = wallet
+        .create_fidelity(
+            Amount::from_btc(0.01).unwrap(),
+            LockTime::from_height(current_height + 950).unwrap(),
+            DEFAULT_TX_FEE_RATE,
+        )
+        .unwrap();
+    let fidelity_spk = wallet.get_fidelity_bonds()[&index].1.clone();
+    let fidelity_address = Address::from_script(&fidelity_spk, Network::Regtest).unwrap();
+    assert!(wallet.dump_private_key(&fidelity_address).is_err());
+
+    // ----- Clean up -----
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
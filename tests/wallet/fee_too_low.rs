@@ -0,0 +1,53 @@
+use bitcoin::{Amount, Network};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::wallet::{Destination, WalletError};
+
+use crate::test_framework::*;
+
+/// Test the broadcast of a transaction paying less than the node's minimum relay fee.
+///
+/// - The broadcast fails with [WalletError::FeeTooLow], carrying the fee required by the node.
+/// - The same payment, re-built to pay the required fee, is accepted.
+#[test]
+fn test_send_tx_fee_too_low() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(None);
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    // ----- Test -----
+    let wallet = taker.get_wallet_mut();
+    let coins = wallet.list_descriptor_utxo_spend_info().unwrap();
+
+    let recipient = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+    let destination = Destination::Multi(vec![(recipient, Amount::from_btc(0.01).unwrap())]);
+
+    // Zero fee is below the node's minimum relay fee.
+    let zero_fee_tx = wallet
+        .spend_from_wallet(0.0, destination.clone(), &coins[..1])
+        .unwrap();
+    let required = match wallet.send_tx(&zero_fee_tx) {
+        Err(WalletError::FeeTooLow { required }) => required,
+        other => panic!("Expected FeeTooLow error, got {:?}", other),
+    };
+    assert!(required > 0);
+
+    // Re-built at the required rate, the payment is accepted.
+    let required_feerate = (required as f64 / zero_fee_tx.vsize() as f64).ceil();
+    let tx = wallet
+        .spend_from_wallet(required_feerate, destination, &coins[..1])
+        .unwrap();
+    let txid = wallet.send_tx(&tx).unwrap();
+    assert!(bitcoind.client.get_raw_mempool().unwrap().contains(&txid));
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
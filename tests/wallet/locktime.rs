@@ -0,0 +1,179 @@
+use bitcoin::{absolute::LockTime, Amount, Network};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, TakerBehavior},
+    utill::{ConnectionType, DEFAULT_TX_FEE_RATE},
+    wallet::Destination,
+};
+use std::{
+    sync::{atomic::Ordering::Relaxed, Arc},
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// Test the anti-fee-sniping nLockTime of regular wallet transactions.
+///
+/// The Taker is configured to set the nLockTime to the tip height, without random back-off.
+/// - A regular payment must have its nLockTime at the current height.
+/// - A fidelity bond funding tx must have its nLockTime at the height it was created at.
+///
+/// Blocks are mined manually here, so the tip doesn't move while the txs are created.
+#[test]
+fn test_anti_fee_sniping_locktime() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(Some("anti_fee_sniping = tip\n"));
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    // ----- Test -----
+    let wallet = taker.get_wallet_mut();
+
+    // Regular payment.
+    let recipient = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+    let coins = wallet.list_descriptor_utxo_spend_info().unwrap();
+    let payment_tx = wallet
+        .spend_from_wallet(
+            DEFAULT_TX_FEE_RATE,
+            Destination::Multi(vec![(recipient, Amount::from_btc(0.01).unwrap())]),
+            &coins[..1],
+        )
+        .unwrap();
+    let current_height = bitcoind.client.get_block_count().unwrap() as u32;
+    assert_eq!(
+        payment_tx.lock_time,
+        LockTime::from_height(current_height).unwrap()
+    );
+
+    // Fidelity bond funding. Its creation waits for a confirmation, so mine one block meanwhile.
+    let creation_height = current_height;
+    let index = thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_secs(3));
+            generate_blocks(bitcoind, 1);
+        });
+        wallet
+            .create_fidelity(
+                Amount::from_btc(0.02).unwrap(),
+                LockTime::from_height(creation_height + 100).unwrap(),
+                DEFAULT_TX_FEE_RATE,
+            )
+            .unwrap()
+    });
+
+    let (_, bond_spk, _) = wallet.get_fidelity_bonds().get(&index).unwrap();
+    let block_hash = bitcoind
+        .client
+        .get_block_hash(creation_height as u64 + 1)
+        .unwrap();
+    let block = bitcoind.client.get_block(&block_hash).unwrap();
+    let funding_tx = block
+        .txdata
+        .iter()
+        .find(|tx| tx.output.iter().any(|o| &o.script_pubkey == bond_spk))
+        .expect("fidelity funding tx should be confirmed in the next block");
+    assert_eq!(
+        funding_tx.lock_time,
+        LockTime::from_height(creation_height).unwrap()
+    );
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
+
+/// Test the anti-fee-sniping nLockTime of the Taker's swap funding txs.
+///
+/// The Taker is configured to set the nLockTime to the tip height, without random back-off.
+/// - After a swap, each funding tx of the Taker must have its nLockTime at the tip height of its
+///   creation, which is between the height at the start of the swap and the one it confirmed at.
+#[test]
+fn test_anti_fee_sniping_swap_funding_locktime() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init_with_config_overrides(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+            ConfigOverrides {
+                taker: Some("anti_fee_sniping = tip\n".to_string()),
+                ..Default::default()
+            },
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || start_maker_server(maker_clone).unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    for maker in &makers {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(5));
+        }
+    }
+
+    // ----- Test -----
+    let start_height = bitcoind.client.get_block_count().unwrap() as u32;
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    let history = taker.get_swap_history().unwrap();
+    assert_eq!(history.len(), 1);
+    assert!(!history[0].funding_outpoints.is_empty());
+    for outpoint in &history[0].funding_outpoints {
+        let funding_tx = bitcoind
+            .client
+            .get_raw_transaction_info(&outpoint.txid, None)
+            .unwrap();
+        let confirmed_height = bitcoind
+            .client
+            .get_block_header_info(&funding_tx.blockhash.unwrap())
+            .unwrap()
+            .height as u32;
+        let lock_time = funding_tx.transaction().unwrap().lock_time;
+        assert!(lock_time.is_block_height());
+        assert!(lock_time.to_consensus_u32() >= start_height);
+        assert!(lock_time.to_consensus_u32() < confirmed_height);
+    }
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,24 @@
+//! Wallet functional tests, sharing one test binary.
+//!
+//! The tests are serialized on the shared regtest data directory by the [test_framework].
+#![cfg(feature = "integration-test")]
+#[path = "../test_framework/mod.rs"]
+mod test_framework;
+
+mod address_strategy;
+mod build_spend;
+mod bump_fee;
+mod coin_protection;
+mod coinbase_maturity;
+mod consolidate;
+mod dump_private_key;
+mod fee_too_low;
+mod locktime;
+mod min_fee_rate;
+mod recoverable_misc;
+mod reimport_swapcoins;
+mod reserve;
+mod rpc_config;
+mod sync;
+mod tx_confirmations;
+mod txindex;
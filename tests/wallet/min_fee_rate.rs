@@ -0,0 +1,54 @@
+use bitcoin::{Amount, Network};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::wallet::Destination;
+
+use crate::test_framework::*;
+
+/// Test the minimum fee rate floor of the wallet.
+///
+/// - On regtest, `estimatesmartfee` returns no estimate, and the floor is used instead.
+/// - A send at a near-zero fee rate is raised to the floor, and relays.
+#[test]
+fn test_min_fee_rate() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(None);
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    // ----- Test -----
+    let min_fee_rate = 10.0;
+    let wallet = taker.get_wallet_mut();
+    wallet.set_min_fee_rate(min_fee_rate);
+
+    // The estimator has nothing to say on regtest.
+    let estimate = bitcoind.client.estimate_smart_fee(6, None).unwrap();
+    assert!(estimate.fee_rate.is_none());
+    assert_eq!(wallet.estimate_fee_rate(6), min_fee_rate);
+
+    let recipient = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+    let coins = wallet.list_descriptor_utxo_spend_info().unwrap();
+    let tx = wallet
+        .spend_from_wallet(
+            0.1,
+            Destination::Multi(vec![(recipient, Amount::from_btc(0.01).unwrap())]),
+            &coins[..1],
+        )
+        .unwrap();
+    let txid = wallet.send_tx(&tx).unwrap();
+
+    let entry = bitcoind.client.get_mempool_entry(&txid).unwrap();
+    let fee_rate = entry.fees.base.to_sat() as f64 / entry.vsize as f64;
+    // Up to the size estimate of the signatures.
+    assert!(fee_rate > min_fee_rate * 0.95);
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
@@ -0,0 +1,121 @@
+use bitcoin::{absolute::LockTime, Address, Amount, Network, OutPoint};
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{utill::DEFAULT_TX_FEE_RATE, wallet::Destination};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::test_framework::*;
+
+/// Test recovering funds sent by mistake to a script the wallet controls.
+///
+/// - Coins are sent to the timelocked redeemscript address of an existing fidelity bond.
+/// - They are left out of every UTXO category, and the scan flags them as recoverable with the bond's spend path.
+/// - Once the timelock expires, they are swept with that spend path, while the bond itself stays untouched.
+#[test]
+fn test_wallet_recoverable_misc() {
+    // ---- Setup ----
+    let test_env = Arc::new(TestEnv::init());
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(None);
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    // Keep mining, so the bond tx confirms.
+    let stop_mining = Arc::new(AtomicBool::new(false));
+    let miner = {
+        let test_env = test_env.clone();
+        let stop_mining = stop_mining.clone();
+        thread::spawn(move || {
+            while !stop_mining.load(Relaxed) {
+                generate_blocks(&test_env.bitcoind, 1);
+                thread::sleep(Duration::from_secs(1));
+            }
+        })
+    };
+
+    let bond_amount = Amount::from_btc(0.01).unwrap();
+    let current_height = bitcoind.client.get_block_count().unwrap() as u32;
+    let wallet = taker.get_wallet_mut();
+    let index = wallet
+        .create_fidelity(
+            bond_amount,
+            LockTime::from_height(current_height + 20).unwrap(),
+            DEFAULT_TX_FEE_RATE,
+        )
+        .unwrap();
+    stop_mining.store(true, Relaxed);
+    miner.join().unwrap();
+    wallet.sync().unwrap();
+
+    // ----- Test -----
+
+    // The bond itself is tracked.
+    assert!(wallet.scan_for_recoverable_misc().unwrap().is_empty());
+
+    let bond_spk = wallet.get_fidelity_bonds()[&index].1.clone();
+    let bond_address = Address::from_script(&bond_spk, Network::Regtest).unwrap();
+    let stray_amount = Amount::from_btc(0.002).unwrap();
+    send_to_address(bitcoind, &bond_address, stray_amount);
+    generate_blocks(bitcoind, 1);
+    wallet.sync().unwrap();
+
+    let recoverable = wallet.scan_for_recoverable_misc().unwrap();
+    assert_eq!(recoverable.len(), 1);
+    let stray = &recoverable[0];
+    assert_eq!(stray.utxo.amount, stray_amount);
+    assert_eq!(stray.utxo.script_pub_key, bond_spk);
+    assert_eq!(stray.spend_info.to_string(), "fidelity-bond");
+
+    // The stray coins are not in any category.
+    assert_eq!(wallet.get_balances().unwrap().fidelity, bond_amount);
+    assert!(!wallet
+        .list_all_utxo_spend_info()
+        .unwrap()
+        .iter()
+        .any(|(utxo, _)| utxo.txid == stray.utxo.txid && utxo.vout == stray.utxo.vout));
+
+    // Reclaim them with the bond's spend path, after its timelock.
+    generate_blocks(bitcoind, 25);
+    let destination_address = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+    let tx = wallet
+        .spend_coins(
+            &[(stray.utxo.clone(), stray.spend_info.clone())],
+            Destination::Sweep(destination_address.clone()),
+            DEFAULT_TX_FEE_RATE,
+        )
+        .unwrap();
+    assert_eq!(tx.input.len(), 1);
+    assert_eq!(
+        tx.input[0].previous_output,
+        OutPoint::new(stray.utxo.txid, stray.utxo.vout)
+    );
+    wallet.send_tx(&tx).unwrap();
+    generate_blocks(bitcoind, 1);
+    wallet.sync().unwrap();
+
+    let received = bitcoind
+        .client
+        .get_received_by_address(&destination_address, None)
+        .unwrap();
+    assert!(received > stray_amount - Amount::from_sat(1_000));
+
+    // The bond is untouched, and nothing is left to recover.
+    assert!(!wallet.get_fidelity_bonds()[&index].2);
+    assert_eq!(wallet.get_balances().unwrap().fidelity, bond_amount);
+    assert!(wallet.scan_for_recoverable_misc().unwrap().is_empty());
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
@@ -0,0 +1,123 @@
+use bitcoin::Amount;
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{start_maker_server, MakerBehavior},
+    taker::{SwapParams, Taker, TakerBehavior},
+    utill::ConnectionType,
+    wallet::RPCConfig,
+};
+use std::{env, fs, sync::Arc};
+
+use crate::test_framework::*;
+
+use std::{sync::atomic::Ordering::Relaxed, thread, time::Duration};
+
+/// Test the re-import of swapcoin scripts, when the Taker's wallet is reloaded.
+///
+/// - The Taker drops the swap after funding. The Makers recover by broadcasting their contracts,
+///   creating contract outputs of the Taker's outgoing swapcoins.
+/// - The Taker's Core wallet is lost, and the Taker is restarted.
+/// - The reloaded wallet re-imports its swapcoin scripts, and the contract outputs show up in the
+///   balances without any manual re-import.
+#[test]
+fn test_reimport_swapcoin_scripts_on_load() {
+    // ---- Setup ----
+    let makers_config_map = [
+        ((6102, None), MakerBehavior::Normal),
+        ((16102, None), MakerBehavior::Normal),
+    ];
+
+    let (test_framework, mut taker, makers, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::DropConnectionAfterFullSetup,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+    let makers_ref = makers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+    fund_and_verify_maker(makers_ref, bitcoind, 4, Amount::from_btc(0.05).unwrap());
+
+    let maker_threads = makers
+        .iter()
+        .map(|maker| {
+            let maker_clone = maker.clone();
+            thread::spawn(move || {
+                start_maker_server(maker_clone).unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    makers.iter().for_each(|maker| {
+        while !maker.is_setup_complete.load(Relaxed) {
+            log::info!("Waiting for maker setup completion");
+            thread::sleep(Duration::from_secs(10));
+        }
+    });
+
+    let swap_params = SwapParams {
+        send_amount: Amount::from_sat(500000),
+        maker_count: 2,
+        tx_count: 3,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    taker.do_coinswap(swap_params).unwrap();
+
+    // Wait for the makers to broadcast their contracts, after the taker went idle.
+    let contract_balance = loop {
+        thread::sleep(Duration::from_secs(10));
+        let wallet = taker.get_wallet_mut();
+        wallet.sync().unwrap();
+        let balances = wallet.get_balances().unwrap();
+        if balances.contract > Amount::ZERO {
+            break balances.contract;
+        }
+        log::info!("Waiting for the contract outputs of the taker");
+    };
+
+    // ----- Test -----
+
+    // Lose the taker's Core wallet, keeping the wallet file.
+    drop(taker);
+    bitcoind.client.unload_wallet(Some("taker-wallet")).unwrap();
+    let temp_dir = env::temp_dir().join("coinswap");
+    fs::remove_dir_all(
+        temp_dir
+            .join(".bitcoin")
+            .join("regtest")
+            .join("wallets")
+            .join("taker-wallet"),
+    )
+    .unwrap();
+
+    let taker = Taker::init(
+        Some(temp_dir.join("taker")),
+        None,
+        Some(RPCConfig::from(test_framework.as_ref())),
+        TakerBehavior::Normal,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+    )
+    .unwrap();
+
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.contract, contract_balance);
+
+    // ----- Clean up -----
+    makers
+        .iter()
+        .for_each(|maker| maker.shutdown.store(true, Relaxed));
+    maker_threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    directory_server_instance.shutdown.store(true, Relaxed);
+    thread::sleep(Duration::from_secs(10));
+
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
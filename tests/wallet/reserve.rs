@@ -0,0 +1,79 @@
+use bitcoin::Amount;
+use coinswap::{
+    maker::MakerBehavior,
+    taker::{error::TakerError, SwapParams, TakerBehavior},
+    utill::ConnectionType,
+    wallet::WalletError,
+};
+
+use crate::test_framework::*;
+
+use std::sync::atomic::Ordering::Relaxed;
+
+/// Test the wallet reserve, which is never spent automatically.
+///
+/// - The reserve is left out of the spendable balance.
+/// - A swap which would dip into the reserve is rejected for insufficient funds.
+/// - Coin selection never spends into the reserve, which stays untouched.
+#[test]
+fn test_wallet_reserve() {
+    // ---- Setup ----
+    let makers_config_map = [((6102, None), MakerBehavior::Normal)];
+
+    let (test_framework, mut taker, _, directory_server_instance, block_generation_handle) =
+        TestFramework::init(
+            makers_config_map.into(),
+            TakerBehavior::Normal,
+            ConnectionType::CLEARNET,
+        );
+
+    let bitcoind = &test_framework.bitcoind;
+
+    // 0.15 BTC in 3 utxos.
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    let reserve = Amount::from_btc(0.12).unwrap();
+    taker.get_wallet_mut().set_reserve_amount(reserve);
+
+    // ----- Test -----
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.regular, Amount::from_btc(0.15).unwrap());
+    assert_eq!(balances.spendable, Amount::from_btc(0.03).unwrap());
+
+    // The swap amount is available, but only by spending the reserve.
+    let swap_params = SwapParams {
+        send_amount: Amount::from_btc(0.05).unwrap(),
+        maker_count: 1,
+        tx_count: 1,
+        required_confirms: 1,
+        ..Default::default()
+    };
+    match taker.do_coinswap(swap_params) {
+        Err(TakerError::Wallet(WalletError::InsufficientFund {
+            available,
+            required,
+        })) => {
+            assert_eq!(available, Amount::from_btc(0.03).unwrap().to_sat());
+            assert!(required > Amount::from_btc(0.05).unwrap().to_sat());
+        }
+        other => panic!("Expected insufficient funds, got {:?}", other),
+    }
+
+    // Coin selection honors the reserve as well.
+    let wallet = taker.get_wallet();
+    assert!(matches!(
+        wallet.coin_select(Amount::from_btc(0.05).unwrap()),
+        Err(WalletError::InsufficientFund { .. })
+    ));
+    assert!(wallet.coin_select(Amount::from_btc(0.02).unwrap()).is_ok());
+
+    // Nothing was spent.
+    let balances = taker.get_wallet().get_balances().unwrap();
+    assert_eq!(balances.regular, Amount::from_btc(0.15).unwrap());
+    assert_eq!(balances.swap, Amount::ZERO);
+
+    // ----- Clean up -----
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,65 @@
+use bitcoind::bitcoincore_rpc::{Client, RpcApi};
+use coinswap::wallet::{RPCConfig, RPC_PASSWORD_ENV};
+use std::{convert::TryFrom, env, fs};
+
+use crate::test_framework::*;
+
+/// Test loading the RPC credentials from a `bitcoin.conf` style file.
+///
+/// - The credentials of the test node are written into a minimal config file.
+/// - An [RPCConfig] loaded from the file must connect to the node.
+/// - A wrong password in the file must be fixable with the password environment variable.
+#[test]
+fn test_rpc_config_from_file() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+    let temp_dir = &test_env.temp_dir;
+
+    // The cookie file holds `__cookie__:<password>`, which works as plain user/password auth.
+    let cookie = fs::read_to_string(&bitcoind.params.cookie_file).unwrap();
+    let (user, password) = cookie.trim().split_once(':').unwrap();
+    let (host, port) = bitcoind
+        .rpc_url()
+        .trim_start_matches("http://")
+        .split_once(':')
+        .map(|(host, port)| (host.to_string(), port.to_string()))
+        .unwrap();
+
+    let conf_path = temp_dir.join("bitcoin.conf");
+    let write_conf = |password: &str| {
+        fs::write(
+            &conf_path,
+            format!(
+                "# Test node\nrpcuser={}\nrpcpassword={}\nrpcconnect={}\nrpcport={}\n",
+                user, password, host, port
+            ),
+        )
+        .unwrap();
+    };
+
+    // ----- Test -----
+
+    // Connect with the parsed credentials.
+    write_conf(password);
+    let rpc_config = RPCConfig::from_config_file(&conf_path).unwrap();
+    assert_eq!(rpc_config.url, format!("{}:{}", host, port));
+    let client = Client::try_from(&rpc_config).unwrap();
+    assert_eq!(client.get_block_count().unwrap(), 101);
+
+    // A wrong password fails to connect.
+    write_conf("wrong-password");
+    let rpc_config = RPCConfig::from_config_file(&conf_path).unwrap();
+    let client = Client::try_from(&rpc_config).unwrap();
+    assert!(client.get_block_count().is_err());
+
+    // The environment overrides the wrong password.
+    env::set_var(RPC_PASSWORD_ENV, password);
+    let rpc_config = RPCConfig::from_config_file(&conf_path).unwrap();
+    let client = Client::try_from(&rpc_config).unwrap();
+    assert_eq!(client.get_block_count().unwrap(), 101);
+    env::remove_var(RPC_PASSWORD_ENV);
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
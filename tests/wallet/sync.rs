@@ -0,0 +1,52 @@
+use crate::test_framework::*;
+use coinswap::{taker::TakerBehavior, utill::ConnectionType, wallet::SyncProgress};
+
+use std::sync::atomic::Ordering::Relaxed;
+
+/// Test batched wallet sync with progress reporting.
+///
+/// The chain is extended by a few hundred blocks, so the rescan spans multiple batches.
+/// The reported progress must be monotonically increasing and must end at 100%,
+/// with the wallet's last synced height matching the reported target.
+#[test]
+fn test_wallet_sync_with_progress() {
+    // ---- Setup ----
+    let (test_framework, mut taker, _, directory_server_instance, block_generation_handle) =
+        TestFramework::init(vec![], TakerBehavior::Normal, ConnectionType::CLEARNET);
+
+    let bitcoind = &test_framework.bitcoind;
+
+    generate_blocks(bitcoind, 300);
+
+    // ----- Test -----
+    let mut reports = Vec::<SyncProgress>::new();
+    taker
+        .get_wallet_mut()
+        .sync_with_progress(|progress| reports.push(progress))
+        .unwrap();
+
+    assert!(reports.len() > 1, "Sync was not split into batches");
+
+    for pair in reports.windows(2) {
+        assert!(pair[1].current_height > pair[0].current_height);
+        assert!(pair[1].percentage > pair[0].percentage);
+    }
+
+    let last = reports.last().unwrap();
+    assert_eq!(last.current_height, last.target_height);
+    assert_eq!(last.percentage, 100.0);
+
+    // A subsequent sync resumes from the checkpoint, and only rescans the remaining blocks.
+    let mut resumed_reports = Vec::<SyncProgress>::new();
+    taker
+        .get_wallet_mut()
+        .sync_with_progress(|progress| resumed_reports.push(progress))
+        .unwrap();
+    assert!(resumed_reports.len() < reports.len());
+    assert_eq!(resumed_reports.last().unwrap().percentage, 100.0);
+
+    // ----- Clean up -----
+    directory_server_instance.shutdown.store(true, Relaxed);
+    test_framework.stop();
+    block_generation_handle.join().unwrap();
+}
@@ -0,0 +1,79 @@
+use bitcoin::{hashes::Hash, Amount, Network, Txid};
+use bitcoind::bitcoincore_rpc::{Auth, Client, RpcApi};
+use coinswap::{utill::DEFAULT_TX_FEE_RATE, wallet::Destination};
+
+use crate::test_framework::*;
+
+/// Test the confirmations lookup of wallet and foreign transactions.
+///
+/// - A tx sent by the Taker wallet is found via the wallet's `gettransaction`.
+/// - A tx between addresses foreign to the Taker wallet is found via `getrawtransaction`.
+/// - Both report no confirmation in the mempool, then their confirmation depth.
+/// - An unknown tx is an error.
+#[test]
+fn test_tx_confirmations() {
+    // ---- Setup ----
+    let test_env = TestEnv::init();
+    let bitcoind = &test_env.bitcoind;
+
+    let mut taker = test_env.init_taker(None);
+
+    fund_and_verify_taker(&mut taker, bitcoind, 3, Amount::from_btc(0.05).unwrap());
+
+    // ----- Test -----
+    let foreign_address = bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .require_network(Network::Regtest)
+        .unwrap();
+
+    // A wallet tx, paying out of the Taker wallet.
+    let wallet = taker.get_wallet_mut();
+    let coins = wallet.list_descriptor_utxo_spend_info().unwrap();
+    let wallet_tx = wallet
+        .spend_from_wallet(
+            DEFAULT_TX_FEE_RATE,
+            Destination::Multi(vec![(
+                foreign_address.clone(),
+                Amount::from_btc(0.01).unwrap(),
+            )]),
+            &coins[..1],
+        )
+        .unwrap();
+    let wallet_txid = wallet.send_tx(&wallet_tx).unwrap();
+
+    // A foreign tx, from the node's default wallet to itself.
+    let foreign_txid = send_to_address(bitcoind, &foreign_address, Amount::from_btc(0.02).unwrap());
+
+    // Only the first one is known to the Taker's Core wallet.
+    let taker_wallet_client = Client::new(
+        &bitcoind.rpc_url_with_wallet("taker-wallet"),
+        Auth::CookieFile(bitcoind.params.cookie_file.clone()),
+    )
+    .unwrap();
+    assert!(taker_wallet_client
+        .get_transaction(&wallet_txid, None)
+        .is_ok());
+    assert!(taker_wallet_client
+        .get_transaction(&foreign_txid, None)
+        .is_err());
+
+    let wallet = taker.get_wallet();
+
+    assert_eq!(wallet.get_tx_confirmations(&wallet_txid).unwrap(), None);
+    assert_eq!(wallet.get_tx_confirmations(&foreign_txid).unwrap(), None);
+
+    generate_blocks(bitcoind, 1);
+    assert_eq!(wallet.get_tx_confirmations(&wallet_txid).unwrap(), Some(1));
+    assert_eq!(wallet.get_tx_confirmations(&foreign_txid).unwrap(), Some(1));
+
+    generate_blocks(bitcoind, 2);
+    assert_eq!(wallet.get_tx_confirmations(&wallet_txid).unwrap(), Some(3));
+    assert_eq!(wallet.get_tx_confirmations(&foreign_txid).unwrap(), Some(3));
+
+    assert!(wallet.get_tx_confirmations(&Txid::all_zeros()).is_err());
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}
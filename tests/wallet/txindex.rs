@@ -0,0 +1,60 @@
+use bitcoind::bitcoincore_rpc::RpcApi;
+use coinswap::{
+    maker::{Maker, MakerBehavior, MakerError},
+    taker::{error::TakerError, Taker, TakerBehavior},
+    utill::ConnectionType,
+    wallet::WalletError,
+};
+
+use crate::test_framework::*;
+
+/// Test the Taker and Maker against a node without `-txindex`.
+///
+/// Both fail upfront at the wallet initialization with [WalletError::TxindexRequired], instead of
+/// failing later on the first tx lookup.
+#[test]
+fn test_wallet_requires_txindex() {
+    // ---- Setup ----
+    let test_env = TestEnv::init_with_node(|dir| init_bitcoind_with_txindex(dir, false));
+    let bitcoind = &test_env.bitcoind;
+    let temp_dir = &test_env.temp_dir;
+    let rpc_config = test_env.rpc_config();
+
+    // ----- Test -----
+    let taker_dir = temp_dir.join("taker");
+    let taker = Taker::init(
+        Some(taker_dir.clone()),
+        None,
+        Some(rpc_config.clone()),
+        TakerBehavior::Normal,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+    );
+    assert!(matches!(
+        taker,
+        Err(TakerError::Wallet(WalletError::TxindexRequired))
+    ));
+    assert!(!taker_dir.join("wallets").join("taker-wallet").exists());
+
+    let maker_dir = temp_dir.join("maker");
+    let maker = Maker::init(
+        Some(maker_dir.clone()),
+        None,
+        Some(rpc_config),
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(ConnectionType::CLEARNET),
+        MakerBehavior::Normal,
+    );
+    assert!(matches!(
+        maker,
+        Err(MakerError::Wallet(WalletError::TxindexRequired))
+    ));
+
+    // ----- Clean up -----
+    let _ = bitcoind.client.stop().unwrap();
+}